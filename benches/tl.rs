@@ -30,7 +30,92 @@ pub fn criterion_benchmark(cr: &mut Criterion) {
             let _ = tl::parse(black_box(INPUT), tl::ParserOptions::default());
         });
     });
+
+    let dom = tl::parse(INPUT, tl::ParserOptions::default()).unwrap();
+    let cache = tl::cache::serialize(&dom);
+    cr.bench_function("tl cache load", |b| {
+        b.iter(|| {
+            let _ = tl::cache::load(black_box(&cache));
+        });
+    });
+}
+
+/// Builds a document with deeply nested lists, where descendant selectors have to walk long ancestor chains
+fn nested_document() -> String {
+    let mut html = String::from("<div id=\"main\">");
+    for i in 0..200 {
+        html.push_str(&format!("<section class=\"s{}\"><ul>", i % 10));
+        for depth in 0..10 {
+            html.push_str(&format!("<li class=\"item d{depth}\"><span>x</span><ul>"));
+        }
+        html.push_str("<li><a href=\"/\">link</a></li>");
+        html.push_str(&"</ul></li>".repeat(10));
+        html.push_str("</ul></section>");
+    }
+    html.push_str("</div>");
+    html
+}
+
+pub fn query_selector_benchmark(cr: &mut Criterion) {
+    let input = nested_document();
+    let dom = tl::parse(&input, tl::ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+
+    for query in ["#main .s3 li > a", "section ul .d0 .d5 span", "li a, span"] {
+        cr.bench_function(&format!("query_selector {query}"), |b| {
+            b.iter(|| dom.query_selector(black_box(query)).unwrap().count());
+        });
+
+        // the recursive matcher that query selectors used before selectors were compiled
+        let selector = tl::parse_query_selector(query).unwrap();
+        cr.bench_function(&format!("query_selector {query} (recursive)"), |b| {
+            b.iter(|| {
+                dom.nodes()
+                    .iter()
+                    .filter(|node| selector.matches(node, parser))
+                    .count()
+            });
+        });
+    }
+}
+
+/// Builds a document whose elements each have the given number of attributes, not counting `id` and `class`
+fn attribute_document(attributes: usize) -> String {
+    let mut html = String::new();
+    for i in 0..2000 {
+        html.push_str(&format!("<a id=\"a{i}\" class=\"link\""));
+        for n in 0..attributes {
+            html.push_str(&format!(" data-{n}=\"{i}\""));
+        }
+        html.push_str(">x</a>");
+    }
+    html
+}
+
+pub fn attributes_benchmark(cr: &mut Criterion) {
+    for attributes in [1, 2, 4, 8, 32] {
+        let input = attribute_document(attributes);
+        cr.bench_function(&format!("parse {attributes} attributes"), |b| {
+            b.iter(|| tl::parse(black_box(&input), tl::ParserOptions::default()));
+        });
+
+        let dom = tl::parse(&input, tl::ParserOptions::default()).unwrap();
+        let last = format!("data-{}", attributes - 1);
+        cr.bench_function(&format!("get of {attributes} attributes"), |b| {
+            b.iter(|| {
+                dom.nodes()
+                    .iter()
+                    .filter_map(|node| node.as_tag()?.attributes().get(black_box(last.as_str())))
+                    .count()
+            });
+        });
+    }
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    query_selector_benchmark,
+    attributes_benchmark
+);
 criterion_main!(benches);
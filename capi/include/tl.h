@@ -0,0 +1,65 @@
+/*
+ * C bindings for tl, a fast HTML parser.
+ *
+ * Documents own a copy of their input and must be freed with tl_document_free.
+ * Nodes are uint32_t handles that stay valid for as long as their document.
+ * Node lists must be freed with tl_node_list_free, and strings with tl_string_free.
+ *
+ * Every fallible function returns a TlStatus and writes its result to the out
+ * pointer only if it returns TL_STATUS_OK.
+ */
+
+#ifndef TL_H
+#define TL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum TlStatus {
+    TL_STATUS_OK = 0,
+    TL_STATUS_NULL_POINTER = 1,
+    TL_STATUS_INVALID_UTF8 = 2,
+    TL_STATUS_PARSE_ERROR = 3,
+    TL_STATUS_INVALID_SELECTOR = 4,
+    TL_STATUS_INVALID_NODE = 5,
+    TL_STATUS_NOT_FOUND = 6,
+    TL_STATUS_INTERNAL_ERROR = 7,
+} TlStatus;
+
+typedef struct TlDocument TlDocument;
+typedef struct TlNodeList TlNodeList;
+
+/* An owned, NUL-terminated string. len does not include the terminator. */
+typedef struct TlString {
+    char *data;
+    size_t len;
+} TlString;
+
+TlStatus tl_parse(const uint8_t *input, size_t len, TlDocument **out);
+void tl_document_free(TlDocument *document);
+TlStatus tl_document_children(const TlDocument *document, TlNodeList **out);
+
+TlStatus tl_query_selector(const TlDocument *document, const char *selector, TlNodeList **out);
+
+size_t tl_node_list_len(const TlNodeList *list);
+TlStatus tl_node_list_get(const TlNodeList *list, size_t index, uint32_t *out);
+void tl_node_list_free(TlNodeList *list);
+
+TlStatus tl_node_inner_text(const TlDocument *document, uint32_t node, TlString *out);
+TlStatus tl_node_outer_html(const TlDocument *document, uint32_t node, TlString *out);
+TlStatus tl_node_tag_name(const TlDocument *document, uint32_t node, TlString *out);
+TlStatus tl_node_attribute(const TlDocument *document, uint32_t node, const char *name, TlString *out);
+
+void tl_string_free(TlString string);
+/* Takes an int so that any value is safe to pass. Unknown values return "unknown status". */
+const char *tl_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TL_H */
@@ -0,0 +1,367 @@
+//! C bindings for [tl](https://docs.rs/tl)
+//!
+//! The functions declared in `include/tl.h` are exported with a stable C ABI, so that bindings for other languages
+//! can be built on top of the shared or static library.
+//!
+//! - Documents are opaque [`TlDocument`] pointers that own their input. They are created by [`tl_parse`]
+//!   and must be freed with [`tl_document_free`].
+//! - Nodes are `uint32_t` handles, which stay valid for as long as the document they belong to.
+//! - Lists of nodes are opaque [`TlNodeList`] pointers, which must be freed with [`tl_node_list_free`].
+//! - Strings are returned as [`TlString`]s, which are NUL-terminated and must be freed with [`tl_string_free`].
+//!   Their length does not include the terminator, so strings containing NUL bytes are not truncated.
+//!
+//! Every fallible function returns a [`TlStatus`] and writes its result to an out pointer only on success.
+//! None of the functions panic across the FFI boundary.
+
+use std::ffi::{c_char, c_int, CStr};
+use std::ptr;
+
+use tl::{HTMLTag, Node, NodeHandle, ParserOptions, VDom, VDomGuard};
+
+/// The result of a fallible function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlStatus {
+    /// The function succeeded
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The input could not be parsed
+    ParseError = 3,
+    /// The query selector could not be parsed
+    InvalidSelector = 4,
+    /// The node handle does not belong to the document, or the node is not an element
+    InvalidNode = 5,
+    /// The requested attribute or list index does not exist
+    NotFound = 6,
+    /// An unexpected error occurred inside the library
+    InternalError = 7,
+}
+
+/// A parsed document that owns its input
+pub struct TlDocument {
+    dom: VDomGuard,
+}
+
+impl TlDocument {
+    fn dom(&self) -> &VDom<'_> {
+        self.dom.get_ref()
+    }
+
+    fn node(&self, node: u32) -> Result<&Node<'_>, TlStatus> {
+        NodeHandle::new(node)
+            .get(self.dom().parser())
+            .ok_or(TlStatus::InvalidNode)
+    }
+
+    fn tag(&self, node: u32) -> Result<&HTMLTag<'_>, TlStatus> {
+        self.node(node)?.as_tag().ok_or(TlStatus::InvalidNode)
+    }
+}
+
+/// A list of node handles
+pub struct TlNodeList {
+    nodes: Vec<u32>,
+}
+
+/// An owned, NUL-terminated string
+///
+/// `len` is the length of the string in bytes, excluding the terminator.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TlString {
+    /// The bytes of the string, followed by a NUL byte
+    pub data: *mut c_char,
+    /// The length of the string in bytes, excluding the terminator
+    pub len: usize,
+}
+
+impl TlString {
+    fn new(string: &str) -> Self {
+        let mut bytes = Vec::with_capacity(string.len() + 1);
+        bytes.extend_from_slice(string.as_bytes());
+        bytes.push(0);
+
+        let len = string.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()).cast::<c_char>();
+        Self { data, len }
+    }
+}
+
+/// Runs `f` and converts its result to a status, writing the value to `out` on success
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+unsafe fn write_result<T>(out: *mut T, f: impl FnOnce() -> Result<T, TlStatus>) -> TlStatus {
+    if out.is_null() {
+        return TlStatus::NullPointer;
+    }
+
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            out.write(value);
+            TlStatus::Ok
+        }
+        Ok(Err(status)) => status,
+        Err(_) => TlStatus::InternalError,
+    }
+}
+
+/// Borrows a document from a pointer
+///
+/// # Safety
+/// `document` must be null or a pointer returned by [`tl_parse`] that was not freed yet.
+unsafe fn as_document<'d>(document: *const TlDocument) -> Result<&'d TlDocument, TlStatus> {
+    document.as_ref().ok_or(TlStatus::NullPointer)
+}
+
+/// Borrows a NUL-terminated UTF-8 string from a pointer
+///
+/// # Safety
+/// `string` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'s>(string: *const c_char) -> Result<&'s str, TlStatus> {
+    if string.is_null() {
+        return Err(TlStatus::NullPointer);
+    }
+
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| TlStatus::InvalidUtf8)
+}
+
+/// Parses `len` bytes of UTF-8 HTML at `input` and writes the document to `out`
+///
+/// The input is copied, so it can be freed after this call. Invalid UTF-8 sequences are replaced.
+///
+/// # Safety
+/// `input` must be valid for reads of `len` bytes, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_parse(
+    input: *const u8,
+    len: usize,
+    out: *mut *mut TlDocument,
+) -> TlStatus {
+    if input.is_null() && len > 0 {
+        return TlStatus::NullPointer;
+    }
+
+    write_result(out, || {
+        let input = match len {
+            0 => String::new(),
+            _ => String::from_utf8_lossy(std::slice::from_raw_parts(input, len)).into_owned(),
+        };
+
+        // SAFETY: the guard owns the input for as long as the document exists
+        let dom =
+            tl::parse_owned(input, ParserOptions::default()).map_err(|_| TlStatus::ParseError)?;
+
+        Ok(Box::into_raw(Box::new(TlDocument { dom })))
+    })
+}
+
+/// Frees a document. Does nothing if `document` is null.
+///
+/// # Safety
+/// `document` must be null or a pointer returned by [`tl_parse`] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tl_document_free(document: *mut TlDocument) {
+    if !document.is_null() {
+        drop(Box::from_raw(document));
+    }
+}
+
+/// Writes the topmost nodes of a document to `out`
+///
+/// # Safety
+/// `document` must be a live document, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_document_children(
+    document: *const TlDocument,
+    out: *mut *mut TlNodeList,
+) -> TlStatus {
+    write_result(out, || {
+        let nodes = as_document(document)?
+            .dom()
+            .children()
+            .iter()
+            .map(NodeHandle::get_inner)
+            .collect();
+
+        Ok(Box::into_raw(Box::new(TlNodeList { nodes })))
+    })
+}
+
+/// Writes all elements that match a NUL-terminated query selector to `out`, in document order
+///
+/// # Safety
+/// `document` must be a live document, `selector` must point to a NUL-terminated string, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_query_selector(
+    document: *const TlDocument,
+    selector: *const c_char,
+    out: *mut *mut TlNodeList,
+) -> TlStatus {
+    write_result(out, || {
+        let selector = str_arg(selector)?;
+        let nodes = as_document(document)?
+            .dom()
+            .query_selector(selector)
+            .ok_or(TlStatus::InvalidSelector)?
+            .map(|handle| handle.get_inner())
+            .collect();
+
+        Ok(Box::into_raw(Box::new(TlNodeList { nodes })))
+    })
+}
+
+/// Returns the number of nodes in a list, or 0 if `list` is null
+///
+/// # Safety
+/// `list` must be null or a live node list.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_list_len(list: *const TlNodeList) -> usize {
+    list.as_ref().map_or(0, |list| list.nodes.len())
+}
+
+/// Writes the node at `index` of a list to `out`
+///
+/// # Safety
+/// `list` must be a live node list, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_list_get(
+    list: *const TlNodeList,
+    index: usize,
+    out: *mut u32,
+) -> TlStatus {
+    write_result(out, || {
+        let list = list.as_ref().ok_or(TlStatus::NullPointer)?;
+        list.nodes.get(index).copied().ok_or(TlStatus::NotFound)
+    })
+}
+
+/// Frees a node list. Does nothing if `list` is null.
+///
+/// # Safety
+/// `list` must be null or a live node list.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_list_free(list: *mut TlNodeList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+/// Writes the text of a node and all of its subnodes to `out`
+///
+/// # Safety
+/// `document` must be a live document, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_inner_text(
+    document: *const TlDocument,
+    node: u32,
+    out: *mut TlString,
+) -> TlStatus {
+    write_result(out, || {
+        let document = as_document(document)?;
+        let text = document.node(node)?.inner_text(document.dom().parser());
+        Ok(TlString::new(&text))
+    })
+}
+
+/// Writes the markup of a node, including the node itself, to `out`
+///
+/// # Safety
+/// `document` must be a live document, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_outer_html(
+    document: *const TlDocument,
+    node: u32,
+    out: *mut TlString,
+) -> TlStatus {
+    write_result(out, || {
+        let document = as_document(document)?;
+        let html = document.node(node)?.outer_html(document.dom().parser());
+        Ok(TlString::new(&html))
+    })
+}
+
+/// Writes the name of an element to `out`
+///
+/// # Safety
+/// `document` must be a live document, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_tag_name(
+    document: *const TlDocument,
+    node: u32,
+    out: *mut TlString,
+) -> TlStatus {
+    write_result(out, || {
+        let name = as_document(document)?.tag(node)?.name().as_utf8_str();
+        Ok(TlString::new(&name))
+    })
+}
+
+/// Writes the value of the attribute with the NUL-terminated `name` of an element to `out`
+///
+/// Attributes without a value, like `disabled`, are written as an empty string.
+/// Returns [`TlStatus::NotFound`] if the element does not have the attribute.
+///
+/// # Safety
+/// `document` must be a live document, `name` must point to a NUL-terminated string, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tl_node_attribute(
+    document: *const TlDocument,
+    node: u32,
+    name: *const c_char,
+    out: *mut TlString,
+) -> TlStatus {
+    write_result(out, || {
+        let name = str_arg(name)?;
+        let value = as_document(document)?
+            .tag(node)?
+            .attributes()
+            .get(name)
+            .ok_or(TlStatus::NotFound)?;
+
+        Ok(TlString::new(
+            &value.map(|v| v.as_utf8_str()).unwrap_or_default(),
+        ))
+    })
+}
+
+/// Frees a string. Does nothing if its data is null.
+///
+/// # Safety
+/// `string` must have been returned by one of the functions of this library and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tl_string_free(string: TlString) {
+    if !string.data.is_null() {
+        let bytes = ptr::slice_from_raw_parts_mut(string.data.cast::<u8>(), string.len + 1);
+        drop(Box::from_raw(bytes));
+    }
+}
+
+/// Returns a static, NUL-terminated description of a status
+///
+/// The status is taken as an integer, because C callers can pass any value of the enum type.
+/// Values that are not a [`TlStatus`] return a description of an unknown status.
+#[no_mangle]
+pub extern "C" fn tl_status_message(status: c_int) -> *const c_char {
+    let message: &'static CStr = match status {
+        0 => c"ok",
+        1 => c"a required pointer was null",
+        2 => c"a string argument was not valid UTF-8",
+        3 => c"the input could not be parsed",
+        4 => c"the query selector could not be parsed",
+        5 => c"the node does not exist or is not an element",
+        6 => c"the requested item does not exist",
+        7 => c"an unexpected internal error occurred",
+        _ => c"unknown status",
+    };
+
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,174 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use super::*;
+
+const HTML: &str = r#"<div id="a"><p class="x">Hello</p><p class="x" hidden>World</p></div>"#;
+
+fn parse(html: &str) -> *mut TlDocument {
+    let mut document = ptr::null_mut();
+    let status = unsafe { tl_parse(html.as_ptr(), html.len(), &mut document) };
+    assert_eq!(status, TlStatus::Ok);
+    assert!(!document.is_null());
+    document
+}
+
+fn query(document: *const TlDocument, selector: &CStr) -> Vec<u32> {
+    let mut list = ptr::null_mut();
+    let status = unsafe { tl_query_selector(document, selector.as_ptr(), &mut list) };
+    assert_eq!(status, TlStatus::Ok);
+
+    let len = unsafe { tl_node_list_len(list) };
+    let nodes = (0..len)
+        .map(|i| {
+            let mut node = 0;
+            assert_eq!(
+                unsafe { tl_node_list_get(list, i, &mut node) },
+                TlStatus::Ok
+            );
+            node
+        })
+        .collect();
+
+    unsafe { tl_node_list_free(list) };
+    nodes
+}
+
+fn take(string: TlString) -> String {
+    let bytes = unsafe { std::slice::from_raw_parts(string.data.cast::<u8>(), string.len + 1) };
+    assert_eq!(bytes[string.len], 0);
+
+    let owned = String::from_utf8(bytes[..string.len].to_vec()).unwrap();
+    unsafe { tl_string_free(string) };
+    owned
+}
+
+fn string_result(f: impl FnOnce(*mut TlString) -> TlStatus) -> Result<String, TlStatus> {
+    let mut string = TlString {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    match f(&mut string) {
+        TlStatus::Ok => Ok(take(string)),
+        status => Err(status),
+    }
+}
+
+#[test]
+fn query_and_read() {
+    let document = parse(HTML);
+
+    let nodes = query(document, c"p.x");
+    assert_eq!(nodes.len(), 2);
+
+    let text = string_result(|out| unsafe { tl_node_inner_text(document, nodes[1], out) });
+    assert_eq!(text.as_deref(), Ok("World"));
+
+    let name = string_result(|out| unsafe { tl_node_tag_name(document, nodes[0], out) });
+    assert_eq!(name.as_deref(), Ok("p"));
+
+    let html = string_result(|out| unsafe { tl_node_outer_html(document, nodes[0], out) });
+    assert_eq!(html.as_deref(), Ok(r#"<p class="x">Hello</p>"#));
+
+    let class = string_result(|out| unsafe {
+        tl_node_attribute(document, nodes[0], c"class".as_ptr(), out)
+    });
+    assert_eq!(class.as_deref(), Ok("x"));
+
+    let hidden = string_result(|out| unsafe {
+        tl_node_attribute(document, nodes[1], c"hidden".as_ptr(), out)
+    });
+    assert_eq!(hidden.as_deref(), Ok(""));
+
+    let missing =
+        string_result(|out| unsafe { tl_node_attribute(document, nodes[0], c"id".as_ptr(), out) });
+    assert_eq!(missing, Err(TlStatus::NotFound));
+
+    let mut children = ptr::null_mut();
+    assert_eq!(
+        unsafe { tl_document_children(document, &mut children) },
+        TlStatus::Ok
+    );
+    assert_eq!(unsafe { tl_node_list_len(children) }, 1);
+    unsafe { tl_node_list_free(children) };
+
+    unsafe { tl_document_free(document) };
+}
+
+#[test]
+fn errors() {
+    let document = parse(HTML);
+
+    let mut list = ptr::null_mut();
+    assert_eq!(
+        unsafe { tl_query_selector(document, ptr::null(), &mut list) },
+        TlStatus::NullPointer
+    );
+    assert_eq!(
+        unsafe { tl_query_selector(ptr::null(), c"p".as_ptr(), &mut list) },
+        TlStatus::NullPointer
+    );
+    assert_eq!(
+        unsafe { tl_query_selector(document, c"\xff".as_ptr(), &mut list) },
+        TlStatus::InvalidUtf8
+    );
+    assert!(list.is_null());
+
+    let mut node = 0;
+    let nodes = query(document, c"div");
+    let mut list = ptr::null_mut();
+    unsafe { tl_query_selector(document, c"div".as_ptr(), &mut list) };
+    assert_eq!(
+        unsafe { tl_node_list_get(list, nodes.len(), &mut node) },
+        TlStatus::NotFound
+    );
+    unsafe { tl_node_list_free(list) };
+
+    // text nodes have no tag name, and handles past the end of the document do not exist
+    let text = nodes[0] + 2;
+    let name = string_result(|out| unsafe { tl_node_tag_name(document, text, out) });
+    assert_eq!(name, Err(TlStatus::InvalidNode));
+
+    let text = string_result(|out| unsafe { tl_node_inner_text(document, 1000, out) });
+    assert_eq!(text, Err(TlStatus::InvalidNode));
+
+    let message = unsafe { CStr::from_ptr(tl_status_message(TlStatus::InvalidNode as c_int)) };
+    assert_eq!(message, c"the node does not exist or is not an element");
+    let message = unsafe { CStr::from_ptr(tl_status_message(-1)) };
+    assert_eq!(message, c"unknown status");
+    let message = unsafe { CStr::from_ptr(tl_status_message(8)) };
+    assert_eq!(message, c"unknown status");
+
+    // freeing null pointers is allowed
+    unsafe {
+        tl_document_free(ptr::null_mut());
+        tl_node_list_free(ptr::null_mut());
+        tl_string_free(TlString {
+            data: ptr::null_mut(),
+            len: 0,
+        });
+    }
+
+    unsafe { tl_document_free(document) };
+}
+
+#[test]
+fn lossy_input() {
+    let input = b"<p>a\xffb</p>";
+    let mut document = ptr::null_mut();
+    assert_eq!(
+        unsafe { tl_parse(input.as_ptr(), input.len(), &mut document) },
+        TlStatus::Ok
+    );
+
+    let nodes = query(document, c"p");
+    let text = string_result(|out| unsafe { tl_node_inner_text(document, nodes[0], out) });
+    assert_eq!(text.as_deref(), Ok("a\u{fffd}b"));
+
+    unsafe { tl_document_free(document) };
+
+    let empty = parse("");
+    assert!(query(empty, c"p").is_empty());
+    unsafe { tl_document_free(empty) };
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use crate::text::is_one_of;
+use crate::{Bytes, Edge, HTMLTag, NodeHandle, Parser, VDom};
+
+/// Input types that do not need a label, because they are not shown or are labeled by their value or `alt` text
+const UNLABELED_INPUT_TYPES: &[&str] = &["hidden", "submit", "reset", "button", "image"];
+
+/// An accessibility problem, as found by [`audit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The document has no language, because its `<html>` element has no `lang` attribute
+    MissingLang,
+    /// An `<img>` has no `alt` attribute and no other text alternative
+    MissingAlt,
+    /// An `<input>`, `<select>` or `<textarea>` has no label
+    MissingLabel,
+    /// A heading skips levels, like an `<h4>` that follows an `<h2>`
+    SkippedHeadingLevel {
+        /// The level of the previous heading
+        previous: u32,
+        /// The level of this heading
+        level: u32,
+    },
+    /// An element has the same `id` as an earlier element
+    DuplicateId(String),
+}
+
+/// An accessibility problem of a node, as returned by [`audit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The element with the problem, or `None` for problems of the whole document without an `<html>` element
+    pub node: Option<NodeHandle>,
+    /// The problem
+    pub issue: Issue,
+}
+
+/// Runs basic accessibility checks over a document
+///
+/// This finds images without a text alternative, form controls without a label, headings that skip levels,
+/// a missing `lang` attribute on the document and duplicate IDs. These are the most common problems that can be found
+/// from the markup alone, so this is no replacement for a full audit in a browser.
+///
+/// - Images have a text alternative if they have an `alt` attribute, even an empty one for decorative images,
+///   or a non-empty `aria-label`, `aria-labelledby` or `title` attribute, or the `presentation` or `none` role.
+/// - Form controls have a label if they are inside of a `<label>`, are referenced by the `for` attribute of a `<label>`,
+///   or have a non-empty `aria-label`, `aria-labelledby` or `title` attribute. Placeholders are not labels.
+/// - Headings are determined like in [`VDom::outline`](crate::VDom::outline). The first heading may have any level.
+///
+/// The contents of `<template>` elements and of elements with the `hidden` attribute are not checked.
+/// Findings are returned in document order, starting with the ones for the whole document.
+///
+/// # Example
+/// ```
+/// use tl::a11y::{audit, Issue};
+///
+/// let dom = tl::parse(r#"<html><body>
+///     <h1>Shop</h1><h3>Offers</h3>
+///     <img src="logo.png"><img src="line.png" alt="">
+///     <label>Name <input name="name"></label><input name="email">
+/// </body></html>"#, Default::default()).unwrap();
+///
+/// let issues = audit(&dom).into_iter().map(|finding| finding.issue).collect::<Vec<_>>();
+///
+/// assert_eq!(issues, [
+///     Issue::MissingLang,
+///     Issue::SkippedHeadingLevel { previous: 1, level: 3 },
+///     Issue::MissingAlt,
+///     Issue::MissingLabel,
+/// ]);
+/// ```
+pub fn audit(dom: &VDom<'_>) -> Vec<Finding> {
+    let parser = dom.parser();
+    let mut findings = Vec::new();
+
+    if dom.lang().is_none() {
+        let html = dom
+            .children()
+            .iter()
+            .copied()
+            .find(|handle| tag(*handle, parser).is_some_and(|tag| is_named(tag, "html")));
+
+        findings.push(Finding {
+            node: html,
+            issue: Issue::MissingLang,
+        });
+    }
+
+    let labeled_ids = labeled_ids(dom);
+    let mut ids = HashSet::new();
+    let mut labels = 0;
+
+    let mut traverse = dom.traverse();
+    while let Some(edge) = traverse.next() {
+        let (handle, open) = match edge {
+            Edge::Open(handle) => (handle, true),
+            Edge::Close(handle) => (handle, false),
+        };
+        let Some(tag) = tag(handle, parser) else {
+            continue;
+        };
+
+        if !open {
+            labels -= is_named(tag, "label") as usize;
+            continue;
+        }
+
+        labels += is_named(tag, "label") as usize;
+
+        if is_named(tag, "template") || tag.attributes().contains("hidden") {
+            traverse.skip_children();
+            continue;
+        }
+
+        let issue = if is_named(tag, "img") {
+            (!has_text_alternative(tag)).then_some(Issue::MissingAlt)
+        } else if is_form_control(tag) {
+            let is_labeled = labels > 0
+                || has_name(tag)
+                || tag
+                    .attributes()
+                    .id()
+                    .is_some_and(|id| labeled_ids.contains(id));
+
+            (!is_labeled).then_some(Issue::MissingLabel)
+        } else {
+            None
+        };
+
+        let duplicate_id = tag
+            .attributes()
+            .id()
+            .filter(|id| !ids.insert(*id))
+            .map(|id| Issue::DuplicateId(id.as_utf8_str().into_owned()));
+
+        for issue in issue.into_iter().chain(duplicate_id) {
+            findings.push(Finding {
+                node: Some(handle),
+                issue,
+            });
+        }
+    }
+
+    let headings = dom.outline();
+    let mut previous: Option<u32> = None;
+    for heading in headings.iter().flat_map(|heading| heading.iter()) {
+        if let Some(previous) = previous.filter(|&previous| heading.level > previous + 1) {
+            findings.push(Finding {
+                node: Some(heading.node),
+                issue: Issue::SkippedHeadingLevel {
+                    previous,
+                    level: heading.level,
+                },
+            });
+        }
+        previous = Some(heading.level);
+    }
+
+    // findings without a node come first, the others are in document order
+    findings.sort_by_key(|finding| finding.node);
+    findings
+}
+
+fn tag<'p, 'a>(handle: NodeHandle, parser: &'p Parser<'a>) -> Option<&'p HTMLTag<'a>> {
+    handle.get(parser)?.as_tag()
+}
+
+fn is_named(tag: &HTMLTag<'_>, name: &str) -> bool {
+    tag.name().as_bytes().eq_ignore_ascii_case(name.as_bytes())
+}
+
+/// Returns the IDs that the `for` attributes of `<label>` elements refer to
+fn labeled_ids<'p, 'a>(dom: &'p VDom<'a>) -> HashSet<&'p Bytes<'a>> {
+    dom.nodes()
+        .iter()
+        .filter_map(|node| node.as_tag())
+        .filter(|tag| is_named(tag, "label"))
+        .filter_map(|tag| tag.attributes().get("for").flatten())
+        .collect()
+}
+
+/// Checks whether an element is a form control that needs a label
+fn is_form_control(tag: &HTMLTag<'_>) -> bool {
+    if is_named(tag, "select") || is_named(tag, "textarea") {
+        return true;
+    }
+
+    is_named(tag, "input")
+        && !tag
+            .attributes()
+            .get("type")
+            .flatten()
+            .is_some_and(|ty| is_one_of(ty.as_bytes().trim_ascii(), UNLABELED_INPUT_TYPES))
+}
+
+/// Checks whether an element has an accessible name from an attribute
+fn has_name(tag: &HTMLTag<'_>) -> bool {
+    ["aria-label", "aria-labelledby", "title"]
+        .iter()
+        .any(|name| {
+            tag.attributes()
+                .get(*name)
+                .flatten()
+                .is_some_and(|value| !value.as_bytes().trim_ascii().is_empty())
+        })
+}
+
+/// Checks whether an image has a text alternative or is marked as decorative
+pub(crate) fn has_text_alternative(tag: &HTMLTag<'_>) -> bool {
+    let attributes = tag.attributes();
+    let is_presentational = attributes.get("role").flatten().is_some_and(|role| {
+        role.as_bytes().split(u8::is_ascii_whitespace).any(|role| {
+            role.eq_ignore_ascii_case(b"presentation") || role.eq_ignore_ascii_case(b"none")
+        })
+    });
+
+    attributes.contains("alt") || is_presentational || has_name(tag)
+}
@@ -1,6 +1,6 @@
 use core::{fmt, fmt::Debug};
 use std::{
-    borrow::Cow,
+    borrow::{Borrow, Cow},
     hash::{Hash, Hasher},
     marker::PhantomData,
     mem::ManuallyDrop,
@@ -33,6 +33,11 @@ enum BytesInner {
     Owned(*mut u8, u32),
 }
 
+// SAFETY: `Bytes` is semantically either a `&'a [u8]` or a `Box<[u8]>`, both of which are `Send` and `Sync`.
+// Raw pointers are only used to keep the size of `Bytes` small, and the data is never mutated through a shared reference.
+unsafe impl<'a> Send for Bytes<'a> {}
+unsafe impl<'a> Sync for Bytes<'a> {}
+
 impl<'a> PartialEq<str> for Bytes<'a> {
     #[inline]
     fn eq(&self, other: &str) -> bool {
@@ -56,6 +61,14 @@ impl<'a> PartialEq for Bytes<'a> {
     }
 }
 
+// `Hash` hashes the same bytes, so maps with `Bytes` keys can be searched with slices
+impl<'a> Borrow<[u8]> for Bytes<'a> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
 impl<'a> Hash for Bytes<'a> {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -148,6 +161,13 @@ impl<'a> Debug for Bytes<'a> {
     }
 }
 
+impl<'a> Default for Bytes<'a> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Bytes<'a> {
     /// Creates an empty `Bytes`
     #[inline]
@@ -171,6 +191,15 @@ impl<'a> Bytes<'a> {
         std::str::from_utf8(self.as_bytes()).ok()
     }
 
+    /// Returns the number of bytes that this struct owns on the heap, which is zero for borrowed data
+    #[inline]
+    pub(crate) fn heap_size(&self) -> usize {
+        match self.data {
+            BytesInner::Borrowed(..) => 0,
+            BytesInner::Owned(_, len) => len as usize,
+        }
+    }
+
     /// Returns the raw data wrapped by this struct
     #[inline]
     pub fn as_bytes(&self) -> &[u8] {
@@ -201,6 +230,23 @@ impl<'a> Bytes<'a> {
         }
     }
 
+    /// Copies the data into new bytes that own it, so they do not borrow from the source of a document anymore
+    ///
+    /// # Example
+    /// ```
+    /// let bytes = {
+    ///     let source = String::from("hello");
+    ///     tl::Bytes::from(source.as_str()).to_static()
+    /// };
+    /// assert_eq!(bytes.as_bytes(), b"hello");
+    /// ```
+    pub fn to_static(&self) -> Bytes<'static> {
+        let mut bytes = Bytes::new();
+        // SAFETY: the length of the data already fits in a u32, because it is stored in `self`
+        unsafe { bytes.set_unchecked(self.as_bytes()) };
+        bytes
+    }
+
     /// Sets the inner data to the given data and returns the old bytes
     pub fn set<B: IntoOwnedBytes>(&mut self, data: B) -> Result<Option<Box<[u8]>>, SetBytesError> {
         const MAX: usize = u32::MAX as usize;
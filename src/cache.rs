@@ -0,0 +1,1083 @@
+//! The cache format is laid out as follows, where all integers are LEB128 varints:
+//!
+//! ```text
+//! header   magic (4 bytes), source length, source, options, HTML version (1 byte), modified (1 byte)
+//! options  flags (1 byte), count, [tracked attribute], count, [extra void element], node capacity,
+//!          duplicate attributes (1 byte), time budget (0 or 1, seconds, nanoseconds), strict (1 byte)
+//! names    count, [bytes]
+//! nodes    count, [node]
+//! ast      count, [handle]
+//! ```
+//!
+//! Nodes only store offsets into the source string, unless their data was replaced with owned data.
+//! Tag names and attribute keys are stored once in the names table and referenced by index.
+//! The lookup tables of IDs, classes, tag names and attribute values are not stored, but rebuilt from the nodes
+//! according to the options.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::errors::CacheError;
+use crate::fragment::register;
+use crate::inline::{hashmap::InlineHashMap, vec::InlineVec};
+use crate::parser::{
+    known_attribute, Attributes, Doctype, ElementName, HTMLTag, HTMLVersion, Namespace, Node,
+    NodeHandle, Parser, Span, TagName, TagSpan,
+};
+use crate::{Bytes, DuplicateAttributes, ParserOptions, VDom, VDomGuard, MAX_TRACKED_ATTRIBUTES};
+
+/// Identifies the format and its version. The last byte must be bumped on every format change.
+const MAGIC: &[u8; 4] = b"tl\0\x06";
+
+const NODE_TAG: u8 = 0;
+const NODE_RAW: u8 = 1;
+const NODE_COMMENT: u8 = 2;
+const NODE_PROCESSING_INSTRUCTION: u8 = 3;
+const NODE_CDATA: u8 = 4;
+const NODE_DOCTYPE: u8 = 5;
+
+const HAS_ID: u8 = 1 << 0;
+const HAS_CLASS: u8 = 1 << 1;
+const MODIFIED: u8 = 1 << 2;
+const VOID: u8 = 1 << 3;
+
+/// Serializes a DOM into the compact binary cache format
+///
+/// The returned buffer contains the source string, so it can be loaded without access to the original input.
+/// This is usually much faster than parsing the HTML again.
+///
+/// The parser options are stored too, except for the callback of [`ParserOptions::node_filter`],
+/// which cannot be serialized. It only affects parsing, so the nodes that it left out stay left out.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(r#"<p id="a">Hello</p>"#, tl::ParserOptions::default().track_ids()).unwrap();
+/// let cache = tl::cache::serialize(&dom);
+///
+/// let dom = tl::cache::load(&cache).unwrap();
+/// let p = dom.get_element_by_id("a").unwrap().get(dom.parser()).unwrap();
+/// assert_eq!(p.inner_text(dom.parser()), "Hello");
+/// ```
+pub fn serialize(dom: &VDom<'_>) -> Vec<u8> {
+    let parser = dom.parser();
+    let source = parser.stream.data();
+
+    let mut writer = Writer {
+        out: Vec::with_capacity(source.len() + parser.tags.len() * 8),
+        source,
+        names: HashMap::new(),
+        name_list: Vec::new(),
+    };
+
+    let mut nodes = Vec::with_capacity(parser.tags.len() * 8);
+    std::mem::swap(&mut writer.out, &mut nodes);
+    writer.varint(parser.tags.len());
+    for node in &parser.tags {
+        writer.node(node);
+    }
+    writer.handles(&parser.ast);
+    std::mem::swap(&mut writer.out, &mut nodes);
+
+    writer.out.extend_from_slice(MAGIC);
+    writer.varint(source.len());
+    writer.out.extend_from_slice(source);
+    writer.options(&parser.options);
+    writer.out.push(match parser.version {
+        None => 0,
+        Some(HTMLVersion::HTML5) => 1,
+        Some(HTMLVersion::StrictHTML401) => 2,
+        Some(HTMLVersion::TransitionalHTML401) => 3,
+        Some(HTMLVersion::FramesetHTML401) => 4,
+    });
+    writer.out.push(parser.modified as u8);
+
+    let names = std::mem::take(&mut writer.name_list);
+    writer.varint(names.len());
+    for (borrowed, bytes) in names {
+        writer.bytes(borrowed, bytes);
+    }
+
+    writer.out.extend_from_slice(&nodes);
+    writer.out
+}
+
+/// Loads a DOM that was previously serialized with [`serialize`]
+///
+/// The returned DOM borrows from `data`, no bytes of the source string are copied.
+///
+/// The names of tracked attributes and extra void elements in the options must be `'static`,
+/// so every distinct name that is not a known element or attribute name is copied once and kept for the rest of the program.
+/// At most 64 KiB are allocated for such names in total, after which loading caches with new names fails.
+///
+/// # Errors
+/// Returns an error if `data` was not created by [`serialize`] of a compatible version of this crate,
+/// or if the tree is malformed: a node is the child of more than one node, or a child comes before its parent.
+/// The latter can happen if the children of a DOM were edited with [`ChildrenMut::top_mut`](crate::ChildrenMut::top_mut).
+/// Loading also fails with [`CacheError::InvalidData`] if the options contain new names after the limit for names was reached.
+pub fn load(data: &[u8]) -> Result<VDom<'_>, CacheError> {
+    let mut reader = Reader {
+        data,
+        idx: 0,
+        document: 0,
+    };
+    let source = reader.header()?;
+    reader.parser(source).map(VDom::from)
+}
+
+/// Loads a DOM that was previously serialized with [`serialize`] into a [`VDomGuard`]
+///
+/// Unlike [`load`], this copies the source string so that the returned DOM does not borrow from `data`.
+///
+/// # Errors
+/// Returns an error if `data` was not created by [`serialize`] of a compatible version of this crate,
+/// or if the serialized source string is not valid UTF-8.
+pub fn load_owned(data: &[u8]) -> Result<VDomGuard, CacheError> {
+    let mut reader = Reader {
+        data,
+        idx: 0,
+        document: 0,
+    };
+    let source = reader.header()?;
+    let source = std::str::from_utf8(source).map_err(|_| CacheError::InvalidUtf8)?;
+
+    VDomGuard::try_new(source.to_owned(), |source| reader.parser(source.as_bytes()))
+}
+
+struct Writer<'s> {
+    out: Vec<u8>,
+    source: &'s [u8],
+    /// Maps names to their index in the names table
+    names: HashMap<&'s [u8], usize>,
+    name_list: Vec<(Option<&'s [u8]>, &'s [u8])>,
+}
+
+impl<'s> Writer<'s> {
+    fn varint(&mut self, mut value: usize) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                self.out.push(byte);
+                return;
+            }
+
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    /// Writes bytes as an offset into the source string if it points into it, otherwise writes the data itself
+    fn bytes(&mut self, borrowed: Option<&[u8]>, bytes: &[u8]) {
+        let source = self.source.as_ptr() as usize;
+        let offset = borrowed
+            .and_then(|b| (b.as_ptr() as usize).checked_sub(source))
+            .filter(|offset| offset + bytes.len() <= self.source.len());
+
+        match offset {
+            Some(offset) => {
+                self.varint(offset << 1);
+                self.varint(bytes.len());
+            }
+            None => {
+                self.varint((bytes.len() << 1) | 1);
+                self.out.extend_from_slice(bytes);
+            }
+        }
+    }
+
+    fn name(&mut self, name: &'s Bytes<'_>) {
+        let bytes = name.as_bytes();
+        let next = self.name_list.len();
+        let index = *self.names.entry(bytes).or_insert(next);
+
+        if index == next {
+            self.name_list.push((name.as_bytes_borrowed(), bytes));
+        }
+
+        self.varint(index);
+    }
+
+    fn options(&mut self, options: &ParserOptions) {
+        self.out.push(options.to_raw());
+
+        let attributes = options.tracked_attributes().collect::<Vec<_>>();
+        self.varint(attributes.len());
+        for name in attributes {
+            self.bytes(None, name.as_bytes());
+        }
+
+        let void_elements = options.void_elements();
+        self.varint(void_elements.len());
+        for name in void_elements {
+            self.bytes(None, name.as_bytes());
+        }
+
+        self.varint(options.node_capacity());
+        self.out.push(match options.duplicate_attributes() {
+            DuplicateAttributes::KeepLast => 0,
+            DuplicateAttributes::KeepFirst => 1,
+        });
+
+        match options.get_time_budget() {
+            Some(budget) => {
+                self.out.push(1);
+                self.varint(budget.as_secs().min(usize::MAX as u64) as usize);
+                self.varint(budget.subsec_nanos() as usize);
+            }
+            None => self.out.push(0),
+        }
+
+        self.out.push(options.is_strict() as u8);
+    }
+
+    fn handles(&mut self, handles: &[NodeHandle]) {
+        self.varint(handles.len());
+        for handle in handles {
+            self.varint(handle.get_inner() as usize);
+        }
+    }
+
+    fn optional_bytes(&mut self, bytes: &Option<Bytes<'_>>) {
+        match bytes {
+            Some(bytes) => {
+                self.out.push(1);
+                self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+            }
+            None => self.out.push(0),
+        }
+    }
+
+    fn node(&mut self, node: &'s Node<'_>) {
+        match node {
+            Node::Raw(bytes) => {
+                self.out.push(NODE_RAW);
+                self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+            }
+            Node::Comment(bytes) => {
+                self.out.push(NODE_COMMENT);
+                self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+            }
+            Node::ProcessingInstruction(bytes) => {
+                self.out.push(NODE_PROCESSING_INSTRUCTION);
+                self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+            }
+            Node::CData(bytes) => {
+                self.out.push(NODE_CDATA);
+                self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+            }
+            Node::Doctype(doctype) => {
+                self.out.push(NODE_DOCTYPE);
+                let raw = doctype.raw();
+                self.bytes(raw.as_bytes_borrowed(), raw.as_bytes());
+            }
+            Node::Tag(tag) => {
+                self.out.push(NODE_TAG);
+                self.name(tag._name.bytes());
+                self.varint(tag._parent.map_or(0, |p| p.get_inner() as usize + 1));
+
+                let span = tag._span;
+                self.varint(span.start_tag.start());
+                self.varint(span.start_tag.len());
+                match span.end_tag {
+                    Some(end) => {
+                        self.varint(end.start() + 1);
+                        self.varint(end.len());
+                    }
+                    None => self.varint(0),
+                }
+
+                self.bytes(tag._raw.as_bytes_borrowed(), tag._raw.as_bytes());
+
+                let attributes = &tag._attributes;
+                let mut flags = 0;
+                if attributes.id.is_some() {
+                    flags |= HAS_ID;
+                }
+                if attributes.class.is_some() {
+                    flags |= HAS_CLASS;
+                }
+                if tag._modified {
+                    flags |= MODIFIED;
+                }
+                if tag._void {
+                    flags |= VOID;
+                }
+                self.out.push(flags);
+                for bytes in [&attributes.id, &attributes.class].into_iter().flatten() {
+                    self.bytes(bytes.as_bytes_borrowed(), bytes.as_bytes());
+                }
+
+                self.varint(attributes.raw.len());
+                for (key, value) in attributes.raw.iter() {
+                    self.name(key);
+                    self.optional_bytes(value);
+                }
+
+                self.handles(tag._children.as_slice());
+            }
+        }
+    }
+}
+
+struct Reader<'d> {
+    data: &'d [u8],
+    idx: usize,
+    /// The document that handles belong to, see [`Parser::handle`]
+    document: u32,
+}
+
+impl<'d> Reader<'d> {
+    /// Reads the magic bytes and the source string
+    fn header(&mut self) -> Result<&'d [u8], CacheError> {
+        if self.take(MAGIC.len())? != MAGIC {
+            return Err(CacheError::InvalidHeader);
+        }
+
+        let len = self.varint()?;
+        self.take(len)
+    }
+
+    /// Reads everything after the header. Borrowed bytes are resolved against `source`.
+    fn parser<'s>(&mut self, source: &'s [u8]) -> Result<Parser<'s>, CacheError> {
+        let options = self.options()?;
+        let version = match self.byte()? {
+            0 => None,
+            1 => Some(HTMLVersion::HTML5),
+            2 => Some(HTMLVersion::StrictHTML401),
+            3 => Some(HTMLVersion::TransitionalHTML401),
+            4 => Some(HTMLVersion::FramesetHTML401),
+            _ => return Err(CacheError::InvalidData),
+        };
+        let modified = match self.byte()? {
+            0 => false,
+            1 => true,
+            _ => return Err(CacheError::InvalidData),
+        };
+
+        let name_count = self.varint()?;
+        let mut names = Vec::with_capacity(name_count.min(self.data.len()));
+        for _ in 0..name_count {
+            names.push(self.bytes(source)?);
+        }
+
+        let node_count = self.varint()?;
+        if node_count > u32::MAX as usize {
+            return Err(CacheError::InvalidData);
+        }
+
+        // the capacity is only a hint for parsing, the nodes are allocated below
+        let mut parser = Parser::new_bytes(source, options.with_capacity(0, 0));
+        parser.options = options;
+        self.document = parser.document();
+        parser.version = version;
+        parser.modified = modified;
+        parser.tags = Vec::with_capacity(node_count.min(self.data.len()));
+        // the sibling lookups of the parser compute the parents again when they are needed
+        let mut parents = vec![None; node_count.min(self.data.len())];
+
+        for _ in 0..node_count {
+            let mut node = self.node(source, &names, node_count)?;
+            let index = parser.tags.len();
+            // the parent of a node is known once it is read, because children come after their parent
+            let listed_parent = *parents.get(index).ok_or(CacheError::InvalidData)?;
+
+            if let Node::Tag(tag) = &mut node {
+                // nodes that were removed from their parent still refer to it, but are not listed as its child
+                if listed_parent.is_some() && tag._parent != listed_parent {
+                    return Err(CacheError::InvalidData);
+                }
+
+                // so is the namespace of the parent
+                let parent = tag
+                    ._parent
+                    .and_then(|parent| parser.tags.get(parent.get_inner() as usize))
+                    .and_then(Node::as_tag);
+                tag._namespace = if options.is_xml_mode() {
+                    let xmlns = tag._attributes.get("xmlns").flatten().map(Bytes::as_bytes);
+                    Namespace::of_xml_element(xmlns, parent.map(|parent| parent._namespace))
+                } else {
+                    Namespace::of_element(tag._name.as_bytes(), parent)
+                };
+
+                let this = parser.handle(index as u32);
+
+                for child in tag._children.iter() {
+                    let child = child.get_inner() as usize;
+                    let parent = parents.get_mut(child).ok_or(CacheError::InvalidData)?;
+
+                    if child <= index || parent.is_some() {
+                        return Err(CacheError::InvalidData);
+                    }
+                    *parent = Some(this);
+                }
+
+                register(&mut parser, this, tag);
+            }
+
+            parser.tags.push(node);
+        }
+        parents.resize(node_count, None);
+
+        // top level nodes have no parent, and nodes that were removed from their parent are not listed at all
+        parser.ast = self.handles(node_count)?;
+        let mut listed = vec![false; node_count];
+        for handle in &parser.ast {
+            let index = handle.get_inner() as usize;
+            if parents[index].is_some() || std::mem::replace(&mut listed[index], true) {
+                return Err(CacheError::InvalidData);
+            }
+        }
+
+        // descendants are not stored, because they follow from the children: children come after their parent,
+        // so visiting the nodes backwards knows the descendants of every child before its parent.
+        // A range only covers the subtrees of children that follow each other, and the nodes between them that are
+        // not part of the tree anymore, e.g. because they were removed or merged. Like in a DOM whose children were
+        // moved, it ends before the first child that does not start where the previous one ended.
+        let detached = |index: usize| parents[index].is_none() && !listed[index];
+        let mut ends = (1..=node_count as u32).collect::<Vec<_>>();
+        for index in (0..node_count).rev() {
+            if let Node::Tag(tag) = &parser.tags[index] {
+                let mut end = index + 1;
+
+                for child in tag._children.iter() {
+                    let child = child.get_inner() as usize;
+                    while end < child && detached(end) {
+                        end = ends[end] as usize;
+                    }
+                    if end != child {
+                        break;
+                    }
+                    end = ends[child] as usize;
+                }
+
+                ends[index] = end as u32;
+            }
+        }
+        for (index, node) in parser.tags.iter_mut().enumerate() {
+            if let Node::Tag(tag) = node {
+                tag._descendants = index as u32 + 1..ends[index];
+            }
+        }
+
+        Ok(parser)
+    }
+
+    fn options(&mut self) -> Result<ParserOptions, CacheError> {
+        let flags = self.byte()?;
+        let mut options = ParserOptions::from_raw_checked(flags).ok_or(CacheError::InvalidData)?;
+
+        let attribute_count = self.varint()?;
+        if attribute_count > MAX_TRACKED_ATTRIBUTES {
+            return Err(CacheError::InvalidData);
+        }
+        for _ in 0..attribute_count {
+            options = options.track_attribute(static_name(&self.name_string()?)?);
+        }
+
+        let void_count = self.varint()?;
+        let mut void_elements = Vec::with_capacity(void_count.min(self.data.len()));
+        for _ in 0..void_count {
+            void_elements.push(static_name(&self.name_string()?)?);
+        }
+        if !void_elements.is_empty() {
+            options = options.extra_void_elements(static_list(void_elements)?);
+        }
+
+        options = options.with_capacity(self.varint()?, 0);
+        options = options.with_duplicate_attributes(match self.byte()? {
+            0 => DuplicateAttributes::KeepLast,
+            1 => DuplicateAttributes::KeepFirst,
+            _ => return Err(CacheError::InvalidData),
+        });
+
+        match self.byte()? {
+            0 => {}
+            1 => {
+                let secs = self.varint()? as u64;
+                let nanos = self.u32_varint()? as u32;
+                if nanos >= 1_000_000_000 {
+                    return Err(CacheError::InvalidData);
+                }
+                options = options.time_budget(Duration::new(secs, nanos));
+            }
+            _ => return Err(CacheError::InvalidData),
+        }
+
+        match self.byte()? {
+            0 => {}
+            1 => options = options.strict(),
+            _ => return Err(CacheError::InvalidData),
+        }
+
+        Ok(options)
+    }
+
+    /// Reads a name of the options, which is always stored inline
+    fn name_string(&mut self) -> Result<String, CacheError> {
+        let bytes = self.bytes(&[])?;
+        bytes
+            .try_as_utf8_str()
+            .map(str::to_owned)
+            .ok_or(CacheError::InvalidData)
+    }
+
+    fn byte(&mut self) -> Result<u8, CacheError> {
+        let byte = *self.data.get(self.idx).ok_or(CacheError::UnexpectedEof)?;
+        self.idx += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'d [u8], CacheError> {
+        let end = self.idx.checked_add(len).ok_or(CacheError::InvalidData)?;
+        let slice = self
+            .data
+            .get(self.idx..end)
+            .ok_or(CacheError::UnexpectedEof)?;
+        self.idx = end;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<usize, CacheError> {
+        let mut value = 0usize;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+
+            if shift >= usize::BITS {
+                return Err(CacheError::InvalidData);
+            }
+
+            value |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn u32_varint(&mut self) -> Result<usize, CacheError> {
+        let value = self.varint()?;
+        if value > u32::MAX as usize {
+            return Err(CacheError::InvalidData);
+        }
+        Ok(value)
+    }
+
+    fn bytes<'s>(&mut self, source: &'s [u8]) -> Result<Bytes<'s>, CacheError> {
+        let tag = self.varint()?;
+
+        if tag & 1 == 0 {
+            let offset = tag >> 1;
+            let len = self.varint()?;
+            let end = offset.checked_add(len).ok_or(CacheError::InvalidData)?;
+            source
+                .get(offset..end)
+                .map(Bytes::from)
+                .ok_or(CacheError::InvalidData)
+        } else {
+            let data = self.take(tag >> 1)?;
+            let mut bytes = Bytes::new();
+            bytes.set(data).map_err(|_| CacheError::InvalidData)?;
+            Ok(bytes)
+        }
+    }
+
+    fn span(&mut self, source: &[u8], start: usize) -> Result<Span, CacheError> {
+        let len = self.varint()?;
+        let end = start.checked_add(len).ok_or(CacheError::InvalidData)?;
+
+        if end > source.len() {
+            return Err(CacheError::InvalidData);
+        }
+
+        Ok(Span::new(start, end))
+    }
+
+    fn handle(&mut self, node_count: usize) -> Result<NodeHandle, CacheError> {
+        let handle = self.u32_varint()?;
+        if handle >= node_count {
+            return Err(CacheError::InvalidData);
+        }
+        Ok(NodeHandle::in_document(handle as u32, self.document))
+    }
+
+    fn handles(&mut self, node_count: usize) -> Result<Vec<NodeHandle>, CacheError> {
+        let len = self.varint()?;
+        let mut handles = Vec::with_capacity(len.min(node_count));
+        for _ in 0..len {
+            handles.push(self.handle(node_count)?);
+        }
+        Ok(handles)
+    }
+
+    fn name<'s>(&mut self, names: &[Bytes<'s>]) -> Result<Bytes<'s>, CacheError> {
+        let index = self.varint()?;
+        names.get(index).cloned().ok_or(CacheError::InvalidData)
+    }
+
+    fn node<'s>(
+        &mut self,
+        source: &'s [u8],
+        names: &[Bytes<'s>],
+        node_count: usize,
+    ) -> Result<Node<'s>, CacheError> {
+        match self.byte()? {
+            NODE_RAW => Ok(Node::Raw(self.bytes(source)?)),
+            NODE_COMMENT => Ok(Node::Comment(self.bytes(source)?)),
+            NODE_PROCESSING_INSTRUCTION => Ok(Node::ProcessingInstruction(self.bytes(source)?)),
+            NODE_CDATA => Ok(Node::CData(self.bytes(source)?)),
+            NODE_DOCTYPE => {
+                // doctypes are never modified, so they always point into the source
+                let raw = self.bytes(source)?;
+                let raw = raw.as_bytes_borrowed().ok_or(CacheError::InvalidData)?;
+                Ok(Node::Doctype(Doctype::parse(raw)))
+            }
+            NODE_TAG => {
+                let name = self.name(names)?;
+                let parent = match self.varint()? {
+                    0 => None,
+                    p if p <= node_count => {
+                        Some(NodeHandle::in_document((p - 1) as u32, self.document))
+                    }
+                    _ => return Err(CacheError::InvalidData),
+                };
+
+                let start = self.varint()?;
+                let start_tag = self.span(source, start)?;
+                let end_tag = match self.varint()? {
+                    0 => None,
+                    start => Some(self.span(source, start - 1)?),
+                };
+
+                let raw = self.bytes(source)?;
+
+                let mut attributes = Attributes::new();
+                let flags = self.byte()?;
+                if flags & HAS_ID != 0 {
+                    attributes.id = Some(self.bytes(source)?);
+                }
+                if flags & HAS_CLASS != 0 {
+                    attributes.class = Some(self.bytes(source)?);
+                }
+
+                let len = self.varint()?;
+                let mut raw_attributes = InlineHashMap::new();
+                for _ in 0..len {
+                    let key = self.name(names)?;
+                    let value = match self.byte()? {
+                        0 => None,
+                        _ => Some(self.bytes(source)?),
+                    };
+                    raw_attributes.insert(key, value);
+                }
+                attributes.raw = raw_attributes;
+
+                let mut children = InlineVec::new();
+                for handle in self.handles(node_count)? {
+                    children.push(handle);
+                }
+
+                let mut tag = HTMLTag::new(
+                    ElementName::new(name),
+                    attributes,
+                    children,
+                    parent,
+                    raw,
+                    start_tag,
+                );
+                tag._span = TagSpan { start_tag, end_tag };
+                tag._modified = flags & MODIFIED != 0;
+                tag._void = flags & VOID != 0;
+
+                Ok(Node::Tag(tag))
+            }
+            _ => Err(CacheError::InvalidData),
+        }
+    }
+}
+
+/// The maximum number of bytes that loading the names of the options may allocate
+///
+/// The names are kept for the rest of the program, so without a limit, loading many caches with distinct names would leak memory.
+const MAX_STATIC_BYTES: usize = 64 * 1024;
+
+/// The names and lists of names of the options that were loaded so far
+struct StaticNames {
+    names: Vec<&'static str>,
+    lists: Vec<&'static [&'static str]>,
+    /// The number of bytes that were allocated for the names and lists
+    bytes: usize,
+}
+
+static STATIC_NAMES: Mutex<StaticNames> = Mutex::new(StaticNames {
+    names: Vec::new(),
+    lists: Vec::new(),
+    bytes: 0,
+});
+
+/// Reserves space for a new name or list, or fails if the limit of [`MAX_STATIC_BYTES`] is reached
+fn reserve_static(names: &mut StaticNames, bytes: usize) -> Result<(), CacheError> {
+    match names.bytes.checked_add(bytes) {
+        Some(total) if total <= MAX_STATIC_BYTES => {
+            names.bytes = total;
+            Ok(())
+        }
+        _ => Err(CacheError::InvalidData),
+    }
+}
+
+/// Returns a `'static` copy of a name of the options, which is only allocated the first time the name is loaded
+///
+/// Known element and attribute names are not allocated at all.
+fn static_name(name: &str) -> Result<&'static str, CacheError> {
+    let known = TagName::new(name)
+        .map(|symbol| symbol.as_bytes())
+        .or_else(|| known_attribute(name.as_bytes())?.as_bytes_borrowed())
+        .filter(|known| *known == name.as_bytes())
+        .and_then(|known| std::str::from_utf8(known).ok());
+    if let Some(known) = known {
+        return Ok(known);
+    }
+
+    let mut names = STATIC_NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(known) = names.names.iter().find(|known| **known == name) {
+        return Ok(known);
+    }
+
+    reserve_static(&mut names, name.len())?;
+    let name: &'static str = Box::leak(name.into());
+    names.names.push(name);
+    Ok(name)
+}
+
+/// Returns a `'static` copy of a list of names of the options, which is only allocated the first time the list is loaded
+fn static_list(list: Vec<&'static str>) -> Result<&'static [&'static str], CacheError> {
+    let mut names = STATIC_NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(known) = names.lists.iter().find(|known| **known == list) {
+        return Ok(known);
+    }
+
+    reserve_static(&mut names, std::mem::size_of_val(list.as_slice()))?;
+    let list: &'static [&'static str] = Box::leak(list.into_boxed_slice());
+    names.lists.push(list);
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag<'a>(dom: &'a VDom<'_>, index: usize) -> &'a HTMLTag<'a> {
+        dom.nodes()[index].as_tag().unwrap()
+    }
+
+    /// Returns a DOM with a doctype, an owned attribute value and a text node at the top level, and its cache
+    fn modified_dom() -> (VDom<'static>, Vec<u8>) {
+        let input = r#"<!DOCTYPE html><div id="a" class="x y"><p data-v=1>Hello<br></p><!-- c --></div>text"#;
+        let mut dom =
+            crate::parse(input, ParserOptions::default().track_ids().track_classes()).unwrap();
+
+        let p = dom.nodes_mut()[2].as_tag_mut().unwrap();
+        p.attributes_mut().insert("title", Some("owned"));
+
+        let cache = serialize(&dom);
+        (dom, cache)
+    }
+
+    /// Serializes and loads a DOM, and checks that loading fails because its tree is malformed
+    fn assert_invalid_tree(dom: &VDom<'_>) {
+        assert_eq!(load(&serialize(dom)).unwrap_err(), CacheError::InvalidData);
+    }
+
+    #[test]
+    fn round_trip_markup() {
+        let (dom, cache) = modified_dom();
+        let loaded = load(&cache).unwrap();
+
+        assert_eq!(loaded.outer_html(), dom.outer_html());
+        assert_eq!(loaded.version(), Some(HTMLVersion::HTML5));
+        assert_eq!(loaded.doctype().unwrap().raw(), "<!DOCTYPE html>");
+        assert_eq!(loaded.nodes().len(), dom.nodes().len());
+        assert_eq!(
+            tag(&loaded, 2)
+                .attributes()
+                .get("title")
+                .flatten()
+                .unwrap()
+                .as_utf8_str(),
+            "owned"
+        );
+    }
+
+    #[test]
+    fn round_trip_lookup_tables() {
+        let (_, cache) = modified_dom();
+        let loaded = load(&cache).unwrap();
+        let parser = loaded.parser();
+
+        assert_eq!(loaded.get_element_by_id("a"), Some(parser.handle(1)));
+        assert_eq!(loaded.get_elements_by_class_name("y").count(), 1);
+        assert_eq!(
+            loaded.query_selector("p[data-v]").unwrap().next(),
+            Some(parser.handle(2))
+        );
+    }
+
+    #[test]
+    fn round_trip_positions() {
+        let (dom, cache) = modified_dom();
+        let loaded = load(&cache).unwrap();
+        let parser = loaded.parser();
+
+        assert_eq!(tag(&loaded, 2).span(), tag(&dom, 2).span());
+        assert_eq!(
+            loaded.nodes()[5].byte_span(parser),
+            dom.nodes()[5].byte_span(dom.parser())
+        );
+        assert_eq!(
+            NodeHandle::new(4).prev_sibling(parser),
+            Some(parser.handle(3))
+        );
+    }
+
+    #[test]
+    fn load_owned_copies_source() {
+        let (dom, cache) = modified_dom();
+        let owned = load_owned(&cache).unwrap();
+
+        drop(cache);
+        assert_eq!(owned.get_ref().outer_html(), dom.outer_html());
+    }
+
+    #[test]
+    fn invalid_header() {
+        assert_eq!(load(b"nope").unwrap_err(), CacheError::InvalidHeader);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let (_, cache) = modified_dom();
+
+        for len in 0..cache.len() {
+            assert!(load(&cache[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn corrupted_data() {
+        let (_, mut cache) = modified_dom();
+        let last = cache.len() - 1;
+        cache[last] = 0xff;
+
+        assert!(load(&cache).is_err());
+    }
+
+    #[test]
+    fn options_are_stored() {
+        let options = ParserOptions::default()
+            .track_ids()
+            .track_tags()
+            .track_attribute("data-testid")
+            .extra_void_elements(&["icon"])
+            .with_duplicate_attributes(DuplicateAttributes::KeepFirst)
+            .with_capacity(16, 0)
+            .time_budget(Duration::from_millis(1500))
+            .strict();
+        let dom = crate::parse("<p></p>", options).unwrap();
+
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+
+        assert_eq!(loaded.parser().options, options);
+    }
+
+    #[test]
+    fn tables_are_rebuilt() {
+        let options = ParserOptions::default()
+            .track_ids()
+            .track_tags()
+            .track_attribute("data-testid")
+            .extra_void_elements(&["icon"]);
+        let mut dom = crate::parse(
+            r#"<div id="a"><icon><button data-testid="buy">Buy</button></div>"#,
+            options,
+        )
+        .unwrap();
+
+        let button = dom.nodes_mut()[2].as_tag_mut().unwrap();
+        button.attributes_mut().insert("id", Some("b"));
+
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+        let parser = loaded.parser();
+        let indices = |handles: Vec<NodeHandle>| {
+            handles
+                .iter()
+                .map(NodeHandle::get_inner)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(!parser.tables_outdated);
+        assert_eq!(parser.attribute_values.len(), 1);
+        let buttons = loaded.get_elements_by_attribute("data-testid", "buy");
+        assert_eq!(indices(buttons.collect()), [2]);
+        assert_eq!(loaded.get_element_by_id("b"), Some(parser.handle(2)));
+        let icons = loaded.get_elements_by_tag_name("icon");
+        assert_eq!(indices(icons.collect()), [1]);
+    }
+
+    #[test]
+    fn child_of_two_elements() {
+        let mut dom = crate::parse("<p><b></b></p><i></i>", ParserOptions::default()).unwrap();
+        let b = dom.parser().handle(1);
+        let i = dom.nodes_mut()[2].as_tag_mut().unwrap();
+        i.children_mut().top_mut().push(b);
+
+        assert_invalid_tree(&dom);
+    }
+
+    #[test]
+    fn child_before_parent() {
+        let mut dom = crate::parse("<p><b></b><i></i></p>", ParserOptions::default()).unwrap();
+        let b = dom.parser().handle(1);
+        let p = dom.nodes_mut()[0].as_tag_mut().unwrap();
+        p.children_mut().top_mut().remove(0);
+        let i = dom.nodes_mut()[2].as_tag_mut().unwrap();
+        i.children_mut().top_mut().push(b);
+
+        assert_invalid_tree(&dom);
+    }
+
+    #[test]
+    fn top_level_child() {
+        let mut dom = crate::parse("<p></p><i></i>", ParserOptions::default()).unwrap();
+        let p = dom.parser().handle(0);
+        let i = dom.nodes_mut()[1].as_tag_mut().unwrap();
+        i.children_mut().top_mut().push(p);
+
+        assert_invalid_tree(&dom);
+    }
+
+    #[test]
+    fn removed_nodes_are_loaded() {
+        let mut dom = crate::parse("<p><b></b></p>", ParserOptions::default()).unwrap();
+        let p = dom.nodes_mut()[0].as_tag_mut().unwrap();
+        p.children_mut().top_mut().remove(0);
+
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+
+        assert_eq!(loaded.nodes().len(), 2);
+        assert!(loaded.nodes()[0].children().unwrap().top().is_empty());
+    }
+
+    #[test]
+    fn ranges_of_unmodified_dom() {
+        let dom = crate::parse(
+            "<div><p>a<b>b</b></p><ul><li>c</li><li></li></ul></div><br><i>d</i>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+
+        for (node, loaded) in dom.nodes().iter().zip(loaded.nodes()) {
+            if let (Node::Tag(tag), Node::Tag(loaded)) = (node, loaded) {
+                assert_eq!(loaded._descendants, tag._descendants);
+            }
+        }
+    }
+
+    #[test]
+    fn moved_node_does_not_extend_ranges() {
+        let mut dom = crate::parse("<p></p><i></i><b>x</b>", ParserOptions::default()).unwrap();
+        let x = dom.parser().handle(3);
+        dom.nodes_mut()[2]
+            .as_tag_mut()
+            .unwrap()
+            .children_mut()
+            .top_mut()
+            .remove(0);
+        dom.nodes_mut()[0]
+            .as_tag_mut()
+            .unwrap()
+            .children_mut()
+            .top_mut()
+            .push(x);
+
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+        let parser = loaded.parser();
+
+        assert_eq!(loaded.outer_html(), "<p>x</p><i></i><b></b>");
+        assert!(!tag(&loaded, 0).contains(parser.handle(1)));
+        assert!(!tag(&loaded, 0).contains(parser.handle(2)));
+        assert!(!tag(&loaded, 2).contains(parser.handle(3)));
+    }
+
+    #[test]
+    fn removed_nodes_between_children_stay_in_range() {
+        let mut dom =
+            crate::parse("<ul><li>0</li>x<li>1</li></ul>", ParserOptions::default()).unwrap();
+        dom.nodes_mut()[0]
+            .as_tag_mut()
+            .unwrap()
+            .children_mut()
+            .top_mut()
+            .remove(1);
+
+        let cache = serialize(&dom);
+        let loaded = load(&cache).unwrap();
+        let parser = loaded.parser();
+
+        assert_eq!(tag(&loaded, 0)._descendants, 1..6);
+        assert!(tag(&loaded, 0).contains(parser.handle(4)));
+        assert_eq!(loaded.query_selector("ul li").unwrap().count(), 2);
+    }
+
+    #[test]
+    fn known_names_are_not_copied() {
+        let href = known_attribute(b"href").unwrap();
+
+        assert_eq!(
+            static_name("href").unwrap().as_ptr(),
+            href.as_bytes_borrowed().unwrap().as_ptr()
+        );
+        assert_eq!(
+            static_name("br").unwrap().as_ptr(),
+            TagName::new("br").unwrap().as_bytes().as_ptr()
+        );
+    }
+
+    #[test]
+    fn names_are_copied_once() {
+        let name = static_name("data-cache-test").unwrap();
+
+        assert_eq!(name, "data-cache-test");
+        assert_eq!(
+            static_name("data-cache-test").unwrap().as_ptr(),
+            name.as_ptr()
+        );
+    }
+
+    #[test]
+    fn static_names_are_limited() {
+        let mut names = StaticNames {
+            names: Vec::new(),
+            lists: Vec::new(),
+            bytes: 0,
+        };
+
+        assert_eq!(reserve_static(&mut names, MAX_STATIC_BYTES - 1), Ok(()));
+        assert_eq!(reserve_static(&mut names, 1), Ok(()));
+        assert_eq!(reserve_static(&mut names, 1), Err(CacheError::InvalidData));
+        assert_eq!(
+            reserve_static(&mut names, usize::MAX),
+            Err(CacheError::InvalidData)
+        );
+        assert_eq!(names.bytes, MAX_STATIC_BYTES);
+    }
+}
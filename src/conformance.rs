@@ -0,0 +1,211 @@
+//! This module reads the tree construction fixtures of [html5lib-tests](https://github.com/html5lib/html5lib-tests)
+//! (the `.dat` files in `tree-construction/`) and compares them to the DOM that tl builds.
+//!
+//! tl is not a spec-compliant parser: it does not insert implied `<html>`, `<head>` and `<body>` elements,
+//! does not decode character references in text and does not apply the adoption agency algorithm,
+//! so most fixtures are expected to fail. The harness is meant for tracking progress on spec compliance
+//! and for catching regressions, not as a pass/fail gate.
+//!
+//! The tokenizer fixtures (`tokenizer/*.test`) are not supported, because tl does not expose a token stream.
+//!
+//! The harness is not part of the public API. It is only compiled for the tests of this crate
+//! and with the internal `__INTERNALS_DO_NOT_USE` feature.
+
+use crate::{Bytes, Node, NodeHandle, Parser, VDom};
+
+/// Section headers of the tree construction format that end the previous section
+const SECTIONS: &[&str] = &[
+    "#data",
+    "#errors",
+    "#new-errors",
+    "#document-fragment",
+    "#script-on",
+    "#script-off",
+    "#document",
+];
+
+/// A single test case of a tree construction fixture file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeTest<'s> {
+    /// The input HTML
+    pub data: &'s str,
+    /// The expected parse errors, one per line
+    pub errors: Vec<&'s str>,
+    /// The context element if the input is parsed as a fragment, i.e. `td` or `svg path`
+    pub document_fragment: Option<&'s str>,
+    /// Whether scripting is enabled (`Some(true)`), disabled (`Some(false)`) or the test applies to both (`None`)
+    pub scripting: Option<bool>,
+    /// The expected tree in the html5lib format, as produced by [`dump`]
+    pub document: String,
+}
+
+impl TreeTest<'_> {
+    /// Parses the input with tl and compares the resulting tree to the expected one
+    ///
+    /// Parse errors are not compared, since tl does not report them.
+    ///
+    /// # Errors
+    /// Returns the actual tree in the html5lib format if it differs from the expected one.
+    pub fn run(&self) -> Result<(), String> {
+        let actual = match crate::parse(self.data, Default::default()) {
+            Ok(dom) => dump(&dom),
+            Err(err) => format!("{err:?}"),
+        };
+
+        if actual == self.document {
+            Ok(())
+        } else {
+            Err(actual)
+        }
+    }
+}
+
+/// Parses a tree construction fixture file into its test cases
+///
+/// Sections of a test case are introduced by lines like `#data` or `#document`.
+/// Test cases without a `#data` section are skipped.
+pub fn parse_tree_tests(input: &str) -> Vec<TreeTest<'_>> {
+    let mut tests = Vec::new();
+    let mut sections: Vec<(&str, &str)> = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+    let mut offset = 0;
+
+    for line in input.split_inclusive('\n') {
+        let header = line.trim_end_matches(['\n', '\r']);
+
+        if SECTIONS.contains(&header) {
+            finish_section(&mut sections, input, current, offset);
+
+            if header == "#data" && !sections.is_empty() {
+                tests.extend(build_test(&sections));
+                sections.clear();
+            }
+
+            current = Some((header, offset + line.len()));
+        }
+
+        offset += line.len();
+    }
+
+    finish_section(&mut sections, input, current, input.len());
+    tests.extend(build_test(&sections));
+
+    tests
+}
+
+/// Adds the section that started at the given offset and ends at `end`
+fn finish_section<'s>(
+    sections: &mut Vec<(&'s str, &'s str)>,
+    input: &'s str,
+    current: Option<(&'s str, usize)>,
+    end: usize,
+) {
+    if let Some((name, start)) = current {
+        // the newline before the next header is not part of the section
+        let content = &input[start..end];
+        sections.push((name, content.strip_suffix('\n').unwrap_or(content)));
+    }
+}
+
+fn build_test<'s>(sections: &[(&str, &'s str)]) -> Option<TreeTest<'s>> {
+    let get = |name: &str| {
+        sections
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, content)| *content)
+    };
+
+    let scripting = if get("#script-on").is_some() {
+        Some(true)
+    } else if get("#script-off").is_some() {
+        Some(false)
+    } else {
+        None
+    };
+
+    Some(TreeTest {
+        data: get("#data")?,
+        errors: get("#errors")
+            .map(|errors| errors.lines().filter(|l| !l.is_empty()).collect())
+            .unwrap_or_default(),
+        document_fragment: get("#document-fragment").map(str::trim),
+        scripting,
+        // tests are separated by a blank line, which is not part of the document
+        document: get("#document").unwrap_or_default().trim_end().to_owned(),
+    })
+}
+
+/// Serializes a DOM in the tree format of html5lib-tests
+///
+/// Every node is written on its own line, starting with `| ` and indented by two spaces per level.
+/// Elements are written as `<name>`, followed by their attributes in alphabetical order, text as `"text"`
+/// and comments as `<!-- comment -->`. A doctype is written as `<!DOCTYPE html>`, followed by its quoted public and
+/// system identifiers if it has any.
+pub fn dump(dom: &VDom<'_>) -> String {
+    let mut lines = Vec::new();
+
+    for &handle in dom.children() {
+        dump_node(&mut lines, handle, dom.parser(), 0);
+    }
+
+    lines.join("\n")
+}
+
+fn dump_node(lines: &mut Vec<String>, handle: NodeHandle, parser: &Parser<'_>, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    match handle.get(parser) {
+        Some(Node::Tag(tag)) => {
+            let name = tag.name().as_utf8_str().to_ascii_lowercase();
+            lines.push(format!("| {indent}<{name}>"));
+
+            let mut attributes = tag
+                .attributes()
+                .iter()
+                .map(|(key, value)| (key.to_ascii_lowercase(), value.unwrap_or_default()))
+                .collect::<Vec<_>>();
+            attributes.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (key, value) in attributes {
+                lines.push(format!("| {indent}  {key}=\"{value}\""));
+            }
+
+            for &child in tag.children().top().iter() {
+                dump_node(lines, child, parser, depth + 1);
+            }
+        }
+        Some(Node::Raw(text)) => lines.push(format!("| {indent}\"{}\"", text.as_utf8_str())),
+        Some(Node::Comment(comment)) => {
+            let comment = comment.as_utf8_str();
+            let content = comment
+                .strip_prefix("<!--")
+                .and_then(|c| c.strip_suffix("-->"))
+                .unwrap_or(&comment);
+            lines.push(format!("| {indent}<!-- {content} -->"));
+        }
+        Some(Node::ProcessingInstruction(instruction)) => {
+            lines.push(format!("| {indent}{}", instruction.as_utf8_str()));
+        }
+        Some(node @ Node::CData(_)) => {
+            lines.push(format!("| {indent}\"{}\"", node.inner_text(parser)));
+        }
+        Some(Node::Doctype(doctype)) => {
+            let name = doctype
+                .name()
+                .map(|name| name.as_utf8_str().to_ascii_lowercase())
+                .unwrap_or_default();
+
+            match (doctype.public_id(), doctype.system_id()) {
+                (None, None) => lines.push(format!("| {indent}<!DOCTYPE {name}>")),
+                (public_id, system_id) => {
+                    let public_id = public_id.map(Bytes::as_utf8_str).unwrap_or_default();
+                    let system_id = system_id.map(Bytes::as_utf8_str).unwrap_or_default();
+                    lines.push(format!(
+                        "| {indent}<!DOCTYPE {name} \"{public_id}\" \"{system_id}\">"
+                    ));
+                }
+            }
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,313 @@
+use crate::microsyntax::{find_top_level, trim_css, StyleDeclarations};
+use crate::queryselector::{Selector, Specificity};
+use crate::{Bytes, VDom};
+
+/// Inlines the declarations of CSS rules into the `style` attributes of the elements they match, as needed for HTML emails
+///
+/// Rules are matched with the query selector engine, so rules with selectors it does not support,
+/// like `a:hover` or `p::first-line`, are not inlined and can be found with [`CssInliner::unsupported`].
+/// At-rules like `@media` or `@font-face` are ignored, so they should stay in a `<style>` element.
+///
+/// Declarations are applied in the order of the cascade: `!important` declarations win over normal ones,
+/// declarations in the `style` attribute win over declarations of rules with the same importance,
+/// and rules with a higher [`Specificity`] win over rules that come earlier in the stylesheet.
+///
+/// # Example
+/// ```
+/// use tl::css_inline::CssInliner;
+///
+/// let mut dom = tl::parse(
+///     r#"<p style="color: green">Hi <a href="/">there</a></p>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let inliner = CssInliner::new("p { color: red; margin: 0 } p a { font-weight: bold } a:hover { color: blue }");
+/// assert_eq!(inliner.unsupported(), ["a:hover"]);
+///
+/// assert_eq!(inliner.inline(&mut dom), 2);
+/// assert_eq!(
+///     dom.outer_html(),
+///     r#"<p style="margin: 0; color: green">Hi <a href="/" style="font-weight: bold">there</a></p>"#
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CssInliner<'c> {
+    rules: Vec<Rule<'c>>,
+    unsupported: Vec<&'c str>,
+}
+
+/// A single selector of a style rule, along with the declarations of the rule
+#[derive(Debug, Clone)]
+struct Rule<'c> {
+    selector: Selector<'c>,
+    specificity: Specificity,
+    declarations: Vec<Declaration<'c>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Declaration<'c> {
+    property: &'c str,
+    /// The value without `!important`
+    value: &'c str,
+    important: bool,
+    /// The position of the declaration in the stylesheet
+    order: usize,
+}
+
+impl<'c> CssInliner<'c> {
+    /// Parses the style rules of a stylesheet
+    pub fn new(css: &'c str) -> Self {
+        let mut inliner = Self {
+            rules: Vec::new(),
+            unsupported: Vec::new(),
+        };
+        let mut order = 0;
+        let mut rest = css;
+
+        loop {
+            rest = trim_css(rest);
+            if rest.is_empty() {
+                break;
+            }
+
+            if rest.starts_with('@') {
+                rest = skip_at_rule(rest);
+                continue;
+            }
+
+            let Ok(open) = find_top_level(rest, b'{') else {
+                break;
+            };
+            let prelude = &rest[..open];
+            let (body, next) = match find_top_level(&rest[open + 1..], b'}') {
+                Ok(close) => (&rest[open + 1..open + 1 + close], &rest[open + 2 + close..]),
+                Err(_) => (&rest[open + 1..], ""),
+            };
+            rest = next;
+
+            let declarations = StyleDeclarations::new(body)
+                .map(|(property, value)| {
+                    let (value, important) = split_important(value);
+                    order += 1;
+                    Declaration {
+                        property,
+                        value,
+                        important,
+                        order,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            for selector in split_selector_list(prelude) {
+                match crate::parse_query_selector(selector).filter(is_supported) {
+                    Some(selector) => inliner.rules.push(Rule {
+                        specificity: selector.specificity(),
+                        selector,
+                        declarations: declarations.clone(),
+                    }),
+                    None => inliner.unsupported.push(selector),
+                }
+            }
+        }
+
+        inliner
+    }
+
+    /// Returns the selectors of the stylesheet that the query selector engine does not support, as written
+    ///
+    /// The declarations of these selectors are not inlined.
+    pub fn unsupported(&self) -> &[&'c str] {
+        &self.unsupported
+    }
+
+    /// Inlines the declarations of all matching rules into the elements of a DOM, and returns the number of changed elements
+    ///
+    /// Existing `style` attributes are merged with the declarations of the rules.
+    /// Elements that no rule matches are left untouched.
+    pub fn inline(&self, dom: &mut VDom<'_>) -> usize {
+        let styles = self.compute(dom);
+        let count = styles.len();
+
+        let nodes = dom.nodes_mut();
+        for (index, style) in styles {
+            if let Some(tag) = nodes[index].as_tag_mut() {
+                let style: Bytes<'static> = Bytes::from(style.as_str()).to_static();
+                tag.attributes_mut().insert("style", Some(style));
+            }
+        }
+
+        count
+    }
+
+    /// Computes the new `style` attributes of all elements that a rule matches
+    fn compute(&self, dom: &VDom<'_>) -> Vec<(usize, String)> {
+        let parser = dom.parser();
+        let mut matched: Vec<Vec<(bool, bool, Specificity, Declaration<'c>)>> =
+            vec![Vec::new(); dom.nodes().len()];
+
+        for rule in &self.rules {
+            for (index, node) in dom.nodes().iter().enumerate() {
+                if node.as_tag().is_some() && rule.selector.matches(node, parser) {
+                    matched[index].extend(rule.declarations.iter().map(|declaration| {
+                        (declaration.important, false, rule.specificity, *declaration)
+                    }));
+                }
+            }
+        }
+
+        matched
+            .into_iter()
+            .enumerate()
+            .filter(|(_, declarations)| !declarations.is_empty())
+            .filter_map(|(index, mut declarations)| {
+                let tag = dom.nodes()[index].as_tag()?;
+
+                for (order, (property, value)) in tag.attributes().style().enumerate() {
+                    let (value, important) = split_important(value);
+                    // inline declarations win over rules of the same importance, regardless of their specificity
+                    declarations.push((
+                        important,
+                        true,
+                        Specificity::default(),
+                        Declaration {
+                            property,
+                            value,
+                            important,
+                            order,
+                        },
+                    ));
+                }
+
+                declarations.sort_by_key(|&(important, inline, specificity, declaration)| {
+                    (important, inline, specificity, declaration.order)
+                });
+
+                // the winning declarations in the order of the cascade, so that a shorthand property
+                // is written after the longhand properties that it overrides
+                let mut winners: Vec<Declaration<'_>> = Vec::new();
+                for (.., declaration) in declarations {
+                    winners.retain(|winner| {
+                        !winner.property.eq_ignore_ascii_case(declaration.property)
+                    });
+                    winners.push(declaration);
+                }
+
+                let style = winners
+                    .iter()
+                    .map(|declaration| {
+                        let important = if declaration.important {
+                            " !important"
+                        } else {
+                            ""
+                        };
+                        format!(
+                            "{}: {}{}",
+                            declaration.property, declaration.value, important
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; ");
+
+                Some((index, style))
+            })
+            .collect()
+    }
+}
+
+/// Checks whether a selector only consists of selectors that the query selector engine supports
+///
+/// The query selector parser reads unknown pseudo-classes and pseudo-elements like `:hover` as part of
+/// the preceding identifier, which cannot contain a colon unless it is escaped.
+fn is_supported(selector: &Selector<'_>) -> bool {
+    match selector {
+        Selector::Tag(name) | Selector::Id(name) | Selector::Class(name) => {
+            let mut bytes = name.iter();
+            while let Some(&byte) = bytes.next() {
+                match byte {
+                    b'\\' => {
+                        bytes.next();
+                    }
+                    b':' => return false,
+                    _ => {}
+                }
+            }
+            true
+        }
+        Selector::And(a, b)
+        | Selector::Or(a, b)
+        | Selector::Descendant(a, b)
+        | Selector::Parent(a, b) => is_supported(a) && is_supported(b),
+        Selector::NamespacedAttribute(_, selector) => is_supported(selector),
+        _ => true,
+    }
+}
+
+/// Skips an at-rule, which either ends with a semicolon like `@import` or has a block like `@media`
+fn skip_at_rule(input: &str) -> &str {
+    let semicolon = find_top_level(input, b';').unwrap_or_else(|end| end);
+    let open = match find_top_level(input, b'{') {
+        Ok(open) if open < semicolon => open,
+        _ => return input.get(semicolon + 1..).unwrap_or_default(),
+    };
+
+    let mut depth = 1;
+    let mut position = open + 1;
+    while depth > 0 {
+        let rest = &input[position..];
+        let Ok(close) = find_top_level(rest, b'}') else {
+            return "";
+        };
+
+        match find_top_level(rest, b'{') {
+            Ok(open) if open < close => {
+                depth += 1;
+                position += open + 1;
+            }
+            _ => {
+                depth -= 1;
+                position += close + 1;
+            }
+        }
+    }
+
+    &input[position..]
+}
+
+/// Splits a selector list at its top level commas, so that each selector keeps its own specificity
+fn split_selector_list(mut list: &str) -> impl Iterator<Item = &str> {
+    std::iter::from_fn(move || loop {
+        if list.is_empty() {
+            return None;
+        }
+
+        let (selector, rest) = match find_top_level(list, b',') {
+            Ok(end) => (&list[..end], &list[end + 1..]),
+            Err(_) => (list, ""),
+        };
+        list = rest;
+
+        let selector = trim_css(selector);
+        if !selector.is_empty() {
+            return Some(selector);
+        }
+    })
+}
+
+/// Removes a trailing `!important` from a value and returns whether there was one
+fn split_important(value: &str) -> (&str, bool) {
+    let trimmed = value.trim_end();
+    let Some(start) = trimmed.len().checked_sub("important".len()) else {
+        return (value, false);
+    };
+
+    match trimmed.get(start..) {
+        Some(keyword) if keyword.eq_ignore_ascii_case("important") => {
+            match trimmed[..start].trim_end().strip_suffix('!') {
+                Some(value) => (value.trim_end(), true),
+                None => (value, false),
+            }
+        }
+        _ => (value, false),
+    }
+}
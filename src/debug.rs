@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::{Edge, Node, NodeHandle, Parser, Traverse};
+
+/// An indented tree of nodes, as returned by [`VDom::debug_tree`](crate::VDom::debug_tree)
+///
+/// Every node is written on its own line and indented by two spaces per level.
+/// Elements are written like a selector that matches them, i.e. `div#main.container`, which makes it easy to see
+/// why a selector does not match. Text is written with collapsed whitespace and truncated to a maximum length,
+/// and text nodes that only contain whitespace are left out. Comments and other nodes are written as markup.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(
+///     r#"<div id="main" class="container"><p class="lead">Hello,   world!</p><!-- footer --></div>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     dom.debug_tree().max_text_len(16).to_string(),
+///     "div#main.container\n  p.lead\n    \"Hello, world!\"\n  <!-- footer -->\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TreeDisplay<'p, 'a> {
+    parser: &'p Parser<'a>,
+    roots: &'p [NodeHandle],
+    max_text_len: usize,
+}
+
+impl<'p, 'a> TreeDisplay<'p, 'a> {
+    /// Creates a tree of the given nodes and their subnodes
+    ///
+    /// This can be used to only write a part of a document, e.g. the children of an element.
+    pub fn new(parser: &'p Parser<'a>, roots: &'p [NodeHandle]) -> Self {
+        Self {
+            parser,
+            roots,
+            max_text_len: 40,
+        }
+    }
+
+    /// Sets the number of characters after which text, comments and other nodes are truncated. Defaults to 40.
+    pub fn max_text_len(mut self, len: usize) -> Self {
+        self.max_text_len = len;
+        self
+    }
+
+    /// Returns the line of a node without its subnodes, or `None` if the node is left out
+    fn label(&self, node: &Node<'_>) -> Option<String> {
+        match node {
+            Node::Tag(tag) => {
+                let mut label = tag.name().as_utf8_str().into_owned();
+
+                if let Some(id) = tag.attributes().id() {
+                    label.push('#');
+                    label.push_str(&id.as_utf8_str());
+                }
+                for class in tag.attributes().class_iter().into_iter().flatten() {
+                    label.push('.');
+                    label.push_str(class);
+                }
+
+                Some(label)
+            }
+            Node::Raw(_) | Node::CData(_) => {
+                let text = node.inner_text(self.parser);
+                let text = text.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+
+                (!text.is_empty()).then(|| format!("\"{}\"", truncate(&text, self.max_text_len)))
+            }
+            _ => Some(truncate(&node.outer_html(self.parser), self.max_text_len)),
+        }
+    }
+}
+
+impl fmt::Display for TreeDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut depth = 0;
+
+        for edge in Traverse::new(self.parser, self.roots) {
+            match edge {
+                Edge::Open(handle) => {
+                    if let Some(label) = handle.get(self.parser).and_then(|n| self.label(n)) {
+                        writeln!(f, "{:indent$}{label}", "", indent = depth * 2)?;
+                    }
+                    depth += 1;
+                }
+                Edge::Close(_) => depth -= 1,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shortens a string to the given number of characters, and marks it with an ellipsis if anything was cut off
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &s[..end]),
+        None => s.to_owned(),
+    }
+}
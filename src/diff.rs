@@ -0,0 +1,313 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::{Bytes, HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// The largest number of cells of the table that aligns two lists of children
+///
+/// Longer lists are only aligned by position to keep the quadratic alignment bounded.
+const MAX_ALIGNMENT_CELLS: usize = 1 << 20;
+
+/// A difference between two documents, as returned by [`diff`]
+///
+/// `old` handles refer to nodes of the old document and `new` handles to nodes of the new document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomEdit {
+    /// A node and its subtree only exist in the new document
+    Added {
+        /// The added node in the new document
+        new: NodeHandle,
+    },
+    /// A node and its subtree only exist in the old document
+    Removed {
+        /// The removed node in the old document
+        old: NodeHandle,
+    },
+    /// A node and its subtree exist unchanged in both documents, but at a different position
+    Moved {
+        /// The node in the old document
+        old: NodeHandle,
+        /// The node in the new document
+        new: NodeHandle,
+    },
+    /// An attribute of an element was added, removed or changed its value
+    AttributeChanged {
+        /// The element in the old document
+        old: NodeHandle,
+        /// The element in the new document
+        new: NodeHandle,
+        /// The name of the attribute
+        name: String,
+    },
+    /// The text of a text node changed
+    TextChanged {
+        /// The text node in the old document
+        old: NodeHandle,
+        /// The text node in the new document
+        new: NodeHandle,
+    },
+}
+
+/// Computes the differences between two documents
+///
+/// The children of every pair of matching nodes are aligned in order, where elements match if they have the same
+/// name and `id`, and text nodes match any text node. The subtrees of nodes without a counterpart are reported as
+/// [`DomEdit::Added`] or [`DomEdit::Removed`], or as [`DomEdit::Moved`] if the same subtree was removed in one place and added in another.
+///
+/// Differences in whitespace are not reported: text is compared with collapsed whitespace, and text nodes that only
+/// contain whitespace are skipped. Edits are returned in the order of the nodes in the documents.
+///
+/// # Example
+/// ```
+/// use tl::diff::DomEdit;
+///
+/// let old = tl::parse(r#"<h1>Shop</h1> <p class="price">10</p><ul><li>A</li></ul>"#, Default::default()).unwrap();
+/// let new = tl::parse(r#"<ul><li>A</li></ul><h1>Shop</h1><p class="price sale">12</p><p>New</p>"#, Default::default()).unwrap();
+///
+/// let edits = tl::diff(&old, &new);
+/// let kinds = edits
+///     .iter()
+///     .map(|edit| match edit {
+///         DomEdit::Added { .. } => "added",
+///         DomEdit::Removed { .. } => "removed",
+///         DomEdit::Moved { .. } => "moved",
+///         DomEdit::AttributeChanged { name, .. } => name.as_str(),
+///         DomEdit::TextChanged { .. } => "text",
+///     })
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(kinds, ["moved", "class", "text", "added"]);
+/// ```
+pub fn diff(old: &VDom<'_>, new: &VDom<'_>) -> Vec<DomEdit> {
+    let mut differ = Differ {
+        old: old.parser(),
+        new: new.parser(),
+        edits: Vec::new(),
+    };
+
+    differ.children(old.children(), new.children());
+    differ.find_moves();
+    differ.edits
+}
+
+struct Differ<'p, 'a, 'b> {
+    old: &'p Parser<'a>,
+    new: &'p Parser<'b>,
+    edits: Vec<DomEdit>,
+}
+
+impl Differ<'_, '_, '_> {
+    /// Aligns two lists of children and compares the nodes that match
+    fn children(&mut self, old: &[NodeHandle], new: &[NodeHandle]) {
+        let old = significant(old, self.old);
+        let new = significant(new, self.new);
+
+        let old_keys = old.iter().map(|&h| key(h, self.old)).collect::<Vec<_>>();
+        let new_keys = new.iter().map(|&h| key(h, self.new)).collect::<Vec<_>>();
+
+        let (mut i, mut j) = (0, 0);
+        for (a, b) in align(&old_keys, &new_keys) {
+            for &handle in &old[i..a] {
+                self.edits.push(DomEdit::Removed { old: handle });
+            }
+            for &handle in &new[j..b] {
+                self.edits.push(DomEdit::Added { new: handle });
+            }
+
+            self.node(old[a], new[b]);
+            (i, j) = (a + 1, b + 1);
+        }
+
+        for &handle in &old[i..] {
+            self.edits.push(DomEdit::Removed { old: handle });
+        }
+        for &handle in &new[j..] {
+            self.edits.push(DomEdit::Added { new: handle });
+        }
+    }
+
+    /// Compares two nodes that have the same key
+    fn node(&mut self, old: NodeHandle, new: NodeHandle) {
+        match (old.get(self.old), new.get(self.new)) {
+            (Some(Node::Tag(a)), Some(Node::Tag(b))) => {
+                let names = attribute_names(a, b);
+
+                for name in names {
+                    if a.attributes().get(name.as_str()) != b.attributes().get(name.as_str()) {
+                        self.edits
+                            .push(DomEdit::AttributeChanged { old, new, name });
+                    }
+                }
+
+                self.children(a.children().top().as_slice(), b.children().top().as_slice());
+            }
+            (Some(a), Some(b))
+                if collapse(&a.inner_text(self.old)) != collapse(&b.inner_text(self.new)) =>
+            {
+                self.edits.push(DomEdit::TextChanged { old, new });
+            }
+            _ => {}
+        }
+    }
+
+    /// Turns pairs of a removed and an added subtree with the same content into moves
+    fn find_moves(&mut self) {
+        let mut removed: HashMap<u64, Vec<(usize, NodeHandle)>> = HashMap::new();
+        for (index, edit) in self.edits.iter().enumerate() {
+            if let DomEdit::Removed { old } = *edit {
+                let Some(hash) = old.get(self.old).map(|n| n.structural_hash(self.old)) else {
+                    continue;
+                };
+                removed.entry(hash).or_default().push((index, old));
+            }
+        }
+
+        if removed.is_empty() {
+            return;
+        }
+
+        // the removals that became part of a move
+        let mut moved = Vec::new();
+
+        for edit in &mut self.edits {
+            if let DomEdit::Added { new } = *edit {
+                let hash = new.get(self.new).map(|n| n.structural_hash(self.new));
+                if let Some((index, old)) = hash.and_then(|h| removed.get_mut(&h)?.pop()) {
+                    *edit = DomEdit::Moved { old, new };
+                    moved.push(index);
+                }
+            }
+        }
+
+        moved.sort_unstable();
+        let mut index = 0;
+        self.edits.retain(|_| {
+            let keep = moved.binary_search(&index).is_err();
+            index += 1;
+            keep
+        });
+    }
+}
+
+/// The part of a node that must be equal for two nodes to be compared with each other instead of being added and removed
+#[derive(PartialEq, Eq)]
+enum Key<'n> {
+    Element(String, Option<Cow<'n, str>>),
+    Text,
+    Other(Cow<'n, str>),
+}
+
+fn key<'n>(handle: NodeHandle, parser: &'n Parser<'_>) -> Key<'n> {
+    match handle.get(parser) {
+        Some(Node::Tag(tag)) => Key::Element(
+            tag.name().as_utf8_str().to_ascii_lowercase(),
+            tag.attributes().id().map(Bytes::as_utf8_str),
+        ),
+        Some(Node::Raw(_) | Node::CData(_)) => Key::Text,
+        Some(node) => Key::Other(node.outer_html(parser)),
+        None => Key::Text,
+    }
+}
+
+/// Returns the children that are compared, i.e. everything except whitespace
+fn significant(handles: &[NodeHandle], parser: &Parser<'_>) -> Vec<NodeHandle> {
+    handles
+        .iter()
+        .copied()
+        .filter(|handle| {
+            !handle.get(parser).is_some_and(|node| {
+                node.as_raw()
+                    .is_some_and(|text| text.as_bytes().iter().all(u8::is_ascii_whitespace))
+            })
+        })
+        .collect()
+}
+
+/// Returns the index pairs of the longest common subsequence of two lists of keys
+fn align<K: PartialEq>(old: &[K], new: &[K]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut pairs = (0..prefix).map(|i| (i, i)).collect::<Vec<_>>();
+
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_ALIGNMENT_CELLS {
+        pairs.extend(
+            old_middle
+                .iter()
+                .zip(new_middle)
+                .enumerate()
+                .filter(|(_, (a, b))| a == b)
+                .map(|(i, _)| (prefix + i, prefix + i)),
+        );
+    } else {
+        pairs.extend(
+            lcs(old_middle, new_middle)
+                .into_iter()
+                .map(|(i, j)| (prefix + i, prefix + j)),
+        );
+    }
+
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    pairs.extend((0..suffix).map(|i| (old_end + i, new_end + i)));
+    pairs
+}
+
+fn lcs<K: PartialEq>(old: &[K], new: &[K]) -> Vec<(usize, usize)> {
+    let width = new.len() + 1;
+    // lengths[i * width + j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+/// Returns the names of the attributes of both elements, sorted and without duplicates
+fn attribute_names(a: &HTMLTag<'_>, b: &HTMLTag<'_>) -> Vec<String> {
+    let mut names = a
+        .attributes()
+        .iter()
+        .chain(b.attributes().iter())
+        .map(|(name, _)| name.into_owned())
+        .collect::<Vec<_>>();
+
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Collapses runs of whitespace into a single space and removes it at both ends
+fn collapse(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
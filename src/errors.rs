@@ -1,11 +1,23 @@
 use core::fmt;
 use std::error::Error;
 
+use crate::Span;
+
 /// An error that occurred during parsing
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ParseError {
     /// The input string length was too large to fit in a `u32`
     InvalidLength,
+    /// Parsing took longer than the time budget of [`ParserOptions::time_budget`](crate::ParserOptions::time_budget)
+    TimeBudgetExceeded,
+    /// The input contains markup that the parser would recover from, which is an error with
+    /// [`ParserOptions::strict`](crate::ParserOptions::strict)
+    InvalidMarkup {
+        /// The kind of invalid markup
+        error: MarkupError,
+        /// The position of the invalid markup in the source string, like the start tag of an unclosed element
+        span: Span,
+    },
 }
 
 impl fmt::Display for ParseError {
@@ -14,12 +26,81 @@ impl fmt::Display for ParseError {
             ParseError::InvalidLength => {
                 write!(f, "The input string length is too large to fit in a `u32`")
             }
+            ParseError::TimeBudgetExceeded => {
+                write!(f, "Parsing took longer than the time budget")
+            }
+            ParseError::InvalidMarkup { error, span } => {
+                write!(f, "{} at offset {}", error, span.start())
+            }
         }
     }
 }
 
 impl Error for ParseError {}
 
+/// Markup that the parser recovers from, which makes parsing fail with [`ParserOptions::strict`](crate::ParserOptions::strict)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarkupError {
+    /// An element has no end tag, although it is not void and its end tag may not be omitted
+    UnclosedElement,
+    /// An end tag does not close the innermost open element
+    StrayEndTag,
+    /// The attributes of a start tag are malformed, like in `<a href=>` or `<a href="/"title="Home">`
+    InvalidAttribute,
+    /// A tag, comment or CDATA section is not terminated, like at the end of the input
+    UnterminatedTag,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            MarkupError::UnclosedElement => write!(f, "Element without an end tag"),
+            MarkupError::StrayEndTag => write!(f, "End tag that does not close an open element"),
+            MarkupError::InvalidAttribute => write!(f, "Invalid attribute syntax"),
+            MarkupError::UnterminatedTag => write!(f, "Unterminated tag"),
+        }
+    }
+}
+
+/// An error that occurred while reading and parsing a document with [`crate::from_reader`], `parse_file` or `parse_compressed`
+#[derive(Debug)]
+pub enum ReadError {
+    /// The input could not be read, i.e. because of an I/O error, a file that could not be mapped or corrupt compressed data
+    Io(std::io::Error),
+    /// The document could not be parsed
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ReadError::Io(error) => write!(f, "Failed to read the input: {}", error),
+            ReadError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(error) => Some(error),
+            ReadError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(error: std::io::Error) -> Self {
+        ReadError::Io(error)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(error: ParseError) -> Self {
+        ReadError::Parse(error)
+    }
+}
+
 /// An error that occurred during a call to `Bytes::set`
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SetBytesError {
@@ -38,3 +119,229 @@ impl fmt::Display for SetBytesError {
 }
 
 impl Error for SetBytesError {}
+
+/// An error that occurred while loading a serialized DOM with [`crate::cache::load`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheError {
+    /// The data does not start with the expected header, or was written by an incompatible version of this crate
+    InvalidHeader,
+    /// The data ended unexpectedly
+    UnexpectedEof,
+    /// The data is malformed, i.e. it references positions or nodes that do not exist
+    InvalidData,
+    /// The serialized source string is not valid UTF-8, which is required for owned DOMs
+    InvalidUtf8,
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            CacheError::InvalidHeader => write!(f, "Invalid or incompatible cache header"),
+            CacheError::UnexpectedEof => write!(f, "Unexpected end of cache data"),
+            CacheError::InvalidData => write!(f, "Malformed cache data"),
+            CacheError::InvalidUtf8 => write!(f, "The cached source string is not valid UTF-8"),
+        }
+    }
+}
+
+impl Error for CacheError {}
+
+/// An error that occurred while parsing an extraction rule with [`crate::eval::Rule::parse`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvalError {
+    /// The query selector of the rule is invalid
+    InvalidSelector,
+    /// The accessor after `@` is neither an attribute name nor one of `text()`, `html()` and `outer_html()`
+    InvalidAccessor,
+    /// The rule contains a transform that does not exist
+    UnknownTransform(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            EvalError::InvalidSelector => write!(f, "Invalid query selector"),
+            EvalError::InvalidAccessor => write!(f, "Invalid accessor"),
+            EvalError::UnknownTransform(name) => write!(f, "Unknown transform `{}`", name),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+/// An error that occurred while extracting values with [`crate::unmarshal::Extractor`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnmarshalError {
+    /// The rule of a field could not be parsed
+    InvalidRule {
+        /// The name of the field
+        field: String,
+        /// The reason why the rule is invalid
+        error: EvalError,
+    },
+    /// The selector that separates records is invalid
+    InvalidSelector,
+    /// A required field did not match anything
+    MissingField(String),
+    /// The value of a field could not be converted to the requested type
+    InvalidValue {
+        /// The name of the field
+        field: String,
+        /// The extracted value
+        value: String,
+    },
+}
+
+impl fmt::Display for UnmarshalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            UnmarshalError::InvalidRule { field, error } => {
+                write!(f, "Invalid rule for field `{}`: {}", field, error)
+            }
+            UnmarshalError::InvalidSelector => write!(f, "Invalid query selector"),
+            UnmarshalError::MissingField(field) => write!(f, "Missing field `{}`", field),
+            UnmarshalError::InvalidValue { field, value } => {
+                write!(f, "Invalid value `{}` for field `{}`", value, field)
+            }
+        }
+    }
+}
+
+impl Error for UnmarshalError {}
+
+/// An error that occurred while parsing an XPath expression with [`crate::xpath::XPath::parse`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XPathError {
+    /// The expression ended unexpectedly, i.e. because of an unclosed bracket or string literal
+    UnexpectedEnd,
+    /// The expression contains an unexpected character or token at the given byte offset
+    UnexpectedToken(usize),
+    /// The expression uses an axis that is not supported
+    UnknownAxis(String),
+    /// The expression calls a function that is not supported
+    UnknownFunction(String),
+    /// A function is called with the wrong number of arguments
+    InvalidArguments(String),
+}
+
+impl fmt::Display for XPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            XPathError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            XPathError::UnexpectedToken(offset) => {
+                write!(f, "Unexpected token at offset {}", offset)
+            }
+            XPathError::UnknownAxis(name) => write!(f, "Unknown axis `{}`", name),
+            XPathError::UnknownFunction(name) => write!(f, "Unknown function `{}`", name),
+            XPathError::InvalidArguments(name) => {
+                write!(f, "Wrong number of arguments for function `{}`", name)
+            }
+        }
+    }
+}
+
+impl Error for XPathError {}
+
+/// An error that occurred while rewriting a document with [`crate::rewrite()`]
+#[derive(Debug)]
+pub enum RewriteError {
+    /// The selector of a handler could not be parsed
+    InvalidSelector(String),
+    /// The selector of a handler depends on the content or the following siblings of an element, like `:empty` or
+    /// `:nth-child(2)`, which are not known yet when the start tag of the element is rewritten
+    UnsupportedSelector(String),
+    /// The input could not be parsed
+    Parse(ParseError),
+    /// The output could not be written to the sink
+    Io(std::io::Error),
+}
+
+impl fmt::Display for RewriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            RewriteError::InvalidSelector(selector) => {
+                write!(f, "Invalid query selector `{}`", selector)
+            }
+            RewriteError::UnsupportedSelector(selector) => {
+                write!(
+                    f,
+                    "Query selector `{}` cannot be matched while streaming",
+                    selector
+                )
+            }
+            RewriteError::Parse(error) => error.fmt(f),
+            RewriteError::Io(error) => write!(f, "Failed to write the output: {}", error),
+        }
+    }
+}
+
+impl Error for RewriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RewriteError::Parse(error) => Some(error),
+            RewriteError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RewriteError {
+    fn from(error: std::io::Error) -> Self {
+        RewriteError::Io(error)
+    }
+}
+
+impl From<ParseError> for RewriteError {
+    fn from(error: ParseError) -> Self {
+        RewriteError::Parse(error)
+    }
+}
+
+/// An error that occurred while parsing a query selector with limits, see [`crate::queryselector::SelectorLimits`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SelectorError {
+    /// The selector is not valid or uses syntax that is not supported
+    Invalid,
+    /// The selector is longer than the maximum length in bytes
+    TooLong {
+        /// The length of the selector in bytes
+        length: usize,
+        /// The maximum length in bytes
+        max: usize,
+    },
+    /// The selector nests `:is()` or `:where()` deeper than the maximum nesting
+    TooDeep {
+        /// The maximum nesting
+        max: usize,
+    },
+    /// The selector consists of more simple selectors than the maximum number of components
+    TooManyComponents {
+        /// The maximum number of components
+        max: usize,
+    },
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SelectorError::Invalid => write!(f, "Invalid query selector"),
+            SelectorError::TooLong { length, max } => write!(
+                f,
+                "The query selector is {} bytes long, but at most {} bytes are allowed",
+                length, max
+            ),
+            SelectorError::TooDeep { max } => write!(
+                f,
+                "The query selector nests more than {} selector lists",
+                max
+            ),
+            SelectorError::TooManyComponents { max } => write!(
+                f,
+                "The query selector consists of more than {} simple selectors",
+                max
+            ),
+        }
+    }
+}
+
+impl Error for SelectorError {}
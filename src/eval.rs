@@ -0,0 +1,235 @@
+//! A rule has the form `selector [@accessor] [| transform]*`:
+//!
+//! - The selector is any query selector that [`VDom::query_selector`] accepts.
+//! - The accessor decides which value is extracted from every matching element:
+//!   - `@name`: the value of the attribute `name`. Elements without a value for this attribute are skipped.
+//!   - `@text()`: the inner text. This is the default if no accessor is given.
+//!   - `@html()`: the inner HTML.
+//!   - `@outer_html()`: the outer HTML.
+//! - Transforms are applied to every extracted value from left to right:
+//!   - `trim`: removes leading and trailing whitespace
+//!   - `collapse`: replaces runs of whitespace with a single space and trims the value
+//!   - `lower`: converts the value to lowercase
+//!   - `upper`: converts the value to uppercase
+
+use crate::errors::EvalError;
+use crate::{HTMLTag, Node, Parser, VDom};
+
+/// What to extract from a matching element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accessor<'s> {
+    /// The value of an attribute
+    Attribute(&'s str),
+    /// The inner text
+    Text,
+    /// The inner HTML
+    InnerHtml,
+    /// The outer HTML
+    OuterHtml,
+}
+
+/// A transformation that is applied to extracted values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    /// Removes leading and trailing whitespace
+    Trim,
+    /// Replaces runs of whitespace with a single space and trims the value
+    Collapse,
+    /// Converts the value to lowercase
+    Lower,
+    /// Converts the value to uppercase
+    Upper,
+}
+
+impl Transform {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trim" => Some(Self::Trim),
+            "collapse" => Some(Self::Collapse),
+            "lower" => Some(Self::Lower),
+            "upper" => Some(Self::Upper),
+            _ => None,
+        }
+    }
+
+    /// Applies this transformation to the given value
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Self::Trim => value.trim().to_owned(),
+            Self::Collapse => value.split_whitespace().collect::<Vec<_>>().join(" "),
+            Self::Lower => value.to_lowercase(),
+            Self::Upper => value.to_uppercase(),
+        }
+    }
+}
+
+/// A parsed extraction rule
+///
+/// See the [module documentation](self) for the syntax.
+///
+/// # Example
+/// ```
+/// use tl::eval::{Accessor, Rule, Transform};
+///
+/// let rule = Rule::parse(".price @data-amount | trim").unwrap();
+/// assert_eq!(rule.selector(), ".price");
+/// assert_eq!(rule.accessor(), Accessor::Attribute("data-amount"));
+/// assert_eq!(rule.transforms(), [Transform::Trim]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rule<'s> {
+    selector: &'s str,
+    accessor: Accessor<'s>,
+    transforms: Vec<Transform>,
+}
+
+impl<'s> Rule<'s> {
+    /// Parses an extraction rule
+    ///
+    /// # Errors
+    /// Returns an error if the selector, the accessor or any of the transforms is invalid.
+    pub fn parse(input: &'s str) -> Result<Self, EvalError> {
+        let mut segments = split_top_level(input, b'|').into_iter();
+        let head = segments.next().unwrap_or_default();
+
+        let (selector, accessor) = match rfind_top_level(head, b'@') {
+            Some(at) => (&head[..at], parse_accessor(head[at + 1..].trim())?),
+            None => (head, Accessor::Text),
+        };
+
+        let selector = selector.trim();
+        if crate::parse_query_selector(selector).is_none() {
+            return Err(EvalError::InvalidSelector);
+        }
+
+        let transforms = segments
+            .map(|name| {
+                let name = name.trim();
+                Transform::from_name(name).ok_or_else(|| EvalError::UnknownTransform(name.into()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            selector,
+            accessor,
+            transforms,
+        })
+    }
+
+    /// Returns the query selector of this rule
+    #[inline]
+    pub fn selector(&self) -> &'s str {
+        self.selector
+    }
+
+    /// Returns the accessor of this rule
+    #[inline]
+    pub fn accessor(&self) -> Accessor<'s> {
+        self.accessor
+    }
+
+    /// Returns the transformations of this rule, in the order they are applied
+    #[inline]
+    pub fn transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
+
+    /// Runs this rule against the given DOM and returns the extracted value of every matching element, in document order
+    pub fn eval(&self, dom: &VDom<'_>) -> Vec<String> {
+        let parser = dom.parser();
+
+        let Some(iter) = dom.query_selector(self.selector) else {
+            return Vec::new();
+        };
+
+        iter.filter_map(|handle| handle.get(parser))
+            .filter_map(|node| self.extract(node, parser))
+            .collect()
+    }
+
+    /// Runs this rule against the subnodes of the given element and returns the extracted value of every matching element,
+    /// in document order
+    pub fn eval_in(&self, tag: &HTMLTag<'_>, parser: &Parser<'_>) -> Vec<String> {
+        let Some(iter) = tag.query_selector(parser, self.selector) else {
+            return Vec::new();
+        };
+
+        iter.filter_map(|handle| handle.get(parser))
+            .filter_map(|node| self.extract(node, parser))
+            .collect()
+    }
+
+    fn extract(&self, node: &Node<'_>, parser: &Parser<'_>) -> Option<String> {
+        let value = match self.accessor {
+            Accessor::Attribute(name) => node
+                .as_tag()?
+                .attributes()
+                .get(name)
+                .flatten()?
+                .as_utf8_str()
+                .into_owned(),
+            Accessor::Text => node.inner_text(parser).into_owned(),
+            Accessor::InnerHtml => node.inner_html(parser).into_owned(),
+            Accessor::OuterHtml => node.outer_html(parser).into_owned(),
+        };
+
+        Some(
+            self.transforms
+                .iter()
+                .fold(value, |value, transform| transform.apply(&value)),
+        )
+    }
+}
+
+fn parse_accessor(accessor: &str) -> Result<Accessor<'_>, EvalError> {
+    match accessor {
+        "text()" => Ok(Accessor::Text),
+        "html()" => Ok(Accessor::InnerHtml),
+        "outer_html()" => Ok(Accessor::OuterHtml),
+        _ if !accessor.is_empty()
+            && accessor
+                .bytes()
+                .all(|c| !c.is_ascii_whitespace() && !b"()[]\"'=".contains(&c)) =>
+        {
+            Ok(Accessor::Attribute(accessor))
+        }
+        _ => Err(EvalError::InvalidAccessor),
+    }
+}
+
+/// Returns the byte positions of all occurrences of `needle` that are not inside of quotes or brackets
+fn top_level_positions(input: &str, needle: u8) -> impl Iterator<Item = usize> + '_ {
+    let mut quote = None;
+    let mut depth = 0usize;
+
+    input.bytes().enumerate().filter_map(move |(idx, c)| {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b'[' | b'(') => depth += 1,
+            (None, b']' | b')') => depth = depth.saturating_sub(1),
+            (None, _) if c == needle && depth == 0 => return Some(idx),
+            _ => {}
+        }
+
+        None
+    })
+}
+
+fn split_top_level(input: &str, separator: u8) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+
+    for idx in top_level_positions(input, separator) {
+        segments.push(&input[start..idx]);
+        start = idx + 1;
+    }
+
+    segments.push(&input[start..]);
+    segments
+}
+
+fn rfind_top_level(input: &str, needle: u8) -> Option<usize> {
+    top_level_positions(input, needle).last()
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use crate::text::{is_one_of, TextOptions, INVISIBLE_ELEMENTS};
+use crate::{Edge, HTMLTag, Node, NodeHandle, Parser, Traverse, VDom};
+
+/// Elements whose text is scored and counted towards their ancestors
+const SCORED_ELEMENTS: &[&str] = &["p", "pre", "td"];
+
+/// Paragraphs with less text than this many bytes are not scored
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Substrings of class names and IDs that indicate content
+const POSITIVE_NAMES: &[&str] = &[
+    "article", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+
+/// Substrings of class names and IDs that indicate boilerplate
+const NEGATIVE_NAMES: &[&str] = &[
+    "ad-", "advert", "banner", "comment", "footer", "footnote", "masthead", "menu", "meta", "nav",
+    "related", "share", "sidebar", "social", "sponsor", "widget",
+];
+
+/// The main content and metadata of a document, as found by [`article`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Article {
+    /// The element that contains the main content, or `None` if the document has no text that looks like content
+    pub content: Option<NodeHandle>,
+    /// The title, from the `og:title` meta tag, the `<title>` tag or the first `<h1>`
+    pub title: Option<String>,
+    /// The author, from the `author` meta tag, a `rel="author"` link or an element whose class or ID contains `byline` or `author`
+    pub byline: Option<String>,
+}
+
+/// Finds the main content of a document, like the text of a news article or blog post, along with its title and author
+///
+/// This is a heuristic in the spirit of Mozilla's Readability:
+/// every paragraph with enough text adds a score to its parent and grandparent, depending on its length and number of commas.
+/// The score of these candidates is adjusted by their tag name, by whether their class name or ID
+/// suggests content (`article`, `post`, ...) or boilerplate (`comment`, `sidebar`, ...),
+/// and by the share of their text that is inside of links. The candidate with the highest score is the content.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(r#"
+///     <title>Example</title>
+///     <meta name="author" content="Jane Doe">
+///     <div class="sidebar"><a href="/">Home</a> <a href="/about">About</a></div>
+///     <div class="post">
+///         <p>This is the first paragraph of the article, and it has plenty of text.</p>
+///         <p>This is the second paragraph, which has some more text, commas, and words.</p>
+///     </div>
+/// "#, Default::default()).unwrap();
+///
+/// let article = tl::extract::article(&dom);
+/// let content = article.content.unwrap().get(dom.parser()).unwrap();
+///
+/// assert_eq!(content.as_tag().unwrap().attributes().class().unwrap(), "post");
+/// assert_eq!(article.title.as_deref(), Some("Example"));
+/// assert_eq!(article.byline.as_deref(), Some("Jane Doe"));
+/// ```
+pub fn article(dom: &VDom<'_>) -> Article {
+    Article {
+        content: find_content(dom.parser()),
+        title: find_title(dom),
+        byline: find_byline(dom),
+    }
+}
+
+fn find_content(parser: &Parser<'_>) -> Option<NodeHandle> {
+    let options = TextOptions::new().collapse_whitespace().skip_invisible();
+    let mut scores = HashMap::<NodeHandle, f64>::new();
+
+    for (idx, node) in parser.tags.iter().enumerate() {
+        let Some(tag) = node.as_tag() else {
+            continue;
+        };
+
+        if !is_one_of(tag.name().as_bytes(), SCORED_ELEMENTS) {
+            continue;
+        }
+
+        let text = tag.inner_text_with(parser, options);
+        if text.len() < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let commas = text.matches(',').count();
+        let score = 1.0 + commas as f64 + (text.len() / 100).min(3) as f64;
+
+        let parent = parser.parent_of(parser.handle(idx as u32));
+        let grandparent = parent.and_then(|p| parser.parent_of(p));
+
+        for (ancestor, share) in [(parent, 1.0), (grandparent, 0.5)] {
+            if let Some(ancestor) = ancestor {
+                *scores
+                    .entry(ancestor)
+                    .or_insert_with(|| initial_score(ancestor, parser)) += score * share;
+            }
+        }
+    }
+
+    scores
+        .into_iter()
+        .map(|(handle, score)| (handle, score * (1.0 - link_density(handle, parser))))
+        .max_by(|(a, a_score), (b, b_score)| {
+            // prefer the earlier element if two candidates have the same score
+            a_score
+                .total_cmp(b_score)
+                .then_with(|| b.get_inner().cmp(&a.get_inner()))
+        })
+        .map(|(handle, _)| handle)
+}
+
+/// Returns the score of a candidate before any paragraphs are counted
+fn initial_score(handle: NodeHandle, parser: &Parser<'_>) -> f64 {
+    let Some(tag) = handle.get(parser).and_then(Node::as_tag) else {
+        return 0.0;
+    };
+
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+    let tag_score = match name.as_str() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+
+    tag_score + name_weight(tag)
+}
+
+/// Returns a weight based on whether the class names and ID of a tag suggest content or boilerplate
+fn name_weight(tag: &HTMLTag<'_>) -> f64 {
+    let attributes = tag.attributes();
+    let mut weight = 0.0;
+
+    for value in [attributes.class(), attributes.id()].into_iter().flatten() {
+        let value = value.as_utf8_str().to_ascii_lowercase();
+
+        if NEGATIVE_NAMES.iter().any(|n| value.contains(n)) {
+            weight -= 25.0;
+        }
+
+        if POSITIVE_NAMES.iter().any(|n| value.contains(n)) {
+            weight += 25.0;
+        }
+    }
+
+    weight
+}
+
+/// Returns the share of the text of an element that is inside of links, between 0 and 1
+fn link_density(handle: NodeHandle, parser: &Parser<'_>) -> f64 {
+    let mut total = 0;
+    let mut linked = 0;
+    let mut link_depth = 0;
+
+    let roots = [handle];
+    let mut traverse = Traverse::new(parser, &roots);
+    while let Some(edge) = traverse.next() {
+        let Some(node) = edge.handle().get(parser) else {
+            continue;
+        };
+
+        match (edge, node) {
+            (Edge::Open(_), Node::Tag(tag)) => {
+                let name = tag.name().as_bytes();
+
+                if is_one_of(name, INVISIBLE_ELEMENTS) {
+                    traverse.skip_children();
+                } else if name.eq_ignore_ascii_case(b"a") {
+                    link_depth += 1;
+                }
+            }
+            (Edge::Close(_), Node::Tag(tag))
+                if tag.name().as_bytes().eq_ignore_ascii_case(b"a") =>
+            {
+                link_depth -= 1;
+            }
+            (Edge::Open(_), Node::Raw(text)) => {
+                let len = text
+                    .as_utf8_str()
+                    .split_ascii_whitespace()
+                    .map(str::len)
+                    .sum::<usize>();
+
+                total += len;
+                if link_depth > 0 {
+                    linked += len;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if total == 0 {
+        0.0
+    } else {
+        linked as f64 / total as f64
+    }
+}
+
+fn find_title(dom: &VDom<'_>) -> Option<String> {
+    meta_content(dom, "meta[property=og:title]")
+        .or_else(|| element_text(dom, "title"))
+        .or_else(|| element_text(dom, "h1"))
+}
+
+fn find_byline(dom: &VDom<'_>) -> Option<String> {
+    const MAX_BYLINE_LENGTH: usize = 100;
+
+    meta_content(dom, "meta[name=author]")
+        .or_else(|| element_text(dom, "[rel=author]"))
+        .or_else(|| {
+            dom.nodes()
+                .iter()
+                .filter_map(Node::as_tag)
+                .filter(|tag| {
+                    let attributes = tag.attributes();
+                    [attributes.class(), attributes.id()]
+                        .into_iter()
+                        .flatten()
+                        .any(|value| {
+                            let value = value.as_utf8_str().to_ascii_lowercase();
+                            value.contains("byline") || value.contains("author")
+                        })
+                })
+                .map(|tag| collapsed_text(tag, dom.parser()))
+                .find(|text| !text.is_empty() && text.len() <= MAX_BYLINE_LENGTH)
+        })
+}
+
+/// Returns the non-empty `content` attribute of the first element that matches the selector
+fn meta_content(dom: &VDom<'_>, selector: &str) -> Option<String> {
+    dom.query_selector(selector)?
+        .filter_map(|handle| handle.get(dom.parser())?.as_tag())
+        .filter_map(|tag| tag.attributes().get("content").flatten())
+        .map(|value| value.as_utf8_str().trim().to_owned())
+        .find(|value| !value.is_empty())
+}
+
+/// Returns the non-empty text of the first element that matches the selector
+fn element_text(dom: &VDom<'_>, selector: &str) -> Option<String> {
+    dom.query_selector(selector)?
+        .filter_map(|handle| handle.get(dom.parser())?.as_tag())
+        .map(|tag| collapsed_text(tag, dom.parser()))
+        .find(|text| !text.is_empty())
+}
+
+fn collapsed_text(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    tag.inner_text_with(
+        parser,
+        TextOptions::new().collapse_whitespace().skip_invisible(),
+    )
+}
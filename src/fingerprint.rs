@@ -0,0 +1,478 @@
+use std::borrow::Cow;
+
+use crate::text::{is_one_of, PREFORMATTED_ELEMENTS};
+use crate::{Edge, HTMLTag, Node, NodeHandle, Parser, Traverse, VDom};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Marks the start of an element in the hashed token stream
+const OPEN: u8 = b'<';
+/// Marks the end of an element in the hashed token stream
+const CLOSE: u8 = b'>';
+/// Separates attribute names in the hashed token stream
+const ATTRIBUTE: u8 = b' ';
+
+/// A 64-bit FNV-1a hasher
+///
+/// Unlike the hashers of the standard library, its output is stable across platforms and releases,
+/// so fingerprints can be stored and compared later.
+#[derive(Debug, Clone, Copy)]
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_lowercase(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write(&[byte.to_ascii_lowercase()]);
+        }
+    }
+}
+
+/// A hasher for the tag structure of a document
+///
+/// The hash only depends on the names and nesting of elements, so pages that were generated from the same template
+/// usually have the same fingerprint even if their text differs. This is useful for clustering similar pages of a crawl.
+/// Text, comments and attribute values are ignored, and tag names are compared case insensitively.
+///
+/// Attribute names can optionally be included with [`StructuralHasher::attribute_names`],
+/// in which case their order within a tag does not matter.
+///
+/// The hash is stable across platforms and releases of this crate.
+///
+/// # Example
+/// ```
+/// use tl::fingerprint::StructuralHasher;
+///
+/// let a = tl::parse(r#"<ul><li class="x" id="a">One</li><li>Two</li></ul>"#, Default::default()).unwrap();
+/// let b = tl::parse(r#"<UL><li id="b" class="y">Uno</li><li>Dos</li></UL>"#, Default::default()).unwrap();
+/// let c = tl::parse(r#"<ul><li>One</li></ul>"#, Default::default()).unwrap();
+///
+/// let hasher = StructuralHasher::new().attribute_names();
+/// assert_eq!(hasher.hash(&a), hasher.hash(&b));
+/// assert_ne!(hasher.hash(&a), hasher.hash(&c));
+///
+/// // Only compare the topmost element
+/// let hasher = StructuralHasher::new().max_depth(1);
+/// assert_eq!(hasher.hash(&a), hasher.hash(&c));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StructuralHasher {
+    max_depth: Option<usize>,
+    attribute_names: bool,
+}
+
+impl StructuralHasher {
+    /// Creates a new hasher that hashes all elements, without attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only hashes elements up to the given depth
+    ///
+    /// Topmost elements have a depth of 1, so a depth of 0 hashes nothing.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Includes the names (but not the values) of attributes in the hash
+    pub fn attribute_names(mut self) -> Self {
+        self.attribute_names = true;
+        self
+    }
+
+    /// Computes the structural hash of a document
+    pub fn hash(&self, dom: &VDom<'_>) -> u64 {
+        self.hash_nodes(dom.children(), dom.parser())
+    }
+
+    /// Computes the structural hash of a single node, including all of its subnodes
+    ///
+    /// Returns the hash of an empty document if the node is not an element.
+    pub fn hash_node(&self, handle: NodeHandle, parser: &Parser<'_>) -> u64 {
+        self.hash_nodes(&[handle], parser)
+    }
+
+    fn is_within_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    fn hash_nodes(&self, roots: &[NodeHandle], parser: &Parser<'_>) -> u64 {
+        let mut hasher = Fnv(FNV_OFFSET);
+        let mut depth = 0;
+        let mut names = Vec::new();
+
+        let mut traverse = Traverse::new(parser, roots);
+        while let Some(edge) = traverse.next() {
+            let Some(Node::Tag(tag)) = edge.handle().get(parser) else {
+                continue;
+            };
+
+            match edge {
+                Edge::Open(_) => {
+                    depth += 1;
+
+                    if !self.is_within_depth(depth) {
+                        traverse.skip_children();
+                        continue;
+                    }
+
+                    hasher.write(&[OPEN]);
+                    hasher.write_lowercase(tag.name().as_bytes());
+
+                    if self.attribute_names {
+                        names.clear();
+                        names.extend(tag.attributes().iter().map(|(k, _)| k.to_ascii_lowercase()));
+                        names.sort_unstable();
+
+                        for name in &names {
+                            hasher.write(&[ATTRIBUTE]);
+                            hasher.write(name.as_bytes());
+                        }
+                    }
+                }
+                Edge::Close(_) => {
+                    if self.is_within_depth(depth) {
+                        hasher.write(&[CLOSE]);
+                    }
+
+                    depth -= 1;
+                }
+            }
+        }
+
+        hasher.0
+    }
+}
+
+/// A part of a node that is compared by [`Node::structural_eq`](crate::Node::structural_eq)
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Token<'n> {
+    /// The start of an element with its lowercased name
+    Open(String),
+    /// An attribute with its lowercased name and value, in the order of the names
+    Attribute(String, Option<Cow<'n, str>>),
+    /// Text, with collapsed whitespace outside of preformatted elements
+    Text(Cow<'n, str>),
+    /// Any other node as written
+    Other(Cow<'n, str>),
+    /// The end of an element
+    Close,
+}
+
+impl Token<'_> {
+    fn hash(&self, hasher: &mut Fnv) {
+        // every token is prefixed with its kind and every string with its length, so that different token streams
+        // cannot produce the same bytes
+        let mut write = |kind: u8, value: &str| {
+            hasher.write(&[kind]);
+            hasher.write(&(value.len() as u64).to_le_bytes());
+            hasher.write(value.as_bytes());
+        };
+
+        match self {
+            Self::Open(name) => write(OPEN, name),
+            Self::Attribute(name, value) => {
+                write(ATTRIBUTE, name);
+                match value {
+                    Some(value) => write(b'=', value),
+                    None => write(b'!', ""),
+                }
+            }
+            Self::Text(text) => write(b'"', text),
+            Self::Other(markup) => write(b'?', markup),
+            Self::Close => write(CLOSE, ""),
+        }
+    }
+}
+
+/// Splits a node and its subtree into the tokens that make up its structure and content
+///
+/// Text that only consists of whitespace is skipped, and whitespace in other text is collapsed,
+/// except in preformatted elements like `<pre>`.
+pub(crate) fn structural_tokens<'n>(node: &'n Node<'_>, parser: &'n Parser<'_>) -> Vec<Token<'n>> {
+    let mut tokens = Vec::new();
+    let mut preformatted = 0;
+
+    let Node::Tag(root) = node else {
+        push_leaf(&mut tokens, node, parser, false);
+        return tokens;
+    };
+
+    push_open(&mut tokens, root, &mut preformatted);
+
+    for edge in Traverse::new(parser, root._children.as_slice()) {
+        match (edge, edge.handle().get(parser)) {
+            (Edge::Open(_), Some(Node::Tag(tag))) => push_open(&mut tokens, tag, &mut preformatted),
+            (Edge::Close(_), Some(Node::Tag(tag))) => {
+                push_close(&mut tokens, tag, &mut preformatted)
+            }
+            (Edge::Open(_), Some(node)) => push_leaf(&mut tokens, node, parser, preformatted > 0),
+            _ => {}
+        }
+    }
+
+    push_close(&mut tokens, root, &mut preformatted);
+    tokens
+}
+
+/// Computes the hash of the tokens of a node, see [`Node::structural_hash`](crate::Node::structural_hash)
+pub(crate) fn structural_hash(node: &Node<'_>, parser: &Parser<'_>) -> u64 {
+    let mut hasher = Fnv(FNV_OFFSET);
+    for token in structural_tokens(node, parser) {
+        token.hash(&mut hasher);
+    }
+    hasher.0
+}
+
+fn push_open<'n>(tokens: &mut Vec<Token<'n>>, tag: &'n HTMLTag<'_>, preformatted: &mut usize) {
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+    *preformatted += is_one_of(name.as_bytes(), PREFORMATTED_ELEMENTS) as usize;
+    tokens.push(Token::Open(name));
+
+    let mut attributes = tag
+        .attributes()
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect::<Vec<_>>();
+    attributes.sort_unstable();
+
+    tokens.extend(
+        attributes
+            .into_iter()
+            .map(|(name, value)| Token::Attribute(name, value)),
+    );
+}
+
+fn push_close(tokens: &mut Vec<Token<'_>>, tag: &HTMLTag<'_>, preformatted: &mut usize) {
+    *preformatted -= is_one_of(tag.name().as_bytes(), PREFORMATTED_ELEMENTS) as usize;
+    tokens.push(Token::Close);
+}
+
+fn push_leaf<'n>(
+    tokens: &mut Vec<Token<'n>>,
+    node: &'n Node<'_>,
+    parser: &'n Parser<'_>,
+    preformatted: bool,
+) {
+    match node {
+        Node::Raw(_) | Node::CData(_) => {
+            let text = node.inner_text(parser);
+
+            // text without any whitespace does not need to be copied
+            if preformatted || (!text.is_empty() && !text.bytes().any(|b| b.is_ascii_whitespace()))
+            {
+                tokens.push(Token::Text(text));
+            } else {
+                let collapsed = text.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+                if !collapsed.is_empty() {
+                    tokens.push(Token::Text(Cow::Owned(collapsed)));
+                }
+            }
+        }
+        _ => tokens.push(Token::Other(node.outer_html(parser))),
+    }
+}
+
+/// Elements whose content is left out of a [`TextFingerprint`], because it is not visible
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "template", "noscript", "head"];
+/// Elements whose content is left out of a [`TextFingerprint`] unless [`TextHasher::include_boilerplate`] is used,
+/// because it is usually shared by all pages of a site
+const BOILERPLATE_ELEMENTS: &[&str] = &["nav", "header", "footer", "aside"];
+/// Roles of elements that are boilerplate like [`BOILERPLATE_ELEMENTS`]
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary"];
+
+/// A SimHash of the visible text of a document, as computed by [`TextHasher`]
+///
+/// Unlike a regular hash, similar texts have similar fingerprints: the more two texts have in common,
+/// the fewer bits of their fingerprints differ. The number of differing bits is returned by [`TextFingerprint::distance`].
+/// Unrelated texts differ in about 32 bits. Which distance still counts as a near-duplicate depends on the length of the texts,
+/// because a change of a few words moves the fingerprint of a short text further.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextFingerprint(pub u64);
+
+impl TextFingerprint {
+    /// Returns the number of bits that differ between two fingerprints, from `0` for equal fingerprints to `64`
+    pub fn distance(&self, other: &TextFingerprint) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// Returns the share of bits that are equal in two fingerprints, from `0.0` to `1.0` for equal fingerprints
+    pub fn similarity(&self, other: &TextFingerprint) -> f64 {
+        1.0 - self.distance(other) as f64 / 64.0
+    }
+}
+
+/// A hasher for the visible text of a document, which computes a [`TextFingerprint`] for finding near-duplicate pages
+///
+/// The text is split into words, which are lowercased and stripped of punctuation, and every run of
+/// [`TextHasher::shingle_size`] consecutive words (a shingle) contributes to the SimHash of the text.
+/// The content of elements that are not visible, like `<script>` and `<style>`, and of elements with the `hidden` attribute
+/// is left out, and so is boilerplate like `<nav>`, `<header>`, `<footer>` and `<aside>`,
+/// because it is usually shared by all pages of a site.
+///
+/// The fingerprint is stable across platforms and releases of this crate.
+///
+/// # Example
+/// ```
+/// use tl::fingerprint::TextHasher;
+///
+/// let article = "<p>The quick brown fox jumps over the lazy dog near the old river bank today.</p>";
+/// let a = tl::parse(article, Default::default()).unwrap();
+/// let page = format!("<nav>Home | About</nav>{article}<script>track()</script>");
+/// let b = tl::parse(&page, Default::default()).unwrap();
+/// let c = tl::parse("<p>Rust is a language empowering everyone to build reliable software.</p>", Default::default()).unwrap();
+///
+/// let hasher = TextHasher::new();
+/// assert_eq!(hasher.hash(&a), hasher.hash(&b));
+/// assert!(hasher.hash(&a).distance(&hasher.hash(&c)) > 16);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextHasher {
+    shingle_size: usize,
+    boilerplate: bool,
+}
+
+impl Default for TextHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextHasher {
+    /// Creates a new hasher with shingles of 3 words, which leaves out boilerplate
+    pub fn new() -> Self {
+        Self {
+            shingle_size: 3,
+            boilerplate: false,
+        }
+    }
+
+    /// Sets the number of consecutive words that are hashed together. Defaults to 3.
+    ///
+    /// Larger shingles make the fingerprint more sensitive to changes in word order. A size of 0 is treated as 1.
+    pub fn shingle_size(mut self, size: usize) -> Self {
+        self.shingle_size = size.max(1);
+        self
+    }
+
+    /// Includes the text of boilerplate elements like `<nav>` and `<footer>` in the fingerprint
+    pub fn include_boilerplate(mut self) -> Self {
+        self.boilerplate = true;
+        self
+    }
+
+    /// Computes the fingerprint of the visible text of a document
+    pub fn hash(&self, dom: &VDom<'_>) -> TextFingerprint {
+        self.hash_nodes(dom.children(), dom.parser())
+    }
+
+    /// Computes the fingerprint of the visible text of a node and its subnodes
+    pub fn hash_node(&self, handle: NodeHandle, parser: &Parser<'_>) -> TextFingerprint {
+        self.hash_nodes(&[handle], parser)
+    }
+
+    /// Computes the fingerprint of a plain text
+    ///
+    /// # Example
+    /// ```
+    /// use tl::fingerprint::TextHasher;
+    ///
+    /// let hasher = TextHasher::new();
+    /// assert_eq!(hasher.hash_text("Hello, World!"), hasher.hash_text("hello world"));
+    /// assert_eq!(hasher.hash_text("").0, 0);
+    /// ```
+    pub fn hash_text(&self, text: &str) -> TextFingerprint {
+        self.simhash(&words(text))
+    }
+
+    fn hash_nodes(&self, roots: &[NodeHandle], parser: &Parser<'_>) -> TextFingerprint {
+        let mut words = Vec::new();
+
+        let mut traverse = Traverse::new(parser, roots);
+        while let Some(edge) = traverse.next() {
+            let Edge::Open(handle) = edge else {
+                continue;
+            };
+
+            match handle.get(parser) {
+                Some(Node::Tag(tag)) if self.is_skipped(tag) => traverse.skip_children(),
+                Some(node @ (Node::Raw(_) | Node::CData(_))) => {
+                    words.extend(self::words(&node.inner_text(parser)));
+                }
+                _ => {}
+            }
+        }
+
+        self.simhash(&words)
+    }
+
+    /// Checks whether the content of an element is left out of the fingerprint
+    fn is_skipped(&self, tag: &HTMLTag<'_>) -> bool {
+        let name = tag.name().as_bytes();
+        let is_boilerplate = || {
+            is_one_of(name, BOILERPLATE_ELEMENTS)
+                || tag.attributes().get("role").flatten().is_some_and(|role| {
+                    role.as_bytes()
+                        .split(u8::is_ascii_whitespace)
+                        .any(|role| is_one_of(role, BOILERPLATE_ROLES))
+                })
+        };
+
+        is_one_of(name, HIDDEN_ELEMENTS)
+            || tag.attributes().contains("hidden")
+            || (!self.boilerplate && is_boilerplate())
+    }
+
+    fn simhash(&self, words: &[String]) -> TextFingerprint {
+        let mut weights = [0i64; 64];
+
+        // texts that are shorter than a shingle are hashed as a single shingle
+        let size = self.shingle_size.min(words.len()).max(1);
+        for shingle in words.windows(size) {
+            let mut hasher = Fnv(FNV_OFFSET);
+            for word in shingle {
+                hasher.write(&(word.len() as u64).to_le_bytes());
+                hasher.write(word.as_bytes());
+            }
+
+            let hash = mix(hasher.0);
+            for (bit, weight) in weights.iter_mut().enumerate() {
+                *weight += if hash & (1 << bit) != 0 { 1 } else { -1 };
+            }
+        }
+
+        TextFingerprint(
+            weights
+                .iter()
+                .enumerate()
+                .filter(|(_, weight)| **weight > 0)
+                .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit),
+        )
+    }
+}
+
+/// Splits a text into lowercase words of letters and digits
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Spreads the bits of an FNV hash, whose high bits depend little on the last bytes of the input,
+/// so that every bit of the SimHash is equally likely to be set
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
@@ -0,0 +1,291 @@
+use crate::text::TextOptions;
+use crate::{Edge, HTMLTag, Node, NodeHandle, Parser, Traverse, VDom};
+
+/// Input types that are never submitted with their default state
+const UNSUBMITTED_INPUT_TYPES: &[&str] = &["button", "file", "image", "reset", "submit"];
+
+/// A `<form>` element and its controls, as returned by [`VDom::forms`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    /// The `<form>` element
+    pub node: NodeHandle,
+    /// The `action` attribute
+    pub action: Option<String>,
+    /// The `method` attribute in lowercase, `get` if it is missing
+    pub method: String,
+    /// The controls of this form in document order: `<input>`, `<select>` and `<textarea>` elements inside of the form,
+    /// and those anywhere in the document whose `form` attribute refers to its ID
+    pub fields: Vec<Field>,
+}
+
+/// A form control
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The element of this control
+    pub node: NodeHandle,
+    /// The `name` attribute, an empty string if it is missing
+    pub name: String,
+    /// The kind of control
+    pub kind: FieldKind,
+    /// The default value: the `value` attribute of inputs or the text of textareas
+    pub value: String,
+    /// Whether a checkbox or radio button is checked by default
+    pub checked: bool,
+    /// Whether the control has a `disabled` attribute
+    pub disabled: bool,
+}
+
+/// The kind of a [`Field`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldKind {
+    /// An `<input>` with the given `type` in lowercase, `text` if it is missing
+    Input(String),
+    /// A `<select>`
+    Select {
+        /// Whether more than one option can be selected
+        multiple: bool,
+        /// The options, including those in an `<optgroup>`
+        options: Vec<SelectOption>,
+    },
+    /// A `<textarea>`
+    TextArea,
+}
+
+/// An `<option>` of a `<select>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectOption {
+    /// The `value` attribute, or the label if it is missing
+    pub value: String,
+    /// The text of the option with collapsed whitespace
+    pub label: String,
+    /// Whether the option is selected by default
+    pub selected: bool,
+    /// Whether the option has a `disabled` attribute
+    pub disabled: bool,
+}
+
+impl Form {
+    /// Returns the name/value pairs that a browser submits for this form if none of its controls are changed
+    ///
+    /// Controls without a name or with a `disabled` attribute, unchecked checkboxes and radio buttons, and buttons
+    /// and file inputs are skipped. A checked checkbox without a value submits `on`. A `<select>` submits its
+    /// selected options, or its first enabled option if none are selected and only one option can be selected.
+    pub fn serialize(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+
+        for field in &self.fields {
+            if field.name.is_empty() || field.disabled {
+                continue;
+            }
+
+            let name = || field.name.clone();
+
+            match &field.kind {
+                FieldKind::Input(ty) if UNSUBMITTED_INPUT_TYPES.contains(&ty.as_str()) => {}
+                FieldKind::Input(ty) if ty == "checkbox" || ty == "radio" => {
+                    if field.checked {
+                        let value = match field.value.as_str() {
+                            "" => "on".to_owned(),
+                            value => value.to_owned(),
+                        };
+                        pairs.push((name(), value));
+                    }
+                }
+                FieldKind::Input(_) | FieldKind::TextArea => {
+                    pairs.push((name(), field.value.clone()))
+                }
+                FieldKind::Select { multiple, options } => {
+                    let enabled = || options.iter().filter(|o| !o.disabled);
+                    let mut selected = enabled().filter(|o| o.selected).peekable();
+
+                    if selected.peek().is_some() {
+                        pairs.extend(selected.map(|o| (name(), o.value.clone())));
+                    } else if !multiple {
+                        pairs.extend(enabled().next().map(|o| (name(), o.value.clone())));
+                    }
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// Returns the URL that this form is submitted to: its `action` attribute resolved against the given base URL
+    ///
+    /// The base URL is usually the one of the document, see [`VDom::base_url`].
+    /// Returns `None` if the form has no `action` or an empty one, because such forms are submitted to the URL of the document itself.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<base href="/shop/"><form action="search"></form>"#, Default::default()).unwrap();
+    /// let base = dom.base_url(Some("https://example.com/"));
+    ///
+    /// assert_eq!(
+    ///     dom.forms()[0].action_url(base.as_deref()).as_deref(),
+    ///     Some("https://example.com/shop/search")
+    /// );
+    /// ```
+    pub fn action_url(&self, base_url: Option<&str>) -> Option<String> {
+        let action = self
+            .action
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty())?;
+
+        Some(match base_url {
+            Some(base) => crate::links::resolve(base, action),
+            None => action.to_owned(),
+        })
+    }
+
+    /// Returns the pairs of [`Form::serialize`] in the `application/x-www-form-urlencoded` format, i.e. `q=hello+world&page=2`
+    pub fn urlencode(&self) -> String {
+        self.serialize()
+            .iter()
+            .map(|(name, value)| format!("{}={}", urlencode(name), urlencode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// Encodes a string as a component of `application/x-www-form-urlencoded` data
+fn urlencode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for byte in input.bytes() {
+        match byte {
+            b' ' => out.push('+'),
+            b'*' | b'-' | b'.' | b'_' => out.push(byte as char),
+            _ if byte.is_ascii_alphanumeric() => out.push(byte as char),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    out
+}
+
+fn attribute(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .flatten()
+        .map(|value| value.as_utf8_str().into_owned())
+}
+
+fn is_named(tag: &HTMLTag<'_>, name: &[u8]) -> bool {
+    tag.name().as_bytes().eq_ignore_ascii_case(name)
+}
+
+fn collapsed_text(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    tag.inner_text_with(parser, TextOptions::new().collapse_whitespace())
+}
+
+/// Collects the forms of a document in document order
+pub(crate) fn collect(dom: &VDom<'_>) -> Vec<Form> {
+    let parser = dom.parser();
+    let tags = || {
+        dom.nodes()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| Some((parser.handle(idx as u32), node.as_tag()?)))
+    };
+
+    tags()
+        .filter(|(_, tag)| is_named(tag, b"form"))
+        .map(|(handle, form)| {
+            let mut controls = Vec::new();
+
+            let roots = [handle];
+            for edge in Traverse::new(parser, &roots) {
+                if let Edge::Open(child) = edge {
+                    controls.push(child);
+                }
+            }
+
+            // controls outside of the form that are associated with it through their `form` attribute
+            if let Some(id) = form.attributes().id() {
+                controls.extend(
+                    tags()
+                        .filter(|(_, tag)| {
+                            tag.attributes()
+                                .get("form")
+                                .flatten()
+                                .is_some_and(|form| form == id)
+                        })
+                        .map(|(handle, _)| handle),
+                );
+                controls.sort_unstable();
+                controls.dedup();
+            }
+
+            Form {
+                node: handle,
+                action: attribute(form, "action"),
+                method: attribute(form, "method")
+                    .map_or_else(|| "get".to_owned(), |m| m.trim().to_ascii_lowercase()),
+                fields: controls
+                    .into_iter()
+                    .filter_map(|handle| field(handle, parser))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn field(handle: NodeHandle, parser: &Parser<'_>) -> Option<Field> {
+    let tag = handle.get(parser)?.as_tag()?;
+
+    let (kind, value) = if is_named(tag, b"input") {
+        let ty = attribute(tag, "type")
+            .map_or_else(|| "text".to_owned(), |t| t.trim().to_ascii_lowercase());
+        (
+            FieldKind::Input(ty),
+            attribute(tag, "value").unwrap_or_default(),
+        )
+    } else if is_named(tag, b"textarea") {
+        let text = tag.inner_text(parser);
+        // a newline directly after the start tag is not part of the value
+        let text = text.strip_prefix('\n').unwrap_or(&text).to_owned();
+        (FieldKind::TextArea, text)
+    } else if is_named(tag, b"select") {
+        let kind = FieldKind::Select {
+            multiple: tag.attributes().get_bool("multiple"),
+            options: options(handle, parser),
+        };
+        (kind, String::new())
+    } else {
+        return None;
+    };
+
+    Some(Field {
+        node: handle,
+        name: attribute(tag, "name").unwrap_or_default(),
+        kind,
+        value,
+        checked: tag.attributes().get_bool("checked"),
+        disabled: tag.attributes().get_bool("disabled"),
+    })
+}
+
+fn options(select: NodeHandle, parser: &Parser<'_>) -> Vec<SelectOption> {
+    let roots = [select];
+
+    Traverse::new(parser, &roots)
+        .filter_map(|edge| match edge {
+            Edge::Open(handle) => match handle.get(parser) {
+                Some(Node::Tag(tag)) if is_named(tag, b"option") => Some(tag),
+                _ => None,
+            },
+            Edge::Close(_) => None,
+        })
+        .map(|option| {
+            let label = collapsed_text(option, parser);
+
+            SelectOption {
+                value: attribute(option, "value").unwrap_or_else(|| label.clone()),
+                label,
+                selected: option.attributes().get_bool("selected"),
+                disabled: option.attributes().get_bool("disabled"),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,266 @@
+use crate::inline::vec::InlineVec;
+use crate::parser::{Attributes, ClassList, HTMLTag, Node, NodeHandle, Parser, ParserOptions};
+use crate::{Bytes, VDom};
+
+/// An owned copy of a subtree, as returned by [`HTMLTag::clone_subtree`]
+///
+/// All text of the nodes is copied, so a fragment does not borrow from the document it was copied from and can outlive it.
+/// It can be inserted into other documents any number of times with [`VDom::insert_fragment`],
+/// or be turned into a document of its own with [`OwnedFragment::to_dom`].
+///
+/// The copied elements count as modified, so their markup is always written from their names and attributes.
+///
+/// # Example
+/// ```
+/// let fragment = {
+///     let source = String::from("<ul><li class='item'>Item</li></ul>");
+///     let dom = tl::parse(&source, Default::default()).unwrap();
+///     let li = dom.query_selector("li").unwrap().next().unwrap();
+///     li.get(dom.parser()).unwrap().as_tag().unwrap().clone_subtree(dom.parser())
+/// };
+///
+/// assert_eq!(fragment.len(), 2);
+/// assert_eq!(fragment.outer_html(), r#"<li class="item">Item</li>"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OwnedFragment {
+    /// The nodes of the subtree in document order, starting with its root
+    ///
+    /// Handles in the nodes are indices into this list.
+    nodes: Vec<Node<'static>>,
+    /// The options of the document that the subtree was copied from
+    options: ParserOptions,
+}
+
+impl OwnedFragment {
+    /// Returns the root of the copied subtree
+    #[inline]
+    pub fn root(&self) -> &Node<'static> {
+        &self.nodes[0]
+    }
+
+    /// Returns all nodes of the copied subtree in document order, starting with its root
+    ///
+    /// The handles of the children of the elements are indices into this slice.
+    #[inline]
+    pub fn nodes(&self) -> &[Node<'static>] {
+        &self.nodes
+    }
+
+    /// Returns the number of nodes in the copied subtree, including its root
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always returns `false`, because a fragment contains at least its root
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Creates a document that only consists of the copied subtree
+    ///
+    /// The document has no source string, so its lookup tables are built with the options of the document
+    /// that the subtree was copied from.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<div><p id="a">Hello</p></div>"#, Default::default()).unwrap();
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    /// let fragment = div.clone_subtree(dom.parser()).to_dom();
+    ///
+    /// assert_eq!(fragment.children().len(), 1);
+    /// assert!(fragment.get_element_by_id("a").is_some());
+    /// ```
+    pub fn to_dom(&self) -> VDom<'static> {
+        let mut parser = Parser::new_bytes(&[], self.options);
+        let root = attach(&mut parser, self, None);
+        parser.ast.push(root);
+        parser.into()
+    }
+
+    /// Returns the markup of the copied subtree
+    pub fn outer_html(&self) -> String {
+        self.to_dom().outer_html()
+    }
+}
+
+/// Copies the subtree of an element, see [`HTMLTag::clone_subtree`]
+pub(crate) fn clone_subtree(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> OwnedFragment {
+    let mut nodes = vec![Node::Tag(copy_tag(tag, 0, None))];
+    // the parent of the children that are copied next, and the position of the next one of them
+    let mut stack = vec![(0, tag._children.as_slice(), 0)];
+
+    while let Some((parent, children, next)) = stack.last_mut() {
+        let parent = *parent;
+
+        let Some(&child) = children.get(*next) else {
+            stack.pop();
+            let end = nodes.len() as u32;
+            if let Some(tag) = nodes[parent].as_tag_mut() {
+                tag._descendants.end = end;
+            }
+            continue;
+        };
+        *next += 1;
+
+        let Some(node) = child.get(parser) else {
+            continue;
+        };
+
+        let index = nodes.len();
+        let handle = NodeHandle::new(index as u32);
+
+        nodes.push(match node {
+            Node::Tag(tag) => {
+                stack.push((index, tag._children.as_slice(), 0));
+                Node::Tag(copy_tag(tag, index, Some(NodeHandle::new(parent as u32))))
+            }
+            Node::Raw(text) => Node::Raw(text.to_static()),
+            Node::Comment(comment) => Node::Comment(comment.to_static()),
+            Node::ProcessingInstruction(pi) => Node::ProcessingInstruction(pi.to_static()),
+            Node::CData(cdata) => Node::CData(cdata.to_static()),
+            Node::Doctype(doctype) => Node::Doctype(doctype.to_static()),
+        });
+
+        if let Some(tag) = nodes[parent].as_tag_mut() {
+            tag._children.push(handle);
+        }
+    }
+
+    OwnedFragment {
+        nodes,
+        options: parser.options,
+    }
+}
+
+/// Copies an element without its children, which is placed at the given index of a fragment
+fn copy_tag(tag: &HTMLTag<'_>, index: usize, parent: Option<NodeHandle>) -> HTMLTag<'static> {
+    let mut attributes = Attributes::new();
+    attributes.id = tag._attributes.id.as_ref().map(Bytes::to_static);
+    attributes.class = tag._attributes.class.as_ref().map(Bytes::to_static);
+    for (key, value) in tag._attributes.raw.iter() {
+        attributes
+            .raw
+            .insert(key.to_static(), value.as_ref().map(Bytes::to_static));
+    }
+
+    let mut copy = HTMLTag::new(
+        tag._name.to_static(),
+        attributes,
+        InlineVec::new(),
+        parent,
+        tag._raw.to_static(),
+        tag._span.start_tag,
+    );
+    copy._span = tag._span;
+    copy._descendants = index as u32 + 1..index as u32 + 1;
+    copy._namespace = tag._namespace;
+    copy._void = tag._void;
+    // the spans point into the source of the other document
+    copy._modified = true;
+    copy
+}
+
+/// Adds the nodes of a fragment to the end of the nodes of a parser and returns the handle of its root
+///
+/// The root is not added to the children of the given parent, which is left to the caller.
+pub(crate) fn attach<'a>(
+    parser: &mut Parser<'a>,
+    fragment: &OwnedFragment,
+    parent: Option<NodeHandle>,
+) -> NodeHandle {
+    let offset = parser.tags.len() as u32;
+    let document = parser.document();
+    let handle = |index: u32| NodeHandle::in_document(index + offset, document);
+
+    let mut parents = vec![parent; fragment.nodes.len()];
+    for (index, tag) in fragment.nodes.iter().enumerate() {
+        for child in tag
+            .as_tag()
+            .into_iter()
+            .flat_map(|tag| tag._children.iter())
+        {
+            parents[child.get_inner() as usize] = Some(handle(index as u32));
+        }
+    }
+
+    for (index, node) in fragment.nodes.iter().enumerate() {
+        let mut node: Node<'a> = node.clone();
+
+        if let Node::Tag(tag) = &mut node {
+            let children = std::mem::replace(&mut tag._children, InlineVec::new());
+            for child in children.iter() {
+                tag._children.push(handle(child.get_inner()));
+            }
+
+            tag._parent = parents[index];
+            tag._descendants = tag._descendants.start + offset..tag._descendants.end + offset;
+            register(parser, handle(index as u32), tag);
+        }
+
+        parser.tags.push(node);
+        // the parents of the other nodes are only known if they were looked up already
+        if let Some(siblings) = parser.siblings.get_mut() {
+            siblings.parents.push(parents[index]);
+        }
+    }
+
+    handle(0)
+}
+
+/// Adds an element to the lookup tables of a parser, according to its options
+pub(crate) fn register<'a>(parser: &mut Parser<'a>, this: NodeHandle, tag: &HTMLTag<'a>) {
+    let options = parser.options;
+
+    if options.is_tracking_tags() {
+        parser
+            .tag_names
+            .entry(tag._name.bytes().clone())
+            .or_default()
+            .push(this);
+    }
+
+    if tag._name.as_bytes().eq_ignore_ascii_case(b"template") && !options.is_xml_mode() {
+        parser.templates.push(this);
+    }
+
+    if let Some(id) = tag
+        ._attributes
+        .id
+        .clone()
+        .filter(|_| options.is_tracking_ids())
+    {
+        parser
+            .ids
+            .entry(id)
+            .or_insert_with(InlineVec::new)
+            .push(this);
+    }
+
+    let classes = tag
+        ._attributes
+        .class
+        .as_ref()
+        .filter(|_| options.is_tracking_classes())
+        .and_then(Bytes::try_as_utf8_str);
+
+    for class in classes.map(ClassList::new).into_iter().flatten() {
+        parser
+            .classes
+            .entry(Bytes::from(class).to_static())
+            .or_insert_with(InlineVec::new)
+            .push(this);
+    }
+
+    for key in options.tracked_attributes() {
+        if let Some(Some(value)) = tag._attributes.get(key) {
+            parser
+                .attribute_values
+                .entry((key, value.clone()))
+                .or_default()
+                .push(this);
+        }
+    }
+}
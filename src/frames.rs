@@ -0,0 +1,172 @@
+use std::borrow::Cow;
+
+use crate::links::resolve;
+use crate::{HTMLTag, NodeHandle, ParseError, ParserOptions, VDom};
+
+/// The longest character reference that is decoded, including `&` and `;`, like `&#x10FFFF;`
+const MAX_REFERENCE_LEN: usize = 10;
+
+/// An `<iframe>` or `<frame>` element, as returned by [`VDom::iframes`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Frame {
+    /// The element of the frame
+    pub node: NodeHandle,
+    /// The `src` attribute, resolved against the base URL if there is one
+    pub src: Option<String>,
+    /// The `srcdoc` attribute with its character references decoded, i.e. the markup of the embedded document
+    ///
+    /// Browsers show this document instead of the one at [`Frame::src`] if both are present.
+    /// `<frame>` elements do not support `srcdoc`, so this is always `None` for them.
+    pub srcdoc: Option<String>,
+    /// The `name` attribute, which links and forms can target
+    pub name: Option<String>,
+    /// The `title` attribute, which describes the content of the frame
+    pub title: Option<String>,
+}
+
+impl Frame {
+    /// Parses the embedded document of the `srcdoc` attribute, or returns `None` if there is none
+    ///
+    /// The document is only parsed when this is called, and it borrows from this frame.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<iframe srcdoc="<p class=&quot;ad&quot;>Buy &amp; save</p>"></iframe>"#,
+    ///     Default::default(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let frames = dom.iframes(None);
+    /// let embedded = frames[0].parse_srcdoc(Default::default()).unwrap().unwrap();
+    ///
+    /// let p = embedded.query_selector("p.ad").unwrap().next().unwrap();
+    /// assert_eq!(p.get(embedded.parser()).unwrap().inner_text(embedded.parser()), "Buy & save");
+    /// ```
+    pub fn parse_srcdoc(&self, options: ParserOptions) -> Option<Result<VDom<'_>, ParseError>> {
+        self.srcdoc
+            .as_deref()
+            .map(|srcdoc| crate::parse(srcdoc, options))
+    }
+
+    /// Checks whether browsers show the document of the `srcdoc` attribute instead of loading [`Frame::src`]
+    pub fn is_srcdoc(&self) -> bool {
+        self.srcdoc.is_some()
+    }
+}
+
+/// Collects the frames of a document in document order, see [`VDom::iframes`]
+pub(crate) fn collect(dom: &VDom<'_>, base_url: Option<&str>) -> Vec<Frame> {
+    let base = dom.base_url(base_url);
+    let parser = dom.parser();
+
+    dom.nodes()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, node)| {
+            let tag = node.as_tag()?;
+            let name = tag.name().as_bytes();
+            let is_iframe = name.eq_ignore_ascii_case(b"iframe");
+            if !is_iframe && !name.eq_ignore_ascii_case(b"frame") {
+                return None;
+            }
+
+            let src = attribute_value(tag, "src")
+                .map(|src| src.trim().to_owned())
+                .filter(|src| !src.is_empty())
+                .map(|src| match &base {
+                    Some(base) => resolve(base, &src),
+                    None => src,
+                });
+
+            let srcdoc = attribute_value(tag, "srcdoc")
+                .filter(|_| is_iframe)
+                .map(|srcdoc| decode_character_references(&srcdoc).into_owned());
+
+            Some(Frame {
+                node: parser.handle(index as u32),
+                src,
+                srcdoc,
+                name: attribute_value(tag, "name"),
+                title: attribute_value(tag, "title"),
+            })
+        })
+        .collect()
+}
+
+fn attribute_value(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .flatten()
+        .map(|value| value.as_utf8_str().into_owned())
+}
+
+/// Decodes numeric character references and the named references that are common in attribute values,
+/// like `&quot;` and `&amp;`
+///
+/// References without a semicolon and unknown named references are kept as they are.
+fn decode_character_references(input: &str) -> Cow<'_, str> {
+    if !input.contains('&') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest
+            .bytes()
+            .take(MAX_REFERENCE_LEN)
+            .position(|c| c == b';')
+            .filter(|&end| end > 1)
+            .and_then(|end| Some((decode_reference(&rest[1..end])?, end + 1)));
+
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Decodes the name of a character reference without `&` and `;`, like `amp` or `#x27`
+fn decode_reference(name: &str) -> Option<char> {
+    let code = match name.strip_prefix('#') {
+        Some(number) => match number.strip_prefix(['x', 'X']) {
+            Some(hex) if hex.bytes().all(|c| c.is_ascii_hexdigit()) => {
+                u32::from_str_radix(hex, 16).ok()?
+            }
+            None if number.bytes().all(|c| c.is_ascii_digit()) => number.parse().ok()?,
+            _ => return None,
+        },
+        None => {
+            return match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => None,
+            }
+        }
+    };
+
+    // like browsers, null characters and invalid code points become the replacement character
+    Some(
+        char::from_u32(code)
+            .filter(|&c| c != '\0')
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+    )
+}
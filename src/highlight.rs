@@ -0,0 +1,223 @@
+use std::{borrow::Cow, cmp::Reverse};
+
+use crate::{Doctype, Node, Parser, VDom};
+
+/// Elements whose content is not markup, and must therefore never contain highlighting tags
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Wraps occurrences of search terms in text nodes with a tag, i.e. `<mark>`
+///
+/// Only text nodes are searched. Attributes, comments and the content of `<script>`, `<style>`, `<textarea>` and `<title>`
+/// elements are left untouched, and matches never split a character reference like `&amp;`.
+/// By default, terms only match whole words and are compared case sensitively.
+///
+/// # Example
+/// ```
+/// use tl::highlight::Highlighter;
+///
+/// let dom = tl::parse(
+///     r#"<p title="rust">Rust is trusty.</p><script>rust()</script>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let html = Highlighter::new(&["rust"]).ignore_case().highlight(&dom);
+/// assert_eq!(html, r#"<p title="rust"><mark>Rust</mark> is trusty.</p><script>rust()</script>"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Highlighter<'t> {
+    terms: Vec<&'t str>,
+    tag: &'t str,
+    ignore_case: bool,
+    whole_words: bool,
+}
+
+impl<'t> Highlighter<'t> {
+    /// Creates a new highlighter for the given terms
+    ///
+    /// If several terms match at the same position, the longest one wins. Empty terms are ignored.
+    pub fn new(terms: &[&'t str]) -> Self {
+        let mut terms: Vec<&str> = terms.iter().copied().filter(|t| !t.is_empty()).collect();
+        terms.sort_by_key(|t| Reverse(t.len()));
+
+        Self {
+            terms,
+            tag: "mark",
+            ignore_case: false,
+            whole_words: true,
+        }
+    }
+
+    /// Sets the name of the tag that matches are wrapped in. Defaults to `mark`.
+    pub fn tag(mut self, tag: &'t str) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Compares terms ASCII case insensitively
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    /// Allows terms to match anywhere, including in the middle of words
+    pub fn partial_words(mut self) -> Self {
+        self.whole_words = false;
+        self
+    }
+
+    /// Returns the markup of the given DOM with all matches highlighted
+    pub fn highlight(&self, dom: &VDom<'_>) -> String {
+        let parser = dom.parser();
+        let mut out = String::with_capacity(parser.stream.len());
+
+        for node in dom.children() {
+            let node = node.get(parser).unwrap();
+            self.write_node(node, parser, false, &mut out);
+        }
+
+        out
+    }
+
+    /// Returns the markup of the given node with all matches in it highlighted
+    pub fn highlight_node<'a>(&self, node: &Node<'a>, parser: &Parser<'a>) -> String {
+        let mut out = String::new();
+        self.write_node(node, parser, false, &mut out);
+        out
+    }
+
+    /// Highlights all matches in the given HTML text (the content of a text node)
+    ///
+    /// # Example
+    /// ```
+    /// use tl::highlight::Highlighter;
+    ///
+    /// let highlighter = Highlighter::new(&["amp", "cat"]);
+    /// assert_eq!(highlighter.highlight_text("cat &amp; catalog"), "<mark>cat</mark> &amp; catalog");
+    /// ```
+    pub fn highlight_text<'s>(&self, text: &'s str) -> Cow<'s, str> {
+        let bytes = text.as_bytes();
+        let mut out = String::new();
+        let mut last = 0;
+        let mut idx = 0;
+
+        while idx < bytes.len() {
+            if bytes[idx] == b'&' {
+                if let Some(len) = char_reference_len(&bytes[idx..]) {
+                    idx += len;
+                    continue;
+                }
+            }
+
+            let at_boundary =
+                !self.whole_words || !text[..idx].chars().next_back().is_some_and(is_word_char);
+
+            if let Some(len) = at_boundary.then(|| self.match_at(text, idx)).flatten() {
+                out.push_str(&text[last..idx]);
+                out.push('<');
+                out.push_str(self.tag);
+                out.push('>');
+                out.push_str(&text[idx..idx + len]);
+                out.push_str("</");
+                out.push_str(self.tag);
+                out.push('>');
+
+                idx += len;
+                last = idx;
+                continue;
+            }
+
+            idx += text[idx..].chars().next().map_or(1, char::len_utf8);
+        }
+
+        if last == 0 {
+            Cow::Borrowed(text)
+        } else {
+            out.push_str(&text[last..]);
+            Cow::Owned(out)
+        }
+    }
+
+    /// Returns the length of the longest term that matches at `idx`
+    fn match_at(&self, text: &str, idx: usize) -> Option<usize> {
+        let rest = &text.as_bytes()[idx..];
+
+        self.terms
+            .iter()
+            .map(|term| term.as_bytes())
+            .find(|term| {
+                let Some(candidate) = rest.get(..term.len()) else {
+                    return false;
+                };
+
+                let matches = if self.ignore_case {
+                    candidate.eq_ignore_ascii_case(term)
+                } else {
+                    candidate == *term
+                };
+
+                let end = idx + term.len();
+
+                matches
+                    && text.is_char_boundary(end)
+                    && !(0..candidate.len())
+                        .any(|i| candidate[i] == b'&' && char_reference_len(&rest[i..]).is_some())
+                    && (!self.whole_words || !text[end..].chars().next().is_some_and(is_word_char))
+            })
+            .map(<[u8]>::len)
+    }
+
+    fn write_node<'a>(
+        &self,
+        node: &Node<'a>,
+        parser: &Parser<'a>,
+        raw_text: bool,
+        out: &mut String,
+    ) {
+        match node {
+            Node::Tag(tag) => {
+                tag.write_start_tag(out);
+
+                if tag.is_void_element() {
+                    return;
+                }
+
+                let raw_text = raw_text
+                    || RAW_TEXT_ELEMENTS
+                        .iter()
+                        .any(|name| tag.name().as_bytes().eq_ignore_ascii_case(name.as_bytes()));
+
+                for child in tag.children().top().iter() {
+                    let child = child.get(parser).unwrap();
+                    self.write_node(child, parser, raw_text, out);
+                }
+
+                tag.write_end_tag(out);
+            }
+            Node::Raw(text) if !raw_text => out.push_str(&self.highlight_text(&text.as_utf8_str())),
+            Node::Raw(text)
+            | Node::Comment(text)
+            | Node::ProcessingInstruction(text)
+            | Node::CData(text)
+            | Node::Doctype(Doctype { _raw: text, .. }) => out.push_str(&text.as_utf8_str()),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the length of the character reference (`&amp;`, `&#39;`) at the start of `bytes`, if there is one
+fn char_reference_len(bytes: &[u8]) -> Option<usize> {
+    const MAX_LEN: usize = 32;
+
+    let end = bytes
+        .iter()
+        .take(MAX_LEN)
+        .skip(1)
+        .position(|&c| !(c.is_ascii_alphanumeric() || c == b'#'))?
+        + 1;
+
+    (end > 1 && bytes[end] == b';').then_some(end + 1)
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{InnerNodeHandle, Node, NodeHandle, Parser, VDom};
+
+/// A cache of the serialized HTML of every element, for documents that are serialized again and again after small edits
+///
+/// Serializing an element stores its markup and the markup of all elements below it.
+/// The next serialization reuses the stored markup of every element that has not been invalidated,
+/// so only the path from an edited element up to the root has to be rebuilt.
+///
+/// The cache cannot see mutations by itself: after changing a node, call [`HtmlCache::invalidate`] with the node,
+/// or with its parent if children were added or removed. The output matches [`VDom::outer_html`] as long as
+/// every mutation is followed by an invalidation.
+///
+/// # Example
+/// ```
+/// use tl::html_cache::HtmlCache;
+///
+/// let mut dom = tl::parse(r#"<div><p id="a">Hello</p><p>World</p></div>"#, Default::default()).unwrap();
+/// let mut cache = HtmlCache::new();
+///
+/// assert_eq!(cache.document_html(&dom), dom.outer_html());
+///
+/// let handle = dom.get_element_by_id("a").unwrap();
+/// let tag = handle.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+/// tag.attributes_mut().insert("class", Some("x"));
+///
+/// // only the edited `<p>` and the `<div>` are serialized again
+/// cache.invalidate(handle, dom.parser());
+/// assert_eq!(cache.document_html(&dom), dom.outer_html());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HtmlCache {
+    /// The markup of the cached elements by their index, so that handles with and without a document find the same entry
+    entries: HashMap<InnerNodeHandle, String>,
+}
+
+impl HtmlCache {
+    /// Creates a new, empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements whose markup is cached
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether no markup is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks whether the markup of the given node is cached
+    pub fn contains(&self, handle: NodeHandle) -> bool {
+        self.entries.contains_key(&handle.get_inner())
+    }
+
+    /// Removes all cached markup
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Removes the cached markup of the given node and all of its ancestors
+    ///
+    /// Ancestors are looked up in the structure of the document at parse time.
+    pub fn invalidate(&mut self, handle: NodeHandle, parser: &Parser<'_>) {
+        let mut current = Some(handle);
+
+        while let Some(handle) = current {
+            self.entries.remove(&handle.get_inner());
+            current = parser.parent_of(handle);
+        }
+    }
+
+    /// Returns the serialized HTML of a node, using and filling the cache
+    ///
+    /// This is equivalent to [`Node::outer_html`].
+    pub fn outer_html(&mut self, handle: NodeHandle, parser: &Parser<'_>) -> String {
+        let mut out = String::new();
+        self.write(handle, parser, &mut out);
+        out
+    }
+
+    /// Returns the serialized HTML of a document, using and filling the cache
+    ///
+    /// This is equivalent to [`VDom::outer_html`].
+    pub fn document_html(&mut self, dom: &VDom<'_>) -> String {
+        let parser = dom.parser();
+        let mut out = String::with_capacity(parser.stream.len());
+
+        for &handle in dom.children() {
+            self.write(handle, parser, &mut out);
+        }
+
+        out
+    }
+
+    fn write(&mut self, handle: NodeHandle, parser: &Parser<'_>, out: &mut String) {
+        let tag = match handle.get(parser) {
+            Some(Node::Tag(tag)) => tag,
+            Some(node) => return out.push_str(&node.outer_html(parser)),
+            None => return,
+        };
+
+        if let Some(html) = self.entries.get(&handle.get_inner()) {
+            return out.push_str(html);
+        }
+
+        let mut html = String::new();
+        tag.write_start_tag(&mut html);
+
+        if !tag.is_void_element() {
+            for &child in tag.children().top().iter() {
+                self.write(child, parser, &mut html);
+            }
+
+            tag.write_end_tag(&mut html);
+        }
+
+        out.push_str(&html);
+        self.entries.insert(handle.get_inner(), html);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::text::is_one_of;
+use crate::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// Elements that a browser places in `<head>` if they occur before any other content
+const METADATA_ELEMENTS: &[&str] = &[
+    "base", "link", "meta", "noscript", "script", "style", "template", "title",
+];
+
+/// A `<head>` or `<body>` element, as returned by [`VDom::head`] and [`VDom::body`]
+///
+/// If the document contains the element, this refers to it and its children.
+/// Otherwise, the element is implied like in a browser, and its children are the nodes that a browser would place in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpliedElement {
+    handle: Option<NodeHandle>,
+    children: Vec<NodeHandle>,
+}
+
+impl ImpliedElement {
+    /// Returns the element if it is present in the document, or `None` if it is implied
+    #[inline]
+    pub fn handle(&self) -> Option<NodeHandle> {
+        self.handle
+    }
+
+    /// Checks whether the element is missing from the document
+    #[inline]
+    pub fn is_implied(&self) -> bool {
+        self.handle.is_none()
+    }
+
+    /// Returns the children of the element
+    #[inline]
+    pub fn children(&self) -> &[NodeHandle] {
+        &self.children
+    }
+
+    /// Returns the text of all children
+    pub fn inner_text(&self, parser: &Parser<'_>) -> String {
+        self.children
+            .iter()
+            .filter_map(|handle| handle.get(parser))
+            .map(|node| node.inner_text(parser))
+            .collect()
+    }
+
+    /// Returns the markup of all children
+    pub fn inner_html(&self, parser: &Parser<'_>) -> String {
+        self.children
+            .iter()
+            .filter_map(|handle| handle.get(parser))
+            .map(|node| node.outer_html(parser))
+            .collect()
+    }
+}
+
+/// Returns the content of the document, i.e. the children of `<html>` and any nodes outside of it
+fn document_content(dom: &VDom<'_>) -> Vec<NodeHandle> {
+    let parser = dom.parser();
+    let mut content = Vec::new();
+
+    for &handle in dom.children() {
+        match handle.get(parser) {
+            Some(Node::Tag(tag)) if tag.name().as_bytes().eq_ignore_ascii_case(b"html") => {
+                content.extend(tag.children().top().iter().copied())
+            }
+            // the doctype is not part of the content
+            Some(Node::Doctype(_)) => {}
+            _ => content.push(handle),
+        }
+    }
+
+    content
+}
+
+/// Returns the first element with the given name in document order
+fn find_element<'d, 'a>(dom: &'d VDom<'a>, name: &[u8]) -> Option<(NodeHandle, &'d HTMLTag<'a>)> {
+    dom.nodes().iter().enumerate().find_map(|(idx, node)| {
+        let tag = node.as_tag()?;
+        tag.name()
+            .as_bytes()
+            .eq_ignore_ascii_case(name)
+            .then(|| (dom.parser().handle(idx as u32), tag))
+    })
+}
+
+fn is_whitespace(node: &Node<'_>) -> bool {
+    node.as_raw()
+        .is_some_and(|text| text.as_bytes().iter().all(u8::is_ascii_whitespace))
+}
+
+/// Returns the nodes that make up an implied `<head>`: the metadata elements before any other content,
+/// along with the comments and whitespace between them
+fn implied_head(dom: &VDom<'_>) -> Vec<NodeHandle> {
+    let parser = dom.parser();
+
+    let is_metadata = |node: &Node<'_>| {
+        node.as_tag()
+            .is_some_and(|tag| is_one_of(tag.name().as_bytes(), METADATA_ELEMENTS))
+    };
+
+    let mut nodes = document_content(dom)
+        .into_iter()
+        .skip_while(|handle| handle.get(parser).is_none_or(is_whitespace))
+        .take_while(|handle| {
+            handle.get(parser).is_some_and(|node| {
+                is_metadata(node) || is_whitespace(node) || node.as_comment().is_some()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let last_metadata = nodes
+        .iter()
+        .rposition(|handle| handle.get(parser).is_some_and(is_metadata));
+    nodes.truncate(last_metadata.map_or(0, |idx| idx + 1));
+
+    nodes
+}
+
+pub(crate) fn head(dom: &VDom<'_>) -> ImpliedElement {
+    match find_element(dom, b"head") {
+        Some((handle, tag)) => ImpliedElement {
+            handle: Some(handle),
+            children: tag.children().top().to_vec(),
+        },
+        None => ImpliedElement {
+            handle: None,
+            children: implied_head(dom),
+        },
+    }
+}
+
+pub(crate) fn body(dom: &VDom<'_>) -> ImpliedElement {
+    if let Some((handle, tag)) = find_element(dom, b"body") {
+        return ImpliedElement {
+            handle: Some(handle),
+            children: tag.children().top().to_vec(),
+        };
+    }
+
+    let parser = dom.parser();
+    let head = find_element(dom, b"head").map(|(handle, _)| handle);
+    let implied_head = match head {
+        Some(_) => Vec::new(),
+        None => implied_head(dom),
+    };
+
+    let children = document_content(dom)
+        .into_iter()
+        .filter(|&handle| Some(handle) != head && !implied_head.contains(&handle))
+        // whitespace before the first content is dropped, like in a browser
+        .skip_while(|handle| handle.get(parser).is_none_or(is_whitespace))
+        .collect();
+
+    ImpliedElement {
+        handle: None,
+        children,
+    }
+}
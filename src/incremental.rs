@@ -0,0 +1,556 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::inline::vec::InlineVec;
+use crate::parser::{Attributes, Doctype, ElementName, HTMLTag, Node, NodeHandle, Parser, Span};
+use crate::{Bytes, ParseError, VDom};
+
+/// A change of the source of a document, as reported by text editors, see [`VDom::reparse_range`]
+///
+/// The bytes `start..old_end` of the old source were replaced with the bytes `start..new_end` of the new source.
+/// All offsets are byte offsets.
+///
+/// # Example
+/// ```
+/// use tl::incremental::Edit;
+///
+/// // "<p>Hello</p>" -> "<p>Hello, world</p>"
+/// let edit = Edit::insert(8, ", world".len());
+/// assert_eq!(edit, Edit { start: 8, old_end: 8, new_end: 15 });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edit {
+    /// The offset of the first changed byte, which is the same in both sources
+    pub start: usize,
+    /// The end of the replaced bytes in the old source
+    pub old_end: usize,
+    /// The end of the inserted bytes in the new source
+    pub new_end: usize,
+}
+
+impl Edit {
+    /// Creates an edit that replaces a range of the old source with `len` bytes
+    pub fn replace(range: Range<usize>, len: usize) -> Self {
+        Self {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start + len,
+        }
+    }
+
+    /// Creates an edit that inserts `len` bytes at the given offset
+    pub fn insert(offset: usize, len: usize) -> Self {
+        Self::replace(offset..offset, len)
+    }
+
+    /// Creates an edit that removes a range of the old source
+    pub fn delete(range: Range<usize>) -> Self {
+        Self::replace(range, 0)
+    }
+}
+
+/// The nodes that changed when a document was parsed again, as returned by [`VDom::reparse_range`]
+///
+/// The replaced nodes of the old document form a contiguous range of handles, and so do the nodes that replaced them.
+/// All other nodes are the same as before, but the ones after the replaced nodes may have moved to another handle,
+/// which [`Changes::map`] returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changes {
+    parent: Option<NodeHandle>,
+    removed: Range<u32>,
+    inserted: Range<u32>,
+    /// The documents that the handles of the old and the new nodes belong to
+    documents: (u32, u32),
+}
+
+impl Changes {
+    /// Returns the element whose content was parsed again, or `None` if the whole document was parsed again
+    #[inline]
+    pub fn parent(&self) -> Option<NodeHandle> {
+        self.parent
+    }
+
+    /// Checks whether the whole document was parsed again, in which case every node was replaced
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.parent.is_none()
+    }
+
+    /// Returns the handles of the replaced nodes of the old document
+    pub fn removed(&self) -> impl Iterator<Item = NodeHandle> {
+        let document = self.documents.0;
+        self.removed
+            .clone()
+            .map(move |id| NodeHandle::in_document(id, document))
+    }
+
+    /// Returns the handles of the nodes of the new document that replaced them
+    pub fn inserted(&self) -> impl Iterator<Item = NodeHandle> {
+        let document = self.documents.1;
+        self.inserted
+            .clone()
+            .map(move |id| NodeHandle::in_document(id, document))
+    }
+
+    /// Returns the handle that a node of the old document has in the new document, or `None` if it was replaced
+    pub fn map(&self, handle: NodeHandle) -> Option<NodeHandle> {
+        let id = handle.get_inner();
+
+        let id = if id < self.removed.start {
+            id
+        } else if id >= self.removed.end {
+            id - self.removed.end + self.inserted.end
+        } else {
+            return None;
+        };
+
+        Some(NodeHandle::in_document(id, self.documents.1))
+    }
+}
+
+/// Parses the new source of a document after an edit, see [`VDom::reparse_range`]
+pub(crate) fn reparse_range<'b>(
+    dom: VDom<'_>,
+    edit: Edit,
+    input: &'b str,
+) -> Result<(VDom<'b>, Changes), ParseError> {
+    if let Some((parser, changes)) = reparse_element(dom.parser(), edit, input.as_bytes()) {
+        return Ok((VDom::from(parser), changes));
+    }
+
+    let removed = 0..dom.nodes().len() as u32;
+    let old_document = dom.parser().document();
+    let dom = dom.reparse(input)?;
+    let changes = Changes {
+        parent: None,
+        removed,
+        inserted: 0..dom.nodes().len() as u32,
+        documents: (old_document, dom.parser().document()),
+    };
+
+    Ok((dom, changes))
+}
+
+/// Parses the content of the innermost element that contains the edit again, and moves all other nodes to the new source
+///
+/// Returns `None` if the edit cannot be confined to the content of an element, e.g. because it adds an end tag or an
+/// unclosed comment, which changes how the rest of the document is parsed.
+fn reparse_element<'b>(
+    old: &Parser<'_>,
+    edit: Edit,
+    input: &'b [u8],
+) -> Option<(Parser<'b>, Changes)> {
+    let source = old.stream.data();
+    let Edit {
+        start,
+        old_end,
+        new_end,
+    } = edit;
+
+    // the edit must describe the difference between the sources, otherwise the unchanged nodes would be wrong
+    let is_valid = start <= old_end
+        && old_end <= source.len()
+        && start <= new_end
+        && new_end <= input.len()
+        && source.len() - old_end == input.len() - new_end
+        && source[..start] == input[..start]
+        && source[old_end..] == input[new_end..];
+
+    // elements that are left out by a node filter leave no trace of the state that the parser had
+    if !is_valid || input.len() > u32::MAX as usize || old.options.get_node_filter().is_some() {
+        return None;
+    }
+
+    let element = innermost_element(old, start, old_end)?;
+    let element_id = element.get_inner();
+    let tag = element.get(old)?.as_tag()?;
+    let removed = tag._descendants.clone();
+    let end_tag = tag._span.end_tag?;
+
+    // a doctype sets the version of the whole document
+    let nodes = old.tags.get(removed.start as usize..removed.end as usize)?;
+    if nodes.iter().any(|node| matches!(node, Node::Doctype(_))) {
+        return None;
+    }
+
+    let mut parser = Parser::new_bytes(input, old.options);
+    parser.version = old.version;
+
+    // handles of the old document do not work with the new one, even if they still point to the same node
+    let document = parser.document();
+    let rebase = Rebase {
+        source,
+        input,
+        edit,
+        document,
+    };
+
+    // the nodes up to the element keep their handles, and only its ancestors refer to nodes after it,
+    // which are fixed once the number of new nodes is known
+    for (index, node) in old.tags[..=element_id as usize].iter().enumerate() {
+        let mut node = rebase.node(node, |id| id)?;
+
+        if let Node::Tag(tag) = &mut node {
+            if index == element_id as usize {
+                tag._children = InlineVec::new();
+                tag._descendants = removed.start..removed.start;
+                tag._raw = input.get(tag._span.start_tag.range())?.into();
+                tag._span.end_tag = None;
+            }
+        }
+
+        parser.tags.push(node);
+    }
+
+    let move_prefix = |handle: NodeHandle| {
+        (handle.get_inner() <= element_id)
+            .then(|| NodeHandle::in_document(handle.get_inner(), document))
+    };
+    copy_tables(old, &mut parser, &rebase, move_prefix);
+    parser
+        .templates
+        .extend(old.templates.iter().copied().filter_map(move_prefix));
+
+    // the open elements at the start of the content are the element and its ancestors
+    let mut ancestor = Some(element);
+    while let Some(handle) = ancestor {
+        parser.stack.push(parser.handle(handle.get_inner()));
+        ancestor = old.parent_of(handle);
+    }
+    let element = parser.handle(element_id);
+    parser.stack.reverse();
+    let ancestors = parser.stack.clone();
+
+    let content_end = rebase.offset(end_tag.start());
+    parser.stream.idx = tag._span.start_tag.end();
+
+    while parser.stream.idx < content_end {
+        parser.parse_single()?;
+
+        if parser.stack.get(ancestors.len() - 1) != Some(&element) {
+            return None;
+        }
+    }
+
+    if parser.stream.idx != content_end || parser.stack != ancestors {
+        return None;
+    }
+
+    // the end tag closes the element just like before, so the rest of the document is parsed the same way
+    parser.parse_single()?;
+    if parser.stream.idx != rebase.offset(end_tag.end())
+        || parser.stack[..] != ancestors[..ancestors.len() - 1]
+        || parser.version != old.version
+    {
+        return None;
+    }
+
+    let inserted = removed.start..parser.tags.len() as u32;
+    let changes = Changes {
+        parent: Some(element),
+        removed: removed.clone(),
+        inserted,
+        documents: (old.document(), document),
+    };
+    let map = |id: u32| {
+        if id >= changes.removed.end {
+            id - changes.removed.end + changes.inserted.end
+        } else {
+            id
+        }
+    };
+
+    for &handle in &ancestors[..ancestors.len() - 1] {
+        let tag = parser.tags[handle.get_inner() as usize].as_tag_mut()?;
+        let mut children = InlineVec::new();
+        for child in tag._children.iter() {
+            children.push(NodeHandle::in_document(map(child.get_inner()), document));
+        }
+        tag._children = children;
+        tag._descendants.end = map(tag._descendants.end);
+    }
+
+    for node in old.tags.iter().skip(removed.end as usize) {
+        parser.tags.push(rebase.node(node, map)?);
+    }
+
+    let move_suffix = |handle: NodeHandle| {
+        (handle.get_inner() >= changes.removed.end)
+            .then(|| NodeHandle::in_document(map(handle.get_inner()), document))
+    };
+    copy_tables(old, &mut parser, &rebase, move_suffix);
+    parser
+        .templates
+        .extend(old.templates.iter().copied().filter_map(move_suffix));
+
+    parser.ast = old
+        .ast
+        .iter()
+        .map(|handle| parser.handle(map(handle.get_inner())))
+        .collect();
+    parser.stack.clear();
+    parser.stream.idx = input.len();
+
+    Some((parser, changes))
+}
+
+/// Returns the innermost element with an end tag whose content contains the given range of the source
+fn innermost_element(parser: &Parser<'_>, start: usize, end: usize) -> Option<NodeHandle> {
+    let mut found = None;
+    let mut siblings = parser.ast.as_slice();
+
+    'descend: loop {
+        for &handle in siblings {
+            let Some(tag) = handle.get(parser).and_then(Node::as_tag) else {
+                continue;
+            };
+            let content_start = tag._span.start_tag.end();
+
+            match tag._span.end_tag {
+                Some(end_tag) if content_start <= start && end <= end_tag.start() => {
+                    found = Some(handle);
+                }
+                // elements without an end tag contain the rest of the document
+                None if content_start <= start => {}
+                _ => continue,
+            }
+
+            siblings = tag._children.as_slice();
+            continue 'descend;
+        }
+
+        return found;
+    }
+}
+
+/// Copies the entries of the lookup tables of the old parser for the nodes that `map` returns a handle for
+fn copy_tables<'b>(
+    old: &Parser<'_>,
+    parser: &mut Parser<'b>,
+    rebase: &Rebase<'_, 'b>,
+    map: impl Fn(NodeHandle) -> Option<NodeHandle> + Copy,
+) {
+    // copied nodes keep the mutations of the old document, which the tables may not reflect
+    parser.tables_outdated |= old.tables_outdated;
+    parser.modified |= old.modified;
+
+    copy_table(&old.ids, &mut parser.ids, |id| rebase.key(id), map);
+    copy_table(&old.classes, &mut parser.classes, |c| rebase.key(c), map);
+    copy_table(
+        &old.tag_names,
+        &mut parser.tag_names,
+        |n| rebase.key(n),
+        map,
+    );
+    copy_table(
+        &old.attribute_values,
+        &mut parser.attribute_values,
+        |(name, value)| (*name, rebase.key(value)),
+        map,
+    );
+}
+
+fn copy_table<'o, K, L, V, W>(
+    from: &'o HashMap<L, W>,
+    into: &mut HashMap<K, V>,
+    key: impl Fn(&'o L) -> K,
+    map: impl Fn(NodeHandle) -> Option<NodeHandle>,
+) where
+    K: Hash + Eq,
+    V: Handles,
+    W: Handles,
+{
+    for (old_key, handles) in from {
+        let mut handles = handles.as_slice().iter().filter_map(|&handle| map(handle));
+
+        if let Some(first) = handles.next() {
+            let list = into.entry(key(old_key)).or_insert_with(V::empty);
+            list.push(first);
+            handles.for_each(|handle| list.push(handle));
+        }
+    }
+}
+
+/// The lists of handles in the lookup tables of a parser
+trait Handles {
+    fn empty() -> Self;
+    fn as_slice(&self) -> &[NodeHandle];
+    fn push(&mut self, handle: NodeHandle);
+}
+
+impl<const N: usize> Handles for InlineVec<NodeHandle, N> {
+    fn empty() -> Self {
+        InlineVec::new()
+    }
+
+    fn as_slice(&self) -> &[NodeHandle] {
+        InlineVec::as_slice(self)
+    }
+
+    fn push(&mut self, handle: NodeHandle) {
+        InlineVec::push(self, handle)
+    }
+}
+
+impl Handles for Vec<NodeHandle> {
+    fn empty() -> Self {
+        Vec::new()
+    }
+
+    fn as_slice(&self) -> &[NodeHandle] {
+        self
+    }
+
+    fn push(&mut self, handle: NodeHandle) {
+        Vec::push(self, handle)
+    }
+}
+
+/// Moves the data of nodes from the old source to the same bytes in the new source
+struct Rebase<'o, 'b> {
+    source: &'o [u8],
+    input: &'b [u8],
+    edit: Edit,
+    /// The new document, which the handles of the moved nodes belong to
+    document: u32,
+}
+
+impl<'o, 'b> Rebase<'o, 'b> {
+    /// Returns the offset in the new source of an offset after the edit in the old source
+    fn offset(&self, offset: usize) -> usize {
+        offset - self.edit.old_end + self.edit.new_end
+    }
+
+    /// Returns the range in the new source of a range of the old source that is before or after the edit,
+    /// or that contains all of it like the source of the elements around the edit
+    fn range(&self, range: Range<usize>) -> Option<Range<usize>> {
+        let Edit { start, old_end, .. } = self.edit;
+
+        if range.end <= start {
+            Some(range)
+        } else if range.start >= old_end {
+            Some(self.offset(range.start)..self.offset(range.end))
+        } else if range.start <= start && range.end >= old_end {
+            Some(range.start..self.offset(range.end))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the range of the old source that the given bytes borrow, or `None` if they do not borrow from it
+    fn source_range(&self, bytes: &Bytes<'_>) -> Option<Range<usize>> {
+        let borrowed = bytes.as_bytes_borrowed()?;
+        let offset = (borrowed.as_ptr() as usize).checked_sub(self.source.as_ptr() as usize)?;
+        let end = offset + borrowed.len();
+
+        (end <= self.source.len()).then_some(offset..end)
+    }
+
+    fn bytes(&self, bytes: &Bytes<'_>) -> Option<Bytes<'b>> {
+        if bytes.as_bytes().is_empty() {
+            return Some(Bytes::new());
+        }
+        if bytes.as_bytes_borrowed().is_none() {
+            return owned(bytes.as_bytes());
+        }
+
+        let range = self.range(self.source_range(bytes)?)?;
+        self.input.get(range).map(Bytes::from)
+    }
+
+    /// Like [`Rebase::bytes`], but keeps the content of the bytes, which are copied if they are part of the edit
+    fn key(&self, bytes: &Bytes<'_>) -> Bytes<'b> {
+        let Edit { start, old_end, .. } = self.edit;
+
+        self.source_range(bytes)
+            .filter(|range| range.end <= start || range.start >= old_end)
+            .and_then(|_| self.bytes(bytes))
+            .or_else(|| owned(bytes.as_bytes()))
+            .unwrap_or_default()
+    }
+
+    fn handle(&self, id: u32) -> NodeHandle {
+        NodeHandle::in_document(id, self.document)
+    }
+
+    fn span(&self, span: Span) -> Option<Span> {
+        let range = self.range(span.range())?;
+        Some(Span::new(range.start, range.end))
+    }
+
+    /// Moves a node to the new source, and changes the handles that it refers to with `map`
+    ///
+    /// Returns `None` for modified elements, whose source is outdated.
+    fn node(&self, node: &Node<'_>, map: impl Fn(u32) -> u32) -> Option<Node<'b>> {
+        Some(match node {
+            Node::Tag(tag) => Node::Tag(self.tag(tag, map)?),
+            Node::Raw(bytes) => Node::Raw(self.bytes(bytes)?),
+            Node::Comment(bytes) => Node::Comment(self.bytes(bytes)?),
+            Node::ProcessingInstruction(bytes) => Node::ProcessingInstruction(self.bytes(bytes)?),
+            Node::CData(bytes) => Node::CData(self.bytes(bytes)?),
+            Node::Doctype(doctype) => Node::Doctype(Doctype::parse(
+                self.bytes(doctype.raw())?.as_bytes_borrowed()?,
+            )),
+        })
+    }
+
+    fn tag(&self, tag: &HTMLTag<'_>, map: impl Fn(u32) -> u32) -> Option<HTMLTag<'b>> {
+        if tag._modified {
+            return None;
+        }
+
+        let mut attributes = Attributes::new();
+        for (key, value) in tag._attributes.raw.iter() {
+            let value = match value {
+                Some(value) => Some(self.bytes(value)?),
+                None => None,
+            };
+            attributes.raw.insert(self.bytes(key)?, value);
+        }
+        attributes.id = match &tag._attributes.id {
+            Some(id) => Some(self.bytes(id)?),
+            None => None,
+        };
+        attributes.class = match &tag._attributes.class {
+            Some(class) => Some(self.bytes(class)?),
+            None => None,
+        };
+
+        let mut children = InlineVec::new();
+        for child in tag._children.iter() {
+            children.push(self.handle(map(child.get_inner())));
+        }
+
+        let name = match &tag._name {
+            ElementName::Known(symbol) => ElementName::Known(*symbol),
+            ElementName::Other(name) => ElementName::Other(self.bytes(name)?),
+        };
+
+        let mut moved = HTMLTag::new(
+            name,
+            attributes,
+            children,
+            tag._parent
+                .map(|parent| self.handle(map(parent.get_inner()))),
+            self.bytes(&tag._raw)?,
+            self.span(tag._span.start_tag)?,
+        );
+        moved._span.end_tag = match tag._span.end_tag {
+            Some(end_tag) => Some(self.span(end_tag)?),
+            None => None,
+        };
+        moved._descendants = map(tag._descendants.start)..map(tag._descendants.end);
+        moved._namespace = tag._namespace;
+        moved._void = tag._void;
+
+        Some(moved)
+    }
+}
+
+/// Copies bytes into owned bytes, which do not borrow from any source
+fn owned<'b>(bytes: &[u8]) -> Option<Bytes<'b>> {
+    let mut owned = Bytes::new();
+    owned.set(bytes).ok()?;
+    Some(owned)
+}
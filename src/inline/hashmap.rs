@@ -3,9 +3,17 @@ use std::hash::Hash;
 use std::ptr;
 use std::{collections::HashMap, mem::MaybeUninit};
 
+/// The raw, partially initialized array that backs an inlined [`InlineHashMap`]
+pub type InlineArray<K, V, const N: usize> = [MaybeUninit<(K, V)>; N];
+
+/// The number of elements up to which a map that no longer fits inline is a vector that is searched linearly,
+/// before it becomes a `HashMap`
+pub const MAX_LINEAR_LEN: usize = 16;
+
 /// Similar to InlineVec, this structure will use an array
 /// if it is small enough to live on the stack, otherwise
-/// it allocates a HashMap on the heap
+/// it allocates a vector on the heap, and a HashMap once it
+/// has more than [`MAX_LINEAR_LEN`] elements
 ///
 /// Hashing can be slower than just iterating through an array
 /// if the array is small, which is where it makes most sense
@@ -27,6 +35,12 @@ where
         self.0.len()
     }
 
+    /// Checks whether this map is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns an iterator over the elements of this map
     ///
     /// This function boxes the returned iterator because it can be either of two:
@@ -37,11 +51,19 @@ where
         self.0.iter()
     }
 
+    /// Returns an iterator over mutable references to the values of this map
+    ///
+    /// Like [`InlineHashMap::iter()`], the returned iterator is boxed.
+    #[inline]
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut V> + '_> {
+        self.0.values_mut()
+    }
+
     /// If `self` is inlined, this returns the underlying raw parts that make up this `InlineHashMap`.
     ///
     /// Only the first `.1` elements are initialized.
     #[inline]
-    pub fn inline_parts_mut(&mut self) -> Option<(&mut [MaybeUninit<(K, V)>; N], usize)> {
+    pub fn inline_parts_mut(&mut self) -> Option<(&mut InlineArray<K, V, N>, usize)> {
         self.0.inline_parts_mut()
     }
 
@@ -61,6 +83,15 @@ where
         self.0.is_heap_allocated()
     }
 
+    /// Returns the approximate number of bytes that this map has allocated on the heap, which is zero while it is inlined
+    pub(crate) fn heap_size(&self) -> usize {
+        match &self.0 {
+            InlineHashMapInner::Heap(map) => super::table_size(map),
+            InlineHashMapInner::List(list) => list.capacity() * std::mem::size_of::<(K, V)>(),
+            InlineHashMapInner::Inline { .. } => 0,
+        }
+    }
+
     /// Inserts a new element into the map
     #[inline]
     pub fn insert(&mut self, key: K, value: V) {
@@ -97,6 +128,8 @@ enum InlineHashMapInner<K, V, const N: usize> {
         len: usize,
         data: [MaybeUninit<(K, V)>; N],
     },
+    /// Elements that do not fit inline anymore, but are still few enough to be searched linearly, in insertion order
+    List(Vec<(K, V)>),
     Heap(HashMap<K, V>),
 }
 
@@ -118,6 +151,7 @@ where
     fn clone(&self) -> Self {
         match self {
             Self::Heap(m) => Self::Heap(m.clone()),
+            Self::List(list) => Self::List(list.clone()),
             Self::Inline { len, data } => {
                 let mut new_data = super::uninit_array();
 
@@ -163,14 +197,26 @@ impl<K, V, const N: usize> InlineHashMapInner<K, V, N> {
             Self::Inline { len, data } => {
                 Box::new(unsafe { InlineHashMapIterator::new(data, *len) })
             }
+            Self::List(list) => Box::new(list.iter().map(|(key, value)| (key, value))),
             Self::Heap(h) => Box::new(h.iter()),
         }
     }
 
     #[inline]
-    pub fn inline_parts_mut(&mut self) -> Option<(&mut [MaybeUninit<(K, V)>; N], usize)> {
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut V> + '_> {
+        match self {
+            Self::Inline { len, data } => Box::new(
+                unsafe { InlineHashMapIteratorMut::new(data, *len) }.map(|(_, value)| value),
+            ),
+            Self::List(list) => Box::new(list.iter_mut().map(|(_, value)| value)),
+            Self::Heap(h) => Box::new(h.values_mut()),
+        }
+    }
+
+    #[inline]
+    pub fn inline_parts_mut(&mut self) -> Option<(&mut InlineArray<K, V, N>, usize)> {
         match self {
-            Self::Heap(_) => None,
+            Self::List(_) | Self::Heap(_) => None,
             Self::Inline { len, data } => Some((data, *len)),
         }
     }
@@ -183,10 +229,11 @@ impl<K, V, const N: usize> InlineHashMapInner<K, V, N> {
     {
         match &self {
             InlineHashMapInner::Heap(m) => m.clone(),
+            InlineHashMapInner::List(list) => list.iter().cloned().collect(),
             InlineHashMapInner::Inline { len, data } => {
                 let mut new_data = HashMap::with_capacity(*len);
 
-                let iter = data.into_iter().take(*len);
+                let iter = data.iter().take(*len);
 
                 for element in iter {
                     let element = unsafe { &*element.as_ptr() };
@@ -203,13 +250,14 @@ impl<K, V, const N: usize> InlineHashMapInner<K, V, N> {
     pub fn len(&self) -> usize {
         match self {
             Self::Inline { len, .. } => *len,
+            Self::List(list) => list.len(),
             Self::Heap(map) => map.len(),
         }
     }
 
     #[inline]
     pub fn is_heap_allocated(&self) -> bool {
-        matches!(self, Self::Heap(_))
+        matches!(self, Self::List(_) | Self::Heap(_))
     }
 }
 
@@ -221,6 +269,10 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
                     .find(|(key, _)| key.eq(&k))
                     .map(|(_, value)| value)
             },
+            Self::List(list) => list
+                .iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, value)| value),
             Self::Heap(map) => map.get(k),
         }
     }
@@ -232,6 +284,10 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
                     .find(|(key, _)| key.eq(k))
                     .map(|(_, value)| value)
             },
+            Self::List(list) => list
+                .iter_mut()
+                .find(|(key, _)| key == k)
+                .map(|(_, value)| value),
             Self::Heap(map) => map.get_mut(k),
         }
     }
@@ -256,13 +312,33 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
 
                 Some(unsafe { element.assume_init().1 })
             }
+            Self::List(list) => {
+                let idx = list.iter().position(|(k, _)| k == key)?;
+                Some(list.remove(idx).1)
+            }
             Self::Heap(h) => h.remove(key),
         }
     }
 
     pub fn insert(&mut self, k: K, v: V) {
+        // like a `HashMap`, an existing key keeps its entry and only gets the new value
+        if let Some(value) = self.get_mut(&k) {
+            *value = v;
+            return;
+        }
+
         let (array, len) = match self {
             Self::Inline { data, len } => (data, len),
+            Self::List(list) if list.len() < MAX_LINEAR_LEN => {
+                list.push((k, v));
+                return;
+            }
+            Self::List(list) => {
+                let mut map = std::mem::take(list).into_iter().collect::<HashMap<_, _>>();
+                map.insert(k, v);
+                *self = Self::Heap(map);
+                return;
+            }
             Self::Heap(map) => {
                 map.insert(k, v);
                 return;
@@ -270,23 +346,20 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
         };
 
         if *len >= N {
-            let mut map = HashMap::with_capacity(*len);
+            let mut list = Vec::with_capacity((*len * 2).max(4));
 
             // move old elements to heap
             for element in array.iter_mut().take(*len) {
                 let element = std::mem::replace(element, MaybeUninit::uninit());
-                let (key, value) = unsafe { element.assume_init() };
-
-                map.insert(key, value);
+                list.push(unsafe { element.assume_init() });
             }
 
             // insert new element
-            map.insert(k, v);
-            let new_heap = Self::Heap(map);
+            list.push((k, v));
+            let new_heap = Self::List(list);
 
             // do not call the destructor!
             unsafe { ptr::write(self, new_heap) };
-            return;
         } else {
             array[*len].write((k, v));
             *len += 1;
@@ -298,6 +371,7 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
             Self::Inline { data, len } => unsafe {
                 InlineHashMapIterator::new(data, *len).any(|(key, _)| key.eq(k))
             },
+            Self::List(list) => list.iter().any(|(key, _)| key == k),
             Self::Heap(map) => map.contains_key(k),
         }
     }
@@ -1,4 +1,5 @@
-use std::mem::MaybeUninit;
+use std::collections::HashMap;
+use std::mem::{self, MaybeUninit};
 
 /// Inline HashMap
 pub mod hashmap;
@@ -9,3 +10,10 @@ fn uninit_array<T, const N: usize>() -> [MaybeUninit<T>; N] {
     // SAFETY: an array of MaybeUninits is allowed to be entirely uninit
     unsafe { MaybeUninit::uninit().assume_init() }
 }
+
+/// Returns the approximate number of bytes that a hash map has allocated on the heap
+///
+/// The table of a hash map stores every entry in a bucket, along with one control byte per bucket.
+pub(crate) fn table_size<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * (mem::size_of::<(K, V)>() + 1)
+}
@@ -20,12 +20,26 @@ impl<T, const N: usize> InlineVec<T, N> {
         self.0.len()
     }
 
+    /// Checks whether this vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Checks whether this vector is allocated on the heap
     #[inline]
     pub fn is_heap_allocated(&self) -> bool {
         self.0.is_heap_allocated()
     }
 
+    /// Returns the number of bytes that this vector has allocated on the heap, which is zero while it is inlined
+    pub(crate) fn heap_size(&self) -> usize {
+        match &self.0 {
+            InlineVecInner::Heap(vec) => vec.capacity() * std::mem::size_of::<T>(),
+            InlineVecInner::Inline { .. } => 0,
+        }
+    }
+
     /// If `self` is inlined, this returns the underlying raw parts that make up this `InlineVec`.
     ///
     /// Only the first `.1` elements are initialized.
@@ -70,6 +84,15 @@ impl<T, const N: usize> InlineVec<T, N> {
         self.0.remove(index)
     }
 
+    /// Inserts an element at a given index, shifting all elements after it to the right
+    ///
+    /// # Panics
+    /// Just like `Vec::insert`, this method will panic if the index is greater than the length of the vector.
+    #[inline]
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.0.insert(index, value)
+    }
+
     /// Returns an iterator over the elements of this vector
     #[inline]
     pub fn iter(&self) -> InlineVecIter<'_, T, N> {
@@ -144,6 +167,15 @@ impl<T, const N: usize> InlineVecInner<T, N> {
         }
     }
 
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match self {
+            Self::Heap(v) => v.as_mut_slice(),
+            Self::Inline { len, data } => unsafe {
+                std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut T, *len)
+            },
+        }
+    }
+
     #[inline]
     pub fn inline_parts_mut(&mut self) -> Option<(&mut [MaybeUninit<T>; N], usize)> {
         match self {
@@ -161,7 +193,7 @@ impl<T, const N: usize> InlineVecInner<T, N> {
             InlineVecInner::Inline { len, data } => {
                 let mut new_data = Vec::with_capacity(*len);
 
-                let iter = data.into_iter().take(*len);
+                let iter = data.iter().take(*len);
 
                 for element in iter {
                     new_data.push(unsafe { T::clone(&*element.as_ptr()) });
@@ -267,6 +299,14 @@ impl<T, const N: usize> InlineVecInner<T, N> {
         }
     }
 
+    pub fn insert(&mut self, idx: usize, value: T) {
+        assert!(idx <= self.len());
+
+        // push the new element to the end and rotate it into place
+        self.push(value);
+        self.as_mut_slice()[idx..].rotate_right(1);
+    }
+
     #[inline]
     pub fn is_heap_allocated(&self) -> bool {
         matches!(self, Self::Heap(_))
@@ -435,7 +475,7 @@ mod tests {
         assert_eq!(x.as_slice(), &[0]);
 
         assert_eq!(x.remove(0), 0);
-        assert_eq!(x.as_slice(), &[]);
+        assert_eq!(x.as_slice(), &[0; 0]);
         assert!(!x.is_heap_allocated());
 
         // trigger heap allocation
@@ -449,6 +489,23 @@ mod tests {
         assert_eq!(x.remove(0), 0);
     }
 
+    #[test]
+    fn inlinevec_insert() {
+        let mut x = InlineVecInner::<usize, 4>::new();
+        x.insert(0, 4);
+        x.insert(0, 0);
+        x.insert(1, 2);
+        assert_eq!(x.as_slice(), &[0, 2, 4]);
+
+        x.insert(3, 6);
+        assert!(!x.is_heap_allocated());
+
+        // trigger heap allocation
+        x.insert(2, 3);
+        assert!(x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &[0, 2, 3, 4, 6]);
+    }
+
     #[test]
     fn inlinevec_remove_heap() {
         let mut x = InlineVecInner::<String, 4>::new();
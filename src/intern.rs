@@ -0,0 +1,184 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use crate::{Bytes, Node, VDom};
+
+/// Statistics about repeated attribute values and text nodes in a DOM, as returned by [`stats`]
+///
+/// Listing pages and other generated markup often repeat the same strings many times,
+/// such as class lists, link targets or labels like "Add to cart".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct InternStats {
+    /// The number of attribute values and text nodes
+    pub values: usize,
+    /// The number of distinct attribute values and text nodes
+    pub unique_values: usize,
+    /// The total length of all attribute values and text nodes in bytes
+    pub bytes: usize,
+    /// The total length of all distinct attribute values and text nodes in bytes
+    pub unique_bytes: usize,
+}
+
+impl InternStats {
+    /// Returns the number of bytes that are taken up by repeated values, i.e. values that have already occurred before
+    #[inline]
+    pub fn duplicate_bytes(&self) -> usize {
+        self.bytes - self.unique_bytes
+    }
+}
+
+/// Counts repeated attribute values and text nodes in the given DOM
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(
+///     r#"<a class="item">Buy</a><a class="item">Buy</a><a class="sold">Sold</a>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let stats = tl::intern::stats(&dom);
+/// assert_eq!(stats.values, 6);
+/// assert_eq!(stats.unique_values, 4);
+/// assert_eq!(stats.duplicate_bytes(), "item".len() + "Buy".len());
+/// ```
+pub fn stats(dom: &VDom<'_>) -> InternStats {
+    let mut seen = HashSet::new();
+    let mut stats = InternStats::default();
+
+    for node in dom.nodes() {
+        for_each_value(node, |bytes| {
+            let bytes = bytes.as_bytes();
+
+            stats.values += 1;
+            stats.bytes += bytes.len();
+
+            if seen.insert(bytes) {
+                stats.unique_values += 1;
+                stats.unique_bytes += bytes.len();
+            }
+        });
+    }
+
+    stats
+}
+
+/// Storage for deduplicated strings, which allows DOMs to share a single copy of repeated attribute values and text nodes
+///
+/// Values that point into the source string do not take up any additional memory.
+/// However, once a value was replaced with owned data (i.e. through [`Bytes::set`]), every occurrence has its own allocation.
+/// [`Interner::dedup`] moves these values into the interner, where each distinct string is only stored once.
+///
+/// The interner must outlive all DOMs that were deduplicated with it.
+///
+/// # Example
+/// ```
+/// use tl::intern::Interner;
+///
+/// let interner = Interner::new();
+/// let mut dom = tl::parse(r#"<a href="/a">a</a><a href="/b">b</a>"#, Default::default()).unwrap();
+///
+/// for tag in dom.nodes_mut().iter_mut().filter_map(|n| n.as_tag_mut()) {
+///     let href = tag.attributes_mut().get_mut("href").flatten().unwrap();
+///     href.set("https://example.com/").unwrap();
+/// }
+///
+/// assert_eq!(interner.dedup(&mut dom), "https://example.com/".len());
+/// assert_eq!(interner.len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct Interner {
+    /// The contents of a box never move, so references to them remain valid even if the set grows
+    strings: RefCell<HashSet<Box<[u8]>>>,
+}
+
+impl Interner {
+    /// Creates a new, empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct strings stored in this interner
+    pub fn len(&self) -> usize {
+        self.strings.borrow().len()
+    }
+
+    /// Checks whether this interner is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the shared copy of the given string, storing it first if it is not already present
+    pub fn intern(&self, bytes: &[u8]) -> &[u8] {
+        let mut strings = self.strings.borrow_mut();
+
+        let ptr: *const [u8] = match strings.get(bytes) {
+            Some(shared) => &**shared,
+            None => {
+                let shared: Box<[u8]> = bytes.into();
+                let ptr: *const [u8] = &*shared;
+                strings.insert(shared);
+                ptr
+            }
+        };
+
+        // SAFETY: strings are never removed from the set, and the data of a box does not move when the set is resized,
+        // so the returned reference is valid for as long as `self` is
+        unsafe { &*ptr }
+    }
+
+    /// Replaces all owned attribute values and text nodes of the given DOM with references to shared copies, and returns the number of freed bytes
+    ///
+    /// Values that point into the source string are left untouched.
+    /// See [`stats`] for finding out how many values are repeated in a DOM.
+    pub fn dedup<'a>(&'a self, dom: &mut VDom<'a>) -> usize {
+        let mut freed = 0;
+
+        for node in dom.nodes_mut() {
+            for_each_value_mut(node, |bytes| {
+                if bytes.as_bytes_borrowed().is_some() {
+                    return;
+                }
+
+                let len = self.len();
+                let shared = self.intern(bytes.as_bytes());
+
+                // the first occurrence only moves into the interner, every other one is freed
+                if self.len() == len {
+                    freed += shared.len();
+                }
+
+                *bytes = Bytes::from(shared);
+            });
+        }
+
+        freed
+    }
+}
+
+fn for_each_value<'n, 'a>(node: &'n Node<'a>, mut f: impl FnMut(&'n Bytes<'a>)) {
+    match node {
+        Node::Tag(tag) => {
+            let attributes = &tag._attributes;
+
+            attributes.id.iter().for_each(&mut f);
+            attributes.class.iter().for_each(&mut f);
+            attributes.raw.iter().flat_map(|(_, v)| v).for_each(f);
+        }
+        Node::Raw(text) => f(text),
+        Node::Comment(_) | Node::ProcessingInstruction(_) | Node::CData(_) | Node::Doctype(_) => {}
+    }
+}
+
+fn for_each_value_mut<'a>(node: &mut Node<'a>, mut f: impl FnMut(&mut Bytes<'a>)) {
+    match node {
+        Node::Tag(tag) => {
+            let attributes = &mut tag._attributes;
+
+            attributes.id.iter_mut().for_each(&mut f);
+            attributes.class.iter_mut().for_each(&mut f);
+            attributes.raw.values_mut().flatten().for_each(f);
+        }
+        Node::Raw(text) => f(text),
+        Node::Comment(_) | Node::ProcessingInstruction(_) | Node::CData(_) | Node::Doctype(_) => {}
+    }
+}
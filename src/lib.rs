@@ -2,19 +2,102 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
+/// Basic accessibility checks
+pub mod a11y;
 mod bytes;
+/// Compact binary DOM cache format
+pub mod cache;
+/// Inlining of CSS rules into `style` attributes
+pub mod css_inline;
+/// Indented tree output for debugging
+pub mod debug;
+/// Tree diffs of two documents
+pub mod diff;
 /// Errors that occur throughout the crate
 pub mod errors;
+/// Single-string extraction rules
+pub mod eval;
+/// Main content extraction
+pub mod extract;
+/// Structural fingerprints of documents
+pub mod fingerprint;
+/// Form extraction and serialization
+pub mod forms;
+/// Owned copies of subtrees
+pub mod fragment;
+/// Inline frames and the documents embedded in their `srcdoc` attribute
+pub mod frames;
+/// Search term highlighting
+pub mod highlight;
+/// Caching of serialized HTML per element
+pub mod html_cache;
+mod implied;
+/// Incremental parsing of edited documents
+pub mod incremental;
 /// Inline data structures
 pub mod inline;
+/// Statistics and deduplication of repeated strings
+pub mod intern;
+/// Link extraction and URL resolution
+pub mod links;
+/// Lint rules for HTML markup
+pub mod lint;
+/// Conversion of documents to Markdown
+pub mod markdown;
+/// Document metadata extraction
+pub mod meta;
+/// Parsers for `srcset` and `style` attribute values
+pub mod microsyntax;
+/// Document outlines built from headings
+pub mod outline;
 mod parser;
+/// Memoization of query selector results
+pub mod query_cache;
 /// Query selector API
 pub mod queryselector;
+/// Selector-driven redaction
+pub mod redact;
+/// Script and stylesheet extraction
+pub mod resources;
+/// Streaming rewriting with selector handlers
+pub mod rewrite;
+mod roundtrip;
+/// Allowlist-based HTML sanitizing
+pub mod sanitize;
+/// Text search over text nodes, and regex search over text and attributes
+pub mod search;
+#[cfg(feature = "serde")]
+mod serde_impl;
+/// Document statistics for corpus analytics
+pub mod stats;
 mod stream;
+/// Attribute stripping
+pub mod strip;
+/// Table extraction
+pub mod table;
 #[cfg(test)]
 mod tests;
+/// Text extraction options
+pub mod text;
+/// Mapping of text offsets to DOM positions
+pub mod text_index;
+mod traverse;
+/// Extraction of structured records with selector-based rules
+pub mod unmarshal;
 mod util;
 mod vdom;
+mod visit;
+/// WebAssembly bindings
+#[cfg(feature = "wasm")]
+pub mod wasm;
+/// XPath queries
+pub mod xpath;
+
+#[doc(hidden)]
+#[cfg(feature = "__INTERNALS_DO_NOT_USE")]
+pub mod conformance;
+#[cfg(all(test, not(feature = "__INTERNALS_DO_NOT_USE")))]
+mod conformance;
 
 #[doc(hidden)]
 #[cfg(feature = "__INTERNALS_DO_NOT_USE")]
@@ -23,10 +106,22 @@ pub mod simd;
 mod simd;
 
 pub use bytes::Bytes;
+pub use diff::diff;
 pub use errors::ParseError;
+pub use implied::ImpliedElement;
 pub use parser::*;
 use queryselector::Selector;
-pub use vdom::{VDom, VDomGuard};
+pub use redact::redact;
+pub use rewrite::rewrite;
+pub use sanitize::sanitize;
+pub use strip::strip_attributes;
+pub use traverse::{Edge, Traverse};
+#[cfg(feature = "http")]
+pub use vdom::BodyVDomGuard;
+#[cfg(feature = "mmap")]
+pub use vdom::MappedVDomGuard;
+pub use vdom::{SharedDom, VDom, VDomGuard};
+pub use visit::{DomVisitor, Visit};
 
 /// Parses the given input string
 ///
@@ -92,6 +187,67 @@ pub fn parse_query_selector(input: &str) -> Option<Selector<'_>> {
     Some(selector)
 }
 
+/// Parses a query selector like [`parse_query_selector`], but resolves namespace prefixes like `svg|` with the given map
+///
+/// Returns `None` if the selector is invalid or uses a prefix that is not declared.
+/// See [`VDom::query_selector_ns`] for the namespace syntax.
+///
+/// # Example
+/// ```
+/// # use tl::queryselector::{selector::Selector, Namespaces};
+/// let namespaces = Namespaces::standard();
+/// let selector = tl::parse_query_selector_ns("svg|circle", &namespaces);
+///
+/// match selector {
+///     Some(Selector::And(left, right)) => {
+///         assert!(matches!(&*left, Selector::Namespace(tl::Namespace::Svg)));
+///         assert!(matches!(&*right, Selector::Tag(b"circle")));
+///     },
+///     _ => unreachable!()
+/// }
+/// ```
+pub fn parse_query_selector_ns<'a>(
+    input: &'a str,
+    namespaces: &'a queryselector::Namespaces,
+) -> Option<Selector<'a>> {
+    queryselector::Parser::with_namespaces(input.as_bytes(), namespaces).selector()
+}
+
+/// Parses an untrusted query selector, rejecting it if it exceeds the given limits
+///
+/// # Errors
+/// See [`queryselector::SelectorLimits`] and [`errors::SelectorError`]
+///
+/// # Example
+/// ```
+/// use tl::errors::SelectorError;
+/// use tl::queryselector::SelectorLimits;
+///
+/// let limits = SelectorLimits::new().max_length(16).max_nesting(1);
+///
+/// assert!(tl::parse_query_selector_with_limits(":is(a, b) > p", limits).is_ok());
+/// assert_eq!(
+///     tl::parse_query_selector_with_limits(":is(:is(a)) > p", limits).unwrap_err(),
+///     SelectorError::TooDeep { max: 1 }
+/// );
+/// assert_eq!(
+///     tl::parse_query_selector_with_limits("div.a.very.long.selector", limits).unwrap_err(),
+///     SelectorError::TooLong { length: 24, max: 16 }
+/// );
+/// assert_eq!(
+///     tl::parse_query_selector_with_limits("p >", limits).unwrap_err(),
+///     SelectorError::Invalid
+/// );
+/// ```
+pub fn parse_query_selector_with_limits(
+    input: &str,
+    limits: queryselector::SelectorLimits,
+) -> Result<Selector<'_>, errors::SelectorError> {
+    queryselector::Parser::new(input.as_bytes())
+        .limits(limits)
+        .try_selector()
+}
+
 /// Parses the given input string and returns an owned, RAII guarded DOM
 ///
 /// # Errors
@@ -105,3 +261,181 @@ pub fn parse_query_selector(input: &str) -> Option<Selector<'_>> {
 pub unsafe fn parse_owned(input: String, options: ParserOptions) -> Result<VDomGuard, ParseError> {
     VDomGuard::parse(input, options)
 }
+
+/// Memory-maps the file at the given path and parses it, without reading it into a string first
+///
+/// The returned guard keeps the file mapped for as long as the DOM is used, so no copy of the file is made
+/// and the operating system only loads the pages that the parser touches. This is useful for processing
+/// large files or many files of a corpus. The file is parsed as bytes like with [`parse_bytes`], so it need not be valid UTF-8.
+///
+/// This function is only available with the `mmap` feature.
+///
+/// # Errors
+/// Returns [`ReadError::Io`](errors::ReadError::Io) if the file cannot be opened or mapped,
+/// and [`ReadError::Parse`](errors::ReadError::Parse) for the errors of [`parse`].
+///
+/// # Safety
+/// The file must not be modified or truncated, by this or any other process, while the returned guard is alive.
+/// Doing so changes or unmaps the memory that the DOM references, which is undefined behavior.
+///
+/// # Example
+/// ```
+/// let path = std::env::temp_dir().join("tl-parse-file-example.html");
+/// std::fs::write(&path, "<title>Hello</title>").unwrap();
+///
+/// let guard = unsafe { tl::parse_file(&path, Default::default()) }.unwrap();
+/// let dom = guard.get_ref();
+/// let title = dom.query_selector("title").unwrap().next().unwrap();
+/// assert_eq!(title.get(dom.parser()).unwrap().inner_text(dom.parser()), "Hello");
+/// # drop(guard);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "mmap")]
+pub unsafe fn parse_file(
+    path: impl AsRef<std::path::Path>,
+    options: ParserOptions,
+) -> Result<MappedVDomGuard, errors::ReadError> {
+    MappedVDomGuard::parse(path.as_ref(), options)
+}
+
+/// Reads a document that may be gzip or zlib compressed, decompresses it while reading and parses it
+///
+/// The compression is detected from the first bytes: gzip data (i.e. `.html.gz` files and WARC payloads) starts with
+/// the bytes `1f 8b`, and zlib data (i.e. responses with `Content-Encoding: deflate`) with a valid zlib header.
+/// Gzip data with several members, like the records of a `.warc.gz` file, is decompressed as a whole.
+/// Everything else is read as is. Invalid UTF-8 in the document is replaced with `U+FFFD`.
+///
+/// This function is only available with the `compression` feature.
+///
+/// # Errors
+/// Returns [`ReadError::Io`](errors::ReadError::Io) if reading fails or the compressed data is corrupt,
+/// and [`ReadError::Parse`](errors::ReadError::Parse) for the errors of [`parse`].
+///
+/// # Example
+/// ```
+/// use std::io::Write;
+///
+/// let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+/// encoder.write_all(b"<title>Hello</title>").unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// let guard = tl::parse_compressed(compressed.as_slice(), Default::default()).unwrap();
+/// let dom = guard.get_ref();
+/// assert_eq!(dom.outer_html(), "<title>Hello</title>");
+/// ```
+#[cfg(feature = "compression")]
+pub fn parse_compressed(
+    reader: impl std::io::Read,
+    options: ParserOptions,
+) -> Result<VDomGuard, errors::ReadError> {
+    use flate2::bufread::{MultiGzDecoder, ZlibDecoder};
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(reader);
+
+    match *reader.fill_buf()? {
+        [0x1f, 0x8b, ..] => from_reader(MultiGzDecoder::new(reader), options),
+        // the deflate method with a window of at most 32K, no preset dictionary and a valid check value
+        [cmf, flg, ..]
+            if cmf & 0x0f == 8
+                && cmf >> 4 <= 7
+                && flg & 0x20 == 0
+                && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0 =>
+        {
+            from_reader(ZlibDecoder::new(reader), options)
+        }
+        _ => from_reader(reader, options),
+    }
+}
+
+/// Reads a document to the end and parses it, e.g. from a file or the body of a blocking HTTP response
+///
+/// The bytes are read into a buffer that is owned by the returned guard, so no intermediate `String` is needed.
+/// Invalid UTF-8 in the document is replaced with `U+FFFD`; no other character encodings are decoded.
+///
+/// # Errors
+/// Returns [`ReadError::Io`](errors::ReadError::Io) if reading fails,
+/// and [`ReadError::Parse`](errors::ReadError::Parse) for the errors of [`parse`].
+///
+/// # Example
+/// ```
+/// let body: &[u8] = b"<p>Hello</p>";
+///
+/// let guard = tl::from_reader(body, Default::default()).unwrap();
+/// assert_eq!(guard.get_ref().outer_html(), "<p>Hello</p>");
+/// ```
+pub fn from_reader(
+    mut reader: impl std::io::Read,
+    options: ParserOptions,
+) -> Result<VDomGuard, errors::ReadError> {
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+
+    let input = String::from_utf8(input)
+        .unwrap_or_else(|error| String::from_utf8_lossy(error.as_bytes()).into_owned());
+
+    Ok(VDomGuard::parse(input, options)?)
+}
+
+/// Parses the body of an HTTP response, as returned by [reqwest](https://docs.rs/reqwest) and [hyper](https://docs.rs/hyper),
+/// without copying it
+///
+/// [`bytes::Bytes`](::bytes::Bytes) is reference counted, so the returned guard shares the body with the caller
+/// and keeps it alive while the DOM is in use. The body is parsed as bytes like with [`parse_bytes`], so it need not be valid UTF-8.
+/// The charset of the `Content-Type` header is not applied. It can be read with
+/// [`MimeType::parse`](links::MimeType::parse) and compared to [`VDom::detected_charset`] to find documents that need decoding.
+///
+/// This function is only available with the `http` feature.
+///
+/// # Errors
+/// See [parse]
+///
+/// # Example
+/// ```
+/// let body = bytes::Bytes::from_static(b"<p>Hello</p>");
+///
+/// let guard = tl::parse_body(body, Default::default()).unwrap();
+/// assert_eq!(guard.get_ref().outer_html(), "<p>Hello</p>");
+/// ```
+#[cfg(feature = "http")]
+pub fn parse_body(
+    body: ::bytes::Bytes,
+    options: ParserOptions,
+) -> Result<BodyVDomGuard, ParseError> {
+    BodyVDomGuard::parse(body, options)
+}
+
+/// Parses a batch of documents in parallel on the global [rayon](https://docs.rs/rayon) thread pool and returns an owned DOM
+/// or the error for each of them, in the order of the inputs
+///
+/// This is equivalent to calling [`parse_owned`] for every input, but spreads the work across all threads.
+/// Parsing is independent for each document, so throughput scales with the number of cores for large batches.
+/// To use a thread pool with a different number of threads, call this within [`rayon::ThreadPool::install`].
+///
+/// This function is only available with the `rayon` feature.
+///
+/// # Example
+/// ```
+/// let pages = vec![
+///     "<title>First</title>".to_string(),
+///     "<title>Second</title>".to_string(),
+/// ];
+///
+/// let doms = tl::parse_many(pages, Default::default());
+/// let dom = doms[1].as_ref().unwrap().get_ref();
+/// let title = dom.query_selector("title").unwrap().next().unwrap();
+///
+/// assert_eq!(title.get(dom.parser()).unwrap().inner_text(dom.parser()), "Second");
+/// ```
+#[cfg(feature = "rayon")]
+pub fn parse_many(
+    inputs: Vec<String>,
+    options: ParserOptions,
+) -> Vec<Result<VDomGuard, ParseError>> {
+    use rayon::prelude::*;
+
+    inputs
+        .into_par_iter()
+        .map(|input| VDomGuard::parse(input, options))
+        .collect()
+}
@@ -0,0 +1,752 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::text::{is_one_of, TextOptions, HEADING_ELEMENTS};
+use crate::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// The MIME types of feeds that are announced with `<link rel="alternate">`
+const FEED_MIME_TYPES: &[&str] = &[
+    "application/atom+xml",
+    "application/feed+json",
+    "application/rdf+xml",
+    "application/rss+xml",
+];
+
+/// The schemes of URLs in which backslashes are slashes, including the `:`
+const SPECIAL_SCHEMES: &[&str] = &["file:", "ftp:", "http:", "https:", "ws:", "wss:"];
+
+/// The kind of element a [`Link`] was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// `<a href>` or `<area href>`
+    Anchor,
+    /// `<img src>` or a candidate of `<img srcset>`
+    Image,
+    /// `<link href>`, like stylesheets, icons or alternate versions
+    Resource,
+    /// `<script src>`
+    Script,
+    /// `<iframe src>`
+    Frame,
+}
+
+/// A URL referenced by an element, as returned by [`VDom::links`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Link {
+    /// The URL, resolved against the base URL if there is one
+    pub url: String,
+    /// The value of the `rel` attribute of the element, like `stylesheet` or `nofollow`
+    pub rel: Option<String>,
+    /// The kind of element the URL was found in
+    pub kind: LinkKind,
+    /// The element the URL was found in
+    pub node: NodeHandle,
+}
+
+/// An `<a href>` or `<area href>` with its context, as returned by [`VDom::anchors`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Anchor {
+    /// The URL, resolved against the base URL if there is one
+    pub url: String,
+    /// The visible text of the link with collapsed whitespace. Links without text use their `alt` attribute,
+    /// which `<area>` elements have, or the `alt` text of an image inside of them.
+    pub text: String,
+    /// The tokens of the `rel` attribute in lowercase, like `nofollow` or `sponsored`
+    pub rel: Vec<String>,
+    /// The text of the closest heading that starts before the link, i.e. the heading of the section it is in
+    pub heading: Option<String>,
+    /// The element of the link
+    pub node: NodeHandle,
+}
+
+impl Anchor {
+    /// Checks whether the `rel` attribute contains the given token, ignoring case
+    pub fn has_rel(&self, token: &str) -> bool {
+        self.rel.iter().any(|rel| rel.eq_ignore_ascii_case(token))
+    }
+}
+
+/// A parsed MIME type, like `application/rss+xml; charset=utf-8`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MimeType {
+    /// The type in lowercase, like `application`
+    pub ty: String,
+    /// The subtype in lowercase, like `rss+xml`
+    pub subtype: String,
+    /// The parameters with their names in lowercase and their values without quotes, like `("charset", "utf-8")`
+    pub parameters: Vec<(String, String)>,
+}
+
+impl MimeType {
+    /// Parses a MIME type, or returns `None` if it has no type or subtype
+    ///
+    /// # Example
+    /// ```
+    /// use tl::links::MimeType;
+    ///
+    /// let mime = MimeType::parse(r#"Application/RSS+XML; charset="utf-8""#).unwrap();
+    ///
+    /// assert_eq!(mime.essence(), "application/rss+xml");
+    /// assert_eq!(mime.parameter("Charset"), Some("utf-8"));
+    /// assert_eq!(MimeType::parse("module"), None);
+    /// ```
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.split(';');
+        let (ty, subtype) = parts.next()?.split_once('/')?;
+        let (ty, subtype) = (ty.trim(), subtype.trim());
+
+        if ty.is_empty() || subtype.is_empty() {
+            return None;
+        }
+
+        let parameters = parts
+            .filter_map(|parameter| {
+                let (name, value) = parameter.split_once('=')?;
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+
+                Some((name.trim().to_ascii_lowercase(), value.to_owned()))
+            })
+            .collect();
+
+        Some(Self {
+            ty: ty.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters,
+        })
+    }
+
+    /// Returns the type and subtype without parameters, like `application/rss+xml`
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.ty, self.subtype)
+    }
+
+    /// Returns the value of the parameter with the given name, ignoring case
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for MimeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.ty, self.subtype)?;
+        for (name, value) in &self.parameters {
+            write!(f, "; {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A size of an icon from the `sizes` attribute of a `<link>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconSize {
+    /// `any`, which is used for scalable icons like SVGs
+    Any,
+    /// A size in pixels, like `32x32`
+    Pixels {
+        /// The width in pixels
+        width: u32,
+        /// The height in pixels
+        height: u32,
+    },
+}
+
+/// A `<link>` element with an `href`, as returned by [`VDom::link_relations`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LinkRelation {
+    /// The URL, resolved against the base URL if there is one
+    pub url: String,
+    /// The tokens of the `rel` attribute in lowercase, like `alternate` or `icon`
+    pub rel: Vec<String>,
+    /// The parsed `type` attribute, like `application/rss+xml`
+    pub mime_type: Option<MimeType>,
+    /// The `title` attribute, which names alternate versions and feeds
+    pub title: Option<String>,
+    /// The `hreflang` attribute, which is the language of an alternate version
+    pub hreflang: Option<String>,
+    /// The `media` attribute, like `(prefers-color-scheme: dark)`
+    pub media: Option<String>,
+    /// The `as` attribute of preloads in lowercase, like `font` or `script`
+    pub destination: Option<String>,
+    /// The valid sizes of the `sizes` attribute of icons. Invalid sizes are left out.
+    pub sizes: Vec<IconSize>,
+    /// The `<link>` element
+    pub node: NodeHandle,
+}
+
+impl LinkRelation {
+    /// Checks whether the `rel` attribute contains the given token, ignoring case
+    pub fn has_rel(&self, token: &str) -> bool {
+        self.rel.iter().any(|rel| rel.eq_ignore_ascii_case(token))
+    }
+
+    /// Checks whether this is a feed, i.e. an `alternate` link with an RSS, Atom or JSON Feed MIME type
+    pub fn is_feed(&self) -> bool {
+        self.has_rel("alternate")
+            && self
+                .mime_type
+                .as_ref()
+                .is_some_and(|mime| is_one_of(mime.essence().as_bytes(), FEED_MIME_TYPES))
+    }
+
+    /// Returns the largest width of the pixel sizes of this icon
+    fn largest_width(&self) -> Option<u32> {
+        self.sizes
+            .iter()
+            .filter_map(|size| match size {
+                IconSize::Pixels { width, .. } => Some(*width),
+                IconSize::Any => None,
+            })
+            .max()
+    }
+}
+
+/// The `<link>` elements of a document grouped by the tokens of their `rel` attribute, as returned by [`VDom::link_relations`]
+///
+/// A link with several tokens, like `rel="alternate stylesheet"`, is part of every group it names.
+/// The links of each group are in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkRelations {
+    groups: BTreeMap<String, Vec<LinkRelation>>,
+}
+
+impl LinkRelations {
+    /// Returns the links whose `rel` attribute contains the given token, ignoring case
+    pub fn get(&self, rel: &str) -> &[LinkRelation] {
+        self.groups
+            .get(&rel.to_ascii_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the `rel` tokens of this document with their links, sorted by token
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[LinkRelation])> {
+        self.groups
+            .iter()
+            .map(|(rel, links)| (rel.as_str(), links.as_slice()))
+    }
+
+    /// Returns the first `canonical` link
+    pub fn canonical(&self) -> Option<&LinkRelation> {
+        self.get("canonical").first()
+    }
+
+    /// Returns the `alternate` links to RSS, Atom and JSON feeds, see [`LinkRelation::is_feed`]
+    pub fn feeds(&self) -> impl Iterator<Item = &LinkRelation> {
+        self.get("alternate").iter().filter(|link| link.is_feed())
+    }
+
+    /// Returns the `alternate` links that are translations of this document, i.e. that have an `hreflang` attribute
+    pub fn translations(&self) -> impl Iterator<Item = &LinkRelation> {
+        self.get("alternate")
+            .iter()
+            .filter(|link| link.hreflang.is_some())
+    }
+
+    /// Returns the `preload` and `modulepreload` links in document order
+    pub fn preloads(&self) -> Vec<&LinkRelation> {
+        self.merged(&["preload", "modulepreload"])
+    }
+
+    /// Returns the icons in document order: `icon` (including `shortcut icon`), `apple-touch-icon` and `mask-icon` links
+    pub fn icons(&self) -> Vec<&LinkRelation> {
+        self.merged(&[
+            "icon",
+            "apple-touch-icon",
+            "apple-touch-icon-precomposed",
+            "mask-icon",
+        ])
+    }
+
+    /// Returns the icon with the largest pixel size, preferring scalable icons with the size `any`
+    ///
+    /// If no icon has a size, the first icon is returned.
+    pub fn largest_icon(&self) -> Option<&LinkRelation> {
+        let icons = self.icons();
+
+        icons
+            .iter()
+            .find(|icon| icon.sizes.contains(&IconSize::Any))
+            .or_else(|| {
+                icons
+                    .iter()
+                    .filter(|icon| icon.largest_width().is_some())
+                    .max_by_key(|icon| icon.largest_width())
+            })
+            .or_else(|| icons.first())
+            .copied()
+    }
+
+    /// Returns the links of several groups in document order and without duplicates
+    fn merged(&self, rels: &[&str]) -> Vec<&LinkRelation> {
+        let mut links = rels
+            .iter()
+            .flat_map(|rel| self.get(rel))
+            .collect::<Vec<_>>();
+
+        links.sort_by_key(|link| link.node);
+        links.dedup_by_key(|link| link.node);
+        links
+    }
+}
+
+/// Counts of the links of a page, as returned by [`summarize`] and [`VDom::link_summary`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkSummary {
+    /// The number of links
+    pub total: usize,
+    /// The number of links to the host of the page, including relative links that could not be resolved
+    pub internal: usize,
+    /// The number of `http` and `https` links to other hosts
+    pub external: usize,
+    /// The number of links with other schemes, like `mailto:` or `javascript:`
+    pub other: usize,
+    /// The number of links with `rel="nofollow"`
+    pub nofollow: usize,
+    /// The hosts of external links and how often each of them is linked, most linked first
+    pub hosts: Vec<(String, usize)>,
+}
+
+/// Summarizes the given links of the page at the given URL
+///
+/// Links are internal if they point to the same host as the page URL. Without a page URL, only relative links are internal.
+///
+/// # Example
+/// ```
+/// use tl::links::summarize;
+///
+/// let dom = tl::parse(r#"
+///     <a href="/about">About</a>
+///     <a href="https://other.org/a" rel="nofollow">A</a>
+///     <a href="https://other.org/b">B</a>
+///     <a href="mailto:me@example.com">Mail</a>
+/// "#, Default::default()).unwrap();
+///
+/// let page = "https://example.com/";
+/// let summary = summarize(&dom.anchors(Some(page)), Some(page));
+///
+/// assert_eq!((summary.total, summary.internal, summary.external, summary.other), (4, 1, 2, 1));
+/// assert_eq!(summary.nofollow, 1);
+/// assert_eq!(summary.hosts, [("other.org".to_owned(), 2)]);
+/// ```
+pub fn summarize(anchors: &[Anchor], page_url: Option<&str>) -> LinkSummary {
+    let page_host = page_url.and_then(host);
+    let mut hosts = HashMap::<String, usize>::new();
+    let mut summary = LinkSummary {
+        total: anchors.len(),
+        ..LinkSummary::default()
+    };
+
+    for anchor in anchors {
+        if anchor.has_rel("nofollow") {
+            summary.nofollow += 1;
+        }
+
+        let scheme = scheme_len(&anchor.url).map(|len| anchor.url[..len - 1].to_ascii_lowercase());
+
+        match scheme.as_deref() {
+            None => summary.internal += 1,
+            Some("http" | "https") => match host(&anchor.url) {
+                Some(host) if Some(&host) == page_host.as_ref() => summary.internal += 1,
+                Some(host) => {
+                    summary.external += 1;
+                    *hosts.entry(host).or_default() += 1;
+                }
+                None => summary.other += 1,
+            },
+            Some(_) => summary.other += 1,
+        }
+    }
+
+    summary.hosts = hosts.into_iter().collect();
+    summary
+        .hosts
+        .sort_unstable_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+
+    summary
+}
+
+/// Returns the host of an absolute URL in lowercase, without user information and port
+fn host(url: &str) -> Option<String> {
+    let rest = &url[scheme_len(url)?..];
+    let authority = rest.strip_prefix("//")?;
+    let authority = &authority[..authority.find(['/', '?', '#']).unwrap_or(authority.len())];
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+
+    // IPv6 addresses contain colons, so the port is only split off after the closing bracket
+    let host = match host.rfind(':') {
+        Some(colon) if !host[colon..].contains(']') => &host[..colon],
+        _ => host,
+    };
+
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Collects the anchors of a document in document order
+pub(crate) fn anchors(dom: &VDom<'_>, base_url: Option<&str>) -> Vec<Anchor> {
+    let parser = dom.parser();
+    let options = TextOptions::new().collapse_whitespace().skip_invisible();
+
+    let headings = dom
+        .nodes()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, node)| {
+            let tag = node.as_tag()?;
+            is_one_of(tag.name().as_bytes(), HEADING_ELEMENTS)
+                .then(|| (idx as u32, tag.inner_text_with(parser, options)))
+        })
+        .collect::<Vec<_>>();
+
+    collect(dom, base_url)
+        .into_iter()
+        .filter(|link| link.kind == LinkKind::Anchor)
+        .filter_map(|link| {
+            let tag = link.node.get(parser)?.as_tag()?;
+
+            let mut text = tag.inner_text_with(parser, options);
+            if text.is_empty() {
+                text = attribute_value(tag, "alt")
+                    .filter(|alt| !alt.is_empty())
+                    .or_else(|| image_alt(tag, parser))
+                    .unwrap_or_default();
+            }
+
+            let preceding =
+                headings.partition_point(|(order, _)| *order < link.node.document_order());
+            let heading = preceding.checked_sub(1).map(|idx| headings[idx].1.clone());
+
+            Some(Anchor {
+                url: link.url,
+                text,
+                rel: link
+                    .rel
+                    .iter()
+                    .flat_map(|rel| rel.split_ascii_whitespace())
+                    .map(str::to_ascii_lowercase)
+                    .collect(),
+                heading,
+                node: link.node,
+            })
+        })
+        .collect()
+}
+
+/// Returns the first non-empty `alt` text of an image inside of the given element
+fn image_alt(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> Option<String> {
+    tag.children()
+        .all(parser)
+        .iter()
+        .filter_map(Node::as_tag)
+        .filter(|tag| tag.name().as_bytes().eq_ignore_ascii_case(b"img"))
+        .find_map(|img| attribute_value(img, "alt").filter(|alt| !alt.is_empty()))
+}
+
+/// Collects the `<link>` elements of a document with an `href` and a `rel` attribute, grouped by `rel` tokens
+pub(crate) fn relations(dom: &VDom<'_>, base_url: Option<&str>) -> LinkRelations {
+    let base = self::base_url(dom, base_url);
+    let mut relations = LinkRelations::default();
+
+    for (idx, node) in dom.nodes().iter().enumerate() {
+        let Some(tag) = node
+            .as_tag()
+            .filter(|tag| tag.name().as_bytes().eq_ignore_ascii_case(b"link"))
+        else {
+            continue;
+        };
+
+        let Some(href) = attribute_value(tag, "href").filter(|href| !href.is_empty()) else {
+            continue;
+        };
+
+        // tokens may be repeated, but a link must only appear once in a group
+        let mut rel = Vec::new();
+        for token in attribute_value(tag, "rel")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+        {
+            let token = token.to_ascii_lowercase();
+            if !rel.contains(&token) {
+                rel.push(token);
+            }
+        }
+
+        if rel.is_empty() {
+            continue;
+        }
+
+        let relation = LinkRelation {
+            url: match &base {
+                Some(base) => resolve(base, &href),
+                None => href,
+            },
+            rel,
+            mime_type: attribute_value(tag, "type").and_then(|ty| MimeType::parse(&ty)),
+            title: attribute_value(tag, "title"),
+            hreflang: attribute_value(tag, "hreflang").filter(|lang| !lang.is_empty()),
+            media: attribute_value(tag, "media").filter(|media| !media.is_empty()),
+            destination: attribute_value(tag, "as")
+                .filter(|destination| !destination.is_empty())
+                .map(|destination| destination.to_ascii_lowercase()),
+            sizes: attribute_value(tag, "sizes")
+                .as_deref()
+                .map(parse_sizes)
+                .unwrap_or_default(),
+            node: dom.parser().handle(idx as u32),
+        };
+
+        for token in &relation.rel {
+            relations
+                .groups
+                .entry(token.clone())
+                .or_default()
+                .push(relation.clone());
+        }
+    }
+
+    relations
+}
+
+/// Parses the `sizes` attribute of an icon, like `16x16 32X32` or `any`, and skips invalid sizes
+fn parse_sizes(sizes: &str) -> Vec<IconSize> {
+    sizes
+        .split_ascii_whitespace()
+        .filter_map(|size| {
+            if size.eq_ignore_ascii_case("any") {
+                return Some(IconSize::Any);
+            }
+
+            let (width, height) = size.split_once(['x', 'X'])?;
+            let parse = |digits: &str| {
+                // `str::parse` also accepts a leading `+`
+                if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                    return None;
+                }
+                digits.parse::<u32>().ok().filter(|&n| n > 0)
+            };
+
+            Some(IconSize::Pixels {
+                width: parse(width)?,
+                height: parse(height)?,
+            })
+        })
+        .collect()
+}
+
+/// Collects the links of a document in document order
+pub(crate) fn collect(dom: &VDom<'_>, base_url: Option<&str>) -> Vec<Link> {
+    let base = self::base_url(dom, base_url);
+    let mut links = Vec::new();
+
+    for (idx, node) in dom.nodes().iter().enumerate() {
+        let Some(tag) = node.as_tag() else {
+            continue;
+        };
+
+        let name = tag.name().as_utf8_str().to_ascii_lowercase();
+        let (kind, attribute) = match name.as_str() {
+            "a" | "area" => (LinkKind::Anchor, "href"),
+            "img" => (LinkKind::Image, "src"),
+            "link" => (LinkKind::Resource, "href"),
+            "script" => (LinkKind::Script, "src"),
+            "iframe" => (LinkKind::Frame, "src"),
+            _ => continue,
+        };
+
+        let mut push = |url: &str| {
+            links.push(Link {
+                url: match &base {
+                    Some(base) => resolve(base, url),
+                    None => url.to_owned(),
+                },
+                rel: attribute_value(tag, "rel"),
+                kind,
+                node: dom.parser().handle(idx as u32),
+            });
+        };
+
+        if let Some(url) = attribute_value(tag, attribute).filter(|url| !url.is_empty()) {
+            push(&url);
+        }
+
+        if kind == LinkKind::Image {
+            for candidate in tag.attributes().srcset() {
+                push(candidate.url);
+            }
+        }
+    }
+
+    links
+}
+
+fn attribute_value(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .flatten()
+        .map(|value| value.as_utf8_str().trim().to_owned())
+}
+
+/// Returns the base URL of a document, see [`VDom::base_url`]
+pub(crate) fn base_url(dom: &VDom<'_>, document_url: Option<&str>) -> Option<String> {
+    let document_url = document_url
+        .map(str::trim)
+        .filter(|url| scheme_len(url).is_some());
+
+    // like browsers, only the first `<base>` with an `href` counts, even if its value is empty or invalid
+    let base_href = dom
+        .nodes()
+        .iter()
+        .filter_map(Node::as_tag)
+        .filter(|tag| tag.name().as_bytes().eq_ignore_ascii_case(b"base"))
+        .find(|tag| tag.attributes().contains("href"))
+        .map(|tag| attribute_value(tag, "href").unwrap_or_default());
+
+    let base = match (document_url, base_href) {
+        (Some(url), Some(href)) => resolve(url, &href),
+        (None, Some(href)) => href,
+        (url, None) => return url.map(str::to_owned),
+    };
+
+    let scheme = scheme_len(&base).map(|len| base[..len - 1].to_ascii_lowercase());
+    match scheme.as_deref() {
+        // relative base URLs can not be resolved, and browsers ignore `data:` and `javascript:` base URLs
+        None | Some("data" | "javascript") => document_url.map(str::to_owned),
+        Some(_) => Some(base),
+    }
+}
+
+/// Splits a URL into the part before the query and fragment, and the rest
+fn split_path(url: &str) -> (&str, &str) {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    url.split_at(end)
+}
+
+/// Returns the length of the scheme of an absolute URL including the `:`, or `None` if the URL is relative
+fn scheme_len(url: &str) -> Option<usize> {
+    let colon = url.find(':')?;
+    let scheme = &url[..colon];
+
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.'));
+
+    is_scheme.then_some(colon + 1)
+}
+
+/// Resolves a possibly relative URL against an absolute base URL, as described in [RFC 3986, section 5.2](https://www.rfc-editor.org/rfc/rfc3986#section-5.2)
+///
+/// The reference is returned unchanged if it is absolute or if the base URL is not absolute.
+///
+/// # Example
+/// ```
+/// use tl::links::resolve;
+///
+/// let base = "https://example.com/blog/post?page=2";
+///
+/// assert_eq!(resolve(base, "other"), "https://example.com/blog/other");
+/// assert_eq!(resolve(base, "../about"), "https://example.com/about");
+/// assert_eq!(resolve(base, "/"), "https://example.com/");
+/// assert_eq!(resolve(base, "//cdn.example.com/a.js"), "https://cdn.example.com/a.js");
+/// assert_eq!(resolve(base, "?page=3"), "https://example.com/blog/post?page=3");
+/// assert_eq!(resolve(base, "#top"), "https://example.com/blog/post?page=2#top");
+/// assert_eq!(resolve(base, "mailto:a@example.com"), "mailto:a@example.com");
+/// assert_eq!(resolve(base, "\\\\cdn.example.com\\a.js"), "https://cdn.example.com/a.js");
+/// assert_eq!(resolve(base, "/a\n/b"), "https://example.com/a/b");
+/// ```
+pub fn resolve(base: &str, reference: &str) -> String {
+    // browsers remove tabs and newlines anywhere in a URL, which happen in attribute values that span several lines
+    let mut reference = reference.trim().replace(['\t', '\n', '\r'], "");
+
+    let Some(base_scheme_len) = scheme_len(base) else {
+        return reference;
+    };
+
+    if scheme_len(&reference).is_some() {
+        return reference;
+    }
+
+    let (scheme, rest) = base.split_at(base_scheme_len);
+
+    // backslashes are slashes in the path of special URLs, so `\\example.com` is protocol-relative
+    if is_one_of(scheme.as_bytes(), SPECIAL_SCHEMES) {
+        let (path, tail) = split_path(&reference);
+        reference = format!("{}{tail}", path.replace('\\', "/"));
+    }
+
+    let reference = reference.as_str();
+    let base = base.split('#').next().unwrap_or_default();
+
+    if let Some(authority_and_path) = reference.strip_prefix("//") {
+        return format!("{scheme}//{authority_and_path}");
+    }
+
+    // the authority of the base URL, including the leading `//`
+    let authority = match rest.strip_prefix("//") {
+        Some(after) => &rest[..2 + after.find(['/', '?', '#']).unwrap_or(after.len())],
+        None => "",
+    };
+    let (base_path, _) = split_path(&rest[authority.len()..]);
+
+    let (path, tail) = split_path(reference);
+
+    if path.is_empty() {
+        return if tail.is_empty() || tail.starts_with('#') {
+            format!("{base}{tail}")
+        } else {
+            format!("{scheme}{authority}{base_path}{tail}")
+        };
+    }
+
+    let merged = if path.starts_with('/') {
+        path.to_owned()
+    } else if !authority.is_empty() && base_path.is_empty() {
+        format!("/{path}")
+    } else {
+        let directory = base_path.rfind('/').map_or("", |idx| &base_path[..=idx]);
+        format!("{directory}{path}")
+    };
+
+    format!("{scheme}{authority}{}{tail}", remove_dot_segments(&merged))
+}
+
+/// Removes `.` and `..` segments from a path
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let absolute = path.starts_with('/');
+    let mut parts = path.split('/').peekable();
+
+    if absolute {
+        parts.next();
+    }
+
+    while let Some(part) = parts.next() {
+        let is_last = parts.peek().is_none();
+
+        match part {
+            "." | ".." => {
+                if part == ".." {
+                    segments.pop();
+                }
+
+                // `a/..` refers to the directory, so it keeps its trailing slash
+                if is_last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(part),
+        }
+    }
+
+    let joined = segments.join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
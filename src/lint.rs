@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::a11y::has_text_alternative;
+use crate::parser::constants::{OPTIONAL_END_TAGS, VOID_TAGS};
+use crate::text::is_one_of;
+use crate::{HTMLTag, Namespace, NodeHandle, Span, VDom};
+
+/// Elements that are obsolete in HTML and should be replaced by other elements or CSS
+const DEPRECATED_ELEMENTS: &[&str] = &[
+    "acronym",
+    "applet",
+    "basefont",
+    "bgsound",
+    "big",
+    "blink",
+    "center",
+    "dir",
+    "font",
+    "frame",
+    "frameset",
+    "isindex",
+    "keygen",
+    "listing",
+    "marquee",
+    "menuitem",
+    "multicol",
+    "nextid",
+    "nobr",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "spacer",
+    "strike",
+    "tt",
+    "xmp",
+];
+
+/// A check that the [`Linter`] runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// An element has the same attribute more than once, of which only the first one is used
+    DuplicateAttribute,
+    /// An element has the same `id` as an earlier element
+    DuplicateId,
+    /// An element has no end tag, although its end tag may not be omitted
+    UnclosedTag,
+    /// An end tag does not close any element, because the element is not open or another element is still open inside of it
+    StrayEndTag,
+    /// An obsolete element like `<center>` or `<font>`
+    DeprecatedElement,
+    /// An event handler attribute like `onclick`
+    InlineEventHandler,
+    /// An `<img>` has no `alt` attribute and no other text alternative, see [`a11y::audit`](crate::a11y::audit)
+    MissingAlt,
+}
+
+impl Rule {
+    /// All rules, in the order in which they are listed here
+    pub const ALL: [Rule; 7] = [
+        Rule::DuplicateAttribute,
+        Rule::DuplicateId,
+        Rule::UnclosedTag,
+        Rule::StrayEndTag,
+        Rule::DeprecatedElement,
+        Rule::InlineEventHandler,
+        Rule::MissingAlt,
+    ];
+
+    /// Returns the name of this rule in kebab case, like `duplicate-attribute`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::DuplicateAttribute => "duplicate-attribute",
+            Rule::DuplicateId => "duplicate-id",
+            Rule::UnclosedTag => "unclosed-tag",
+            Rule::StrayEndTag => "stray-end-tag",
+            Rule::DeprecatedElement => "deprecated-element",
+            Rule::InlineEventHandler => "inline-event-handler",
+            Rule::MissingAlt => "missing-alt",
+        }
+    }
+
+    /// Returns the severity that this rule has unless it is changed with [`Linter::severity`]
+    ///
+    /// Rules that find markup which browsers parse differently than intended are errors, the others are warnings.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::DuplicateAttribute
+            | Rule::DuplicateId
+            | Rule::UnclosedTag
+            | Rule::StrayEndTag => Severity::Error,
+            Rule::DeprecatedElement | Rule::InlineEventHandler | Rule::MissingAlt => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The severity of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// A problem that does not change how the document is parsed
+    Warning,
+    /// A problem that likely makes the document behave differently than intended
+    Error,
+}
+
+/// A problem in the markup of a document, as found by a [`Linter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The rule that found the problem
+    pub rule: Rule,
+    /// The severity of the rule
+    pub severity: Severity,
+    /// The element with the problem, which is `None` for stray end tags
+    pub node: Option<NodeHandle>,
+    /// The position of the problem in the source string, like the start tag of an element or a single attribute
+    pub span: Span,
+    /// A human-readable description of the problem
+    pub message: String,
+}
+
+/// Checks the markup of a document for common problems, according to a configurable set of [`Rule`]s
+///
+/// Only elements whose start tag is in the source string are checked, so elements that were
+/// inserted or modified after parsing may not be reported correctly.
+///
+/// The parser does not imply end tags, so in `<ul><li>a</ul>`, the `</ul>` is reported as a stray end tag
+/// and the `<ul>` as unclosed, because the `<li>` is still open when the `</ul>` occurs.
+///
+/// # Example
+/// ```
+/// use tl::lint::{Linter, Rule, Severity};
+///
+/// let source = r#"<div id="a"><center onclick="go()">Hi</center><img src="a.png" src="b.png"></span>"#;
+/// let dom = tl::parse(source, Default::default()).unwrap();
+///
+/// let diagnostics = Linter::new()
+///     .disable(Rule::MissingAlt)
+///     .severity(Rule::DeprecatedElement, Severity::Error)
+///     .lint(&dom);
+///
+/// let found = diagnostics
+///     .iter()
+///     .map(|diagnostic| (diagnostic.rule, &source[diagnostic.span.range()]))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(found, [
+///     (Rule::UnclosedTag, r#"<div id="a">"#),
+///     (Rule::DeprecatedElement, r#"<center onclick="go()">"#),
+///     (Rule::InlineEventHandler, r#"onclick="go()""#),
+///     (Rule::DuplicateAttribute, r#"src="b.png""#),
+///     (Rule::StrayEndTag, "</span>"),
+/// ]);
+/// assert_eq!(diagnostics[1].severity, Severity::Error);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Linter {
+    rules: Vec<(Rule, Severity)>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter that runs all rules with their default severity
+    pub fn new() -> Self {
+        Self {
+            rules: Rule::ALL
+                .iter()
+                .map(|rule| (*rule, rule.default_severity()))
+                .collect(),
+        }
+    }
+
+    /// Creates a linter that runs no rules, which can be enabled with [`Linter::enable`]
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Runs a rule with its default severity, unless it already runs
+    pub fn enable(mut self, rule: Rule) -> Self {
+        if self.severity_of(rule).is_none() {
+            self.rules.push((rule, rule.default_severity()));
+        }
+        self
+    }
+
+    /// Stops running a rule
+    pub fn disable(mut self, rule: Rule) -> Self {
+        self.rules.retain(|(r, _)| *r != rule);
+        self
+    }
+
+    /// Runs a rule with the given severity
+    pub fn severity(self, rule: Rule, severity: Severity) -> Self {
+        let mut this = self.disable(rule);
+        this.rules.push((rule, severity));
+        this
+    }
+
+    /// Returns the severity of a rule, or `None` if it does not run
+    pub fn severity_of(&self, rule: Rule) -> Option<Severity> {
+        self.rules
+            .iter()
+            .find(|(r, _)| *r == rule)
+            .map(|(_, severity)| *severity)
+    }
+
+    /// Checks a document and returns the problems that were found, ordered by their position in the source string
+    pub fn lint(&self, dom: &VDom<'_>) -> Vec<Diagnostic> {
+        let parser = dom.parser();
+        let source = parser.stream.data();
+        let mut diagnostics = Vec::new();
+        let mut ids = HashSet::new();
+
+        let mut report = |rule: Rule, node: Option<NodeHandle>, span: Span, message: String| {
+            if let Some(severity) = self.severity_of(rule) {
+                diagnostics.push(Diagnostic {
+                    rule,
+                    severity,
+                    node,
+                    span,
+                    message,
+                });
+            }
+        };
+
+        for (index, node) in dom.nodes().iter().enumerate() {
+            let Some(tag) = node.as_tag() else {
+                continue;
+            };
+            let handle = Some(parser.handle(index as u32));
+            let start_tag = tag.span().start_tag();
+            let name = tag.name().as_utf8_str();
+
+            let is_void = !parser.options.is_xml_mode() && is_void(tag);
+
+            if tag.span().end_tag().is_none()
+                && !is_void
+                && !source
+                    .get(start_tag.range())
+                    .is_some_and(|start_tag| start_tag.ends_with(b"/>"))
+                && !is_one_of(tag.name().as_bytes(), OPTIONAL_END_TAGS)
+            {
+                report(
+                    Rule::UnclosedTag,
+                    handle,
+                    start_tag,
+                    format!("<{name}> has no end tag"),
+                );
+            }
+
+            if tag.namespace() == Namespace::Html {
+                if is_one_of(tag.name().as_bytes(), DEPRECATED_ELEMENTS) {
+                    report(
+                        Rule::DeprecatedElement,
+                        handle,
+                        start_tag,
+                        format!("<{name}> is obsolete"),
+                    );
+                }
+
+                if name == "img" && !has_text_alternative(tag) {
+                    report(
+                        Rule::MissingAlt,
+                        handle,
+                        start_tag,
+                        String::from("<img> has no alt attribute"),
+                    );
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for (attribute, value, span) in tag.source_attributes() {
+                let attribute = attribute.as_utf8_str().to_ascii_lowercase();
+
+                if !seen.insert(attribute.clone()) {
+                    report(
+                        Rule::DuplicateAttribute,
+                        handle,
+                        span,
+                        format!("<{name}> has more than one {attribute} attribute"),
+                    );
+                    continue;
+                }
+
+                if attribute.len() > 2 && attribute.starts_with("on") {
+                    report(
+                        Rule::InlineEventHandler,
+                        handle,
+                        span,
+                        format!("<{name}> has an inline {attribute} event handler"),
+                    );
+                }
+
+                if let Some(id) = value.filter(|_| attribute == "id") {
+                    if !ids.insert(id.as_utf8_str().into_owned()) {
+                        report(
+                            Rule::DuplicateId,
+                            handle,
+                            span,
+                            format!("the id {} is already used", id.as_utf8_str()),
+                        );
+                    }
+                }
+            }
+        }
+
+        for &span in &parser.stray_end_tags {
+            let end_tag = String::from_utf8_lossy(&source[span.range()]);
+            report(
+                Rule::StrayEndTag,
+                None,
+                span,
+                format!("{end_tag} does not close an open element"),
+            );
+        }
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start());
+        diagnostics
+    }
+}
+
+/// Checks whether an element has no content, either because it is a void HTML element or a custom void element
+fn is_void(tag: &HTMLTag<'_>) -> bool {
+    tag._void
+        || (tag.namespace() == Namespace::Html
+            && VOID_TAGS
+                .iter()
+                .any(|void| void.eq_ignore_ascii_case(tag.name().as_bytes())))
+}
+
+/// Checks a document with all rules, see [`Linter`]
+///
+/// # Example
+/// ```
+/// use tl::lint::Rule;
+///
+/// let dom = tl::parse(r#"<p id="x">a</p><p id="x">b</p>"#, Default::default()).unwrap();
+/// let diagnostics = tl::lint::lint(&dom);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].rule, Rule::DuplicateId);
+/// assert_eq!(diagnostics[0].message, "the id x is already used");
+/// ```
+pub fn lint(dom: &VDom<'_>) -> Vec<Diagnostic> {
+    Linter::new().lint(dom)
+}
@@ -0,0 +1,358 @@
+use crate::text::{is_one_of, BLOCK_ELEMENTS, HEADING_ELEMENTS, INVISIBLE_ELEMENTS};
+use crate::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// Characters that have a meaning in Markdown and are escaped in text
+const ESCAPED: &[char] = &['\\', '`', '*', '_', '[', ']', '<', '>', '#', '|'];
+
+/// Converts a document to Markdown
+///
+/// Headings, paragraphs, links, images, emphasis, inline code, code blocks, lists, block quotes, horizontal rules and tables are converted to their Markdown equivalent.
+/// Tables use the GitHub Flavored Markdown syntax, with the first row as the header.
+/// Other elements are replaced by their content, except for `<head>`, `<script>`, `<style>` and `<template>`, which are removed.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(r#"
+///     <h1>Title</h1>
+///     <p>Some <b>bold</b> text with a <a href="https://example.com">link</a>.</p>
+///     <ul><li>One</li><li>Two</li></ul>
+/// "#, Default::default()).unwrap();
+///
+/// assert_eq!(
+///     tl::markdown::to_markdown(&dom),
+///     "# Title\n\nSome **bold** text with a [link](https://example.com).\n\n- One\n- Two"
+/// );
+/// ```
+pub fn to_markdown(dom: &VDom<'_>) -> String {
+    render_nodes(dom.children(), dom.parser())
+        .trim_end()
+        .to_owned()
+}
+
+/// Converts a single node and its subnodes to Markdown
+///
+/// See [`to_markdown`] for details.
+pub fn node_to_markdown(handle: NodeHandle, parser: &Parser<'_>) -> String {
+    render_nodes(&[handle], parser).trim_end().to_owned()
+}
+
+/// Builds Markdown, keeping track of whitespace and blank lines that are only written once more content follows
+#[derive(Default)]
+struct Writer {
+    out: String,
+    pending_space: bool,
+    pending_breaks: usize,
+}
+
+impl Writer {
+    fn flush(&mut self) {
+        if self.out.is_empty() {
+            // no whitespace at the very start
+        } else if self.pending_breaks > 0 {
+            let trailing = self.out.len() - self.out.trim_end_matches('\n').len();
+            for _ in trailing..self.pending_breaks {
+                self.out.push('\n');
+            }
+        } else if self.pending_space && !self.out.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+
+        self.pending_breaks = 0;
+        self.pending_space = false;
+    }
+
+    /// Writes text, collapsing whitespace and escaping Markdown syntax
+    fn text(&mut self, text: &str) {
+        for (idx, word) in text.split_ascii_whitespace().enumerate() {
+            if idx > 0 || text.starts_with(|c: char| c.is_ascii_whitespace()) {
+                self.pending_space = true;
+            }
+            self.flush();
+
+            for c in word.chars() {
+                if ESCAPED.contains(&c) {
+                    self.out.push('\\');
+                }
+                self.out.push(c);
+            }
+        }
+
+        if text.ends_with(|c: char| c.is_ascii_whitespace()) {
+            self.pending_space = true;
+        }
+    }
+
+    /// Writes already rendered inline Markdown
+    fn inline(&mut self, markdown: &str) {
+        if !markdown.is_empty() {
+            self.flush();
+            self.out.push_str(markdown);
+        }
+    }
+
+    /// Writes inline content between delimiters, i.e. `**bold**`
+    ///
+    /// Surrounding whitespace is moved outside of the delimiters, as Markdown does not allow it inside.
+    fn wrapped(&mut self, open: &str, close: &str, content: &str) {
+        let trimmed = content.trim();
+
+        if content.starts_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+
+        if !trimmed.is_empty() {
+            self.inline(&format!("{open}{trimmed}{close}"));
+        }
+
+        if content.ends_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+    }
+
+    /// Writes a block that is separated from the surrounding content by blank lines
+    fn block(&mut self, markdown: &str) {
+        let markdown = markdown.trim_end();
+        if markdown.is_empty() {
+            return;
+        }
+
+        self.pending_breaks = 2;
+        self.flush();
+        self.out.push_str(markdown);
+        self.pending_breaks = 2;
+    }
+
+    /// Returns the Markdown, with a trailing space if the content ended with whitespace
+    fn finish(mut self) -> String {
+        if self.pending_space && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+        self.out
+    }
+}
+
+fn render_nodes(handles: &[NodeHandle], parser: &Parser<'_>) -> String {
+    let mut writer = Writer::default();
+
+    for node in handles.iter().filter_map(|h| h.get(parser)) {
+        render_node(&mut writer, node, parser);
+    }
+
+    writer.finish()
+}
+
+fn render_children(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    render_nodes(tag.children().top().as_slice(), parser)
+}
+
+fn render_node(writer: &mut Writer, node: &Node<'_>, parser: &Parser<'_>) {
+    let tag = match node {
+        Node::Tag(tag) => tag,
+        Node::Raw(text) => return writer.text(&text.as_utf8_str()),
+        Node::CData(_) => return writer.text(&node.inner_text(parser)),
+        Node::Comment(_) | Node::ProcessingInstruction(_) | Node::Doctype(_) => return,
+    };
+
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+
+    match name.as_str() {
+        _ if is_one_of(name.as_bytes(), INVISIBLE_ELEMENTS) => {}
+        "head" => {}
+        _ if is_one_of(name.as_bytes(), HEADING_ELEMENTS) => {
+            let level = name.as_bytes()[1] - b'0';
+            let content = render_children(tag, parser).trim().replace('\n', " ");
+            if !content.is_empty() {
+                writer.block(&format!("{} {}", "#".repeat(level as usize), content));
+            }
+        }
+        "br" => {
+            writer.flush();
+            writer.out.push_str("  \n");
+        }
+        "hr" => writer.block("---"),
+        "strong" | "b" => writer.wrapped("**", "**", &render_children(tag, parser)),
+        "em" | "i" => writer.wrapped("*", "*", &render_children(tag, parser)),
+        "del" | "s" | "strike" => writer.wrapped("~~", "~~", &render_children(tag, parser)),
+        "code" => {
+            let code = tag.inner_text(parser);
+            // inline code cannot span lines, but other whitespace is significant
+            let code = code.lines().collect::<Vec<_>>().join(" ");
+            writer.wrapped("`", "`", &code);
+        }
+        "a" => {
+            let content = render_children(tag, parser);
+            match attribute(tag, "href") {
+                Some(href) => writer.wrapped("[", &format!("]({})", escape_url(&href)), &content),
+                None => writer.wrapped("", "", &content),
+            }
+        }
+        "img" => {
+            if let Some(src) = attribute(tag, "src") {
+                let alt = attribute(tag, "alt").unwrap_or_default();
+                let alt = alt.replace('[', "\\[").replace(']', "\\]");
+                writer.inline(&format!("![{alt}]({})", escape_url(&src)));
+            }
+        }
+        "pre" => writer.block(&render_code_block(tag, parser)),
+        "blockquote" => {
+            let content = render_children(tag, parser);
+            let quoted = content
+                .trim_end()
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_owned()
+                    } else {
+                        format!("> {line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            writer.block(&quoted);
+        }
+        "ul" | "ol" => writer.block(&render_list(tag, parser, name == "ol")),
+        "table" => writer.block(&render_table(tag, parser)),
+        "p" | "body" | "html" => writer.block(&render_children(tag, parser)),
+        _ if is_one_of(name.as_bytes(), BLOCK_ELEMENTS) => {
+            writer.block(&render_children(tag, parser))
+        }
+        _ => {
+            for node in tag.children().top().iter().filter_map(|h| h.get(parser)) {
+                render_node(writer, node, parser);
+            }
+        }
+    }
+}
+
+fn attribute(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .flatten()
+        .map(|value| value.as_utf8_str().into_owned())
+}
+
+/// Escapes characters that would end a link destination
+fn escape_url(url: &str) -> String {
+    url.trim()
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// Renders a `<pre>` element as a fenced code block, using the language of a `<code class="language-*">` child if present
+fn render_code_block(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    let code = tag.inner_text(parser);
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+
+    let language = tag
+        .child_elements(parser)
+        .filter_map(|h| h.get(parser)?.as_tag())
+        .find(|child| child.name().as_bytes().eq_ignore_ascii_case(b"code"))
+        .and_then(|code| code.attributes().class_iter())
+        .and_then(|mut classes| classes.find_map(|c| c.strip_prefix("language-")))
+        .unwrap_or_default();
+
+    // the fence must be longer than any run of backticks in the code
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn render_list(tag: &HTMLTag<'_>, parser: &Parser<'_>, ordered: bool) -> String {
+    let items = tag
+        .child_elements(parser)
+        .filter_map(|h| h.get(parser)?.as_tag())
+        .filter(|child| child.name().as_bytes().eq_ignore_ascii_case(b"li"));
+
+    let mut out = Vec::new();
+
+    for (idx, item) in items.enumerate() {
+        let marker = if ordered {
+            format!("{}. ", idx + 1)
+        } else {
+            "- ".to_owned()
+        };
+        let indent = " ".repeat(marker.len());
+        let content = render_children(item, parser);
+
+        let mut lines = content.trim_end().lines();
+        let first = lines.next().unwrap_or_default();
+        let mut rendered = format!("{marker}{first}").trim_end().to_owned();
+
+        for line in lines {
+            rendered.push('\n');
+            if !line.is_empty() {
+                rendered.push_str(&indent);
+                rendered.push_str(line);
+            }
+        }
+
+        out.push(rendered);
+    }
+
+    out.join("\n")
+}
+
+fn render_table(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    fn is_named(tag: &HTMLTag<'_>, names: &[&str]) -> bool {
+        is_one_of(tag.name().as_bytes(), names)
+    }
+
+    let children = |tag: &HTMLTag<'_>| {
+        tag.child_elements(parser)
+            .filter_map(|h| h.get(parser)?.as_tag())
+            .collect::<Vec<_>>()
+    };
+
+    let rows = children(tag)
+        .into_iter()
+        .flat_map(|child| {
+            if is_named(child, &["thead", "tbody", "tfoot"]) {
+                children(child)
+            } else {
+                vec![child]
+            }
+        })
+        .filter(|row| is_named(row, &["tr"]))
+        .map(|row| {
+            children(row)
+                .into_iter()
+                .filter(|cell| is_named(cell, &["td", "th"]))
+                .map(|cell| {
+                    render_children(cell, parser)
+                        .trim()
+                        .replace('\n', " ")
+                        .replace("\\|", "|")
+                        .replace('|', "\\|")
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    if columns == 0 {
+        return String::new();
+    }
+
+    let format_row = |cells: &[String]| {
+        let mut line = String::from("|");
+        for idx in 0..columns {
+            line.push(' ');
+            line.push_str(cells.get(idx).map_or("", String::as_str));
+            line.push_str(" |");
+        }
+        line
+    };
+
+    let mut out = vec![
+        format_row(&rows[0]),
+        format_row(&vec!["---".to_owned(); columns]),
+    ];
+    out.extend(rows[1..].iter().map(|row| format_row(row)));
+    out.join("\n")
+}
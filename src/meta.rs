@@ -0,0 +1,192 @@
+use crate::{Bytes, HTMLTag, Node, Parser, VDom};
+
+/// Metadata of a document, as returned by [`VDom::extract_metadata`]
+///
+/// All values are taken as they appear in the document, URLs are not resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Metadata {
+    /// OpenGraph properties (`<meta property="og:*">`) in document order, like `("og:title", "Example")`
+    ///
+    /// Properties can occur more than once, i.e. `og:image` for multiple images.
+    pub open_graph: Vec<(String, String)>,
+    /// Twitter card properties (`<meta name="twitter:*">`) in document order, like `("twitter:card", "summary")`
+    pub twitter: Vec<(String, String)>,
+    /// The URL of the first `<link rel="canonical">`
+    pub canonical: Option<String>,
+    /// The URLs of all `<link>` tags whose `rel` contains `icon`, like `icon`, `shortcut icon` or `apple-touch-icon`
+    pub icons: Vec<String>,
+    /// The contents of all `<script type="application/ld+json">` tags
+    ///
+    /// The JSON is not parsed or validated.
+    pub json_ld: Vec<String>,
+}
+
+impl Metadata {
+    /// Returns the first value of the OpenGraph property with the given name, i.e. `og:title`
+    pub fn open_graph(&self, property: &str) -> Option<&str> {
+        find_property(&self.open_graph, property)
+    }
+
+    /// Returns the first value of the Twitter card property with the given name, i.e. `twitter:card`
+    pub fn twitter(&self, property: &str) -> Option<&str> {
+        find_property(&self.twitter, property)
+    }
+}
+
+fn find_property<'m>(properties: &'m [(String, String)], name: &str) -> Option<&'m str> {
+    properties
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Collects the metadata of a document in a single pass over all nodes
+pub(crate) fn extract(dom: &VDom<'_>) -> Metadata {
+    let parser = dom.parser();
+    let mut metadata = Metadata::default();
+
+    for tag in dom.nodes().iter().filter_map(Node::as_tag) {
+        let name = tag.name().as_bytes();
+
+        if name.eq_ignore_ascii_case(b"meta") {
+            collect_meta(&mut metadata, tag);
+        } else if name.eq_ignore_ascii_case(b"link") {
+            collect_link(&mut metadata, tag);
+        } else if name.eq_ignore_ascii_case(b"script") {
+            collect_script(&mut metadata, tag, parser);
+        }
+    }
+
+    metadata
+}
+
+fn attribute(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .flatten()
+        .map(|value| value.as_utf8_str().trim().to_owned())
+}
+
+fn collect_meta(metadata: &mut Metadata, tag: &HTMLTag<'_>) {
+    // OpenGraph uses `property`, Twitter cards use `name`, but both are found in the wild
+    let Some(key) = attribute(tag, "property").or_else(|| attribute(tag, "name")) else {
+        return;
+    };
+    let Some(content) = attribute(tag, "content") else {
+        return;
+    };
+
+    let prefix = |prefix: &str| {
+        key.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+
+    if prefix("og:") {
+        metadata.open_graph.push((key, content));
+    } else if prefix("twitter:") {
+        metadata.twitter.push((key, content));
+    }
+}
+
+fn collect_link(metadata: &mut Metadata, tag: &HTMLTag<'_>) {
+    let (Some(rel), Some(href)) = (attribute(tag, "rel"), attribute(tag, "href")) else {
+        return;
+    };
+
+    let rel = rel.to_ascii_lowercase();
+    let mut rel = rel.split_ascii_whitespace();
+
+    if rel.clone().any(|r| r == "canonical") {
+        metadata.canonical.get_or_insert(href);
+    } else if rel.any(|r| r == "icon" || r.ends_with("-icon")) {
+        metadata.icons.push(href);
+    }
+}
+
+fn collect_script(metadata: &mut Metadata, tag: &HTMLTag<'_>, parser: &Parser<'_>) {
+    let is_json_ld =
+        attribute(tag, "type").is_some_and(|ty| ty.eq_ignore_ascii_case("application/ld+json"));
+
+    if is_json_ld {
+        metadata
+            .json_ld
+            .push(tag.inner_text(parser).trim().to_owned());
+    }
+}
+
+/// The byte order marks that determine the encoding of a document, and the labels of their encodings
+const BYTE_ORDER_MARKS: &[(&[u8], &str)] = &[
+    (b"\xEF\xBB\xBF", "utf-8"),
+    (b"\xFE\xFF", "utf-16be"),
+    (b"\xFF\xFE", "utf-16le"),
+];
+
+/// Determines the character encoding of a document from its byte order mark or its `<meta>` tags,
+/// see [`VDom::detected_charset`]
+pub(crate) fn detected_charset(dom: &VDom<'_>) -> Option<String> {
+    let source = dom.parser().stream.data();
+
+    if let Some((_, label)) = BYTE_ORDER_MARKS
+        .iter()
+        .find(|(bom, _)| source.starts_with(bom))
+    {
+        return Some((*label).to_owned());
+    }
+
+    dom.nodes()
+        .iter()
+        .filter_map(Node::as_tag)
+        .filter(|tag| tag.name().as_bytes().eq_ignore_ascii_case(b"meta"))
+        .find_map(meta_charset)
+}
+
+/// Returns the charset declared by a `<meta charset>` or `<meta http-equiv="Content-Type">` tag
+fn meta_charset(tag: &HTMLTag<'_>) -> Option<String> {
+    if let Some(charset) = attribute(tag, "charset") {
+        return (!charset.is_empty()).then(|| charset.to_ascii_lowercase());
+    }
+
+    let is_content_type = attribute(tag, "http-equiv")
+        .is_some_and(|value| value.eq_ignore_ascii_case("content-type"));
+
+    is_content_type
+        .then(|| attribute(tag, "content"))
+        .flatten()
+        .and_then(|content| charset_from_content_type(&content))
+}
+
+/// Extracts the charset parameter of a content type like `text/html; charset=ISO-8859-1`
+fn charset_from_content_type(content: &str) -> Option<String> {
+    let lower = content.to_ascii_lowercase();
+    let rest = lower[lower.find("charset")? + "charset".len()..].trim_start();
+    let rest = rest.strip_prefix('=')?.trim_start();
+
+    let value = match rest.strip_prefix(['"', '\'']) {
+        Some(quoted) => quoted.split(['"', '\'']).next(),
+        None => rest.split([';', ' ', '\t', '\n', '\r']).next(),
+    }?;
+
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+/// Returns the language of an element from its own or its nearest ancestor's `lang` attribute,
+/// see [`HTMLTag::effective_lang`]
+pub(crate) fn effective_lang<'p, 'a>(
+    tag: &'p HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+) -> Option<&'p Bytes<'a>> {
+    let mut current = tag;
+
+    loop {
+        let attributes = current.attributes();
+        // `xml:lang` takes precedence over `lang`, and an empty value means that the language is unknown
+        if let Some(lang) = attributes
+            .get("xml:lang")
+            .or_else(|| attributes.get("lang"))
+        {
+            return lang.filter(|lang| !lang.as_bytes().trim_ascii().is_empty());
+        }
+
+        current = current._parent?.get(parser)?.as_tag()?;
+    }
+}
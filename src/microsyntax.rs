@@ -0,0 +1,279 @@
+//! Parsers for attribute values with their own small grammar, like `srcset` and `style`
+//!
+//! These are usually obtained through [`Attributes::srcset()`](crate::Attributes::srcset) and
+//! [`Attributes::style()`](crate::Attributes::style), but can also be used on strings directly.
+
+/// The descriptor of a [`SrcsetCandidate`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Descriptor {
+    /// A pixel density descriptor, like `2x`
+    ///
+    /// Candidates without a descriptor have a density of `1`.
+    Density(f64),
+    /// A width descriptor, like `480w`, optionally with a height descriptor, like `480w 320h`
+    Width {
+        /// The width of the image in pixels
+        width: u32,
+        /// The height of the image in pixels, if given
+        height: Option<u32>,
+    },
+}
+
+/// An image candidate of a `srcset` attribute, like `small.jpg 480w`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SrcsetCandidate<'s> {
+    /// The URL of the image, as written in the attribute
+    pub url: &'s str,
+    /// The descriptor of the image
+    pub descriptor: Descriptor,
+}
+
+/// Parses the value of a `srcset` attribute into its image candidates
+///
+/// This follows the [HTML rules for parsing a srcset attribute](https://html.spec.whatwg.org/multipage/images.html#parsing-a-srcset-attribute),
+/// so commas inside of URLs and descriptors like `1.5x` are handled correctly.
+/// Candidates with invalid descriptors are skipped, just like browsers do.
+///
+/// # Example
+/// ```
+/// use tl::microsyntax::{srcset, Descriptor};
+///
+/// let candidates = srcset("a.jpg, data:image/png;base64,iVBO 2x, c.jpg 100q, d.jpg 480w");
+///
+/// assert_eq!(candidates.len(), 3);
+/// assert_eq!(candidates[0].url, "a.jpg");
+/// assert_eq!(candidates[0].descriptor, Descriptor::Density(1.0));
+/// assert_eq!(candidates[1].url, "data:image/png;base64,iVBO");
+/// assert_eq!(candidates[1].descriptor, Descriptor::Density(2.0));
+/// assert_eq!(candidates[2].descriptor, Descriptor::Width { width: 480, height: None });
+/// ```
+pub fn srcset(input: &str) -> Vec<SrcsetCandidate<'_>> {
+    let mut candidates = Vec::new();
+    let mut rest = input;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return candidates;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let (url, tail) = rest.split_at(end);
+        rest = tail;
+
+        // a URL that ends with a comma has no descriptors
+        let descriptors = if url.ends_with(',') {
+            Vec::new()
+        } else {
+            let (descriptors, tail) = descriptor_tokens(rest);
+            rest = tail;
+            descriptors
+        };
+
+        let url = url.trim_end_matches(',');
+
+        if let Some(descriptor) = parse_descriptors(&descriptors) {
+            candidates.push(SrcsetCandidate { url, descriptor });
+        }
+    }
+}
+
+/// Splits the descriptors of a candidate into tokens and returns them along with the rest of the input after the candidate
+fn descriptor_tokens(input: &str) -> (Vec<&str>, &str) {
+    let input = input.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_parens = false;
+
+    for (i, c) in input.char_indices() {
+        if in_parens {
+            in_parens = c != ')';
+            continue;
+        }
+
+        match c {
+            '(' => {
+                start.get_or_insert(i);
+                in_parens = true;
+            }
+            ',' => {
+                tokens.extend(start.map(|start| &input[start..i]));
+                return (tokens, &input[i + 1..]);
+            }
+            c if c.is_ascii_whitespace() => {
+                tokens.extend(start.take().map(|start| &input[start..i]));
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+
+    tokens.extend(start.map(|start| &input[start..]));
+    (tokens, "")
+}
+
+/// Parses the descriptor tokens of a candidate, or returns `None` if they are invalid
+fn parse_descriptors(tokens: &[&str]) -> Option<Descriptor> {
+    let mut width = None;
+    let mut height = None;
+    let mut density = None;
+
+    for token in tokens {
+        let kind = token.chars().last()?;
+        let value = &token[..token.len() - kind.len_utf8()];
+
+        match kind {
+            'w' if width.is_none() && density.is_none() => {
+                width = Some(parse_positive_integer(value)?);
+            }
+            'h' if height.is_none() && density.is_none() => {
+                height = Some(parse_positive_integer(value)?);
+            }
+            'x' if width.is_none() && height.is_none() && density.is_none() => {
+                let is_number = value
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+' | b'e' | b'E'));
+
+                density = Some(
+                    value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|d| is_number && *d >= 0.0)?,
+                );
+            }
+            _ => return None,
+        }
+    }
+
+    match (width, height, density) {
+        (Some(width), height, None) => Some(Descriptor::Width { width, height }),
+        (None, None, density) => Some(Descriptor::Density(density.unwrap_or(1.0))),
+        // a height is only allowed together with a width
+        (None, Some(_), _) | (Some(_), _, Some(_)) => None,
+    }
+}
+
+fn parse_positive_integer(value: &str) -> Option<u32> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok().filter(|&n| n > 0)
+}
+
+/// An iterator over the declarations of an inline style, like `color: red; margin: 0`
+///
+/// Each item is a `(property, value)` pair with surrounding whitespace removed.
+/// Properties are returned as written, so they may need to be lowercased before comparing them.
+/// Semicolons and colons within quotes, parentheses and comments are not treated as separators,
+/// comments around properties and values are removed and declarations without a property or value are skipped.
+/// A trailing `!important` is kept as part of the value.
+///
+/// # Example
+/// ```
+/// use tl::microsyntax::StyleDeclarations;
+///
+/// let style = r#"color: red; /* note */ background: url("a;b.png") ; ; font-family: "A:B", serif !important"#;
+/// let declarations = StyleDeclarations::new(style).collect::<Vec<_>>();
+///
+/// assert_eq!(declarations, [
+///     ("color", "red"),
+///     ("background", r#"url("a;b.png")"#),
+///     ("font-family", r#""A:B", serif !important"#),
+/// ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StyleDeclarations<'s> {
+    rest: &'s str,
+}
+
+impl<'s> StyleDeclarations<'s> {
+    /// Creates an iterator over the declarations of the given inline style
+    pub fn new(style: &'s str) -> Self {
+        Self { rest: style }
+    }
+}
+
+impl<'s> Iterator for StyleDeclarations<'s> {
+    type Item = (&'s str, &'s str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.rest.is_empty() {
+            let (declaration, rest) = match find_top_level(self.rest, b';') {
+                Ok(end) => (&self.rest[..end], &self.rest[end + 1..]),
+                Err(end) => (&self.rest[..end], ""),
+            };
+            self.rest = rest;
+
+            let Ok(colon) = find_top_level(declaration, b':') else {
+                continue;
+            };
+
+            let property = trim_css(&declaration[..colon]);
+            let value = trim_css(&declaration[colon + 1..]);
+
+            if !property.is_empty() && !value.is_empty() {
+                return Some((property, value));
+            }
+        }
+
+        None
+    }
+}
+
+/// Returns the position of the first `needle` in `input` that is not within quotes, parentheses or a comment
+///
+/// If there is none, this returns the length of `input` without an unterminated comment at its end.
+pub(crate) fn find_top_level(input: &str, needle: u8) -> Result<usize, usize> {
+    let bytes = input.as_bytes();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        match quote {
+            Some(_) if byte == b'\\' => i += 1,
+            Some(q) if byte == q => quote = None,
+            Some(_) => {}
+            None => match byte {
+                b'\\' => i += 1,
+                b'"' | b'\'' => quote = Some(byte),
+                b'(' => depth += 1,
+                b')' => depth = depth.saturating_sub(1),
+                b'/' if bytes.get(i + 1) == Some(&b'*') => match input[i + 2..].find("*/") {
+                    Some(end) => i += 2 + end + 1,
+                    None => return Err(i),
+                },
+                _ if byte == needle && depth == 0 => return Ok(i),
+                _ => {}
+            },
+        }
+
+        i += 1;
+    }
+
+    Err(bytes.len())
+}
+
+/// Removes whitespace and comments from both ends of a part of a declaration
+pub(crate) fn trim_css(mut input: &str) -> &str {
+    loop {
+        input = input.trim_matches(|c: char| c.is_ascii_whitespace());
+
+        if let Some(rest) = input.strip_prefix("/*") {
+            input = rest.find("*/").map_or("", |end| &rest[end + 2..]);
+        } else if input.ends_with("*/") {
+            match input[..input.len() - 2].rfind("/*") {
+                Some(start) => input = &input[..start],
+                None => return input,
+            }
+        } else {
+            return input;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::text::{is_one_of, TextOptions, HEADING_ELEMENTS, INVISIBLE_ELEMENTS};
+use crate::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// The level of an element with `role="heading"` and no valid `aria-level`, as defined by WAI-ARIA
+const DEFAULT_ARIA_LEVEL: u32 = 2;
+
+/// A heading of a document outline, as returned by [`VDom::outline`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// The heading element
+    pub node: NodeHandle,
+    /// The level of the heading, from `1` for `<h1>` to `6` for `<h6>`, or the value of its `aria-level` attribute
+    ///
+    /// Only `aria-level` can result in levels above `6`.
+    pub level: u32,
+    /// The visible text of the heading with collapsed whitespace, which may be empty
+    pub text: String,
+    /// The headings of the subsections of this heading: the following headings with a greater level,
+    /// up to the next heading with the same or a lower level
+    pub children: Vec<Heading>,
+}
+
+impl Heading {
+    /// Returns an iterator over this heading and all of its subheadings, in document order
+    pub fn iter(&self) -> impl Iterator<Item = &Heading> + '_ {
+        let mut stack = vec![self];
+
+        std::iter::from_fn(move || {
+            let heading = stack.pop()?;
+            stack.extend(heading.children.iter().rev());
+            Some(heading)
+        })
+    }
+}
+
+/// Builds the outline of a document from its headings in document order
+pub(crate) fn outline(dom: &VDom<'_>) -> Vec<Heading> {
+    let parser = dom.parser();
+    let nodes = dom.nodes();
+
+    let mut roots = Vec::new();
+    // the headings whose sections are still open, with increasing levels
+    let mut open: Vec<Heading> = Vec::new();
+
+    let mut index = 0;
+    while let Some(node) = nodes.get(index) {
+        let handle = parser.handle(index as u32);
+        index += 1;
+
+        let Node::Tag(tag) = node else {
+            continue;
+        };
+
+        if is_hidden(tag) {
+            index = tag._descendants.end as usize;
+            continue;
+        }
+
+        let heading = if tag.name().as_bytes().eq_ignore_ascii_case(b"hgroup") {
+            hgroup_heading(tag, parser)
+        } else {
+            heading_level(tag).map(|level| (handle, tag, level))
+        };
+
+        let Some((node, heading_tag, level)) = heading else {
+            continue;
+        };
+
+        // nested headings are part of the text of their heading, and an `<hgroup>` only has a single heading
+        index = tag._descendants.end as usize;
+
+        let heading = Heading {
+            node,
+            level,
+            text: heading_tag.inner_text_with(
+                parser,
+                TextOptions::new().collapse_whitespace().skip_invisible(),
+            ),
+            children: Vec::new(),
+        };
+
+        while open.last().is_some_and(|last| last.level >= heading.level) {
+            close(&mut open, &mut roots);
+        }
+        open.push(heading);
+    }
+
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+
+    roots
+}
+
+/// Closes the section of the innermost open heading and adds the heading to its parent
+fn close(open: &mut Vec<Heading>, roots: &mut Vec<Heading>) {
+    if let Some(heading) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(heading),
+            None => roots.push(heading),
+        }
+    }
+}
+
+/// Returns the level of an element if it is a heading
+fn heading_level(tag: &HTMLTag<'_>) -> Option<u32> {
+    let name = tag.name().as_bytes();
+    let attributes = tag.attributes();
+
+    let element_level = HEADING_ELEMENTS
+        .iter()
+        .position(|heading| heading.as_bytes().eq_ignore_ascii_case(name))
+        .map(|position| position as u32 + 1);
+
+    let is_aria_heading = attributes.get("role").flatten().is_some_and(|role| {
+        role.as_bytes()
+            .split(u8::is_ascii_whitespace)
+            .any(|role| role.eq_ignore_ascii_case(b"heading"))
+    });
+
+    if element_level.is_none() && !is_aria_heading {
+        return None;
+    }
+
+    // like in the accessibility tree of browsers, a valid `aria-level` overrides the level of heading elements
+    let aria_level = attributes
+        .get_int("aria-level")
+        .and_then(|level| u32::try_from(level).ok())
+        .filter(|&level| level > 0);
+
+    aria_level.or(element_level).or(Some(DEFAULT_ARIA_LEVEL))
+}
+
+/// Returns the heading of an `<hgroup>`: its first heading with the lowest level
+fn hgroup_heading<'p, 'a>(
+    hgroup: &HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+) -> Option<(NodeHandle, &'p HTMLTag<'a>, u32)> {
+    let mut best: Option<(NodeHandle, &HTMLTag<'a>, u32)> = None;
+
+    let mut index = hgroup._descendants.start;
+    while index < hgroup._descendants.end {
+        let handle = parser.handle(index);
+        index += 1;
+
+        let Some(Node::Tag(tag)) = handle.get(parser) else {
+            continue;
+        };
+
+        if is_hidden(tag) {
+            index = tag._descendants.end;
+            continue;
+        }
+
+        if let Some(level) = heading_level(tag) {
+            index = tag._descendants.end;
+
+            if best.is_none_or(|(_, _, best)| level < best) {
+                best = Some((handle, tag, level));
+            }
+        }
+    }
+
+    best
+}
+
+/// Checks whether an element and its content are not rendered
+fn is_hidden(tag: &HTMLTag<'_>) -> bool {
+    is_one_of(tag.name().as_bytes(), INVISIBLE_ELEMENTS) || tag.attributes().contains("hidden")
+}
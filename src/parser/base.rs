@@ -1,12 +1,26 @@
 use super::{
+    classes::ClassList,
     constants,
+    doctype::Doctype,
     handle::NodeHandle,
-    tag::{Attributes, HTMLTag, Node},
+    namespace::Namespace,
+    options::NodeFilter,
+    span::Span,
+    symbol::{known_attribute, ElementName},
+    tag::{Attributes, HTMLTag, Node, SourceAttribute},
 };
+use crate::errors::MarkupError;
+use crate::text::{is_one_of, PREFORMATTED_ELEMENTS};
 use crate::InnerNodeHandle;
 use crate::{bytes::Bytes, inline::vec::InlineVec, simd, ParseError};
-use crate::{stream::Stream, ParserOptions};
+use crate::{stream::Stream, DuplicateAttributes, ParserOptions};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// The number of tags and texts that are parsed between checks of the time budget, see [`ParserOptions::time_budget`]
+const DEADLINE_CHECK_INTERVAL: u32 = 256;
 
 /// A list of HTML nodes
 pub type Tree<'a> = Vec<Node<'a>>;
@@ -14,6 +28,11 @@ pub type Tree<'a> = Vec<Node<'a>>;
 /// Inline class vector
 pub type ClassVec = InlineVec<NodeHandle, 2>;
 
+/// Inline ID vector
+///
+/// IDs are supposed to be unique, so this usually only contains a single element.
+pub type IdVec = InlineVec<NodeHandle, 1>;
+
 /// HTML Version (<!DOCTYPE>)
 #[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(C)]
@@ -44,45 +63,146 @@ pub struct Parser<'a> {
     pub(crate) tags: Tree<'a>,
     /// The topmost HTML nodes
     pub(crate) ast: Vec<NodeHandle>,
-    /// A HashMap that maps Tag ID to a Node ID
-    pub(crate) ids: HashMap<Bytes<'a>, NodeHandle>,
-    /// A HashMap that maps Tag Class to a Node ID
+    /// The parents and sibling positions of all nodes, which are computed on the first lookup
+    ///
+    /// Most documents are never asked for the parent of a text node or the position of a node,
+    /// so parsing does not pay for this.
+    pub(crate) siblings: OnceLock<Siblings>,
+    /// A HashMap that maps Tag ID to a list of Node IDs, in document order
+    ///
+    /// Documents may contain the same ID more than once, so all of them are kept
+    pub(crate) ids: HashMap<Bytes<'a>, IdVec>,
+    /// A HashMap that maps Tag Class to a list of Node IDs, in document order
     pub(crate) classes: HashMap<Bytes<'a>, ClassVec>,
+    /// A HashMap that maps Tag name to a list of Node IDs, in document order
+    pub(crate) tag_names: HashMap<Bytes<'a>, Vec<NodeHandle>>,
+    /// A HashMap that maps an attribute name and value to a list of Node IDs, in document order
+    ///
+    /// Only contains the attributes that were tracked with `ParserOptions::track_attribute()`
+    pub(crate) attribute_values: HashMap<(&'static str, Bytes<'a>), Vec<NodeHandle>>,
+    /// Whether the nodes were borrowed mutably since parsing, which could make the lookup tables outdated
+    ///
+    /// Query selectors then search all nodes instead of the lookup tables.
+    pub(crate) tables_outdated: bool,
+    /// Whether the nodes were borrowed mutably or merged since parsing, so that the source may not represent them anymore
+    pub(crate) modified: bool,
     /// The current HTML version, if set
     pub(crate) version: Option<HTMLVersion>,
+    /// The `<template>` elements of the document, in document order
+    ///
+    /// Their contents are not rendered, so query selectors skip them
+    pub(crate) templates: Vec<NodeHandle>,
+    /// The names of the open elements of a subtree that is left out because of [`NodeFilter::SkipSubtree`]
+    skipped_subtree: Vec<&'a [u8]>,
+    /// The open elements that are left out without their content because of [`NodeFilter::SkipNode`],
+    /// with the length of the stack when they were opened
+    skipped_elements: Vec<(usize, &'a [u8])>,
+    /// The end tags that did not close an element, because it was not the innermost open element
+    pub(crate) stray_end_tags: Vec<Span>,
+    /// The first markup that the parser recovered from, which is only recorded with [`ParserOptions::strict`]
+    markup_error: Option<(MarkupError, Span)>,
+    /// A number that is unique to this document and its current state, see [`Parser::generation`]
+    generation: u64,
+    /// A number that identifies this document in the handles of its nodes, see [`NodeHandle`]
+    document: u32,
+}
+
+/// The parent and the position among its siblings of every node of a parser, at the same index as the node
+#[derive(Debug, Default)]
+pub(crate) struct Siblings {
+    /// The parent of every node
+    ///
+    /// Unlike tags, text and comment nodes have no way of storing their parent themselves,
+    /// so it is tracked here for sibling lookups
+    pub(crate) parents: Vec<Option<NodeHandle>>,
+    /// The position of every node among its siblings
+    ///
+    /// This is cleared when the nodes are borrowed mutably, because mutations could make it outdated.
+    /// Lookups then fall back to searching the siblings.
+    pub(crate) positions: Vec<SiblingPosition>,
+}
+
+/// The position of a node among the children of its parent, or among the topmost nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SiblingPosition {
+    /// The index of the node in the list of its siblings
+    pub(crate) index: u32,
+    /// The number of elements before the node in the list of its siblings
+    pub(crate) element_index: u32,
+}
+
+impl SiblingPosition {
+    /// The position of a node that was removed from the lists of children
+    const DETACHED: Self = Self {
+        index: u32::MAX,
+        element_index: u32::MAX,
+    };
 }
 
 impl<'a> Parser<'a> {
-    pub(crate) fn new(input: &str, options: ParserOptions) -> Parser {
+    pub(crate) fn new(input: &str, options: ParserOptions) -> Parser<'_> {
+        Parser::new_bytes(input.as_bytes(), options)
+    }
+
+    pub(crate) fn new_bytes(input: &[u8], options: ParserOptions) -> Parser<'_> {
+        let capacity = options.node_capacity();
+
         Parser {
             stack: Vec::with_capacity(4),
             options,
-            tags: Vec::new(),
-            stream: Stream::new(input.as_bytes()),
+            tags: Vec::with_capacity(capacity),
+            stream: Stream::new(input),
             ast: Vec::new(),
+            siblings: OnceLock::new(),
             ids: HashMap::new(),
             classes: HashMap::new(),
+            tag_names: HashMap::new(),
+            attribute_values: HashMap::new(),
+            tables_outdated: false,
+            modified: false,
             version: None,
+            templates: Vec::new(),
+            skipped_subtree: Vec::new(),
+            skipped_elements: Vec::new(),
+            stray_end_tags: Vec::new(),
+            markup_error: None,
+            generation: next_generation(),
+            document: next_document(),
         }
     }
 
-    pub(crate) fn new_bytes(input: &[u8], options: ParserOptions) -> Parser {
+    /// Clears this parser for parsing the given input, keeping the allocations of its node table and lookup tables
+    pub(crate) fn reset(self, input: &[u8]) -> Parser<'_> {
         Parser {
-            stack: Vec::with_capacity(4),
-            options,
-            tags: Vec::new(),
+            stack: recycle(self.stack),
+            options: self.options,
+            tags: recycle(self.tags),
             stream: Stream::new(input),
-            ast: Vec::new(),
-            ids: HashMap::new(),
-            classes: HashMap::new(),
+            ast: recycle(self.ast),
+            siblings: OnceLock::new(),
+            // keys borrow from the previous input, so the tables cannot be reused directly,
+            // but starting at the same size avoids growing them again
+            ids: HashMap::with_capacity(self.ids.capacity()),
+            classes: HashMap::with_capacity(self.classes.capacity()),
+            tag_names: HashMap::with_capacity(self.tag_names.capacity()),
+            attribute_values: HashMap::with_capacity(self.attribute_values.capacity()),
+            tables_outdated: false,
+            modified: false,
             version: None,
+            templates: recycle(self.templates),
+            skipped_subtree: Vec::new(),
+            skipped_elements: Vec::new(),
+            stray_end_tags: Vec::new(),
+            markup_error: None,
+            generation: next_generation(),
+            document: next_document(),
         }
     }
 
     #[inline(always)]
     fn register_tag(&mut self, node: Node<'a>) -> NodeHandle {
         self.tags.push(node);
-        NodeHandle::new((self.tags.len() - 1) as u32)
+        self.handle((self.tags.len() - 1) as u32)
     }
 
     #[inline(always)]
@@ -138,7 +258,13 @@ impl<'a> Parser<'a> {
     }
 
     fn skip_comment_with_start(&mut self, start: usize) -> &'a [u8] {
+        // jump from dash to dash instead of comparing the end marker at every byte
         while !self.stream.is_eof() {
+            self.read_to(b'-');
+            if self.stream.is_eof() {
+                break;
+            }
+
             let idx = self.stream.idx;
 
             if self
@@ -156,7 +282,36 @@ impl<'a> Parser<'a> {
         &[]
     }
 
+    /// Skips the content of a CDATA section up to and including its end marker
+    fn skip_cdata(&mut self) {
+        while !self.stream.is_eof() {
+            self.read_to(b']');
+
+            if self
+                .stream
+                .slice_len(self.stream.idx, constants::CDATA_END.len())
+                .eq(constants::CDATA_END)
+            {
+                self.stream.advance_by(constants::CDATA_END.len());
+                return;
+            }
+
+            self.stream.advance();
+        }
+    }
+
+    /// Records markup that the parser recovers from, which makes parsing fail in strict mode
+    #[cold]
+    fn report(&mut self, error: MarkupError, span: Span) {
+        if self.options.is_strict() && self.markup_error.is_none() {
+            // the stream may be one past the end after an unterminated construct
+            let end = span.end().min(self.stream.len());
+            self.markup_error = Some((error, Span::new(span.start(), end)));
+        }
+    }
+
     fn parse_attribute(&mut self) -> Option<(&'a [u8], Option<&'a [u8]>)> {
+        let start = self.stream.idx;
         let name = self.read_ident()?;
         self.skip_whitespaces();
 
@@ -167,18 +322,85 @@ impl<'a> Parser<'a> {
 
         self.skip_whitespaces();
 
-        let value = if let Some(quote) = self.stream.expect_oneof_and_skip(&[b'"', b'\'']) {
-            self.read_to(quote)
+        let value = if let Some(quote) = self.stream.expect_oneof_and_skip(b"\"'") {
+            let value = self.read_to(quote);
+            // skip the closing quote
+            self.stream.advance();
+
+            // attributes without whitespace between them are only an error in strict mode
+            let is_separated = |c: u8| c.is_ascii_whitespace() || simd::is_closing(c);
+            if self.options.is_strict() && !self.stream.current_cpy().is_some_and(is_separated) {
+                self.report(
+                    MarkupError::InvalidAttribute,
+                    Span::new(start, self.stream.idx),
+                );
+            }
+
+            value
         } else {
-            self.read_to4([b' ', b'\n', b'/', b'>'])
+            let value = self.read_to4([b' ', b'\n', b'/', b'>']);
+            if value.is_empty() {
+                self.report(
+                    MarkupError::InvalidAttribute,
+                    Span::new(start, self.stream.idx),
+                );
+            }
+            value
         };
 
         Some((name, Some(value)))
     }
 
-    fn parse_attributes(&mut self) -> Option<Attributes<'a>> {
+    /// Parses the attributes of a start tag, whose names are converted to lowercase if `lowercase` is `true`
+    fn parse_attributes(&mut self, lowercase: bool) -> Option<Attributes<'a>> {
         let mut attributes = Attributes::new();
+        let keep_first = self.options.duplicate_attributes() == DuplicateAttributes::KeepFirst;
+        let is_xml = self.options.is_xml_mode();
+
+        self.scan_attributes(|key, value, _| {
+            let value: Option<Bytes<'a>> = value.map(Into::into);
+            let key = if lowercase {
+                lowercase_attribute_name(key)
+            } else {
+                key.into()
+            };
 
+            match key.as_bytes() {
+                name if name.eq_ignore_ascii_case(b"id") => {
+                    if !(keep_first && attributes.id.is_some()) {
+                        attributes.id = value;
+                    }
+                }
+                name if name.eq_ignore_ascii_case(b"class") => {
+                    if !(keep_first && attributes.class.is_some()) {
+                        attributes.class = value;
+                    }
+                }
+                _ => {
+                    // names that only differ in case are the same attribute, except in XML
+                    let key = if lowercase || is_xml {
+                        key
+                    } else {
+                        attributes.stored_key(key)
+                    };
+
+                    if !(keep_first && attributes.raw.contains_key(&key)) {
+                        attributes.raw.insert(key, value);
+                    }
+                }
+            };
+        })?;
+
+        Some(attributes)
+    }
+
+    /// Parses the attributes of a start tag and calls `f` with the name, value and span of each of them, in source order
+    ///
+    /// Repeated attributes are passed to `f` every time they occur.
+    fn scan_attributes<F>(&mut self, mut f: F) -> Option<()>
+    where
+        F: FnMut(&'a [u8], Option<&'a [u8]>, Span),
+    {
         loop {
             self.skip_whitespaces();
 
@@ -188,22 +410,47 @@ impl<'a> Parser<'a> {
                 break;
             }
 
+            let start = self.stream.idx;
+
             if let Some((key, value)) = self.parse_attribute() {
-                let value: Option<Bytes<'a>> = value.map(Into::into);
+                // other whitespace than spaces and newlines is skipped below
+                if key.is_empty() && !cur.is_ascii_whitespace() {
+                    let end = self.stream.idx.max(start + 1);
+                    self.report(MarkupError::InvalidAttribute, Span::new(start, end));
+                }
 
-                match key {
-                    b"id" => attributes.id = value,
-                    b"class" => attributes.class = value,
-                    _ => attributes.raw.insert(key.into(), value),
-                };
+                f(key, value, Span::new(start, self.stream.idx));
             }
 
-            if !simd::is_closing(self.stream.current_cpy()?) {
+            // a character that cannot start an attribute is skipped, but after a valueless attribute
+            // the stream is already at the start of the next one
+            if self.stream.idx == start && !simd::is_closing(self.stream.current_cpy()?) {
                 self.stream.advance();
             }
         }
 
-        Some(attributes)
+        Some(())
+    }
+
+    /// Parses the attributes of the given start tag, like `<a href="/">`, in source order
+    ///
+    /// Spans are shifted by `offset`, which is the position of the start tag in the source string.
+    pub(crate) fn source_attributes(start_tag: &[u8], offset: usize) -> Vec<SourceAttribute<'_>> {
+        let mut attributes = Vec::new();
+        let mut parser = Parser::new_bytes(start_tag, ParserOptions::default());
+
+        parser.stream.advance();
+        parser.skip_whitespaces();
+
+        if parser.read_ident().is_some() {
+            parser.skip_whitespaces();
+            parser.scan_attributes(|key, value, span| {
+                let span = Span::new(offset + span.start(), offset + span.end());
+                attributes.push((key.into(), value.map(Into::into), span));
+            });
+        }
+
+        attributes
     }
 
     #[inline]
@@ -227,25 +474,68 @@ impl<'a> Parser<'a> {
         self.stack.last().copied()
     }
 
-    fn read_end(&mut self) {
+    /// Returns the innermost element that has not been closed yet, which new nodes are added to
+    #[inline]
+    fn open_element(&self) -> Option<&HTMLTag<'a>> {
+        self.get_parent()?.get(self)?.as_tag()
+    }
+
+    fn read_end(&mut self, start: usize) {
         self.stream.advance();
 
         let closing_tag_name = self.read_to(b'>');
 
-        self.stream.expect_and_skip_cond(b'>');
+        if !self.stream.expect_and_skip_cond(b'>') {
+            self.report(
+                MarkupError::UnterminatedTag,
+                Span::new(start, self.stream.idx),
+            );
+        }
+
+        // end tags close elements regardless of their case, except in XML
+        let is_xml = self.options.is_xml_mode();
+        let closes = |name: &[u8]| {
+            if is_xml {
+                name == closing_tag_name
+            } else {
+                name.eq_ignore_ascii_case(closing_tag_name)
+            }
+        };
+
+        if let Some(&name) = self.skipped_subtree.last() {
+            if closes(name) {
+                self.skipped_subtree.pop();
+            }
+            return;
+        }
+
+        if let Some(&(depth, name)) = self.skipped_elements.last() {
+            if depth == self.stack.len() && closes(name) {
+                self.skipped_elements.pop();
+                return;
+            }
+        }
 
         let closing_tag_matches_parent = self
             .stack
             .last()
             .and_then(|last_handle| last_handle.get(self))
             .and_then(|last_item| last_item.as_tag())
-            .map_or(false, |last_tag| last_tag.name() == closing_tag_name);
+            .is_some_and(|last_tag| closes(last_tag.name().as_bytes()));
 
         if !closing_tag_matches_parent {
+            let span = Span::new(start, self.stream.idx);
+            self.stray_end_tags.push(span);
+            self.report(MarkupError::StrayEndTag, span);
             return;
         }
 
         if let Some(handle) = self.stack.pop() {
+            // skipped elements inside of this one cannot be closed anymore
+            let depth = self.stack.len();
+            self.skipped_elements.retain(|&(d, _)| d <= depth);
+
+            let end = self.tags.len() as u32;
             let tag = self
                 .tags
                 .get_mut(handle.get_inner() as usize)
@@ -258,31 +548,8 @@ impl<'a> Parser<'a> {
             let offset = offset - ptr;
 
             tag._raw = self.stream.slice(offset, self.stream.idx).into();
-
-            let (track_classes, track_ids) = (
-                self.options.is_tracking_classes(),
-                self.options.is_tracking_ids(),
-            );
-
-            if let (true, Some(bytes)) = (track_classes, &tag._attributes.class) {
-                let s = bytes
-                    .as_bytes_borrowed()
-                    .and_then(|x| std::str::from_utf8(x).ok())
-                    .map(|x| x.split_ascii_whitespace());
-
-                if let Some(s) = s {
-                    for class in s {
-                        self.classes
-                            .entry(class.into())
-                            .or_insert_with(InlineVec::new)
-                            .push(handle);
-                    }
-                }
-            }
-
-            if let (true, Some(bytes)) = (track_ids, &tag._attributes.id) {
-                self.ids.insert(bytes.clone(), handle);
-            }
+            tag._span.end_tag = Some(Span::new(start, self.stream.idx));
+            tag._descendants.end = end;
         }
     }
 
@@ -298,32 +565,108 @@ impl<'a> Parser<'a> {
             .slice_len(self.stream.idx, 2)
             .eq(constants::COMMENT);
 
+        let is_cdata = self
+            .stream
+            .slice_len(self.stream.idx, constants::CDATA_START.len())
+            .eq(constants::CDATA_START);
+
         if is_comment {
             let comment = self.skip_comment_with_start(start);
+            if comment.is_empty() {
+                self.report(
+                    MarkupError::UnterminatedTag,
+                    Span::new(start, self.stream.idx),
+                );
+            }
+
+            if self.is_skipping()
+                || self.filter(b"#comment", &Attributes::new()) != NodeFilter::Keep
+            {
+                return Some(());
+            }
+
             let comment = self.register_tag(Node::Comment(comment.into()));
             self.add_to_parent(comment);
+        } else if is_cdata {
+            self.stream.advance_by(constants::CDATA_START.len());
+            self.skip_cdata();
+
+            let section = self
+                .stream
+                .slice(start, self.stream.idx.min(self.stream.len()));
+            if !section.ends_with(constants::CDATA_END) {
+                self.report(
+                    MarkupError::UnterminatedTag,
+                    Span::new(start, self.stream.idx),
+                );
+            }
+
+            if self.is_skipping() {
+                return Some(());
+            }
+
+            let section = self.register_tag(Node::CData(section.into()));
+            self.add_to_parent(section);
         } else {
             let tag = self.read_ident()?;
 
             self.skip_whitespaces();
 
             if simd::matches_case_insensitive(tag, *b"doctype") {
-                let doctype = self.read_ident()?;
+                self.read_to(b'>');
+                if self.stream.is_eof() {
+                    self.report(
+                        MarkupError::UnterminatedTag,
+                        Span::new(start, self.stream.idx),
+                    );
+                }
+                self.stream.advance(); // skip >
 
-                let html5 = simd::matches_case_insensitive(doctype, *b"html");
+                let raw = self
+                    .stream
+                    .slice(start, self.stream.idx.min(self.stream.len()));
+                let doctype = Doctype::parse(raw);
 
-                if html5 {
-                    self.version = Some(HTMLVersion::HTML5);
+                if self.is_skipping() {
+                    return Some(());
                 }
 
-                self.skip_whitespaces();
-                self.stream.advance(); // skip >
+                if let Some(version) = doctype.version() {
+                    self.version = Some(version);
+                }
+
+                let doctype = self.register_tag(Node::Doctype(doctype));
+                self.add_to_parent(doctype);
             }
         }
 
         Some(())
     }
 
+    #[cold]
+    #[inline(never)]
+    fn read_processing_instruction(&mut self, start: usize) {
+        while !self.stream.is_eof() {
+            self.read_to(b'?');
+            self.stream.advance();
+
+            if self.stream.current_cpy() == Some(b'>') {
+                self.stream.advance();
+                break;
+            }
+        }
+
+        if self.is_skipping() {
+            return;
+        }
+
+        let instruction = self
+            .stream
+            .slice(start, self.stream.idx.min(self.stream.len()));
+        let instruction = self.register_tag(Node::ProcessingInstruction(instruction.into()));
+        self.add_to_parent(instruction);
+    }
+
     fn parse_tag(&mut self) -> Option<()> {
         let start = self.stream.idx;
 
@@ -332,35 +675,141 @@ impl<'a> Parser<'a> {
         let cur = self.stream.current_cpy()?;
 
         match cur {
-            b'/' => self.read_end(),
+            b'/' => self.read_end(start),
             b'!' => {
                 self.read_markdown();
             }
+            b'?' => {
+                self.read_processing_instruction(start);
+            }
             _ => {
-                let name = self.read_ident()?;
+                let mut name = self.read_ident()?;
+
+                // `/` is an identifier character because of unquoted attribute values,
+                // but at the end of a tag name it belongs to `/>`, like in `<br/>`
+                if let Some(trimmed) = name.strip_suffix(b"/").filter(|name| !name.is_empty()) {
+                    name = trimmed;
+                    self.stream.idx -= 1;
+                }
+
                 self.skip_whitespaces();
 
-                let attr = self.parse_attributes()?;
+                if self.is_skipping() {
+                    return self.skip_start_tag(name);
+                }
+
+                // names are case sensitive in foreign content and XML, except for the names of `<svg>` and `<math>`
+                // elements, which are still parsed as HTML
+                let is_xml = self.options.is_xml_mode();
+                let parent = self.open_element();
+                let parent_namespace = parent.map(|parent| parent._namespace);
+                let children_namespace = parent.map_or(Namespace::Html, Namespace::of_children);
+                let is_foreign_parent = children_namespace.is_foreign();
+                let is_foreign = Namespace::of_child(name, children_namespace).is_foreign();
+                let lowercase =
+                    !self.options.is_preserving_attribute_case() && !is_xml && !is_foreign;
+                let attr = self.parse_attributes(lowercase)?;
+                let element_name = if is_xml || is_foreign_parent {
+                    ElementName::new(name.into())
+                } else {
+                    ElementName::lowercase(name)
+                };
 
                 let is_self_closing = self.stream.expect_and_skip_cond(b'/');
 
                 self.stream.expect_and_skip(b'>')?;
 
-                let this = self.register_tag(Node::Tag(HTMLTag::new(
-                    name.into(),
+                match self.filter(name, &attr) {
+                    NodeFilter::Keep => {}
+                    NodeFilter::SkipNode => {
+                        if !is_self_closing && !self.is_void(name) {
+                            self.skipped_elements.push((self.stack.len(), name));
+                        }
+                        return Some(());
+                    }
+                    NodeFilter::SkipSubtree => {
+                        if !is_self_closing && !self.is_void(name) {
+                            self.skipped_subtree.push(name);
+                        }
+                        return Some(());
+                    }
+                }
+
+                let id = attr.id.clone().filter(|_| self.options.is_tracking_ids());
+
+                let classes = attr
+                    .class
+                    .as_ref()
+                    .filter(|_| self.options.is_tracking_classes())
+                    .and_then(Bytes::as_bytes_borrowed)
+                    .and_then(|class| std::str::from_utf8(class).ok())
+                    .map(ClassList::new);
+
+                let namespace = if is_xml {
+                    let xmlns = attr.get("xmlns").flatten().map(Bytes::as_bytes);
+                    Namespace::of_xml_element(xmlns, parent_namespace)
+                } else {
+                    Namespace::of_child(name, children_namespace)
+                };
+
+                let tracked_name = self
+                    .options
+                    .is_tracking_tags()
+                    .then(|| element_name.bytes().clone());
+                let mut tag = HTMLTag::new(
+                    element_name,
                     attr,
                     InlineVec::new(),
                     self.get_parent(),
                     self.stream.slice(start, self.stream.idx).into(),
-                )));
-
-                self.add_to_parent(this);
+                    Span::new(start, self.stream.idx),
+                );
+                // the descendants are the nodes registered until this tag is closed, see `read_end`
+                let first_descendant = self.tags.len() as u32 + 1;
+                tag._descendants = first_descendant..first_descendant;
+                tag._namespace = namespace;
 
                 // some tags are self closing, so even though there might not be a /,
                 // we don't always want to push them to the stack
                 // e.g. <br><p>Hello</p>
                 // <p> should not be a subtag of <br>
-                if !is_self_closing && !constants::VOID_TAGS.contains(&name) {
+                // foreign elements and XML have no void elements and need a / or an end tag instead
+                let is_html = !namespace.is_foreign() && !self.options.is_xml_mode();
+                let is_html_void = is_html && constants::VOID_TAGS.contains(&tag._name.as_bytes());
+                tag._void = is_html && !is_html_void && self.options.is_custom_void(name);
+                let is_void = is_html_void || tag._void;
+
+                let this = self.register_tag(Node::Tag(tag));
+
+                self.add_to_parent(this);
+
+                if let Some(name) = tracked_name {
+                    self.tag_names.entry(name).or_default().push(this);
+                }
+
+                if name.eq_ignore_ascii_case(b"template") && !self.options.is_xml_mode() {
+                    self.templates.push(this);
+                }
+
+                // IDs and classes are registered here rather than in `read_end`, so that elements without an end tag
+                // are found too and the lookup tables are in document order
+                if let Some(id) = id {
+                    self.ids.entry(id).or_insert_with(InlineVec::new).push(this);
+                }
+
+                for class in classes.into_iter().flatten() {
+                    self.classes
+                        .entry(class.into())
+                        .or_insert_with(InlineVec::new)
+                        .push(this);
+                }
+
+                // most documents are parsed without tracked attributes, so their values are not even looked up then
+                if self.options.is_tracking_any_attribute() {
+                    self.track_attribute_values(this);
+                }
+
+                if !is_self_closing && !is_void {
                     self.stack.push(this);
                 }
             }
@@ -369,18 +818,346 @@ impl<'a> Parser<'a> {
         Some(())
     }
 
+    /// Adds the given element to the lookup table of the values of the tracked attributes that it has
+    fn track_attribute_values(&mut self, handle: NodeHandle) {
+        let Some(tag) = self.tags[handle.get_inner() as usize].as_tag() else {
+            return;
+        };
+
+        let values = self
+            .options
+            .tracked_attributes()
+            .filter_map(|key| Some((key, tag._attributes.get(key)??.clone())))
+            .collect::<Vec<_>>();
+
+        for key in values {
+            self.attribute_values.entry(key).or_default().push(handle);
+        }
+    }
+
+    /// Checks whether the parser is inside of a subtree that is left out because of [`NodeFilter::SkipSubtree`]
+    #[inline]
+    fn is_skipping(&self) -> bool {
+        !self.skipped_subtree.is_empty()
+    }
+
+    /// Decides whether a node is kept with the callback of [`ParserOptions::node_filter`]
+    fn filter(&self, name: &[u8], attributes: &Attributes<'a>) -> NodeFilter {
+        match self.options.get_node_filter() {
+            Some(filter) => filter(&String::from_utf8_lossy(name), attributes),
+            None => NodeFilter::Keep,
+        }
+    }
+
+    /// Checks whether an element that is added to the open element has no content, like `<br>`
+    fn is_void(&self, name: &[u8]) -> bool {
+        let is_foreign = Namespace::of_element(name, self.open_element()).is_foreign()
+            || self.skipped_subtree.iter().any(|name| {
+                name.eq_ignore_ascii_case(b"svg") || name.eq_ignore_ascii_case(b"math")
+            });
+
+        !is_foreign
+            && !self.options.is_xml_mode()
+            && (constants::VOID_TAGS
+                .iter()
+                .any(|tag| tag.eq_ignore_ascii_case(name))
+                || self.options.is_custom_void(name))
+    }
+
+    /// Reads the rest of a start tag inside of a skipped subtree, without creating its attributes
+    fn skip_start_tag(&mut self, name: &'a [u8]) -> Option<()> {
+        self.scan_attributes(|_, _, _| {})?;
+
+        let is_self_closing = self.stream.expect_and_skip_cond(b'/');
+        self.stream.expect_and_skip(b'>')?;
+
+        if !is_self_closing && !self.is_void(name) {
+            self.skipped_subtree.push(name);
+        }
+
+        Some(())
+    }
+
+    /// Parses the next tag or text
     pub(crate) fn parse_single(&mut self) -> Option<()> {
-        loop {
-            let cur = self.stream.current()?;
+        if let Some(text) = self.read_noscript_text() {
+            let handle = self.register_tag(Node::Raw(text.into()));
+            self.add_to_parent(handle);
+            return Some(());
+        }
 
-            if *cur == b'<' {
-                self.parse_tag();
-            } else {
-                let raw = Node::Raw(self.read_to(b'<').into());
-                let handle = self.register_tag(raw);
-                self.add_to_parent(handle);
+        let cur = self.stream.current()?;
+
+        if *cur == b'<' {
+            let start = self.stream.idx;
+            if self.parse_tag().is_none() {
+                let span = Span::new(start, self.stream.idx);
+                self.report(MarkupError::UnterminatedTag, span);
+            }
+        } else {
+            let text = self.read_to(b'<');
+
+            if self.is_skipping() {
+                return Some(());
+            }
+
+            if self.options.is_skipping_whitespace_text()
+                && text.iter().all(u8::is_ascii_whitespace)
+                && !self.in_preformatted()
+            {
+                return Some(());
             }
+
+            let handle = self.register_tag(Node::Raw(text.into()));
+            self.add_to_parent(handle);
         }
+
+        Some(())
+    }
+
+    /// Reads the content of an open `<noscript>` element up to its end tag as text,
+    /// unless its content is parsed as markup, see [`ParserOptions::parse_noscript_content`]
+    fn read_noscript_text(&mut self) -> Option<&'a [u8]> {
+        if self.options.is_parsing_noscript_content() || self.options.is_xml_mode() {
+            return None;
+        }
+
+        let open = self.open_element()?;
+        if open._namespace != Namespace::Html
+            || !open._name.as_bytes().eq_ignore_ascii_case(b"noscript")
+        {
+            return None;
+        }
+
+        let start = self.stream.idx;
+        let data = self.stream.data();
+        // without an end tag, the text goes until the end of the input
+        let mut end = data.len();
+        let mut position = start;
+
+        while let Some(offset) = simd::find(&data[position..], b'<') {
+            position += offset;
+            let name = data.get(position + 2..position + 2 + b"noscript".len());
+            let after = data.get(position + 2 + b"noscript".len());
+
+            if data.get(position + 1) == Some(&b'/')
+                && name.is_some_and(|name| name.eq_ignore_ascii_case(b"noscript"))
+                // like in `read_end`, the name of the end tag goes until `>`
+                && after.is_none_or(|&c| c == b'>')
+            {
+                end = position;
+                break;
+            }
+
+            position += 1;
+        }
+
+        if end == start {
+            return None;
+        }
+
+        self.stream.idx = end;
+        Some(self.stream.slice(start, end))
+    }
+
+    /// Merges adjacent text nodes and removes empty ones from every list of children, see [`VDom::normalize`](crate::VDom::normalize)
+    pub(crate) fn normalize(&mut self) {
+        self.generation = next_generation();
+        self.modified = true;
+
+        let top = std::mem::take(&mut self.ast);
+        self.ast = merge_text_nodes(&mut self.tags, &top);
+
+        for index in 0..self.tags.len() {
+            let children = match &mut self.tags[index] {
+                Node::Tag(tag) => std::mem::replace(&mut tag._children, InlineVec::new()),
+                _ => continue,
+            };
+
+            let merged = merge_text_nodes(&mut self.tags, children.as_slice());
+
+            if let Node::Tag(tag) = &mut self.tags[index] {
+                if merged.len() == children.len() {
+                    tag._children = children;
+                } else {
+                    for handle in merged {
+                        tag._children.push(handle);
+                    }
+                }
+            }
+        }
+
+        // merged text nodes are removed from the lists of children, which moves their siblings
+        self.siblings = OnceLock::new();
+    }
+
+    /// Checks whether one of the open elements is an element whose whitespace is part of its content, like `<pre>`
+    fn in_preformatted(&self) -> bool {
+        self.stack.iter().any(|handle| {
+            handle
+                .get(self)
+                .and_then(Node::as_tag)
+                .is_some_and(|tag| is_one_of(tag.name().as_bytes(), PREFORMATTED_ELEMENTS))
+        })
+    }
+
+    /// Returns the parent of the given node, or `None` if it is one of the topmost nodes
+    #[inline]
+    pub(crate) fn parent_of(&self, handle: NodeHandle) -> Option<NodeHandle> {
+        self.siblings()
+            .parents
+            .get(handle.get_inner() as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the list of nodes that the given node is part of, i.e. the children of its parent
+    pub(crate) fn siblings_of(&self, handle: NodeHandle) -> &[NodeHandle] {
+        match self.parent_of(handle) {
+            Some(parent) => parent
+                .get(self)
+                .and_then(Node::as_tag)
+                .map_or(&[], |tag| tag._children.as_slice()),
+            None => &self.ast,
+        }
+    }
+
+    /// Returns the position of the given node among its siblings
+    ///
+    /// This is a constant time lookup, unless the nodes were borrowed mutably since parsing.
+    pub(crate) fn position_of(&self, handle: NodeHandle) -> Option<SiblingPosition> {
+        if self.has_positions() {
+            return self
+                .siblings()
+                .positions
+                .get(handle.get_inner() as usize)
+                .copied()
+                .filter(|&position| position != SiblingPosition::DETACHED);
+        }
+
+        let siblings = self.siblings_of(handle);
+        let index = siblings
+            .iter()
+            .position(|sibling| sibling.get_inner() == handle.get_inner())?;
+        let element_index = siblings[..index]
+            .iter()
+            .filter(|sibling| matches!(sibling.get(self), Some(Node::Tag(_))))
+            .count();
+
+        Some(SiblingPosition {
+            index: index as u32,
+            element_index: element_index as u32,
+        })
+    }
+
+    /// Checks whether the positions of the nodes among their siblings are known, i.e. the nodes were not mutated
+    #[inline]
+    pub(crate) fn has_positions(&self) -> bool {
+        self.siblings().positions.len() == self.tags.len()
+    }
+
+    /// Returns the handle of a node of this parser, or of a value inside of such a node like an [`HTMLTag`]
+    ///
+    /// The handle is computed from the address of the node, so this returns `None` for values that are not part of this parser.
+    pub(crate) fn handle_of<T>(&self, value: &T) -> Option<NodeHandle> {
+        let offset = (value as *const T as usize).checked_sub(self.tags.as_ptr() as usize)?;
+        let index = offset / std::mem::size_of::<Node<'_>>();
+
+        (index < self.tags.len()).then(|| self.handle(index as u32))
+    }
+
+    /// Clears the positions of the nodes among their siblings and marks the lookup tables as outdated,
+    /// because the nodes are about to be mutated
+    #[inline]
+    pub(crate) fn invalidate_positions(&mut self) {
+        if let Some(siblings) = self.siblings.get_mut() {
+            siblings.positions.clear();
+        }
+        self.tables_outdated = true;
+        self.modified = true;
+        self.generation = next_generation();
+    }
+
+    /// Returns a number that identifies this document and its current state
+    ///
+    /// The number is unique among all documents, and changes whenever the nodes may have been mutated,
+    /// i.e. when they are borrowed mutably. Caches of results computed from a document can store it
+    /// to find out whether they are still valid.
+    #[inline]
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the number that identifies this document in the handles of its nodes
+    #[inline]
+    pub(crate) fn document(&self) -> u32 {
+        self.document
+    }
+
+    /// Returns a handle to the node at the given index, which belongs to this document
+    #[inline]
+    pub(crate) fn handle(&self, index: InnerNodeHandle) -> NodeHandle {
+        NodeHandle::in_document(index, self.document)
+    }
+
+    /// Checks whether a handle belongs to this document and points to one of its nodes
+    ///
+    /// Handles of other documents would resolve to unrelated nodes, so [`NodeHandle::get`] returns `None` for them.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Hello</p>", Default::default()).unwrap();
+    /// let p = dom.children()[0];
+    /// assert!(dom.parser().contains(p));
+    ///
+    /// let dom = dom.reparse("<p>World</p>").unwrap();
+    /// assert!(!dom.parser().contains(p));
+    /// assert!(dom.parser().contains(dom.children()[0]));
+    /// ```
+    pub fn contains(&self, handle: NodeHandle) -> bool {
+        handle.belongs_to(self) && (handle.get_inner() as usize) < self.tags.len()
+    }
+
+    /// Returns the parents and sibling positions of all nodes, which are computed from the current lists of children
+    /// the first time that they are needed
+    #[inline]
+    pub(crate) fn siblings(&self) -> &Siblings {
+        self.siblings.get_or_init(|| self.index_siblings())
+    }
+
+    /// Computes the parents and the positions among their siblings of all nodes from the current lists of children
+    fn index_siblings(&self) -> Siblings {
+        let mut parents = vec![None; self.tags.len()];
+        let mut positions = vec![SiblingPosition::DETACHED; self.tags.len()];
+
+        let lists = std::iter::once((None, self.ast.as_slice())).chain(
+            self.tags.iter().enumerate().filter_map(|(index, node)| {
+                let tag = node.as_tag()?;
+                Some((Some(self.handle(index as u32)), tag._children.as_slice()))
+            }),
+        );
+
+        for (parent, siblings) in lists {
+            let mut element_index = 0;
+
+            for (index, handle) in siblings.iter().enumerate() {
+                let inner = handle.get_inner() as usize;
+
+                if let (Some(slot), Some(position)) =
+                    (parents.get_mut(inner), positions.get_mut(inner))
+                {
+                    *slot = parent;
+                    *position = SiblingPosition {
+                        index: index as u32,
+                        element_index,
+                    };
+                }
+
+                element_index += matches!(self.tags.get(inner), Some(Node::Tag(_))) as u32;
+            }
+        }
+
+        Siblings { parents, positions }
     }
 
     /// Resolves an internal Node ID obtained from a NodeHandle to a Node
@@ -392,18 +1169,181 @@ impl<'a> Parser<'a> {
     /// Resolves an internal Node ID obtained from a NodeHandle to a Node
     #[inline]
     pub fn resolve_node_id_mut(&mut self, id: InnerNodeHandle) -> Option<&mut Node<'a>> {
+        self.invalidate_positions();
         self.tags.get_mut(id as usize)
     }
 
     pub(crate) fn parse(&mut self) -> Result<(), ParseError> {
+        self.parse_with(|_, _, _| {})
+    }
+
+    /// Parses the input like [`Parser::parse`], and calls `step` after every token
+    ///
+    /// `step` gets the number of nodes and of open elements from before the token, so that it can inspect the new nodes.
+    pub(crate) fn parse_with(
+        &mut self,
+        mut step: impl FnMut(&mut Self, usize, usize),
+    ) -> Result<(), ParseError> {
         if self.stream.len() > u32::MAX as usize {
             return Err(ParseError::InvalidLength);
         }
 
+        let deadline = self
+            .options
+            .get_time_budget()
+            .map(|budget| Instant::now() + budget);
+        let mut steps = 0u32;
+        let is_strict = self.options.is_strict();
+
         while !self.stream.is_eof() {
+            let (nodes, depth) = (self.tags.len(), self.stack.len());
             self.parse_single();
+            step(self, nodes, depth);
+
+            if is_strict {
+                if let Some((error, span)) = self.markup_error {
+                    return Err(ParseError::InvalidMarkup { error, span });
+                }
+            }
+
+            // reading the clock is comparatively slow, so it is only done every few tags
+            if let Some(deadline) = deadline {
+                steps = steps.wrapping_add(1);
+                if steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+                    return Err(ParseError::TimeBudgetExceeded);
+                }
+            }
+        }
+
+        if self.options.is_strict() {
+            let is_xml = self.options.is_xml_mode();
+            let unclosed = self
+                .stack
+                .iter()
+                .filter_map(|handle| self.tags.get(handle.get_inner() as usize)?.as_tag())
+                .find(|tag| {
+                    is_xml
+                        || tag._namespace != Namespace::Html
+                        || !is_one_of(tag._name.as_bytes(), constants::OPTIONAL_END_TAGS)
+                });
+
+            if let Some(tag) = unclosed {
+                return Err(ParseError::InvalidMarkup {
+                    error: MarkupError::UnclosedElement,
+                    span: tag._span.start_tag,
+                });
+            }
+        }
+
+        // elements without an end tag contain everything up to the end of the document
+        let end = self.tags.len() as u32;
+        for handle in self.stack.drain(..) {
+            if let Some(tag) = self
+                .tags
+                .get_mut(handle.get_inner() as usize)
+                .and_then(Node::as_tag_mut)
+            {
+                tag._descendants.end = end;
+            }
         }
 
         Ok(())
     }
 }
+
+/// Clears a vector and reuses its allocation for a type that only differs in lifetimes
+///
+/// Collecting an iterator of a vector into a vector of a type with the same layout happens in place,
+/// so this does not allocate.
+pub(crate) fn recycle<T, U>(mut vec: Vec<T>) -> Vec<U> {
+    vec.clear();
+    vec.into_iter().map(|_| unreachable!()).collect()
+}
+
+/// Returns a generation number that has not been used by any parser yet, see [`Parser::generation`]
+fn next_generation() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns a number that identifies a new document in the handles of its nodes, see [`NodeHandle`]
+///
+/// 0 is never used, so that handles can store the number in a `NonZeroU32`, and `u32::MAX` is reserved for handles
+/// that work with every document.
+fn next_document() -> u32 {
+    static NEXT: AtomicU32 = AtomicU32::new(1);
+
+    loop {
+        let document = NEXT.fetch_add(1, Ordering::Relaxed);
+        if document != 0 && document != u32::MAX {
+            return document;
+        }
+    }
+}
+
+/// Appends the content of text nodes to the text node before them and leaves them empty,
+/// and returns the children without the empty text nodes
+///
+/// Merged nodes stay in the list of all nodes, so they are emptied to not show up twice in the text of their parent.
+fn merge_text_nodes(nodes: &mut [Node<'_>], children: &[NodeHandle]) -> Vec<NodeHandle> {
+    let mut kept: Vec<NodeHandle> = Vec::with_capacity(children.len());
+
+    for &handle in children {
+        let index = handle.get_inner() as usize;
+        let Some(text) = nodes.get(index).and_then(Node::as_raw) else {
+            kept.push(handle);
+            continue;
+        };
+
+        if text.as_bytes().is_empty() {
+            continue;
+        }
+
+        let previous = kept.last().map(|previous| previous.get_inner() as usize);
+        let previous_text = previous.and_then(|previous| nodes.get(previous)?.as_raw());
+
+        if let (Some(previous), Some(previous_text)) = (previous, previous_text) {
+            let mut merged = previous_text.as_bytes().to_vec();
+            merged.extend_from_slice(text.as_bytes());
+
+            let is_merged = nodes[previous]
+                .as_raw_mut()
+                .is_some_and(|previous| previous.set(merged).is_ok());
+
+            if is_merged {
+                if let Some(text) = nodes[index].as_raw_mut() {
+                    *text = Bytes::new();
+                }
+                continue;
+            }
+        }
+
+        kept.push(handle);
+    }
+
+    kept
+}
+
+/// Returns a tag or attribute name in lowercase, which is only copied if it contains uppercase letters
+pub(crate) fn lowercase_name(name: &[u8]) -> Bytes<'_> {
+    let mut bytes = Bytes::from(name);
+
+    if name.iter().any(u8::is_ascii_uppercase) {
+        // SAFETY: the name is part of the input, whose length fits into a `u32`
+        unsafe { bytes.set_unchecked(name.to_ascii_lowercase()) };
+    }
+
+    bytes
+}
+
+/// Returns an attribute name in lowercase, which is only copied if it contains uppercase letters and is not a known name
+#[inline]
+fn lowercase_attribute_name(name: &[u8]) -> Bytes<'_> {
+    if name.iter().any(u8::is_ascii_uppercase) {
+        if let Some(known) = known_attribute(name) {
+            return known;
+        }
+    }
+
+    lowercase_name(name)
+}
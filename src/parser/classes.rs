@@ -0,0 +1,86 @@
+use std::str::SplitAsciiWhitespace;
+
+/// A set-like view of the class names of an HTML element
+///
+/// Class names are separated by ASCII whitespace. Each class name is only yielded once, in the order of its first occurrence,
+/// so `class="a b a"` contains the two classes `a` and `b`. The tokens of other attributes, like `rel`, follow the same rules,
+/// see [`Attributes::get_list`](crate::Attributes::get_list).
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(r#"<div class="card  card--wide card"></div>"#, Default::default()).unwrap();
+/// let classes = dom.nodes()[0].as_tag().unwrap().attributes().classes();
+///
+/// assert_eq!(classes.len(), 2);
+/// assert!(classes.contains("card--wide"));
+/// assert!(!classes.contains("card--"));
+/// assert_eq!(classes.iter().collect::<Vec<_>>(), ["card", "card--wide"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassList<'b> {
+    class: &'b str,
+}
+
+impl<'b> ClassList<'b> {
+    /// Creates a view of the given whitespace separated class names
+    #[inline]
+    pub(crate) fn new(class: &'b str) -> Self {
+        Self { class }
+    }
+
+    /// Checks whether the given class name is in this list
+    pub fn contains<B: AsRef<[u8]>>(&self, class: B) -> bool {
+        let class = class.as_ref();
+        self.class
+            .split_ascii_whitespace()
+            .any(|c| c.as_bytes() == class)
+    }
+
+    /// Returns the number of distinct class names in this list
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks whether this list has no class names
+    pub fn is_empty(&self) -> bool {
+        self.class.split_ascii_whitespace().next().is_none()
+    }
+
+    /// Returns an iterator over the distinct class names in this list
+    pub fn iter(&self) -> ClassIter<'b> {
+        ClassIter {
+            class: self.class,
+            split: self.class.split_ascii_whitespace(),
+        }
+    }
+}
+
+impl<'b> IntoIterator for ClassList<'b> {
+    type Item = &'b str;
+    type IntoIter = ClassIter<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the distinct class names of a [`ClassList`], or the tokens of [`Attributes::get_list`](crate::Attributes::get_list)
+#[derive(Debug, Clone)]
+pub struct ClassIter<'b> {
+    class: &'b str,
+    split: SplitAsciiWhitespace<'b>,
+}
+
+impl<'b> Iterator for ClassIter<'b> {
+    type Item = &'b str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let class = self.class;
+
+        self.split.by_ref().find(|&name| {
+            // class lists are short, so looking for an earlier occurrence is cheaper than keeping a set around
+            let offset = name.as_ptr() as usize - class.as_ptr() as usize;
+            !class[..offset].split_ascii_whitespace().any(|c| c == name)
+        })
+    }
+}
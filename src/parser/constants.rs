@@ -1,6 +1,306 @@
 pub const COMMENT: &[u8; 2] = b"--";
 pub const COMMENT_END: &[u8; 3] = b"-->";
+pub const CDATA_START: &[u8; 7] = b"[CDATA[";
+pub const CDATA_END: &[u8; 3] = b"]]>";
 pub const VOID_TAGS: &[&[u8]; 15] = &[
     b"area", b"base", b"br", b"col", b"embed", b"hr", b"img", b"input", b"keygen", b"link",
     b"meta", b"param", b"source", b"track", b"wbr",
 ];
+/// Elements whose end tag may be omitted, which are therefore not reported as unclosed by the linter and strict mode
+pub const OPTIONAL_END_TAGS: &[&str] = &[
+    "html", "head", "body", "p", "li", "dt", "dd", "option", "optgroup", "rb", "rt", "rp", "rtc",
+    "tr", "td", "th", "thead", "tbody", "tfoot", "colgroup", "caption",
+];
+/// Common attribute names, which are interned while parsing so that names in uppercase are not copied
+pub const KNOWN_ATTRIBUTES: &[&[u8]] = &[
+    b"accept",
+    b"accept-charset",
+    b"accesskey",
+    b"action",
+    b"align",
+    b"allow",
+    b"alt",
+    b"async",
+    b"autocapitalize",
+    b"autocomplete",
+    b"autofocus",
+    b"autoplay",
+    b"background",
+    b"bgcolor",
+    b"border",
+    b"charset",
+    b"checked",
+    b"cite",
+    b"color",
+    b"cols",
+    b"colspan",
+    b"content",
+    b"contenteditable",
+    b"controls",
+    b"coords",
+    b"crossorigin",
+    b"data",
+    b"datetime",
+    b"decoding",
+    b"default",
+    b"defer",
+    b"dir",
+    b"dirname",
+    b"disabled",
+    b"download",
+    b"draggable",
+    b"enctype",
+    b"enterkeyhint",
+    b"for",
+    b"form",
+    b"formaction",
+    b"headers",
+    b"height",
+    b"hidden",
+    b"high",
+    b"href",
+    b"hreflang",
+    b"http-equiv",
+    b"inert",
+    b"inputmode",
+    b"integrity",
+    b"is",
+    b"itemid",
+    b"itemprop",
+    b"itemref",
+    b"itemscope",
+    b"itemtype",
+    b"kind",
+    b"label",
+    b"lang",
+    b"language",
+    b"list",
+    b"loading",
+    b"loop",
+    b"low",
+    b"max",
+    b"maxlength",
+    b"media",
+    b"method",
+    b"min",
+    b"minlength",
+    b"multiple",
+    b"muted",
+    b"name",
+    b"nonce",
+    b"novalidate",
+    b"onblur",
+    b"onchange",
+    b"onclick",
+    b"onerror",
+    b"onfocus",
+    b"oninput",
+    b"onkeydown",
+    b"onkeyup",
+    b"onload",
+    b"onmouseout",
+    b"onmouseover",
+    b"onsubmit",
+    b"open",
+    b"optimum",
+    b"pattern",
+    b"ping",
+    b"placeholder",
+    b"playsinline",
+    b"popover",
+    b"poster",
+    b"preload",
+    b"readonly",
+    b"referrerpolicy",
+    b"rel",
+    b"required",
+    b"reversed",
+    b"role",
+    b"rows",
+    b"rowspan",
+    b"sandbox",
+    b"scope",
+    b"selected",
+    b"shape",
+    b"size",
+    b"sizes",
+    b"slot",
+    b"span",
+    b"spellcheck",
+    b"src",
+    b"srcdoc",
+    b"srclang",
+    b"srcset",
+    b"start",
+    b"step",
+    b"style",
+    b"tabindex",
+    b"target",
+    b"title",
+    b"translate",
+    b"type",
+    b"usemap",
+    b"valign",
+    b"value",
+    b"width",
+    b"wrap",
+    b"xmlns",
+];
+/// The names of all current and obsolete HTML elements, so that other elements can be recognized as unknown
+pub const KNOWN_ELEMENTS: &[&[u8]] = &[
+    b"a",
+    b"abbr",
+    b"acronym",
+    b"address",
+    b"applet",
+    b"area",
+    b"article",
+    b"aside",
+    b"audio",
+    b"b",
+    b"base",
+    b"basefont",
+    b"bdi",
+    b"bdo",
+    b"bgsound",
+    b"big",
+    b"blink",
+    b"blockquote",
+    b"body",
+    b"br",
+    b"button",
+    b"canvas",
+    b"caption",
+    b"center",
+    b"cite",
+    b"code",
+    b"col",
+    b"colgroup",
+    b"command",
+    b"data",
+    b"datalist",
+    b"dd",
+    b"del",
+    b"details",
+    b"dfn",
+    b"dialog",
+    b"dir",
+    b"div",
+    b"dl",
+    b"dt",
+    b"em",
+    b"embed",
+    b"fieldset",
+    b"figcaption",
+    b"figure",
+    b"font",
+    b"footer",
+    b"form",
+    b"frame",
+    b"frameset",
+    b"h1",
+    b"h2",
+    b"h3",
+    b"h4",
+    b"h5",
+    b"h6",
+    b"head",
+    b"header",
+    b"hgroup",
+    b"hr",
+    b"html",
+    b"i",
+    b"iframe",
+    b"image",
+    b"img",
+    b"input",
+    b"ins",
+    b"isindex",
+    b"kbd",
+    b"keygen",
+    b"label",
+    b"legend",
+    b"li",
+    b"link",
+    b"listing",
+    b"main",
+    b"map",
+    b"mark",
+    b"marquee",
+    b"math",
+    b"menu",
+    b"menuitem",
+    b"meta",
+    b"meter",
+    b"multicol",
+    b"nav",
+    b"nextid",
+    b"nobr",
+    b"noembed",
+    b"noframes",
+    b"noscript",
+    b"object",
+    b"ol",
+    b"optgroup",
+    b"option",
+    b"output",
+    b"p",
+    b"param",
+    b"picture",
+    b"plaintext",
+    b"pre",
+    b"progress",
+    b"q",
+    b"rb",
+    b"rp",
+    b"rt",
+    b"rtc",
+    b"ruby",
+    b"s",
+    b"samp",
+    b"script",
+    b"search",
+    b"section",
+    b"select",
+    b"slot",
+    b"small",
+    b"source",
+    b"span",
+    b"strike",
+    b"strong",
+    b"style",
+    b"sub",
+    b"summary",
+    b"sup",
+    b"svg",
+    b"table",
+    b"tbody",
+    b"td",
+    b"template",
+    b"textarea",
+    b"tfoot",
+    b"th",
+    b"thead",
+    b"time",
+    b"title",
+    b"tr",
+    b"track",
+    b"tt",
+    b"u",
+    b"ul",
+    b"var",
+    b"video",
+    b"wbr",
+    b"xmp",
+];
+/// Names that contain a hyphen, but are reserved by SVG and MathML and therefore cannot be custom element names
+pub const RESERVED_CUSTOM_ELEMENT_NAMES: &[&[u8]] = &[
+    b"annotation-xml",
+    b"color-profile",
+    b"font-face",
+    b"font-face-src",
+    b"font-face-uri",
+    b"font-face-format",
+    b"font-face-name",
+    b"missing-glyph",
+];
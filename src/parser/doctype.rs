@@ -0,0 +1,154 @@
+use crate::Bytes;
+
+use super::HTMLVersion;
+
+/// The public identifiers of the HTML 4.01 doctypes and their versions
+const HTML401_PUBLIC_IDS: &[(&[u8], HTMLVersion)] = &[
+    (b"-//W3C//DTD HTML 4.01//EN", HTMLVersion::StrictHTML401),
+    (
+        b"-//W3C//DTD HTML 4.01 Transitional//EN",
+        HTMLVersion::TransitionalHTML401,
+    ),
+    (
+        b"-//W3C//DTD HTML 4.01 Frameset//EN",
+        HTMLVersion::FramesetHTML401,
+    ),
+];
+
+/// A document type declaration, like `<!DOCTYPE html>`
+///
+/// The name and identifiers are returned as written, so that quirks mode can be determined from them
+/// and the original declaration is kept when the document is written back with [`Node::outer_html`](crate::Node::outer_html).
+///
+/// # Example
+/// ```
+/// let input = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd"><p>Hi</p>"#;
+/// let dom = tl::parse(input, Default::default()).unwrap();
+/// let doctype = dom.doctype().unwrap();
+///
+/// assert_eq!(doctype.name().unwrap(), "html");
+/// assert_eq!(doctype.public_id().unwrap(), "-//W3C//DTD HTML 4.01//EN");
+/// assert_eq!(doctype.system_id().unwrap(), "http://www.w3.org/TR/html4/strict.dtd");
+/// assert_eq!(dom.version(), Some(tl::HTMLVersion::StrictHTML401));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Doctype<'a> {
+    pub(crate) _raw: Bytes<'a>,
+    _name: Option<Bytes<'a>>,
+    _public_id: Option<Bytes<'a>>,
+    _system_id: Option<Bytes<'a>>,
+}
+
+impl<'a> Doctype<'a> {
+    /// Parses a whole declaration, from `<!` to the closing `>`
+    ///
+    /// Like in browsers, identifiers without quotes are ignored and a missing closing quote ends the identifier at the `>`.
+    pub(crate) fn parse(raw: &'a [u8]) -> Self {
+        let inner = raw.strip_prefix(b"<!").unwrap_or(raw);
+        let inner = inner.strip_suffix(b">").unwrap_or(inner);
+        // skip the `DOCTYPE` keyword
+        let inner = inner.get(b"doctype".len()..).unwrap_or_default();
+
+        let (name, rest) = word(inner);
+        let (keyword, rest) = word(rest);
+
+        let (public_id, system_id) = match keyword {
+            Some(k) if k.eq_ignore_ascii_case(b"public") => {
+                let (public_id, rest) = quoted(rest);
+                let system_id = public_id.and_then(|_| quoted(rest).0);
+                (public_id, system_id)
+            }
+            Some(k) if k.eq_ignore_ascii_case(b"system") => (None, quoted(rest).0),
+            _ => (None, None),
+        };
+
+        Self {
+            _raw: raw.into(),
+            _name: name.map(Bytes::from),
+            _public_id: public_id.map(Bytes::from),
+            _system_id: system_id.map(Bytes::from),
+        }
+    }
+
+    /// Copies this declaration into one that owns its data, see [`Bytes::to_static`]
+    pub(crate) fn to_static(&self) -> Doctype<'static> {
+        Doctype {
+            _raw: self._raw.to_static(),
+            _name: self._name.as_ref().map(Bytes::to_static),
+            _public_id: self._public_id.as_ref().map(Bytes::to_static),
+            _system_id: self._system_id.as_ref().map(Bytes::to_static),
+        }
+    }
+
+    /// Returns the whole declaration, like `<!DOCTYPE html>`
+    #[inline]
+    pub fn raw(&self) -> &Bytes<'a> {
+        &self._raw
+    }
+
+    /// Returns the name of the document type, like `html`, or `None` if the declaration has no name
+    #[inline]
+    pub fn name(&self) -> Option<&Bytes<'a>> {
+        self._name.as_ref()
+    }
+
+    /// Returns the public identifier, like `-//W3C//DTD HTML 4.01//EN`, without its quotes
+    #[inline]
+    pub fn public_id(&self) -> Option<&Bytes<'a>> {
+        self._public_id.as_ref()
+    }
+
+    /// Returns the system identifier, like `http://www.w3.org/TR/html4/strict.dtd`, without its quotes
+    #[inline]
+    pub fn system_id(&self) -> Option<&Bytes<'a>> {
+        self._system_id.as_ref()
+    }
+
+    /// Returns the HTML version that this declaration stands for, if it is HTML5 or HTML 4.01
+    pub fn version(&self) -> Option<HTMLVersion> {
+        if !self
+            .name()
+            .is_some_and(|name| name.as_bytes().eq_ignore_ascii_case(b"html"))
+        {
+            return None;
+        }
+
+        match self.public_id() {
+            Some(public_id) => HTML401_PUBLIC_IDS
+                .iter()
+                .find(|(id, _)| id.eq_ignore_ascii_case(public_id.as_bytes()))
+                .map(|&(_, version)| version),
+            None => Some(HTMLVersion::HTML5),
+        }
+    }
+}
+
+/// Splits off the first whitespace separated word
+fn word(input: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    let input = input.trim_ascii_start();
+    let end = input
+        .iter()
+        .position(u8::is_ascii_whitespace)
+        .unwrap_or(input.len());
+
+    let (word, rest) = input.split_at(end);
+    ((!word.is_empty()).then_some(word), rest)
+}
+
+/// Splits off a quoted identifier and returns it without its quotes
+fn quoted(input: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    let input = input.trim_ascii_start();
+
+    match input.first() {
+        Some(&quote @ (b'"' | b'\'')) => {
+            let input = &input[1..];
+            let end = input
+                .iter()
+                .position(|&b| b == quote)
+                .unwrap_or(input.len());
+            let rest = input.get(end + 1..).unwrap_or_default();
+            (Some(&input[..end]), rest)
+        }
+        _ => (None, input),
+    }
+}
@@ -1,3 +1,9 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::num::{NonZeroU32, ParseIntError};
+use std::str::FromStr;
+
 use crate::Node;
 
 use super::Parser;
@@ -15,38 +21,262 @@ pub type InnerNodeHandle = u32;
 /// of nodes, and store indices into the vector instead of references.
 /// In the case of tl, the "global" HTML tag vector is stored in the [`Parser`] and [`NodeHandle`] represents the index.
 /// Because [`NodeHandle`] is only an index and completely detached from anything, you need to pass a parser to `NodeHandle::get()`
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[repr(transparent)]
-pub struct NodeHandle(InnerNodeHandle);
+///
+/// # Documents
+/// Handles that are returned by a document also remember which document they belong to, so that using them with
+/// another document returns `None` instead of an unrelated node that happens to have the same index.
+/// Parsing a document again, e.g. with [`VDom::reparse`](crate::VDom::reparse), creates a new document.
+/// Handles that are created with [`NodeHandle::new`] belong to no particular document and work with every document.
+/// Handles are compared and hashed by their index only, like before they remembered their document, so a handle
+/// returned by a document is equal to [`NodeHandle::new`] with the same index, and so are the handles of two documents
+/// that point to the same index. Use [`Parser::contains`] to find out whether a handle belongs to a document.
+///
+/// # Example
+/// ```
+/// let a = tl::parse("<p>a</p>", Default::default()).unwrap();
+/// let b = tl::parse("<p>b</p>", Default::default()).unwrap();
+///
+/// let p = a.children()[0];
+/// assert!(a.parser().contains(p));
+/// assert!(!b.parser().contains(p));
+/// assert!(p.get(b.parser()).is_none());
+///
+/// // handles without a document work everywhere, and are equal to the handles of the same index
+/// let first = tl::NodeHandle::new(0);
+/// assert_eq!(first, p);
+/// assert_eq!(first, b.children()[0]);
+/// assert!(first.get(b.parser()).is_some());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NodeHandle {
+    index: InnerNodeHandle,
+    /// The document that this handle belongs to, see [`Parser::document`], or [`ANY_DOCUMENT`] if it works with every document
+    ///
+    /// This is never 0, so an `Option<NodeHandle>` is as large as a handle.
+    document: NonZeroU32,
+}
+
+/// The document of handles that work with every document, see [`NodeHandle::new`]
+const ANY_DOCUMENT: NonZeroU32 = NonZeroU32::MAX;
+
+impl PartialEq for NodeHandle {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl Eq for NodeHandle {}
+
+impl Hash for NodeHandle {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl PartialOrd for NodeHandle {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NodeHandle {
+    /// Orders handles by their index, i.e. in document order
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
 
 impl NodeHandle {
-    /// Creates a new handle to the given node
+    /// Creates a new handle to the given node, which works with every document
     #[inline]
     pub fn new(node: InnerNodeHandle) -> Self {
-        NodeHandle(node)
+        NodeHandle {
+            index: node,
+            document: ANY_DOCUMENT,
+        }
+    }
+
+    /// Creates a handle to the given node of a document, see [`Parser::handle`]
+    #[inline]
+    pub(crate) fn in_document(node: InnerNodeHandle, document: u32) -> Self {
+        NodeHandle {
+            index: node,
+            // documents are never numbered 0, see `next_document`
+            document: NonZeroU32::new(document).unwrap_or(ANY_DOCUMENT),
+        }
+    }
+
+    /// Checks whether this handle may be used with the document of the given parser
+    #[inline]
+    pub(crate) fn belongs_to(&self, parser: &Parser<'_>) -> bool {
+        self.document == ANY_DOCUMENT || self.document.get() == parser.document()
     }
 
     /// Returns a reference to the node that is associated to this specific handle
     ///
-    /// It is an error to pass in the wrong parser.
-    /// It will either return `None` if this index points outside of the nodes table,
-    /// or it will return the one it points to.
+    /// It will return `None` if this index points outside of the nodes table or if the handle belongs to another
+    /// document, or it will return the one it points to.
     pub fn get<'p, 'buf>(&self, parser: &'p Parser<'buf>) -> Option<&'p Node<'buf>> {
-        parser.resolve_node_id(self.0)
+        if !self.belongs_to(parser) {
+            return None;
+        }
+        parser.resolve_node_id(self.index)
     }
 
     /// Returns a mutable reference to the node that is associated to this specific handle
     ///
-    /// It is an error to pass in the wrong parser.
-    /// It will either return `None` if this index points outside of the nodes table,
-    /// or it will return the one it points to.
+    /// It will return `None` if this index points outside of the nodes table or if the handle belongs to another
+    /// document, or it will return the one it points to.
     pub fn get_mut<'p, 'buf>(&self, parser: &'p mut Parser<'buf>) -> Option<&'p mut Node<'buf>> {
-        parser.resolve_node_id_mut(self.0)
+        if !self.belongs_to(parser) {
+            return None;
+        }
+        parser.resolve_node_id_mut(self.index)
+    }
+
+    /// Returns a handle to the node that immediately follows this node in its parent's children
+    ///
+    /// Returns `None` for handles of another document.
+    /// This is a constant time lookup, unless the nodes were borrowed mutably since parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>a</p>text<span>b</span>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let p = dom.children()[0];
+    ///
+    /// let next = p.next_sibling(parser).unwrap();
+    /// assert_eq!(next.get(parser).unwrap().inner_text(parser), "text");
+    ///
+    /// let next = next.next_sibling(parser).unwrap();
+    /// assert_eq!(next.get(parser).unwrap().inner_text(parser), "b");
+    ///
+    /// assert_eq!(next.next_sibling(parser), None);
+    /// ```
+    pub fn next_sibling(&self, parser: &Parser) -> Option<NodeHandle> {
+        if !self.belongs_to(parser) {
+            return None;
+        }
+        let position = parser.position_of(*self)?;
+        parser
+            .siblings_of(*self)
+            .get(position.index as usize + 1)
+            .copied()
+    }
+
+    /// Returns a handle to the node that immediately precedes this node in its parent's children
+    ///
+    /// Returns `None` for handles of another document.
+    /// This is a constant time lookup, unless the nodes were borrowed mutably since parsing.
+    pub fn prev_sibling(&self, parser: &Parser) -> Option<NodeHandle> {
+        if !self.belongs_to(parser) {
+            return None;
+        }
+        let position = parser.position_of(*self)?;
+        (position.index as usize)
+            .checked_sub(1)
+            .and_then(|index| parser.siblings_of(*self).get(index))
+            .copied()
     }
 
     /// Returns the internal unique Node ID that maps to a specific node in the node table
     #[inline]
     pub fn get_inner(&self) -> InnerNodeHandle {
+        self.index
+    }
+
+    /// Returns the position of this node in document order
+    ///
+    /// Nodes are numbered in the order in which they start in the source, which is the order of a depth-first
+    /// traversal: an element comes before its children, and its children come before its next sibling.
+    /// This is guaranteed to stay that way, and the [`Ord`] implementation of [`NodeHandle`] compares by it, so
+    /// matched nodes can be sorted, deduplicated and binary-searched without traversing the document again.
+    /// The descendants of an element are exactly the nodes after it up to and including
+    /// [`Children::end`](crate::Children::end).
+    ///
+    /// The numbering is done at parse time. Moving handles between children lists afterwards does not renumber nodes.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p>a</p></div><p>b</p>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let mut paragraphs = dom.query_selector("p").unwrap().collect::<Vec<_>>();
+    /// paragraphs.sort_by_key(|p| p.document_order());
+    ///
+    /// // the paragraphs inside of the `<div>` come first
+    /// let div = dom.children()[0];
+    /// let end = div.get(parser).unwrap().children().unwrap().end(parser).unwrap();
+    /// let inside = paragraphs.partition_point(|p| p.document_order() <= end);
+    ///
+    /// assert_eq!(inside, 1);
+    /// assert!(div.document_order() < paragraphs[0].document_order());
+    /// ```
+    #[inline]
+    pub fn document_order(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns an identifier of this node that stays the same when the same source is parsed again
+    ///
+    /// Unlike the handle itself, which only works with the document it was returned by, the identifier can be stored,
+    /// e.g. in a database, and turned back into a handle with [`VDom::resolve_stable_id`](crate::VDom::resolve_stable_id)
+    /// after parsing the same source with the same options. It is derived from the [document order](NodeHandle::document_order),
+    /// so it does not say anything about whether the source is still the same; use
+    /// [`VDom::serialize_handles`](crate::VDom::serialize_handles) to store handles together with a hash of the source.
+    ///
+    /// # Example
+    /// ```
+    /// let input = r#"<ul><li>a</li><li id="b">b</li></ul>"#;
+    ///
+    /// let dom = tl::parse(input, Default::default()).unwrap();
+    /// let id = dom.get_element_by_id("b").unwrap().stable_id().to_string();
+    ///
+    /// // later, e.g. in another run of the program
+    /// let dom = tl::parse(input, Default::default()).unwrap();
+    /// let li = dom.resolve_stable_id(id.parse().unwrap()).unwrap();
+    /// assert_eq!(li.get(dom.parser()).unwrap().inner_text(dom.parser()), "b");
+    /// ```
+    #[inline]
+    pub fn stable_id(&self) -> StableId {
+        StableId(self.index)
+    }
+}
+
+/// An identifier of a node that stays the same when the same source is parsed again, see [`NodeHandle::stable_id`]
+///
+/// It is formatted and parsed as a decimal number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableId(u32);
+
+impl StableId {
+    /// Creates an identifier from its numeric value, as returned by [`StableId::get`]
+    #[inline]
+    pub fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    /// Returns the numeric value of this identifier
+    #[inline]
+    pub fn get(&self) -> u32 {
         self.0
     }
 }
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for StableId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
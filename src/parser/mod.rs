@@ -1,10 +1,20 @@
 mod base;
+mod classes;
 pub(crate) mod constants;
+mod doctype;
 mod handle;
+mod namespace;
 mod options;
+mod span;
+mod symbol;
 mod tag;
 
 pub use base::*;
+pub use classes::*;
+pub use doctype::*;
 pub use handle::*;
+pub use namespace::*;
 pub use options::*;
+pub use span::*;
+pub use symbol::*;
 pub use tag::*;
@@ -0,0 +1,111 @@
+use super::HTMLTag;
+
+/// The namespace of an element, as returned by [`HTMLTag::namespace`]
+///
+/// Elements are in the HTML namespace, except for `<svg>` and `<math>` elements and their descendants, which are
+/// foreign content. The content of the SVG elements `<foreignObject>`, `<desc>` and `<title>` and of the MathML
+/// text elements and `<annotation-xml>` is HTML again, as in browsers.
+///
+/// In foreign content, `/>` always closes an element and HTML void elements like `<source>` need an end tag.
+///
+/// # Example
+/// ```
+/// use tl::Namespace;
+///
+/// let dom = tl::parse(r#"<svg viewBox="0 0 10 10"><linearGradient id="g"/><foreignObject><p>Hi</p></foreignObject></svg>"#, Default::default()).unwrap();
+/// let parser = dom.parser();
+///
+/// let namespace = |selector| {
+///     let handle = dom.query_selector(selector).unwrap().next().unwrap();
+///     handle.get(parser).unwrap().as_tag().unwrap().namespace()
+/// };
+///
+/// assert_eq!(namespace("linearGradient"), Namespace::Svg);
+/// assert_eq!(namespace("p"), Namespace::Html);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Namespace {
+    /// The HTML namespace
+    #[default]
+    Html,
+    /// The SVG namespace, for `<svg>` elements and their descendants
+    Svg,
+    /// The MathML namespace, for `<math>` elements and their descendants
+    MathMl,
+    /// Any other namespace, or no namespace at all
+    ///
+    /// This is only used in [XML mode](crate::ParserOptions::xml_mode), for elements that do not declare the HTML,
+    /// SVG or MathML namespace with an `xmlns` attribute and are not inside of an element that does.
+    Other,
+}
+
+impl Namespace {
+    /// Returns the namespace URI, like `http://www.w3.org/2000/svg`, or `None` for [`Namespace::Other`]
+    pub fn uri(&self) -> Option<&'static str> {
+        match self {
+            Self::Html => Some("http://www.w3.org/1999/xhtml"),
+            Self::Svg => Some("http://www.w3.org/2000/svg"),
+            Self::MathMl => Some("http://www.w3.org/1998/Math/MathML"),
+            Self::Other => None,
+        }
+    }
+
+    /// Returns the namespace with the given URI, or [`Namespace::Other`] if it is not the HTML, SVG or MathML namespace
+    pub fn from_uri(uri: &[u8]) -> Self {
+        [Self::Html, Self::Svg, Self::MathMl]
+            .into_iter()
+            .find(|namespace| namespace.uri().is_some_and(|u| u.as_bytes() == uri))
+            .unwrap_or(Self::Other)
+    }
+
+    /// Checks whether this is the namespace of foreign content, i.e. anything but HTML
+    #[inline]
+    pub fn is_foreign(&self) -> bool {
+        *self != Self::Html
+    }
+
+    /// Returns the namespace of an element with the given name and parent
+    pub(crate) fn of_element(name: &[u8], parent: Option<&HTMLTag<'_>>) -> Self {
+        Self::of_child(name, parent.map_or(Self::Html, Self::of_children))
+    }
+
+    /// Returns the namespace of an element whose parent has children in the given namespace, see [`Namespace::of_children`]
+    #[inline]
+    pub(crate) fn of_child(name: &[u8], namespace: Self) -> Self {
+        match namespace {
+            Self::Html if name.eq_ignore_ascii_case(b"svg") => Self::Svg,
+            Self::Html if name.eq_ignore_ascii_case(b"math") => Self::MathMl,
+            namespace => namespace,
+        }
+    }
+
+    /// Returns the namespace of an element in XML mode, which is declared with an `xmlns` attribute or inherited from the parent,
+    /// given the namespace of the parent
+    pub(crate) fn of_xml_element(xmlns: Option<&[u8]>, parent: Option<Self>) -> Self {
+        match xmlns {
+            Some(uri) => Self::from_uri(uri),
+            None => parent.unwrap_or(Self::Other),
+        }
+    }
+
+    /// Returns the namespace that the children of an element are in, which is HTML for integration points
+    #[inline]
+    pub(crate) fn of_children(parent: &HTMLTag<'_>) -> Self {
+        if parent._namespace == Self::Html {
+            return Self::Html;
+        }
+
+        let name = parent._name.as_bytes();
+        let is_one_of = |names: &[&[u8]]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+
+        match parent._namespace {
+            Self::Svg if is_one_of(&[b"foreignObject", b"desc", b"title"]) => Self::Html,
+            Self::MathMl
+                if is_one_of(&[b"mi", b"mo", b"mn", b"ms", b"mtext", b"annotation-xml"]) =>
+            {
+                Self::Html
+            }
+            namespace => namespace,
+        }
+    }
+}
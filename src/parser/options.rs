@@ -1,23 +1,91 @@
+use std::time::Duration;
+
+use super::Attributes;
+
 mod flags {
     pub const TRACK_IDS: u8 = 1 << 0;
     pub const TRACK_CLASSES: u8 = 1 << 1;
-    pub const HIGHEST: u8 = TRACK_CLASSES;
+    pub const TRACK_TAGS: u8 = 1 << 2;
+    pub const XML_MODE: u8 = 1 << 3;
+    pub const UNKNOWN_AS_VOID: u8 = 1 << 4;
+    pub const SKIP_WHITESPACE_TEXT: u8 = 1 << 5;
+    pub const PRESERVE_ATTRIBUTE_CASE: u8 = 1 << 6;
+    pub const OPAQUE_NOSCRIPT: u8 = 1 << 7;
+    pub const HIGHEST: u8 = OPAQUE_NOSCRIPT;
+    pub const TRACKING: u8 = TRACK_IDS | TRACK_CLASSES | TRACK_TAGS;
 }
 
+/// The maximum number of attributes that can be tracked with [`ParserOptions::track_attribute`]
+pub const MAX_TRACKED_ATTRIBUTES: usize = 4;
+
+/// The average number of bytes of input per node, which is used to estimate the number of nodes from [`ParserOptions::with_capacity`]
+const BYTES_PER_NODE: usize = 48;
+
+/// Which value the parser keeps if a tag has the same attribute more than once, like `<a href="/x" href="/y">`
+///
+/// All occurrences can still be inspected with [`HTMLTag::source_attributes`](crate::HTMLTag::source_attributes).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DuplicateAttributes {
+    /// Keep the value of the last occurrence
+    #[default]
+    KeepLast,
+    /// Keep the value of the first occurrence, like browsers do
+    KeepFirst,
+}
+
+/// What the parser does with an element or comment, as decided by the callback of [`ParserOptions::node_filter`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NodeFilter {
+    /// Keep the node and its content
+    Keep,
+    /// Leave out the node, but keep its content, which becomes part of the parent of the node
+    SkipNode,
+    /// Leave out the node and all of its content
+    SkipSubtree,
+}
+
+/// The callback of [`ParserOptions::node_filter`], which is called with the name and the attributes of every element
+pub type NodeFilterFn = fn(&str, &Attributes<'_>) -> NodeFilter;
+
 /// Options for the HTML Parser
 ///
 /// This allows users of this library to configure the parser.
 /// The default options (`ParserOptions::default()`) are optimized for raw parsing.
 /// If you need to do HTML tag lookups by ID or class names, you can enable tracking.
 /// This will cache HTML nodes as they appear in the source code on the fly.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct ParserOptions {
     flags: u8,
+    /// Names of attributes whose values are tracked
+    attributes: [Option<&'static str>; MAX_TRACKED_ATTRIBUTES],
+    /// Number of nodes to allocate space for up front
+    node_capacity: usize,
+    /// Which value to keep for repeated attributes
+    duplicate_attributes: DuplicateAttributes,
+    /// Names of elements that are void in addition to the HTML void elements
+    extra_void_elements: &'static [&'static str],
+    /// Decides which elements and comments are left out while parsing
+    node_filter: Option<NodeFilterFn>,
+    /// The longest time that parsing may take
+    time_budget: Option<Duration>,
+    /// Whether parsing fails on markup that is otherwise recovered from
+    strict: bool,
 }
 
-impl Default for ParserOptions {
-    fn default() -> Self {
-        Self { flags: 0 }
+impl PartialEq for ParserOptions {
+    fn eq(&self, other: &Self) -> bool {
+        // node filters are compared by their address, so the same function may compare as different
+        // if it was instantiated more than once
+        let filter = |options: &Self| options.node_filter.map(|filter| filter as usize);
+
+        self.flags == other.flags
+            && self.attributes == other.attributes
+            && self.node_capacity == other.node_capacity
+            && self.duplicate_attributes == other.duplicate_attributes
+            && self.extra_void_elements == other.extra_void_elements
+            && filter(self) == filter(other)
+            && self.time_budget == other.time_budget
+            && self.strict == other.strict
     }
 }
 
@@ -29,17 +97,19 @@ impl ParserOptions {
 
     /// Creates a [ParserOptions] from a bitset
     pub fn from_raw_checked(flags: u8) -> Option<Self> {
-        if flags > flags::HIGHEST * 2 - 1 {
+        if flags.leading_zeros() < flags::HIGHEST.leading_zeros() {
             None
         } else {
             Some(Self {
                 flags,
-                ..Default::default()
+                ..Self::default()
             })
         }
     }
 
     /// Returns the raw flags of this bitset
+    ///
+    /// Attributes tracked by [`ParserOptions::track_attribute`] are not part of the raw flags.
     pub fn to_raw(&self) -> u8 {
         self.flags
     }
@@ -69,6 +139,358 @@ impl ParserOptions {
         self
     }
 
+    /// Enables tracking of HTML Tag names and stores them in a lookup table.
+    ///
+    /// This makes `get_elements_by_tag_name()` lookups ~O(1)
+    pub fn track_tags(mut self) -> Self {
+        self.set_flag(flags::TRACK_TAGS);
+        self
+    }
+
+    /// Enables tracking of the `name` attribute of elements, like form controls, and stores its values in a lookup table.
+    ///
+    /// This makes `get_elements_by_name()` lookups ~O(1).
+    /// It is the same as `track_attribute("name")`, so it counts towards the [`MAX_TRACKED_ATTRIBUTES`] tracked attributes.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().track_names();
+    /// assert!(options.is_tracking_names());
+    /// assert!(options.is_tracking_attribute("name"));
+    /// ```
+    pub fn track_names(self) -> Self {
+        self.track_attribute("name")
+    }
+
+    /// Enables tracking of the values of the given attribute and stores them in a lookup table.
+    ///
+    /// This makes `get_elements_by_attribute()` lookups for this attribute ~O(1).
+    /// Calling this more than once tracks all of the given attributes.
+    ///
+    /// # Panics
+    /// Panics if more than [`MAX_TRACKED_ATTRIBUTES`] distinct attributes are tracked.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().track_attribute("data-testid");
+    /// assert!(options.is_tracking_attribute("data-testid"));
+    /// assert!(!options.is_tracking_attribute("name"));
+    /// ```
+    pub fn track_attribute(mut self, name: &'static str) -> Self {
+        if self.is_tracking_attribute(name) {
+            return self;
+        }
+
+        let slot = self
+            .attributes
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .expect("cannot track more than `MAX_TRACKED_ATTRIBUTES` attributes");
+
+        *slot = Some(name);
+        self
+    }
+
+    /// Parses the input as XML, such as XHTML documents or feeds, instead of HTML.
+    ///
+    /// In XML mode, no element has special meaning: there are no void elements, so every element needs an end tag
+    /// or `/>`, and the contents of `<template>` elements are not treated differently.
+    /// Elements are in [`Namespace::Other`](crate::Namespace::Other), unless they or one of their ancestors declare
+    /// the HTML, SVG or MathML namespace with an `xmlns` attribute.
+    ///
+    /// Element and attribute names are kept as written and compared case-sensitively, both when matching end tags to
+    /// start tags and in query selectors, so `<Item>` is not closed by `</item>` and is not matched by `item`.
+    ///
+    /// # Example
+    /// ```
+    /// let input = r#"<?xml version="1.0"?><rss><channel><link>https://example.com/</link><title><![CDATA[A & B]]></title></channel></rss>"#;
+    /// let dom = tl::parse(input, tl::ParserOptions::default().xml_mode()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// assert!(dom.children()[0].get(parser).unwrap().as_processing_instruction().is_some());
+    ///
+    /// let link = dom.query_selector("link").unwrap().next().unwrap();
+    /// assert_eq!(link.get(parser).unwrap().inner_text(parser), "https://example.com/");
+    ///
+    /// let title = dom.query_selector("title").unwrap().next().unwrap();
+    /// assert_eq!(title.get(parser).unwrap().inner_text(parser), "A & B");
+    /// ```
+    pub fn xml_mode(mut self) -> Self {
+        self.set_flag(flags::XML_MODE);
+        self
+    }
+
+    /// Parses the elements with the given names as void elements, in addition to the HTML void elements like `<br>`.
+    ///
+    /// Void elements have neither content nor an end tag, so the content that follows them is not parsed as their children.
+    /// This is useful for legacy or embedded HTML dialects with custom empty tags. Names are compared ASCII case insensitively.
+    /// Like the HTML void elements, these are only void outside of `<svg>` and `<math>` and not in [XML mode](ParserOptions::xml_mode).
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().extra_void_elements(&["icon", "spacer"]);
+    /// let dom = tl::parse(r#"<p><icon name="x">Text</p>"#, options).unwrap();
+    ///
+    /// let p = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(p.children().top().len(), 2);
+    /// assert_eq!(dom.outer_html(), r#"<p><icon name="x">Text</p>"#);
+    /// ```
+    pub fn extra_void_elements(mut self, names: &'static [&'static str]) -> Self {
+        self.extra_void_elements = names;
+        self
+    }
+
+    /// Parses elements that are not HTML elements as void elements, if `enabled` is `true`.
+    ///
+    /// Custom elements, whose names contain a `-`, are never treated as void. See [`ParserOptions::extra_void_elements`].
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().treat_unknown_as_void(true);
+    /// let dom = tl::parse("<div><foo><my-card><b>Hi</b></my-card></div>", options).unwrap();
+    ///
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(div.children().top().len(), 2);
+    /// ```
+    pub fn treat_unknown_as_void(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.set_flag(flags::UNKNOWN_AS_VOID);
+        } else {
+            self.flags &= !flags::UNKNOWN_AS_VOID;
+        }
+        self
+    }
+
+    /// Does not create text nodes for text that only consists of whitespace, like the indentation between `<li>` items
+    /// or inside of a `<table>`.
+    ///
+    /// This makes the tree of a typical page a lot smaller, so loops over children do not need to skip whitespace.
+    /// Whitespace inside of `<pre>`, `<code>` and `<textarea>` elements is kept, because it is part of their content.
+    /// Note that whitespace between inline elements like in `<b>a</b> <i>b</i>` is skipped as well,
+    /// so the text of such elements loses the space.
+    ///
+    /// # Example
+    /// ```
+    /// let input = "<ul>\n  <li>A</li>\n  <li>B</li>\n</ul>";
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default()).unwrap();
+    /// assert_eq!(dom.nodes().len(), 8);
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default().skip_whitespace_text()).unwrap();
+    /// assert_eq!(dom.nodes().len(), 5);
+    /// assert_eq!(dom.outer_html(), "<ul><li>A</li><li>B</li></ul>");
+    /// ```
+    pub fn skip_whitespace_text(mut self) -> Self {
+        self.set_flag(flags::SKIP_WHITESPACE_TEXT);
+        self
+    }
+
+    /// Keeps the names of attributes as written, instead of converting them to lowercase.
+    ///
+    /// Attribute names are case insensitive in HTML, so by default the parser stores them in lowercase, like browsers do.
+    /// With this option, they are stored and serialized by [`Node::outer_html`](crate::Node::outer_html) as written,
+    /// like `onClick`. The names of attributes of `<svg>` and `<math>` elements, their descendants and of elements in
+    /// [XML mode](ParserOptions::xml_mode) are always kept, because they are case sensitive.
+    ///
+    /// [`Attributes::get`] and the other lookups of attributes ignore the case of names either way, and names that only
+    /// differ in case are the same attribute, except in XML mode.
+    ///
+    /// # Example
+    /// ```
+    /// let input = r#"<a HREF="/" onClick="go()">Home</a>"#;
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default()).unwrap();
+    /// let names = dom.nodes()[0].as_tag().unwrap().attributes().iter().map(|(name, _)| name.into_owned()).collect::<Vec<_>>();
+    /// assert!(names.contains(&"href".to_owned()) && names.contains(&"onclick".to_owned()));
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default().preserve_attribute_case()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    /// assert!(attributes.iter().any(|(name, _)| name == "onClick"));
+    /// assert_eq!(attributes.get("onclick").flatten().unwrap().as_utf8_str(), "go()");
+    /// ```
+    pub fn preserve_attribute_case(mut self) -> Self {
+        self.set_flag(flags::PRESERVE_ATTRIBUTE_CASE);
+        self
+    }
+
+    /// Decides whether the content of `<noscript>` elements is parsed as markup, which is the default, or kept as a single text node.
+    ///
+    /// Browsers with scripting enabled treat the content as text, so the fallback markup, like tracking pixels or images
+    /// that are otherwise loaded lazily, is not part of the tree. Parsing it as markup makes it available to
+    /// [`VDom::query_selector`](crate::VDom::query_selector) and the other lookups, which is useful for scraping fallback content.
+    /// As text, the content is written back unchanged and elements inside of it are not found or tracked.
+    ///
+    /// # Example
+    /// ```
+    /// let input = r#"<noscript><img src="pixel.gif"></noscript>"#;
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default()).unwrap();
+    /// assert_eq!(dom.query_selector("noscript img").unwrap().count(), 1);
+    ///
+    /// let dom = tl::parse(input, tl::ParserOptions::default().parse_noscript_content(false)).unwrap();
+    /// assert_eq!(dom.query_selector("img").unwrap().count(), 0);
+    /// assert_eq!(dom.nodes()[1].as_raw().unwrap().as_utf8_str(), r#"<img src="pixel.gif">"#);
+    /// assert_eq!(dom.outer_html(), input);
+    /// ```
+    pub fn parse_noscript_content(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.flags &= !flags::OPAQUE_NOSCRIPT;
+        } else {
+            self.set_flag(flags::OPAQUE_NOSCRIPT);
+        }
+        self
+    }
+
+    /// Calls the given function for every element and comment while parsing, which decides whether the node is kept.
+    ///
+    /// The function is called with the name and the attributes of every element, and with the name `#comment` and no attributes
+    /// for comments. Nodes that are skipped are never added to the document, so leaving out large subtrees like inline SVG icons
+    /// or ads saves the memory of their nodes. Skipped elements are not tracked, see [`ParserOptions::track_ids`].
+    ///
+    /// The function cannot capture any variables, because the options are `Copy`. It is not called for the content of skipped subtrees.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::NodeFilter;
+    ///
+    /// let options = tl::ParserOptions::default().node_filter(|name, attributes| match name {
+    ///     "svg" | "#comment" => NodeFilter::SkipSubtree,
+    ///     "div" if attributes.is_class_member("ad") => NodeFilter::SkipSubtree,
+    ///     "font" => NodeFilter::SkipNode,
+    ///     _ => NodeFilter::Keep,
+    /// });
+    ///
+    /// let dom = tl::parse(
+    ///     r#"<p><svg><path d="M0"/></svg><font color="red">Hi</font><!-- note --></p><div class="ad"><p>Buy</p></div>"#,
+    ///     options,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(dom.outer_html(), "<p>Hi</p>");
+    /// assert_eq!(dom.nodes().len(), 2);
+    /// ```
+    pub fn node_filter(mut self, filter: NodeFilterFn) -> Self {
+        self.node_filter = Some(filter);
+        self
+    }
+
+    /// Stops parsing with [`ParseError::TimeBudgetExceeded`](crate::ParseError::TimeBudgetExceeded) if it takes longer than the given duration.
+    ///
+    /// This bounds the time spent on huge or pathological documents, e.g. when parsing inside of a request handler
+    /// with a latency limit. The clock is checked every few hundred tags, so parsing may take slightly longer than the budget.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let input = "<p>Hello</p>".repeat(100_000);
+    ///
+    /// let options = tl::ParserOptions::default().time_budget(Duration::ZERO);
+    /// assert_eq!(tl::parse(&input, options).unwrap_err(), tl::ParseError::TimeBudgetExceeded);
+    ///
+    /// let options = tl::ParserOptions::default().time_budget(Duration::from_secs(60));
+    /// assert!(tl::parse(&input, options).is_ok());
+    /// ```
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Makes parsing fail with [`ParseError::InvalidMarkup`](crate::ParseError::InvalidMarkup) on the first markup
+    /// that the parser would otherwise silently recover from, instead of returning a document.
+    ///
+    /// This is meant for validating generated HTML, e.g. in tests or when fuzzing, where recovering hides bugs.
+    /// The error contains the [`MarkupError`](crate::errors::MarkupError) and its position in the source string.
+    /// Parsing fails on
+    /// - elements without an end tag, unless they are void, closed with `/>` or may omit their end tag in HTML, like `<li>`,
+    /// - end tags that do not close the innermost open element, like the `</ul>` in `<ul><li>a</ul>` (the parser does not
+    ///   imply end tags),
+    /// - invalid attribute syntax, like characters that cannot start an attribute, missing values after `=`,
+    ///   unterminated quotes and attributes that are not separated by whitespace,
+    /// - tags, comments and CDATA sections without their closing `>`, `-->` or `]]>`.
+    ///
+    /// Documents that parse in strict mode parse to the same nodes without it.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::errors::MarkupError;
+    /// use tl::{ParseError, ParserOptions};
+    ///
+    /// let options = ParserOptions::default().strict();
+    /// assert!(tl::parse(r#"<ul><li class="a">One</li><li>Two</li></ul><br><p>End"#, options).is_ok());
+    ///
+    /// let input = r#"<div><a href="/">Home</span></div>"#;
+    /// let Err(ParseError::InvalidMarkup { error, span }) = tl::parse(input, options) else {
+    ///     panic!("expected invalid markup");
+    /// };
+    /// assert_eq!(error, MarkupError::StrayEndTag);
+    /// assert_eq!(&input[span.range()], "</span>");
+    ///
+    /// // without strict mode, the `</span>` is ignored
+    /// assert!(tl::parse(input, ParserOptions::default()).is_ok());
+    /// ```
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Allocates space for the expected number of nodes and length of the input up front.
+    ///
+    /// If many documents of a similar size are parsed, this avoids growing the node table over and over while parsing.
+    /// The byte length is used to estimate the number of nodes if that is larger than `nodes`, so either hint can be `0`
+    /// if it is not known. Use [`VDom::reparse`](crate::VDom::reparse) to reuse the allocations of a previous document instead.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().with_capacity(1000, 0);
+    /// assert_eq!(options.node_capacity(), 1000);
+    ///
+    /// let dom = tl::parse("<p>Hello</p>", options).unwrap();
+    /// assert_eq!(dom.nodes().len(), 2);
+    /// ```
+    pub fn with_capacity(mut self, nodes: usize, bytes: usize) -> Self {
+        self.node_capacity = nodes.max(bytes / BYTES_PER_NODE);
+        self
+    }
+
+    /// Returns the number of nodes the parser allocates space for up front, as set by [`ParserOptions::with_capacity`].
+    #[inline]
+    pub fn node_capacity(&self) -> usize {
+        self.node_capacity
+    }
+
+    /// Sets which value is kept if a tag has the same attribute more than once.
+    ///
+    /// By default, the last occurrence wins.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::DuplicateAttributes;
+    ///
+    /// let options = tl::ParserOptions::default().with_duplicate_attributes(DuplicateAttributes::KeepFirst);
+    /// let dom = tl::parse(r#"<a href="/x" href="/y"></a>"#, options).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert_eq!(tag.href().as_deref(), Some("/x"));
+    /// ```
+    pub fn with_duplicate_attributes(mut self, policy: DuplicateAttributes) -> Self {
+        self.duplicate_attributes = policy;
+        self
+    }
+
+    /// Returns which value the parser keeps for repeated attributes, as set by [`ParserOptions::with_duplicate_attributes`].
+    #[inline]
+    pub fn duplicate_attributes(&self) -> DuplicateAttributes {
+        self.duplicate_attributes
+    }
+
+    /// Returns these options without any lookup tables, for parsers that do not keep the nodes around
+    pub(crate) fn without_tracking(mut self) -> Self {
+        self.flags &= !flags::TRACKING;
+        self.attributes = [None; MAX_TRACKED_ATTRIBUTES];
+        self
+    }
+
     /// Returns whether the parser is tracking HTML Tag IDs.
     #[inline]
     pub fn is_tracking_ids(&self) -> bool {
@@ -81,12 +503,111 @@ impl ParserOptions {
         self.has_flag(flags::TRACK_CLASSES)
     }
 
-    /// Returns whether the parser is tracking HTML Tag IDs or classes (previously enabled by a call to `track_ids()` or `track_classes()`).
+    /// Returns whether the parser is tracking HTML Tag names.
+    #[inline]
+    pub fn is_tracking_tags(&self) -> bool {
+        self.has_flag(flags::TRACK_TAGS)
+    }
+
+    /// Returns whether the parser is tracking the `name` attribute, as set by [`ParserOptions::track_names`].
+    #[inline]
+    pub fn is_tracking_names(&self) -> bool {
+        self.is_tracking_attribute("name")
+    }
+
+    /// Returns whether the input is parsed as XML, as set by [`ParserOptions::xml_mode`].
+    #[inline]
+    pub fn is_xml_mode(&self) -> bool {
+        self.has_flag(flags::XML_MODE)
+    }
+
+    /// Returns the names of the elements that are void in addition to the HTML void elements,
+    /// as set by [`ParserOptions::extra_void_elements`].
+    #[inline]
+    pub fn void_elements(&self) -> &'static [&'static str] {
+        self.extra_void_elements
+    }
+
+    /// Returns whether elements that are not HTML elements are parsed as void elements,
+    /// as set by [`ParserOptions::treat_unknown_as_void`].
+    #[inline]
+    pub fn is_treating_unknown_as_void(&self) -> bool {
+        self.has_flag(flags::UNKNOWN_AS_VOID)
+    }
+
+    /// Returns whether text nodes that only consist of whitespace are skipped, as set by [`ParserOptions::skip_whitespace_text`].
+    #[inline]
+    pub fn is_skipping_whitespace_text(&self) -> bool {
+        self.has_flag(flags::SKIP_WHITESPACE_TEXT)
+    }
+
+    /// Returns whether attribute names are kept as written, as set by [`ParserOptions::preserve_attribute_case`].
+    #[inline]
+    pub fn is_preserving_attribute_case(&self) -> bool {
+        self.has_flag(flags::PRESERVE_ATTRIBUTE_CASE)
+    }
+
+    /// Returns whether the content of `<noscript>` elements is parsed as markup, as set by [`ParserOptions::parse_noscript_content`].
+    #[inline]
+    pub fn is_parsing_noscript_content(&self) -> bool {
+        !self.has_flag(flags::OPAQUE_NOSCRIPT)
+    }
+
+    /// Returns the callback that decides which nodes are kept, as set by [`ParserOptions::node_filter`].
+    #[inline]
+    pub fn get_node_filter(&self) -> Option<NodeFilterFn> {
+        self.node_filter
+    }
+
+    /// Returns the longest time that parsing may take, as set by [`ParserOptions::time_budget`].
+    #[inline]
+    pub fn get_time_budget(&self) -> Option<Duration> {
+        self.time_budget
+    }
+
+    /// Returns whether parsing fails on markup that is otherwise recovered from, as set by [`ParserOptions::strict`].
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Checks whether an element that is not an HTML void element is void because of these options
+    #[inline]
+    pub(crate) fn is_custom_void(&self, name: &[u8]) -> bool {
+        if self.extra_void_elements.is_empty() && !self.is_treating_unknown_as_void() {
+            return false;
+        }
+
+        self.extra_void_elements
+            .iter()
+            .any(|void| void.as_bytes().eq_ignore_ascii_case(name))
+            || (self.is_treating_unknown_as_void()
+                && !super::tag::is_custom_element_name(name)
+                && !super::constants::KNOWN_ELEMENTS
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(name)))
+    }
+
+    /// Returns whether the parser is tracking the values of the given attribute.
+    #[inline]
+    pub fn is_tracking_attribute(&self, name: &str) -> bool {
+        self.tracked_attributes().any(|n| n == name)
+    }
+
+    /// Returns an iterator over the names of all attributes whose values are tracked.
+    pub fn tracked_attributes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.attributes.iter().map_while(|name| *name)
+    }
+
+    /// Returns whether the values of any attribute are tracked
+    #[inline]
+    pub(crate) fn is_tracking_any_attribute(&self) -> bool {
+        self.attributes[0].is_some()
+    }
+
+    /// Returns whether the parser is tracking HTML Tag IDs, classes, names or attributes (previously enabled by a call to `track_ids()`, `track_classes()`, `track_tags()` or `track_attribute()`).
     #[inline]
     pub fn is_tracking(&self) -> bool {
-        // for now we can just check if any bit is set, may or may not lead to better codegen than two cmps
-        // this must be changed in some way if we ever add more flags
-        // self.is_tracking_ids() || self.is_tracking_classes()
-        self.flags != 0
+        self.has_flag(flags::TRACKING) || self.is_tracking_any_attribute()
     }
 }
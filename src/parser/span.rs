@@ -0,0 +1,153 @@
+use std::ops::Range;
+
+/// A range of bytes `start..end` in the source string
+///
+/// Positions are byte offsets, not character offsets.
+/// The parser only accepts inputs whose length fits in a `u32`, so spans are stored as `u32`s internally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    start: u32,
+    end: u32,
+}
+
+impl Span {
+    /// Creates a new span from a start (inclusive) and end (exclusive) position
+    #[inline]
+    pub(crate) fn new(start: usize, end: usize) -> Self {
+        debug_assert!(start <= end);
+        Self {
+            start: start as u32,
+            end: end as u32,
+        }
+    }
+
+    /// Returns the (inclusive) starting position of this span
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start as usize
+    }
+
+    /// Returns the (exclusive) ending position of this span
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end as usize
+    }
+
+    /// Returns the number of bytes covered by this span
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.end() - self.start()
+    }
+
+    /// Checks whether this span is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns this span as a `Range<usize>`, which can be used to index into the source string
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+}
+
+impl From<Span> for Range<usize> {
+    #[inline]
+    fn from(span: Span) -> Self {
+        span.range()
+    }
+}
+
+/// Source positions of an HTML tag
+///
+/// # Example
+/// ```
+/// let source = "<div><p class=\"x\">hello</p></div>";
+/// let dom = tl::parse(source, Default::default()).unwrap();
+/// let p = dom.nodes()[1].as_tag().unwrap();
+/// let span = p.span();
+///
+/// assert_eq!(&source[span.start_tag().range()], "<p class=\"x\">");
+/// assert_eq!(&source[span.end_tag().unwrap().range()], "</p>");
+/// assert_eq!(&source[span.element().range()], "<p class=\"x\">hello</p>");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TagSpan {
+    pub(crate) start_tag: Span,
+    pub(crate) end_tag: Option<Span>,
+}
+
+impl TagSpan {
+    /// Returns the span of the start tag, i.e. `<p class="x">`
+    #[inline]
+    pub fn start_tag(&self) -> Span {
+        self.start_tag
+    }
+
+    /// Returns the span of the end tag, i.e. `</p>`
+    ///
+    /// This is `None` for void elements, self-closing tags and tags that were never closed.
+    #[inline]
+    pub fn end_tag(&self) -> Option<Span> {
+        self.end_tag
+    }
+
+    /// Returns the span of the full element, from the start of the start tag to the end of the end tag
+    ///
+    /// If this tag has no end tag, this is the same as [`TagSpan::start_tag`].
+    #[inline]
+    pub fn element(&self) -> Span {
+        let end = self.end_tag.unwrap_or(self.start_tag).end();
+        Span::new(self.start_tag.start(), end)
+    }
+}
+
+/// Source positions of an attribute, as returned by [`HTMLTag::attribute_span`](crate::HTMLTag::attribute_span)
+///
+/// # Example
+/// ```
+/// let source = r#"<a href="/old" hidden>link</a>"#;
+/// let dom = tl::parse(source, Default::default()).unwrap();
+/// let a = dom.nodes()[0].as_tag().unwrap();
+///
+/// let span = a.attribute_span("href").unwrap();
+/// assert_eq!(&source[span.attribute().range()], r#"href="/old""#);
+/// assert_eq!(&source[span.name().range()], "href");
+/// assert_eq!(&source[span.value().unwrap().range()], "/old");
+///
+/// // patch the value without serializing the document again
+/// let mut patched = String::from(source);
+/// patched.replace_range(span.value().unwrap().range(), "/new");
+/// assert_eq!(patched, r#"<a href="/new" hidden>link</a>"#);
+///
+/// assert_eq!(a.attribute_span("hidden").unwrap().value(), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttributeSpan {
+    pub(crate) attribute: Span,
+    pub(crate) name: Span,
+    pub(crate) value: Option<Span>,
+}
+
+impl AttributeSpan {
+    /// Returns the span of the whole attribute, i.e. `href="/old"`
+    #[inline]
+    pub fn attribute(&self) -> Span {
+        self.attribute
+    }
+
+    /// Returns the span of the name of the attribute, i.e. `href`
+    #[inline]
+    pub fn name(&self) -> Span {
+        self.name
+    }
+
+    /// Returns the span of the value of the attribute without its quotes, i.e. `/old`
+    ///
+    /// This is `None` for attributes without a value, like `hidden`, and empty for `href=""`.
+    #[inline]
+    pub fn value(&self) -> Option<Span> {
+        self.value
+    }
+}
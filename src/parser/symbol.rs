@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::sync::OnceLock;
+
+use super::constants::{KNOWN_ATTRIBUTES, KNOWN_ELEMENTS};
+use crate::Bytes;
+
+/// An interned tag name
+///
+/// The names of known HTML elements, like `div` or `p`, are interned while parsing, so comparing the names of two tags
+/// is a single integer comparison instead of a string comparison, and elements with these names do not store their
+/// name at all. Symbols are the same in every document. Other names, like those of custom elements, have no symbol.
+/// Names are compared exactly as they are stored, which is in lowercase for HTML elements, so `<div>` and `<DIV>`
+/// have the same symbol, but an `<A>` element inside of an `<svg>` element has none.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse("<div><p>a</p><P>b</P><my-p>c</my-p></div>", Default::default()).unwrap();
+///
+/// let p = tl::TagName::new("p").unwrap();
+/// let count = dom
+///     .nodes()
+///     .iter()
+///     .filter_map(|node| node.as_tag())
+///     .filter(|tag| tag.tag_name() == Some(p))
+///     .count();
+///
+/// assert_eq!(count, 2);
+/// assert_eq!(p.as_bytes(), b"p");
+/// assert_eq!(tl::TagName::new("my-p"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct TagName(u32);
+
+impl TagName {
+    /// Returns the symbol of the given name, or `None` if it is not the name of a known HTML element
+    ///
+    /// The name is compared case sensitively, so it has to be in lowercase.
+    #[inline]
+    pub fn new(name: &str) -> Option<Self> {
+        Self::of(name.as_bytes())
+    }
+
+    /// Returns the symbol of the given name, comparing it case sensitively
+    #[inline]
+    pub(crate) fn of(name: &[u8]) -> Option<Self> {
+        elements().get(name, false).map(Self)
+    }
+
+    /// Returns the name of this symbol
+    #[inline]
+    pub fn as_bytes(&self) -> &'static [u8] {
+        self.name().as_bytes()
+    }
+
+    /// Returns the name of this symbol as bytes, which is what [`HTMLTag::name`](crate::HTMLTag::name) returns
+    #[inline]
+    pub(crate) fn name(&self) -> &'static Bytes<'static> {
+        &elements().names[self.0 as usize]
+    }
+
+    /// Returns the internal ID of this symbol, which is its index into the list of known names
+    #[inline]
+    pub fn get_inner(&self) -> u32 {
+        self.0
+    }
+}
+
+/// The name of an element, which is a [`TagName`] for the names of known HTML elements
+#[derive(Debug, Clone)]
+pub(crate) enum ElementName<'a> {
+    /// The name of a known HTML element
+    Known(TagName),
+    /// Any other name, including names that were set with [`HTMLTag::name_mut`](crate::HTMLTag::name_mut)
+    Other(Bytes<'a>),
+}
+
+impl<'a> ElementName<'a> {
+    /// Returns the name of an element as it is stored, which is interned if it is known
+    #[inline]
+    pub(crate) fn new(name: Bytes<'a>) -> Self {
+        match TagName::of(name.as_bytes()) {
+            Some(symbol) => Self::Known(symbol),
+            None => Self::Other(name),
+        }
+    }
+
+    /// Returns the name of an element in lowercase, which is interned if it is known
+    ///
+    /// Unlike [`ElementName::new`], the name is only copied if it is unknown and contains uppercase letters.
+    #[inline]
+    pub(crate) fn lowercase(name: &'a [u8]) -> Self {
+        match elements().get(name, true) {
+            Some(symbol) => Self::Known(TagName(symbol)),
+            None => Self::Other(super::base::lowercase_name(name)),
+        }
+    }
+
+    /// Returns the symbol of this name, if it is known and was not changed with [`HTMLTag::name_mut`](crate::HTMLTag::name_mut)
+    #[inline]
+    pub(crate) fn symbol(&self) -> Option<TagName> {
+        match self {
+            Self::Known(symbol) => Some(*symbol),
+            Self::Other(_) => None,
+        }
+    }
+
+    /// Returns this name as [`Bytes`]
+    #[inline]
+    pub(crate) fn bytes(&self) -> &Bytes<'a> {
+        match self {
+            Self::Known(symbol) => symbol.name(),
+            Self::Other(name) => name,
+        }
+    }
+
+    /// Returns this name as a byte slice
+    #[inline]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        self.bytes().as_bytes()
+    }
+
+    /// Returns a mutable reference to this name, which is no longer interned
+    pub(crate) fn to_mut(&mut self) -> &mut Bytes<'a> {
+        if let Self::Known(symbol) = self {
+            *self = Self::Other(symbol.name().clone());
+        }
+
+        match self {
+            Self::Other(name) => name,
+            Self::Known(_) => unreachable!(),
+        }
+    }
+
+    /// Returns a copy of this name that does not borrow from the source
+    pub(crate) fn to_static(&self) -> ElementName<'static> {
+        match self {
+            Self::Known(symbol) => ElementName::Known(*symbol),
+            Self::Other(name) => ElementName::Other(name.to_static()),
+        }
+    }
+
+    /// Returns the approximate number of bytes that this name has allocated on the heap
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Self::Known(_) => 0,
+            Self::Other(name) => name.heap_size(),
+        }
+    }
+}
+
+/// Returns a known attribute name in lowercase without copying it, or `None` if the name is not known
+#[inline]
+pub(crate) fn known_attribute(name: &[u8]) -> Option<Bytes<'static>> {
+    let table = attributes();
+    let symbol = table.get(name, true)?;
+    Some(table.names[symbol as usize].clone())
+}
+
+/// The longest name in the tables of known names, rounded up
+const MAX_KNOWN_NAME: usize = 16;
+
+/// The number of slots of the table of short names of a [`NameTable`], which is a power of two
+const SHORT_SLOTS: usize = 1024;
+
+/// A table of known names, which are interned at the index of the name
+#[derive(Debug)]
+struct NameTable {
+    names: Box<[Bytes<'static>]>,
+    /// The symbols of names of up to 7 bytes, packed by [`pack_short_name`], in an open addressing table
+    ///
+    /// Almost all tag and attribute names are this short, so they are found without hashing and comparing strings.
+    short: Box<[(u64, u32)]>,
+    /// The symbols of longer names
+    long: HashMap<&'static [u8], u32, BuildHasherDefault<FnvHasher>>,
+}
+
+impl NameTable {
+    fn new(names: &'static [&'static [u8]]) -> Self {
+        debug_assert!(names.iter().all(|name| name.len() <= MAX_KNOWN_NAME));
+        debug_assert!(names.len() * 4 <= SHORT_SLOTS);
+
+        let mut short = vec![(0, 0); SHORT_SLOTS].into_boxed_slice();
+        let mut long = HashMap::default();
+
+        for (symbol, &name) in names.iter().enumerate() {
+            match pack_short_name(name, false) {
+                Some(packed) => {
+                    let mut slot = short_slot(packed);
+                    while short[slot].0 != 0 {
+                        slot = (slot + 1) % SHORT_SLOTS;
+                    }
+                    short[slot] = (packed, symbol as u32);
+                }
+                None => {
+                    long.insert(name, symbol as u32);
+                }
+            }
+        }
+
+        Self {
+            names: names.iter().map(|&name| Bytes::from(name)).collect(),
+            short,
+            long,
+        }
+    }
+
+    /// Returns the symbol of the given name, comparing it ASCII case insensitively if `ignore_case` is true
+    #[inline]
+    fn get(&self, name: &[u8], ignore_case: bool) -> Option<u32> {
+        if let Some(packed) = pack_short_name(name, ignore_case) {
+            let mut slot = short_slot(packed);
+            loop {
+                match self.short[slot] {
+                    (0, _) => return None,
+                    (key, symbol) if key == packed => return Some(symbol),
+                    _ => slot = (slot + 1) % SHORT_SLOTS,
+                }
+            }
+        }
+
+        let mut buffer = [0; MAX_KNOWN_NAME];
+        let name = if ignore_case {
+            let lowercase = buffer.get_mut(..name.len())?;
+            lowercase.copy_from_slice(name);
+            lowercase.make_ascii_lowercase();
+            lowercase
+        } else {
+            name
+        };
+
+        self.long.get(name).copied()
+    }
+}
+
+/// Packs a name of 1 to 7 bytes into an integer, with its length in the highest byte so that the result is never 0,
+/// converting it to lowercase if `lowercase` is true
+#[inline]
+fn pack_short_name(name: &[u8], lowercase: bool) -> Option<u64> {
+    if name.is_empty() || name.len() > 7 {
+        return None;
+    }
+
+    let mut packed = (name.len() as u64) << 56;
+    for (index, &byte) in name.iter().enumerate() {
+        let byte = if lowercase {
+            byte.to_ascii_lowercase()
+        } else {
+            byte
+        };
+        packed |= u64::from(byte) << (index * 8);
+    }
+
+    Some(packed)
+}
+
+/// Returns the slot of the table of short names of a [`NameTable`] where the search for a packed name starts
+#[inline]
+fn short_slot(packed: u64) -> usize {
+    (packed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (u64::BITS - SHORT_SLOTS.trailing_zeros()))
+        as usize
+}
+
+/// Returns the table of the names of known HTML elements, whose symbols are [`TagName`]s
+fn elements() -> &'static NameTable {
+    static ELEMENTS: OnceLock<NameTable> = OnceLock::new();
+    ELEMENTS.get_or_init(|| NameTable::new(KNOWN_ELEMENTS))
+}
+
+/// Returns the table of known attribute names
+fn attributes() -> &'static NameTable {
+    static ATTRIBUTES: OnceLock<NameTable> = OnceLock::new();
+    ATTRIBUTES.get_or_init(|| NameTable::new(KNOWN_ATTRIBUTES))
+}
+
+/// FNV-1a, which is much faster than the default hasher for short keys like tag names
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Mixes in the length prefix of slices at once, instead of byte by byte
+    #[inline]
+    fn write_usize(&mut self, value: usize) {
+        self.0 ^= value as u64;
+        self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, HTMLTag, ParserOptions, VDom};
+
+    fn tags<'a>(dom: &'a VDom<'_>) -> Vec<&'a HTMLTag<'a>> {
+        dom.nodes()
+            .iter()
+            .filter_map(|node| node.as_tag())
+            .collect()
+    }
+
+    #[test]
+    fn known_names_have_symbols() {
+        for &name in KNOWN_ELEMENTS {
+            let symbol = TagName::of(name).unwrap();
+            assert_eq!(symbol.as_bytes(), name);
+        }
+
+        assert_eq!(TagName::new("div").unwrap().as_bytes(), b"div");
+        assert_eq!(TagName::new("DIV"), None);
+        assert_eq!(TagName::new("my-element"), None);
+        assert_eq!(TagName::new(""), None);
+        assert_eq!(TagName::new("blockquoteblockquote"), None);
+    }
+
+    #[test]
+    fn symbols_ignore_case_of_html_elements() {
+        let dom = parse("<p>a</p><P>b</P><p>c</p>", ParserOptions::default()).unwrap();
+        let symbols = tags(&dom)
+            .iter()
+            .map(|tag| tag.tag_name())
+            .collect::<Vec<_>>();
+
+        assert_eq!(symbols, vec![TagName::new("p"); 3]);
+        assert_eq!(tags(&dom)[1].name(), "p");
+        assert_eq!(tags(&dom)[1].raw_name().as_utf8_str(), "P");
+    }
+
+    #[test]
+    fn known_names_are_not_stored() {
+        let dom = parse(
+            "<DIV><Custom-Element></Custom-Element></DIV>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let tags = tags(&dom);
+
+        assert!(matches!(tags[0]._name, ElementName::Known(_)));
+        assert_eq!(tags[0]._name.heap_size(), 0);
+        assert!(matches!(tags[1]._name, ElementName::Other(_)));
+        assert_eq!(tags[1].name(), "custom-element");
+        assert_eq!(
+            std::mem::size_of::<ElementName<'_>>(),
+            std::mem::size_of::<Bytes<'_>>()
+        );
+    }
+
+    #[test]
+    fn unknown_names_have_no_symbol() {
+        let dom = parse("<my-p>a</my-p><foo></foo>", ParserOptions::default()).unwrap();
+
+        assert!(tags(&dom).iter().all(|tag| tag.tag_name().is_none()));
+        assert_eq!(dom.query_selector("my-p").unwrap().count(), 1);
+        assert_eq!(dom.query_selector("foo").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn foreign_and_xml_names_are_case_sensitive() {
+        let dom = parse(
+            "<svg><a></a><A></A><title></title></svg>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let symbols = tags(&dom)
+            .iter()
+            .map(|tag| tag.tag_name())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            symbols,
+            [
+                TagName::new("svg"),
+                TagName::new("a"),
+                None,
+                TagName::new("title")
+            ]
+        );
+        assert_eq!(dom.query_selector("svg > a").unwrap().count(), 1);
+
+        let xml = parse("<a><A></A></a>", ParserOptions::default().xml_mode()).unwrap();
+        let symbols = tags(&xml)
+            .iter()
+            .map(|tag| tag.tag_name())
+            .collect::<Vec<_>>();
+
+        assert_eq!(symbols, [TagName::new("a"), None]);
+        assert_eq!(xml.query_selector("A").unwrap().count(), 1);
+        assert_eq!(xml.query_selector("a").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn symbols_are_the_same_in_every_document() {
+        let a = parse("<span></span>", ParserOptions::default()).unwrap();
+        let b = parse("<div><span></span></div>", ParserOptions::default()).unwrap();
+
+        assert_eq!(tags(&a)[0].tag_name(), tags(&b)[1].tag_name());
+
+        let cached = crate::cache::serialize(&b);
+        let loaded = crate::cache::load(&cached).unwrap();
+        assert_eq!(tags(&loaded)[0].tag_name(), TagName::new("div"));
+
+        let reparsed = b.reparse("<section></section>").unwrap();
+        assert_eq!(tags(&reparsed)[0].tag_name(), TagName::new("section"));
+    }
+
+    #[test]
+    fn renamed_tags_lose_their_symbol() {
+        let mut dom = parse("<p>a</p><p>b</p>", ParserOptions::default()).unwrap();
+
+        let b = dom.nodes_mut()[2].as_tag_mut().unwrap();
+        *b.name_mut() = "div".into();
+        assert_eq!(b.tag_name(), None);
+        assert_eq!(b.name(), "div");
+
+        // tag selectors still find renamed tags by their name
+        assert_eq!(dom.query_selector("p").unwrap().count(), 1);
+        assert_eq!(dom.query_selector("div").unwrap().count(), 1);
+        assert_eq!(dom.outer_html(), "<p>a</p><div>b</div>");
+    }
+
+    #[test]
+    fn known_attribute_names_are_not_copied() {
+        let dom = parse(
+            r#"<a HREF="x" Data-Foo="y" title="z"></a>"#,
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let attributes = &tags(&dom)[0]._attributes;
+        let size = |name: &str| {
+            attributes
+                .raw
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(key, _)| key.heap_size())
+        };
+
+        assert_eq!(size("href"), Some(0));
+        assert_eq!(size("title"), Some(0));
+        assert!(size("data-foo").unwrap() > 0);
+        assert_eq!(attributes.get("href").flatten().unwrap(), "x");
+
+        assert_eq!(known_attribute(b"SRC").unwrap(), *"src");
+        assert_eq!(known_attribute(b"data-foo"), None);
+    }
+}
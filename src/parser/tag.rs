@@ -1,11 +1,25 @@
 use crate::{
+    errors::SetBytesError,
+    fragment::OwnedFragment,
     inline::{hashmap::InlineHashMap, vec::InlineVec},
-    queryselector::{self, QuerySelectorIterator},
+    microsyntax::{self, SrcsetCandidate, StyleDeclarations},
+    queryselector::{self, Matcher, QuerySelectorIterator},
+    table::Table,
+    text::TextOptions,
     Bytes, InnerNodeHandle,
 };
-use std::{borrow::Cow, mem};
-
-use super::{handle::NodeHandle, Parser};
+use std::{borrow::Cow, collections::HashMap, mem, ops::Range};
+
+use super::{
+    classes::{ClassIter, ClassList},
+    constants,
+    doctype::Doctype,
+    handle::NodeHandle,
+    namespace::Namespace,
+    span::{AttributeSpan, Span, TagSpan},
+    symbol::{ElementName, TagName},
+    Parser,
+};
 
 const INLINED_ATTRIBUTES: usize = 2;
 const INLINED_SUBNODES: usize = 2;
@@ -63,13 +77,13 @@ impl<'a> Attributes<'a> {
 
     /// Checks whether a given string is in the class names list
     pub fn is_class_member<B: AsRef<[u8]>>(&self, member: B) -> bool {
-        self.class_iter()
-            .map_or(false, |mut i| i.any(|s| s.as_bytes() == member.as_ref()))
+        self.classes().contains(member)
     }
 
     /// Checks whether this attributes collection contains a given key and returns its value
     ///
-    /// Attributes that exist in this tag but have no value set will have their inner Option set to None
+    /// Attributes that exist in this tag but have no value set will have their inner Option set to None.
+    /// Names are compared ASCII case insensitively, like in HTML, see [`ParserOptions::preserve_attribute_case`](crate::ParserOptions::preserve_attribute_case).
     pub fn get<B>(&self, key: B) -> Option<Option<&Bytes<'a>>>
     where
         B: Into<Bytes<'a>>,
@@ -77,10 +91,32 @@ impl<'a> Attributes<'a> {
         let key: Bytes = key.into();
 
         match key.as_bytes() {
-            b"id" => self.id.as_ref().map(Some),
-            b"class" => self.class.as_ref().map(Some),
-            _ => self.raw.get(&key).map(|x| x.as_ref()),
+            key if key.eq_ignore_ascii_case(b"id") => self.id.as_ref().map(Some),
+            key if key.eq_ignore_ascii_case(b"class") => self.class.as_ref().map(Some),
+            _ => match self.raw.get(&key) {
+                Some(value) => Some(value.as_ref()),
+                None => self.raw.get(&self.stored_key(key)).map(|x| x.as_ref()),
+            },
+        }
+    }
+
+    /// Returns the name under which an attribute other than `id` and `class` is stored, comparing names ASCII case insensitively
+    ///
+    /// Returns the given name if there is no such attribute.
+    pub(crate) fn stored_key<'k>(&self, key: Bytes<'k>) -> Bytes<'k>
+    where
+        'a: 'k,
+    {
+        if self.raw.contains_key(&key) {
+            return key;
         }
+
+        self.raw
+            .iter()
+            .map(|(name, _)| name)
+            .find(|name| name.as_bytes().eq_ignore_ascii_case(key.as_bytes()))
+            .cloned()
+            .unwrap_or(key)
     }
 
     /// Checks whether this attributes collection contains a given key
@@ -91,6 +127,186 @@ impl<'a> Attributes<'a> {
         self.get(key).is_some()
     }
 
+    /// Parses the value of an attribute as an integer, following the
+    /// [HTML rules for parsing integers](https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-integers)
+    ///
+    /// Leading whitespace and anything after the digits is ignored, so `" 2px"` parses as `2`.
+    /// Returns `None` if the attribute is missing, has no value, does not start with a number or does not fit into an `i64`.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<td colspan=" 2" rowspan="3x" width="wide"></td>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert_eq!(attributes.get_int("colspan"), Some(2));
+    /// assert_eq!(attributes.get_int("rowspan"), Some(3));
+    /// assert_eq!(attributes.get_int("width"), None);
+    /// ```
+    pub fn get_int<B>(&self, key: B) -> Option<i64>
+    where
+        B: Into<Bytes<'a>>,
+    {
+        let value = self.get(key).flatten()?.as_bytes();
+        let value = value.trim_ascii_start();
+
+        let (negative, digits) = match value.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            Some((b'+', rest)) => (false, rest),
+            _ => (false, value),
+        };
+
+        let len = digits.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+
+        digits[..len].iter().try_fold(0i64, |acc, &digit| {
+            let digit = i64::from(digit - b'0');
+            let acc = acc.checked_mul(10)?;
+
+            if negative {
+                acc.checked_sub(digit)
+            } else {
+                acc.checked_add(digit)
+            }
+        })
+    }
+
+    /// Returns the value of an attribute as a URL, with surrounding whitespace removed
+    /// and resolved against the given base URL with [`links::resolve`](crate::links::resolve) if there is one
+    ///
+    /// Returns `None` if the attribute is missing or has no value.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a href=" ../about ">About</a>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert_eq!(attributes.get_url("href", None).as_deref(), Some("../about"));
+    /// assert_eq!(
+    ///     attributes.get_url("href", Some("https://example.com/blog/post")).as_deref(),
+    ///     Some("https://example.com/about")
+    /// );
+    /// ```
+    pub fn get_url<B>(&self, key: B, base: Option<&str>) -> Option<String>
+    where
+        B: Into<Bytes<'a>>,
+    {
+        let value = self.get(key).flatten()?.as_utf8_str();
+        let value = value.trim_matches(|c: char| c.is_ascii_whitespace());
+
+        Some(match base {
+            Some(base) => crate::links::resolve(base, value),
+            None => value.to_owned(),
+        })
+    }
+
+    /// Checks whether a boolean attribute like `disabled` or `async` is set
+    ///
+    /// As in HTML, only the presence of the attribute matters: `async=""`, `async="async"` and even `async="false"` all count as set.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<script async defer="false"></script>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert!(attributes.get_bool("async"));
+    /// assert!(attributes.get_bool("defer"));
+    /// assert!(!attributes.get_bool("nomodule"));
+    /// ```
+    pub fn get_bool<B>(&self, key: B) -> bool
+    where
+        B: Into<Bytes<'a>>,
+    {
+        self.contains(key)
+    }
+
+    /// Parses the `srcset` attribute into its image candidates
+    ///
+    /// Returns an empty list if the attribute is missing or has no value. See [`microsyntax::srcset()`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::microsyntax::Descriptor;
+    ///
+    /// let dom = tl::parse(r#"<img srcset="small.jpg 480w, large.jpg 1080w">"#, Default::default()).unwrap();
+    /// let candidates = dom.nodes()[0].as_tag().unwrap().attributes().srcset();
+    ///
+    /// assert_eq!(candidates[1].url, "large.jpg");
+    /// assert_eq!(candidates[1].descriptor, Descriptor::Width { width: 1080, height: None });
+    /// ```
+    pub fn srcset(&self) -> Vec<SrcsetCandidate<'_>> {
+        self.get("srcset")
+            .flatten()
+            .and_then(Bytes::try_as_utf8_str)
+            .map(microsyntax::srcset)
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over the `(property, value)` declarations of the `style` attribute
+    ///
+    /// The iterator is empty if the attribute is missing or has no value. See [`StyleDeclarations`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p style="color: red; margin:0">x</p>"#, Default::default()).unwrap();
+    /// let mut style = dom.nodes()[0].as_tag().unwrap().attributes().style();
+    ///
+    /// assert_eq!(style.next(), Some(("color", "red")));
+    /// assert_eq!(style.next(), Some(("margin", "0")));
+    /// assert_eq!(style.next(), None);
+    /// ```
+    pub fn style(&self) -> StyleDeclarations<'_> {
+        let style = self.get("style").flatten().and_then(Bytes::try_as_utf8_str);
+        StyleDeclarations::new(style.unwrap_or_default())
+    }
+
+    /// Returns an iterator over the tokens of an attribute whose value is a list separated by ASCII whitespace,
+    /// like `rel`, `sizes` or `headers`
+    ///
+    /// Like `DOMTokenList` in browsers, every token is only yielded once, in the order of its first occurrence.
+    /// The iterator is empty if the attribute is missing, has no value or is not valid UTF-8.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<a rel=' nofollow\tnoopener nofollow '>x</a>", Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert_eq!(attributes.get_list("rel").collect::<Vec<_>>(), ["nofollow", "noopener"]);
+    /// assert_eq!(attributes.get_list("headers").count(), 0);
+    /// ```
+    pub fn get_list<B>(&self, key: B) -> ClassIter<'_>
+    where
+        B: Into<Bytes<'a>>,
+    {
+        let value = self.get(key).flatten().and_then(Bytes::try_as_utf8_str);
+        ClassList::new(value.unwrap_or_default()).iter()
+    }
+
+    /// Checks whether a token is in the whitespace separated list of an attribute, see [`Attributes::get_list`]
+    ///
+    /// Tokens are compared case sensitively, like the `[rel~=token]` query selector.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<td headers="name  price">1</td>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert!(attributes.contains_token("headers", "price"));
+    /// assert!(!attributes.contains_token("headers", "pri"));
+    /// assert!(!attributes.contains_token("headers", ""));
+    /// ```
+    pub fn contains_token<B, T>(&self, key: B, token: T) -> bool
+    where
+        B: Into<Bytes<'a>>,
+        T: AsRef<[u8]>,
+    {
+        self.get(key)
+            .flatten()
+            .and_then(Bytes::try_as_utf8_str)
+            .is_some_and(|value| ClassList::new(value).contains(token))
+    }
+
     /// Removes an attribute from this collection and returns it.
     ///
     /// As with [`Attributes::get()`], the outer Option is set to None if the attribute does not exist.
@@ -112,9 +328,9 @@ impl<'a> Attributes<'a> {
         let key: Bytes = key.into();
 
         match key.as_bytes() {
-            b"id" => self.id.take().map(Some),
-            b"class" => self.class.take().map(Some),
-            _ => self.raw.remove(&key),
+            key if key.eq_ignore_ascii_case(b"id") => self.id.take().map(Some),
+            key if key.eq_ignore_ascii_case(b"class") => self.class.take().map(Some),
+            _ => self.raw.remove(&self.stored_key(key)),
         }
     }
 
@@ -136,9 +352,9 @@ impl<'a> Attributes<'a> {
         let key: Bytes = key.into();
 
         match key.as_bytes() {
-            b"id" => self.id.take(),
-            b"class" => self.class.take(),
-            _ => self.raw.get_mut(&key).and_then(mem::take),
+            key if key.eq_ignore_ascii_case(b"id") => self.id.take(),
+            key if key.eq_ignore_ascii_case(b"class") => self.class.take(),
+            _ => self.raw.get_mut(&self.stored_key(key)).and_then(mem::take),
         }
     }
 
@@ -150,13 +366,15 @@ impl<'a> Attributes<'a> {
         let key: Bytes = key.into();
 
         match key.as_bytes() {
-            b"id" => self.id.as_mut().map(Some),
-            b"class" => self.class.as_mut().map(Some),
-            _ => self.raw.get_mut(&key).map(Option::as_mut),
+            key if key.eq_ignore_ascii_case(b"id") => self.id.as_mut().map(Some),
+            key if key.eq_ignore_ascii_case(b"class") => self.class.as_mut().map(Some),
+            _ => self.raw.get_mut(&self.stored_key(key)).map(Option::as_mut),
         }
     }
 
     /// Inserts a new attribute into this attributes collection
+    ///
+    /// If the attribute already exists under a name that only differs in case, its value is replaced and its name is kept.
     pub fn insert<K, V>(&mut self, key: K, value: Option<V>)
     where
         K: Into<Bytes<'a>>,
@@ -166,14 +384,56 @@ impl<'a> Attributes<'a> {
         let value = value.map(Into::into);
 
         match key.as_bytes() {
-            b"id" => self.id = value,
-            b"class" => self.class = value,
-            _ => self.raw.insert(key, value),
+            key if key.eq_ignore_ascii_case(b"id") => self.id = value,
+            key if key.eq_ignore_ascii_case(b"class") => self.class = value,
+            _ => self.raw.insert(self.stored_key(key), value),
         };
     }
 
+    /// Retains only the attributes for which the given closure returns `true`, and removes the rest.
+    ///
+    /// The closure is called with the attribute key and its value, if any.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<a href="/" onclick="x()" id="y"></a>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    ///
+    /// attributes.retain(|key, _| !key.as_bytes().starts_with(b"on"));
+    ///
+    /// assert!(!attributes.contains("onclick"));
+    /// assert_eq!(attributes.len(), 2);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Bytes<'a>, Option<&Bytes<'a>>) -> bool,
+    {
+        let remove = self
+            .raw
+            .iter()
+            .filter(|(k, v)| !f(k, v.as_ref()))
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+
+        for key in remove {
+            self.raw.remove(&key);
+        }
+
+        if let Some(id) = &self.id {
+            if !f(&Bytes::from("id"), Some(id)) {
+                self.id = None;
+            }
+        }
+
+        if let Some(class) = &self.class {
+            if !f(&Bytes::from("class"), Some(class)) {
+                self.class = None;
+            }
+        }
+    }
+
     /// Returns an iterator `(attribute_key, attribute_value)` over the attributes of this `HTMLTag`
-    pub fn iter(&self) -> impl Iterator<Item = (Cow<str>, Option<Cow<str>>)> + '_ {
+    pub fn iter(&self) -> impl Iterator<Item = (Cow<'_, str>, Option<Cow<'_, str>>)> + '_ {
         self.raw
             .iter()
             .map(|(k, v)| {
@@ -184,11 +444,11 @@ impl<'a> Attributes<'a> {
             })
             .chain([
                 (
-                    self.id.is_some().then(|| Cow::Borrowed("id")),
+                    self.id.is_some().then_some(Cow::Borrowed("id")),
                     self.id.as_ref().map(|x| x.as_utf8_str()),
                 ),
                 (
-                    self.class.is_some().then(|| Cow::Borrowed("class")),
+                    self.class.is_some().then_some(Cow::Borrowed("class")),
                     self.class.as_ref().map(|x| x.as_utf8_str()),
                 ),
             ])
@@ -213,6 +473,93 @@ impl<'a> Attributes<'a> {
             .map(str::split_ascii_whitespace)
     }
 
+    /// Returns a set-like view of the class names of this HTML tag
+    ///
+    /// Unlike [`Attributes::class_iter()`], the view yields every class name only once,
+    /// and it is empty if the tag has no `class` attribute.
+    ///
+    /// See [`ClassList`] for more details.
+    pub fn classes(&self) -> ClassList<'_> {
+        let class = self.class.as_ref().and_then(Bytes::try_as_utf8_str);
+        ClassList::new(class.unwrap_or_default())
+    }
+
+    /// Adds a class name to the `class` attribute, creating the attribute if it does not exist yet
+    ///
+    /// Returns `Ok(false)` without changing anything if the class is already present, or if it is empty or contains whitespace
+    /// and therefore is not a single class name.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<p class="a"></p><p></p>"#, Default::default()).unwrap();
+    ///
+    /// let first = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    /// assert_eq!(first.add_class("b"), Ok(true));
+    /// assert_eq!(first.add_class("a"), Ok(false));
+    /// assert_eq!(first.class().unwrap(), "a b");
+    ///
+    /// let second = dom.nodes_mut()[1].as_tag_mut().unwrap().attributes_mut();
+    /// assert_eq!(second.add_class("c"), Ok(true));
+    /// assert_eq!(second.class().unwrap(), "c");
+    /// ```
+    pub fn add_class(&mut self, class: &str) -> Result<bool, SetBytesError> {
+        let is_single = !class.is_empty() && !class.contains(|c: char| c.is_ascii_whitespace());
+
+        if !is_single || self.is_class_member(class) {
+            return Ok(false);
+        }
+
+        let value = match &self.class {
+            Some(old) => {
+                let old = old.as_utf8_str();
+                let old = old.trim_end_matches(|c: char| c.is_ascii_whitespace());
+
+                if old.is_empty() {
+                    class.to_owned()
+                } else {
+                    format!("{old} {class}")
+                }
+            }
+            None => class.to_owned(),
+        };
+
+        self.class.get_or_insert_with(Bytes::new).set(value)?;
+        Ok(true)
+    }
+
+    /// Removes all occurrences of a class name from the `class` attribute
+    ///
+    /// The remaining class names are separated by single spaces. As in the DOM, the `class` attribute is kept
+    /// even if it becomes empty. Returns `Ok(false)` without changing anything if the class is not present.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<p class="a  b a c"></p>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    ///
+    /// assert_eq!(attributes.remove_class("a"), Ok(true));
+    /// assert_eq!(attributes.remove_class("d"), Ok(false));
+    /// assert_eq!(attributes.class().unwrap(), "b c");
+    /// ```
+    pub fn remove_class(&mut self, class: &str) -> Result<bool, SetBytesError> {
+        if !self.is_class_member(class) {
+            return Ok(false);
+        }
+
+        let value = self
+            .classes()
+            .iter()
+            .filter(|&c| c != class)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if let Some(old) = &mut self.class {
+            old.set(value)?;
+        }
+
+        Ok(true)
+    }
+
     /// Returns the underlying raw map for attributes
     ///
     /// ## A note on stability
@@ -228,22 +575,34 @@ impl<'a> Attributes<'a> {
 /// Represents a single HTML element
 #[derive(Debug, Clone)]
 pub struct HTMLTag<'a> {
-    pub(crate) _name: Bytes<'a>,
+    pub(crate) _name: ElementName<'a>,
     pub(crate) _attributes: Attributes<'a>,
     pub(crate) _children: RawChildren,
     pub(crate) _parent: Option<RawParent>,
     pub(crate) _raw: Bytes<'a>,
+    pub(crate) _span: TagSpan,
+    /// The handles of all descendants of this element
+    ///
+    /// Handles are assigned in document order, so the descendants of an element always form a contiguous range
+    /// that starts right after the element itself.
+    pub(crate) _descendants: Range<u32>,
+    pub(crate) _namespace: Namespace,
+    /// Whether the name or attributes may have been changed, so that the start tag in the source is outdated
+    pub(crate) _modified: bool,
+    /// Whether this element was parsed as a void element because of the parser options, although it is not an HTML void element
+    pub(crate) _void: bool,
 }
 
 impl<'a> HTMLTag<'a> {
     /// Creates a new HTMLTag
     #[inline(always)]
     pub(crate) fn new(
-        name: Bytes<'a>,
+        name: ElementName<'a>,
         attr: Attributes<'a>,
         children: InlineVec<NodeHandle, INLINED_SUBNODES>,
         parent: Option<NodeHandle>,
         raw: Bytes<'a>,
+        start_tag: Span,
     ) -> Self {
         Self {
             _name: name,
@@ -251,47 +610,549 @@ impl<'a> HTMLTag<'a> {
             _children: children,
             _parent: parent,
             _raw: raw,
+            _span: TagSpan {
+                start_tag,
+                end_tag: None,
+            },
+            _descendants: 0..0,
+            _namespace: Namespace::Html,
+            _modified: false,
+            _void: false,
+        }
+    }
+
+    /// Returns a wrapper around the children of this HTML tag.
+    #[inline]
+    pub fn children(&self) -> Children<'a, '_> {
+        Children(self)
+    }
+
+    /// Returns an iterator over the direct children of this HTML tag that are elements.
+    ///
+    /// Unlike [`Children::top`], this skips text and comment nodes.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul> <li>1</li> <li>2</li> </ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert_eq!(ul.children().top().len(), 5);
+    /// assert_eq!(ul.child_elements(parser).count(), 2);
+    /// ```
+    pub fn child_elements<'p>(
+        &'p self,
+        parser: &'p Parser<'a>,
+    ) -> impl Iterator<Item = NodeHandle> + 'p {
+        self._children.iter().copied().filter(move |handle| {
+            handle
+                .get(parser)
+                .is_some_and(|node| node.as_tag().is_some())
+        })
+    }
+
+    /// Returns a mutable wrapper around the children of this HTML tag.
+    pub fn children_mut(&mut self) -> ChildrenMut<'a, '_> {
+        ChildrenMut(self)
+    }
+
+    /// Returns the contents of this element if it is a `<template>`, or `None` otherwise
+    ///
+    /// The contents of a template are not rendered, so they are not part of the document in browsers.
+    /// They are still parsed as the children of the template, but query selectors on the document or on
+    /// any element that contains the template do not match them. Query selectors on the template itself do.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<template><img src="a.png"></template><img src="b.png">"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// assert_eq!(dom.query_selector("img").unwrap().count(), 1);
+    ///
+    /// let template = dom.nodes()[0].as_tag().unwrap();
+    /// let content = template.template_content().unwrap();
+    /// assert_eq!(content.len(), 1);
+    /// assert_eq!(template.query_selector(parser, "img").unwrap().count(), 1);
+    /// ```
+    pub fn template_content(&self) -> Option<&[NodeHandle]> {
+        self._name
+            .as_bytes()
+            .eq_ignore_ascii_case(b"template")
+            .then(|| self._children.as_slice())
+    }
+
+    /// Returns a wrapper around the parent of this HTML tag.
+    pub fn parent(&self) -> Parent<'a, '_> {
+        Parent(self)
+    }
+
+    /// Checks whether the given node is a descendant of this HTML tag
+    ///
+    /// This is a constant time range check on the node handles, so it does not walk the parents of the node.
+    /// The element itself is not its own descendant.
+    ///
+    /// **Note:** This reflects the structure of the document at the time of parsing.
+    /// Moving nodes with [`HTMLTag::children_mut`] does *not* update it.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p><b>Hi</b></p></div><span></span>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let div = dom.query_selector("div").unwrap().next().unwrap();
+    /// let b = dom.query_selector("b").unwrap().next().unwrap();
+    /// let span = dom.query_selector("span").unwrap().next().unwrap();
+    ///
+    /// let div = div.get(parser).unwrap().as_tag().unwrap();
+    /// assert!(div.contains(b));
+    /// assert!(!div.contains(span));
+    /// ```
+    #[inline]
+    pub fn contains(&self, node: NodeHandle) -> bool {
+        self._descendants.contains(&node.get_inner())
+    }
+
+    /// Returns the language of this element, which is inherited from the nearest ancestor with a `lang` attribute
+    ///
+    /// Like in browsers, `xml:lang` takes precedence over `lang`, and an empty attribute means that the language is unknown.
+    /// The value is returned as written, e.g. `en-US`.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<html lang="en"><body><p>Hi</p><p lang="de-AT">Servus</p><p lang="">?</p></body></html>"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let langs = dom
+    ///     .query_selector("p")
+    ///     .unwrap()
+    ///     .tags()
+    ///     .map(|p| p.effective_lang(parser).map(|lang| lang.as_utf8_str().into_owned()))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(langs, [Some("en".to_owned()), Some("de-AT".to_owned()), None]);
+    /// ```
+    pub fn effective_lang<'p>(&'p self, parser: &'p Parser<'a>) -> Option<&'p Bytes<'a>> {
+        crate::meta::effective_lang(self, parser)
+    }
+
+    /// Checks whether this element is a custom element, like `<my-card>`
+    ///
+    /// Following the HTML specification, this is an element in the HTML namespace whose name starts with a letter
+    /// and contains a hyphen, except for a few names that are reserved by SVG and MathML like `font-face`.
+    /// Unlike HTML elements, custom elements are never void, see [`ParserOptions::treat_unknown_as_void`](crate::ParserOptions::treat_unknown_as_void).
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<my-card><h2>Title</h2></my-card><font-face></font-face>", Default::default()).unwrap();
+    /// let tags = dom.nodes().iter().filter_map(|node| node.as_tag()).collect::<Vec<_>>();
+    ///
+    /// assert!(tags[0].is_custom_element());
+    /// assert!(!tags[1].is_custom_element());
+    /// assert!(!tags[2].is_custom_element());
+    /// ```
+    pub fn is_custom_element(&self) -> bool {
+        !self._namespace.is_foreign() && is_custom_element_name(self._name.as_bytes())
+    }
+
+    /// Returns the position of this element among the elements with the same parent, starting at 0
+    ///
+    /// Elements at the top level of the document are siblings of each other.
+    pub(crate) fn element_index(&self, parser: &Parser<'a>) -> Option<usize> {
+        let position = parser.position_of(self.handle(parser)?)?;
+        Some(position.element_index as usize)
+    }
+
+    /// Returns the position of this element among the siblings with the same tag name, starting at 0,
+    /// and the number of these siblings including this element
+    ///
+    /// Tag names are compared ASCII case-insensitively.
+    pub(crate) fn type_index(&self, parser: &Parser<'a>) -> Option<(usize, usize)> {
+        let handle = self.handle(parser)?;
+        let name = self._name.as_bytes();
+
+        let mut index = None;
+        let mut count = 0;
+
+        for &sibling in parser.siblings_of(handle) {
+            let same_type = sibling
+                .get(parser)
+                .and_then(Node::as_tag)
+                .is_some_and(|tag| tag._name.as_bytes().eq_ignore_ascii_case(name));
+
+            if sibling.get_inner() == handle.get_inner() {
+                index = Some(count);
+            }
+            count += same_type as usize;
+        }
+
+        Some((index?, count))
+    }
+
+    /// Returns the handle of this element, or `None` if it is not part of the given parser
+    fn handle(&self, parser: &Parser<'a>) -> Option<NodeHandle> {
+        parser.handle_of(self).filter(|handle| {
+            handle
+                .get(parser)
+                .and_then(Node::as_tag)
+                .is_some_and(|tag| std::ptr::eq(tag, self))
+        })
+    }
+
+    /// Returns the child element at the given index, starting at 0, skipping text, comments and other nodes
+    ///
+    /// The parser stores the position of every node among its siblings, so this does not need to walk the children
+    /// unless the document was borrowed mutably since parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul>\n  <li>A</li>\n  <!-- B -->\n  <li>C</li>\n</ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// let second = ul.child_element_at(1, parser).unwrap();
+    /// assert_eq!(second.get(parser).unwrap().inner_text(parser), "C");
+    /// assert_eq!(ul.child_element_at(2, parser), None);
+    /// ```
+    pub fn child_element_at(&self, index: usize, parser: &Parser<'a>) -> Option<NodeHandle> {
+        let children = self._children.as_slice();
+        let is_element = |handle: &&NodeHandle| matches!(handle.get(parser), Some(Node::Tag(_)));
+
+        if !parser.has_positions() {
+            return children.iter().filter(is_element).nth(index).copied();
+        }
+
+        // the number of elements before a child never decreases, so the first child with `index` elements before it
+        // is found with a binary search, and the element is the first one from there
+        let element_index = |handle: &NodeHandle| {
+            parser
+                .siblings()
+                .positions
+                .get(handle.get_inner() as usize)
+                .map_or(0, |position| position.element_index as usize)
+        };
+        let start = children.partition_point(|handle| element_index(handle) < index);
+
+        children[start..].iter().find(is_element).copied()
+    }
+
+    /// Generates a query selector that uniquely identifies this element in its document, like `div#content > ul > li:nth-child(3) > a`
+    ///
+    /// The path starts at the nearest ancestor with an ID that is unique in the document, or at the top level.
+    /// Elements that have siblings with the same name are identified by their position with `:nth-child()`.
+    /// The selector uses standard CSS syntax, so it can also be used in browsers and other tools.
+    ///
+    /// A path that starts at the top level of a fragment without an `<html>` element can also match deeper elements,
+    /// because selectors cannot express that an element has no parent.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<div id="content"><ul><li>1</li><li>2</li><li><a href="/3">3</a></li></ul></div>"#,
+    ///     Default::default(),
+    /// )
+    /// .unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let link = dom.query_selector("a").unwrap().next().unwrap();
+    /// let path = link.get(parser).unwrap().as_tag().unwrap().css_path(parser);
+    ///
+    /// assert_eq!(path, "div#content > ul > li:nth-child(3) > a");
+    /// assert_eq!(dom.query_selector(&path).unwrap().collect::<Vec<_>>(), [link]);
+    /// ```
+    pub fn css_path(&self, parser: &Parser<'a>) -> String {
+        let mut parts = Vec::new();
+        let mut current = self;
+
+        loop {
+            let mut part = current.name().as_utf8_str().into_owned();
+
+            if let Some(id) = current._attributes.id.as_ref() {
+                if is_css_identifier(id.as_bytes()) && is_unique_id(id, parser) {
+                    part.push('#');
+                    part.push_str(&id.as_utf8_str());
+                    parts.push(part);
+                    break;
+                }
+            }
+
+            let parent = current.parent().get(parser).and_then(Node::as_tag);
+            let siblings = match parent {
+                Some(parent) => parent._children.as_slice(),
+                None => &parser.ast,
+            };
+
+            let same_name = siblings
+                .iter()
+                .filter_map(|handle| handle.get(parser)?.as_tag())
+                .filter(|sibling| sibling._name.as_bytes() == current._name.as_bytes())
+                .count();
+
+            if same_name > 1 {
+                if let Some(index) = current.element_index(parser) {
+                    part.push_str(&format!(":nth-child({})", index + 1));
+                }
+            }
+
+            parts.push(part);
+
+            match parent {
+                Some(parent) => current = parent,
+                None => break,
+            }
         }
+
+        parts.reverse();
+        parts.join(" > ")
+    }
+
+    /// Returns the name of this HTML tag
+    ///
+    /// Names of HTML elements are in lowercase, because they are case insensitive, so `<DIV>` is named `div`.
+    /// The names of elements inside of `<svg>` and `<math>` and of elements in [XML mode](crate::ParserOptions::xml_mode)
+    /// are case sensitive and kept as written. See [`HTMLTag::raw_name`] for the name as written.
+    #[inline]
+    pub fn name(&self) -> &Bytes<'a> {
+        self._name.bytes()
+    }
+
+    /// Returns the name of this HTML tag as written in the source, like `DIV` for `<DIV>`
+    ///
+    /// This is the same as [`HTMLTag::name`] for tags that were renamed with [`HTMLTag::name_mut`].
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<DIV><Br></DIV>", Default::default()).unwrap();
+    /// let tag = dom.nodes()[1].as_tag().unwrap();
+    ///
+    /// assert_eq!(tag.name(), "br");
+    /// assert_eq!(tag.raw_name().as_utf8_str(), "Br");
+    /// assert_eq!(dom.query_selector("div > br").unwrap().count(), 1);
+    /// ```
+    pub fn raw_name(&self) -> Bytes<'_> {
+        let name = self._name.as_bytes();
+        let source = self._raw.as_bytes().strip_prefix(b"<").unwrap_or_default();
+
+        source
+            .trim_ascii_start()
+            .get(..name.len())
+            .filter(|raw| raw.eq_ignore_ascii_case(name))
+            .unwrap_or(name)
+            .into()
     }
 
-    /// Returns a wrapper around the children of this HTML tag.
-    #[inline]
-    pub fn children(&self) -> Children<'a, '_> {
-        Children(self)
+    /// Returns the interned symbol of the name of this HTML tag, or `None` if it is not the name of a known HTML element
+    /// or the tag was renamed with [`HTMLTag::name_mut`]
+    ///
+    /// See [`TagName`] for more details.
+    #[inline]
+    pub fn tag_name(&self) -> Option<TagName> {
+        self._name.symbol()
+    }
+
+    /// Returns the namespace of this element, which is [`Namespace::Svg`] or [`Namespace::MathMl`] within `<svg>` and `<math>` elements
+    #[inline]
+    pub fn namespace(&self) -> Namespace {
+        self._namespace
+    }
+
+    /// Returns a mutable reference to the name of this HTML tag
+    ///
+    /// The tag loses its interned [`TagName`] symbol, even if the new name is a known name.
+    #[inline]
+    pub fn name_mut(&mut self) -> &mut Bytes<'a> {
+        self._modified = true;
+        self._name.to_mut()
+    }
+
+    /// Returns attributes of this HTML tag
+    #[inline]
+    pub fn attributes(&self) -> &Attributes<'a> {
+        &self._attributes
+    }
+
+    /// Returns a mutable reference to the attributes of this HTML tag
+    ///
+    /// The tag counts as modified for [`VDom::to_html`](crate::VDom::to_html) afterwards, even if the attributes were not changed.
+    #[inline]
+    pub fn attributes_mut(&mut self) -> &mut Attributes<'a> {
+        self._modified = true;
+        &mut self._attributes
+    }
+
+    /// Returns an iterator over the attributes of this HTML tag as they appear in the source, including repeated attributes
+    ///
+    /// Each item is the name, the value if there is one, and the byte span of the whole attribute in the source string.
+    /// Unlike [`Attributes`], this reflects the start tag as it was parsed and does not include changes made after parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let source = r#"<input name="q" disabled name='r'>"#;
+    /// let dom = tl::parse(source, Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// let names = tag
+    ///     .source_attributes()
+    ///     .map(|(name, ..)| name.as_utf8_str().into_owned())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(names, ["name", "disabled", "name"]);
+    ///
+    /// let (_, value, span) = tag.source_attributes().last().unwrap();
+    /// assert_eq!(value.unwrap().as_utf8_str(), "r");
+    /// assert_eq!(&source[span.range()], "name='r'");
+    /// ```
+    pub fn source_attributes(&self) -> SourceAttributes<'_> {
+        let offset = self._span.start_tag.start();
+        let raw = self._raw.as_bytes();
+        let start_tag = raw.get(..self._span.start_tag.len()).unwrap_or(raw);
+
+        SourceAttributes(Parser::source_attributes(start_tag, offset).into_iter())
+    }
+
+    /// Returns the positions of an attribute and of its name and value in the source string, comparing names ASCII case insensitively
+    ///
+    /// This can be used to patch a single attribute in the source string without serializing the document again.
+    /// If the attribute occurs more than once, this is the occurrence whose value [`Attributes`] holds,
+    /// or the first one if the attribute was changed after parsing.
+    /// Like [`HTMLTag::source_attributes`], this reflects the start tag as it was parsed,
+    /// so it returns `None` for attributes that were added after parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let source = r#"<img src="a.png" SRC='b.png' alt>"#;
+    /// let dom = tl::parse(source, Default::default()).unwrap();
+    /// let img = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// // the last occurrence is kept by default
+    /// let span = img.attribute_span("src").unwrap();
+    /// assert_eq!(&source[span.name().range()], "SRC");
+    /// assert_eq!(&source[span.value().unwrap().range()], "b.png");
+    ///
+    /// assert!(img.attribute_span("title").is_none());
+    /// ```
+    pub fn attribute_span<B: AsRef<[u8]>>(&self, name: B) -> Option<AttributeSpan> {
+        let name = name.as_ref();
+        let raw = self._raw.as_bytes();
+        let offset = self._span.start_tag.start();
+        // keys and values borrow from the start tag, so their positions follow from their addresses
+        let span_of = |bytes: &[u8]| {
+            let start = offset + (bytes.as_ptr() as usize - raw.as_ptr() as usize);
+            Span::new(start, start + bytes.len())
+        };
+
+        let candidates = self
+            .source_attributes()
+            .filter(|(key, ..)| key.as_bytes().eq_ignore_ascii_case(name))
+            .collect::<Vec<_>>();
+
+        let current = self
+            ._attributes
+            .get(name)
+            .map(|value| value.map(Bytes::as_bytes));
+        let (key, value, attribute) = candidates
+            .iter()
+            .rev()
+            .find(|(_, value, _)| Some(value.as_ref().map(Bytes::as_bytes)) == current)
+            .or_else(|| candidates.first())?;
+
+        Some(AttributeSpan {
+            attribute: *attribute,
+            name: span_of(key.as_bytes()),
+            value: value.as_ref().map(|value| span_of(value.as_bytes())),
+        })
     }
 
-    /// Returns a mutable wrapper around the children of this HTML tag.
-    pub fn children_mut(&mut self) -> ChildrenMut<'a, '_> {
-        ChildrenMut(self)
+    /// Returns the value of the `id` attribute of this HTML tag, if present
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<section id="intro"></section>"#, Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(tag.id().as_deref(), Some("intro"));
+    /// ```
+    pub fn id(&self) -> Option<Cow<'_, str>> {
+        self._attributes.id.as_ref().map(Bytes::as_utf8_str)
     }
 
-    /// Returns a wrapper around the parent of this HTML tag.
-    pub fn parent(&self) -> Parent {
-        Parent(self)
+    /// Returns the value of the `href` attribute of this HTML tag, if present and not empty
+    ///
+    /// The value is returned as written in the source. Use [`Attributes::get_url()`] to trim and resolve it.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a href="/about">About</a><a href>Home</a>"#, Default::default()).unwrap();
+    /// let nodes = dom.nodes();
+    ///
+    /// assert_eq!(nodes[0].as_tag().unwrap().href().as_deref(), Some("/about"));
+    /// assert_eq!(nodes[2].as_tag().unwrap().href(), None);
+    /// ```
+    pub fn href(&self) -> Option<Cow<'_, str>> {
+        self.non_empty_attribute("href")
     }
 
-    /// Returns the name of this HTML tag
-    #[inline]
-    pub fn name(&self) -> &Bytes<'a> {
-        &self._name
+    /// Returns the value of the `src` attribute of this HTML tag, if present and not empty
+    ///
+    /// The value is returned as written in the source. Use [`Attributes::get_url()`] to trim and resolve it.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<img src="logo.png">"#, Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(tag.src().as_deref(), Some("logo.png"));
+    /// ```
+    pub fn src(&self) -> Option<Cow<'_, str>> {
+        self.non_empty_attribute("src")
     }
 
-    /// Returns a mutable reference to the name of this HTML tag
-    #[inline]
-    pub fn name_mut(&mut self) -> &mut Bytes<'a> {
-        &mut self._name
+    fn non_empty_attribute(&self, name: &'static str) -> Option<Cow<'_, str>> {
+        self._attributes
+            .get(name)
+            .flatten()
+            .filter(|value| !value.as_bytes().is_empty())
+            .map(Bytes::as_utf8_str)
     }
 
-    /// Returns attributes of this HTML tag
-    #[inline]
-    pub fn attributes(&self) -> &Attributes<'a> {
-        &self._attributes
+    /// Checks whether a boolean attribute like `disabled` or `checked` is set on this HTML tag
+    ///
+    /// This is a shorthand for [`Attributes::get_bool()`], so only the presence of the attribute matters.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<input type="checkbox" checked>"#, Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert!(tag.is_boolean_attr_set("checked"));
+    /// assert!(!tag.is_boolean_attr_set("disabled"));
+    /// ```
+    pub fn is_boolean_attr_set(&self, name: &str) -> bool {
+        self._attributes.get_bool(name)
     }
 
-    /// Returns a mutable reference to the attributes of this HTML tag
-    #[inline]
-    pub fn attributes_mut(&mut self) -> &mut Attributes<'a> {
-        &mut self._attributes
+    /// Returns all `data-*` attributes of this HTML tag as a map
+    ///
+    /// As with [HTMLElement#dataset](https://developer.mozilla.org/en-US/docs/Web/API/HTMLElement/dataset) in browsers,
+    /// the `data-` prefix is removed and the rest of the name is converted to camel case, so `data-user-id` becomes `userId`.
+    /// Attributes without a value map to an empty string.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<li data-user-id="42" data-active>Ada</li>"#, Default::default()).unwrap();
+    /// let dataset = dom.nodes()[0].as_tag().unwrap().dataset();
+    ///
+    /// assert_eq!(dataset.len(), 2);
+    /// assert_eq!(dataset["userId"], "42");
+    /// assert_eq!(dataset["active"], "");
+    /// ```
+    pub fn dataset(&self) -> HashMap<String, Cow<'_, str>> {
+        self._attributes
+            .raw
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.as_bytes().strip_prefix(b"data-")?;
+                let value = value.as_ref().map(Bytes::as_utf8_str).unwrap_or_default();
+
+                Some((dataset_key(name), value))
+            })
+            .collect()
     }
 
     /// Returns the contained markup
@@ -302,10 +1163,35 @@ impl<'a> HTMLTag<'a> {
     ///
     /// Equivalent to [Element#outerHTML](https://developer.mozilla.org/en-US/docs/Web/API/Element/outerHTML) in browsers)
     pub fn outer_html<'p>(&'p self, parser: &'p Parser<'a>) -> String {
-        let tag_name = self._name.as_utf8_str();
-        let is_void_element = HTML_VOID_ELEMENTS.contains(&tag_name.as_ref());
-        let mut outer_html = format!("<{}", &tag_name);
+        let mut outer_html = String::new();
+        self.write_start_tag(&mut outer_html);
 
+        // void elements have neither content nor a closing tag.
+        if self.is_void_element() {
+            return outer_html;
+        }
+
+        // TODO(y21): More of an idea than a TODO, but a potential perf improvement
+        // could be having some kind of internal inner_html function that takes a &mut String
+        // and simply writes to it instead of returning a newly allocated string for every element
+        // and appending it
+        outer_html.push_str(&self.inner_html(parser));
+
+        self.write_end_tag(&mut outer_html);
+
+        outer_html
+    }
+
+    /// Checks whether this tag is a void element, i.e. an element that has neither content nor a closing tag (like `<br>`)
+    #[inline]
+    pub(crate) fn is_void_element(&self) -> bool {
+        self._void
+            || (!self._namespace.is_foreign()
+                && HTML_VOID_ELEMENTS.contains(&self.name().as_utf8_str().as_ref()))
+    }
+
+    /// Writes the start tag including all attributes (`<p class="x">`) to `dest`
+    pub(crate) fn write_start_tag(&self, dest: &mut String) {
         #[inline]
         fn write_attribute(dest: &mut String, k: Cow<str>, v: Option<Cow<str>>) {
             dest.push(' ');
@@ -319,30 +1205,21 @@ impl<'a> HTMLTag<'a> {
             }
         }
 
-        let attr = self.attributes();
-
-        for (k, v) in attr.iter() {
-            write_attribute(&mut outer_html, k, v);
-        }
-
-        outer_html.push('>');
+        dest.push('<');
+        dest.push_str(&self.name().as_utf8_str());
 
-        // void elements have neither content nor a closing tag.
-        if is_void_element {
-            return outer_html;
+        for (k, v) in self.attributes().iter() {
+            write_attribute(dest, k, v);
         }
 
-        // TODO(y21): More of an idea than a TODO, but a potential perf improvement
-        // could be having some kind of internal inner_html function that takes a &mut String
-        // and simply writes to it instead of returning a newly allocated string for every element
-        // and appending it
-        outer_html.push_str(&self.inner_html(parser));
-
-        outer_html.push_str("</");
-        outer_html.push_str(&self._name.as_utf8_str());
-        outer_html.push('>');
+        dest.push('>');
+    }
 
-        outer_html
+    /// Writes the end tag (`</p>`) to `dest`
+    pub(crate) fn write_end_tag(&self, dest: &mut String) {
+        dest.push_str("</");
+        dest.push_str(&self.name().as_utf8_str());
+        dest.push('>');
     }
 
     /// Returns the contained markup
@@ -361,11 +1238,78 @@ impl<'a> HTMLTag<'a> {
             .collect::<String>()
     }
 
+    /// Returns the markup of this element, borrowing it from the source string if neither the element nor any of its
+    /// descendants were modified
+    ///
+    /// This is the counterpart of [`VDom::to_html`](crate::VDom::to_html) for a single element: unmodified elements return
+    /// their exact source without allocating, and modified ones are serialized again, still copying the source of
+    /// every unmodified descendant. Unlike [`HTMLTag::raw`], the result is never outdated.
+    /// Whether the element was modified is checked by going through its descendants, see [`HTMLTag::is_modified`].
+    ///
+    /// # Example
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// let mut dom = tl::parse("<ul class='menu'><li>Home</li><li id=a>About</li></ul>", Default::default()).unwrap();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    /// assert!(matches!(ul.to_html(dom.parser()), Cow::Borrowed("<ul class='menu'><li>Home</li><li id=a>About</li></ul>")));
+    ///
+    /// let li = dom.get_element_by_id("a").unwrap();
+    /// li.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap().attributes_mut().remove("id");
+    ///
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(ul.to_html(dom.parser()), "<ul class='menu'><li>Home</li><li>About</li></ul>");
+    /// assert_eq!(ul.to_inner_html(dom.parser()), "<li>Home</li><li>About</li>");
+    /// ```
+    pub fn to_html<'p>(&'p self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        crate::roundtrip::element_html(self, parser, false)
+    }
+
+    /// Returns the markup of the children of this element, borrowing it from the source string if neither the element
+    /// nor any of its descendants were modified
+    ///
+    /// This is the counterpart of [`HTMLTag::inner_html`] that keeps the source of unmodified nodes, see [`HTMLTag::to_html`].
+    pub fn to_inner_html<'p>(&'p self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        crate::roundtrip::element_html(self, parser, true)
+    }
+
+    /// Checks whether this element or any of its descendants were modified since parsing,
+    /// i.e. whether their start tags were changed, text was replaced or children were added, removed or moved
+    ///
+    /// Elements that are not part of the given parser count as modified.
+    pub fn is_modified(&self, parser: &Parser<'a>) -> bool {
+        !parser
+            .handle_of(self)
+            .is_some_and(|handle| crate::roundtrip::is_subtree_pristine(handle, parser))
+    }
+
+    /// Copies this element and all of its descendants into an owned fragment, which does not borrow from the document
+    ///
+    /// The fragment can be inserted into any document with [`VDom::insert_fragment`](crate::VDom::insert_fragment),
+    /// which is useful for stamping out templates. The current children are copied, including changes made after parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<template><li>Item</li></template>", Default::default()).unwrap();
+    /// let li = dom.nodes()[1].as_tag().unwrap().clone_subtree(dom.parser());
+    ///
+    /// let mut list = tl::parse("<ul></ul>", Default::default()).unwrap();
+    /// let ul = list.children()[0];
+    /// list.insert_fragment(Some(ul), 0, &li).unwrap();
+    /// list.insert_fragment(Some(ul), 1, &li).unwrap();
+    ///
+    /// assert_eq!(list.outer_html(), "<ul><li>Item</li><li>Item</li></ul>");
+    /// ```
+    pub fn clone_subtree(&self, parser: &Parser<'a>) -> OwnedFragment {
+        crate::fragment::clone_subtree(self, parser)
+    }
+
     /// Returns the raw HTML of this tag.
     /// This is a cheaper version of `HTMLTag::inner_html` if you never mutate any nodes.
     ///
     /// **Note:** Mutating this tag does *not* re-compute the HTML representation of this tag.
-    /// This simply returns a reference to the substring.
+    /// This simply returns a reference to the substring. Use [`HTMLTag::to_html`] to get up to date markup
+    /// that is still borrowed from the source if nothing was modified.
     pub fn raw(&self) -> &Bytes<'a> {
         &self._raw
     }
@@ -391,6 +1335,15 @@ impl<'a> HTMLTag<'a> {
         (offset, end)
     }
 
+    /// Returns the positions of the start tag, end tag and the full element in the source string.
+    ///
+    /// **Note:** These are the positions at the time of parsing.
+    /// Mutating this tag does *not* update the span.
+    #[inline]
+    pub fn span(&self) -> TagSpan {
+        self._span
+    }
+
     /// Returns the contained text of this element, excluding any markup.
     /// Equivalent to [Element#innerText](https://developer.mozilla.org/en-US/docs/Web/API/Element/innerText) in browsers)
     /// This function may not allocate memory for a new string as it can just return the part of the tag that doesn't have markup.
@@ -409,7 +1362,10 @@ impl<'a> HTMLTag<'a> {
             match &first {
                 Node::Tag(t) => return t.inner_text(parser),
                 Node::Raw(e) => return e.as_utf8_str(),
-                Node::Comment(_) => return Cow::Borrowed(""),
+                Node::CData(c) => return cdata_text(c),
+                Node::Comment(_) | Node::ProcessingInstruction(_) | Node::Doctype(_) => {
+                    return Cow::Borrowed("")
+                }
             }
         }
 
@@ -423,13 +1379,58 @@ impl<'a> HTMLTag<'a> {
             match &node {
                 Node::Tag(t) => s.push_str(&t.inner_text(parser)),
                 Node::Raw(e) => s.push_str(&e.as_utf8_str()),
-                Node::Comment(_) => { /* no op */ }
+                Node::CData(c) => s.push_str(&cdata_text(c)),
+                Node::Comment(_) | Node::ProcessingInstruction(_) | Node::Doctype(_) => {
+                    /* no op */
+                }
             }
         }
 
         Cow::Owned(s)
     }
 
+    /// Returns the contained text of this element according to the given options.
+    ///
+    /// Unlike [`HTMLTag::inner_text`], this can produce human-readable text by collapsing whitespace,
+    /// inserting line breaks for block-level elements and skipping the content of `<script>` and `<style>` tags.
+    /// See [`TextOptions`] for an example.
+    pub fn inner_text_with(&self, parser: &Parser<'a>, options: TextOptions) -> String {
+        crate::text::inner_text_with(self._children.as_slice(), parser, options)
+    }
+
+    /// Resolves a `<table>` element into a grid of cells, taking `<thead>`, `<tbody>`, `<tfoot>`,
+    /// `rowspan` and `colspan` into account. Returns `None` if this is not a `<table>`.
+    ///
+    /// Nested tables are not part of the grid, but their text is part of the text of the cell that contains them.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <table>
+    ///         <thead><tr><th>Name</th><th>Team</th><th>Score</th></tr></thead>
+    ///         <tbody>
+    ///             <tr><td>Ann</td><td rowspan="2">Red</td><td>3</td></tr>
+    ///             <tr><td>Bob</td><td>5</td></tr>
+    ///         </tbody>
+    ///     </table>
+    /// "#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let handle = dom.query_selector("table").unwrap().next().unwrap();
+    /// let table = handle.get(parser).unwrap().as_tag().unwrap().as_table(parser).unwrap();
+    ///
+    /// assert_eq!(table.headers(), ["Name", "Team", "Score"]);
+    /// assert_eq!(table.rows[2][1].text, "Red");
+    ///
+    /// let records = table.records();
+    /// assert_eq!(records[1]["Name"], "Bob");
+    /// assert_eq!(records[1]["Team"], "Red");
+    /// assert_eq!(records[1]["Score"], "5");
+    /// ```
+    pub fn as_table(&self, parser: &Parser<'a>) -> Option<Table> {
+        crate::table::from_tag(self, parser)
+    }
+
     /// Tries to parse the query selector and returns an iterator over elements that match the given query selector.
     ///
     /// # Example
@@ -477,6 +1478,43 @@ impl<'a> HTMLTag<'a> {
         Some(iter)
     }
 
+    /// Returns the first subnode of this tag in document order that matches the given query selector, see [`VDom::query_selector_first`](crate::VDom::query_selector_first)
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul><li>a</li><li class="x">b</li><li class="x">c</li></ul>"#, Default::default()).unwrap();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// let li = ul.query_selector_first(dom.parser(), ".x").unwrap();
+    /// assert_eq!(li.get(dom.parser()).unwrap().inner_text(dom.parser()), "b");
+    /// ```
+    pub fn query_selector_first(&self, parser: &Parser<'a>, selector: &str) -> Option<NodeHandle> {
+        self.query_selector(parser, selector)?.next()
+    }
+
+    /// Returns an iterator over the subnodes of this tag that match a custom [`Matcher`], see [`VDom::query_selector_with`](crate::VDom::query_selector_with)
+    ///
+    /// # Example
+    /// ```
+    /// use tl::{Node, Parser};
+    ///
+    /// let dom = tl::parse(r#"<ul><li>a</li><li data-x>b</li></ul><li data-x>c</li>"#, Default::default()).unwrap();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// let has_data = |node: &Node<'_>, _: &Parser<'_>| {
+    ///     node.as_tag().is_some_and(|tag| tag.attributes().iter().any(|(name, _)| name.starts_with("data-")))
+    /// };
+    ///
+    /// assert_eq!(ul.query_selector_with(dom.parser(), has_data).texts().collect::<Vec<_>>(), ["b"]);
+    /// ```
+    pub fn query_selector_with<'b, M: Matcher>(
+        &'b self,
+        parser: &'b Parser<'a>,
+        matcher: M,
+    ) -> QuerySelectorIterator<'a, 'b, Self, M> {
+        QuerySelectorIterator::with_matcher(matcher, parser, self)
+    }
+
     /// Calls the given closure with each tag as parameter
     ///
     /// The closure must return a boolean, indicating whether it should stop iterating
@@ -496,19 +1534,79 @@ impl<'a> HTMLTag<'a> {
     }
 }
 
+/// The name, value and byte span of an attribute, as returned by [`HTMLTag::source_attributes`]
+pub type SourceAttribute<'b> = (Bytes<'b>, Option<Bytes<'b>>, Span);
+
+/// An iterator over the attributes of an HTML tag in source order, returned by [`HTMLTag::source_attributes`]
+#[derive(Debug, Clone)]
+pub struct SourceAttributes<'b>(std::vec::IntoIter<SourceAttribute<'b>>);
+
+impl<'b> Iterator for SourceAttributes<'b> {
+    type Item = SourceAttribute<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for SourceAttributes<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for SourceAttributes<'_> {}
+
+/// Converts the name of a `data-*` attribute without its prefix to camel case, like the `dataset` property in browsers
+fn dataset_key(name: &[u8]) -> String {
+    let mut key = String::with_capacity(name.len());
+    let mut upper = false;
+
+    for c in String::from_utf8_lossy(name).chars() {
+        if c == '-' && !upper {
+            upper = true;
+            continue;
+        }
+
+        if upper && c.is_ascii_lowercase() {
+            key.push(c.to_ascii_uppercase());
+        } else {
+            if upper {
+                key.push('-');
+            }
+            key.push(c);
+        }
+
+        upper = false;
+    }
+
+    if upper {
+        key.push('-');
+    }
+
+    key
+}
+
 /// A thin wrapper around the parent of [`HTMLTag`]
 #[derive(Debug, Clone)]
 pub struct Parent<'a, 'b>(&'b HTMLTag<'a>);
 
 impl<'a, 'b> Parent<'a, 'b> {
     /// Returns parent node if exists
-    pub fn get(&self, parser: &'a Parser) -> Option<&Node> {
-        self.0._parent.map_or(None, |parent| parent.get(parser))
+    pub fn get(&self, parser: &'a Parser) -> Option<&'a Node<'a>> {
+        self.0._parent.and_then(|parent| parent.get(parser))
     }
 
     /// Utility method that checks node is parent for other.
     pub fn is_parent_for(&self, node: NodeHandle) -> bool {
-        self.0._children.iter().any(|handle| *handle == node)
+        self.0
+            ._children
+            .iter()
+            .any(|handle| handle.get_inner() == node.get_inner())
     }
 }
 
@@ -632,6 +1730,17 @@ fn find_last_node_handle<'a>(tag: &HTMLTag<'a>, parser: &Parser<'a>) -> Option<N
     }
 }
 
+/// Returns the text of a CDATA section, without the `<![CDATA[` and `]]>` markers
+fn cdata_text<'s>(section: &'s Bytes<'_>) -> Cow<'s, str> {
+    String::from_utf8_lossy(cdata_content(section.as_bytes()))
+}
+
+/// Returns the content of a CDATA section without the `<![CDATA[` and `]]>` markers
+pub(crate) fn cdata_content(section: &[u8]) -> &[u8] {
+    let bytes = section.strip_prefix(b"<![CDATA[").unwrap_or(section);
+    bytes.strip_suffix(b"]]>").unwrap_or(bytes)
+}
+
 /// An HTML Node
 #[derive(Debug, Clone)]
 pub enum Node<'a> {
@@ -641,23 +1750,60 @@ pub enum Node<'a> {
     Raw(Bytes<'a>),
     /// Comment (<!-- -->)
     Comment(Bytes<'a>),
+    /// Processing instruction (`<?xml version="1.0"?>` or `<?php ... ?>`), which ends at the first `?>`
+    ProcessingInstruction(Bytes<'a>),
+    /// CDATA section (`<![CDATA[...]]>`)
+    ///
+    /// Like comments, this contains the whole section. Its text is returned by [`Node::inner_text`].
+    CData(Bytes<'a>),
+    /// Document type declaration (`<!DOCTYPE html>`)
+    Doctype(Doctype<'a>),
 }
 
 impl<'a> Node<'a> {
     /// Returns the inner text of this node
     pub fn inner_text<'s, 'p: 's>(&'s self, parser: &'p Parser<'a>) -> Cow<'s, str> {
         match self {
-            Node::Comment(_) => Cow::Borrowed(""),
+            Node::Comment(_) | Node::ProcessingInstruction(_) | Node::Doctype(_) => {
+                Cow::Borrowed("")
+            }
             Node::Raw(r) => r.as_utf8_str(),
+            Node::CData(c) => cdata_text(c),
             Node::Tag(t) => t.inner_text(parser),
         }
     }
 
+    /// Returns the position of this node among all nodes with the same parent, including text and comments, starting at 0
+    ///
+    /// Nodes at the top level of the document are siblings of each other. The parser stores the position of every node,
+    /// so this is a constant time lookup unless the document was borrowed mutably since parsing.
+    /// Returns `None` if this node is not part of the given parser or was removed from its parent.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Hello <b>world</b>!</p>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let b = dom.query_selector("b").unwrap().next().unwrap().get(parser).unwrap();
+    /// assert_eq!(b.sibling_index(parser), Some(1));
+    /// assert_eq!(dom.nodes()[0].sibling_index(parser), Some(0));
+    /// ```
+    pub fn sibling_index(&self, parser: &Parser<'a>) -> Option<usize> {
+        let handle = parser.handle_of(self).filter(|handle| {
+            handle
+                .get(parser)
+                .is_some_and(|node| std::ptr::eq(node, self))
+        })?;
+
+        Some(parser.position_of(handle)?.index as usize)
+    }
+
     /// Returns the outer HTML of this node
     pub fn outer_html<'s>(&'s self, parser: &Parser<'a>) -> Cow<'s, str> {
         match self {
-            Node::Comment(c) => c.as_utf8_str(),
+            Node::Comment(c) | Node::ProcessingInstruction(c) | Node::CData(c) => c.as_utf8_str(),
             Node::Raw(r) => r.as_utf8_str(),
+            Node::Doctype(d) => d.raw().as_utf8_str(),
             Node::Tag(t) => Cow::Owned(t.outer_html(parser)),
         }
     }
@@ -665,12 +1811,85 @@ impl<'a> Node<'a> {
     /// Returns the inner HTML of this node
     pub fn inner_html<'s>(&'s self, parser: &Parser<'a>) -> Cow<'s, str> {
         match self {
-            Node::Comment(c) => c.as_utf8_str(),
+            Node::Comment(c) | Node::ProcessingInstruction(c) | Node::CData(c) => c.as_utf8_str(),
             Node::Raw(r) => r.as_utf8_str(),
+            Node::Doctype(d) => d.raw().as_utf8_str(),
             Node::Tag(t) => Cow::Owned(t.inner_html(parser)),
         }
     }
 
+    /// Returns the position of this node in the source string
+    ///
+    /// For tags, this is the span of the full element (see [`TagSpan::element`]).
+    /// For text and comments, this is the span of the text or comment itself.
+    ///
+    /// This returns `None` if this node was not created by the parser, i.e. if its text was replaced with owned data or a string that does not point into the source.
+    ///
+    /// # Example
+    /// ```
+    /// let source = "<p>Hello <!-- comment --></p>";
+    /// let dom = tl::parse(source, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let spans: Vec<_> = dom
+    ///     .nodes()
+    ///     .iter()
+    ///     .map(|node| &source[node.byte_span(parser).unwrap().range()])
+    ///     .collect();
+    ///
+    /// assert_eq!(spans, ["<p>Hello <!-- comment --></p>", "Hello ", "<!-- comment -->"]);
+    /// ```
+    pub fn byte_span(&self, parser: &Parser<'a>) -> Option<Span> {
+        match self {
+            Node::Tag(t) => Some(t.span().element()),
+            Node::Raw(b)
+            | Node::Comment(b)
+            | Node::ProcessingInstruction(b)
+            | Node::CData(b)
+            | Node::Doctype(Doctype { _raw: b, .. }) => {
+                let bytes = b.as_bytes_borrowed()?;
+                let input = parser.stream.data();
+                let start = (bytes.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+                let end = start + bytes.len();
+
+                (end <= input.len()).then(|| Span::new(start, end))
+            }
+        }
+    }
+
+    /// Checks whether this node and another node have the same structure and content, possibly in different documents
+    ///
+    /// Tag and attribute names are compared case insensitively and the order of attributes does not matter.
+    /// Text that only consists of whitespace is ignored and whitespace in other text is collapsed into a single space,
+    /// except within `<pre>`, `<code>` and `<textarea>` elements. Comments and other nodes are compared as written.
+    ///
+    /// # Example
+    /// ```
+    /// let a = tl::parse(r#"<div class="card" id="a"><p>Hello  world</p></div>"#, Default::default()).unwrap();
+    /// let b = tl::parse("<DIV id=\"a\" class=\"card\">\n  <p>\n    Hello world\n  </p>\n</DIV>", Default::default()).unwrap();
+    /// let c = tl::parse(r#"<div class="card" id="a"><p>Hello</p></div>"#, Default::default()).unwrap();
+    ///
+    /// assert!(a.nodes()[0].structural_eq(&b.nodes()[0], a.parser(), b.parser()));
+    /// assert!(!a.nodes()[0].structural_eq(&c.nodes()[0], a.parser(), c.parser()));
+    /// assert_eq!(a.nodes()[0].structural_hash(a.parser()), b.nodes()[0].structural_hash(b.parser()));
+    /// ```
+    pub fn structural_eq(
+        &self,
+        other: &Node<'_>,
+        parser: &Parser<'a>,
+        other_parser: &Parser<'_>,
+    ) -> bool {
+        crate::fingerprint::structural_tokens(self, parser)
+            == crate::fingerprint::structural_tokens(other, other_parser)
+    }
+
+    /// Computes a hash of the structure and content of this node, which is equal for nodes that are equal
+    /// according to [`Node::structural_eq`]
+    ///
+    /// Like [`StructuralHasher`](crate::fingerprint::StructuralHasher), the hash is stable across platforms and releases of this crate.
+    pub fn structural_hash(&self, parser: &Parser<'a>) -> u64 {
+        crate::fingerprint::structural_hash(self, parser)
+    }
+
     /// Returns an iterator over subnodes ("children") of this HTML tag, if this is a tag
     pub fn children(&self) -> Option<Children<'a, '_>> {
         match self {
@@ -679,6 +1898,18 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// Returns an iterator over the direct children of this node that are elements, if this is a tag
+    ///
+    /// See [`HTMLTag::child_elements`].
+    pub fn child_elements<'p>(
+        &'p self,
+        parser: &'p Parser<'a>,
+    ) -> impl Iterator<Item = NodeHandle> + 'p {
+        self.as_tag()
+            .into_iter()
+            .flat_map(move |tag| tag.child_elements(parser))
+    }
+
     /// Calls the given closure with each tag as parameter
     ///
     /// The closure must return a boolean, indicating whether it should stop iterating
@@ -736,6 +1967,32 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// Tries to coerce this node into a processing instruction, returning the whole instruction like `<?xml version="1.0"?>`
+    pub fn as_processing_instruction(&self) -> Option<&Bytes<'a>> {
+        match self {
+            Self::ProcessingInstruction(p) => Some(p),
+            _ => None,
+        }
+    }
+
+    /// Tries to coerce this node into a CDATA section, returning the whole section like `<![CDATA[text]]>`
+    ///
+    /// Use [`Node::inner_text`] to get the text of the section.
+    pub fn as_cdata(&self) -> Option<&Bytes<'a>> {
+        match self {
+            Self::CData(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Tries to coerce this node into a document type declaration
+    pub fn as_doctype(&self) -> Option<&Doctype<'a>> {
+        match self {
+            Self::Doctype(d) => Some(d),
+            _ => None,
+        }
+    }
+
     /// Tries to coerce this node into a raw text node, returning the text
     ///
     /// "Raw text nodes" are nodes that are not HTML tags, but just text
@@ -756,3 +2013,44 @@ impl<'a> Node<'a> {
         }
     }
 }
+
+/// Checks whether the given name is a valid custom element name, ignoring ASCII case because HTML names are case insensitive
+pub(crate) fn is_custom_element_name(name: &[u8]) -> bool {
+    name.first().is_some_and(u8::is_ascii_alphabetic)
+        && name.contains(&b'-')
+        && name
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_') || !b.is_ascii())
+        && !constants::RESERVED_CUSTOM_ELEMENT_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+/// Checks whether the given ID is used by exactly one element of the document
+fn is_unique_id(id: &Bytes<'_>, parser: &Parser<'_>) -> bool {
+    if parser.options.is_tracking_ids() {
+        return parser.ids.get(id).is_some_and(|handles| handles.len() == 1);
+    }
+
+    parser
+        .tags
+        .iter()
+        .filter_map(Node::as_tag)
+        .filter(|tag| tag._attributes.id.as_ref() == Some(id))
+        .nth(1)
+        .is_none()
+}
+
+/// Checks whether the given name can be written in a CSS selector as it is, without escaping
+fn is_css_identifier(name: &[u8]) -> bool {
+    let starts_like_number = match name {
+        [b'-', second, ..] => second.is_ascii_digit() || *second == b'-',
+        [first, ..] => first.is_ascii_digit(),
+        [] => true,
+    };
+
+    !starts_like_number
+        && name
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::{NodeHandle, VDom};
+
+/// A cache of query selector results, for documents that are queried with the same selectors again and again
+///
+/// The cache stores the matches of every selector string it is queried with. It notices by itself when the document
+/// may have been mutated, i.e. when its nodes were borrowed mutably, and then clears all results before running the query.
+/// The same holds for queries on another document, so a cache can be reused for many documents, one after another.
+///
+/// # Example
+/// ```
+/// use tl::query_cache::QueryCache;
+///
+/// let mut dom = tl::parse(r#"<p class="a">1</p><p class="a">2</p>"#, Default::default()).unwrap();
+/// let mut cache = QueryCache::new();
+///
+/// assert_eq!(cache.query_selector(&dom, "p.a").unwrap().len(), 2);
+/// // the second query is answered from the cache
+/// assert_eq!(cache.query_selector(&dom, "p.a").unwrap().len(), 2);
+/// assert_eq!(cache.len(), 1);
+///
+/// let handle = dom.query_selector("p").unwrap().next().unwrap();
+/// let tag = handle.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+/// tag.attributes_mut().insert("class", Some("b"));
+///
+/// // the mutation invalidated the cache
+/// assert_eq!(cache.query_selector(&dom, "p.a").unwrap().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryCache {
+    /// The generation of the document that the results were computed from
+    generation: Option<u64>,
+    /// The matches of every selector, or `None` for selectors that are invalid
+    entries: HashMap<String, Option<Box<[NodeHandle]>>>,
+}
+
+impl QueryCache {
+    /// Creates a new, empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of selectors whose results are cached
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether no results are cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all cached results
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.generation = None;
+    }
+
+    /// Returns the elements that match the given query selector in document order, using and filling the cache
+    ///
+    /// This is equivalent to collecting [`VDom::query_selector`]. Returns `None` if the selector is invalid.
+    pub fn query_selector(&mut self, dom: &VDom<'_>, selector: &str) -> Option<&[NodeHandle]> {
+        let generation = dom.parser().generation();
+        if self.generation != Some(generation) {
+            self.entries.clear();
+            self.generation = Some(generation);
+        }
+
+        if !self.entries.contains_key(selector) {
+            let matches = dom.query_selector(selector).map(|iter| iter.collect());
+            self.entries.insert(selector.to_owned(), matches);
+        }
+
+        self.entries[selector].as_deref()
+    }
+}
@@ -0,0 +1,300 @@
+use std::{ops::Range, sync::OnceLock};
+
+use crate::{Node, Parser, TagName};
+
+use super::{iter::candidates, selector::has_type, Selector};
+
+/// A single step of a [`CompiledSelector`]
+#[derive(Debug, Clone)]
+enum Op<'a> {
+    /// Checks the tag name of the current node, comparing interned symbols where possible
+    Tag(&'a [u8], Option<TagName>),
+    /// Checks a simple selector against the current node
+    Check(Selector<'a>),
+    /// Moves to the parent of the current node
+    Parent,
+    /// Moves to the parent of the current node and remembers it, so that the following steps are retried
+    /// on every further ancestor if they fail
+    Ancestor,
+    /// Checks that the current node is a descendant of an element matching the selector, by looking up its parent
+    /// in the handle ranges of the matching elements
+    ///
+    /// This is used instead of walking the ancestors if the elements that can match the selector are known from the
+    /// lookup tables. The ranges are only computed when they are first needed.
+    Within(Selector<'a>, OnceLock<Vec<Range<u32>>>),
+    /// Matches the current node against a selector that cannot be compiled, with the recursive matcher
+    Fallback(Selector<'a>),
+    /// Ends an alternative successfully
+    Accept,
+}
+
+/// A query selector compiled into a flat sequence of steps
+///
+/// Like in browser engines, a compiled selector is matched from right to left: the rightmost compound selector is
+/// checked against the node first, and the combinators move to its parent or ancestors from there.
+/// This avoids the recursion of [`Selector::matches`] and only backtracks to the nearest descendant combinator if a
+/// step fails, which is much faster for deep documents and long selectors.
+/// If the parser tracked the elements on the left of a descendant combinator (see [`ParserOptions`](crate::ParserOptions)),
+/// their ancestors are not walked at all: the node only needs to lie within the handle range of one of them,
+/// see [`HTMLTag::contains`](crate::HTMLTag::contains).
+///
+/// Query selector iterators use a compiled selector, so this is only needed to match nodes manually.
+/// Selectors that do not have the structure of a parsed query selector are matched with [`Selector::matches`].
+///
+/// # Example
+/// ```
+/// use tl::queryselector::CompiledSelector;
+///
+/// let dom = tl::parse(r#"<ul class="menu"><li><a href="/">Home</a></li></ul><a>Other</a>"#, Default::default()).unwrap();
+/// let parser = dom.parser();
+///
+/// let selector = tl::parse_query_selector(".menu li > a").unwrap();
+/// let compiled = CompiledSelector::new(&selector, parser);
+///
+/// let matches = dom
+///     .nodes()
+///     .iter()
+///     .filter(|node| compiled.matches(node, parser))
+///     .count();
+///
+/// assert_eq!(matches, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompiledSelector<'a> {
+    code: Vec<Op<'a>>,
+}
+
+impl<'a> CompiledSelector<'a> {
+    /// Compiles a selector for matching nodes of the document of the given parser
+    ///
+    /// The compiled selector depends on the options and lookup tables of this document, so it must only be used with nodes of the same document.
+    pub fn new(selector: &Selector<'a>, parser: &Parser<'_>) -> Self {
+        let mut alternatives = Vec::new();
+        flatten_or(selector, &mut alternatives);
+
+        let mut code = Vec::new();
+
+        for alternative in alternatives {
+            match Complex::from_selector(alternative) {
+                Some(complex) => complex.compile(parser, &mut code),
+                None => code.push(Op::Fallback(alternative.clone())),
+            }
+
+            code.push(Op::Accept);
+        }
+
+        Self { code }
+    }
+
+    /// Checks if the given node matches this selector
+    pub fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool {
+        let mut start = 0;
+
+        while start < self.code.len() {
+            match self.run(start, node, parser) {
+                Ok(()) => return true,
+                Err(next) => start = next,
+            }
+        }
+
+        false
+    }
+
+    /// Runs the alternative starting at `pc`, and returns the start of the next alternative if it does not match
+    fn run<'b>(&self, mut pc: usize, node: &Node<'b>, parser: &Parser<'b>) -> Result<(), usize> {
+        let next_alternative = |pc: usize| {
+            pc + self.code[pc..]
+                .iter()
+                .position(|op| matches!(op, Op::Accept))
+                .unwrap_or(self.code.len() - pc)
+                + 1
+        };
+
+        let mut current = node;
+        // the position after the last descendant combinator and the ancestor it is currently tried on
+        let mut backtrack: Option<(usize, &Node<'b>)> = None;
+
+        loop {
+            let ok = match &self.code[pc] {
+                Op::Tag(name, symbol) => current.as_tag().is_some_and(|tag| match tag.tag_name() {
+                    Some(tag_name) => Some(tag_name) == *symbol,
+                    // renamed tags have no symbol
+                    None => has_type(tag, name, parser),
+                }),
+                Op::Check(selector) | Op::Fallback(selector) => selector.matches(current, parser),
+                Op::Parent => match parent(current, parser) {
+                    Some(node) => {
+                        current = node;
+                        true
+                    }
+                    None => false,
+                },
+                Op::Within(selector, ranges) => {
+                    let ranges = ranges.get_or_init(|| subtree_ranges(selector, parser));
+
+                    current
+                        .as_tag()
+                        .and_then(|tag| tag._parent)
+                        .is_some_and(|parent| {
+                            let parent = parent.get_inner();
+                            let index = ranges.partition_point(|range| range.start <= parent);
+                            index > 0 && ranges[index - 1].contains(&parent)
+                        })
+                }
+                Op::Ancestor => match parent(current, parser) {
+                    Some(node) => {
+                        current = node;
+                        backtrack = Some((pc + 1, node));
+                        true
+                    }
+                    // there are no ancestors left to retry on
+                    None => return Err(next_alternative(pc)),
+                },
+                Op::Accept => return Ok(()),
+            };
+
+            if ok {
+                pc += 1;
+                continue;
+            }
+
+            // retry the steps after the last descendant combinator on the next ancestor
+            match backtrack.and_then(|(to, ancestor)| Some((to, parent(ancestor, parser)?))) {
+                Some((to, ancestor)) => {
+                    pc = to;
+                    current = ancestor;
+                    backtrack = Some((to, ancestor));
+                }
+                None => return Err(next_alternative(pc)),
+            }
+        }
+    }
+}
+
+/// Returns the parent of a node, or `None` if it is not an element or one of the topmost nodes
+fn parent<'p, 'b>(node: &Node<'b>, parser: &'p Parser<'b>) -> Option<&'p Node<'b>> {
+    node.as_tag()?._parent?.get(parser)
+}
+
+/// Returns the handle ranges of the elements matching a selector together with their descendants
+///
+/// The ranges of elements are either nested or disjoint, so only the outermost ones are kept, which leaves sorted and
+/// disjoint ranges that can be binary searched.
+fn subtree_ranges(selector: &Selector<'_>, parser: &Parser<'_>) -> Vec<Range<u32>> {
+    let mut ranges: Vec<Range<u32>> = Vec::new();
+
+    for &handle in candidates(selector, parser).unwrap_or_default() {
+        let index = handle.get_inner();
+        if ranges.last().is_some_and(|range| range.contains(&index)) {
+            continue;
+        }
+
+        if let Some(node @ Node::Tag(tag)) = handle.get(parser) {
+            if selector.matches(node, parser) {
+                ranges.push(index..tag._descendants.end);
+            }
+        }
+    }
+
+    ranges
+}
+
+/// Collects the alternatives of a selector list
+fn flatten_or<'s, 'a>(selector: &'s Selector<'a>, out: &mut Vec<&'s Selector<'a>>) {
+    match selector {
+        Selector::Or(a, b) => {
+            flatten_or(a, out);
+            flatten_or(b, out);
+        }
+        _ => out.push(selector),
+    }
+}
+
+/// A complex selector, split into its rightmost compound selector and the combinators and compound selectors to its left
+struct Complex<'s, 'a> {
+    /// The simple selectors that the matched node itself must match
+    subject: Vec<&'s Selector<'a>>,
+    /// The combinator to the left of the subject and the complex selector that it applies to, both as written and split up
+    left: Option<(Combinator, &'s Selector<'a>, Box<Complex<'s, 'a>>)>,
+}
+
+#[derive(Clone, Copy)]
+enum Combinator {
+    Parent,
+    Descendant,
+}
+
+impl<'s, 'a> Complex<'s, 'a> {
+    /// Splits a selector into a chain of compound selectors, or returns `None` if it is not a linear chain
+    fn from_selector(selector: &'s Selector<'a>) -> Option<Self> {
+        match selector {
+            Selector::Descendant(a, b) | Selector::Parent(a, b) => {
+                let combinator = match selector {
+                    Selector::Parent(..) => Combinator::Parent,
+                    _ => Combinator::Descendant,
+                };
+
+                let subject = Self::from_selector(b)?;
+                if subject.left.is_some() {
+                    return None;
+                }
+
+                Some(Self {
+                    subject: subject.subject,
+                    left: Some((combinator, a, Box::new(Self::from_selector(a)?))),
+                })
+            }
+            // both sides apply to the same node, so their subjects can be merged as long as only one of them has combinators
+            Selector::And(a, b) => {
+                let (mut a, mut b) = (Self::from_selector(a)?, Self::from_selector(b)?);
+
+                if a.left.is_some() && b.left.is_some() {
+                    return None;
+                }
+
+                a.subject.append(&mut b.subject);
+                Some(Self {
+                    subject: a.subject,
+                    left: a.left.or(b.left),
+                })
+            }
+            // selector lists at the top level are split into alternatives, so this is the list of `:is()` or `:where()`,
+            // which is checked like a simple selector
+            _ => Some(Self {
+                subject: vec![selector],
+                left: None,
+            }),
+        }
+    }
+
+    /// Writes the steps of this selector to `code`, from right to left
+    fn compile(self, parser: &Parser<'_>, code: &mut Vec<Op<'a>>) {
+        for selector in self.subject {
+            match selector {
+                // escaped and uppercase names are rare, so they are only resolved by the simple selector
+                Selector::Tag(name)
+                    if !name.contains(&b'\\')
+                        && (parser.options.is_xml_mode()
+                            || !name.iter().any(u8::is_ascii_uppercase)) =>
+                {
+                    code.push(Op::Tag(name, TagName::of(name)))
+                }
+                Selector::All => {}
+                _ => code.push(Op::Check(selector.clone())),
+            }
+        }
+
+        if let Some((combinator, selector, left)) = self.left {
+            match combinator {
+                Combinator::Parent => code.push(Op::Parent),
+                Combinator::Descendant if candidates(selector, parser).is_some() => {
+                    code.push(Op::Within(selector.clone(), OnceLock::new()));
+                    return;
+                }
+                Combinator::Descendant => code.push(Op::Ancestor),
+            }
+
+            left.compile(parser, code);
+        }
+    }
+}
@@ -1,20 +1,33 @@
-use std::marker::PhantomData;
+use std::{borrow::Cow, marker::PhantomData, ops::Range};
 
-use crate::{NodeHandle, Parser};
+use crate::{Bytes, HTMLTag, Node, NodeHandle, Parser};
 
-use super::{iterable::QueryIterable, Selector};
+use super::{iterable::QueryIterable, CompiledSelector, Matcher, Selector};
 
 /// A query selector iterator that yields matching HTML nodes
-pub struct QuerySelectorIterator<'a, 'b, Q: QueryIterable<'a>> {
-    selector: Selector<'b>,
+///
+/// If the rightmost part of the selector is an ID, class or tag selector and the parser tracked it
+/// (see [`ParserOptions`](crate::ParserOptions)), only the nodes in the corresponding lookup table are checked
+/// instead of every node of the collection.
+/// The contents of `<template>` elements in the collection are skipped, see [`HTMLTag::template_content`](crate::HTMLTag::template_content).
+///
+/// Matching nodes are yielded in document order, i.e. in the order of their handles, whether or not a lookup table is used.
+///
+/// Nodes are matched with a compiled query selector, or with any other [`Matcher`], see [`QuerySelectorIterator::with_matcher`].
+pub struct QuerySelectorIterator<'a, 'b, Q: QueryIterable<'a>, M = CompiledSelector<'b>> {
+    selector: M,
     collection: &'b Q,
     parser: &'b Parser<'a>,
     index: usize,
     len: usize,
+    /// The only nodes that can match the selector, in document order, if they are known from a lookup table
+    candidates: Option<&'b [NodeHandle]>,
+    /// The handle ranges of the contents of `<template>` elements in the collection, which are not matched
+    templates: Vec<Range<u32>>,
     _a: PhantomData<&'a ()>,
 }
 
-impl<'a, 'b, Q: QueryIterable<'a>> Clone for QuerySelectorIterator<'a, 'b, Q> {
+impl<'a, 'b, Q: QueryIterable<'a>, M: Clone> Clone for QuerySelectorIterator<'a, 'b, Q, M> {
     fn clone(&self) -> Self {
         Self {
             selector: self.selector.clone(),
@@ -22,6 +35,8 @@ impl<'a, 'b, Q: QueryIterable<'a>> Clone for QuerySelectorIterator<'a, 'b, Q> {
             parser: self.parser,
             index: self.index,
             len: self.len,
+            candidates: self.candidates,
+            templates: self.templates.clone(),
             _a: PhantomData,
         }
     }
@@ -30,28 +45,284 @@ impl<'a, 'b, Q: QueryIterable<'a>> Clone for QuerySelectorIterator<'a, 'b, Q> {
 impl<'a, 'b, Q: QueryIterable<'a>> QuerySelectorIterator<'a, 'b, Q> {
     /// Creates a new query selector iterator
     pub fn new(selector: Selector<'b>, parser: &'b Parser<'a>, collection: &'b Q) -> Self {
+        let len = collection.len(parser);
+        let start = collection.start().unwrap_or_default();
+        let end = start.saturating_add(len as u32);
+
+        let candidates = candidates(&selector, parser).map(|candidates| {
+            // narrow the candidates down to the nodes of the collection
+            let from = candidates.partition_point(|h| h.get_inner() < start);
+            let to = candidates.partition_point(|h| h.get_inner() < end);
+            &candidates[from..to]
+        });
+
+        Self {
+            selector: CompiledSelector::new(&selector, parser),
+            collection,
+            index: 0,
+            len,
+            parser,
+            candidates,
+            templates: template_contents(parser, start..end),
+            _a: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'b, Q: QueryIterable<'a>, M: Matcher> QuerySelectorIterator<'a, 'b, Q, M> {
+    /// Creates an iterator over the nodes of a collection that match the given [`Matcher`]
+    ///
+    /// Every node of the collection is checked, because the lookup tables of the parser only apply to query selectors.
+    pub fn with_matcher(matcher: M, parser: &'b Parser<'a>, collection: &'b Q) -> Self {
+        let len = collection.len(parser);
+        let start = collection.start().unwrap_or_default();
+        let end = start.saturating_add(len as u32);
+
         Self {
-            selector,
+            selector: matcher,
             collection,
             index: 0,
-            len: collection.len(parser),
+            len,
             parser,
+            candidates: None,
+            templates: template_contents(parser, start..end),
             _a: PhantomData,
         }
     }
+
+    fn matches(&self, node: &Node<'a>) -> bool {
+        self.selector.matches(node, self.parser)
+    }
+
+    /// Returns the end of the template contents that the given node is part of, if any
+    fn template_end(&self, handle: NodeHandle) -> Option<u32> {
+        let index = handle.get_inner();
+        let position = self.templates.partition_point(|range| range.start <= index);
+
+        position
+            .checked_sub(1)
+            .map(|position| &self.templates[position])
+            .filter(|range| range.contains(&index))
+            .map(|range| range.end)
+    }
+}
+
+/// Returns the outermost template contents of the `<template>` elements within the given handle range, in document order
+///
+/// A template that contains the whole range is not included, so that its contents can be queried through the template.
+pub(super) fn template_contents(parser: &Parser<'_>, range: Range<u32>) -> Vec<Range<u32>> {
+    let from = parser
+        .templates
+        .partition_point(|h| h.get_inner() < range.start);
+    let mut contents: Vec<Range<u32>> = Vec::new();
+
+    for handle in &parser.templates[from..] {
+        if handle.get_inner() >= range.end {
+            break;
+        }
+
+        if contents
+            .last()
+            .is_some_and(|last| last.contains(&handle.get_inner()))
+        {
+            continue;
+        }
+
+        if let Some(tag) = handle.get(parser).and_then(Node::as_tag) {
+            contents.push(tag._descendants.clone());
+        }
+    }
+
+    contents
 }
 
-impl<'a, 'b, Q: QueryIterable<'a>> Iterator for QuerySelectorIterator<'a, 'b, Q> {
+/// Returns the nodes that can match the given selector from the lookup tables of the parser, if its rightmost part was tracked
+///
+/// The returned nodes are in document order. Returns `None` if the nodes were mutated, because the tables may be outdated.
+pub(super) fn candidates<'b>(
+    selector: &Selector<'b>,
+    parser: &'b Parser<'_>,
+) -> Option<&'b [NodeHandle]> {
+    if parser.tables_outdated {
+        return None;
+    }
+
+    let options = parser.options;
+
+    match selector {
+        // the lookup tables cannot be searched for escaped names, which are rare, so their candidates are all nodes
+        Selector::Id(name) | Selector::Class(name) | Selector::Tag(name)
+            if name.contains(&b'\\') =>
+        {
+            None
+        }
+        Selector::Id(id) if options.is_tracking_ids() => Some(
+            parser
+                .ids
+                .get(&Bytes::from(*id))
+                .map_or(&[], |nodes| nodes.as_slice()),
+        ),
+        Selector::Class(class) if options.is_tracking_classes() => Some(
+            parser
+                .classes
+                .get(&Bytes::from(*class))
+                .map_or(&[], |nodes| nodes.as_slice()),
+        ),
+        // type selectors are case insensitive for HTML elements, whose names are stored in lowercase,
+        // but not for foreign elements, so uppercase names cannot be looked up either
+        Selector::Tag(name)
+            if !options.is_xml_mode() && name.iter().any(u8::is_ascii_uppercase) =>
+        {
+            None
+        }
+        Selector::Tag(name) if options.is_tracking_tags() => Some(
+            parser
+                .tag_names
+                .get(&Bytes::from(*name))
+                .map_or(&[], Vec::as_slice),
+        ),
+        // both sides must match, so the smaller set of candidates is enough
+        Selector::And(a, b) => match (candidates(a, parser), candidates(b, parser)) {
+            (Some(a), Some(b)) => Some(if a.len() <= b.len() { a } else { b }),
+            (a, b) => a.or(b),
+        },
+        // only the ancestors are checked against the left side
+        Selector::Descendant(_, b) | Selector::Parent(_, b) => candidates(b, parser),
+        _ => None,
+    }
+}
+
+impl<'a, 'b, Q: QueryIterable<'a>, M: Matcher> QuerySelectorIterator<'a, 'b, Q, M> {
+    /// Only yields the matching nodes whose text matches the given pattern
+    ///
+    /// The pattern is checked against the [inner text](crate::Node::inner_text) of every node,
+    /// which includes the text of all of its subnodes. See [`TextPattern`] for the supported patterns.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p>Price: <b>12</b></p><p>Stock: 3</p>"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let price = dom
+    ///     .query_selector("p")
+    ///     .unwrap()
+    ///     .filter_text("Price")
+    ///     .next()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(price.get(parser).unwrap().inner_text(parser), "Price: 12");
+    /// ```
+    pub fn filter_text<P: TextPattern>(self, pattern: P) -> TextFilterIterator<'a, 'b, Q, P, M> {
+        TextFilterIterator {
+            inner: self,
+            pattern,
+        }
+    }
+
+    /// Returns the first matching node
+    ///
+    /// This is the same as calling [`Iterator::next`] once, which reads better if only one node is expected.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p>a</p><p class="b">b</p>"#, Default::default()).unwrap();
+    /// let handle = dom.query_selector("p").unwrap().first().unwrap();
+    ///
+    /// assert_eq!(handle.get(dom.parser()).unwrap().inner_text(dom.parser()), "a");
+    /// assert!(dom.query_selector("div").unwrap().first().is_none());
+    /// ```
+    pub fn first(mut self) -> Option<NodeHandle> {
+        self.next()
+    }
+
+    /// Returns an iterator over the matching elements instead of their handles
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a href="/a">A</a><a id="b">B</a>"#, Default::default()).unwrap();
+    /// let names = dom
+    ///     .query_selector("a")
+    ///     .unwrap()
+    ///     .tags()
+    ///     .map(|tag| tag.inner_text(dom.parser()))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(names, ["A", "B"]);
+    /// ```
+    pub fn tags(self) -> Tags<'a, 'b, Self> {
+        Tags {
+            parser: self.parser,
+            inner: self,
+        }
+    }
+
+    /// Returns an iterator over the [inner text](crate::Node::inner_text) of the matching nodes
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<li>One</li><li><b>Two</b>!</li>"#, Default::default()).unwrap();
+    /// let texts = dom.query_selector("li").unwrap().texts().collect::<Vec<_>>();
+    ///
+    /// assert_eq!(texts, ["One", "Two!"]);
+    /// ```
+    pub fn texts(self) -> Texts<'a, 'b, Self> {
+        Texts {
+            parser: self.parser,
+            inner: self,
+        }
+    }
+
+    /// Returns an iterator over the values of the given attribute of the matching elements
+    ///
+    /// Elements without the attribute are skipped, and attributes without a value yield an empty string.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a href="/a">A</a><a>B</a><a href="/c">C</a>"#, Default::default()).unwrap();
+    /// let links = dom.query_selector("a").unwrap().attrs("href").collect::<Vec<_>>();
+    ///
+    /// assert_eq!(links, ["/a", "/c"]);
+    /// ```
+    pub fn attrs(self, name: &'b str) -> Attrs<'a, 'b, Self> {
+        Attrs {
+            inner: self.tags(),
+            name,
+        }
+    }
+}
+
+impl<'a, 'b, Q: QueryIterable<'a>, M: Matcher> Iterator for QuerySelectorIterator<'a, 'b, Q, M> {
     type Item = NodeHandle;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(candidates) = self.candidates {
+            while let Some(&handle) = candidates.get(self.index) {
+                self.index += 1;
+
+                if self.template_end(handle).is_some() {
+                    continue;
+                }
+
+                let node = handle.get(self.parser);
+                if node.is_some_and(|node| self.matches(node)) {
+                    return Some(handle);
+                }
+            }
+
+            return None;
+        }
+
         while self.index < self.len {
             let node = self.collection.get(self.parser, self.index);
             self.index += 1;
             if let Some((node, id)) = node {
-                let matches = self.selector.matches(node, self.parser);
+                if let Some(end) = self.template_end(id) {
+                    // skip the rest of the template contents at once
+                    self.index += (end - id.get_inner() - 1) as usize;
+                    continue;
+                }
 
-                if matches {
+                if self.matches(node) {
                     return Some(id);
                 }
             }
@@ -59,4 +330,160 @@ impl<'a, 'b, Q: QueryIterable<'a>> Iterator for QuerySelectorIterator<'a, 'b, Q>
 
         None
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.candidates {
+            Some(candidates) => candidates.len().saturating_sub(self.index),
+            None => self.len.saturating_sub(self.index),
+        };
+
+        (0, Some(remaining))
+    }
+}
+
+/// A pattern that the text of a node can be matched against with [`QuerySelectorIterator::filter_text`]
+///
+/// Strings and characters match text that contains them.
+/// With the `regex` feature enabled, `regex::Regex` matches text that contains a match of the expression.
+pub trait TextPattern {
+    /// Checks whether the given text matches this pattern
+    fn is_match(&self, text: &str) -> bool;
+}
+
+impl TextPattern for &str {
+    fn is_match(&self, text: &str) -> bool {
+        text.contains(*self)
+    }
+}
+
+impl TextPattern for String {
+    fn is_match(&self, text: &str) -> bool {
+        text.contains(self.as_str())
+    }
+}
+
+impl TextPattern for char {
+    fn is_match(&self, text: &str) -> bool {
+        text.contains(*self)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl TextPattern for regex::Regex {
+    fn is_match(&self, text: &str) -> bool {
+        regex::Regex::is_match(self, text)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl TextPattern for &regex::Regex {
+    fn is_match(&self, text: &str) -> bool {
+        regex::Regex::is_match(self, text)
+    }
+}
+
+/// A query selector iterator that only yields nodes whose text matches a [`TextPattern`]
+///
+/// This is created by [`QuerySelectorIterator::filter_text`].
+pub struct TextFilterIterator<'a, 'b, Q: QueryIterable<'a>, P, M = CompiledSelector<'b>> {
+    inner: QuerySelectorIterator<'a, 'b, Q, M>,
+    pattern: P,
+}
+
+impl<'a, 'b, Q: QueryIterable<'a>, P: TextPattern, M: Matcher> Iterator
+    for TextFilterIterator<'a, 'b, Q, P, M>
+{
+    type Item = NodeHandle;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parser = self.inner.parser;
+        let pattern = &self.pattern;
+
+        self.inner.find(|handle| {
+            handle
+                .get(parser)
+                .is_some_and(|node| pattern.is_match(&node.inner_text(parser)))
+        })
+    }
+}
+
+impl<'a, 'b, Q: QueryIterable<'a>, P: TextPattern, M: Matcher> TextFilterIterator<'a, 'b, Q, P, M> {
+    /// Returns the first matching node, see [`QuerySelectorIterator::first`]
+    pub fn first(mut self) -> Option<NodeHandle> {
+        self.next()
+    }
+
+    /// Returns an iterator over the matching elements, see [`QuerySelectorIterator::tags`]
+    pub fn tags(self) -> Tags<'a, 'b, Self> {
+        Tags {
+            parser: self.inner.parser,
+            inner: self,
+        }
+    }
+
+    /// Returns an iterator over the text of the matching nodes, see [`QuerySelectorIterator::texts`]
+    pub fn texts(self) -> Texts<'a, 'b, Self> {
+        Texts {
+            parser: self.inner.parser,
+            inner: self,
+        }
+    }
+
+    /// Returns an iterator over the values of an attribute of the matching elements, see [`QuerySelectorIterator::attrs`]
+    pub fn attrs(self, name: &'b str) -> Attrs<'a, 'b, Self> {
+        Attrs {
+            inner: self.tags(),
+            name,
+        }
+    }
+}
+
+/// An iterator over the elements of a query, created by [`QuerySelectorIterator::tags`]
+pub struct Tags<'a, 'b, I> {
+    inner: I,
+    parser: &'b Parser<'a>,
+}
+
+impl<'a, 'b, I: Iterator<Item = NodeHandle>> Iterator for Tags<'a, 'b, I> {
+    type Item = &'b HTMLTag<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parser = self.parser;
+        self.inner
+            .find_map(|handle| handle.get(parser).and_then(Node::as_tag))
+    }
+}
+
+/// An iterator over the text of the nodes of a query, created by [`QuerySelectorIterator::texts`]
+pub struct Texts<'a, 'b, I> {
+    inner: I,
+    parser: &'b Parser<'a>,
+}
+
+impl<'a, 'b, I: Iterator<Item = NodeHandle>> Iterator for Texts<'a, 'b, I> {
+    type Item = Cow<'b, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parser = self.parser;
+        self.inner
+            .find_map(|handle| Some(handle.get(parser)?.inner_text(parser)))
+    }
+}
+
+/// An iterator over the attribute values of the elements of a query, created by [`QuerySelectorIterator::attrs`]
+pub struct Attrs<'a, 'b, I> {
+    inner: Tags<'a, 'b, I>,
+    name: &'b str,
+}
+
+impl<'a, 'b, I: Iterator<Item = NodeHandle>> Iterator for Attrs<'a, 'b, I> {
+    type Item = Cow<'b, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let name = self.name;
+        self.inner.find_map(|tag| {
+            let value = tag.attributes().get(name)?;
+            Some(value.map_or(Cow::Borrowed(""), Bytes::as_utf8_str))
+        })
+    }
 }
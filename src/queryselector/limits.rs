@@ -0,0 +1,68 @@
+/// Limits for parsing untrusted query selectors, like selectors that users of a service enter
+///
+/// Parsing and matching a selector takes time and memory in proportion to its size, so a selector that is very long
+/// or very deeply nested can be used to exhaust the resources of a server, or overflow the stack.
+/// Selectors that exceed a limit are rejected with a [`SelectorError`](crate::errors::SelectorError)
+/// as soon as the limit is reached, before the rest of the selector is parsed.
+///
+/// The default limits allow all selectors that are written by hand or generated by browser devtools.
+///
+/// # Example
+/// ```
+/// use tl::errors::SelectorError;
+/// use tl::queryselector::SelectorLimits;
+///
+/// let limits = SelectorLimits::new().max_components(4);
+///
+/// assert!(tl::parse_query_selector_with_limits("ul > li.item a", limits).is_ok());
+/// assert_eq!(
+///     tl::parse_query_selector_with_limits("div p span a b", limits).unwrap_err(),
+///     SelectorError::TooManyComponents { max: 4 }
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SelectorLimits {
+    pub(crate) max_length: usize,
+    pub(crate) max_nesting: usize,
+    pub(crate) max_components: usize,
+}
+
+impl Default for SelectorLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelectorLimits {
+    /// Creates the default limits: a length of 1024 bytes, a nesting of 8 selector lists and 128 components
+    pub fn new() -> Self {
+        Self {
+            max_length: 1024,
+            max_nesting: 8,
+            max_components: 128,
+        }
+    }
+
+    /// Sets the maximum length of a selector in bytes
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = length;
+        self
+    }
+
+    /// Sets how deeply the selector lists of `:is()` and `:where()` may be nested
+    ///
+    /// A nesting of 0 does not allow these pseudo-classes at all.
+    pub fn max_nesting(mut self, nesting: usize) -> Self {
+        self.max_nesting = nesting;
+        self
+    }
+
+    /// Sets the maximum number of simple selectors, like `div`, `.item`, `[href]` or `:root`, in all parts of a selector
+    ///
+    /// The simple selectors in the selector lists of `:is()` and `:where()` are counted as well,
+    /// and each of these pseudo-classes counts as one more component.
+    pub fn max_components(mut self, components: usize) -> Self {
+        self.max_components = components;
+        self
+    }
+}
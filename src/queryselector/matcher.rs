@@ -0,0 +1,62 @@
+use crate::{Node, Parser};
+
+use super::{CompiledSelector, Selector};
+
+/// A predicate that decides which nodes a query yields, see [`VDom::query_selector_with`](crate::VDom::query_selector_with)
+///
+/// Query selectors implement this trait, and so do closures that take a node and the parser of its document,
+/// which makes it possible to query nodes by conditions that selectors cannot express, like a regular expression on
+/// their text or data that is stored outside of the document. Such queries reuse the iterator of query selectors,
+/// so they skip the contents of `<template>` elements and can be narrowed down with
+/// [`QuerySelectorIterator::filter_text`](super::QuerySelectorIterator::filter_text) and the other adapters.
+///
+/// Unlike query selectors, other matchers cannot use the lookup tables of the parser, so every node of the
+/// collection is checked.
+///
+/// # Example
+/// ```
+/// use tl::queryselector::Matcher;
+/// use tl::{Node, Parser};
+///
+/// /// Matches elements that have more than the given number of attributes
+/// struct AttributeCount(usize);
+///
+/// impl Matcher for AttributeCount {
+///     fn matches<'b>(&self, node: &Node<'b>, _parser: &Parser<'b>) -> bool {
+///         node.as_tag().is_some_and(|tag| tag.attributes().len() > self.0)
+///     }
+/// }
+///
+/// let dom = tl::parse(r#"<a href="/">Home</a><a href="/x" id="x" rel="nofollow">X</a>"#, Default::default()).unwrap();
+///
+/// let ids = dom
+///     .query_selector_with(AttributeCount(2))
+///     .attrs("id")
+///     .collect::<Vec<_>>();
+/// assert_eq!(ids, ["x"]);
+/// ```
+pub trait Matcher {
+    /// Checks whether the given node of the document of the given parser matches
+    fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool;
+}
+
+impl<F> Matcher for F
+where
+    F: Fn(&Node<'_>, &Parser<'_>) -> bool,
+{
+    fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool {
+        self(node, parser)
+    }
+}
+
+impl Matcher for Selector<'_> {
+    fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool {
+        Selector::matches(self, node, parser)
+    }
+}
+
+impl Matcher for CompiledSelector<'_> {
+    fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool {
+        CompiledSelector::matches(self, node, parser)
+    }
+}
@@ -1,12 +1,27 @@
+/// Compiled query selectors
+pub mod compiled;
 /// Query selector iterator
 pub mod iter;
 /// Query selector iterable
 pub mod iterable;
+/// Limits for untrusted query selectors
+pub mod limits;
+/// Custom predicates for queries
+pub mod matcher;
+/// Namespace prefixes of query selectors
+pub mod namespaces;
 /// Query selector parser
 pub mod parser;
 /// Query selector
 pub mod selector;
+/// Sets of query selectors that are matched in a single pass
+pub mod set;
 
+pub use compiled::*;
 pub use iter::*;
+pub use limits::*;
+pub use matcher::*;
+pub use namespaces::*;
 pub use parser::*;
 pub use selector::*;
+pub use set::*;
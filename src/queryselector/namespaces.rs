@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::Namespace;
+
+/// The XLink namespace, of attributes like `xlink:href` in SVG
+const XLINK: &str = "http://www.w3.org/1999/xlink";
+/// The XML namespace, of attributes like `xml:lang`
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+/// The XMLNS namespace, of namespace declarations like `xmlns:xlink`
+const XMLNS: &str = "http://www.w3.org/2000/xmlns/";
+
+/// A map of namespace prefixes to namespace URIs, which resolves prefixes like `svg|` in query selectors,
+/// see [`VDom::query_selector_ns`](crate::VDom::query_selector_ns)
+///
+/// This is what `@namespace` rules declare in CSS. The prefixes of a selector are independent of the prefixes in the
+/// document, so a selector may use `s|a` for SVG links.
+///
+/// # Example
+/// ```
+/// use tl::queryselector::Namespaces;
+///
+/// let namespaces = Namespaces::new().prefix("s", "http://www.w3.org/2000/svg");
+///
+/// assert_eq!(namespaces.get("s"), Some("http://www.w3.org/2000/svg"));
+/// assert_eq!(namespaces.get("svg"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    prefixes: HashMap<String, String>,
+}
+
+impl Namespaces {
+    /// Creates an empty map, in which no prefix is declared
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a map with the usual prefixes `html`, `svg`, `math`, `xlink`, `xml` and `xmlns`
+    pub fn standard() -> Self {
+        [Namespace::Html, Namespace::Svg, Namespace::MathMl]
+            .into_iter()
+            .zip(["html", "svg", "math"])
+            .filter_map(|(namespace, prefix)| Some((prefix, namespace.uri()?)))
+            .chain([("xlink", XLINK), ("xml", XML), ("xmlns", XMLNS)])
+            .fold(Self::new(), |namespaces, (prefix, uri)| {
+                namespaces.prefix(prefix, uri)
+            })
+    }
+
+    /// Declares a prefix for the namespace with the given URI, replacing an earlier declaration of the same prefix
+    pub fn prefix(mut self, prefix: &str, uri: &str) -> Self {
+        self.prefixes.insert(prefix.to_owned(), uri.to_owned());
+        self
+    }
+
+    /// Returns the namespace URI of a prefix, or `None` if it is not declared
+    pub fn get(&self, prefix: &str) -> Option<&str> {
+        self.prefixes.get(prefix).map(String::as_str)
+    }
+
+    /// Returns the namespace of the elements that a prefix selects
+    ///
+    /// The parser only knows the HTML, SVG and MathML namespaces, so every other URI selects the elements in
+    /// [`Namespace::Other`].
+    pub(crate) fn element_namespace(&self, prefix: &[u8]) -> Option<Namespace> {
+        let uri = self.get(std::str::from_utf8(prefix).ok()?)?;
+        Some(Namespace::from_uri(uri.as_bytes()))
+    }
+
+    /// Returns the prefix that attributes in the namespace of a prefix have in documents
+    ///
+    /// HTML documents write attributes of foreign content with fixed prefixes like `xlink:href`, so the XLink, XML and
+    /// XMLNS namespaces are resolved to these. For every other namespace, the document is assumed to use the prefix of the selector.
+    pub(crate) fn attribute_prefix<'a>(&self, prefix: &'a [u8]) -> Option<&'a [u8]> {
+        let uri = self.get(std::str::from_utf8(prefix).ok()?)?;
+        Some(match uri {
+            XLINK => b"xlink",
+            XML => b"xml",
+            XMLNS => b"xmlns",
+            _ => prefix,
+        })
+    }
+}
@@ -1,10 +1,37 @@
-use crate::{stream::Stream, util};
+use std::borrow::Cow;
 
-use super::Selector;
+use crate::{errors::SelectorError, stream::Stream, util, Namespace};
+
+use super::{AttributeNamespace, Namespaces, Selector, SelectorLimits};
+
+const PSEUDO_CONTAINS: &[u8] = b":contains(";
+const PSEUDO_NTH_CHILD: &[u8] = b":nth-child(";
+const PSEUDO_IS: &[u8] = b":is(";
+const PSEUDO_WHERE: &[u8] = b":where(";
+const PSEUDO_NTH_OF_TYPE: &[u8] = b":nth-of-type(";
+
+/// The pseudo-classes without an argument and the selectors they are parsed into
+const PSEUDO_KEYWORDS: &[(&[u8], Selector<'static>)] = &[
+    (b":empty", Selector::Empty),
+    (b":root", Selector::Root),
+    (b":first-of-type", Selector::NthOfType(1)),
+    (b":last-of-type", Selector::LastOfType),
+    (b":only-of-type", Selector::OnlyOfType),
+];
 
 /// A query selector parser
 pub struct Parser<'a> {
     stream: Stream<'a, u8>,
+    /// The number of selector lists of `:is()` and `:where()` that the stream is in
+    depth: usize,
+    /// The declared namespace prefixes, without which selectors with a prefix like `svg|circle` are invalid
+    namespaces: Option<&'a Namespaces>,
+    /// The limits of an untrusted selector, see [`Parser::limits`]
+    limits: Option<SelectorLimits>,
+    /// The number of simple selectors that were parsed so far
+    components: usize,
+    /// The limit that was exceeded, after which parsing stops
+    error: Option<SelectorError>,
 }
 
 impl<'a> Parser<'a> {
@@ -12,9 +39,49 @@ impl<'a> Parser<'a> {
     pub fn new(input: &'a [u8]) -> Self {
         Self {
             stream: Stream::new(input),
+            depth: 0,
+            namespaces: None,
+            limits: None,
+            components: 0,
+            error: None,
         }
     }
 
+    /// Creates a new query selector parser that resolves namespace prefixes like `svg|` with the given map
+    pub fn with_namespaces(input: &'a [u8], namespaces: &'a Namespaces) -> Self {
+        Self {
+            namespaces: Some(namespaces),
+            ..Self::new(input)
+        }
+    }
+
+    /// Rejects selectors that exceed the given limits, which is needed for parsing untrusted selectors safely
+    ///
+    /// The limits are only reported as errors by [`Parser::try_selector`]. [`Parser::selector`] returns `None` instead.
+    pub fn limits(mut self, limits: SelectorLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Parses a query selector list like [`Parser::selector`], but returns an error that tells why parsing failed
+    ///
+    /// # Errors
+    /// [`SelectorError::Invalid`] for invalid selectors, and the other errors if the selector exceeds the limits
+    /// set with [`Parser::limits`].
+    pub fn try_selector(&mut self) -> Result<Selector<'a>, SelectorError> {
+        let selector = self.selector();
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => selector.ok_or(SelectorError::Invalid),
+        }
+    }
+
+    /// Records that a limit was exceeded, which stops parsing
+    fn exceeded<T>(&mut self, error: SelectorError) -> Option<T> {
+        self.error.get_or_insert(error);
+        None
+    }
+
     fn skip_whitespaces(&mut self) -> bool {
         let has_whitespace = self.stream.expect_and_skip_cond(b' ');
         while !self.stream.is_eof() {
@@ -25,12 +92,16 @@ impl<'a> Parser<'a> {
         has_whitespace
     }
 
+    /// Reads an identifier as written, including its escapes, which are resolved when matching with [`unescape`]
     fn read_identifier(&mut self) -> &'a [u8] {
         let start = self.stream.idx;
 
-        while !self.stream.is_eof() {
-            let is_ident = self.stream.current().copied().map_or(false, util::is_ident);
-            if !is_ident {
+        while let Some(tok) = self.stream.current_cpy() {
+            if tok == b'\\' {
+                if !self.skip_escape() {
+                    break;
+                }
+            } else if !is_ident(tok) || self.at_pseudo_class() {
                 break;
             } else {
                 self.stream.advance();
@@ -40,11 +111,89 @@ impl<'a> Parser<'a> {
         self.stream.slice(start, self.stream.idx)
     }
 
+    /// Skips the escape at the current backslash, or returns `false` if the backslash does not start an escape
+    ///
+    /// An escape is either up to six hex digits followed by an optional whitespace, like `\31 ` for `1`,
+    /// or any other character except a newline, like `\:` for `:`.
+    fn skip_escape(&mut self) -> bool {
+        let Some(&next) = self.stream.data().get(self.stream.idx + 1) else {
+            return false;
+        };
+        if next == b'\n' {
+            return false;
+        }
+
+        self.stream.advance();
+        if !next.is_ascii_hexdigit() {
+            self.stream.advance();
+            return true;
+        }
+
+        let mut digits = 0;
+        while digits < 6
+            && self
+                .stream
+                .current_cpy()
+                .is_some_and(|c| c.is_ascii_hexdigit())
+        {
+            self.stream.advance();
+            digits += 1;
+        }
+        if self
+            .stream
+            .current_cpy()
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.stream.advance();
+        }
+        true
+    }
+
+    /// Checks whether the stream is at the start of a supported pseudo-class
+    ///
+    /// Identifiers may contain colons, so this is needed to tell `p:contains(..)` apart from a tag named `p:contains`.
+    fn at_pseudo_class(&self) -> bool {
+        self.at(PSEUDO_CONTAINS)
+            || self.at(PSEUDO_NTH_CHILD)
+            || self.at(PSEUDO_IS)
+            || self.at(PSEUDO_WHERE)
+            || self.at(PSEUDO_NTH_OF_TYPE)
+            || self.pseudo_keyword().is_some()
+    }
+
+    /// Returns the pseudo-class without an argument that the stream is at, like `:empty`, and the length of its name
+    fn pseudo_keyword(&self) -> Option<(usize, &'static Selector<'static>)> {
+        PSEUDO_KEYWORDS.iter().find_map(|(name, selector)| {
+            let after = self.stream.data().get(self.stream.idx + name.len());
+
+            // `:rooted` is not `:root`
+            (self.at(name) && !after.copied().is_some_and(is_ident))
+                .then_some((name.len(), selector))
+        })
+    }
+
+    /// Checks whether the stream is at the given ASCII case insensitive prefix
+    fn at(&self, prefix: &[u8]) -> bool {
+        self.stream
+            .slice_checked(self.stream.idx, self.stream.idx + prefix.len())
+            .eq_ignore_ascii_case(prefix)
+    }
+
     /// Parses a query selector list
     ///
     /// A query selector list is a list of complex selectors separated by
     /// commas. This is the entire query selector string.
     pub fn selector(&mut self) -> Option<Selector<'a>> {
+        if let Some(limits) = self.limits {
+            let length = self.stream.len();
+            if length > limits.max_length {
+                return self.exceeded(SelectorError::TooLong {
+                    length,
+                    max: limits.max_length,
+                });
+            }
+        }
+
         let mut left = self.parse_complex_selector(false)?;
 
         while let Some(right) = self.parse_complex_selector(false) {
@@ -67,6 +216,8 @@ impl<'a> Parser<'a> {
 
         while let Some(tok) = self.stream.current_cpy() {
             match tok {
+                // the end of a selector in the list of `:is()` or `:where()`, which is consumed by the caller
+                b',' | b')' if self.depth > 0 => return Some(left),
                 b',' => {
                     self.stream.advance();
                     return Some(left);
@@ -111,18 +262,64 @@ impl<'a> Parser<'a> {
             }
             Some(b'*') => {
                 self.stream.advance();
-                Some(Selector::All)
+                match self.stream.expect_and_skip(b'|') {
+                    // `*|circle` is an element in any namespace, like `circle`
+                    Some(_) => self.parse_namespaced_type(None),
+                    None => Some(Selector::All),
+                }
+            }
+            Some(b'|') => {
+                self.stream.advance();
+                self.parse_namespaced_type(Some(Selector::Namespace(Namespace::Other)))
             }
             Some(b'[') => {
                 self.stream.advance();
                 self.parse_attribute()
             }
-            Some(tok) if util::is_ident(tok) => {
+            Some(b':') if self.at(PSEUDO_CONTAINS) => {
+                self.stream.advance_by(PSEUDO_CONTAINS.len());
+                self.parse_contains()
+            }
+            Some(b':') if self.at(PSEUDO_NTH_CHILD) => {
+                self.stream.advance_by(PSEUDO_NTH_CHILD.len());
+                self.parse_index().map(Selector::NthChild)
+            }
+            Some(b':') if self.at(PSEUDO_NTH_OF_TYPE) => {
+                self.stream.advance_by(PSEUDO_NTH_OF_TYPE.len());
+                self.parse_index().map(Selector::NthOfType)
+            }
+            Some(b':') if self.pseudo_keyword().is_some() => {
+                let (len, selector) = self.pseudo_keyword()?;
+                self.stream.advance_by(len);
+                Some(selector.clone())
+            }
+            Some(b':') if self.at(PSEUDO_IS) => {
+                self.stream.advance_by(PSEUDO_IS.len());
+                self.parse_nested_list()
+            }
+            Some(b':') if self.at(PSEUDO_WHERE) => {
+                self.stream.advance_by(PSEUDO_WHERE.len());
+                self.parse_nested_list()
+            }
+            Some(tok) if is_ident(tok) || tok == b'\\' => {
                 let tag = self.read_identifier();
-                Some(Selector::Tag(tag))
+                match self.stream.expect_and_skip(b'|') {
+                    Some(_) => {
+                        let namespace = self.namespaces?.element_namespace(tag)?;
+                        self.parse_namespaced_type(Some(Selector::Namespace(namespace)))
+                    }
+                    None => Some(Selector::Tag(tag)),
+                }
             }
             _ => None,
         } {
+            self.components += 1;
+            if let Some(max) = self.limits.map(|limits| limits.max_components) {
+                if self.components > max {
+                    return self.exceeded(SelectorError::TooManyComponents { max });
+                }
+            }
+
             if let Some(left) = result {
                 result = Some(Selector::And(Box::new(left), Box::new(right)));
             } else {
@@ -133,8 +330,44 @@ impl<'a> Parser<'a> {
         result
     }
 
+    /// Parses the type selector after the `|` of a namespace, like `circle` in `svg|circle`
+    fn parse_namespaced_type(&mut self, namespace: Option<Selector<'a>>) -> Option<Selector<'a>> {
+        let tag = match self.stream.current_cpy()? {
+            b'*' => {
+                self.stream.advance();
+                None
+            }
+            tok if is_ident(tok) || tok == b'\\' => Some(Selector::Tag(self.read_identifier())),
+            _ => return None,
+        };
+
+        match (namespace, tag) {
+            (Some(namespace), Some(tag)) => Some(Selector::And(Box::new(namespace), Box::new(tag))),
+            (namespace, tag) => namespace.or(tag).or(Some(Selector::All)),
+        }
+    }
+
     fn parse_attribute(&mut self) -> Option<Selector<'a>> {
-        let attribute = self.read_identifier();
+        self.skip_whitespaces();
+
+        let mut namespace = None;
+        if self.at(b"*|") {
+            self.stream.advance_by(2);
+            namespace = Some(AttributeNamespace::Any);
+        } else {
+            // `[|foo]` is an attribute without a namespace, like `[foo]`
+            self.stream.expect_and_skip(b'|');
+        }
+
+        let mut attribute = self.read_identifier();
+        if namespace.is_none() && self.at(b"|") && !self.at(b"|=") {
+            self.stream.advance();
+            let prefix = self.namespaces?.attribute_prefix(attribute)?;
+            namespace = Some(AttributeNamespace::Prefix(prefix));
+            attribute = self.read_identifier();
+        }
+        self.skip_whitespaces();
+
         let ty = match self.stream.current_cpy() {
             Some(b']') => {
                 self.stream.advance();
@@ -142,25 +375,13 @@ impl<'a> Parser<'a> {
             }
             Some(b'=') => {
                 self.stream.advance();
-                let quote = self.stream.expect_oneof_and_skip(&[b'"', b'\'']);
-                let value = self.read_identifier();
-                if let Some(quote) = quote {
-                    // Only require the given quote if the value starts with a quote
-                    self.stream.expect_and_skip(quote)?;
-                }
-                self.stream.expect_and_skip(b']')?;
+                let value = self.parse_attribute_value()?;
                 Selector::AttributeValue(attribute, value)
             }
             Some(c @ b'~' | c @ b'^' | c @ b'$' | c @ b'*') => {
                 self.stream.advance();
                 self.stream.expect_and_skip(b'=')?;
-                let quote = self.stream.expect_oneof_and_skip(&[b'"', b'\'']);
-                let value = self.read_identifier();
-                if let Some(quote) = quote {
-                    // Only require the given quote if the value starts with a quote
-                    self.stream.expect_and_skip(quote)?;
-                }
-                self.stream.expect_and_skip(b']')?;
+                let value = self.parse_attribute_value()?;
                 match c {
                     b'~' => Selector::AttributeValueWhitespacedContains(attribute, value),
                     b'^' => Selector::AttributeValueStartsWith(attribute, value),
@@ -171,6 +392,184 @@ impl<'a> Parser<'a> {
             }
             _ => return None,
         };
-        Some(ty)
+
+        match namespace {
+            Some(namespace) => Some(Selector::NamespacedAttribute(namespace, Box::new(ty))),
+            None => Some(ty),
+        }
+    }
+
+    /// Reads a string after its opening quote up to and including its closing quote, and returns its content as written
+    ///
+    /// Escaped quotes like `\"` do not end the string. Like identifiers, escapes are resolved with [`unescape`] when matching.
+    fn read_string(&mut self, quote: u8) -> Option<&'a [u8]> {
+        let start = self.stream.idx;
+        while self.stream.current_cpy()? != quote {
+            if self.stream.current_cpy() == Some(b'\\') {
+                self.stream.advance();
+            }
+            self.stream.advance();
+        }
+
+        let text = self.stream.slice(start, self.stream.idx);
+        self.stream.advance();
+        Some(text)
+    }
+
+    /// Parses the value of an attribute selector up to and including the closing bracket
+    ///
+    /// The value is either a quoted string, which may contain any character, or an identifier.
+    fn parse_attribute_value(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespaces();
+
+        let value = match self.stream.expect_oneof_and_skip(b"\"'") {
+            Some(quote) => self.read_string(quote)?,
+            None => self.read_identifier(),
+        };
+
+        self.skip_whitespaces();
+        self.stream.expect_and_skip(b']')?;
+        Some(value)
     }
-}
\ No newline at end of file
+
+    /// Parses the argument of `:contains()`, which is either a quoted string or the text up to the closing parenthesis
+    fn parse_contains(&mut self) -> Option<Selector<'a>> {
+        self.skip_whitespaces();
+
+        let text = match self.stream.expect_oneof_and_skip(b"\"'") {
+            Some(quote) => {
+                let text = self.read_string(quote)?;
+                self.skip_whitespaces();
+                text
+            }
+            None => {
+                let start = self.stream.idx;
+                while self.stream.current_cpy()? != b')' {
+                    self.stream.advance();
+                }
+                self.stream.slice(start, self.stream.idx).trim_ascii_end()
+            }
+        };
+
+        self.stream.expect_and_skip(b')')?;
+        Some(Selector::Contains(text))
+    }
+
+    /// Parses the selector list of `:is()` or `:where()` up to and including the closing parenthesis
+    ///
+    /// Both match an element that matches any of the selectors in the list, like a selector list at the top level.
+    /// They only differ in their specificity, which is irrelevant for matching.
+    fn parse_nested_list(&mut self) -> Option<Selector<'a>> {
+        self.depth += 1;
+        if let Some(max) = self.limits.map(|limits| limits.max_nesting) {
+            if self.depth > max {
+                return self.exceeded(SelectorError::TooDeep { max });
+            }
+        }
+
+        let mut list = self.parse_complex_selector(false)?;
+        while self.stream.expect_and_skip(b',').is_some() {
+            let right = self.parse_complex_selector(false)?;
+            list = Selector::Or(Box::new(list), Box::new(right));
+        }
+
+        self.stream.expect_and_skip(b')')?;
+        self.depth -= 1;
+        Some(list)
+    }
+
+    /// Parses the argument of `:nth-child()` and `:nth-of-type()` up to and including the closing parenthesis,
+    /// which must be a positive integer
+    fn parse_index(&mut self) -> Option<usize> {
+        self.skip_whitespaces();
+
+        let start = self.stream.idx;
+        while self.stream.current_cpy()?.is_ascii_digit() {
+            self.stream.advance();
+        }
+        let index = std::str::from_utf8(self.stream.slice(start, self.stream.idx))
+            .ok()?
+            .parse::<usize>()
+            .ok()
+            .filter(|&index| index > 0)?;
+
+        self.skip_whitespaces();
+        self.stream.expect_and_skip(b')')?;
+        Some(index)
+    }
+}
+
+/// Checks whether a byte can be part of an identifier in a selector, which unlike in HTML includes all non-ASCII characters
+fn is_ident(c: u8) -> bool {
+    util::is_ident(c) || !c.is_ascii()
+}
+
+/// Resolves the CSS escapes of an identifier or string in a selector, like `\31 23` for `123` or `foo\:bar` for `foo:bar`
+///
+/// The values of [`Selector`]s are stored as written in the selector and resolved with this when matching.
+/// Code points that are zero or not valid are replaced with U+FFFD, and the input is borrowed if it has no escapes.
+///
+/// # Example
+/// ```
+/// use tl::queryselector::unescape;
+///
+/// assert_eq!(&*unescape(br"\31 23"), b"123");
+/// assert_eq!(&*unescape(br"md\:w-1\/2"), b"md:w-1/2");
+/// assert_eq!(&*unescape(br"caf\e9"), "café".as_bytes());
+/// ```
+pub fn unescape(input: &[u8]) -> Cow<'_, [u8]> {
+    if !input.contains(&b'\\') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some((&c, tail)) = rest.split_first() {
+        rest = tail;
+        if c != b'\\' {
+            output.push(c);
+            continue;
+        }
+
+        let digits = rest
+            .iter()
+            .take(6)
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+
+        if digits == 0 {
+            match rest.split_first() {
+                // an escaped newline continues a string on the next line
+                Some((b'\n', tail)) => rest = tail,
+                Some((&c, tail)) => {
+                    output.push(c);
+                    rest = tail;
+                }
+                None => output.extend_from_slice(
+                    char::REPLACEMENT_CHARACTER
+                        .encode_utf8(&mut [0; 4])
+                        .as_bytes(),
+                ),
+            }
+            continue;
+        }
+
+        let code = rest[..digits].iter().fold(0, |code, &digit| {
+            code * 16 + (digit as char).to_digit(16).unwrap_or_default()
+        });
+        rest = &rest[digits..];
+        if let Some((c, tail)) = rest.split_first() {
+            if c.is_ascii_whitespace() {
+                rest = tail;
+            }
+        }
+
+        let c = char::from_u32(code)
+            .filter(|&c| c != '\0')
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    Cow::Owned(output)
+}
@@ -1,6 +1,11 @@
-use crate::{Node, Parser};
+use crate::{ClassList, HTMLTag, Namespace, Node, Parser};
+
+use super::unescape;
 
 /// A single query selector node
+///
+/// Identifiers and values are stored as written in the selector, including CSS escapes like `\31 23`,
+/// which are resolved with [`unescape`] when matching.
 #[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum Selector<'a> {
@@ -15,12 +20,14 @@ pub enum Selector<'a> {
     /// And combinator: .foo.bar
     And(Box<Selector<'a>>, Box<Selector<'a>>),
     /// Or combinator: .foo, .bar
+    ///
+    /// This is also used for the selector lists of `:is(.foo, .bar)` and `:where(.foo, .bar)`
     Or(Box<Selector<'a>>, Box<Selector<'a>>),
     /// Descendant combinator: .foo .bar
     Descendant(Box<Selector<'a>>, Box<Selector<'a>>),
     /// Parent combinator: .foo > .bar
     Parent(Box<Selector<'a>>, Box<Selector<'a>>),
-    /// Attribute: [foo]
+    /// Attribute: \[foo\]
     Attribute(&'a [u8]),
     /// Attribute with value: [foo=bar]
     AttributeValue(&'a [u8], &'a [u8]),
@@ -32,19 +39,138 @@ pub enum Selector<'a> {
     AttributeValueEndsWith(&'a [u8], &'a [u8]),
     /// Attribute with value that contains: [foo*=bar]
     AttributeValueSubstring(&'a [u8], &'a [u8]),
+    /// Element whose text contains a substring: :contains("foo")
+    Contains(&'a [u8]),
+    /// Element that is the nth element among its siblings, counting from 1: :nth-child(2)
+    NthChild(usize),
+    /// Element without children other than whitespace and comments: :empty
+    Empty,
+    /// Element without a parent, i.e. the `<html>` element of a document or a topmost element of a fragment: :root
+    Root,
+    /// Element that is the nth element with its tag name among its siblings, counting from 1: :nth-of-type(2)
+    ///
+    /// This is also used for `:first-of-type`, which is `:nth-of-type(1)`.
+    NthOfType(usize),
+    /// Element that is the last element with its tag name among its siblings: :last-of-type
+    LastOfType,
+    /// Element that is the only element with its tag name among its siblings: :only-of-type
+    OnlyOfType,
+    /// Element in a namespace: svg|*
+    ///
+    /// A type selector with a namespace like `svg|circle` is parsed into this and a tag selector.
+    Namespace(Namespace),
+    /// Attribute in a namespace: [xlink|href], [xlink|href="#a"]
+    ///
+    /// The attribute selector checks the local name of the attribute, like `href`.
+    NamespacedAttribute(AttributeNamespace<'a>, Box<Selector<'a>>),
+}
+
+/// The specificity of a selector, see [`Selector::specificity`]
+///
+/// Specificities are ordered by their number of IDs first, then by their number of classes, attributes and pseudo-classes,
+/// and then by their number of types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity {
+    /// The number of ID selectors, like `#nav`
+    pub ids: u32,
+    /// The number of class selectors, attribute selectors and pseudo-classes, like `.item`, `[href]` or `:root`
+    pub classes: u32,
+    /// The number of type selectors, like `li`
+    pub types: u32,
+}
+
+/// The namespace of an attribute in a selector, see [`Selector::NamespacedAttribute`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeNamespace<'a> {
+    /// Any namespace or none: [*|href]
+    Any,
+    /// The namespace of attributes with the given prefix in the document, like `xlink` for `xlink:href`
+    Prefix(&'a [u8]),
+}
+
+impl AttributeNamespace<'_> {
+    /// Checks whether an attribute name as written in the document has this namespace and the given local name
+    fn matches(&self, name: &[u8], local: &[u8]) -> bool {
+        match name.strip_suffix(local) {
+            Some(b"") => *self == Self::Any,
+            Some(prefix) => prefix.strip_suffix(b":").is_some_and(|prefix| match self {
+                Self::Any => !prefix.is_empty(),
+                Self::Prefix(expected) => prefix == *expected,
+            }),
+            None => false,
+        }
+    }
 }
 
 impl<'a> Selector<'a> {
+    /// Returns the specificity of this selector, which decides which of several matching CSS rules wins
+    ///
+    /// A selector list like `a, b` and the arguments of `:is()` and `:where()` count as their most specific selector.
+    /// Both pseudo-classes are parsed into the same selector, so `:where()` is not treated as having no specificity.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::queryselector::Specificity;
+    ///
+    /// let selector = tl::parse_query_selector("#nav > li.item a").unwrap();
+    /// assert_eq!(selector.specificity(), Specificity { ids: 1, classes: 1, types: 2 });
+    /// ```
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Self::Id(_) => Specificity {
+                ids: 1,
+                ..Default::default()
+            },
+            Self::Tag(_) => Specificity {
+                types: 1,
+                ..Default::default()
+            },
+            Self::All | Self::Namespace(_) => Specificity::default(),
+            Self::And(a, b) | Self::Descendant(a, b) | Self::Parent(a, b) => {
+                let (a, b) = (a.specificity(), b.specificity());
+                Specificity {
+                    ids: a.ids + b.ids,
+                    classes: a.classes + b.classes,
+                    types: a.types + b.types,
+                }
+            }
+            Self::Or(a, b) => a.specificity().max(b.specificity()),
+            Self::Class(_)
+            | Self::Attribute(_)
+            | Self::AttributeValue(..)
+            | Self::AttributeValueWhitespacedContains(..)
+            | Self::AttributeValueStartsWith(..)
+            | Self::AttributeValueEndsWith(..)
+            | Self::AttributeValueSubstring(..)
+            | Self::Contains(_)
+            | Self::NthChild(_)
+            | Self::Empty
+            | Self::Root
+            | Self::NthOfType(_)
+            | Self::LastOfType
+            | Self::OnlyOfType
+            | Self::NamespacedAttribute(..) => Specificity {
+                classes: 1,
+                ..Default::default()
+            },
+        }
+    }
+
     /// Checks if the given node matches this selector
     pub fn matches<'b>(&self, node: &Node<'b>, parser: &Parser<'b>) -> bool {
         match self {
-            Self::Tag(tag) => node.as_tag().map_or(false, |t| t._name.as_bytes().eq(*tag)),
-            Self::Id(id) => node
+            Self::Tag(tag) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.id == Some((*id).into())),
+                .is_some_and(|t| has_type(t, &unescape(tag), parser)),
+            Self::Id(id) => node.as_tag().is_some_and(|t| {
+                t._attributes
+                    .id
+                    .as_ref()
+                    .is_some_and(|value| value.as_bytes() == &*unescape(id))
+            }),
             Self::Class(class) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.is_class_member(*class)),
+                .is_some_and(|t| t._attributes.classes().contains(unescape(class))),
             Self::And(a, b) => a.matches(node, parser) && b.matches(node, parser),
             Self::Or(a, b) => a.matches(node, parser) || b.matches(node, parser),
             Self::All => true,
@@ -56,12 +182,12 @@ impl<'a> Selector<'a> {
                 .unwrap_or_default(),
             Self::Descendant(a, b) => {
                 if !b.matches(node, parser) {
-                    return false
+                    return false;
                 }
                 let mut curr = node;
                 while let Some(ancestor) = curr.as_tag().and_then(|t| t._parent?.get(parser)) {
                     if a.matches(ancestor, parser) {
-                        return true
+                        return true;
                     }
                     curr = ancestor;
                 }
@@ -69,7 +195,7 @@ impl<'a> Selector<'a> {
             }
             Self::Attribute(attribute) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.get(*attribute).is_some()),
+                .is_some_and(|t| t._attributes.get(&*unescape(attribute)).is_some()),
             Self::AttributeValue(attribute, value) => {
                 check_attribute(node, attribute, value, |attr, value| attr == value)
             }
@@ -86,23 +212,118 @@ impl<'a> Selector<'a> {
             }
             Self::AttributeValueWhitespacedContains(attribute, value) => {
                 check_attribute(node, attribute, value, |attr, value| {
-                    attr.split_whitespace().any(|x| x == value)
+                    ClassList::new(attr).contains(value)
                 })
             }
+            Self::Contains(text) => node.as_tag().is_some_and(|t| {
+                t.inner_text(parser)
+                    .contains(&*String::from_utf8_lossy(&unescape(text)))
+            }),
+            Self::NthChild(index) => node
+                .as_tag()
+                .and_then(|t| t.element_index(parser))
+                .is_some_and(|i| i + 1 == *index),
+            Self::Empty => node.as_tag().is_some_and(|t| {
+                t.children()
+                    .top()
+                    .iter()
+                    .all(|child| match child.get(parser) {
+                        Some(Node::Raw(text)) => {
+                            text.as_bytes().iter().all(u8::is_ascii_whitespace)
+                        }
+                        Some(Node::Comment(_)) => true,
+                        _ => false,
+                    })
+            }),
+            Self::Root => node.as_tag().is_some_and(|t| t._parent.is_none()),
+            Self::NthOfType(index) => node
+                .as_tag()
+                .and_then(|t| t.type_index(parser))
+                .is_some_and(|(i, _)| i + 1 == *index),
+            Self::LastOfType => node
+                .as_tag()
+                .and_then(|t| t.type_index(parser))
+                .is_some_and(|(i, count)| i + 1 == count),
+            Self::OnlyOfType => node
+                .as_tag()
+                .and_then(|t| t.type_index(parser))
+                .is_some_and(|(_, count)| count == 1),
+            Self::Namespace(namespace) => node.as_tag().is_some_and(|t| t._namespace == *namespace),
+            Self::NamespacedAttribute(namespace, selector) => node.as_tag().is_some_and(|t| {
+                let Some(local) = selector.attribute_name().map(unescape) else {
+                    return false;
+                };
+
+                t._attributes.iter().any(|(name, value)| {
+                    namespace.matches(name.as_bytes(), &local)
+                        && selector.matches_attribute_value(value.as_deref())
+                })
+            }),
+        }
+    }
+
+    /// Returns the attribute name of an attribute selector
+    fn attribute_name(&self) -> Option<&'a [u8]> {
+        match self {
+            Self::Attribute(name)
+            | Self::AttributeValue(name, _)
+            | Self::AttributeValueWhitespacedContains(name, _)
+            | Self::AttributeValueStartsWith(name, _)
+            | Self::AttributeValueEndsWith(name, _)
+            | Self::AttributeValueSubstring(name, _) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Checks the value of an attribute against an attribute selector, regardless of the name of the attribute
+    fn matches_attribute_value(&self, attr: Option<&str>) -> bool {
+        let check = |value: &[u8], callback: fn(&str, &str) -> bool| {
+            attr.is_some_and(|attr| callback(attr, &String::from_utf8_lossy(&unescape(value))))
+        };
+
+        match self {
+            Self::Attribute(_) => true,
+            Self::AttributeValue(_, value) => check(value, |attr, value| attr == value),
+            Self::AttributeValueEndsWith(_, value) => {
+                check(value, |attr, value| attr.ends_with(value))
+            }
+            Self::AttributeValueStartsWith(_, value) => {
+                check(value, |attr, value| attr.starts_with(value))
+            }
+            Self::AttributeValueSubstring(_, value) => {
+                check(value, |attr, value| attr.contains(value))
+            }
+            Self::AttributeValueWhitespacedContains(_, value) => {
+                check(value, |attr, value| ClassList::new(attr).contains(value))
+            }
+            _ => false,
         }
     }
 }
 
+/// Checks whether the given tag has the given name, which is compared ASCII case insensitively for HTML elements
+/// outside of XML mode, because their names are case insensitive
+pub(crate) fn has_type(tag: &HTMLTag<'_>, name: &[u8], parser: &Parser<'_>) -> bool {
+    if parser.options.is_xml_mode() || tag._namespace.is_foreign() {
+        tag._name.as_bytes() == name
+    } else {
+        tag._name.as_bytes().eq_ignore_ascii_case(name)
+    }
+}
+
 fn check_attribute<F>(node: &Node, attribute: &[u8], value: &[u8], callback: F) -> bool
 where
     F: Fn(&str, &str) -> bool,
 {
-    node.as_tag().map_or(false, |t| {
+    node.as_tag().is_some_and(|t| {
         t._attributes
-            .get(attribute)
+            .get(&*unescape(attribute))
             .flatten()
-            .map_or(false, |attr| {
-                callback(&attr.as_utf8_str(), &String::from_utf8_lossy(value))
+            .is_some_and(|attr| {
+                callback(
+                    &attr.as_utf8_str(),
+                    &String::from_utf8_lossy(&unescape(value)),
+                )
             })
     })
 }
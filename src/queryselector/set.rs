@@ -0,0 +1,365 @@
+use crate::{NodeHandle, Parser};
+
+use super::{iter::template_contents, iterable::QueryIterable, CompiledSelector, Selector};
+
+/// A selector of a [`SelectorSet`]
+#[derive(Debug, Clone)]
+struct Rule<'a> {
+    selector: Selector<'a>,
+    priority: i32,
+    /// Whether the rule stops matching after its first match
+    first_only: bool,
+}
+
+/// A set of query selectors that are matched against a collection in a single pass
+///
+/// Every selector of the set has a priority and is either added with [`SelectorSet::add`], which collects all of
+/// its matches, or with [`SelectorSet::add_first`], which stops after its first match.
+///
+/// Nodes are visited in document order and every node is checked against the selectors in the order of their
+/// priority, highest first, or in the order they were added if their priorities are equal.
+/// A node is assigned to the first selector that matches it, so it is never a match of more than one selector.
+/// Selectors that stop after their first match are not checked anymore once they matched, which means that
+/// later nodes can be assigned to selectors with a lower priority again.
+/// Like in query selector iterators, the contents of `<template>` elements are skipped.
+///
+/// Once every selector of the set has stopped, the remaining nodes are not visited at all, so extracting a few
+/// values that appear early in a large document, like the title and metadata in its `<head>`, is cheap.
+///
+/// # Example
+/// ```
+/// use tl::queryselector::SelectorSet;
+///
+/// let dom = tl::parse(r#"
+///     <head>
+///         <meta property="og:title" content="Open Graph title">
+///         <title>Document title</title>
+///     </head>
+///     <body><h1>Heading</h1><h1>Another heading</h1></body>
+/// "#, Default::default()).unwrap();
+/// let parser = dom.parser();
+///
+/// let mut set = SelectorSet::new();
+/// let og_title = set.add_first("meta[property=og:title]", 10).unwrap();
+/// let title = set.add_first("title, h1", 0).unwrap();
+///
+/// let matches = set.run(parser, &dom);
+/// let og_title = matches.first(og_title).unwrap().get(parser).unwrap();
+/// let title = matches.first(title).unwrap().get(parser).unwrap();
+///
+/// assert_eq!(og_title.as_tag().unwrap().attributes().get("content").flatten().unwrap(), "Open Graph title");
+/// assert_eq!(title.inner_text(parser), "Document title");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SelectorSet<'a> {
+    rules: Vec<Rule<'a>>,
+    /// The indices of the rules, sorted by priority
+    order: Vec<usize>,
+}
+
+impl<'a> SelectorSet<'a> {
+    /// Creates an empty selector set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a query selector that collects all of its matches
+    ///
+    /// Returns the index of the selector in the set, or `None` if the selector could not be parsed.
+    pub fn add(&mut self, selector: &'a str, priority: i32) -> Option<usize> {
+        self.push(selector, priority, false)
+    }
+
+    /// Adds a query selector that stops after its first match
+    ///
+    /// Returns the index of the selector in the set, or `None` if the selector could not be parsed.
+    pub fn add_first(&mut self, selector: &'a str, priority: i32) -> Option<usize> {
+        self.push(selector, priority, true)
+    }
+
+    fn push(&mut self, selector: &'a str, priority: i32, first_only: bool) -> Option<usize> {
+        let selector = crate::parse_query_selector(selector)?;
+        let index = self.rules.len();
+
+        self.rules.push(Rule {
+            selector,
+            priority,
+            first_only,
+        });
+
+        // the sort is stable, so rules with the same priority stay in the order they were added
+        self.order.push(index);
+        self.order
+            .sort_by_key(|&index| std::cmp::Reverse(self.rules[index].priority));
+
+        Some(index)
+    }
+
+    /// Returns the number of selectors in this set
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Checks whether this set has no selectors
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Matches the selectors of this set against the nodes of a collection, like a [`VDom`](crate::VDom) or an [`HTMLTag`](crate::HTMLTag)
+    pub fn run<'b, Q: QueryIterable<'b>>(&self, parser: &Parser<'b>, collection: &Q) -> SetMatches {
+        self.scan(parser, collection).0
+    }
+
+    /// Matches the selectors of this set and returns the number of nodes that were visited
+    fn scan<'b, Q: QueryIterable<'b>>(
+        &self,
+        parser: &Parser<'b>,
+        collection: &Q,
+    ) -> (SetMatches, usize) {
+        let mut matches = vec![Vec::new(); self.rules.len()];
+        let compiled = self
+            .rules
+            .iter()
+            .map(|rule| CompiledSelector::new(&rule.selector, parser))
+            .collect::<Vec<_>>();
+        // the rules that are still checked, in the order of their priority
+        let mut active = self.order.clone();
+        let len = collection.len(parser);
+        let start = collection.start().unwrap_or_default();
+        let templates = template_contents(parser, start..start.saturating_add(len as u32));
+        let mut templates = templates.iter().peekable();
+        let mut index = 0;
+
+        while index < len && !active.is_empty() {
+            let node = collection.get(parser, index);
+            index += 1;
+
+            let Some((node, handle)) = node else {
+                continue;
+            };
+
+            // like query selectors, the contents of templates are skipped
+            let inner = handle.get_inner();
+            while templates.next_if(|range| range.end <= inner).is_some() {}
+            if let Some(range) = templates.peek().filter(|range| range.contains(&inner)) {
+                index += (range.end - inner - 1) as usize;
+                continue;
+            }
+
+            let Some(position) = active
+                .iter()
+                .position(|&rule| compiled[rule].matches(node, parser))
+            else {
+                continue;
+            };
+
+            let rule = active[position];
+            matches[rule].push(handle);
+
+            if self.rules[rule].first_only {
+                active.remove(position);
+            }
+        }
+
+        (SetMatches { matches }, index)
+    }
+}
+
+/// The matches of the selectors of a [`SelectorSet`], see [`SelectorSet::run`]
+#[derive(Debug, Clone)]
+pub struct SetMatches {
+    matches: Vec<Vec<NodeHandle>>,
+}
+
+impl SetMatches {
+    /// Returns the matches of the selector with the given index in document order
+    ///
+    /// The slice is empty if the selector did not match any node, or if there is no selector with this index.
+    pub fn get(&self, selector: usize) -> &[NodeHandle] {
+        self.matches
+            .get(selector)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the first match of the selector with the given index
+    pub fn first(&self, selector: usize) -> Option<NodeHandle> {
+        self.get(selector).first().copied()
+    }
+
+    /// Returns an iterator over the matches of all selectors, in the order they were added to the set
+    pub fn iter(&self) -> impl Iterator<Item = &[NodeHandle]> {
+        self.matches.iter().map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParserOptions;
+
+    fn texts(matches: &[NodeHandle], parser: &Parser<'_>) -> Vec<String> {
+        matches
+            .iter()
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn collects_all_matches() {
+        let dom = crate::parse(
+            "<p>a</p><div><p>b</p></div><p>c</p>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let p = set.add("p", 0).unwrap();
+
+        assert_eq!(texts(set.run(parser, &dom).get(p), parser), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn first_only_stops_after_first_match() {
+        let dom = crate::parse(
+            "<p>a</p><p>b</p><span>c</span><span>d</span>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let p = set.add_first("p", 0).unwrap();
+        let span = set.add("span", 0).unwrap();
+        let matches = set.run(parser, &dom);
+
+        assert_eq!(texts(matches.get(p), parser), ["a"]);
+        assert_eq!(texts(matches.get(span), parser), ["c", "d"]);
+    }
+
+    #[test]
+    fn higher_priority_claims_node() {
+        let dom = crate::parse(r#"<p class="x">a</p><p>b</p>"#, ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let any = set.add("p", 0).unwrap();
+        let class = set.add(".x", 1).unwrap();
+        let matches = set.run(parser, &dom);
+
+        assert_eq!(texts(matches.get(class), parser), ["a"]);
+        assert_eq!(texts(matches.get(any), parser), ["b"]);
+    }
+
+    #[test]
+    fn equal_priority_keeps_insertion_order() {
+        let dom = crate::parse(r#"<p class="x">a</p>"#, ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let any = set.add("p", 0).unwrap();
+        let class = set.add(".x", 0).unwrap();
+        let matches = set.run(parser, &dom);
+
+        assert_eq!(texts(matches.get(any), parser), ["a"]);
+        assert!(matches.get(class).is_empty());
+    }
+
+    #[test]
+    fn satisfied_rule_releases_nodes_to_lower_priority() {
+        let dom = crate::parse("<h1>a</h1><h1>b</h1>", ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let first = set.add_first("h1", 1).unwrap();
+        let rest = set.add("h1", 0).unwrap();
+        let matches = set.run(parser, &dom);
+
+        assert_eq!(texts(matches.get(first), parser), ["a"]);
+        assert_eq!(texts(matches.get(rest), parser), ["b"]);
+    }
+
+    #[test]
+    fn stops_once_every_rule_is_satisfied() {
+        let dom = crate::parse(
+            "<title>a</title><p>b</p><p>c</p><p>d</p>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        set.add_first("title", 0).unwrap();
+        let p = set.add_first("p", 0).unwrap();
+        let (matches, visited) = set.scan(parser, &dom);
+
+        assert_eq!(texts(matches.get(p), parser), ["b"]);
+        // the title, its text and the first paragraph
+        assert_eq!(visited, 3);
+    }
+
+    #[test]
+    fn collecting_rule_visits_every_node() {
+        let dom =
+            crate::parse("<title>a</title><p>b</p><p>c</p>", ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        set.add_first("title", 0).unwrap();
+        set.add("p", 0).unwrap();
+        let (_, visited) = set.scan(parser, &dom);
+
+        assert_eq!(visited, dom.nodes().len());
+    }
+
+    #[test]
+    fn runs_within_subtree() {
+        let dom = crate::parse(
+            "<p>a</p><div><p>b</p><p>c</p></div>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+        let div = dom.query_selector("div").unwrap().next().unwrap();
+        let div = div.get(parser).unwrap().as_tag().unwrap();
+
+        let mut set = SelectorSet::new();
+        let p = set.add_first("p", 0).unwrap();
+
+        assert_eq!(texts(set.run(parser, div).get(p), parser), ["b"]);
+    }
+
+    #[test]
+    fn skips_template_contents() {
+        let dom = crate::parse(
+            "<template><p>a</p></template><p>b</p>",
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+
+        let mut set = SelectorSet::new();
+        let p = set.add("p", 0).unwrap();
+
+        assert_eq!(texts(set.run(parser, &dom).get(p), parser), ["b"]);
+    }
+
+    #[test]
+    fn invalid_selector() {
+        let mut set = SelectorSet::new();
+
+        assert_eq!(set.add("", 0), None);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn unknown_index_has_no_matches() {
+        let dom = crate::parse("<p>a</p>", ParserOptions::default()).unwrap();
+
+        let matches = SelectorSet::new().run(dom.parser(), &dom);
+
+        assert!(matches.get(0).is_empty());
+        assert_eq!(matches.first(0), None);
+    }
+}
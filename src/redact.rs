@@ -0,0 +1,212 @@
+use crate::{Attributes, Bytes, Edge, Node, NodeHandle, Traverse, VDom};
+
+/// A policy that decides how [`redact`] replaces the contents of matched elements
+///
+/// By default, every text node inside of a matched element is replaced with `[REDACTED]`, and attributes are left untouched.
+///
+/// # Example
+/// ```
+/// use tl::redact::RedactPolicy;
+///
+/// let policy = RedactPolicy::new()
+///     .placeholder("***")
+///     .attributes(&["href", "title"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactPolicy<'p> {
+    placeholder: &'p str,
+    mask: Option<char>,
+    text: bool,
+    attributes: Vec<&'p str>,
+}
+
+impl Default for RedactPolicy<'_> {
+    fn default() -> Self {
+        Self {
+            placeholder: "[REDACTED]",
+            mask: None,
+            text: true,
+            attributes: Vec::new(),
+        }
+    }
+}
+
+impl<'p> RedactPolicy<'p> {
+    /// Creates a new [`RedactPolicy`] that replaces text with `[REDACTED]`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the string that replaces redacted text and attribute values
+    pub fn placeholder(mut self, placeholder: &'p str) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Replaces every non-whitespace character with the given character instead of using a placeholder,
+    /// which keeps the length and word boundaries of the original text
+    pub fn mask(mut self, mask: char) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Leaves text nodes untouched, so that only attributes are redacted
+    pub fn keep_text(mut self) -> Self {
+        self.text = false;
+        self
+    }
+
+    /// Redacts the values of the given attributes on matched elements
+    ///
+    /// Calling this more than once extends the list.
+    pub fn attributes(mut self, names: &[&'p str]) -> Self {
+        self.attributes.extend_from_slice(names);
+        self
+    }
+
+    /// Returns the redacted version of a text or attribute value
+    ///
+    /// Leading and trailing whitespace of text is kept, so that redacted text does not run into its surroundings.
+    pub fn replace(&self, value: &str) -> String {
+        if let Some(mask) = self.mask {
+            return value
+                .chars()
+                .map(|c| if c.is_whitespace() { c } else { mask })
+                .collect();
+        }
+
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return value.to_owned();
+        }
+
+        let start = value.len() - value.trim_start().len();
+        let end = start + trimmed.len();
+        format!("{}{}{}", &value[..start], self.placeholder, &value[end..])
+    }
+}
+
+/// Redacts the elements that match any of the given query selectors, and returns the number of matched elements
+///
+/// The text of matched elements and their subnodes, as well as the attributes listed in the policy, are replaced
+/// according to the policy. Tags, their nesting and all other attributes are kept, so the document keeps its structure.
+/// Whitespace-only text is never replaced.
+///
+/// Returns `None` without modifying the document if any of the selectors is invalid.
+///
+/// # Example
+/// ```
+/// use tl::redact::RedactPolicy;
+///
+/// let mut dom = tl::parse(
+///     r#"<p>Contact <a class="email" href="mailto:jane@example.com">jane@example.com</a> or <b class="name">Jane <i>Doe</i></b></p>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let policy = RedactPolicy::new().attributes(&["href"]);
+/// assert_eq!(tl::redact(&mut dom, &[".email", ".name"], &policy), Some(2));
+/// assert_eq!(
+///     dom.outer_html(),
+///     r#"<p>Contact <a href="[REDACTED]" class="email">[REDACTED]</a> or <b class="name">[REDACTED] <i>[REDACTED]</i></b></p>"#
+/// );
+/// ```
+pub fn redact(dom: &mut VDom<'_>, selectors: &[&str], policy: &RedactPolicy<'_>) -> Option<usize> {
+    let mut matched = Vec::new();
+
+    for selector in selectors {
+        matched.extend(dom.query_selector(selector)?);
+    }
+
+    matched.sort_unstable();
+    matched.dedup();
+
+    let texts = if policy.text {
+        text_nodes(dom, &matched)
+    } else {
+        Vec::new()
+    };
+
+    let parser = dom.parser_mut();
+
+    for handle in texts {
+        if let Some(Node::Raw(text)) = handle.get_mut(parser) {
+            replace_bytes(text, policy);
+        }
+    }
+
+    for &handle in &matched {
+        let Some(tag) = handle.get_mut(parser).and_then(Node::as_tag_mut) else {
+            continue;
+        };
+
+        redact_attributes(tag.attributes_mut(), policy);
+    }
+
+    Some(matched.len())
+}
+
+/// Returns the text nodes below the given elements without duplicates
+fn text_nodes(dom: &VDom<'_>, roots: &[NodeHandle]) -> Vec<NodeHandle> {
+    let parser = dom.parser();
+    let mut texts = Vec::new();
+
+    for edge in Traverse::new(parser, roots) {
+        if let Edge::Open(handle) = edge {
+            if let Some(Node::Raw(_)) = handle.get(parser) {
+                texts.push(handle);
+            }
+        }
+    }
+
+    // nested matches visit the same text nodes more than once
+    texts.sort_unstable();
+    texts.dedup();
+    texts
+}
+
+/// Redacts the values of the attributes that the policy lists, comparing names case insensitively
+fn redact_attributes(attributes: &mut Attributes<'_>, policy: &RedactPolicy<'_>) {
+    let is_redacted = |name: &[u8]| {
+        policy
+            .attributes
+            .iter()
+            .any(|n| n.as_bytes().eq_ignore_ascii_case(name))
+    };
+
+    if is_redacted(b"id") {
+        attributes
+            .id
+            .iter_mut()
+            .for_each(|v| replace_bytes(v, policy));
+    }
+
+    if is_redacted(b"class") {
+        attributes
+            .class
+            .iter_mut()
+            .for_each(|v| replace_bytes(v, policy));
+    }
+
+    let keys = attributes
+        .raw
+        .iter()
+        .filter(|(key, _)| is_redacted(key.as_bytes()))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+
+    for key in keys {
+        if let Some(Some(value)) = attributes.raw.get_mut(&key) {
+            replace_bytes(value, policy);
+        }
+    }
+}
+
+fn replace_bytes(bytes: &mut Bytes<'_>, policy: &RedactPolicy<'_>) {
+    let replaced = policy.replace(&bytes.as_utf8_str());
+
+    // setting only fails for values longer than `u32::MAX` bytes, in which case nothing of the value is kept
+    if bytes.set(replaced).is_err() {
+        let _ = bytes.set("");
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{HTMLTag, NodeHandle, Parser, VDom};
+
+/// The MIME types that browsers run as classic scripts, besides a missing or empty `type`
+const JAVASCRIPT_MIME_TYPES: &[&str] = &[
+    "application/ecmascript",
+    "application/javascript",
+    "application/x-ecmascript",
+    "application/x-javascript",
+    "text/ecmascript",
+    "text/javascript",
+    "text/javascript1.0",
+    "text/javascript1.1",
+    "text/javascript1.2",
+    "text/javascript1.3",
+    "text/javascript1.4",
+    "text/javascript1.5",
+    "text/jscript",
+    "text/livescript",
+    "text/x-ecmascript",
+    "text/x-javascript",
+];
+
+/// What a `<script>` element contains, as determined by its `type` attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptKind {
+    /// A classic script, without a `type` or with a JavaScript MIME type like `text/javascript`
+    Classic,
+    /// A module script, with `type="module"`
+    Module,
+    /// An import map, with `type="importmap"`
+    ImportMap,
+    /// A data block that browsers do not run, like `application/json` or `text/template`
+    Data,
+}
+
+/// A `<script>` element, as returned by [`VDom::scripts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    /// The `<script>` element
+    pub node: NodeHandle,
+    /// The kind of script
+    pub kind: ScriptKind,
+    /// The value of the `type` attribute in lowercase and without parameters, like `application/ld+json`
+    pub mime_type: Option<String>,
+    /// The URL of an external script, as written in the `src` attribute
+    pub src: Option<String>,
+    /// The inline content of the script, which is `None` for external scripts and empty scripts
+    pub content: Option<String>,
+    /// Whether the script has the `async` attribute
+    pub is_async: bool,
+    /// Whether the script has the `defer` attribute
+    pub is_defer: bool,
+    /// Whether the script has the `nomodule` attribute, which is used for fallbacks of module scripts
+    pub is_nomodule: bool,
+    /// The subresource integrity metadata of an external script, like `sha384-...`
+    pub integrity: Option<String>,
+    /// The value of the `crossorigin` attribute, which is empty if it has no value
+    pub crossorigin: Option<String>,
+}
+
+impl Script {
+    /// Checks whether this is a JSON data block like `application/json` or `application/ld+json`, or an import map
+    pub fn is_json(&self) -> bool {
+        self.kind == ScriptKind::ImportMap
+            || self
+                .mime_type
+                .as_deref()
+                .is_some_and(|mime| mime == "application/json" || mime.ends_with("+json"))
+    }
+
+    /// Returns the inline content of this script if it is JSON, see [`Script::is_json`]
+    ///
+    /// The JSON is not parsed or validated.
+    pub fn json(&self) -> Option<&str> {
+        self.content.as_deref().filter(|_| self.is_json())
+    }
+}
+
+/// A stylesheet, from a `<link rel="stylesheet">` or a `<style>` element, as returned by [`VDom::stylesheets`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stylesheet {
+    /// The `<link>` or `<style>` element
+    pub node: NodeHandle,
+    /// The URL of an external stylesheet, as written in the `href` attribute
+    pub href: Option<String>,
+    /// The content of a `<style>` element
+    pub content: Option<String>,
+    /// The media query of the `media` attribute, like `print`
+    pub media: Option<String>,
+    /// Whether this is an alternate stylesheet, i.e. the `rel` attribute also contains `alternate`
+    pub is_alternate: bool,
+    /// The subresource integrity metadata of an external stylesheet, like `sha384-...`
+    pub integrity: Option<String>,
+}
+
+/// Collects the scripts of a document in document order, see [`VDom::scripts`]
+pub(crate) fn scripts(dom: &VDom<'_>) -> Vec<Script> {
+    let parser = dom.parser();
+
+    elements(dom, "script")
+        .map(|(node, tag)| {
+            let mime_type = attribute(tag, "type")
+                .map(|ty| essence(&ty))
+                .filter(|ty| !ty.is_empty());
+
+            let kind = match mime_type.as_deref() {
+                None => ScriptKind::Classic,
+                Some(ty) if JAVASCRIPT_MIME_TYPES.contains(&ty) => ScriptKind::Classic,
+                Some("module") => ScriptKind::Module,
+                Some("importmap") => ScriptKind::ImportMap,
+                Some(_) => ScriptKind::Data,
+            };
+
+            let src = attribute(tag, "src");
+
+            Script {
+                node,
+                kind,
+                mime_type,
+                content: src.is_none().then(|| content(tag, parser)).flatten(),
+                src,
+                is_async: tag.attributes().contains("async"),
+                is_defer: tag.attributes().contains("defer"),
+                is_nomodule: tag.attributes().contains("nomodule"),
+                integrity: attribute(tag, "integrity"),
+                crossorigin: attribute(tag, "crossorigin"),
+            }
+        })
+        .collect()
+}
+
+/// Collects the stylesheets of a document in document order, see [`VDom::stylesheets`]
+pub(crate) fn stylesheets(dom: &VDom<'_>) -> Vec<Stylesheet> {
+    let parser = dom.parser();
+
+    dom.nodes()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, node)| {
+            let tag = node.as_tag()?;
+            let node = parser.handle(index as u32);
+            let name = tag.name().as_bytes();
+
+            let (href, content, is_alternate) = if name.eq_ignore_ascii_case(b"style") {
+                (None, content(tag, parser), false)
+            } else if name.eq_ignore_ascii_case(b"link") {
+                let rel = attribute(tag, "rel")?.to_ascii_lowercase();
+                let mut rel = rel.split_ascii_whitespace();
+
+                if !rel.clone().any(|rel| rel == "stylesheet") {
+                    return None;
+                }
+
+                let href = attribute(tag, "href").filter(|href| !href.is_empty())?;
+                (Some(href), None, rel.any(|rel| rel == "alternate"))
+            } else {
+                return None;
+            };
+
+            Some(Stylesheet {
+                node,
+                href,
+                content,
+                media: attribute(tag, "media"),
+                is_alternate,
+                integrity: attribute(tag, "integrity"),
+            })
+        })
+        .collect()
+}
+
+/// Returns the elements with the given name in document order
+fn elements<'d, 'a>(
+    dom: &'d VDom<'a>,
+    name: &'d str,
+) -> impl Iterator<Item = (NodeHandle, &'d HTMLTag<'a>)> + 'd {
+    dom.nodes()
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, node)| {
+            let tag = node.as_tag()?;
+            tag.name()
+                .as_bytes()
+                .eq_ignore_ascii_case(name.as_bytes())
+                .then(|| (dom.parser().handle(index as u32), tag))
+        })
+}
+
+fn attribute(tag: &HTMLTag<'_>, name: &str) -> Option<String> {
+    tag.attributes()
+        .get(name)
+        .map(|value| value.map_or_else(String::new, |value| value.as_utf8_str().trim().to_owned()))
+}
+
+/// Returns the source inside of an element, or `None` if it only consists of whitespace
+///
+/// The source is read up to the next end tag like a browser does, because the content of scripts and styles is not HTML
+/// and may contain something that looks like a tag, like `a <b` in a comparison, which the parser reads as a tag.
+/// Modified elements fall back to the markup of their children.
+fn content(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> Option<String> {
+    let content = if tag._modified {
+        tag.inner_html(parser).into()
+    } else {
+        let source = parser.stream.data();
+        let rest = source
+            .get(tag.span().start_tag().end()..)
+            .unwrap_or_default();
+        let name = tag.name().as_bytes();
+        let end = rest
+            .windows(name.len() + 2)
+            .position(|window| window.starts_with(b"</") && window[2..].eq_ignore_ascii_case(name))
+            .unwrap_or(rest.len());
+
+        String::from_utf8_lossy(&rest[..end])
+    };
+
+    let trimmed = content.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_owned())
+}
+
+/// Returns a MIME type in lowercase and without parameters, like `text/javascript` for `Text/JavaScript; charset=utf-8`
+fn essence(mime_type: &str) -> String {
+    mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
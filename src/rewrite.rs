@@ -0,0 +1,510 @@
+use std::io::Write;
+
+use crate::errors::RewriteError;
+use crate::queryselector::Selector;
+use crate::{Bytes, HTMLTag, Node, ParseError, Parser, ParserOptions};
+
+/// A handler that is called with every element that matches its selector, see [`Handlers::on`]
+type ElementHandler<'h> = Box<dyn FnMut(&mut Element<'_, '_>) + 'h>;
+
+/// The element handlers of [`rewrite()`], each of which is registered for a query selector
+///
+/// Handlers are called in the order in which they were registered. A handler only sees the start tag of an element
+/// and its ancestors, because the rest of the document has not been read yet when the element is rewritten.
+/// Selectors that depend on the content or the siblings of an element, like `:empty`, `:contains()`, `:nth-child()`
+/// and `:last-of-type`, can therefore not be used.
+///
+/// # Example
+/// ```
+/// use tl::rewrite::Handlers;
+///
+/// let mut links = 0;
+/// let handlers = Handlers::new()
+///     .on("a[href]", |_| links += 1)
+///     .on("script", |element| element.remove());
+///
+/// let mut output = Vec::new();
+/// tl::rewrite(r#"<a href="/">Home</a><script>track()</script>"#, handlers, &mut output).unwrap();
+///
+/// assert_eq!(output, br#"<a href="/">Home</a>"#);
+/// assert_eq!(links, 1);
+/// ```
+#[derive(Default)]
+pub struct Handlers<'h> {
+    elements: Vec<(&'h str, ElementHandler<'h>)>,
+    options: ParserOptions,
+}
+
+impl std::fmt::Debug for Handlers<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handlers")
+            .field(
+                "elements",
+                &self
+                    .elements
+                    .iter()
+                    .map(|(selector, _)| selector)
+                    .collect::<Vec<_>>(),
+            )
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<'h> Handlers<'h> {
+    /// Creates an empty set of handlers, which copies the input unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options that the input is parsed with
+    ///
+    /// Options that enable lookup tables, like [`ParserOptions::track_ids`], have no effect, because no document is built.
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Registers a handler that is called with every element that matches the given query selector
+    ///
+    /// Invalid and unsupported selectors are reported by [`rewrite()`].
+    pub fn on<F>(mut self, selector: &'h str, handler: F) -> Self
+    where
+        F: FnMut(&mut Element<'_, '_>) + 'h,
+    {
+        self.elements.push((selector, Box::new(handler)));
+        self
+    }
+}
+
+/// The changes that handlers made to an element besides its start tag
+#[derive(Debug, Default)]
+struct Edits {
+    before: String,
+    after: String,
+    prepend: String,
+    append: String,
+    inner: Option<String>,
+    replacement: Option<String>,
+    /// Whether the element is left out together with its content
+    removed: bool,
+    /// Whether the start and end tag are left out, but not the content
+    unwrapped: bool,
+}
+
+/// An element that is being rewritten, as passed to the handlers of [`Handlers::on`]
+///
+/// The start tag can be changed through [`Element::tag_mut`], and markup can be inserted around the element and its content.
+/// Inserted markup is written as is, so text in it must already be escaped.
+/// The content of void elements like `<img>` cannot be changed, so [`Element::prepend`], [`Element::append`] and
+/// [`Element::set_inner_content`] have no effect for them.
+#[derive(Debug)]
+pub struct Element<'r, 'a> {
+    tag: &'r mut HTMLTag<'a>,
+    edits: &'r mut Edits,
+}
+
+impl<'r, 'a> Element<'r, 'a> {
+    /// Returns the element as parsed from its start tag, without any children
+    #[inline]
+    pub fn tag(&self) -> &HTMLTag<'a> {
+        self.tag
+    }
+
+    /// Returns a mutable reference to the element
+    ///
+    /// The start tag is written from the name and attributes of the element if they are changed, see [`HTMLTag::attributes_mut`].
+    #[inline]
+    pub fn tag_mut(&mut self) -> &mut HTMLTag<'a> {
+        self.tag
+    }
+
+    /// Returns the value of an attribute, or `None` if the element does not have the attribute or it has no value
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        let tag: &HTMLTag<'_> = self.tag;
+        tag.attributes()
+            .get(name)
+            .flatten()
+            .map(|value| value.as_utf8_str().into_owned())
+    }
+
+    /// Sets the value of an attribute, adding it if the element does not have it
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        let name: Bytes<'a> = Bytes::from(name).to_static();
+        let value: Bytes<'a> = Bytes::from(value).to_static();
+        self.tag.attributes_mut().insert(name, Some(value));
+    }
+
+    /// Removes an attribute and returns whether the element had it
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        let tag: &HTMLTag<'_> = self.tag;
+        if !tag.attributes().contains(name) {
+            return false;
+        }
+
+        let name: Bytes<'a> = Bytes::from(name).to_static();
+        self.tag.attributes_mut().remove(name);
+        true
+    }
+
+    /// Inserts markup right before the element, after the markup inserted by earlier calls
+    pub fn before(&mut self, html: &str) {
+        self.edits.before.push_str(html);
+    }
+
+    /// Inserts markup right after the element, before the markup inserted by earlier calls
+    pub fn after(&mut self, html: &str) {
+        self.edits.after.insert_str(0, html);
+    }
+
+    /// Inserts markup at the start of the content of the element, before the markup inserted by earlier calls
+    pub fn prepend(&mut self, html: &str) {
+        self.edits.prepend.insert_str(0, html);
+    }
+
+    /// Inserts markup at the end of the content of the element, after the markup inserted by earlier calls
+    pub fn append(&mut self, html: &str) {
+        self.edits.append.push_str(html);
+    }
+
+    /// Replaces the content of the element with the given markup
+    pub fn set_inner_content(&mut self, html: &str) {
+        self.edits.inner = Some(html.to_owned());
+    }
+
+    /// Replaces the element and its content with the given markup
+    pub fn replace(&mut self, html: &str) {
+        self.edits.replacement = Some(html.to_owned());
+        self.edits.removed = true;
+    }
+
+    /// Leaves out the element and its content
+    ///
+    /// Markup inserted with [`Element::before`] and [`Element::after`] is still written.
+    pub fn remove(&mut self) {
+        self.edits.removed = true;
+    }
+
+    /// Leaves out the start and end tag of the element, but keeps its content
+    pub fn remove_and_keep_content(&mut self) {
+        self.edits.unwrapped = true;
+    }
+
+    /// Checks whether the element is left out, because of [`Element::remove`] or [`Element::replace`]
+    #[inline]
+    pub fn is_removed(&self) -> bool {
+        self.edits.removed
+    }
+}
+
+/// An element whose end tag has not been read yet
+#[derive(Debug)]
+struct Open {
+    append: String,
+    after: String,
+    /// Whether the start tag was written, so the end tag is written too
+    has_tags: bool,
+    /// The end tag that replaces the one in the source
+    end_tag: Option<String>,
+}
+
+/// Rewrites a document with the given handlers and writes the result to `sink`, without building a tree
+///
+/// This is the streaming counterpart of parsing a document, mutating it and writing it with [`VDom::to_html`](crate::VDom::to_html):
+/// the input is read one tag at a time, the handlers of matching elements are called as soon as their start tag is read,
+/// and the output is written right away. Only the currently open elements are kept in memory, so large documents can be
+/// rewritten with little memory, like in a proxy.
+///
+/// Everything that no handler changed is copied from the input exactly, including whitespace, comments and stray end tags.
+/// Elements are matched like in a parsed document, i.e. the same elements are nested in each other as with [`parse()`](crate::parse).
+///
+/// # Errors
+/// Returns an error if the selector of a handler is invalid or not supported (see [`Handlers`]),
+/// if the input is too long to be parsed, or if writing to the sink fails.
+///
+/// # Example
+/// ```
+/// use tl::rewrite::Handlers;
+///
+/// let input = r#"<p><a href="https://example.com">Example</a> <img src="ad.png"></p>"#;
+///
+/// let handlers = Handlers::new()
+///     .on("p > a[href^=https]", |element| element.set_attribute("rel", "nofollow"))
+///     .on("img", |element| element.replace("<!-- ad -->"));
+///
+/// let mut output = Vec::new();
+/// tl::rewrite(input, handlers, &mut output).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     r#"<p><a href="https://example.com" rel="nofollow">Example</a> <!-- ad --></p>"#
+/// );
+/// ```
+pub fn rewrite<W: Write>(input: &str, handlers: Handlers<'_>, sink: W) -> Result<(), RewriteError> {
+    if input.len() > u32::MAX as usize {
+        return Err(ParseError::InvalidLength.into());
+    }
+
+    let elements = handlers
+        .elements
+        .into_iter()
+        .map(|(selector, handler)| Ok((compile(selector)?, handler)))
+        .collect::<Result<Vec<_>, RewriteError>>()?;
+
+    let mut rewriter = Rewriter {
+        input: input.as_bytes(),
+        parser: Parser::new(input, handlers.options.without_tracking()),
+        elements,
+        open: Vec::new(),
+        suppressed: None,
+        sink,
+    };
+
+    rewriter.run()
+}
+
+/// Parses the selector of a handler and checks that it can be matched without knowing the content of elements
+fn compile(selector: &str) -> Result<Selector<'_>, RewriteError> {
+    let parsed = crate::parse_query_selector(selector)
+        .ok_or_else(|| RewriteError::InvalidSelector(selector.to_owned()))?;
+
+    if is_streamable(&parsed) {
+        Ok(parsed)
+    } else {
+        Err(RewriteError::UnsupportedSelector(selector.to_owned()))
+    }
+}
+
+/// Checks whether a selector only depends on an element itself and its ancestors
+fn is_streamable(selector: &Selector<'_>) -> bool {
+    match selector {
+        Selector::And(a, b)
+        | Selector::Or(a, b)
+        | Selector::Descendant(a, b)
+        | Selector::Parent(a, b) => is_streamable(a) && is_streamable(b),
+        Selector::NamespacedAttribute(_, selector) => is_streamable(selector),
+        Selector::Contains(_)
+        | Selector::NthChild(_)
+        | Selector::Empty
+        | Selector::NthOfType(_)
+        | Selector::LastOfType
+        | Selector::OnlyOfType => false,
+        _ => true,
+    }
+}
+
+struct Rewriter<'a, 'h, W> {
+    input: &'a [u8],
+    /// The parser, whose nodes are only the open elements, in the order of nesting
+    ///
+    /// Every other node is removed as soon as it is written, so the handle of an open element is its depth.
+    parser: Parser<'a>,
+    elements: Vec<(Selector<'h>, ElementHandler<'h>)>,
+    /// The open elements, in the order of nesting
+    open: Vec<Open>,
+    /// The depth of the element whose content is left out, if any
+    suppressed: Option<usize>,
+    sink: W,
+}
+
+impl<W: Write> Rewriter<'_, '_, W> {
+    fn run(&mut self) -> Result<(), RewriteError> {
+        let input = self.input;
+
+        while !self.parser.stream.is_eof() {
+            let start = self.parser.stream.idx;
+            let nodes = self.parser.tags.len();
+            let depth = self.parser.stack.len();
+
+            self.parser.parse_single();
+
+            let end = self.parser.stream.idx.min(input.len());
+            let source = &input[start..end];
+
+            if self.parser.stack.len() < depth {
+                self.close(depth - 1, Some(source))?;
+            } else if self.parser.tags.len() > nodes {
+                match &self.parser.tags[nodes] {
+                    Node::Tag(_) => self.start(nodes, source)?,
+                    _ => {
+                        self.write(source)?;
+                        self.discard(nodes);
+                    }
+                }
+            } else {
+                // tokens that do not create a node, like end tags that do not close anything
+                self.write(source)?;
+            }
+
+            // templates and stray end tags are tracked regardless of the options
+            self.parser.templates.clear();
+            self.parser.stray_end_tags.clear();
+        }
+
+        // elements without an end tag end with the document
+        while let Some(open) = self.parser.stack.pop() {
+            self.close(open.get_inner() as usize, None)?;
+        }
+
+        self.sink.flush()?;
+        Ok(())
+    }
+
+    /// Writes the start tag of an element, after calling the handlers that match it
+    fn start(&mut self, index: usize, source: &[u8]) -> Result<(), RewriteError> {
+        let is_open = self.parser.stack.len() > index;
+
+        if self.suppressed.is_some() {
+            return self.enter(index, is_open, Open::default());
+        }
+
+        let original = match &self.parser.tags[index] {
+            Node::Tag(tag) => tag,
+            _ => return self.enter(index, is_open, Open::default()),
+        };
+
+        // handlers change a copy, because the parser needs the name of the element as parsed to find its end tag,
+        // and the selectors of later elements are matched against their ancestors as parsed
+        let mut tag = None;
+        let mut edits = Edits::default();
+        for (selector, handler) in &mut self.elements {
+            if selector.matches(&self.parser.tags[index], &self.parser) {
+                handler(&mut Element {
+                    tag: tag.get_or_insert_with(|| original.clone()),
+                    edits: &mut edits,
+                });
+            }
+        }
+
+        // the end tag is only written again if the element was renamed
+        let is_renamed = tag
+            .as_ref()
+            .is_some_and(|tag| tag.name() != original.name());
+        self.write(edits.before.as_bytes())?;
+
+        let has_tags = !edits.removed && !edits.unwrapped;
+        if let Some(replacement) = edits.replacement.as_deref() {
+            self.write(replacement.as_bytes())?;
+        }
+
+        let tag = tag.filter(|tag| tag._modified);
+        if has_tags {
+            match &tag {
+                Some(tag) => {
+                    let mut start_tag = String::new();
+                    tag.write_start_tag(&mut start_tag);
+                    // self-closing tags matter in foreign content like `<svg>`
+                    if source.ends_with(b"/>") {
+                        start_tag.insert(start_tag.len() - 1, '/');
+                    }
+                    self.write(start_tag.as_bytes())?;
+                }
+                None => self.write(source)?,
+            }
+        }
+
+        if is_open && !edits.removed {
+            self.write(edits.prepend.as_bytes())?;
+            if let Some(inner) = edits.inner.as_deref() {
+                self.write(inner.as_bytes())?;
+            }
+        }
+
+        if is_open && (edits.removed || edits.inner.is_some()) {
+            self.suppressed = Some(index);
+        }
+
+        let open = Open {
+            append: if edits.removed {
+                String::new()
+            } else {
+                edits.append
+            },
+            after: edits.after,
+            has_tags,
+            end_tag: tag.filter(|_| is_renamed).map(|tag| {
+                let mut end_tag = String::new();
+                tag.write_end_tag(&mut end_tag);
+                end_tag
+            }),
+        };
+
+        self.enter(index, is_open, open)
+    }
+
+    /// Keeps track of an element whose start tag was written, which is closed right away if it is void
+    fn enter(&mut self, index: usize, is_open: bool, open: Open) -> Result<(), RewriteError> {
+        self.open.push(open);
+
+        if is_open {
+            Ok(())
+        } else {
+            self.close(index, None)
+        }
+    }
+
+    /// Writes the end of an element, with its end tag if it has one, and removes it from the open elements
+    fn close(&mut self, index: usize, end_tag: Option<&[u8]>) -> Result<(), RewriteError> {
+        let open = self.open.pop().unwrap_or_default();
+
+        if self.suppressed == Some(index) {
+            self.suppressed = None;
+        }
+
+        if self.suppressed.is_none() {
+            self.write(open.append.as_bytes())?;
+
+            if let Some(source) = end_tag.filter(|_| open.has_tags) {
+                match &open.end_tag {
+                    Some(end_tag) => self.write(end_tag.as_bytes())?,
+                    None => self.write(source)?,
+                }
+            }
+
+            self.write(open.after.as_bytes())?;
+        }
+
+        self.discard(index);
+        Ok(())
+    }
+
+    /// Removes the node at the given index, which is the last node, from the parser
+    fn discard(&mut self, index: usize) {
+        let parser = &mut self.parser;
+        parser.tags.truncate(index);
+
+        match parser.stack.last() {
+            Some(parent) => {
+                if let Some(parent) = parser.tags[parent.get_inner() as usize].as_tag_mut() {
+                    let len = parent._children.len();
+                    if len > 0 {
+                        parent._children.remove(len - 1);
+                    }
+                }
+            }
+            None => {
+                parser.ast.pop();
+            }
+        }
+    }
+
+    /// Writes to the sink, unless the output is left out because of a removed element
+    fn write(&mut self, bytes: &[u8]) -> Result<(), RewriteError> {
+        if self.suppressed.is_none() && !bytes.is_empty() {
+            self.sink.write_all(bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Open {
+    fn default() -> Self {
+        Self {
+            append: String::new(),
+            after: String::new(),
+            has_tags: true,
+            end_tag: None,
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// Writes a document, copying the source of unmodified nodes and only regenerating modified ones
+pub(crate) fn to_html(dom: &VDom<'_>) -> String {
+    let parser = dom.parser();
+    let source = parser.stream.data();
+
+    // the source of a document whose nodes were never borrowed mutably also contains the markup outside of any node,
+    // like stray end tags or unterminated markup at the end of the input
+    if !parser.modified {
+        return String::from_utf8_lossy(source).into_owned();
+    }
+
+    let writer = Writer {
+        parser,
+        source,
+        pristine: pristine_subtrees(parser),
+    };
+
+    let mut out = Vec::new();
+    let top = dom.children();
+
+    // otherwise such markup is only kept if the whole document is unmodified
+    if is_partition(top, 0..parser.tags.len() as u32, parser)
+        && top.iter().all(|h| writer.pristine[h.get_inner() as usize])
+    {
+        out.extend_from_slice(writer.source);
+    } else {
+        for &handle in top {
+            writer.write_node(handle, &mut out);
+        }
+    }
+
+    String::from_utf8(out)
+        .unwrap_or_else(|error| String::from_utf8_lossy(error.as_bytes()).into_owned())
+}
+
+struct Writer<'p, 'a> {
+    parser: &'p Parser<'a>,
+    source: &'p [u8],
+    /// Whether the subtree of every node is unmodified, by handle
+    pristine: Vec<bool>,
+}
+
+impl Writer<'_, '_> {
+    fn write_node(&self, handle: NodeHandle, out: &mut Vec<u8>) {
+        match handle.get(self.parser) {
+            Some(Node::Tag(tag)) => self.write_tag(handle, tag, out),
+            Some(node) => out.extend_from_slice(node.outer_html(self.parser).as_bytes()),
+            None => {}
+        }
+    }
+
+    fn write_tag(&self, handle: NodeHandle, tag: &HTMLTag<'_>, out: &mut Vec<u8>) {
+        let span = tag._span;
+
+        if self.pristine[handle.get_inner() as usize] {
+            if let Some(end) = source_end(tag, self.parser) {
+                out.extend_from_slice(&self.source[span.start_tag.start()..end]);
+                return;
+            }
+        }
+
+        let start_tag_pristine = is_start_tag_pristine(tag, self.parser);
+
+        if start_tag_pristine {
+            out.extend_from_slice(&self.source[span.start_tag.range()]);
+        } else {
+            let mut start_tag = String::new();
+            tag.write_start_tag(&mut start_tag);
+            out.extend_from_slice(start_tag.as_bytes());
+
+            if tag.is_void_element() {
+                return;
+            }
+        }
+
+        for &child in tag.children().top().iter() {
+            self.write_node(child, out);
+        }
+
+        match span.end_tag {
+            Some(end_tag) if start_tag_pristine => {
+                out.extend_from_slice(&self.source[end_tag.range()]);
+            }
+            // like in the source, an element without an end tag is closed implicitly
+            None if start_tag_pristine => {}
+            _ => {
+                let mut end_tag = String::new();
+                tag.write_end_tag(&mut end_tag);
+                out.extend_from_slice(end_tag.as_bytes());
+            }
+        }
+    }
+}
+
+/// Returns the markup of an element or of its children, see [`HTMLTag::to_html`] and [`HTMLTag::to_inner_html`]
+///
+/// The markup of an unmodified element is borrowed from the source.
+pub(crate) fn element_html<'p>(
+    tag: &'p HTMLTag<'_>,
+    parser: &'p Parser<'_>,
+    inner: bool,
+) -> Cow<'p, str> {
+    let Some(handle) = parser.handle_of(tag) else {
+        // the element is not part of this document, so nothing of it can be copied from the source
+        return match inner {
+            true => Cow::Owned(tag.inner_html(parser)),
+            false => Cow::Owned(tag.outer_html(parser)),
+        };
+    };
+
+    let source = parser.stream.data();
+    let span = tag._span;
+
+    if is_subtree_pristine(handle, parser) {
+        if let Some(end) = source_end(tag, parser) {
+            let range = match inner {
+                true => span.start_tag.end()..span.end_tag.map_or(end, |end_tag| end_tag.start()),
+                false => span.start_tag.start()..end,
+            };
+
+            return String::from_utf8_lossy(&source[range]);
+        }
+    }
+
+    let writer = Writer {
+        parser,
+        source,
+        pristine: pristine_subtrees(parser),
+    };
+
+    let mut out = Vec::new();
+    if inner {
+        for &child in tag.children().top().iter() {
+            writer.write_node(child, &mut out);
+        }
+    } else {
+        writer.write_tag(handle, tag, &mut out);
+    }
+
+    Cow::Owned(
+        String::from_utf8(out)
+            .unwrap_or_else(|error| String::from_utf8_lossy(error.as_bytes()).into_owned()),
+    )
+}
+
+/// Returns the end of an element in the source, which is the end of its end tag,
+/// or for an element without an end tag the end of its last descendant
+fn source_end(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> Option<usize> {
+    let span = tag._span;
+
+    match span.end_tag {
+        Some(end_tag) => Some(end_tag.end()),
+        None if tag._descendants.is_empty() => Some(span.start_tag.end()),
+        // the last descendant has no descendants itself
+        None => parser
+            .handle(tag._descendants.end - 1)
+            .get(parser)
+            .and_then(|last| last.byte_span(parser))
+            .map(|last| last.end()),
+    }
+}
+
+/// Checks whether the start tag of an element in the source is still up to date
+fn is_start_tag_pristine(tag: &HTMLTag<'_>, parser: &Parser<'_>) -> bool {
+    let source = parser.stream.data().as_ptr_range();
+
+    // tags that were moved from another document point into a different source
+    !tag._modified
+        && tag
+            ._raw
+            .as_bytes_borrowed()
+            .is_some_and(|raw| source.contains(&raw.as_ptr()))
+}
+
+/// Checks whether the given handles are exactly the nodes that were parsed as the children of a node with the given
+/// descendants, in their original order
+fn is_partition(children: &[NodeHandle], descendants: Range<u32>, parser: &Parser<'_>) -> bool {
+    let mut next = descendants.start;
+
+    for &child in children {
+        if child.get_inner() != next {
+            return false;
+        }
+
+        next = match child.get(parser) {
+            Some(Node::Tag(tag)) => tag._descendants.end,
+            Some(_) => next + 1,
+            None => return false,
+        };
+    }
+
+    next == descendants.end
+}
+
+/// Checks whether a node itself is unmodified, i.e. its start tag and the list of its children for elements
+fn is_node_pristine(index: usize, node: &Node<'_>, parser: &Parser<'_>) -> bool {
+    match node {
+        Node::Tag(tag) => {
+            is_start_tag_pristine(tag, parser)
+                && tag._descendants.start as usize == index + 1
+                && is_partition(tag._children.as_slice(), tag._descendants.clone(), parser)
+        }
+        // text that was replaced does not point into the source anymore
+        node => node.byte_span(parser).is_some(),
+    }
+}
+
+/// Checks whether neither a node nor any of its descendants were modified
+///
+/// If the children of every element are exactly the nodes that were parsed as its children, the subtree consists of
+/// the contiguous range of handles of the node and its descendants, so they can be checked one after another.
+pub(crate) fn is_subtree_pristine(handle: NodeHandle, parser: &Parser<'_>) -> bool {
+    let start = handle.get_inner() as usize;
+    let end = match handle.get(parser) {
+        Some(Node::Tag(tag)) => tag._descendants.end as usize,
+        Some(_) => start + 1,
+        None => return false,
+    };
+
+    parser.tags[start..end]
+        .iter()
+        .enumerate()
+        .all(|(offset, node)| is_node_pristine(start + offset, node, parser))
+}
+
+/// Determines for every node whether neither it nor any of its descendants were modified
+///
+/// Nodes are visited in reverse, so the children of an element are always known before the element itself.
+fn pristine_subtrees(parser: &Parser<'_>) -> Vec<bool> {
+    let mut pristine = vec![false; parser.tags.len()];
+
+    for (index, node) in parser.tags.iter().enumerate().rev() {
+        pristine[index] = is_node_pristine(index, node, parser)
+            && node.as_tag().is_none_or(|tag| {
+                tag._children
+                    .as_slice()
+                    .iter()
+                    .all(|c| pristine[c.get_inner() as usize])
+            });
+    }
+
+    pristine
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{HTMLTag, ParserOptions, VDom};
+
+    fn roundtrip(input: &str) -> String {
+        crate::parse(input, ParserOptions::default())
+            .unwrap()
+            .to_html()
+    }
+
+    const INPUT: &str = "<!doctype html>\n<div   class=a  data-x='1'><p>x &amp; y</span></p><br/><!-- c --><ul><li>a</li><li>b</li></ul></div>\n<p>unclosed";
+
+    /// Replaces the text of the first paragraph of [`INPUT`] with `z`
+    fn replace_text(dom: &mut VDom<'_>) {
+        let p = dom.query_selector("p").unwrap().next().unwrap();
+        let text = p.get(dom.parser()).unwrap().children().unwrap().top()[0];
+        let text = text.get_mut(dom.parser_mut()).unwrap();
+        text.as_raw_mut().unwrap().set("z").unwrap();
+    }
+
+    /// Removes an attribute of the `<div>` of [`INPUT`] and moves its first child to the end
+    fn modify_div(dom: &mut VDom<'_>) {
+        let div = dom.query_selector("div").unwrap().next().unwrap();
+        let div = div.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+        div.attributes_mut().remove("data-x");
+        let mut children = div.children_mut();
+        let p = children.top_mut().remove(0);
+        children.top_mut().push(p);
+    }
+
+    #[test]
+    fn unmodified_document_is_the_input() {
+        assert_eq!(roundtrip(INPUT), INPUT);
+    }
+
+    #[test]
+    fn replaced_text_keeps_surrounding_source() {
+        let mut dom = crate::parse(INPUT, ParserOptions::default()).unwrap();
+        replace_text(&mut dom);
+
+        assert_eq!(
+            dom.to_html(),
+            "<!doctype html>\n<div   class=a  data-x='1'><p>z</p><br/><!-- c --><ul><li>a</li><li>b</li></ul></div>\n<p>unclosed"
+        );
+    }
+
+    #[test]
+    fn modified_start_tags_are_regenerated() {
+        let mut dom = crate::parse(INPUT, ParserOptions::default()).unwrap();
+        replace_text(&mut dom);
+        modify_div(&mut dom);
+
+        assert_eq!(
+            dom.to_html(),
+            "<!doctype html>\n<div class=\"a\"><br/><!-- c --><ul><li>a</li><li>b</li></ul><p>z</p></div>\n<p>unclosed"
+        );
+    }
+
+    #[test]
+    fn modified_cache_is_regenerated() {
+        let mut dom = crate::parse(INPUT, ParserOptions::default()).unwrap();
+        replace_text(&mut dom);
+        modify_div(&mut dom);
+
+        let cache = crate::cache::serialize(&dom);
+        assert_eq!(crate::cache::load(&cache).unwrap().to_html(), dom.to_html());
+    }
+
+    #[test]
+    fn unterminated_comment_is_kept() {
+        assert_eq!(roundtrip("<p>a</p><!-- x"), "<p>a</p><!-- x");
+    }
+
+    #[test]
+    fn unterminated_tag_is_kept() {
+        assert_eq!(roundtrip("<ul><a b=</ul>y<!--"), "<ul><a b=</ul>y<!--");
+    }
+
+    #[test]
+    fn unmodified_cache_is_copied() {
+        let input = "<p>a</p><!-- x";
+        let dom = crate::parse(input, ParserOptions::default()).unwrap();
+        let cache = crate::cache::serialize(&dom);
+
+        assert_eq!(crate::cache::load(&cache).unwrap().to_html(), input);
+    }
+
+    #[test]
+    fn modified_document_is_regenerated() {
+        let mut dom = crate::parse("<p>a</p><!-- x", ParserOptions::default()).unwrap();
+        dom.nodes_mut()[1].as_raw_mut().unwrap().set("b").unwrap();
+
+        assert_eq!(dom.to_html(), "<p>b</p>");
+    }
+
+    const ELEMENTS: &str = r#"<div id="root"><p class='a'>x &amp; y</p><p>z<br></p></div><span id="other">o<b>unclosed"#;
+
+    fn element<'a>(dom: &'a VDom<'_>, id: &str) -> &'a HTMLTag<'a> {
+        let handle = dom.get_element_by_id(id).unwrap();
+        handle.get(dom.parser()).unwrap().as_tag().unwrap()
+    }
+
+    /// Adds a class to the `<br>` of [`ELEMENTS`]
+    fn modify_br(dom: &mut VDom<'_>) {
+        let br = dom.query_selector("br").unwrap().next().unwrap();
+        let br = br.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+        br.attributes_mut().insert("class", Some("b"));
+    }
+
+    #[test]
+    fn unmodified_element_is_borrowed() {
+        let dom = crate::parse(ELEMENTS, ParserOptions::default().track_ids()).unwrap();
+        let root = element(&dom, "root");
+
+        assert!(!root.is_modified(dom.parser()));
+        assert!(matches!(
+            root.to_html(dom.parser()),
+            Cow::Borrowed(r#"<div id="root"><p class='a'>x &amp; y</p><p>z<br></p></div>"#)
+        ));
+        assert!(matches!(
+            root.to_inner_html(dom.parser()),
+            Cow::Borrowed("<p class='a'>x &amp; y</p><p>z<br></p>")
+        ));
+    }
+
+    #[test]
+    fn element_without_end_tag_ends_with_last_descendant() {
+        let dom = crate::parse(ELEMENTS, ParserOptions::default().track_ids()).unwrap();
+        let other = element(&dom, "other");
+
+        assert_eq!(
+            other.to_html(dom.parser()),
+            "<span id=\"other\">o<b>unclosed"
+        );
+        assert_eq!(other.to_inner_html(dom.parser()), "o<b>unclosed");
+    }
+
+    #[test]
+    fn modified_descendant_is_regenerated() {
+        let mut dom = crate::parse(ELEMENTS, ParserOptions::default().track_ids()).unwrap();
+        modify_br(&mut dom);
+        let root = element(&dom, "root");
+
+        assert!(root.is_modified(dom.parser()));
+        assert_eq!(
+            root.to_html(dom.parser()),
+            r#"<div id="root"><p class='a'>x &amp; y</p><p>z<br class="b"></p></div>"#
+        );
+        assert_eq!(
+            root.to_inner_html(dom.parser()),
+            r#"<p class='a'>x &amp; y</p><p>z<br class="b"></p>"#
+        );
+    }
+
+    #[test]
+    fn unmodified_sibling_is_borrowed() {
+        let mut dom = crate::parse(ELEMENTS, ParserOptions::default().track_ids()).unwrap();
+        modify_br(&mut dom);
+        let other = element(&dom, "other");
+
+        assert!(!other.is_modified(dom.parser()));
+        assert!(matches!(other.to_html(dom.parser()), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn moved_children_modify_parent() {
+        let mut dom =
+            crate::parse("<ul><li>1</li><li>2</li></ul>", ParserOptions::default()).unwrap();
+        let ul = dom.nodes_mut()[0].as_tag_mut().unwrap();
+        let mut children = ul.children_mut();
+        let top = children.top_mut();
+        let first = top.remove(0);
+        top.push(first);
+
+        let ul = dom.nodes()[0].as_tag().unwrap();
+        assert!(ul.is_modified(dom.parser()));
+        assert_eq!(ul.to_inner_html(dom.parser()), "<li>2</li><li>1</li>");
+    }
+
+    #[test]
+    fn clone_is_not_part_of_document() {
+        let dom = crate::parse("<ul><li>1</li></ul>", ParserOptions::default()).unwrap();
+        let ul = dom.nodes()[0].as_tag().unwrap();
+        let clone = ul.clone();
+
+        assert!(clone.is_modified(dom.parser()));
+        assert_eq!(clone.to_html(dom.parser()), ul.outer_html(dom.parser()));
+    }
+}
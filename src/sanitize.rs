@@ -0,0 +1,300 @@
+use crate::text::{is_one_of, INVISIBLE_ELEMENTS};
+use crate::{Edge, HTMLTag, Node, Traverse, VDom};
+
+/// Attributes whose value is a URL and is checked against the allowed URL schemes
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "formaction",
+    "href",
+    "longdesc",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+/// Tags that [`SanitizePolicy::basic`] allows
+const BASIC_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// A policy that decides which parts of a document [`sanitize`] keeps
+///
+/// Everything that is not explicitly allowed is removed:
+/// - Tags that are not allowed are removed, but their content is kept. Tags whose content is removed as well can be set
+///   with [`SanitizePolicy::remove_content`], and default to `<script>`, `<style>` and `<template>`.
+/// - Attributes that are not allowed for their tag are removed.
+/// - URL attributes like `href` and `src` are removed if their URL has a scheme that is not allowed. Relative URLs are always allowed.
+/// - Comments are always removed.
+///
+/// Tag and attribute names as well as URL schemes are compared case insensitively.
+///
+/// # Example
+/// ```
+/// use tl::sanitize::SanitizePolicy;
+///
+/// let policy = SanitizePolicy::new()
+///     .allow_tags(&["p", "a"])
+///     .allow_attributes("a", &["href"])
+///     .allow_url_schemes(&["https"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SanitizePolicy<'p> {
+    tags: Vec<&'p str>,
+    attributes: Vec<(&'p str, &'p str)>,
+    url_schemes: Vec<&'p str>,
+    remove_content: Vec<&'p str>,
+}
+
+impl Default for SanitizePolicy<'_> {
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            attributes: Vec::new(),
+            url_schemes: Vec::new(),
+            remove_content: INVISIBLE_ELEMENTS.to_vec(),
+        }
+    }
+}
+
+impl<'p> SanitizePolicy<'p> {
+    /// Creates a new [`SanitizePolicy`] that does not allow any tags, so only text is kept
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`SanitizePolicy`] that allows common formatting tags, links and images
+    ///
+    /// Links may have a `href` and `title`, images may have a `src`, `alt`, `title`, `width` and `height`,
+    /// and table cells may have a `colspan` and `rowspan`. Allowed URL schemes are `http`, `https` and `mailto`.
+    pub fn basic() -> Self {
+        Self::new()
+            .allow_tags(BASIC_TAGS)
+            .allow_attributes("a", &["href", "title"])
+            .allow_attributes("img", &["src", "alt", "title", "width", "height"])
+            .allow_attributes("td", &["colspan", "rowspan"])
+            .allow_attributes("th", &["colspan", "rowspan"])
+            .allow_url_schemes(&["http", "https", "mailto"])
+    }
+
+    /// Allows the given tags
+    ///
+    /// Calling this more than once extends the list.
+    pub fn allow_tags(mut self, names: &[&'p str]) -> Self {
+        self.tags.extend_from_slice(names);
+        self
+    }
+
+    /// Allows the given attributes on a tag, or on all tags if `tag` is `"*"`
+    ///
+    /// Calling this more than once extends the list.
+    pub fn allow_attributes(mut self, tag: &'p str, names: &[&'p str]) -> Self {
+        self.attributes
+            .extend(names.iter().map(|&name| (tag, name)));
+        self
+    }
+
+    /// Allows URLs with the given schemes, like `https` or `mailto`
+    ///
+    /// Calling this more than once extends the list.
+    pub fn allow_url_schemes(mut self, schemes: &[&'p str]) -> Self {
+        self.url_schemes.extend_from_slice(schemes);
+        self
+    }
+
+    /// Removes the given tags including their content
+    ///
+    /// This takes precedence over [`SanitizePolicy::allow_tags`]. Calling this more than once extends the list.
+    pub fn remove_content(mut self, names: &[&'p str]) -> Self {
+        self.remove_content.extend_from_slice(names);
+        self
+    }
+
+    /// Checks whether a tag with the given name is kept according to this policy
+    pub fn is_tag_allowed(&self, name: &[u8]) -> bool {
+        is_one_of(name, &self.tags) && !is_one_of(name, &self.remove_content)
+    }
+
+    /// Checks whether an attribute with the given name and value is kept on a tag according to this policy
+    pub fn is_attribute_allowed(&self, tag: &[u8], name: &[u8], value: Option<&[u8]>) -> bool {
+        let allowed = self.attributes.iter().any(|&(t, n)| {
+            (t == "*" || t.as_bytes().eq_ignore_ascii_case(tag))
+                && n.as_bytes().eq_ignore_ascii_case(name)
+        });
+
+        match value {
+            Some(value) if allowed && is_one_of(name, URL_ATTRIBUTES) => self.is_url_allowed(value),
+            _ => allowed,
+        }
+    }
+
+    /// Checks whether the given URL is relative or has an allowed scheme
+    ///
+    /// URLs whose scheme contains character references are rejected, because browsers decode them before looking at the scheme.
+    pub fn is_url_allowed(&self, url: &[u8]) -> bool {
+        // browsers ignore whitespace and control characters anywhere in a URL
+        let url = url
+            .iter()
+            .copied()
+            .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+            .collect::<Vec<_>>();
+
+        let head = url
+            .split(|&c| matches!(c, b'/' | b'?' | b'#'))
+            .next()
+            .unwrap_or_default();
+
+        if head.contains(&b'&') {
+            return false;
+        }
+
+        match head.iter().position(|&c| c == b':') {
+            Some(idx) => is_one_of(&head[..idx], &self.url_schemes),
+            None => true,
+        }
+    }
+
+    fn removes_content(&self, name: &[u8]) -> bool {
+        is_one_of(name, &self.remove_content)
+    }
+}
+
+/// Serializes the given DOM to HTML, keeping only the tags and attributes that the policy allows
+///
+/// The output is always well-formed: text is escaped, attribute values are quoted and every kept tag is closed.
+/// Attributes are written in alphabetical order.
+/// The DOM itself is not modified.
+///
+/// # Example
+/// ```
+/// use tl::sanitize::SanitizePolicy;
+///
+/// let dom = tl::parse(
+///     r#"<p onclick="x()">Hi <a href="javascript:alert(1)">there</a><script>alert(2)</script><a href="/home" title="Home">home</a></p>"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     tl::sanitize(&dom, &SanitizePolicy::basic()),
+///     r#"<p>Hi <a>there</a><a href="/home" title="Home">home</a></p>"#
+/// );
+/// ```
+pub fn sanitize(dom: &VDom<'_>, policy: &SanitizePolicy<'_>) -> String {
+    let parser = dom.parser();
+    let mut out = String::new();
+
+    let mut traverse = Traverse::new(parser, dom.children());
+    while let Some(edge) = traverse.next() {
+        let Some(node) = edge.handle().get(parser) else {
+            continue;
+        };
+
+        match (edge, node) {
+            (Edge::Open(_), Node::Tag(tag)) => {
+                let name = tag.name().as_bytes();
+
+                if policy.removes_content(name) {
+                    traverse.skip_children();
+                } else if policy.is_tag_allowed(name) {
+                    write_start_tag(&mut out, tag, policy);
+                }
+            }
+            (Edge::Close(_), Node::Tag(tag)) => {
+                let name = tag.name().as_bytes();
+
+                if policy.is_tag_allowed(name) && !tag.is_void_element() {
+                    out.push_str("</");
+                    out.push_str(&tag.name().as_utf8_str().to_ascii_lowercase());
+                    out.push('>');
+                }
+            }
+            (Edge::Open(_), Node::Raw(text)) => escape_into(&mut out, &text.as_utf8_str(), false),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn write_start_tag(out: &mut String, tag: &HTMLTag<'_>, policy: &SanitizePolicy<'_>) {
+    let name = tag.name().as_bytes();
+
+    out.push('<');
+    out.push_str(&tag.name().as_utf8_str().to_ascii_lowercase());
+
+    // attributes are stored in a hash map, so sort them to produce the same output every time
+    let mut attributes = tag
+        .attributes()
+        .iter()
+        .filter(|(key, value)| {
+            policy.is_attribute_allowed(name, key.as_bytes(), value.as_deref().map(str::as_bytes))
+        })
+        .map(|(key, value)| (key.to_ascii_lowercase(), value))
+        .collect::<Vec<_>>();
+    attributes.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (key, value) in attributes {
+        out.push(' ');
+        out.push_str(&key);
+
+        if let Some(value) = value {
+            out.push_str("=\"");
+            escape_into(out, &value, true);
+            out.push('"');
+        }
+    }
+
+    out.push('>');
+}
+
+/// Escapes characters that could start markup, keeping existing character references intact
+fn escape_into(out: &mut String, text: &str, attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
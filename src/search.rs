@@ -0,0 +1,239 @@
+use std::ops::Range;
+#[cfg(feature = "regex")]
+use std::sync::Arc;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use crate::{Edge, Node, NodeHandle, VDom};
+
+/// A pattern that the text of text nodes is searched for, see [`VDom::find_all_text_nodes`]
+///
+/// It is implemented for strings, which match literally, and for `regex::Regex` if the `regex` feature is enabled.
+pub trait TextPattern {
+    /// Returns the byte ranges of all non-overlapping matches in the given text, in order
+    fn find_in(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+impl TextPattern for str {
+    fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        text.match_indices(self)
+            .map(|(start, matched)| start..start + matched.len())
+            .collect()
+    }
+}
+
+impl TextPattern for String {
+    fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        self.as_str().find_in(text)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl TextPattern for Regex {
+    fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        self.find_iter(text).map(|m| m.range()).collect()
+    }
+}
+
+/// A match of a [`TextPattern`] in a text node, as returned by [`VDom::find_all_text_nodes`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextMatch {
+    node: NodeHandle,
+    element: Option<NodeHandle>,
+    range: Range<usize>,
+}
+
+impl TextMatch {
+    /// Returns the handle of the text node that contains the match
+    #[inline]
+    pub fn node(&self) -> NodeHandle {
+        self.node
+    }
+
+    /// Returns the handle of the element that contains the text node, or `None` if the text node is one of the topmost nodes
+    #[inline]
+    pub fn element(&self) -> Option<NodeHandle> {
+        self.element
+    }
+
+    /// Returns the byte range of the match in the text of the text node
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+}
+
+/// Searches the text nodes of a document for a pattern, see [`VDom::find_all_text_nodes`]
+pub(crate) fn find_text_nodes<P>(dom: &VDom<'_>, pattern: &P) -> Vec<TextMatch>
+where
+    P: TextPattern + ?Sized,
+{
+    let parser = dom.parser();
+    let mut matches = Vec::new();
+
+    for edge in dom.traverse() {
+        let handle = edge.handle();
+        let node = match (edge, handle.get(parser)) {
+            (Edge::Open(_), Some(node @ (Node::Raw(_) | Node::CData(_)))) => node,
+            _ => continue,
+        };
+
+        let element = parser.parent_of(handle);
+        let ranges = pattern.find_in(&node.inner_text(parser));
+
+        matches.extend(
+            ranges
+                .into_iter()
+                .filter(|range| !range.is_empty())
+                .map(|range| TextMatch {
+                    node: handle,
+                    element,
+                    range,
+                }),
+        );
+    }
+
+    matches
+}
+
+#[cfg(feature = "regex")]
+/// A match of a regular expression in the text or an attribute value of a node,
+/// as returned by [`VDom::find_text`](crate::VDom::find_text) and [`VDom::find_attribute`](crate::VDom::find_attribute)
+///
+/// The captured groups are copied, so matches can be kept around independently of the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexMatch {
+    node: NodeHandle,
+    range: Range<usize>,
+    groups: Vec<Option<String>>,
+    names: Arc<[Option<String>]>,
+}
+
+#[cfg(feature = "regex")]
+impl RegexMatch {
+    /// Returns the handle of the text node or element that matched
+    #[inline]
+    pub fn node(&self) -> NodeHandle {
+        self.node
+    }
+
+    /// Returns the byte range of the whole match in the text or attribute value
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the whole match
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.groups[0].as_deref().unwrap_or_default()
+    }
+
+    /// Returns the text of the capture group with the given index, where 0 is the whole match
+    ///
+    /// Returns `None` if the group did not participate in the match.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.groups.get(index)?.as_deref()
+    }
+
+    /// Returns the text of the named capture group, or `None` if there is no such group or it did not participate in the match
+    pub fn name(&self, name: &str) -> Option<&str> {
+        let index = self.names.iter().position(|n| n.as_deref() == Some(name))?;
+        self.get(index)
+    }
+
+    /// Returns the number of capture groups, including the whole match
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Always returns `false`, because the whole match is a group
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "regex")]
+/// Searches the text nodes of a document, see [`VDom::find_text`]
+pub(crate) fn find_text(dom: &VDom<'_>, regex: &Regex) -> Vec<RegexMatch> {
+    let parser = dom.parser();
+    let names = capture_names(regex);
+    let mut matches = Vec::new();
+
+    for edge in dom.traverse() {
+        let handle = edge.handle();
+        let node = match (edge, handle.get(parser)) {
+            (Edge::Open(_), Some(node @ (Node::Raw(_) | Node::CData(_)))) => node,
+            _ => continue,
+        };
+
+        push_matches(
+            &mut matches,
+            handle,
+            &node.inner_text(parser),
+            regex,
+            &names,
+        );
+    }
+
+    matches
+}
+
+#[cfg(feature = "regex")]
+/// Searches the values of an attribute of all elements of a document, see [`VDom::find_attribute`]
+pub(crate) fn find_attribute(dom: &VDom<'_>, name: &str, regex: &Regex) -> Vec<RegexMatch> {
+    let parser = dom.parser();
+    let names = capture_names(regex);
+    let mut matches = Vec::new();
+
+    for edge in dom.traverse() {
+        let Edge::Open(handle) = edge else {
+            continue;
+        };
+
+        let value = handle
+            .get(parser)
+            .and_then(Node::as_tag)
+            .and_then(|tag| tag.attributes().get(name).flatten());
+
+        if let Some(value) = value {
+            push_matches(&mut matches, handle, &value.as_utf8_str(), regex, &names);
+        }
+    }
+
+    matches
+}
+
+#[cfg(feature = "regex")]
+fn capture_names(regex: &Regex) -> Arc<[Option<String>]> {
+    regex
+        .capture_names()
+        .map(|name| name.map(str::to_owned))
+        .collect()
+}
+
+#[cfg(feature = "regex")]
+fn push_matches(
+    matches: &mut Vec<RegexMatch>,
+    node: NodeHandle,
+    text: &str,
+    regex: &Regex,
+    names: &Arc<[Option<String>]>,
+) {
+    for captures in regex.captures_iter(text) {
+        let whole = captures.get(0).expect("group 0 is always part of a match");
+
+        matches.push(RegexMatch {
+            node,
+            range: whole.range(),
+            groups: captures
+                .iter()
+                .map(|group| group.map(|g| g.as_str().to_owned()))
+                .collect(),
+            names: Arc::clone(names),
+        });
+    }
+}
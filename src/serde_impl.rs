@@ -0,0 +1,158 @@
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::{Attributes, Bytes, HTMLTag, Node, NodeHandle, Parser, StableId, VDom};
+
+impl Serialize for Bytes<'_> {
+    /// Serializes the bytes as a string, replacing invalid UTF-8 sequences
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.as_utf8_str())
+    }
+}
+
+impl Serialize for NodeHandle {
+    /// Serializes the handle as its index into [`VDom::nodes`]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.get_inner())
+    }
+}
+
+impl Serialize for StableId {
+    /// Serializes the identifier as its numeric value
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.get())
+    }
+}
+
+impl Serialize for Attributes<'_> {
+    /// Serializes the attributes as a map from name to value, or `null` for attributes without a value.
+    /// Names are sorted so that the output does not depend on the order of the underlying hash map.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut attributes = self.iter().collect::<Vec<_>>();
+        attributes.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut map = serializer.serialize_map(Some(attributes.len()))?;
+        for (key, value) in &attributes {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for HTMLTag<'_> {
+    /// Serializes the tag as `{"tag": .., "attrs": {..}, "children": [..]}`, where the children are
+    /// the indices of the child nodes into [`VDom::nodes`]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("tag", self.name())?;
+        map.serialize_entry("attrs", self.attributes())?;
+        map.serialize_entry("children", self.children().top().as_slice())?;
+        map.end()
+    }
+}
+
+impl Serialize for Node<'_> {
+    /// Serializes a tag like [`HTMLTag`], text as `{"text": ..}`, a comment as `{"comment": ..}`,
+    /// a processing instruction as `{"processing_instruction": ..}`, a CDATA section as `{"cdata": ..}`
+    /// and a doctype as `{"doctype": ..}` with the whole declaration
+    ///
+    /// Children are referred to by their indices into [`VDom::nodes`], so serializing `dom.nodes()` produces a flat list.
+    /// Use [`VDom`] or [`Node::serialize_with`] for a nested tree.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Node::Tag(tag) => tag.serialize(serializer),
+            Node::Raw(text) => leaf(serializer, "text", text),
+            Node::Comment(comment) => leaf(serializer, "comment", comment),
+            Node::ProcessingInstruction(instruction) => {
+                leaf(serializer, "processing_instruction", instruction)
+            }
+            Node::CData(section) => leaf(serializer, "cdata", section),
+            Node::Doctype(doctype) => leaf(serializer, "doctype", doctype.raw()),
+        }
+    }
+}
+
+impl Serialize for VDom<'_> {
+    /// Serializes the document as an array of its top level nodes, where every tag contains its child nodes
+    /// as `{"tag": .., "attrs": {..}, "children": [..]}`
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p class="x">Hello <b>world</b></p><!-- end -->"#, Default::default()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     serde_json::to_string(&dom).unwrap(),
+    ///     concat!(
+    ///         r#"[{"tag":"p","attrs":{"class":"x"},"children":["#,
+    ///         r#"{"text":"Hello "},{"tag":"b","attrs":{},"children":[{"text":"world"}]}]},"#,
+    ///         r#"{"comment":"<!-- end -->"}]"#
+    ///     )
+    /// );
+    /// ```
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Tree {
+            handles: self.children(),
+            parser: self.parser(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Returns a value that serializes this node and all of its subnodes as a nested tree, in the same format as [`VDom`]
+    pub fn serialize_with<'p>(&'p self, parser: &'p Parser<'a>) -> impl Serialize + 'p {
+        TreeNode { node: self, parser }
+    }
+}
+
+fn leaf<S: Serializer>(serializer: S, key: &str, value: &Bytes<'_>) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(key, value)?;
+    map.end()
+}
+
+/// A list of nodes that serializes their subnodes recursively
+struct Tree<'p, 'a> {
+    handles: &'p [NodeHandle],
+    parser: &'p Parser<'a>,
+}
+
+impl Serialize for Tree<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.handles.len()))?;
+        for handle in self.handles {
+            if let Some(node) = handle.get(self.parser) {
+                seq.serialize_element(&TreeNode {
+                    node,
+                    parser: self.parser,
+                })?;
+            }
+        }
+        seq.end()
+    }
+}
+
+/// A node that serializes its subnodes recursively
+struct TreeNode<'p, 'a> {
+    node: &'p Node<'a>,
+    parser: &'p Parser<'a>,
+}
+
+impl Serialize for TreeNode<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Node::Tag(tag) = self.node else {
+            return self.node.serialize(serializer);
+        };
+
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("tag", tag.name())?;
+        map.serialize_entry("attrs", tag.attributes())?;
+        map.serialize_entry(
+            "children",
+            &Tree {
+                handles: tag.children().top().as_slice(),
+                parser: self.parser,
+            },
+        )?;
+        map.end()
+    }
+}
@@ -9,23 +9,91 @@ pub fn is_closing(needle: u8) -> bool {
     eq1 | eq2
 }
 
+/// Returns a bitmask of the bytes in a 16 byte `chunk` that are equal to any byte in `needle`,
+/// where bit `i` is set if `chunk[i]` matches
+///
+/// SSE2 and NEON are part of the baseline of x86_64 and aarch64, so these are used without runtime detection.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline(always)]
+fn mask16<const N: usize>(chunk: &[u8], needle: [u8; N]) -> u16 {
+    use std::arch::x86_64::*;
+
+    debug_assert_eq!(chunk.len(), 16);
+
+    // SAFETY: SSE2 is enabled, and `chunk` is 16 bytes long, which `_mm_loadu_si128` reads without any alignment requirements
+    unsafe {
+        let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+        let mut eq = _mm_setzero_si128();
+
+        for n in needle {
+            eq = _mm_or_si128(eq, _mm_cmpeq_epi8(bytes, _mm_set1_epi8(n as i8)));
+        }
+
+        _mm_movemask_epi8(eq) as u16
+    }
+}
+
+/// Returns a bitmask of the bytes in a 16 byte `chunk` that are equal to any byte in `needle`,
+/// where bit `i` is set if `chunk[i]` matches
+///
+/// SSE2 and NEON are part of the baseline of x86_64 and aarch64, so these are used without runtime detection.
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+#[inline(always)]
+fn mask16<const N: usize>(chunk: &[u8], needle: [u8; N]) -> u16 {
+    use std::arch::aarch64::*;
+
+    const BITS: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+
+    debug_assert_eq!(chunk.len(), 16);
+
+    // SAFETY: NEON is enabled, and `chunk` is 16 bytes long
+    unsafe {
+        let bytes = vld1q_u8(chunk.as_ptr());
+        let mut eq = vdupq_n_u8(0);
+
+        for n in needle {
+            eq = vorrq_u8(eq, vceqq_u8(bytes, vdupq_n_u8(n)));
+        }
+
+        // NEON has no movemask, so every matching lane keeps its bit and the halves are summed up
+        let bits = vandq_u8(eq, vld1q_u8(BITS.as_ptr()));
+        let low = vaddv_u8(vget_low_u8(bits)) as u16;
+        let high = vaddv_u8(vget_high_u8(bits)) as u16;
+
+        low | (high << 8)
+    }
+}
+
+/// Returns a bitmask of the bytes in a 16 byte `chunk` that are equal to any byte in `needle`,
+/// where bit `i` is set if `chunk[i]` matches
+#[cfg(not(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "aarch64", target_feature = "neon")
+)))]
+#[inline(always)]
+fn mask16<const N: usize>(chunk: &[u8], needle: [u8; N]) -> u16 {
+    let mut mask = 0u16;
+
+    for (j, &byte) in chunk.iter().enumerate() {
+        for &n in &needle {
+            mask |= ((byte == n) as u16) << j;
+        }
+    }
+
+    mask
+}
+
 /// Optimized, stable function for finding any byte in `haystack`
 pub fn find_multi<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<usize> {
     let mut index = 0;
 
     for (i, chunk) in haystack.chunks_exact(16).enumerate() {
-        index = i * 16;
-        let mut mask = 0u16;
-
-        for (j, &byte) in chunk.into_iter().enumerate() {
-            for k in 0..N {
-                mask |= ((byte == needle[k]) as u16) << j;
-            }
-        }
+        index = (i + 1) * 16;
+        let mask = mask16(chunk, needle);
 
         if mask != 0 {
             let local_index = mask.trailing_zeros() as usize;
-            return Some(index + local_index);
+            return Some(i * 16 + local_index);
         }
     }
 
@@ -37,16 +105,12 @@ pub fn find(haystack: &[u8], needle: u8) -> Option<usize> {
     let mut index = 0;
 
     for (i, chunk) in haystack.chunks_exact(16).enumerate() {
-        index = i * 16;
-        let mut mask = 0u16;
-
-        for (j, &byte) in chunk.into_iter().enumerate() {
-            mask |= ((byte == needle) as u16) << j;
-        }
+        index = (i + 1) * 16;
+        let mask = mask16(chunk, [needle]);
 
         if mask != 0 {
             let local_index = mask.trailing_zeros() as usize;
-            return Some(index + local_index);
+            return Some(i * 16 + local_index);
         }
     }
 
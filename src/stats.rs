@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use crate::inline::table_size;
+use crate::parser::{cdata_content, SiblingPosition};
+use crate::text::{self, TextOptions};
+use crate::{
+    Bytes, Edge, HTMLTag, Namespace, Node, NodeHandle, ParseError, Parser, ParserOptions, Traverse,
+    VDom,
+};
+
+/// Statistics of a document, as returned by [`Collector::collect`] and [`VDom::statistics`](crate::VDom::statistics)
+///
+/// Only nodes that are part of the tree are counted, so nodes that were detached by mutations are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The number of elements
+    pub elements: usize,
+    /// The number of elements per tag name, with the names in lowercase
+    pub tags: BTreeMap<String, usize>,
+    /// The number of text nodes, including CDATA sections
+    pub text_nodes: usize,
+    /// The number of bytes of all text nodes as written in the source, i.e. with character references not decoded
+    pub text_bytes: usize,
+    /// The number of comments
+    pub comments: usize,
+    /// The number of attributes of all elements
+    pub attributes: usize,
+    /// The largest number of nested nodes, where the topmost nodes have a depth of 1
+    pub max_depth: usize,
+}
+
+/// Collects the statistics of many documents, one after another, for analytics over large corpora
+///
+/// The documents are parsed like with [`crate::parse_bytes`], but nodes are counted as soon as they are parsed and then
+/// dropped, so only the open elements are kept instead of the whole tree. The collector also keeps the allocations
+/// of the parser and reuses them for the next document, so memory use stays bounded by the deepest nesting rather than
+/// by the size of the documents, and allocations become rare after the first few.
+///
+/// # Example
+/// ```
+/// use tl::stats::Collector;
+///
+/// let pages = ["<p>a</p>", "<ul><li>b</li><li>c</li></ul>"];
+///
+/// let mut collector = Collector::new(Default::default());
+/// let mut elements = 0;
+///
+/// for page in pages {
+///     let stats = collector.collect(page.as_bytes()).unwrap();
+///     elements += stats.elements;
+/// }
+///
+/// assert_eq!(elements, 4);
+/// ```
+#[derive(Debug)]
+pub struct Collector {
+    pub(crate) parser: Option<Parser<'static>>,
+    options: ParserOptions,
+}
+
+impl Collector {
+    /// Creates a collector that parses documents with the given options
+    ///
+    /// Lookup tables are never built, so tracking options have no effect.
+    pub fn new(options: ParserOptions) -> Self {
+        Self {
+            parser: None,
+            options: options.without_tracking(),
+        }
+    }
+
+    /// Parses a document and returns its statistics
+    ///
+    /// # Errors
+    /// See [`crate::parse`]
+    pub fn collect(&mut self, input: &[u8]) -> Result<Statistics, ParseError> {
+        let mut parser = match self.parser.take() {
+            Some(parser) => parser.reset(input),
+            None => Parser::new_bytes(input, self.options),
+        };
+
+        let mut stats = Statistics::default();
+        let result = parser
+            .parse_with(|parser, nodes, depth| count(parser, nodes, depth, &mut stats))
+            .map(|()| stats);
+
+        // the node table borrows from the input, so it is cleared before it is stored
+        self.parser = Some(parser.reset(&[]));
+        result
+    }
+}
+
+/// Counts the nodes that were added by the last token, and removes every node that is not an open element
+///
+/// The open elements are always the first nodes, because all other nodes are removed right after they are counted,
+/// so the handle of an open element is its depth. A token opens or closes at most one element.
+fn count(parser: &mut Parser<'_>, nodes: usize, depth: usize, stats: &mut Statistics) {
+    for node in &parser.tags[nodes..] {
+        stats.max_depth = stats.max_depth.max(depth + 1);
+
+        match node {
+            Node::Tag(tag) => {
+                stats.elements += 1;
+                stats.attributes += tag.attributes().len();
+                *stats
+                    .tags
+                    .entry(tag.name().as_utf8_str().to_ascii_lowercase())
+                    .or_default() += 1;
+            }
+            Node::Raw(text) => {
+                stats.text_nodes += 1;
+                stats.text_bytes += text.as_bytes().len();
+            }
+            Node::CData(section) => {
+                stats.text_nodes += 1;
+                stats.text_bytes += cdata_content(section.as_bytes()).len();
+            }
+            Node::Comment(_) => stats.comments += 1,
+            _ => {}
+        }
+    }
+
+    let open = parser.stack.len();
+    parser.tags.truncate(open);
+
+    let children = match open.checked_sub(1) {
+        Some(parent) => match &mut parser.tags[parent] {
+            Node::Tag(tag) => &mut tag._children,
+            _ => return,
+        },
+        None => {
+            parser.ast.clear();
+            return;
+        }
+    };
+    while children
+        .as_slice()
+        .last()
+        .is_some_and(|child| child.get_inner() as usize >= open)
+    {
+        children.remove(children.len() - 1);
+    }
+
+    // templates and stray end tags are tracked regardless of the options
+    parser.templates.clear();
+    parser.stray_end_tags.clear();
+}
+
+/// Computes the statistics of a parsed document, see [`VDom::statistics`](crate::VDom::statistics)
+pub(crate) fn statistics(parser: &Parser<'_>) -> Statistics {
+    let mut stats = Statistics::default();
+    let mut depth = 0;
+
+    for edge in Traverse::new(parser, &parser.ast) {
+        let handle = match edge {
+            Edge::Open(handle) => handle,
+            Edge::Close(_) => {
+                depth -= 1;
+                continue;
+            }
+        };
+
+        depth += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+
+        match handle.get(parser) {
+            Some(Node::Tag(tag)) => {
+                stats.elements += 1;
+                stats.attributes += tag.attributes().len();
+                *stats
+                    .tags
+                    .entry(tag.name().as_utf8_str().to_ascii_lowercase())
+                    .or_default() += 1;
+            }
+            Some(Node::Raw(text)) => {
+                stats.text_nodes += 1;
+                stats.text_bytes += text.as_bytes().len();
+            }
+            Some(Node::CData(section)) => {
+                stats.text_nodes += 1;
+                stats.text_bytes += cdata_content(section.as_bytes()).len();
+            }
+            Some(Node::Comment(_)) => stats.comments += 1,
+            _ => {}
+        }
+    }
+
+    stats
+}
+
+/// Metrics of a page for SEO audits, as returned by [`VDom::page_stats`](crate::VDom::page_stats)
+///
+/// Lengths are counted in characters of the text as written in the document, with whitespace collapsed,
+/// so character references like `&amp;` are not decoded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageStats {
+    /// The number of words in the visible text of the body, without the content of scripts and styles
+    ///
+    /// Words are separated by whitespace, and must contain at least one letter or digit.
+    pub words: usize,
+    /// The number of `<h1>` to `<h6>` elements, with the number of `<h1>` elements at index 0
+    pub headings: [usize; 6],
+    /// The number of `<img>` elements
+    pub images: usize,
+    /// The number of `<img>` elements without an `alt` attribute
+    ///
+    /// Images with an empty `alt` attribute are decorative and are not counted.
+    pub images_without_alt: usize,
+    /// The number of `<a href>` and `<area href>` links to the host of the page, see [`LinkSummary`](crate::links::LinkSummary)
+    pub internal_links: usize,
+    /// The number of `http` and `https` links to other hosts
+    pub external_links: usize,
+    /// The length of the first `<title>`, or `None` if there is none
+    pub title_length: Option<usize>,
+    /// The length of the content of the first `<meta name="description">`, or `None` if there is none
+    pub meta_description_length: Option<usize>,
+}
+
+/// Computes the SEO metrics of a page at the given URL, see [`VDom::page_stats`](crate::VDom::page_stats)
+pub(crate) fn page_stats(dom: &VDom<'_>, page_url: Option<&str>) -> PageStats {
+    let parser = dom.parser();
+    let mut stats = PageStats::default();
+
+    for tag in dom.nodes().iter().filter_map(Node::as_tag) {
+        if tag.namespace() != Namespace::Html {
+            continue;
+        }
+
+        let name = tag.name().as_bytes();
+        match name {
+            [b'h', level @ b'1'..=b'6'] => stats.headings[(level - b'1') as usize] += 1,
+            b"img" => {
+                stats.images += 1;
+                stats.images_without_alt += !tag.attributes().contains("alt") as usize;
+            }
+            b"title" if stats.title_length.is_none() => {
+                stats.title_length = Some(length(&tag.inner_text(parser)));
+            }
+            b"meta" if stats.meta_description_length.is_none() && is_description(tag) => {
+                let content = tag.attributes().get("content").flatten();
+                stats.meta_description_length =
+                    Some(content.map_or(0, |content| length(&content.as_utf8_str())));
+            }
+            _ => {}
+        }
+    }
+
+    let text = text::inner_text_with(dom.body().children(), parser, TextOptions::readable());
+    stats.words = text
+        .split_whitespace()
+        // character references like `&mdash;` are not decoded, but are no words either
+        .filter(|word| !(word.starts_with('&') && word.ends_with(';')))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+
+    let links = dom.link_summary(page_url);
+    stats.internal_links = links.internal;
+    stats.external_links = links.external;
+
+    stats
+}
+
+fn is_description(tag: &HTMLTag<'_>) -> bool {
+    tag.attributes().get("name").flatten().is_some_and(|name| {
+        name.as_bytes()
+            .trim_ascii()
+            .eq_ignore_ascii_case(b"description")
+    })
+}
+
+/// Returns the number of characters of a text with collapsed whitespace
+fn length(text: &str) -> usize {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    words.iter().map(|word| word.chars().count()).sum::<usize>() + words.len().saturating_sub(1)
+}
+
+/// The memory that a document uses, by what it is used for, as returned by [`VDom::memory_usage`](crate::VDom::memory_usage)
+///
+/// All sizes are in bytes and include the unused capacity of lists and tables, because it is allocated too.
+/// The sizes of hash tables are estimated from their capacity, so the numbers are close to, but not exactly,
+/// what an allocator reports. The source string is not included, because the document borrows it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The list of all nodes, along with the parent and the position among its siblings of every node
+    pub nodes: usize,
+    /// The lists of children of elements that have more children than fit into the element itself, and the list of topmost nodes
+    pub children: usize,
+    /// The attributes of elements that have more attributes than fit into the element itself
+    pub attributes: usize,
+    /// Names, attributes and text that are owned by the document instead of borrowed from the source string,
+    /// like the values of modified attributes
+    pub owned_text: usize,
+    /// The lookup table of [`ParserOptions::track_ids`](crate::ParserOptions::track_ids)
+    pub ids: usize,
+    /// The lookup table of [`ParserOptions::track_classes`](crate::ParserOptions::track_classes)
+    pub classes: usize,
+    /// The lookup table of [`ParserOptions::track_tags`](crate::ParserOptions::track_tags)
+    pub tag_names: usize,
+    /// The lookup table of [`ParserOptions::track_attribute`](crate::ParserOptions::track_attribute)
+    pub attribute_values: usize,
+    /// The list of `<template>` elements
+    pub other: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the total number of bytes
+    pub fn total(&self) -> usize {
+        self.nodes
+            + self.children
+            + self.attributes
+            + self.owned_text
+            + self.ids
+            + self.classes
+            + self.tag_names
+            + self.attribute_values
+            + self.other
+    }
+}
+
+/// Estimates the memory that the document of a parser uses, see [`VDom::memory_usage`](crate::VDom::memory_usage)
+pub(crate) fn memory_usage(parser: &Parser<'_>) -> MemoryUsage {
+    let mut usage = MemoryUsage {
+        nodes: parser.tags.capacity() * mem::size_of::<Node<'_>>()
+            + parser.siblings.get().map_or(0, |siblings| {
+                siblings.parents.capacity() * mem::size_of::<Option<NodeHandle>>()
+                    + siblings.positions.capacity() * mem::size_of::<SiblingPosition>()
+            }),
+        children: parser.ast.capacity() * mem::size_of::<NodeHandle>(),
+        ids: table_size(&parser.ids),
+        classes: table_size(&parser.classes),
+        tag_names: table_size(&parser.tag_names),
+        attribute_values: table_size(&parser.attribute_values),
+        other: parser.templates.capacity() * mem::size_of::<NodeHandle>(),
+        ..MemoryUsage::default()
+    };
+
+    for (key, handles) in &parser.ids {
+        usage.ids += key.heap_size() + handles.heap_size();
+    }
+    for (key, handles) in &parser.classes {
+        usage.classes += key.heap_size() + handles.heap_size();
+    }
+    for (key, handles) in &parser.tag_names {
+        usage.tag_names += key.heap_size() + handles.capacity() * mem::size_of::<NodeHandle>();
+    }
+    for ((_, value), handles) in &parser.attribute_values {
+        usage.attribute_values +=
+            value.heap_size() + handles.capacity() * mem::size_of::<NodeHandle>();
+    }
+
+    for node in &parser.tags {
+        usage.owned_text += match node {
+            Node::Tag(tag) => {
+                usage.children += tag._children.heap_size();
+                usage.attributes += tag._attributes.raw.heap_size();
+
+                let attributes = &tag._attributes;
+                let values = attributes
+                    .raw
+                    .iter()
+                    .map(|(key, value)| {
+                        key.heap_size() + value.as_ref().map_or(0, Bytes::heap_size)
+                    })
+                    .sum::<usize>();
+
+                tag._name.heap_size()
+                    + tag._raw.heap_size()
+                    + values
+                    + attributes.id.as_ref().map_or(0, Bytes::heap_size)
+                    + attributes.class.as_ref().map_or(0, Bytes::heap_size)
+            }
+            Node::Raw(text)
+            | Node::Comment(text)
+            | Node::ProcessingInstruction(text)
+            | Node::CData(text) => text.heap_size(),
+            Node::Doctype(doctype) => doctype._raw.heap_size(),
+        };
+    }
+
+    usage
+}
@@ -0,0 +1,131 @@
+use crate::{Bytes, VDom};
+
+/// Attribute name prefixes that are commonly used for analytics and click tracking
+const TRACKING_PREFIXES: &[&[u8]] = &[b"data-track", b"data-ga", b"data-gtm", b"data-analytics"];
+
+/// Attribute names that are commonly used for analytics and click tracking
+const TRACKING_ATTRIBUTES: &[&[u8]] = &[b"ping"];
+
+/// A policy that decides which attributes [`strip_attributes`] removes
+///
+/// Attribute names are compared case insensitively.
+/// An attribute is removed if any of the enabled rules matches it.
+///
+/// # Example
+/// ```
+/// use tl::strip::AttributePolicy;
+///
+/// let policy = AttributePolicy::new()
+///     .strip_event_handlers()
+///     .deny("style");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AttributePolicy<'p> {
+    event_handlers: bool,
+    tracking: bool,
+    allowlist: Option<Vec<&'p str>>,
+    denylist: Vec<&'p str>,
+}
+
+impl<'p> AttributePolicy<'p> {
+    /// Creates a new [`AttributePolicy`] that does not remove any attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes inline event handler attributes (any attribute starting with `on`, like `onclick`)
+    pub fn strip_event_handlers(mut self) -> Self {
+        self.event_handlers = true;
+        self
+    }
+
+    /// Removes attributes that are commonly used for tracking, such as `ping` and `data-track*`, `data-ga*`, `data-gtm*` and `data-analytics*`
+    pub fn strip_tracking(mut self) -> Self {
+        self.tracking = true;
+        self
+    }
+
+    /// Removes every attribute that is not in the given list
+    ///
+    /// Calling this more than once extends the list.
+    pub fn allow(mut self, names: &[&'p str]) -> Self {
+        self.allowlist
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(names);
+        self
+    }
+
+    /// Removes the attribute with the given name
+    pub fn deny(mut self, name: &'p str) -> Self {
+        self.denylist.push(name);
+        self
+    }
+
+    /// Checks whether an attribute with the given name should be removed according to this policy
+    pub fn should_strip(&self, name: &[u8]) -> bool {
+        let is_any = |list: &[&str]| list.iter().any(|n| n.as_bytes().eq_ignore_ascii_case(name));
+
+        if self.event_handlers && starts_with_ignore_case(name, b"on") {
+            return true;
+        }
+
+        if self.tracking
+            && (TRACKING_ATTRIBUTES
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(name))
+                || TRACKING_PREFIXES
+                    .iter()
+                    .any(|p| starts_with_ignore_case(name, p)))
+        {
+            return true;
+        }
+
+        if let Some(allowlist) = &self.allowlist {
+            if !is_any(allowlist) {
+                return true;
+            }
+        }
+
+        is_any(&self.denylist)
+    }
+}
+
+fn starts_with_ignore_case(haystack: &[u8], prefix: &[u8]) -> bool {
+    haystack
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Removes attributes from every tag in the given DOM according to the policy, and returns the number of removed attributes
+///
+/// This only concerns attributes, tags and their content are left untouched.
+/// Query selectors search all nodes afterwards, because the lookup tables may still contain stripped IDs and classes.
+///
+/// # Example
+/// ```
+/// use tl::strip::AttributePolicy;
+///
+/// let mut dom = tl::parse(
+///     r#"<a href="/" onclick="track()" ping="/log">Home</a><img src="x.png" onerror="alert(1)">"#,
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let policy = AttributePolicy::new().strip_event_handlers().strip_tracking();
+/// assert_eq!(tl::strip_attributes(&mut dom, &policy), 3);
+/// assert_eq!(dom.outer_html(), r#"<a href="/">Home</a><img src="x.png">"#);
+/// ```
+pub fn strip_attributes(dom: &mut VDom<'_>, policy: &AttributePolicy<'_>) -> usize {
+    let mut removed = 0;
+
+    for tag in dom.nodes_mut().iter_mut().filter_map(|n| n.as_tag_mut()) {
+        let attributes = tag.attributes_mut();
+        let len = attributes.len();
+
+        attributes.retain(|key: &Bytes, _| !policy.should_strip(key.as_bytes()));
+
+        removed += len - attributes.len();
+    }
+
+    removed
+}
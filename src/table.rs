@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use crate::text::TextOptions;
+use crate::{HTMLTag, NodeHandle, Parser};
+
+/// The largest `colspan` that is honored, as in browsers
+const MAX_COL_SPAN: usize = 1000;
+/// The largest `rowspan` that is honored, as in browsers
+const MAX_ROW_SPAN: usize = 65534;
+
+/// A cell of a [`Table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    /// The `<td>` or `<th>` element, or `None` for a position that no cell covers
+    pub node: Option<NodeHandle>,
+    /// The visible text of the cell with collapsed whitespace, on a single line
+    pub text: String,
+    /// Whether the cell is a `<th>`
+    pub header: bool,
+    /// The number of rows the cell spans
+    pub row_span: usize,
+    /// The number of columns the cell spans
+    pub col_span: usize,
+    /// The row and column of the top left position of the cell
+    ///
+    /// A cell that spans multiple rows or columns is repeated at every position it covers,
+    /// and this is where it was defined.
+    pub origin: (usize, usize),
+}
+
+impl Cell {
+    /// Checks whether this is the position at which the cell was defined, rather than one it covers through a span
+    pub fn is_origin(&self, row: usize, column: usize) -> bool {
+        self.origin == (row, column)
+    }
+}
+
+/// The grid of a `<table>` element, as returned by [`HTMLTag::as_table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    /// The rows of the table: the rows of `<thead>`, then those of `<tbody>` elements and the table itself, then those of `<tfoot>`
+    ///
+    /// Every row has the same number of columns.
+    pub rows: Vec<Vec<Cell>>,
+    /// The number of rows at the start of [`Table::rows`] that make up the header:
+    /// the rows of `<thead>`, or the first row if it only contains `<th>` cells
+    pub header_rows: usize,
+}
+
+impl Table {
+    /// Returns the number of columns
+    pub fn columns(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// Returns the name of every column: the text of the last header row, or an empty string if there is no header
+    pub fn headers(&self) -> Vec<&str> {
+        match self.header_rows.checked_sub(1) {
+            Some(last) => self.rows[last].iter().map(|c| c.text.as_str()).collect(),
+            None => vec![""; self.columns()],
+        }
+    }
+
+    /// Returns the rows after the header
+    pub fn body(&self) -> &[Vec<Cell>] {
+        &self.rows[self.header_rows..]
+    }
+
+    /// Returns every row after the header as a map from column name to cell text
+    ///
+    /// Columns with an empty name are skipped. If several columns have the same name, the last one wins.
+    pub fn records(&self) -> Vec<HashMap<String, String>> {
+        let headers = self.headers();
+
+        self.body()
+            .iter()
+            .map(|row| {
+                headers
+                    .iter()
+                    .zip(row)
+                    .filter(|(header, _)| !header.is_empty())
+                    .map(|(header, cell)| ((*header).to_owned(), cell.text.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn is_named(tag: &HTMLTag<'_>, name: &[u8]) -> bool {
+    tag.name().as_bytes().eq_ignore_ascii_case(name)
+}
+
+/// Returns the element children of a tag with one of the given names
+fn children<'p, 'a>(
+    tag: &HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+    names: &'p [&[u8]],
+) -> impl Iterator<Item = (NodeHandle, &'p HTMLTag<'a>)> + 'p {
+    let handles = tag.children().top().to_vec();
+
+    handles.into_iter().filter_map(move |handle| {
+        let tag = handle.get(parser)?.as_tag()?;
+        names
+            .iter()
+            .any(|name| is_named(tag, name))
+            .then_some((handle, tag))
+    })
+}
+
+fn span(tag: &HTMLTag<'_>, name: &str, max: usize) -> usize {
+    tag.attributes()
+        .get_int(name)
+        .and_then(|span| usize::try_from(span).ok())
+        .map_or(1, |span| span.clamp(1, max))
+}
+
+/// Returns the visible text of a cell on a single line, with a space between block-level elements like paragraphs
+fn text(cell: &HTMLTag<'_>, parser: &Parser<'_>) -> String {
+    cell.inner_text_with(parser, TextOptions::readable())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds the grid of a `<table>`, or returns `None` for any other element
+pub(crate) fn from_tag(table: &HTMLTag<'_>, parser: &Parser<'_>) -> Option<Table> {
+    if !is_named(table, b"table") {
+        return None;
+    }
+
+    let mut head = Vec::new();
+    let mut body = Vec::new();
+    let mut foot = Vec::new();
+
+    for (_, child) in children(table, parser, &[b"thead", b"tbody", b"tfoot", b"tr"]) {
+        let rows = if is_named(child, b"thead") {
+            &mut head
+        } else if is_named(child, b"tfoot") {
+            &mut foot
+        } else {
+            &mut body
+        };
+
+        if is_named(child, b"tr") {
+            rows.push(child);
+        } else {
+            rows.extend(children(child, parser, &[b"tr"]).map(|(_, tr)| tr));
+        }
+    }
+
+    let header_rows = head.len();
+    let rows = head.into_iter().chain(body).chain(foot).collect::<Vec<_>>();
+
+    let mut grid: Vec<Vec<Option<Cell>>> = vec![Vec::new(); rows.len()];
+
+    for (row, tr) in rows.iter().enumerate() {
+        let mut column = 0;
+
+        for (handle, cell) in children(tr, parser, &[b"td", b"th"]) {
+            // skip the positions that cells of previous rows cover
+            while grid[row].get(column).is_some_and(Option::is_some) {
+                column += 1;
+            }
+
+            let row_span = span(cell, "rowspan", MAX_ROW_SPAN).min(rows.len() - row);
+            let col_span = span(cell, "colspan", MAX_COL_SPAN);
+
+            let cell = Cell {
+                node: Some(handle),
+                text: text(cell, parser),
+                header: is_named(cell, b"th"),
+                row_span,
+                col_span,
+                origin: (row, column),
+            };
+
+            for cells in &mut grid[row..row + row_span] {
+                if cells.len() < column + col_span {
+                    cells.resize(column + col_span, None);
+                }
+
+                for slot in &mut cells[column..column + col_span] {
+                    *slot = Some(cell.clone());
+                }
+            }
+
+            column += col_span;
+        }
+    }
+
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let rows = grid
+        .into_iter()
+        .enumerate()
+        .map(|(row, cells)| {
+            let len = cells.len();
+
+            cells
+                .into_iter()
+                .chain(std::iter::repeat_n(None, columns - len))
+                .enumerate()
+                .map(|(column, cell)| {
+                    cell.unwrap_or(Cell {
+                        node: None,
+                        text: String::new(),
+                        header: false,
+                        row_span: 1,
+                        col_span: 1,
+                        origin: (row, column),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // without a `<thead>`, a first row of only `<th>` cells is the header
+    let header_rows = match header_rows {
+        0 if rows
+            .first()
+            .is_some_and(|row| !row.is_empty() && row.iter().all(|cell| cell.header)) =>
+        {
+            1
+        }
+        n => n,
+    };
+
+    Some(Table { rows, header_rows })
+}
@@ -1,5 +1,10 @@
 use crate::{parse, parse_owned, Bytes};
-use crate::{parser::*, queryselector::{Parser, Selector}, HTMLTag, Node};
+use crate::{
+    parser::*,
+    queryselector::{Parser, Selector},
+    HTMLTag, Node,
+};
+use std::borrow::Cow;
 
 fn force_as_tag<'a, 'b>(actual: &'a Node<'b>) -> &'a HTMLTag<'b> {
     match actual {
@@ -8,6 +13,11 @@ fn force_as_tag<'a, 'b>(actual: &'a Node<'b>) -> &'a HTMLTag<'b> {
     }
 }
 
+/// Returns the indices of the given handles, which can be compared between documents unlike the handles themselves
+fn indices<'h>(handles: impl IntoIterator<Item = &'h NodeHandle>) -> Vec<InnerNodeHandle> {
+    handles.into_iter().map(NodeHandle::get_inner).collect()
+}
+
 #[test]
 fn outer_html() {
     let dom = parse(
@@ -88,6 +98,38 @@ fn get_element_by_id_tracking() {
     assert_eq!(el.outer_html(parser), "<p id=\"test\"></p>")
 }
 
+#[test]
+fn get_elements_by_id_duplicates() {
+    let input = r#"<div id="a"><p id="b">1</p><input id="a"></div><p id="b">2</p><p id="c"></p>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_ids(),
+    ] {
+        let dom = parse(input, options).unwrap();
+
+        let ids = |id| {
+            dom.get_elements_by_id(id)
+                .map(|h| h.get_inner())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("a"), [0, 3]);
+        assert_eq!(ids("b"), [1, 4]);
+        assert_eq!(ids("c"), [6]);
+        assert_eq!(ids("d"), [0; 0]);
+        assert_eq!(dom.get_element_by_id("b"), Some(dom.parser().handle(1)));
+
+        let duplicates = dom
+            .duplicate_ids()
+            .into_iter()
+            .map(|(id, handles)| (id.as_utf8_str().into_owned(), handles.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(duplicates, [("a".to_owned(), 2), ("b".to_owned(), 2)]);
+    }
+}
+
 #[test]
 fn get_element_by_class_name_default() {
     let dom = parse(
@@ -118,12 +160,68 @@ fn get_element_by_class_name_tracking() {
     assert_eq!(el.inner_text(dom.parser()), "hey");
 }
 
+#[test]
+fn get_elements_by_tag_name() {
+    let input = "<p>a</p><br><div><p>b<p>c</p></p></div>";
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_tags(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        let parser = dom.parser();
+
+        let texts = dom
+            .get_elements_by_tag_name("p")
+            .map(|h| h.get(parser).unwrap().inner_text(parser).into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(texts, ["a", "bc", "c"]);
+        assert_eq!(dom.get_elements_by_tag_name("br").count(), 1);
+        assert_eq!(dom.get_elements_by_tag_name("span").count(), 0);
+    }
+
+    let dom = parse(input, ParserOptions::default().track_tags()).unwrap();
+    let cache = crate::cache::serialize(&dom);
+    let dom = crate::cache::load(&cache).unwrap();
+    assert_eq!(dom.get_elements_by_tag_name("p").count(), 3);
+}
+
+#[test]
+fn get_elements_by_attribute() {
+    let input = r#"<input name="q"><div data-testid="a"><p data-testid="a" name="q">x</p></div><p data-testid>y</p>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default()
+            .track_attribute("data-testid")
+            .track_attribute("name"),
+    ] {
+        let dom = parse(input, options).unwrap();
+
+        let handles = |name, value| {
+            dom.get_elements_by_attribute(name, value)
+                .map(|h| h.get_inner())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(handles("data-testid", "a"), [1, 2]);
+        assert_eq!(handles("name", "q"), [0, 2]);
+        assert_eq!(handles("data-testid", ""), [0; 0]);
+        assert_eq!(handles("data-testid", "b"), [0; 0]);
+    }
+
+    // attributes that are not tracked fall back to a full scan
+    let dom = parse(input, ParserOptions::default().track_attribute("name")).unwrap();
+    assert_eq!(dom.get_elements_by_attribute("data-testid", "a").count(), 2);
+}
+
 #[test]
 fn html5() {
     let dom = parse("<!DOCTYPE html> hello", ParserOptions::default()).unwrap();
 
     assert_eq!(dom.version(), Some(HTMLVersion::HTML5));
-    assert_eq!(dom.children().len(), 1)
+    assert_eq!(dom.children().len(), 2)
 }
 
 #[test]
@@ -161,7 +259,7 @@ pub fn children_mut() {
 
     let mut children = child.children_mut();
     let top = children.top_mut();
-    let handle = top[0].clone();
+    let handle = top[0];
     let node = handle.get_mut(dom.parser_mut()).unwrap();
     *node = Node::Raw("Hello".into());
 
@@ -186,8 +284,7 @@ fn nested_inner_text() {
 fn owned_dom() {
     let owned_dom = {
         let input = String::from("<p id=\"test\">hello</p>");
-        let dom = unsafe { parse_owned(input, ParserOptions::default()).unwrap() };
-        dom
+        unsafe { parse_owned(input, ParserOptions::default()).unwrap() }
     };
 
     let dom = owned_dom.get_ref();
@@ -229,7 +326,7 @@ fn with() {
     let tag = dom
         .nodes()
         .iter()
-        .find(|x| x.as_tag().map_or(false, |x| x.name() == "span"));
+        .find(|x| x.as_tag().is_some_and(|x| x.name() == "span"));
 
     assert_eq!(
         tag.map(|tag| tag.inner_text(parser)),
@@ -251,7 +348,7 @@ fn dom_nodes() {
     let element = dom
         .nodes()
         .iter()
-        .find(|x| x.as_tag().map_or(false, |x| x.name().eq("a")));
+        .find(|x| x.as_tag().is_some_and(|x| x.name().eq("a")));
 
     assert_eq!(element.map(|x| x.inner_text(parser)), Some("nested".into()));
 }
@@ -319,6 +416,25 @@ mod simd {
         assert_eq!(crate::simd::find(long.as_bytes(), b'b'), Some(count));
     }
 
+    #[test]
+    fn string_search_matches_scalar() {
+        const NEEDLE: [u8; 4] = [b'<', b'>', b'&', b'"'];
+
+        // every length and position around the 16 byte chunks, with bytes that differ from the needles only in the high bit
+        for len in 0..50 {
+            for pos in 0..=len {
+                let mut haystack = vec![b'<' | 0x80; len];
+                if pos < len {
+                    haystack[pos] = b'&';
+                }
+                let expected = (pos < len).then_some(pos);
+
+                assert_eq!(crate::simd::find(&haystack, b'&'), expected);
+                assert_eq!(crate::simd::find4(&haystack, NEEDLE), expected);
+            }
+        }
+    }
+
     #[test]
     fn string_search_4() {
         const NEEDLE: [u8; 4] = [b'a', b'b', b'c', b'd'];
@@ -493,6 +609,52 @@ fn valueless_attribute() {
     assert!(element.is_some());
 }
 
+#[test]
+fn consecutive_valueless_attributes() {
+    let dom = parse(r#"<input a b c = "d" e>"#, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let attributes = dom.children()[0]
+        .get(parser)
+        .unwrap()
+        .as_tag()
+        .unwrap()
+        .attributes();
+
+    assert_eq!(attributes.len(), 4);
+    assert_eq!(attributes.get("a"), Some(None));
+    assert_eq!(attributes.get("b"), Some(None));
+    assert_eq!(attributes.get("c"), Some(Some(&"d".into())));
+    assert_eq!(attributes.get("e"), Some(None));
+}
+
+#[test]
+fn attribute_coercion() {
+    let dom = parse(
+        r#"<td colspan="  3 " rowspan="-2" width="+4.5" height="99999999999999999999" data-x=""></td><a href="  //cdn.example.com/x.png" hidden></a>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let attributes = |idx: usize| dom.nodes()[idx].as_tag().unwrap().attributes();
+
+    let td = attributes(0);
+    assert_eq!(td.get_int("colspan"), Some(3));
+    assert_eq!(td.get_int("rowspan"), Some(-2));
+    assert_eq!(td.get_int("width"), Some(4));
+    assert_eq!(td.get_int("height"), None);
+    assert_eq!(td.get_int("data-x"), None);
+    assert_eq!(td.get_int("missing"), None);
+    assert!(td.get_bool("data-x"));
+    assert!(!td.get_bool("hidden"));
+
+    let a = attributes(1);
+    assert!(a.get_bool("hidden"));
+    assert_eq!(a.get_url("hidden", None), None);
+    assert_eq!(
+        a.get_url("href", Some("https://example.com/")).as_deref(),
+        Some("https://cdn.example.com/x.png")
+    );
+}
+
 #[test]
 fn unquoted() {
     // https://github.com/y21/tl/issues/12
@@ -591,23 +753,19 @@ mod query_selector {
         let node_option = dom
             .query_selector(r#"meta[property="og:title"]"#)
             .and_then(|mut iter| iter.next());
-        let value = if let Some(node) = node_option {
-            Some(
-                node.get(parser)
-                    .unwrap()
-                    .as_tag()
-                    .unwrap()
-                    .attributes()
-                    .get("content")
-                    .flatten()
-                    .unwrap()
-                    .try_as_utf8_str()
-                    .unwrap()
-                    .to_string(),
-            )
-        } else {
-            None
-        };
+        let value = node_option.map(|node| {
+            node.get(parser)
+                .unwrap()
+                .as_tag()
+                .unwrap()
+                .attributes()
+                .get("content")
+                .flatten()
+                .unwrap()
+                .try_as_utf8_str()
+                .unwrap()
+                .to_string()
+        });
 
         assert_eq!(value, Some("hello".to_string()));
     }
@@ -629,14 +787,49 @@ mod query_selector {
             let dom = parse(input, Default::default()).unwrap();
             let selector = dom.query_selector(selector).unwrap();
 
-            assert_eq!(selector.map(|handle| handle.get(dom.parser()).unwrap().inner_text(dom.parser())).collect::<Vec<_>>(), expected_text)
+            assert_eq!(
+                selector
+                    .map(|handle| handle.get(dom.parser()).unwrap().inner_text(dom.parser()))
+                    .collect::<Vec<_>>(),
+                expected_text
+            )
         }
 
-        assert_selector(".a > .b", Selector::Parent(Box::new(Selector::Class(&[97])), Box::new(Selector::Class(&[98]))));
-        assert_selector(".a > .b > .c", Selector::Parent(Box::new(Selector::Parent(Box::new(Selector::Class(&[97])), Box::new(Selector::Class(&[98])))), Box::new(Selector::Class(&[99]))));
+        assert_selector(
+            ".a > .b",
+            Selector::Parent(
+                Box::new(Selector::Class(&[97])),
+                Box::new(Selector::Class(&[98])),
+            ),
+        );
+        assert_selector(
+            ".a > .b > .c",
+            Selector::Parent(
+                Box::new(Selector::Parent(
+                    Box::new(Selector::Class(&[97])),
+                    Box::new(Selector::Class(&[98])),
+                )),
+                Box::new(Selector::Class(&[99])),
+            ),
+        );
 
-        assert_selector(".a .b", Selector::Descendant(Box::new(Selector::Class(&[97])), Box::new(Selector::Class(&[98]))));
-        assert_selector(".a .b .c", Selector::Descendant(Box::new(Selector::Descendant(Box::new(Selector::Class(&[97])), Box::new(Selector::Class(&[98])))), Box::new(Selector::Class(&[99]))));
+        assert_selector(
+            ".a .b",
+            Selector::Descendant(
+                Box::new(Selector::Class(&[97])),
+                Box::new(Selector::Class(&[98])),
+            ),
+        );
+        assert_selector(
+            ".a .b .c",
+            Selector::Descendant(
+                Box::new(Selector::Descendant(
+                    Box::new(Selector::Class(&[97])),
+                    Box::new(Selector::Class(&[98])),
+                )),
+                Box::new(Selector::Class(&[99])),
+            ),
+        );
 
         assert_selector(
             "div > .hello.world#id > span, main section",
@@ -647,38 +840,167 @@ mod query_selector {
                         Box::new(Selector::And(
                             Box::new(Selector::And(
                                 Box::new(Selector::Class(&[104, 101, 108, 108, 111])),
-                                Box::new(Selector::Class(&[119, 111, 114, 108, 100]))
+                                Box::new(Selector::Class(&[119, 111, 114, 108, 100])),
                             )),
-                            Box::new(Selector::Id(&[105, 100]))
-                        ))
+                            Box::new(Selector::Id(&[105, 100])),
+                        )),
                     )),
-                    Box::new(Selector::Tag(&[115, 112, 97, 110]))
+                    Box::new(Selector::Tag(&[115, 112, 97, 110])),
                 )),
                 Box::new(Selector::Descendant(
                     Box::new(Selector::Tag(&[109, 97, 105, 110])),
-                    Box::new(Selector::Tag(&[115, 101, 99, 116, 105, 111, 110]))
-                ))
-            )
+                    Box::new(Selector::Tag(&[115, 101, 99, 116, 105, 111, 110])),
+                )),
+            ),
+        );
+
+        assert_query_selector_count(
+            r#"<div><meta property="og:title" content="hello" /></div>"#,
+            "div meta",
+            1,
+        );
+        assert_query_selector_count(
+            r#"<div><meta property="og:title" content="hello" /></div>"#,
+            "div > meta",
+            1,
+        );
+        assert_query_selector_count(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "div div",
+            1,
+        );
+        assert_query_selector_text(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "div div",
+            Vec::from(["PASS".to_owned()]),
+        );
+        assert_query_selector_count(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p div",
+            2,
+        );
+        assert_query_selector_count(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p > div > div",
+            1,
+        );
+        assert_query_selector_text(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p > div > div",
+            Vec::from(["PASS".to_owned()]),
+        );
+        assert_query_selector_count(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            ".z .y",
+            1,
+        );
+        assert_query_selector_text(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            ".z .y",
+            Vec::from(["PASS".to_owned()]),
+        );
+        assert_query_selector_count(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p .y",
+            1,
+        );
+        assert_query_selector_text(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p .z .y",
+            Vec::from(["PASS".to_owned()]),
+        );
+        assert_query_selector_text(
+            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            "p > .z > .y",
+            Vec::from(["PASS".to_owned()]),
+        );
+
+        assert_query_selector_text(
+            r#"<div><p class="hi">hello</p></div>"#,
+            "div p",
+            Vec::from(["hello".to_owned()]),
+        );
+        assert_query_selector_text(
+            r#"<div><p class="hi"><span>hello</span></p></div>"#,
+            "div > .hi > span",
+            Vec::from(["hello".to_owned()]),
+        );
+
+        assert_query_selector_count(
+            r#"<main><div><p class="hello world" id="id"><span>cond1</span><section>cond2</section></p></div></main>"#,
+            "div > .hello.world#id > span, main section",
+            2,
+        );
+        assert_query_selector_text(
+            r#"<main><div><p class="hello world" id="id"><span>cond1</span><section>cond2</section></p></div></main>"#,
+            "div > .hello.world#id > span, main section",
+            Vec::from(["cond1".to_owned(), "cond2".to_owned()]),
+        )
+    }
+
+    #[test]
+    fn contains() {
+        let input = r#"<ul><li class="a">Buy <b>now</b></li><li class="a">Sold out</li><li>Buy (later)</li></ul>"#;
+        let dom = parse(input, Default::default()).unwrap();
+        let parser = dom.parser();
+
+        let texts = |iter: &mut dyn Iterator<Item = NodeHandle>| {
+            iter.map(|h| h.get(parser).unwrap().inner_text(parser).into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            Parser::new(b"p:contains( 'a b' )").selector(),
+            Some(Selector::And(
+                Box::new(Selector::Tag(b"p")),
+                Box::new(Selector::Contains(b"a b"))
+            ))
         );
 
-        assert_query_selector_count(r#"<div><meta property="og:title" content="hello" /></div>"#, "div meta", 1);
-        assert_query_selector_count(r#"<div><meta property="og:title" content="hello" /></div>"#, "div > meta", 1);
-        assert_query_selector_count(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "div div", 1);
-        assert_query_selector_text(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "div div", Vec::from(["PASS".to_owned()]));
-        assert_query_selector_count(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p div", 2);
-        assert_query_selector_count(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p > div > div", 1);
-        assert_query_selector_text(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p > div > div", Vec::from(["PASS".to_owned()]));
-        assert_query_selector_count(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, ".z .y", 1);
-        assert_query_selector_text(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, ".z .y", Vec::from(["PASS".to_owned()]));
-        assert_query_selector_count(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p .y", 1);
-        assert_query_selector_text(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p .z .y", Vec::from(["PASS".to_owned()]));
-        assert_query_selector_text(r#"<p><div class="z"><div class="y">PASS</div></div></p>"#, "p > .z > .y", Vec::from(["PASS".to_owned()]));
+        assert_eq!(
+            texts(&mut dom.query_selector("li:contains(Buy)").unwrap()),
+            ["Buy now", "Buy (later)"]
+        );
+        assert_eq!(
+            texts(&mut dom.query_selector(r#".a:contains("now")"#).unwrap()),
+            ["Buy now"]
+        );
+        assert_eq!(
+            texts(&mut dom.query_selector(r#"ul :contains("(later)")"#).unwrap()),
+            ["Buy (later)"]
+        );
+        assert_eq!(dom.query_selector(":contains(Buy)").unwrap().count(), 3);
 
-        assert_query_selector_text(r#"<div><p class="hi">hello</p></div>"#, "div p", Vec::from(["hello".to_owned()]));
-        assert_query_selector_text(r#"<div><p class="hi"><span>hello</span></p></div>"#, "div > .hi > span", Vec::from(["hello".to_owned()]));
+        // colons are still allowed in tag names
+        let dom2 = parse("<svg:rect>x</svg:rect>", Default::default()).unwrap();
+        assert_eq!(dom2.query_selector("svg:rect").unwrap().count(), 1);
+
+        assert_eq!(
+            texts(&mut dom.query_selector("li").unwrap().filter_text("Sold")),
+            ["Sold out"]
+        );
+        assert_eq!(
+            texts(
+                &mut dom
+                    .query_selector(".a")
+                    .unwrap()
+                    .filter_text(String::from("now"))
+            ),
+            ["Buy now"]
+        );
+        assert_eq!(
+            dom.query_selector("li").unwrap().filter_text('(').count(),
+            1
+        );
 
-        assert_query_selector_count(r#"<main><div><p class="hello world" id="id"><span>cond1</span><section>cond2</section></p></div></main>"#, "div > .hello.world#id > span, main section", 2);
-        assert_query_selector_text(r#"<main><div><p class="hello world" id="id"><span>cond1</span><section>cond2</section></p></div></main>"#, "div > .hello.world#id > span, main section", Vec::from(["cond1".to_owned(), "cond2".to_owned()]))
+        #[cfg(feature = "regex")]
+        {
+            let pattern = regex::Regex::new(r"^Buy \w+$").unwrap();
+            assert_eq!(
+                texts(&mut dom.query_selector("li").unwrap().filter_text(&pattern)),
+                ["Buy now"]
+            );
+        }
     }
 }
 
@@ -695,13 +1017,13 @@ fn nodes_order() {
     assert_eq!(nodes.len(), 5);
 
     // First node is <p>
-    assert_eq!(&nodes[0].as_tag().unwrap()._name, "p");
+    assert_eq!(nodes[0].as_tag().unwrap().name(), "p");
     // Second node is inner text of <p>: test
     assert_eq!(nodes[1].as_raw().unwrap().as_bytes(), b"test");
     // Third node is <div>
-    assert_eq!(&nodes[2].as_tag().unwrap()._name, "div");
+    assert_eq!(nodes[2].as_tag().unwrap().name(), "div");
     // Fourth node is inner <span> node
-    assert_eq!(&nodes[3].as_tag().unwrap()._name, "span");
+    assert_eq!(nodes[3].as_tag().unwrap().name(), "span");
     // Fifth node is inner text of <span>: test2
     assert_eq!(nodes[4].as_raw().unwrap().as_bytes(), b"test2");
 }
@@ -728,6 +1050,24 @@ fn comment_end() {
     )
 }
 
+#[test]
+fn comment_dashes() {
+    let dom = parse(
+        "<!-- a - b -- c -> d ---><p>x</p><!-- -",
+        Default::default(),
+    )
+    .unwrap();
+    let nodes = dom.nodes();
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(
+        nodes[0].as_comment().unwrap().as_utf8_str(),
+        "<!-- a - b -- c -> d --->"
+    );
+    assert_eq!(nodes[2].inner_text(dom.parser()), "x");
+    // an unterminated comment is empty
+    assert_eq!(nodes[3].as_comment().unwrap().as_utf8_str(), "");
+}
+
 #[test]
 fn tag_parent() {
     fn assert_parent_rel(input: &str, node: usize, parent: usize) {
@@ -837,6 +1177,86 @@ fn insert_attribute_owned() {
     assert_eq!(attr.get("style"), Some(Some(&"some style".into())));
 }
 
+#[test]
+fn class_list() {
+    let mut dom = parse(
+        "<p class=\" a\tb a \">x</p><p class=b>y</p>",
+        Default::default(),
+    )
+    .unwrap();
+
+    let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    assert_eq!(attributes.classes().iter().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(attributes.class_iter().unwrap().count(), 3);
+    assert!(attributes.is_class_member("b"));
+
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    assert_eq!(attributes.add_class("a b"), Ok(false));
+    assert_eq!(attributes.add_class(""), Ok(false));
+    assert_eq!(attributes.add_class("c"), Ok(true));
+    assert_eq!(attributes.class().unwrap(), " a\tb a c");
+    assert_eq!(attributes.remove_class("a"), Ok(true));
+    assert_eq!(attributes.remove_class("b"), Ok(true));
+    assert_eq!(attributes.remove_class("c"), Ok(true));
+    assert!(attributes.classes().is_empty());
+    assert_eq!(dom.outer_html(), "<p class=\"\">x</p><p class=\"b\">y</p>");
+
+    let query = dom.query_selector(".b").unwrap().collect::<Vec<_>>();
+    assert_eq!(query, [dom.parser().handle(2)]);
+}
+
+#[test]
+fn typed_attribute_accessors() {
+    let dom = parse(
+        r#"<a id=x href="" data-a-b-c="1" data--x="2" data-a-="3" data-B="4" disabled=false>link</a>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    let tag = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(tag.id().as_deref(), Some("x"));
+    assert_eq!(tag.href(), None);
+    assert_eq!(tag.src(), None);
+    assert!(tag.is_boolean_attr_set("disabled"));
+
+    let dataset = tag.dataset();
+    assert_eq!(dataset.len(), 4);
+    assert_eq!(dataset["aBC"], "1");
+    assert_eq!(dataset["X"], "2");
+    assert_eq!(dataset["a-"], "3");
+    // attribute names are lowercase, so `data-B` is `b` like in browsers
+    assert_eq!(dataset["b"], "4");
+}
+
+#[test]
+fn duplicate_attributes() {
+    let source = r#"<p>x</p><a id=a class="b" id=c href="/1" class=d href='/2' download>y</a>"#;
+
+    let dom = parse(source, Default::default()).unwrap();
+    let tag = dom.nodes()[2].as_tag().unwrap();
+    assert_eq!(tag.id().as_deref(), Some("c"));
+    assert_eq!(tag.attributes().class().unwrap(), "d");
+    assert_eq!(tag.href().as_deref(), Some("/2"));
+
+    let attributes = tag.source_attributes().collect::<Vec<_>>();
+    assert_eq!(attributes.len(), 7);
+    assert_eq!(attributes[5].0.as_bytes(), b"href");
+    assert_eq!(attributes[5].1.as_ref().unwrap(), "/2");
+    assert_eq!(&source[attributes[5].2.range()], "href='/2'");
+    assert_eq!(attributes[6].1, None);
+    assert_eq!(&source[attributes[6].2.range()], "download");
+    assert_eq!(&source[attributes[0].2.range()], "id=a");
+
+    let options =
+        ParserOptions::default().with_duplicate_attributes(DuplicateAttributes::KeepFirst);
+    let dom = parse(source, options).unwrap();
+    let tag = dom.nodes()[2].as_tag().unwrap();
+    assert_eq!(tag.id().as_deref(), Some("a"));
+    assert_eq!(tag.attributes().class().unwrap(), "b");
+    assert_eq!(tag.href().as_deref(), Some("/1"));
+    assert_eq!(tag.attributes().len(), 4);
+}
+
 #[test]
 fn boundaries() {
     // https://github.com/y21/tl/issues/25
@@ -900,3 +1320,4834 @@ fn tag_raw_abrupt_stop() {
     let from_raw = first_tag.raw().try_as_utf8_str().unwrap();
     assert_eq!(from_raw, "<p>abcd</p");
 }
+
+#[test]
+fn node_spans() {
+    let input = "<div id=\"a\">x<br><img /><!-- c --><span>y</span></div><p>unclosed";
+    let dom = parse(input, Default::default()).unwrap();
+    let parser = dom.parser();
+    let nodes = dom.nodes();
+
+    let span_of = |idx: usize| &input[nodes[idx].byte_span(parser).unwrap().range()];
+
+    let div = nodes[0].as_tag().unwrap().span();
+    assert_eq!(&input[div.start_tag().range()], "<div id=\"a\">");
+    assert_eq!(&input[div.end_tag().unwrap().range()], "</div>");
+    assert_eq!(span_of(0), &input[..input.find("<p>").unwrap()]);
+
+    assert_eq!(span_of(1), "x");
+    assert_eq!(span_of(2), "<br>");
+    assert_eq!(nodes[2].as_tag().unwrap().span().end_tag(), None);
+    assert_eq!(span_of(3), "<img />");
+    assert_eq!(span_of(4), "<!-- c -->");
+    assert_eq!(span_of(5), "<span>y</span>");
+    assert_eq!(span_of(6), "y");
+
+    let p = nodes[7].as_tag().unwrap().span();
+    assert_eq!(p.end_tag(), None);
+    assert_eq!(&input[p.element().range()], "<p>");
+    assert_eq!(span_of(8), "unclosed");
+}
+
+#[test]
+fn node_span_mutated() {
+    let mut dom = parse("<p>a</p>", Default::default()).unwrap();
+    dom.nodes_mut()[1].as_raw_mut().unwrap().set("b").unwrap();
+    assert_eq!(dom.nodes()[1].byte_span(dom.parser()), None);
+
+    dom.nodes_mut()[1] = Node::Raw("c".into());
+    assert_eq!(dom.nodes()[1].byte_span(dom.parser()), None);
+}
+
+#[test]
+fn siblings() {
+    let dom = parse(
+        "<ul><li>1</li>text<!-- c --><li>2</li></ul><p></p>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let nodes = dom.nodes();
+
+    let ul = parser.handle(0);
+    let first_li = parser.handle(1);
+    let text = parser.handle(3);
+    let comment = parser.handle(4);
+    let last_li = parser.handle(5);
+    let p = parser.handle(7);
+
+    assert_eq!(first_li.prev_sibling(parser), None);
+    assert_eq!(first_li.next_sibling(parser), Some(text));
+    assert_eq!(text.next_sibling(parser), Some(comment));
+    assert_eq!(comment.next_sibling(parser), Some(last_li));
+    assert_eq!(last_li.next_sibling(parser), None);
+    assert_eq!(last_li.prev_sibling(parser), Some(comment));
+    assert_eq!(comment.prev_sibling(parser), Some(text));
+
+    // topmost nodes
+    assert_eq!(ul.next_sibling(parser), Some(p));
+    assert_eq!(p.prev_sibling(parser), Some(ul));
+    assert_eq!(ul.prev_sibling(parser), None);
+
+    let elements: Vec<_> = nodes[0].child_elements(parser).collect();
+    assert_eq!(elements, [first_li, last_li]);
+    assert_eq!(nodes[3].child_elements(parser).count(), 0);
+}
+
+#[test]
+fn strip_attributes() {
+    use crate::strip::AttributePolicy;
+
+    let input = r#"<div id="x" class="y" ONCLICK="a()" data-ga-label="z"><a href="/" data-x="1" ping="/p">a</a></div>"#;
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = AttributePolicy::new().strip_event_handlers();
+    assert_eq!(crate::strip_attributes(&mut dom, &policy), 1);
+    assert!(!dom.nodes()[0]
+        .as_tag()
+        .unwrap()
+        .attributes()
+        .contains("ONCLICK"));
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = AttributePolicy::new().strip_tracking();
+    assert_eq!(crate::strip_attributes(&mut dom, &policy), 2);
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = AttributePolicy::new().allow(&["href", "class"]);
+    assert_eq!(crate::strip_attributes(&mut dom, &policy), 5);
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div class="y"><a href="/">a</a></div>"#
+    );
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = AttributePolicy::new().deny("id").deny("data-x");
+    assert_eq!(crate::strip_attributes(&mut dom, &policy), 2);
+    assert_eq!(dom.get_element_by_id("x"), None);
+}
+
+#[test]
+fn traverse() {
+    use crate::Edge::{Close, Open};
+
+    let dom = parse("<div><p>a</p><br>b</div><!-- c -->", Default::default()).unwrap();
+    let h = |index| dom.parser().handle(index);
+
+    let edges: Vec<_> = dom.traverse().collect();
+    assert_eq!(
+        edges,
+        [
+            Open(h(0)),
+            Open(h(1)),
+            Open(h(2)),
+            Close(h(2)),
+            Close(h(1)),
+            Open(h(3)),
+            Close(h(3)),
+            Open(h(4)),
+            Close(h(4)),
+            Close(h(0)),
+            Open(h(5)),
+            Close(h(5)),
+        ]
+    );
+
+    let empty = parse("", Default::default()).unwrap();
+    assert_eq!(empty.traverse().count(), 0);
+}
+
+#[test]
+fn highlight() {
+    use crate::highlight::Highlighter;
+
+    let dom = parse(
+        "<div>Foo food <b>foo</b><!-- foo --><style>.foo{}</style><br>é foo_x foo</div>",
+        Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        Highlighter::new(&["foo"]).highlight(&dom),
+        "<div>Foo food <b><mark>foo</mark></b><!-- foo --><style>.foo{}</style><br>é foo_x <mark>foo</mark></div>"
+    );
+
+    assert_eq!(
+        Highlighter::new(&["foo"])
+            .ignore_case()
+            .partial_words()
+            .tag("em")
+            .highlight_node(&dom.nodes()[0], dom.parser()),
+        "<div><em>Foo</em> <em>foo</em>d <b><em>foo</em></b><!-- foo --><style>.foo{}</style><br>é <em>foo</em>_x <em>foo</em></div>"
+    );
+
+    let highlighter = Highlighter::new(&["a", "a b", ""]);
+    assert_eq!(
+        highlighter.highlight_text("a b a"),
+        "<mark>a b</mark> <mark>a</mark>"
+    );
+    assert_eq!(highlighter.highlight_text("&amp;a"), "&amp;<mark>a</mark>");
+    assert!(matches!(
+        highlighter.highlight_text("xyz"),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[test]
+fn visitor() {
+    use crate::{DomVisitor, Visit};
+
+    #[derive(Default)]
+    struct Recorder(String);
+
+    impl<'a> DomVisitor<'a> for Recorder {
+        fn visit_tag(&mut self, _: NodeHandle, tag: &HTMLTag<'a>, _: &crate::Parser<'a>) -> Visit {
+            self.0.push_str(&format!("[{}]", tag.name().as_utf8_str()));
+            match tag.name().as_bytes() {
+                b"skip" => Visit::SkipChildren,
+                b"stop" => Visit::Stop,
+                _ => Visit::Continue,
+            }
+        }
+
+        fn leave_tag(&mut self, _: NodeHandle, tag: &HTMLTag<'a>, _: &crate::Parser<'a>) {
+            self.0.push_str(&format!("[/{}]", tag.name().as_utf8_str()));
+        }
+
+        fn visit_text(&mut self, _: NodeHandle, text: &Bytes<'a>, _: &crate::Parser<'a>) -> Visit {
+            self.0.push_str(&text.as_utf8_str());
+            Visit::Continue
+        }
+
+        fn visit_comment(&mut self, _: NodeHandle, _: &Bytes<'a>, _: &crate::Parser<'a>) -> Visit {
+            self.0.push('#');
+            Visit::Continue
+        }
+    }
+
+    let dom = parse(
+        "<div>a<skip>b<i>c</i></skip><!-- x -->d</div><stop>e</stop>f",
+        Default::default(),
+    )
+    .unwrap();
+
+    let mut recorder = Recorder::default();
+    dom.visit(&mut recorder);
+    assert_eq!(recorder.0, "[div]a[skip][/skip]#d[/div][stop]");
+
+    let edges: Vec<_> = {
+        let mut traverse = dom.traverse();
+        let first = traverse.next();
+        traverse.skip_children();
+        first.into_iter().chain(traverse).collect()
+    };
+    assert_eq!(
+        edges[..3],
+        [
+            crate::Edge::Open(dom.parser().handle(0)),
+            crate::Edge::Close(dom.parser().handle(0)),
+            crate::Edge::Open(dom.parser().handle(8)),
+        ]
+    );
+}
+
+#[test]
+fn text_index() {
+    use crate::text_index::{TextIndex, TextPosition, TextRange};
+
+    let dom = parse(
+        "<p>Grüße <b>aus</b></p><script>x()</script><!-- c --><p></p>Berlin",
+        Default::default(),
+    )
+    .unwrap();
+    let index = TextIndex::new(&dom);
+    let h = |index| dom.parser().handle(index);
+    let pos = |node, offset| TextPosition {
+        node: h(node),
+        offset,
+    };
+
+    assert_eq!(index.text(), "Grüße ausBerlin");
+    assert_eq!(index.char_len(), 15);
+
+    assert_eq!(index.position(0), Some(pos(1, 0)));
+    assert_eq!(index.position(4), Some(pos(1, 6)));
+    assert_eq!(index.position(6), Some(pos(3, 0)));
+    assert_eq!(index.position(9), Some(pos(8, 0)));
+    assert_eq!(index.position(15), None);
+
+    assert_eq!(
+        index.range(6..9),
+        Some(TextRange {
+            start: pos(3, 0),
+            end: pos(3, 3)
+        })
+    );
+    assert_eq!(
+        index.range(3..12),
+        Some(TextRange {
+            start: pos(1, 4),
+            end: pos(8, 3)
+        })
+    );
+    assert_eq!(
+        index.range(2..2),
+        Some(TextRange {
+            start: pos(1, 2),
+            end: pos(1, 2)
+        })
+    );
+    assert_eq!(index.range(10..16), None);
+
+    assert_eq!(index.node_range(h(3)), Some(6..9));
+    assert_eq!(index.node_range(h(5)), None);
+    assert_eq!(index.node_range(h(0)), None);
+
+    let empty = TextIndex::new(&parse("", Default::default()).unwrap());
+    assert_eq!(empty.char_len(), 0);
+    assert_eq!(empty.range(0..0), None);
+}
+
+#[test]
+fn intern_dedup() {
+    use crate::intern::Interner;
+
+    let interner = Interner::new();
+    let mut dom = parse(
+        r#"<a href="/x">a</a><a href="/y">b</a><a href="/x">c</a>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    // only owned values are deduplicated, values that point into the source are left alone
+    assert_eq!(interner.dedup(&mut dom), 0);
+    assert!(interner.is_empty());
+
+    for tag in dom.nodes_mut().iter_mut().filter_map(Node::as_tag_mut) {
+        tag.attributes_mut()
+            .get_mut("href")
+            .flatten()
+            .unwrap()
+            .set("/same")
+            .unwrap();
+    }
+
+    let stats = crate::intern::stats(&dom);
+    assert_eq!(stats.values, 6);
+    assert_eq!(stats.unique_values, 4);
+    assert_eq!(stats.duplicate_bytes(), 2 * "/same".len());
+
+    assert_eq!(interner.dedup(&mut dom), 2 * "/same".len());
+    assert_eq!(interner.dedup(&mut dom), 0);
+    assert_eq!(interner.len(), 1);
+
+    let hrefs = dom
+        .nodes()
+        .iter()
+        .filter_map(Node::as_tag)
+        .map(|t| t.attributes().get("href").flatten().unwrap().as_ptr())
+        .collect::<Vec<_>>();
+
+    assert!(hrefs.iter().all(|&p| p == hrefs[0]));
+    assert_eq!(
+        dom.outer_html(),
+        r#"<a href="/same">a</a><a href="/same">b</a><a href="/same">c</a>"#
+    );
+}
+
+mod threads {
+    use crate::{parse, parse_owned, Bytes, HTMLTag, Node, Parser, SharedDom, VDom, VDomGuard};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn auto_traits() {
+        assert_send_sync::<Bytes<'_>>();
+        assert_send_sync::<Node<'_>>();
+        assert_send_sync::<HTMLTag<'_>>();
+        assert_send_sync::<Parser<'_>>();
+        assert_send_sync::<VDom<'_>>();
+        assert_send_sync::<VDomGuard>();
+        assert_send_sync::<SharedDom<'_>>();
+    }
+
+    #[test]
+    fn shared_dom() {
+        let input = (0..100)
+            .map(|i| format!(r#"<p class="c{}">{i}</p>"#, i % 4))
+            .collect::<String>();
+        let dom = parse(&input, Default::default()).unwrap().into_shared();
+
+        let counts = std::thread::scope(|s| {
+            let threads = (0..4)
+                .map(|i| {
+                    let dom = dom.clone();
+                    s.spawn(move || dom.query_selector(&format!("p.c{i}")).unwrap().count())
+                })
+                .collect::<Vec<_>>();
+
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(counts, [25; 4]);
+        assert_eq!(dom.reference_count(), 1);
+
+        let mut dom = dom.try_unwrap().unwrap();
+        dom.children_mut().reverse();
+        assert!(SharedDom::from(dom)
+            .outer_html()
+            .starts_with(r#"<p class="c3">99</p>"#));
+    }
+
+    #[test]
+    fn concurrent_queries() {
+        let input = "<ul>".to_owned()
+            + &(0..500)
+                .map(|i| format!(r#"<li id="i{i}" class="c{}">{i}</li>"#, i % 5))
+                .collect::<String>()
+            + "</ul>";
+
+        let dom = parse(&input, crate::ParserOptions::default().track_ids()).unwrap();
+        let parser = dom.parser();
+
+        std::thread::scope(|s| {
+            let handles = (0..8)
+                .map(|t| {
+                    let dom = &dom;
+                    s.spawn(move || {
+                        let class = format!("li.c{}", t % 5);
+                        let count = dom.query_selector(&class).unwrap().count();
+                        let text = dom
+                            .get_element_by_id(format!("i{t}").as_str())
+                            .and_then(|h| h.get(parser))
+                            .map(|n| n.inner_text(parser).into_owned());
+                        (count, text, dom.outer_html().len())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (t, handle) in handles.into_iter().enumerate() {
+                let (count, text, len) = handle.join().unwrap();
+                assert_eq!(count, 100);
+                assert_eq!(text, Some(t.to_string()));
+                assert_eq!(len, input.len());
+            }
+        });
+    }
+
+    #[test]
+    fn owned_dom_across_threads() {
+        let dom = std::sync::Arc::new(
+            unsafe { parse_owned("<p>Hello</p><p>World</p>".to_owned(), Default::default()) }
+                .unwrap(),
+        );
+
+        let threads = (0..4)
+            .map(|_| {
+                let dom = dom.clone();
+                std::thread::spawn(move || dom.get_ref().query_selector("p").unwrap().count())
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), 2);
+        }
+    }
+}
+
+#[test]
+fn eval_rules() {
+    use crate::errors::EvalError;
+
+    let dom = parse(
+        r#"<div class="item" data-id="a"><h2>  First   Item </h2><a href="/a">Go</a></div>
+        <div class="item"><h2>SECOND</h2><a>Go</a><b title="x|y">!</b></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        dom.eval(".item h2 | collapse").unwrap(),
+        ["First Item", "SECOND"]
+    );
+    assert_eq!(
+        dom.eval("h2 @text() | trim | lower").unwrap(),
+        ["first   item", "second"]
+    );
+    assert_eq!(dom.eval(".item a @href").unwrap(), ["/a"]);
+    assert_eq!(dom.eval("div@data-id|upper").unwrap(), ["A"]);
+    assert_eq!(dom.eval("b[title] @title").unwrap(), ["x|y"]);
+    assert_eq!(
+        dom.eval("b @outer_html()").unwrap(),
+        [r#"<b title="x|y">!</b>"#]
+    );
+    assert_eq!(dom.eval("h2 @html()").unwrap()[1], "SECOND");
+    assert!(dom.eval("table").unwrap().is_empty());
+
+    assert_eq!(dom.eval("@href"), Err(EvalError::InvalidSelector));
+    assert_eq!(dom.eval("a @"), Err(EvalError::InvalidAccessor));
+    assert_eq!(dom.eval("a @text() foo"), Err(EvalError::InvalidAccessor));
+    assert_eq!(
+        dom.eval("a | reverse"),
+        Err(EvalError::UnknownTransform("reverse".into()))
+    );
+}
+
+#[test]
+fn inner_text_with() {
+    use crate::text::TextOptions;
+
+    let dom = parse(
+        "<div>\n  <h1>Title</h1>\n  <p>Some   <i>styled</i>text<br>next line</p>\n  <style>p {}</style>\n  <ul><li>a</li><li> b </li></ul>tail\n</div>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    assert_eq!(
+        div.inner_text_with(parser, TextOptions::new()),
+        div.inner_text(parser)
+    );
+    assert_eq!(
+        div.inner_text_with(parser, TextOptions::readable()),
+        "Title\n\nSome styledtext\nnext line\n\na\nb\ntail"
+    );
+    assert_eq!(
+        div.inner_text_with(parser, TextOptions::new().collapse_whitespace()),
+        "Title Some styledtextnext line p {} a b tail"
+    );
+
+    let dom = parse(
+        "<div><p>a </p><p>b<br>c</p><script>d</script></div>",
+        Default::default(),
+    )
+    .unwrap();
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(
+        div.inner_text_with(dom.parser(), TextOptions::new().line_breaks()),
+        "a \n\nb\nc\n\nd"
+    );
+}
+
+#[test]
+fn structural_hash() {
+    use crate::fingerprint::StructuralHasher;
+
+    let hash =
+        |input, hasher: StructuralHasher| hasher.hash(&parse(input, Default::default()).unwrap());
+    let hasher = StructuralHasher::new();
+
+    let base = hash(
+        r#"<div class="a"><p>Hello</p><!-- x --><p>World</p></div>"#,
+        hasher,
+    );
+    assert_eq!(
+        base,
+        hash(r#"<DIV id="b"><p>Other</p>text<p></p></DIV>"#, hasher)
+    );
+    assert_ne!(base, hash(r#"<div><p>Hello</p></div><p>World</p>"#, hasher));
+    assert_ne!(base, hash(r#"<div><p>Hello<p>World</p></p></div>"#, hasher));
+
+    // the hash must be stable across releases
+    assert_eq!(hash("", hasher), 0xcbf2_9ce4_8422_2325);
+
+    let hasher = StructuralHasher::new().attribute_names();
+    assert_eq!(
+        hash(r#"<a href="/" title="x"></a>"#, hasher),
+        hash(r#"<a TITLE="y" href="/z"></a>"#, hasher)
+    );
+    assert_ne!(
+        hash(r#"<a href="/" title="x"></a>"#, hasher),
+        hash(r#"<a href="/"></a>"#, hasher)
+    );
+
+    let hasher = StructuralHasher::new().max_depth(2);
+    assert_eq!(
+        hash("<div><p><b>x</b></p></div>", hasher),
+        hash("<div><p><i>y</i><i>z</i></p></div>", hasher)
+    );
+    assert_ne!(
+        hash("<div><p></p></div>", hasher),
+        hash("<div><span></span></div>", hasher)
+    );
+    assert_eq!(
+        hash("<div></div>", StructuralHasher::new().max_depth(0)),
+        hash("", hasher)
+    );
+
+    let dom = parse("<div><p>a</p></div><p>b</p>", Default::default()).unwrap();
+    let p = NodeHandle::new(3);
+    assert_eq!(
+        StructuralHasher::new().hash_node(p, dom.parser()),
+        hash("<p></p>", StructuralHasher::new())
+    );
+}
+
+#[test]
+fn markdown() {
+    use crate::markdown::{node_to_markdown, to_markdown};
+
+    fn md(input: &str) -> String {
+        to_markdown(&parse(input, Default::default()).unwrap())
+    }
+
+    assert_eq!(md("<h2>A <i>b</i></h2><p>c</p>"), "## A *b*\n\nc");
+    assert_eq!(md("<p>a <b> bold </b>b</p>"), "a **bold** b");
+    assert_eq!(md("<p>1 * 2 = <code>a*b</code></p>"), "1 \\* 2 = `a*b`");
+    assert_eq!(md(r#"<img src="a b.png" alt="x">"#), "![x](a%20b.png)");
+    assert_eq!(md("a<br>b<hr>c"), "a  \nb\n\n---\n\nc");
+    assert_eq!(
+        md("<script>x()</script><head><title>t</title></head>x"),
+        "x"
+    );
+
+    assert_eq!(
+        md("<ol><li>One<ul><li>A</li><li>B</li></ul></li><li><p>Two</p></li></ol>"),
+        "1. One\n\n   - A\n   - B\n2. Two"
+    );
+    assert_eq!(
+        md("<blockquote><p>a</p><p>b</p></blockquote>"),
+        "> a\n>\n> b"
+    );
+    assert_eq!(
+        md("<pre><code class=\"language-rust\">fn main() {\n    ```\n}</code></pre>"),
+        "````rust\nfn main() {\n    ```\n}\n````"
+    );
+    assert_eq!(
+        md("<table><thead><tr><th>A</th><th>B</th></tr></thead><tbody><tr><td>1|2</td></tr></tbody></table>"),
+        "| A | B |\n| --- | --- |\n| 1\\|2 |  |"
+    );
+
+    let dom = parse(
+        "<div><p>a</p><a href=\"/x\">b</a></div>",
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        node_to_markdown(NodeHandle::new(3), dom.parser()),
+        "[b](/x)"
+    );
+}
+
+#[test]
+fn sanitize() {
+    use crate::sanitize::SanitizePolicy;
+
+    fn clean(input: &str, policy: &SanitizePolicy) -> String {
+        crate::sanitize(&parse(input, Default::default()).unwrap(), policy)
+    }
+
+    let policy = SanitizePolicy::new();
+    assert_eq!(clean("<div>a <b>b</b><!-- c --></div>", &policy), "a b");
+    assert_eq!(
+        clean("a<script>alert(1)</script><style>*{}</style>b", &policy),
+        "ab"
+    );
+
+    let policy = SanitizePolicy::new()
+        .allow_tags(&["a", "img", "br", "script"])
+        .allow_attributes("a", &["href"])
+        .allow_attributes("*", &["title"])
+        .allow_url_schemes(&["https"]);
+
+    assert_eq!(
+        clean(
+            r#"<A HREF="https://x" Title='a"b' onclick="y()">x</A>"#,
+            &policy
+        ),
+        r#"<a href="https://x" title="a&quot;b">x</a>"#
+    );
+    assert_eq!(
+        clean("<img title=x><br>", &policy),
+        r#"<img title="x"><br>"#
+    );
+    assert_eq!(clean("<script>x</script>", &policy), "");
+
+    for url in [
+        "javascript:alert(1)",
+        " JavaScript:alert(1)",
+        "java\tscript:alert(1)",
+        "javascript&#58;alert(1)",
+        "http://x",
+    ] {
+        let input = format!(r#"<a href="{url}">x</a>"#);
+        assert_eq!(clean(&input, &policy), "<a>x</a>", "{url}");
+    }
+
+    for url in [
+        "/path:with:colons",
+        "?a=b:c",
+        "#x",
+        "page.html",
+        "HTTPS://x",
+    ] {
+        let input = format!(r#"<a href="{url}">x</a>"#);
+        assert_eq!(
+            clean(&input, &policy),
+            format!(r#"<a href="{url}">x</a>"#),
+            "{url}"
+        );
+    }
+
+    let policy = SanitizePolicy::basic().remove_content(&["table"]);
+    assert_eq!(
+        clean("<p>x<table><tr><td>y</td></tr></table></p>", &policy),
+        "<p>x</p>"
+    );
+}
+
+#[test]
+fn extract_article() {
+    use crate::extract::article;
+
+    let paragraph =
+        "<p>Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod.</p>";
+
+    let input = format!(
+        r#"<meta property="og:title" content="OG title"><title>Title</title>
+        <div id="comments">{paragraph}{paragraph}{paragraph}</div>
+        <main><span class="byline">By   someone</span><section>{paragraph}{paragraph}</section></main>
+        <div><p><a href="/">{paragraph}</a></p><p><a href="/">{paragraph}</a></p></div>"#
+    );
+    let dom = parse(&input, Default::default()).unwrap();
+    let result = article(&dom);
+
+    let content = result.content.unwrap().get(dom.parser()).unwrap();
+    assert_eq!(content.as_tag().unwrap().name(), "main");
+    assert_eq!(result.title.as_deref(), Some("OG title"));
+    assert_eq!(result.byline.as_deref(), Some("By someone"));
+
+    let dom = parse("<h1> Heading </h1><p>short</p>", Default::default()).unwrap();
+    assert_eq!(
+        article(&dom),
+        crate::extract::Article {
+            content: None,
+            title: Some("Heading".into()),
+            byline: None,
+        }
+    );
+}
+
+#[test]
+fn conformance_fixtures() {
+    use crate::conformance::parse_tree_tests;
+
+    let fixtures = "#data\nTest\n#errors\n(1,0): expected-doctype-but-got-chars\n#document\n| <html>\n|   <head>\n|   <body>\n|     \"Test\"\n\n#data\n<b id=x CLASS=y>a\n#b</b>\n#errors\n#document-fragment\ntd\n#script-on\n#document\n| <b>\n|   class=\"y\"\n|   id=\"x\"\n|   \"a\n#b\"\n";
+    let tests = parse_tree_tests(fixtures);
+
+    assert_eq!(tests.len(), 2);
+    assert_eq!(tests[0].data, "Test");
+    assert_eq!(tests[0].errors, ["(1,0): expected-doctype-but-got-chars"]);
+    assert_eq!(tests[0].document_fragment, None);
+    assert_eq!(tests[0].scripting, None);
+    assert_eq!(tests[0].run(), Err("| \"Test\"".to_owned()));
+
+    assert_eq!(tests[1].data, "<b id=x CLASS=y>a\n#b</b>");
+    assert!(tests[1].errors.is_empty());
+    assert_eq!(tests[1].document_fragment, Some("td"));
+    assert_eq!(tests[1].scripting, Some(true));
+    assert_eq!(tests[1].run(), Ok(()));
+
+    assert!(parse_tree_tests("").is_empty());
+
+    let dom = parse(r#"<div id="a">Hi<!-- x --></div>"#, Default::default()).unwrap();
+    assert_eq!(
+        crate::conformance::dump(&dom),
+        "| <div>\n|   id=\"a\"\n|   \"Hi\"\n|   <!--  x  -->"
+    );
+}
+
+#[test]
+fn extract_metadata() {
+    let input = r#"
+        <head>
+            <meta property="og:image" content=" /a.png ">
+            <meta name="og:image" content="/b.png">
+            <meta property="og:description">
+            <meta name="TWITTER:title" content="Title">
+            <meta name="description" content="ignored">
+            <link rel="canonical" href="/first"><link rel="canonical" href="/second">
+            <link rel="icon" href="/favicon.ico"><link rel="apple-touch-icon" href="/touch.png">
+            <link rel="stylesheet" href="/style.css">
+        </head>
+        <script type="application/ld+json">
+            {"a": 1}
+        </script>
+        <script>{"b": 2}</script>
+    "#;
+    let dom = parse(input, Default::default()).unwrap();
+    let metadata = dom.extract_metadata();
+
+    assert_eq!(
+        metadata.open_graph,
+        [
+            ("og:image".to_owned(), "/a.png".to_owned()),
+            ("og:image".to_owned(), "/b.png".to_owned())
+        ]
+    );
+    assert_eq!(metadata.open_graph("OG:IMAGE"), Some("/a.png"));
+    assert_eq!(metadata.open_graph("og:title"), None);
+    assert_eq!(metadata.twitter("twitter:title"), Some("Title"));
+    assert_eq!(metadata.canonical.as_deref(), Some("/first"));
+    assert_eq!(metadata.icons, ["/favicon.ico", "/touch.png"]);
+    assert_eq!(metadata.json_ld, [r#"{"a": 1}"#]);
+
+    let dom = parse("<p>nothing</p>", Default::default()).unwrap();
+    assert_eq!(dom.extract_metadata(), Default::default());
+}
+
+#[test]
+fn html_cache() {
+    use crate::html_cache::HtmlCache;
+
+    let mut dom = parse(
+        "<div><p>a<br></p><!-- c --></div><ul><li>x</li></ul>text",
+        Default::default(),
+    )
+    .unwrap();
+    let mut cache = HtmlCache::new();
+
+    assert_eq!(cache.document_html(&dom), dom.outer_html());
+    // div, p, br, ul, li
+    assert_eq!(cache.len(), 5);
+
+    let br = NodeHandle::new(3);
+    let li = NodeHandle::new(6);
+    *li.get_mut(dom.parser_mut())
+        .unwrap()
+        .as_tag_mut()
+        .unwrap()
+        .name_mut() = "ol".into();
+
+    // without invalidating, the stale markup is returned
+    assert_eq!(cache.outer_html(li, dom.parser()), "<li>x</li>");
+
+    cache.invalidate(li, dom.parser());
+    assert!(!cache.contains(li));
+    assert!(!cache.contains(NodeHandle::new(5)));
+    assert!(cache.contains(br));
+
+    assert_eq!(cache.outer_html(li, dom.parser()), "<ol>x</ol>");
+    assert_eq!(cache.document_html(&dom), dom.outer_html());
+    assert_eq!(cache.len(), 5);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn links() {
+    use crate::links::{resolve, LinkKind};
+
+    let input = r#"
+        <base href="/docs/">
+        <a href=" guide.html ">Guide</a><a>no href</a><a href="">empty</a>
+        <area href="map"><script src="app.js"></script><iframe src="https://other.example/embed"></iframe>
+        <img srcset="small.png 480w, large.png 1080w">
+    "#;
+    let dom = parse(input, Default::default()).unwrap();
+
+    let links = dom.links(Some("https://example.com/index.html"));
+    let summary = links
+        .iter()
+        .map(|link| (link.url.as_str(), link.kind))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        summary,
+        [
+            ("https://example.com/docs/guide.html", LinkKind::Anchor),
+            ("https://example.com/docs/map", LinkKind::Anchor),
+            ("https://example.com/docs/app.js", LinkKind::Script),
+            ("https://other.example/embed", LinkKind::Frame),
+            ("https://example.com/docs/small.png", LinkKind::Image),
+            ("https://example.com/docs/large.png", LinkKind::Image),
+        ]
+    );
+    assert_eq!(
+        links[0]
+            .node
+            .get(dom.parser())
+            .unwrap()
+            .inner_text(dom.parser()),
+        "Guide"
+    );
+    assert_eq!(links[0].rel, None);
+
+    // the relative base is used as is without a base URL
+    let links = dom.links(None);
+    assert_eq!(links[0].url, "guide.html");
+
+    let base = "http://a/b/c/d;p?q";
+    for (reference, expected) in [
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        ("g?y#s", "http://a/b/c/g?y#s"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../..", "http://a/"),
+        ("../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("g.", "http://a/b/c/g."),
+        ("./../g", "http://a/b/g"),
+        ("g;x=1/../y", "http://a/b/c/y"),
+    ] {
+        assert_eq!(resolve(base, reference), expected, "{reference}");
+    }
+
+    assert_eq!(resolve("https://example.com", "a"), "https://example.com/a");
+    assert_eq!(resolve("relative/base", "a"), "a");
+}
+
+#[test]
+fn redact() {
+    use crate::redact::RedactPolicy;
+
+    let input = r#"<div class="user" data-id="42"> <span>Jane</span> <span class="user">Doe</span> </div><p>public</p>"#;
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = RedactPolicy::new()
+        .mask('x')
+        .attributes(&["data-id", "missing"]);
+    assert_eq!(crate::redact(&mut dom, &[".user"], &policy), Some(2));
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div data-id="xx" class="user"> <span>xxxx</span> <span class="user">xxx</span> </div><p>public</p>"#
+    );
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    let policy = RedactPolicy::new()
+        .placeholder("?")
+        .keep_text()
+        .attributes(&["class"]);
+    assert_eq!(
+        crate::redact(&mut dom, &["div", "p", "span"], &policy),
+        Some(4)
+    );
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div data-id="42" class="?"> <span>Jane</span> <span class="?">Doe</span> </div><p>public</p>"#
+    );
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    assert_eq!(
+        crate::redact(&mut dom, &["p", "[invalid"], &Default::default()),
+        None
+    );
+    assert_eq!(
+        dom.outer_html(),
+        parse(input, Default::default()).unwrap().outer_html()
+    );
+
+    assert_eq!(RedactPolicy::new().replace("  a b \n"), "  [REDACTED] \n");
+    assert_eq!(RedactPolicy::new().replace(" "), " ");
+}
+
+#[test]
+fn implied_head_and_body() {
+    let dom = parse(
+        "<!DOCTYPE html><html><head><title>a</title></head><body><p>b</p></body></html>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    assert!(!dom.head().is_implied());
+    assert_eq!(dom.head().inner_html(parser), "<title>a</title>");
+    assert_eq!(dom.body().inner_html(parser), "<p>b</p>");
+
+    // the head is given, the body is not
+    let dom = parse(
+        "<html><head><title>a</title></head>\n<p>b</p> <!-- c --></html>x",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    assert!(dom.body().is_implied());
+    assert_eq!(dom.body().inner_html(parser), "<p>b</p> <!-- c -->x");
+
+    // neither is given, metadata ends at the first content
+    let dom = parse(
+        "\n<meta charset=utf-8> <!-- x --> <link rel=icon href=\"/i.png\">\n<h1>a</h1><style></style>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let head = dom.head();
+    assert!(head.is_implied());
+    assert_eq!(head.handle(), None);
+    assert_eq!(
+        head.inner_html(parser),
+        r#"<meta charset="utf-8"> <!-- x --> <link rel="icon" href="/i.png">"#
+    );
+    assert_eq!(dom.body().inner_html(parser), "<h1>a</h1><style></style>");
+
+    let dom = parse("<!-- x --><p>a</p>", Default::default()).unwrap();
+    assert!(dom.head().children().is_empty());
+    assert_eq!(dom.body().children().len(), 2);
+
+    let dom = parse("", Default::default()).unwrap();
+    assert!(dom.head().children().is_empty());
+    assert!(dom.body().children().is_empty());
+}
+
+#[test]
+fn forms() {
+    use crate::forms::FieldKind;
+
+    let input = r#"
+        <form id="f">
+            <input name="text" value="a&b=c">
+            <input name="empty">
+            <input value="no name">
+            <input name="disabled" value="x" disabled>
+            <input type="CHECKBOX" name="unchecked" value="1">
+            <input type="radio" name="choice" value="r1">
+            <input type="radio" name="choice" value="r2" checked>
+            <input type="submit" name="go" value="Go">
+            <textarea name="comment">
+line 1
+line 2</textarea>
+            <select name="multi" multiple>
+                <optgroup><option selected disabled>skip</option><option value="1" selected> One </option></optgroup>
+                <option selected>Two  words</option>
+            </select>
+            <select name="first"><option disabled>x</option><option>y</option></select>
+            <select name="none" multiple><option>z</option></select>
+        </form>
+        <input name="outside" value="o" form="f">
+        <input name="unrelated">
+        <form><input name="second"></form>
+    "#;
+    let dom = parse(input, Default::default()).unwrap();
+    let forms = dom.forms();
+
+    assert_eq!(forms.len(), 2);
+    assert_eq!(forms[0].action, None);
+    assert_eq!(forms[0].method, "get");
+
+    let form = &forms[0];
+    assert_eq!(form.fields.len(), 13);
+    assert_eq!(form.fields[4].kind, FieldKind::Input("checkbox".into()));
+
+    let FieldKind::Select { multiple, options } = &form.fields[9].kind else {
+        panic!("expected a select");
+    };
+    assert!(*multiple);
+    assert_eq!(options.len(), 3);
+    assert_eq!(options[1].value, "1");
+    assert_eq!(options[1].label, "One");
+    assert_eq!(options[2].value, "Two words");
+
+    let pairs = form
+        .serialize()
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pairs,
+        [
+            "text=a&b=c",
+            "empty=",
+            "choice=r2",
+            "comment=line 1\nline 2",
+            "multi=1",
+            "multi=Two words",
+            "first=y",
+            "outside=o",
+        ]
+    );
+    assert_eq!(
+        form.urlencode(),
+        "text=a%26b%3Dc&empty=&choice=r2&comment=line+1%0Aline+2&multi=1&multi=Two+words&first=y&outside=o"
+    );
+
+    assert_eq!(forms[1].urlencode(), "second=");
+}
+
+#[test]
+fn as_table() {
+    let dom = parse(
+        r#"<table>
+            <caption>Results</caption>
+            <tr><th>Name</th><th colspan="2">Score</th><th>Note</th></tr>
+            <tr><td rowspan="3">Ann</td><td>1</td><td>2</td><td rowspan="9"> good
+                <b>work</b> </td></tr>
+            <tr><td colspan="2">3</td></tr>
+            <tr><td>4</td></tr>
+            <tr><td>Bob<table><tr><td>nested</td></tr></table></td></tr>
+        </table><div></div>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let tag = |idx: usize| dom.nodes()[idx].as_tag().unwrap();
+
+    assert_eq!(tag(dom.nodes().len() - 1).as_table(parser), None);
+
+    let table = tag(0).as_table(parser).unwrap();
+    let texts = table
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|c| c.text.as_str()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        texts,
+        [
+            ["Name", "Score", "Score", "Note"],
+            ["Ann", "1", "2", "good work"],
+            ["Ann", "3", "3", "good work"],
+            ["Ann", "4", "", "good work"],
+            ["Bob nested", "", "", "good work"],
+        ]
+    );
+    assert_eq!(table.header_rows, 1);
+    assert_eq!(table.columns(), 4);
+
+    let note = &table.rows[4][3];
+    assert_eq!((note.row_span, note.origin), (4, (1, 3)));
+    assert!(!note.is_origin(4, 3));
+    assert!(table.rows[1][3].is_origin(1, 3));
+    assert_eq!(table.rows[3][2].node, None);
+
+    let records = table.records();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[2]["Name"], "Ann");
+    assert_eq!(records[2]["Score"], "");
+    assert_eq!(records[3]["Name"], "Bob nested");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_json_tree() {
+    let dom = parse(
+        r#"<div id="a" class="b" data-z="1" hidden data-y="2"><br>text</div>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let attrs = r#"{"class":"b","data-y":"2","data-z":"1","hidden":null,"id":"a"}"#;
+    let tree = format!(
+        r#"{{"tag":"div","attrs":{attrs},"children":[{{"tag":"br","attrs":{{}},"children":[]}},{{"text":"text"}}]}}"#
+    );
+
+    assert_eq!(serde_json::to_string(&dom).unwrap(), format!("[{tree}]"));
+    assert_eq!(
+        serde_json::to_string(&dom.nodes()[0].serialize_with(parser)).unwrap(),
+        tree
+    );
+    assert_eq!(
+        serde_json::to_string(dom.nodes()).unwrap(),
+        format!(
+            r#"[{{"tag":"div","attrs":{attrs},"children":[1,2]}},{{"tag":"br","attrs":{{}},"children":[]}},{{"text":"text"}}]"#
+        )
+    );
+}
+
+#[test]
+fn document_order() {
+    for input in [
+        "<div><p>a<b>b</b></p>c<br>d<!-- e --></div><span>f</span>",
+        "<ul><li>a<li>b</ul></div><p>unclosed<i>c",
+        "text<a><b></a></b><img src=x>",
+    ] {
+        let dom = parse(input, ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+
+        let opened = dom
+            .traverse()
+            .filter_map(|edge| match edge {
+                crate::Edge::Open(handle) => Some(handle.document_order()),
+                crate::Edge::Close(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(opened, (0..dom.nodes().len() as u32).collect::<Vec<_>>());
+
+        for (idx, node) in dom.nodes().iter().enumerate() {
+            let handle = NodeHandle::new(idx as u32);
+
+            if let Some(end) = node.children().and_then(|c| c.end(parser)) {
+                assert!(handle.document_order() < end);
+            }
+        }
+    }
+}
+
+#[test]
+fn unmarshal() {
+    use crate::errors::{EvalError, UnmarshalError};
+    use crate::unmarshal::Extractor;
+
+    let dom = parse(
+        r#"<h1> Catalog </h1>
+        <ul>
+            <li><a href="/a">A</a><span class="qty">3</span></li>
+            <li><a href="/b">B</a><span class="qty">many</span></li>
+            <li><a href="/c">C</a></li>
+        </ul>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let record = Extractor::new()
+        .field("title", "h1 | trim")
+        .field("first", "a @href")
+        .list("links", "a @href")
+        .field("missing", "table")
+        .extract(&dom)
+        .unwrap();
+
+    assert_eq!(record.get("title"), Some("Catalog"));
+    assert_eq!(record.get_all("first"), ["/a"]);
+    assert_eq!(record.get_all("links"), ["/a", "/b", "/c"]);
+    assert_eq!(record.get("missing"), None);
+    assert_eq!(record.get_all("unknown"), [] as [String; 0]);
+    assert_eq!(
+        record.required("missing"),
+        Err(UnmarshalError::MissingField("missing".into()))
+    );
+
+    let items = Extractor::new()
+        .field("name", "a")
+        .field("qty", ".qty")
+        .extract_each(&dom, "li")
+        .unwrap();
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].parse::<u32>("qty"), Ok(Some(3)));
+    assert_eq!(items[2].parse::<u32>("qty"), Ok(None));
+    assert_eq!(
+        items[1].parse::<u32>("qty"),
+        Err(UnmarshalError::InvalidValue {
+            field: "qty".into(),
+            value: "many".into()
+        })
+    );
+
+    assert_eq!(
+        Extractor::new().field("x", "a @").extract(&dom),
+        Err(UnmarshalError::InvalidRule {
+            field: "x".into(),
+            error: EvalError::InvalidAccessor
+        })
+    );
+    assert_eq!(
+        Extractor::new().extract_each(&dom, "").map(|r| r.len()),
+        Err(UnmarshalError::InvalidSelector)
+    );
+}
+
+#[test]
+fn preformatted_text() {
+    use crate::text::TextOptions;
+
+    let dom = parse(
+        "<div>\n  a   b\n<pre>\n  x  <b>y</b>\n\n</pre><p>Use <code>f( 1,  2 )</code> or <kbd>Ctrl  C</kbd></p><textarea>\r\n\n t </textarea></div>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    assert_eq!(
+        div.inner_text_with(parser, TextOptions::new().collapse_whitespace()),
+        "a b x y Use f( 1, 2 ) or Ctrl C t"
+    );
+    assert_eq!(
+        div.inner_text_with(parser, TextOptions::readable()),
+        "a b\n  x  y\n\nUse f( 1,  2 ) or Ctrl C\n\n\n t "
+    );
+    assert_eq!(
+        div.inner_text_with(
+            parser,
+            TextOptions::readable().preformatted_elements(&["KBD"])
+        ),
+        "a b\n  x  y\n\nUse f( 1,  2 ) or Ctrl  C\n\n\n t "
+    );
+    assert_eq!(
+        crate::markdown::to_markdown(&dom),
+        "a b\n\n```\n  x  y\n```\n\nUse `f( 1,  2 )` or Ctrl C\n\nt"
+    );
+}
+
+#[test]
+fn anchors() {
+    let dom = parse(
+        r#"<a href="/top">Top</a>
+        <h1>Title <a href="HTTPS://User@Example.COM:8443/x">self</a></h1>
+        <h2>Links</h2>
+        <ul>
+            <li><a href="https://a.org/1" rel="external">  One <script>x</script></a></li>
+            <li><a href="http://[::1]:80/">local</a></li>
+            <li><a href="https://b.org" rel="nofollow ugc"></a></li>
+            <li><a href="https://a.org/2">Two</a></li>
+            <li><a href="javascript:void(0)">JS</a></li>
+            <li><a>no href</a></li>
+        </ul>
+        <map><area href="https://b.org/map" alt="Map"></map>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let anchors = dom.anchors(Some("https://example.com/page"));
+    let rows = anchors
+        .iter()
+        .map(|a| (a.url.as_str(), a.text.as_str(), a.heading.as_deref()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        rows,
+        [
+            ("https://example.com/top", "Top", None),
+            (
+                "HTTPS://User@Example.COM:8443/x",
+                "self",
+                Some("Title self")
+            ),
+            ("https://a.org/1", "One", Some("Links")),
+            ("http://[::1]:80/", "local", Some("Links")),
+            ("https://b.org", "", Some("Links")),
+            ("https://a.org/2", "Two", Some("Links")),
+            ("javascript:void(0)", "JS", Some("Links")),
+            ("https://b.org/map", "Map", Some("Links")),
+        ]
+    );
+    assert!(anchors[4].has_rel("UGC"));
+    assert!(!anchors[2].has_rel("nofollow"));
+
+    let summary = dom.link_summary(Some("https://example.com/page"));
+    assert_eq!(
+        (
+            summary.total,
+            summary.internal,
+            summary.external,
+            summary.other,
+            summary.nofollow
+        ),
+        (8, 2, 5, 1, 1)
+    );
+    assert_eq!(
+        summary.hosts,
+        [
+            ("a.org".to_owned(), 2),
+            ("b.org".to_owned(), 2),
+            ("[::1]".to_owned(), 1)
+        ]
+    );
+
+    let summary = dom.link_summary(None);
+    assert_eq!((summary.internal, summary.external), (1, 6));
+}
+
+#[test]
+fn xpath() {
+    use crate::errors::XPathError;
+    use crate::xpath::{Item, Value, XPath};
+
+    let input = r#"
+        <div id="main">
+            <h1>Title</h1>
+            <ul>
+                <li class="x" data-n="1">one</li>
+                <li data-n="2">two <b>bold</b></li>
+                <li class="x" data-n="3">three</li>
+            </ul>
+            <!-- note -->
+        </div>
+        <p>after</p>
+    "#;
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+
+    let texts = |expression: &str| {
+        dom.xpath(expression)
+            .unwrap()
+            .iter()
+            .map(|item| item.string_value(&dom).trim().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(texts("//li"), ["one", "two bold", "three"]);
+    assert_eq!(texts("/div/ul/li[2]"), ["two bold"]);
+    assert_eq!(texts("//LI[last()]"), ["three"]);
+    assert_eq!(texts("//li[@class='x'][2]"), ["three"]);
+    assert_eq!(texts("//li[@data-n > 1 and not(@class)]"), ["two bold"]);
+    assert_eq!(texts("//li[position() mod 2 = 1]/@data-n"), ["1", "3"]);
+    assert_eq!(texts("//li/@*"), ["x", "1", "2", "x", "3"]);
+    assert_eq!(texts("//b/ancestor::*[1]"), ["two bold"]);
+    assert_eq!(texts("//b/ancestor::*[last()]/@id"), ["main"]);
+    assert_eq!(texts("//li[1]/following-sibling::li[1]"), ["two bold"]);
+    assert_eq!(texts("//li[3]/preceding-sibling::li[1]"), ["two bold"]);
+    assert_eq!(texts("//b/../@data-n"), ["2"]);
+    assert_eq!(texts("//li[b]/text()"), ["two"]);
+    assert_eq!(texts("//comment()"), ["note"]);
+    assert_eq!(texts("//h1 | //p | //h1"), ["Title", "after"]);
+    assert_eq!(texts("(//li)[last()]"), ["three"]);
+    assert_eq!(
+        texts("//li[starts-with(., 't')][contains(., 'ee')]"),
+        ["three"]
+    );
+    assert_eq!(texts("//*[name() = 'h1']"), ["Title"]);
+    assert_eq!(texts("//li[. = 'one']"), ["one"]);
+
+    // the document comes first in document order, and attributes come right after their element
+    let items = dom.xpath("//ul/@* | //li[1]/@data-n | //ul | /").unwrap();
+    assert_eq!(items[0], Item::Document);
+    assert_eq!(
+        items[1]
+            .handle()
+            .unwrap()
+            .get(parser)
+            .unwrap()
+            .as_tag()
+            .unwrap()
+            .name(),
+        "ul"
+    );
+    assert!(
+        matches!(&items[2], Item::Attribute { name, value, .. } if name == "data-n" && value == "1")
+    );
+    assert_eq!(items.len(), 3);
+
+    let evaluate = |expression: &str| XPath::parse(expression).unwrap().evaluate(&dom);
+
+    assert_eq!(evaluate("count(//li) * 2 - 1"), Value::Number(5.0));
+    assert_eq!(evaluate("sum"), Value::Nodes(Vec::new()));
+    assert_eq!(evaluate("-(3 div 2)"), Value::Number(-1.5));
+    assert_eq!(evaluate("//li/@data-n = 3"), Value::Boolean(true));
+    assert_eq!(evaluate("//li/@data-n != 3"), Value::Boolean(true));
+    assert_eq!(evaluate("//li/@data-n > 3"), Value::Boolean(false));
+    assert_eq!(evaluate("2 < //li/@data-n"), Value::Boolean(true));
+    assert_eq!(evaluate("//nothing = ''"), Value::Boolean(false));
+    assert_eq!(
+        evaluate("concat(string(//h1), '-', string-length('abc'))"),
+        Value::String("Title-3".into())
+    );
+    assert_eq!(
+        evaluate("translate(normalize-space('  a  b '), 'ab', 'B')"),
+        Value::String("B ".into())
+    );
+    assert_eq!(evaluate("number('x') = number('x')"), Value::Boolean(false));
+    assert_eq!(evaluate("boolean(//p) and true()"), Value::Boolean(true));
+
+    let li = dom.xpath("//li[2]").unwrap().remove(0);
+    let xpath = XPath::parse("count(../li) + count(b)").unwrap();
+    assert_eq!(xpath.evaluate_from(&dom, li), Value::Number(4.0));
+
+    assert_eq!(dom.xpath("//li["), Err(XPathError::UnexpectedEnd));
+    assert_eq!(dom.xpath("//li]"), Err(XPathError::UnexpectedToken(4)));
+    assert_eq!(dom.xpath("'abc"), Err(XPathError::UnexpectedEnd));
+    assert_eq!(
+        dom.xpath("following::li"),
+        Err(XPathError::UnknownAxis("following".into()))
+    );
+    assert_eq!(
+        dom.xpath("sum(//li)"),
+        Err(XPathError::UnknownFunction("sum".into()))
+    );
+    assert_eq!(
+        dom.xpath("contains('a')"),
+        Err(XPathError::InvalidArguments("contains".into()))
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parse_many() {
+    let inputs = (0..100)
+        .map(|i| format!("<p id=\"p{i}\">{i}</p>"))
+        .collect::<Vec<_>>();
+
+    let doms = crate::parse_many(inputs, ParserOptions::default());
+    assert_eq!(doms.len(), 100);
+
+    for (i, dom) in doms.iter().enumerate() {
+        let dom = dom.as_ref().unwrap().get_ref();
+        let p = dom.get_element_by_id(format!("p{i}").as_str()).unwrap();
+        assert_eq!(
+            p.get(dom.parser()).unwrap().inner_text(dom.parser()),
+            i.to_string()
+        );
+    }
+
+    assert!(crate::parse_many(Vec::new(), ParserOptions::default()).is_empty());
+}
+
+#[test]
+fn reuse_allocations() {
+    let options = ParserOptions::default().with_capacity(10, 4800).track_ids();
+    assert_eq!(options.node_capacity(), 100);
+
+    let dom = parse(r#"<p id="a">x</p>"#, options).unwrap();
+    assert!(dom.parser().tags.capacity() >= 100);
+
+    let big = "<p>x</p>".repeat(500);
+    let dom = dom.reparse(&big).unwrap();
+    let capacity = dom.parser().tags.capacity();
+    let ptr = dom.parser().tags.as_ptr() as usize;
+    assert_eq!(dom.nodes().len(), 1000);
+
+    // the node table is reused, and no state carries over from the previous document
+    let dom = dom.reparse(r#"<div id="b"><p>y</p></div>"#).unwrap();
+    assert_eq!(dom.parser().tags.capacity(), capacity);
+    assert_eq!(dom.parser().tags.as_ptr() as usize, ptr);
+    assert_eq!(dom.nodes().len(), 3);
+    assert_eq!(dom.children().len(), 1);
+    assert!(dom.get_element_by_id("a").is_none());
+    assert_eq!(dom.get_element_by_id("b"), Some(dom.parser().handle(0)));
+    assert_eq!(dom.parser().options, options);
+}
+
+#[test]
+fn srcset_and_style() {
+    use crate::microsyntax::{srcset, Descriptor, StyleDeclarations};
+
+    let candidates = srcset(
+        " a.png 1.5x,b.png,, c(1).png 100w 50h , d.png 2x 2x, e.png 0w, f.png 10h, g.png -1x",
+    );
+    let urls = candidates.iter().map(|c| c.url).collect::<Vec<_>>();
+    assert_eq!(urls, ["a.png", "b.png", "c(1).png"]);
+    assert_eq!(candidates[0].descriptor, Descriptor::Density(1.5));
+    assert_eq!(candidates[1].descriptor, Descriptor::Density(1.0));
+    assert_eq!(
+        candidates[2].descriptor,
+        Descriptor::Width {
+            width: 100,
+            height: Some(50)
+        }
+    );
+    assert!(srcset("").is_empty());
+    assert_eq!(srcset("x.png foo(1, 2) 1x").len(), 0);
+    assert_eq!(srcset("x.png 1x (y, z)").len(), 0);
+
+    let declarations = |style| StyleDeclarations::new(style).collect::<Vec<_>>();
+    assert_eq!(declarations(""), []);
+    assert_eq!(declarations(";:;color;:red"), []);
+    assert_eq!(
+        declarations("/* a */ color /* b */ : /* c */ red /* d */;x:'\\';'"),
+        [("color", "red"), ("x", "'\\';'")]
+    );
+    assert_eq!(
+        declarations("content: \"a;b\"; grid: calc(1px; 2px) /* ; */"),
+        [("content", "\"a;b\""), ("grid", "calc(1px; 2px)")]
+    );
+    assert_eq!(declarations("a: b /* unterminated ; c: d"), [("a", "b")]);
+
+    let dom = parse(
+        r#"<img srcset="a.png 2x" style="width: 10px"><img>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    assert_eq!(attributes.srcset().len(), 1);
+    assert_eq!(attributes.style().collect::<Vec<_>>(), [("width", "10px")]);
+    let attributes = dom.nodes()[1].as_tag().unwrap().attributes();
+    assert!(attributes.srcset().is_empty());
+    assert_eq!(attributes.style().next(), None);
+}
+
+#[test]
+fn query_selector_lookup_tables() {
+    let input = r#"
+        <div id="main">
+            <ul class="list"><li class="item"><a href="/1">1</a></li><li class="item x"><a>2</a></li></ul>
+            <p class="item"><a href="/3">3</a><span class="item item">4</span></p>
+        </div>
+        <ul><li class="item"><a href="/5">5</a></li></ul>
+    "#;
+
+    let selectors = [
+        "#main .item a",
+        "#main .item",
+        ".item",
+        ".item.x",
+        "li.item > a[href]",
+        "a",
+        "#main",
+        "#missing a",
+        ".missing",
+        "ul li, p",
+        "*",
+    ];
+
+    let plain = parse(input, Default::default()).unwrap();
+    let tracked = parse(
+        input,
+        ParserOptions::default()
+            .track_ids()
+            .track_classes()
+            .track_tags(),
+    )
+    .unwrap();
+
+    for selector in selectors {
+        let expected = plain.query_selector(selector).unwrap().collect::<Vec<_>>();
+        let iter = tracked.query_selector(selector).unwrap();
+        let (_, upper) = iter.size_hint();
+        let actual = iter.collect::<Vec<_>>();
+        let (actual, expected) = (indices(&actual), indices(&expected));
+
+        assert_eq!(actual, expected, "{selector}");
+        assert!(upper.unwrap() >= actual.len(), "{selector}");
+    }
+
+    assert_eq!(
+        tracked.query_selector(".item").unwrap().size_hint(),
+        (0, Some(5))
+    );
+
+    let by_class = tracked
+        .get_elements_by_class_name("item")
+        .collect::<Vec<_>>();
+    assert_eq!(
+        indices(&by_class),
+        indices(&plain.get_elements_by_class_name("item").collect::<Vec<_>>())
+    );
+
+    // queries on an element only consider its subnodes
+    for dom in [&plain, &tracked] {
+        let parser = dom.parser();
+        let main = dom.get_element_by_id("main").unwrap().get(parser).unwrap();
+        let links = main
+            .as_tag()
+            .unwrap()
+            .query_selector(parser, ".item a")
+            .unwrap()
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser))
+            .collect::<Vec<_>>();
+
+        assert_eq!(links, ["1", "2", "3"]);
+    }
+}
+
+#[test]
+fn compiled_selectors() {
+    use crate::queryselector::{CompiledSelector, Selector};
+
+    let input = r#"
+        <div id="a" class="x">
+            <section class="y">
+                <div class="x"><p class="y">1</p><span><p>2</p></span></div>
+                <p class="z" data-k="v">3</p>
+            </section>
+            <ul><li><a href="/">4</a><ul><li class="x"><a>5</a></li></ul></li></ul>
+        </div>
+        text <p>6</p>
+    "#;
+
+    let mut dom = parse(input, Default::default()).unwrap();
+    // renamed tags have no symbol and must still match by name
+    let renamed = dom
+        .nodes_mut()
+        .iter_mut()
+        .filter_map(Node::as_tag_mut)
+        .find(|tag| tag.name() == "span")
+        .unwrap();
+    *renamed.name_mut() = "em".into();
+
+    let parser = dom.parser();
+
+    let selectors = [
+        "p",
+        "em p",
+        "*",
+        "div *",
+        ".x p",
+        ".x > p",
+        ".x > .y",
+        "div div p",
+        "#a p",
+        "#a > section > div > p",
+        "section > * p",
+        "ul li a",
+        "ul > li > a",
+        "li li a",
+        "div.x section .x .y",
+        ".x p, ul a, #missing",
+        "[data-k=v]",
+        "section [data-k]",
+        "div > p",
+        "div p:contains(2)",
+        "missing p",
+        "a>b c",
+        "ul ul",
+    ];
+
+    let check = |selector: &Selector<'_>| {
+        let compiled = CompiledSelector::new(selector, parser);
+        for node in dom.nodes() {
+            assert_eq!(
+                compiled.matches(node, parser),
+                selector.matches(node, parser),
+                "{selector:?} on {:?}",
+                node.outer_html(parser)
+            );
+        }
+    };
+
+    for selector in selectors {
+        check(&crate::parse_query_selector(selector).unwrap());
+    }
+
+    // selectors that are not a linear chain fall back to the recursive matcher
+    check(&Selector::And(
+        Box::new(crate::parse_query_selector("div p").unwrap()),
+        Box::new(crate::parse_query_selector("section > *").unwrap()),
+    ));
+    check(&Selector::Descendant(
+        Box::new(Selector::Tag(b"div")),
+        Box::new(crate::parse_query_selector("section > p").unwrap()),
+    ));
+}
+
+#[test]
+fn descendant_ranges() {
+    use crate::queryselector::CompiledSelector;
+
+    let input = r#"
+        <div id="a" class="x">
+            <section class="y"><div class="x"><p class="y">1</p><br><img src="a.png"/><p>2</p></div></section>
+            <!-- comment --><ul><li class="x"><a>3</a></li><li><p>4</p></li></ul>
+        </div>
+        <p class="y">5</p><div class="x"><span><p>6</p>
+    "#;
+
+    let options = ParserOptions::default()
+        .track_ids()
+        .track_classes()
+        .track_tags();
+    let dom = parse(input, options).unwrap();
+    let parser = dom.parser();
+
+    let check = |dom: &crate::VDom<'_>| {
+        let parser = dom.parser();
+        let is_descendant = |ancestor: NodeHandle, mut node: NodeHandle| {
+            while let Some(parent) = parser.parent_of(node) {
+                if parent == ancestor {
+                    return true;
+                }
+                node = parent;
+            }
+            false
+        };
+
+        for (a, node) in dom.nodes().iter().enumerate() {
+            let Some(tag) = node.as_tag() else {
+                continue;
+            };
+
+            for b in 0..dom.nodes().len() {
+                let (a, b) = (parser.handle(a as u32), parser.handle(b as u32));
+                assert_eq!(tag.contains(b), is_descendant(a, b), "{a:?} contains {b:?}");
+            }
+        }
+    };
+
+    check(&dom);
+    let cache = crate::cache::serialize(&dom);
+    check(&crate::cache::load(&cache).unwrap());
+
+    // the ancestors of descendant combinators are looked up in the tracked tables
+    for selector in [
+        ".x p",
+        "#a p",
+        "div p",
+        "div .y",
+        ".x .x p",
+        "section p, li a",
+        "div > .x p",
+        "ul p",
+        "#missing p",
+        ".x",
+    ] {
+        let selector = crate::parse_query_selector(selector).unwrap();
+        let compiled = CompiledSelector::new(&selector, parser);
+        for node in dom.nodes() {
+            assert_eq!(
+                compiled.matches(node, parser),
+                selector.matches(node, parser),
+                "{selector:?} on {:?}",
+                node.outer_html(parser)
+            );
+        }
+    }
+
+    let texts = dom
+        .query_selector(".x p")
+        .unwrap()
+        .map(|node| node.get(parser).unwrap().inner_text(parser))
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["1", "2", "4", "6"]);
+}
+
+#[test]
+fn outline() {
+    let input = r#"
+        <h2>Before</h2>
+        <h1>Title <small>v1</small></h1>
+        <hgroup><p>Intro</p><h3>Sub</h3><h2>Group</h2><h2>Ignored</h2></hgroup>
+        <section>
+            <H3 id="deep">Deep</H3>
+            <h6>Deeper</h6>
+            <p role="note heading" aria-level="4">Aria</p>
+            <h4 aria-level="9">Nine</h4>
+            <div role="heading" aria-level="x">Default <h5>nested</h5></div>
+        </section>
+        <template><h1>Template</h1></template>
+        <div hidden><h1>Hidden</h1></div>
+        <h2></h2>
+        <h1>End</h1>
+    "#;
+
+    let dom = parse(input, Default::default()).unwrap();
+    let parser = dom.parser();
+    let outline = dom.outline();
+
+    let summary = |heading: &crate::outline::Heading| {
+        heading
+            .iter()
+            .map(|h| format!("{}:{}", h.level, h.text))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(outline.len(), 3);
+    assert_eq!(summary(&outline[0]), ["2:Before"]);
+    assert_eq!(
+        summary(&outline[1]),
+        [
+            "1:Title v1",
+            "2:Group",
+            "3:Deep",
+            "6:Deeper",
+            "4:Aria",
+            "9:Nine",
+            "2:Default nested",
+            "2:"
+        ]
+    );
+    assert_eq!(summary(&outline[2]), ["1:End"]);
+
+    let group = &outline[1].children[0];
+    assert_eq!(group.children.len(), 1);
+    assert_eq!(group.children[0].children.len(), 2);
+    assert_eq!(group.children[0].children[1].children[0].text, "Nine");
+
+    let deep = group.children[0]
+        .node
+        .get(parser)
+        .unwrap()
+        .as_tag()
+        .unwrap();
+    assert_eq!(deep.id().as_deref(), Some("deep"));
+
+    assert!(parse("<p>No headings</p>", Default::default())
+        .unwrap()
+        .outline()
+        .is_empty());
+}
+
+#[test]
+fn template_content() {
+    let input = r#"
+        <div class="a">
+            <img class="x" src="1.png">
+            <template id="t"><img class="x" src="2.png"><TEMPLATE><img class="x" src="3.png"></TEMPLATE></template>
+            <img class="x" src="4.png">
+        </div>
+        <template /><img class="x" src="5.png">
+    "#;
+
+    let sources = |dom: &crate::VDom<'_>, root: Option<&HTMLTag<'_>>, selector: &str| {
+        let parser = dom.parser();
+        let handles = match root {
+            Some(root) => root
+                .query_selector(parser, selector)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            None => dom.query_selector(selector).unwrap().collect(),
+        };
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                let tag = handle.get(parser).unwrap().as_tag().unwrap();
+                tag.src().unwrap().into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_classes().track_tags(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        let parser = dom.parser();
+
+        assert_eq!(sources(&dom, None, "img"), ["1.png", "4.png", "5.png"]);
+        assert_eq!(sources(&dom, None, ".a .x"), ["1.png", "4.png"]);
+        assert_eq!(dom.query_selector("template").unwrap().count(), 2);
+
+        let div = dom.get_elements_by_class_name("a").next().unwrap();
+        let div = div.get(parser).unwrap().as_tag().unwrap();
+        assert_eq!(sources(&dom, Some(div), ".x"), ["1.png", "4.png"]);
+
+        // queries on a template match its contents, but not those of nested templates
+        let template = dom.get_element_by_id("t").unwrap();
+        let template = template.get(parser).unwrap().as_tag().unwrap();
+        assert_eq!(template.template_content().unwrap().len(), 2);
+        assert_eq!(sources(&dom, Some(template), "img"), ["2.png"]);
+        assert_eq!(sources(&dom, Some(template), "#t .x"), ["2.png"]);
+
+        assert!(div.template_content().is_none());
+
+        let cached = crate::cache::serialize(&dom);
+        let dom = crate::cache::load(&cached).unwrap();
+        assert_eq!(sources(&dom, None, "img"), ["1.png", "4.png", "5.png"]);
+    }
+}
+
+#[test]
+fn foreign_content() {
+    use crate::Namespace;
+
+    let input = r#"<p>a<br/>b</p><svg viewBox="0 0 10 10"><defs><linearGradient id="g"/><circle r="1"/></defs><style><![CDATA[ a > b { fill: url(#g) } ]]></style><link>x</link><foreignObject><div>html<br>text</div></foreignObject></svg><math><mi>x</mi><mrow><mn>2</mn></mrow></math><div id="h"><![CDATA[ignored]]></div>"#;
+
+    let dom = parse(input, Default::default()).unwrap();
+    let parser = dom.parser();
+
+    let tag = |selector: &str| {
+        let handle = dom.query_selector(selector).unwrap().next().unwrap();
+        handle.get(parser).unwrap().as_tag().unwrap()
+    };
+
+    // a trailing slash is not part of the tag name
+    assert_eq!(tag("br").name(), "br");
+    assert_eq!(tag("p").inner_text(parser), "ab");
+
+    assert_eq!(tag("svg").namespace(), Namespace::Svg);
+    assert_eq!(tag("linearGradient").namespace(), Namespace::Svg);
+    assert!(tag("linearGradient").children().top().is_empty());
+    assert_eq!(tag("defs").children().top().len(), 2);
+
+    assert_eq!(tag("style").inner_text(parser), " a > b { fill: url(#g) } ");
+    // void HTML elements need an end tag in foreign content
+    assert_eq!(tag("link").inner_text(parser), "x");
+    assert_eq!(tag("link").outer_html(parser), "<link>x</link>");
+
+    assert_eq!(tag("foreignObject").namespace(), Namespace::Svg);
+    assert_eq!(tag("foreignObject div").namespace(), Namespace::Html);
+    assert_eq!(tag("foreignObject div").inner_text(parser), "htmltext");
+
+    assert_eq!(tag("math").namespace(), Namespace::MathMl);
+    assert_eq!(tag("mn").namespace(), Namespace::MathMl);
+    assert!(tag("math").namespace().is_foreign());
+
+    // CDATA sections are parsed in HTML content as well
+    assert_eq!(tag("#h").namespace(), Namespace::Html);
+    assert_eq!(tag("#h").inner_html(parser), "<![CDATA[ignored]]>");
+    assert_eq!(
+        tag("p").namespace().uri(),
+        Some("http://www.w3.org/1999/xhtml")
+    );
+
+    let cache = crate::cache::serialize(&dom);
+    let loaded = crate::cache::load(&cache).unwrap();
+    let namespaces = |dom: &crate::VDom<'_>| {
+        dom.nodes()
+            .iter()
+            .filter_map(Node::as_tag)
+            .map(HTMLTag::namespace)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(namespaces(&loaded), namespaces(&dom));
+}
+
+#[test]
+fn xml_mode() {
+    use crate::Namespace;
+
+    let input = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><body><link>https://example.com/</link><template><p>shown</p></template><script><![CDATA[ if (a < b) {} ]]></script><svg xmlns="http://www.w3.org/2000/svg"><rect/></svg><feed xmlns="urn:x"><Item/><item/></feed><?php echo 1 ?></body></html>"#;
+
+    let options = ParserOptions::default().xml_mode().track_tags();
+    assert!(options.is_xml_mode());
+    assert!(options.is_tracking());
+    assert!(!ParserOptions::default().xml_mode().is_tracking());
+
+    let dom = parse(input, options).unwrap();
+    let parser = dom.parser();
+
+    let tag = |selector: &str| {
+        let handle = dom.query_selector(selector).unwrap().next().unwrap();
+        handle.get(parser).unwrap().as_tag().unwrap()
+    };
+
+    let declaration = dom.children()[0].get(parser).unwrap();
+    assert_eq!(
+        declaration
+            .as_processing_instruction()
+            .unwrap()
+            .as_utf8_str(),
+        r#"<?xml version="1.0" encoding="utf-8"?>"#
+    );
+    assert_eq!(declaration.inner_text(parser), "");
+
+    // there are no void elements, and templates are not special
+    assert_eq!(tag("link").inner_text(parser), "https://example.com/");
+    assert_eq!(dom.query_selector("p").unwrap().count(), 1);
+
+    let script = tag("script");
+    let section = script.children().top()[0].get(parser).unwrap();
+    assert_eq!(
+        section.as_cdata().unwrap().as_utf8_str(),
+        "<![CDATA[ if (a < b) {} ]]>"
+    );
+    assert_eq!(script.inner_text(parser), " if (a < b) {} ");
+    assert_eq!(script.inner_html(parser), "<![CDATA[ if (a < b) {} ]]>");
+
+    assert_eq!(tag("body").namespace(), Namespace::Html);
+    assert_eq!(tag("rect").namespace(), Namespace::Svg);
+    assert_eq!(tag("Item").namespace(), Namespace::Other);
+    assert_eq!(Namespace::Other.uri(), None);
+    assert_eq!(dom.query_selector("item").unwrap().count(), 1);
+
+    // end tags only close elements with the same case
+    let mixed = parse("<Item><b></item></b>c</Item>", options).unwrap();
+    let item = mixed.nodes()[0].as_tag().unwrap();
+    assert_eq!(item.children().top().len(), 2);
+    assert_eq!(item.inner_text(mixed.parser()), "c");
+
+    let body = tag("body");
+    let last = *body.children().top().as_slice().last().unwrap();
+    assert_eq!(
+        last.get(parser).unwrap().outer_html(parser),
+        "<?php echo 1 ?>"
+    );
+
+    let cache = crate::cache::serialize(&dom);
+    let loaded = crate::cache::load(&cache).unwrap();
+    assert!(loaded.parser().options.is_xml_mode());
+    for (a, b) in dom.nodes().iter().zip(loaded.nodes()) {
+        assert_eq!(a.outer_html(parser), b.outer_html(loaded.parser()));
+        assert_eq!(
+            a.as_tag().map(HTMLTag::namespace),
+            b.as_tag().map(HTMLTag::namespace)
+        );
+    }
+
+    // processing instructions and CDATA sections are parsed the same way in HTML mode
+    let html = parse(input, Default::default()).unwrap();
+    let count = |f: fn(&Node<'_>) -> bool| html.nodes().iter().filter(|node| f(node)).count();
+    assert_eq!(count(|node| node.as_processing_instruction().is_some()), 2);
+    assert_eq!(count(|node| node.as_cdata().is_some()), 1);
+}
+
+#[test]
+fn doctype() {
+    let input = r#"<!DOCTYPE html PUBLIC '-//W3C//DTD HTML 4.01 Transitional//EN' "http://www.w3.org/TR/html4/loose.dtd"><p>Hi</p>"#;
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let doctype = dom.doctype().unwrap();
+
+    assert_eq!(doctype.name().unwrap(), "html");
+    assert_eq!(
+        doctype.public_id().unwrap(),
+        "-//W3C//DTD HTML 4.01 Transitional//EN"
+    );
+    assert_eq!(
+        doctype.system_id().unwrap(),
+        "http://www.w3.org/TR/html4/loose.dtd"
+    );
+    assert_eq!(dom.version(), Some(HTMLVersion::TransitionalHTML401));
+    assert_eq!(dom.outer_html(), input);
+    assert_eq!(dom.children().len(), 2);
+    assert_eq!(
+        dom.nodes()[0].byte_span(dom.parser()).unwrap().range(),
+        0..input.find("<p>").unwrap()
+    );
+
+    let system = parse(
+        r#"<!doctype HTML system "about:legacy-compat">"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let doctype = system.doctype().unwrap();
+    assert_eq!(doctype.public_id(), None);
+    assert_eq!(doctype.system_id().unwrap(), "about:legacy-compat");
+    assert_eq!(system.version(), Some(HTMLVersion::HTML5));
+
+    // identifiers without quotes are ignored, and unknown public identifiers have no version
+    let bogus = parse(
+        r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" bogus><!DOCTYPE>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let doctype = bogus.doctype().unwrap();
+    assert_eq!(doctype.system_id(), None);
+    assert_eq!(bogus.version(), None);
+    assert_eq!(bogus.nodes()[1].as_doctype().unwrap().name(), None);
+
+    assert!(parse("<p>Hi</p>", ParserOptions::default())
+        .unwrap()
+        .doctype()
+        .is_none());
+}
+
+#[test]
+fn processing_instructions_and_cdata_in_html() {
+    let input = r#"<?xml version="1.0"?><div><?php echo $a->b ? "<b>" : ""; ?><p><![CDATA[ x < y ]]></p><svg><![CDATA[ z ]]></svg></div>"#;
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+
+    assert_eq!(dom.outer_html(), input);
+    assert_eq!(
+        dom.children()[0]
+            .get(parser)
+            .unwrap()
+            .as_processing_instruction()
+            .unwrap(),
+        r#"<?xml version="1.0"?>"#
+    );
+
+    let div = dom.nodes()[1].as_tag().unwrap();
+    let php = div.children().top()[0].get(parser).unwrap();
+    assert_eq!(
+        php.as_processing_instruction().unwrap(),
+        r#"<?php echo $a->b ? "<b>" : ""; ?>"#
+    );
+    assert_eq!(div.child_elements(parser).count(), 2);
+
+    let p = dom.query_selector("p").unwrap().next().unwrap();
+    let p = p.get(parser).unwrap();
+    assert!(p.children().unwrap().top()[0]
+        .get(parser)
+        .unwrap()
+        .as_cdata()
+        .is_some());
+    assert_eq!(p.inner_text(parser), " x < y ");
+
+    let svg = dom.query_selector("svg").unwrap().next().unwrap();
+    assert_eq!(svg.get(parser).unwrap().inner_text(parser), " z ");
+
+    // an unterminated instruction extends to the end of the input
+    let dom = parse("<p>a<?php echo 1;", ParserOptions::default()).unwrap();
+    assert_eq!(dom.nodes().len(), 3);
+    assert_eq!(dom.outer_html(), "<p>a<?php echo 1;</p>");
+}
+
+#[test]
+fn diff() {
+    use crate::diff::DomEdit;
+
+    let old = parse(
+        r#"<div id="a"><p class="x">Hello  world</p><span>gone</span><!-- c --></div><ul><li id="1">1</li><li id="2">2</li></ul>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(crate::diff(&old, &old), []);
+
+    let reformatted = parse(
+        "<div id=\"a\">\n  <p class=\"x\">Hello world</p>\n  <span>gone</span>\n  <!-- c -->\n</div>\n<ul><li id=\"1\">1</li><li id=\"2\">2</li></ul>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(crate::diff(&old, &reformatted), []);
+
+    let new = parse(
+        r#"<div id="a"><p>Hello there</p><!-- c --></div><ul><li id="2">2</li><li id="1">1</li></ul><div id="b"></div>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let handle = |dom: &crate::VDom<'_>, selector: &str| {
+        dom.query_selector(selector).unwrap().next().unwrap()
+    };
+    let text = |dom: &crate::VDom<'_>, selector: &str| {
+        let tag = handle(dom, selector);
+        tag.get(dom.parser()).unwrap().children().unwrap().top()[0]
+    };
+
+    assert_eq!(
+        crate::diff(&old, &new),
+        [
+            DomEdit::AttributeChanged {
+                old: handle(&old, "p"),
+                new: handle(&new, "p"),
+                name: "class".into()
+            },
+            DomEdit::TextChanged {
+                old: text(&old, "p"),
+                new: text(&new, "p")
+            },
+            DomEdit::Removed {
+                old: handle(&old, "span")
+            },
+            DomEdit::Moved {
+                old: handle(&old, "[id='1']"),
+                new: handle(&new, "[id='1']")
+            },
+            DomEdit::Added {
+                new: handle(&new, "#b")
+            },
+        ]
+    );
+}
+
+#[test]
+fn structural_eq() {
+    let a = crate::parse(
+        r#"<div id="x" class="a b"><p>Hello <b>big</b>  world</p><pre> a  b </pre><!-- note --></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let b = crate::parse(
+        "<DIV class=\"a b\" id='x'>\n  <p>\n    Hello <B>big</B> world\n  </p>\n  <pre> a  b </pre>\n  <!-- note -->\n</DIV>",
+        Default::default(),
+    )
+    .unwrap();
+
+    fn root<'a>(dom: &crate::VDom<'a>) -> Node<'a> {
+        dom.children()[0].get(dom.parser()).unwrap().clone()
+    }
+
+    let (ra, rb) = (root(&a), root(&b));
+
+    assert!(ra.structural_eq(&rb, a.parser(), b.parser()));
+    assert_eq!(
+        ra.structural_hash(a.parser()),
+        rb.structural_hash(b.parser())
+    );
+
+    // whitespace in preformatted text, attribute values and comments are significant
+    for other in [
+        r#"<div id="x" class="a b"><p>Hello <b>big</b> world</p><pre> a b </pre><!-- note --></div>"#,
+        r#"<div id="x" class="b a"><p>Hello <b>big</b> world</p><pre> a  b </pre><!-- note --></div>"#,
+        r#"<div id="x" class="a b"><p>Hello <b>big</b> world</p><pre> a  b </pre><!--note--></div>"#,
+        r#"<div id="x" class="a b"><p>Hello <i>big</i> world</p><pre> a  b </pre><!-- note --></div>"#,
+        r#"<div id="x" class="a b"><p>Hello</p><pre> a  b </pre><!-- note --></div>"#,
+    ] {
+        let c = crate::parse(other, Default::default()).unwrap();
+        let rc = root(&c);
+        assert!(!ra.structural_eq(&rc, a.parser(), c.parser()), "{other}");
+        assert_ne!(
+            ra.structural_hash(a.parser()),
+            rc.structural_hash(c.parser()),
+            "{other}"
+        );
+    }
+}
+
+#[test]
+fn debug_tree() {
+    let dom = crate::parse(
+        "<!DOCTYPE html>\n<html>\n<body>\n  <div id=\"main\" class=\"container wide\">\n    <p>The quick brown fox jumps over the lazy dog</p>\n    <!-- a very long comment -->\n  </div>\n</body>\n</html>",
+        Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        dom.debug_tree().max_text_len(20).to_string(),
+        "<!DOCTYPE html>\n\
+         html\n  \
+           body\n    \
+             div#main.container.wide\n      \
+               p\n        \
+                 \"The quick brown fox …\"\n      \
+               <!-- a very long com…\n"
+    );
+
+    let div = dom.get_element_by_id("main").unwrap();
+    let div = div.get(dom.parser()).unwrap().as_tag().unwrap();
+    let children = div.children();
+
+    assert_eq!(
+        crate::debug::TreeDisplay::new(dom.parser(), children.top().as_slice()).to_string(),
+        "p\n  \"The quick brown fox jumps over the lazy …\"\n<!-- a very long comment -->\n"
+    );
+}
+
+#[test]
+fn query_iterator_adapters() {
+    let dom = crate::parse(
+        r#"<ul><li><a href="/one">One</a></li><li><a>Two</a></li><li><a href>Three</a></li></ul>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    let first = dom.query_selector("a").unwrap().first().unwrap();
+    assert_eq!(first, dom.query_selector("a").unwrap().next().unwrap());
+
+    let names = dom
+        .query_selector("li > *")
+        .unwrap()
+        .tags()
+        .map(|tag| tag.name().as_utf8_str().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "a", "a"]);
+
+    let texts = dom
+        .query_selector("li")
+        .unwrap()
+        .texts()
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["One", "Two", "Three"]);
+
+    let links = dom
+        .query_selector("a")
+        .unwrap()
+        .attrs("href")
+        .collect::<Vec<_>>();
+    assert_eq!(links, ["/one", ""]);
+
+    let filtered = || dom.query_selector("a").unwrap().filter_text("T");
+    assert_eq!(filtered().texts().collect::<Vec<_>>(), ["Two", "Three"]);
+    assert_eq!(filtered().attrs("href").collect::<Vec<_>>(), [""]);
+}
+
+#[test]
+fn css_path() {
+    let dom = crate::parse(
+        r#"<html><body><p>intro</p>
+        <div id="content">
+            <ul><li>1</li><li class="x">2</li><span></span><li><a href="/3">3</a></li></ul>
+            <p id="dup">a</p><p id="dup">b</p><p id="1st"><b>c</b></p>
+        </div>
+        <p>outro</p></body></html>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let path = |selector| {
+        let handle = dom.query_selector(selector).unwrap().next().unwrap();
+        handle
+            .get(parser)
+            .unwrap()
+            .as_tag()
+            .unwrap()
+            .css_path(parser)
+    };
+
+    assert_eq!(path("a"), "div#content > ul > li:nth-child(4) > a");
+    assert_eq!(path("span"), "div#content > ul > span");
+    assert_eq!(path("b"), "div#content > p:nth-child(4) > b");
+    assert_eq!(path("p"), "html > body > p:nth-child(1)");
+
+    // every generated path matches exactly the element it was generated for
+    for (index, node) in dom.nodes().iter().enumerate() {
+        if let Some(tag) = node.as_tag() {
+            let path = tag.css_path(parser);
+            let matches = dom.query_selector(&path).unwrap().collect::<Vec<_>>();
+            assert_eq!(matches, [parser.handle(index as u32)], "{path}");
+        }
+    }
+
+    assert_eq!(
+        dom.query_selector("li:nth-child(2)")
+            .unwrap()
+            .texts()
+            .collect::<Vec<_>>(),
+        ["2"]
+    );
+    assert!(crate::parse_query_selector("li:nth-child(0)").is_none());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_search() {
+    let dom = crate::parse(
+        r#"<p>Call 555-1234 or 555-9876</p><p data-sku="AB-12">Item <i>x-1</i></p><p data-sku="none">No</p>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let phone = regex::Regex::new(r"(\d{3})-(?<line>\d{4})").unwrap();
+    let matches = dom.find_text(&phone);
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].as_str(), "555-1234");
+    assert_eq!(matches[0].range(), 5..13);
+    assert_eq!(matches[0].get(1), Some("555"));
+    assert_eq!(matches[1].name("line"), Some("9876"));
+    assert_eq!(matches[1].name("missing"), None);
+    assert_eq!(matches[0].len(), 3);
+    assert_eq!(matches[0].node(), matches[1].node());
+    assert!(matches[0].node().get(parser).unwrap().as_raw().is_some());
+
+    let short = regex::Regex::new(r"\b[a-zA-Z]+-(\d+)").unwrap();
+    let texts = dom.find_text(&short);
+    assert_eq!(texts.len(), 1);
+    assert_eq!(
+        texts[0].node().get(parser).unwrap().as_raw().unwrap(),
+        "x-1"
+    );
+
+    let attributes = dom.find_attribute("data-sku", &short);
+    assert_eq!(attributes.len(), 1);
+    assert_eq!(attributes[0].get(1), Some("12"));
+    let tag = attributes[0].node().get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(tag.inner_text(parser), "Item x-1");
+}
+
+#[test]
+fn charset_and_lang() {
+    let charset = |input: &[u8]| {
+        crate::parse_bytes(input, Default::default())
+            .unwrap()
+            .detected_charset()
+    };
+
+    assert_eq!(charset(b"<p>no charset</p>"), None);
+    assert_eq!(
+        charset(b"<meta charset=\" UTF-8 \">").as_deref(),
+        Some("utf-8")
+    );
+    assert_eq!(
+        charset(b"<meta http-equiv=content-type content='text/html;charset=\"Shift_JIS\"'><meta charset=utf-8>")
+            .as_deref(),
+        Some("shift_jis")
+    );
+    assert_eq!(
+        charset(b"<meta http-equiv=refresh content=\"5; charset=x\"><meta content=\"charset=x\">"),
+        None
+    );
+    assert_eq!(
+        charset(b"\xFF\xFE<\0m\0e\0t\0a\0>\0").as_deref(),
+        Some("utf-16le")
+    );
+
+    let dom = crate::parse(
+        r#"<html lang="en-GB"><body><div xml:lang="de" lang="fr"><p>a</p><p lang=" ">b</p></div><svg><text>c</text></svg></body></html>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    assert_eq!(dom.lang().unwrap(), "en-GB");
+
+    let langs = dom
+        .query_selector("p, text")
+        .unwrap()
+        .tags()
+        .map(|tag| tag.effective_lang(parser).map(|lang| lang.as_utf8_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(langs, [Some("de".into()), None, Some("en-GB".into())]);
+
+    let fragment = crate::parse("<p lang=nl>Hallo</p>", Default::default()).unwrap();
+    assert_eq!(fragment.lang(), None);
+}
+
+#[test]
+fn custom_void_elements() {
+    let options = ParserOptions::default().extra_void_elements(&["ICON", "spacer"]);
+    let dom = crate::parse(
+        r#"<div><icon src="a"><spacer>text<svg><icon></icon></svg></div><p>after</p>"#,
+        options,
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let div = dom.nodes()[0].as_tag().unwrap();
+    let names = div
+        .children()
+        .top()
+        .iter()
+        .map(|child| child.get(parser).unwrap().outer_html(parser))
+        .collect::<Vec<_>>();
+    // `<icon>` in foreign content is not void
+    assert_eq!(
+        names,
+        [
+            r#"<icon src="a">"#,
+            "<spacer>",
+            "text",
+            "<svg><icon></icon></svg>"
+        ]
+    );
+    assert_eq!(dom.children().len(), 2);
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div><icon src="a"><spacer>text<svg><icon></icon></svg></div><p>after</p>"#
+    );
+
+    let bytes = crate::cache::serialize(&dom);
+    let cached = crate::cache::load(&bytes).unwrap();
+    assert_eq!(cached.outer_html(), dom.outer_html());
+
+    let options = ParserOptions::default().treat_unknown_as_void(true);
+    assert!(options.is_treating_unknown_as_void());
+    assert!(!options
+        .treat_unknown_as_void(false)
+        .is_treating_unknown_as_void());
+
+    let dom = crate::parse("<p><foo>a<center>b</center><x-card>c</x-card></p>", options).unwrap();
+    let p = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(p.children().top().len(), 4);
+    assert_eq!(
+        dom.outer_html(),
+        "<p><foo>a<center>b</center><x-card>c</x-card></p>"
+    );
+
+    let xml = crate::parse("<a><foo>b</foo></a>", options.xml_mode()).unwrap();
+    assert_eq!(xml.nodes()[0].as_tag().unwrap().children().top().len(), 1);
+}
+
+#[test]
+fn custom_elements() {
+    let input = r#"<my-app><Nav-Bar>
+        <user-avatar src="a.png"><img src="b.png"></user-avatar>
+    </Nav-Bar><svg><font-face></font-face><x-shape></x-shape></svg><p-1>x</p-1><1-a></1-a></my-app>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_tags(),
+        ParserOptions::default().treat_unknown_as_void(true),
+    ] {
+        let dom = crate::parse(input, options).unwrap();
+        let parser = dom.parser();
+
+        let names = dom
+            .custom_elements()
+            .iter()
+            .map(|handle| {
+                let tag = handle.get(parser).unwrap().as_tag().unwrap();
+                tag.name().as_utf8_str().into_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["my-app", "nav-bar", "user-avatar", "p-1"]);
+
+        // custom elements nest like any other element
+        let avatar = dom.query_selector("user-avatar").unwrap().next().unwrap();
+        let avatar = avatar.get(parser).unwrap().as_tag().unwrap();
+        assert_eq!(avatar.css_path(parser), "my-app > nav-bar > user-avatar");
+        assert_eq!(avatar.children().top().len(), 1);
+    }
+}
+
+#[test]
+fn a11y_audit() {
+    use crate::a11y::{audit, Finding, Issue};
+
+    let dom = crate::parse(
+        r#"<html lang="en"><body>
+        <h2>Intro</h2><h4>Details</h4><div role="heading" aria-level="5">More</div>
+        <img src="a.png" id="x"><img src="b.png" aria-label="B"><img src="c.png" role="presentation">
+        <form>
+            <label for="name">Name</label><input id="name">
+            <input id="x" type="text" placeholder="Email">
+            <input type="hidden" name="token"><input type="submit">
+            <textarea title="Message"></textarea><select></select>
+        </form>
+        <template><img src="d.png"></template><div hidden><input></div>
+        </body></html>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    let handle = |selector| dom.query_selector(selector).unwrap().next();
+
+    assert_eq!(
+        audit(&dom),
+        [
+            Finding {
+                node: handle("h4"),
+                issue: Issue::SkippedHeadingLevel {
+                    previous: 2,
+                    level: 4
+                }
+            },
+            Finding {
+                node: handle("img"),
+                issue: Issue::MissingAlt
+            },
+            Finding {
+                node: handle("input[type=text]"),
+                issue: Issue::MissingLabel
+            },
+            Finding {
+                node: handle("input[type=text]"),
+                issue: Issue::DuplicateId("x".into())
+            },
+            Finding {
+                node: handle("select"),
+                issue: Issue::MissingLabel
+            },
+        ]
+    );
+
+    let fragment = crate::parse("<p>Hi</p>", Default::default()).unwrap();
+    assert_eq!(
+        audit(&fragment),
+        [Finding {
+            node: None,
+            issue: Issue::MissingLang
+        }]
+    );
+}
+
+#[test]
+fn scripts_and_stylesheets() {
+    use crate::resources::ScriptKind;
+
+    let dom = crate::parse(
+        r#"<html><head>
+            <script src="/app.js" type="module" async integrity="sha384-abc"></script>
+            <script nomodule src="/legacy.js"></script>
+            <script type="Text/JavaScript; charset=utf-8">if (a <b) { go("</p>"); }</script>
+            <script type="importmap">{"imports": {}}</script>
+            <script type="application/ld+json">{"@type": "Article"}</script>
+            <script type="text/template"><p>Hi</p></script>
+            <script>   </script>
+            <link rel="Stylesheet" href="/main.css" integrity="sha384-def">
+            <link rel="alternate stylesheet" href="/dark.css">
+            <link rel="stylesheet">
+            <link rel="icon" href="/icon.png">
+            <style media="print">p { color: black }</style>
+        </head></html>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    let scripts = dom.scripts();
+    let kinds = scripts.iter().map(|s| s.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ScriptKind::Module,
+            ScriptKind::Classic,
+            ScriptKind::Classic,
+            ScriptKind::ImportMap,
+            ScriptKind::Data,
+            ScriptKind::Data,
+            ScriptKind::Classic,
+        ]
+    );
+
+    assert_eq!(scripts[0].src.as_deref(), Some("/app.js"));
+    assert_eq!(scripts[0].content, None);
+    assert!(scripts[0].is_async && !scripts[0].is_nomodule);
+    assert_eq!(scripts[0].integrity.as_deref(), Some("sha384-abc"));
+    assert!(scripts[1].is_nomodule && !scripts[1].is_async);
+    assert_eq!(scripts[2].mime_type.as_deref(), Some("text/javascript"));
+    assert_eq!(
+        scripts[2].content.as_deref(),
+        Some(r#"if (a <b) { go("</p>"); }"#)
+    );
+    assert_eq!(scripts[2].json(), None);
+    assert_eq!(scripts[3].json(), Some(r#"{"imports": {}}"#));
+    assert_eq!(scripts[4].json(), Some(r#"{"@type": "Article"}"#));
+    assert_eq!(scripts[5].content.as_deref(), Some("<p>Hi</p>"));
+    assert!(!scripts[5].is_json());
+    assert_eq!(scripts[6].content, None);
+
+    let stylesheets = dom.stylesheets();
+    assert_eq!(stylesheets.len(), 3);
+    assert_eq!(stylesheets[0].href.as_deref(), Some("/main.css"));
+    assert_eq!(stylesheets[0].integrity.as_deref(), Some("sha384-def"));
+    assert!(!stylesheets[0].is_alternate);
+    assert_eq!(stylesheets[1].href.as_deref(), Some("/dark.css"));
+    assert!(stylesheets[1].is_alternate);
+    assert_eq!(stylesheets[2].href, None);
+    assert_eq!(stylesheets[2].media.as_deref(), Some("print"));
+    assert_eq!(
+        stylesheets[2].content.as_deref(),
+        Some("p { color: black }")
+    );
+}
+
+#[test]
+fn link_relations() {
+    use crate::links::{IconSize, MimeType};
+
+    let dom = parse(
+        r#"<head>
+            <base href="/site/">
+            <link rel="canonical" href="https://example.com/post">
+            <link rel="canonical" href="https://example.com/other">
+            <link rel="alternate" type="application/atom+xml" href="atom.xml" title="Atom">
+            <link rel="ALTERNATE" type="Application/Feed+JSON; charset=utf-8" href="feed.json">
+            <link rel="alternate" type="text/html" href="print.html" media="print">
+            <link rel="alternate stylesheet" href="dark.css" title="Dark">
+            <link rel="icon icon" type="image/svg+xml" sizes="any" href="icon.svg">
+            <link rel="icon" sizes="16x16 32X32 +1x1 0x8 big" href="icon.png">
+            <link rel="apple-touch-icon" sizes="180x180" href="touch.png">
+            <link rel="modulepreload" href="app.js">
+            <link rel="preload" as="Image" href="hero.jpg">
+            <link rel="icon">
+            <link href="no-rel.css">
+        </head>"#,
+        Default::default(),
+    )
+    .unwrap();
+
+    let relations = dom.link_relations(Some("https://example.com/blog/post"));
+    let rels = relations.iter().map(|(rel, _)| rel).collect::<Vec<_>>();
+    assert_eq!(
+        rels,
+        [
+            "alternate",
+            "apple-touch-icon",
+            "canonical",
+            "icon",
+            "modulepreload",
+            "preload",
+            "stylesheet"
+        ]
+    );
+
+    assert_eq!(relations.get("canonical").len(), 2);
+    assert_eq!(
+        relations.canonical().unwrap().url,
+        "https://example.com/post"
+    );
+
+    let feeds = relations
+        .feeds()
+        .map(|l| l.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        feeds,
+        [
+            "https://example.com/site/atom.xml",
+            "https://example.com/site/feed.json"
+        ]
+    );
+    let json = relations.get("alternate")[1].mime_type.as_ref().unwrap();
+    assert_eq!(json.essence(), "application/feed+json");
+    assert_eq!(json.parameter("charset"), Some("utf-8"));
+    assert_eq!(json.to_string(), "application/feed+json; charset=utf-8");
+
+    let stylesheet = &relations.get("stylesheet")[0];
+    assert_eq!(stylesheet.rel, ["alternate", "stylesheet"]);
+    assert!(!stylesheet.is_feed());
+    assert_eq!(
+        relations.get("alternate")[2].media.as_deref(),
+        Some("print")
+    );
+
+    let icons = relations.icons();
+    assert_eq!(icons.len(), 3);
+    assert_eq!(relations.get("icon")[0].rel, ["icon"]);
+    assert_eq!(
+        icons[1].sizes,
+        [
+            IconSize::Pixels {
+                width: 16,
+                height: 16
+            },
+            IconSize::Pixels {
+                width: 32,
+                height: 32
+            }
+        ]
+    );
+    assert_eq!(
+        relations.largest_icon().unwrap().url,
+        "https://example.com/site/icon.svg"
+    );
+
+    let preloads = relations.preloads();
+    assert_eq!(preloads.len(), 2);
+    assert_eq!(preloads[1].destination.as_deref(), Some("image"));
+    assert!(relations.get("nofollow").is_empty());
+
+    assert_eq!(MimeType::parse("text/"), None);
+    assert_eq!(
+        MimeType::parse(" text / css ").unwrap().essence(),
+        "text/css"
+    );
+}
+
+#[test]
+fn base_url() {
+    let page = Some("https://example.com/blog/post?page=2#comments");
+
+    let dom = parse("<p>Hi</p>", Default::default()).unwrap();
+    assert_eq!(dom.base_url(page).as_deref(), page);
+    assert_eq!(dom.base_url(Some("relative/page")), None);
+    assert_eq!(dom.resolve_url(None, " a.html "), "a.html");
+    assert_eq!(
+        dom.resolve_url(page, "#top"),
+        "https://example.com/blog/post?page=2#top"
+    );
+    assert_eq!(
+        dom.resolve_url(page, ""),
+        "https://example.com/blog/post?page=2"
+    );
+
+    // an empty `href` still counts as the first `<base href>`
+    let dom = parse(
+        r#"<base href><base href="https://other.org/">"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.base_url(page).as_deref(),
+        Some("https://example.com/blog/post?page=2")
+    );
+
+    let dom = parse(
+        r#"<base href="javascript:alert(1)"><a href="a.html">A</a>"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(dom.base_url(page).as_deref(), page);
+
+    let dom = parse(
+        r#"<base href="//cdn.example.org/static/"><a href="\\evil.org\x">A</a><img srcset="i.png 2x">"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.base_url(page).as_deref(),
+        Some("https://cdn.example.org/static/")
+    );
+    assert_eq!(dom.base_url(None), None);
+
+    let urls = dom
+        .links(page)
+        .into_iter()
+        .map(|link| link.url)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        urls,
+        ["https://evil.org/x", "https://cdn.example.org/static/i.png"]
+    );
+
+    assert_eq!(
+        crate::links::resolve("foo://host/a/b", "c\\d"),
+        "foo://host/a/c\\d"
+    );
+    assert_eq!(
+        crate::links::resolve("https://example.com/", "/a?q=\\#\\"),
+        "https://example.com/a?q=\\#\\"
+    );
+}
+
+#[test]
+fn normalize() {
+    // the stray end tags are dropped, which leaves adjacent text nodes behind
+    let mut dom = parse("a</x>b</y>c<p>d</q><!---->e</p><br>", Default::default()).unwrap();
+    assert_eq!(dom.children().len(), 5);
+
+    // removing the comment leaves adjacent text nodes behind as well
+    let p = dom.query_selector("p").unwrap().next().unwrap();
+    let p = p.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+    p.children_mut().top_mut().remove(1);
+    assert_eq!(p.children().top().len(), 2);
+
+    dom.normalize();
+
+    let parser = dom.parser();
+    let top = dom
+        .children()
+        .iter()
+        .map(|handle| handle.get(parser).unwrap().outer_html(parser))
+        .collect::<Vec<_>>();
+    assert_eq!(top, ["abc", "<p>de</p>", "<br>"]);
+
+    let p = dom.children()[1].get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(p.children().top().len(), 1);
+    assert_eq!(p.inner_text(parser), "de");
+    assert_eq!(dom.outer_html(), "abc<p>de</p><br>");
+
+    // handles of merged nodes stay valid, but are empty
+    assert_eq!(dom.nodes()[1].as_raw().unwrap().as_bytes(), b"");
+
+    let cache = crate::cache::serialize(&dom);
+    let loaded = crate::cache::load(&cache).unwrap();
+    assert_eq!(loaded.outer_html(), "abc<p>de</p><br>");
+
+    // normalizing twice changes nothing
+    dom.normalize();
+    assert_eq!(dom.children().len(), 3);
+
+    dom.nodes_mut()[0].as_raw_mut().unwrap().set("").unwrap();
+    dom.normalize();
+    assert_eq!(dom.outer_html(), "<p>de</p><br>");
+    assert_eq!(dom.children().len(), 2);
+}
+
+#[test]
+fn skip_whitespace_text() {
+    let input = "<table>\n  <tr>\n    <td> a </td>\n    <td>\t</td>\n  </tr>\n</table>\n<pre>\n  <code>  </code>\n</pre><textarea> </textarea> <b>x</b> <i>y</i>";
+
+    let options = ParserOptions::default().skip_whitespace_text();
+    assert!(options.is_skipping_whitespace_text());
+    assert!(!ParserOptions::default().is_skipping_whitespace_text());
+
+    let dom = parse(input, options).unwrap();
+    let parser = dom.parser();
+    // only the whitespace inside of `<pre>`, `<code>` and `<textarea>` is left
+    let whitespace = dom
+        .nodes()
+        .iter()
+        .filter_map(Node::as_raw)
+        .filter(|text| text.as_bytes().iter().all(u8::is_ascii_whitespace))
+        .count();
+    assert_eq!(whitespace, 4);
+
+    assert_eq!(
+        dom.outer_html(),
+        "<table><tr><td> a </td><td></td></tr></table><pre>\n  <code>  </code>\n</pre><textarea> </textarea><b>x</b><i>y</i>"
+    );
+
+    let tr = dom.query_selector("tr").unwrap().next().unwrap();
+    assert_eq!(tr.get(parser).unwrap().children().unwrap().top().len(), 2);
+
+    // the option survives the cache
+    let cache = crate::cache::serialize(&dom);
+    let loaded = crate::cache::load(&cache).unwrap();
+    assert!(loaded.parser().options.is_skipping_whitespace_text());
+    assert_eq!(loaded.outer_html(), dom.outer_html());
+}
+
+#[test]
+fn sibling_positions() {
+    let mut dom = parse(
+        "<ul>\n<li>0</li><!---->\n<li>1</li>x</y>y<li>2</li></ul><p>a</p>",
+        Default::default(),
+    )
+    .unwrap();
+
+    fn check(dom: &crate::VDom<'_>, expected: &[&str]) {
+        let parser = dom.parser();
+        let ul = dom.nodes()[0].as_tag().unwrap();
+
+        let items = (0..4)
+            .map_while(|index| ul.child_element_at(index, parser))
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser))
+            .collect::<Vec<_>>();
+        assert_eq!(items, expected);
+
+        for (index, handle) in ul.children().top().iter().enumerate() {
+            assert_eq!(
+                handle.get(parser).unwrap().sibling_index(parser),
+                Some(index)
+            );
+        }
+    }
+
+    check(&dom, &["0", "1", "2"]);
+    let parser = dom.parser();
+    let p = dom.query_selector("p").unwrap().next().unwrap();
+    assert_eq!(p.get(parser).unwrap().sibling_index(parser), Some(1));
+    let li = dom
+        .query_selector("li:nth-child(3)")
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(li.get(parser).unwrap().inner_text(parser), "2");
+
+    // nodes that are not part of the document have no position
+    let other = parse("<p>a</p>", Default::default()).unwrap();
+    assert_eq!(other.nodes()[0].sibling_index(parser), None);
+
+    // "x" and "y" are merged, so the handle of "y" is detached
+    dom.normalize();
+    assert!(dom.parser().has_positions());
+    check(&dom, &["0", "1", "2"]);
+    let y = dom
+        .nodes()
+        .iter()
+        .position(|n| n.as_raw().is_some_and(|t| t.as_bytes().is_empty()))
+        .unwrap();
+    assert_eq!(dom.nodes()[y].sibling_index(dom.parser()), None);
+
+    // mutating the document falls back to searching the siblings
+    let ul = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    ul.children_mut().top_mut().remove(1);
+    assert!(!dom.parser().has_positions());
+    check(&dom, &["1", "2"]);
+
+    let cache = crate::cache::serialize(&dom);
+    let loaded = crate::cache::load(&cache).unwrap();
+    assert!(loaded.parser().has_positions());
+    check(&loaded, &["1", "2"]);
+}
+
+#[test]
+fn sibling_positions_after_mutation_before_lookup() {
+    let mut dom = parse(
+        "<ul><li>0</li>x<li>1</li><li>2</li></ul>",
+        Default::default(),
+    )
+    .unwrap();
+
+    // the positions are only computed on the first lookup, which then sees the mutated children
+    let ul = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    ul.children_mut().top_mut().remove(0);
+
+    let parser = dom.parser();
+    let ul = dom.nodes()[0].as_tag().unwrap();
+    let children = ul.children().top().to_vec();
+    assert!(parser.has_positions());
+    assert_eq!(
+        children[0].get(parser).unwrap().sibling_index(parser),
+        Some(0)
+    );
+    assert_eq!(parser.parent_of(children[0]), dom.parser().handle_of(ul));
+    assert_eq!(
+        ul.child_element_at(1, parser)
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser)),
+        Some("2".into())
+    );
+    assert_eq!(parser.parent_of(parser.handle(1)), None);
+}
+
+#[test]
+fn node_filter() {
+    use crate::NodeFilter;
+
+    let options = ParserOptions::default()
+        .track_ids()
+        .node_filter(|name, attributes| {
+            if attributes.is_class_member("ad") || name == "#comment" {
+                NodeFilter::SkipSubtree
+            } else if attributes.is_class_member("wrapper") {
+                NodeFilter::SkipNode
+            } else {
+                NodeFilter::Keep
+            }
+        });
+    assert!(options.get_node_filter().is_some());
+    assert!(ParserOptions::default().get_node_filter().is_none());
+
+    let dom = parse(
+        concat!(
+            r#"<span><span class="wrapper">a<span>b</span>c</span>d</span>"#,
+            r#"<div class="ad"><div id="inner">x<!-- y --></div><img><svg><path d="M0"/><br/></svg><br></div>"#,
+            r#"<p id="kept">ok<br class="wrapper">!<!-- gone --></p>"#,
+            r#"<section class="ad">unclosed <b>subtree"#,
+        ),
+        options,
+    )
+    .unwrap();
+
+    assert_eq!(
+        dom.outer_html(),
+        r#"<span>a<span>b</span>cd</span><p id="kept">ok!</p>"#
+    );
+    assert!(dom.get_element_by_id("inner").is_none());
+    assert!(dom.get_element_by_id("kept").is_some());
+    assert!(dom.nodes().iter().all(|node| node.as_comment().is_none()));
+
+    let span = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(span.children().top().len(), 4);
+}
+
+#[test]
+fn time_budget() {
+    use std::time::Duration;
+
+    let input = "<div><p>Hello</p></div>".repeat(10_000);
+
+    let options = ParserOptions::default().time_budget(Duration::ZERO);
+    assert_eq!(options.get_time_budget(), Some(Duration::ZERO));
+    assert_eq!(
+        parse(&input, options).unwrap_err(),
+        crate::ParseError::TimeBudgetExceeded
+    );
+
+    // small documents are parsed before the clock is first checked
+    assert!(parse("<p>Hello</p>", options).is_ok());
+
+    let options = ParserOptions::default().time_budget(Duration::from_secs(60));
+    let dom = parse(&input, options).unwrap();
+    assert_eq!(dom.children().len(), 10_000);
+    assert!(ParserOptions::default().get_time_budget().is_none());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn parse_file() {
+    use crate::errors::ReadError;
+
+    let path = std::env::temp_dir().join(format!("tl-parse-file-{}.html", std::process::id()));
+    std::fs::write(&path, b"<p id=\"a\">caf\xc3\xa9 \xff</p>").unwrap();
+
+    let guard = unsafe { crate::parse_file(&path, ParserOptions::default().track_ids()) }.unwrap();
+    let dom = guard.get_ref();
+    let p = dom
+        .get_element_by_id("a")
+        .unwrap()
+        .get(dom.parser())
+        .unwrap();
+    assert_eq!(p.inner_text(dom.parser()), "café \u{fffd}");
+    drop(guard);
+
+    std::fs::write(&path, b"").unwrap();
+    let guard = unsafe { crate::parse_file(&path, ParserOptions::default()) }.unwrap();
+    assert!(guard.get_ref().children().is_empty());
+    drop(guard);
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        unsafe { crate::parse_file(&path, ParserOptions::default()) },
+        Err(ReadError::Io(_))
+    ));
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn parse_compressed() {
+    use crate::errors::ReadError;
+    use flate2::{write::GzEncoder, write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    let outer_html = |input: &[u8]| {
+        let guard = crate::parse_compressed(input, ParserOptions::default()).unwrap();
+        guard.get_ref().outer_html()
+    };
+
+    // gzip with two members
+    let mut input = gzip(b"<p>caf\xc3\xa9 ");
+    input.extend(gzip(b"\xff</p>"));
+    assert_eq!(outer_html(&input), "<p>café \u{fffd}</p>");
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"<b>zlib</b>").unwrap();
+    assert_eq!(outer_html(&encoder.finish().unwrap()), "<b>zlib</b>");
+
+    assert_eq!(outer_html(b"<i>plain</i>"), "<i>plain</i>");
+    assert_eq!(outer_html(b"x"), "x");
+    assert_eq!(outer_html(b""), "");
+
+    let mut corrupt = gzip(b"<p>truncated</p>");
+    corrupt.truncate(corrupt.len() - 12);
+    assert!(matches!(
+        crate::parse_compressed(corrupt.as_slice(), ParserOptions::default()),
+        Err(ReadError::Io(_))
+    ));
+}
+
+#[test]
+fn from_reader() {
+    use crate::errors::ReadError;
+    use std::io::{self, Read};
+
+    let guard =
+        crate::from_reader(&b"<p>caf\xc3\xa9 \xff</p>"[..], ParserOptions::default()).unwrap();
+    assert_eq!(guard.get_ref().outer_html(), "<p>café \u{fffd}</p>");
+
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("connection reset"))
+        }
+    }
+    assert!(matches!(
+        crate::from_reader(Failing, ParserOptions::default()),
+        Err(ReadError::Io(_))
+    ));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn parse_body() {
+    let body = ::bytes::Bytes::from(b"<div><p id=\"a\">caf\xc3\xa9</p></div>".to_vec());
+    let data = body.as_ptr();
+    let guard = crate::parse_body(body.clone(), ParserOptions::default().track_ids()).unwrap();
+    drop(body);
+
+    let dom = guard.get_ref();
+    let p = dom
+        .get_element_by_id("a")
+        .unwrap()
+        .get(dom.parser())
+        .unwrap();
+    assert_eq!(p.inner_text(dom.parser()), "café");
+    // the body is shared, not copied
+    assert_eq!(dom.parser().stream.data().as_ptr(), data);
+}
+
+#[test]
+fn query_cache() {
+    use crate::query_cache::QueryCache;
+
+    let mut dom = parse(
+        r#"<ul><li>a</li><li class="x">b</li></ul>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let mut cache = QueryCache::new();
+
+    assert_eq!(cache.query_selector(&dom, "li").unwrap().len(), 2);
+    assert_eq!(cache.query_selector(&dom, "li.x").unwrap().len(), 1);
+    assert!(cache.query_selector(&dom, "li:nth-child(0)").is_none());
+    assert_eq!(cache.len(), 3);
+
+    // read-only access keeps the results
+    let _ = dom.parser();
+    dom.normalize();
+    assert!(cache.query_selector(&dom, "li").is_some());
+    assert_eq!(cache.len(), 1);
+
+    cache.query_selector(&dom, "li.x");
+    dom.children_mut();
+    assert_eq!(cache.query_selector(&dom, "li.x").unwrap().len(), 1);
+    assert_eq!(cache.len(), 1);
+
+    // another document with the same structure is not answered from the cache
+    let other = parse(
+        r#"<ul><li class="x">a</li><li class="x">b</li></ul>"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(cache.query_selector(&other, "li.x").unwrap().len(), 2);
+
+    let other = other.reparse(r#"<li class="x">c</li>"#).unwrap();
+    assert_eq!(cache.query_selector(&other, "li.x").unwrap().len(), 1);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn statistics() {
+    use crate::stats::Collector;
+
+    let input = r#"<!DOCTYPE html><div id="a" class="b" hidden><P>x &amp; y<br></P><![CDATA[z]]></div><!-- c -->"#;
+    let stats = parse(input, Default::default()).unwrap().statistics();
+
+    assert_eq!(stats.elements, 3);
+    assert_eq!(
+        stats
+            .tags
+            .iter()
+            .map(|(k, v)| (k.as_str(), *v))
+            .collect::<Vec<_>>(),
+        [("br", 1), ("div", 1), ("p", 1)]
+    );
+    assert_eq!(stats.text_nodes, 2);
+    assert_eq!(stats.text_bytes, "x &amp; y".len() + "z".len());
+    assert_eq!(stats.comments, 1);
+    assert_eq!(stats.attributes, 3);
+    assert_eq!(stats.max_depth, 3);
+
+    let mut collector = Collector::new(ParserOptions::default());
+    assert_eq!(collector.collect(input.as_bytes()).unwrap(), stats);
+    assert_eq!(collector.collect(b"").unwrap(), Default::default());
+    assert_eq!(collector.collect(input.as_bytes()).unwrap(), stats);
+
+    // the collector counts nodes while parsing, which gives the same results as counting the tree
+    for input in [
+        "<ul><li>a<li>b</ul></div>text<p>unclosed<b>x",
+        "<svg><circle/><g><path></path></g></svg><template><p>t</p></template>",
+        "<table><tr><td>1</td></tr></table><!-- x --><br><img src=a>",
+    ] {
+        let expected = parse(input, Default::default()).unwrap().statistics();
+        assert_eq!(
+            collector.collect(input.as_bytes()).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+
+    // only the open elements are kept while parsing
+    let input = "<div><p>a</p>".repeat(100);
+    assert_eq!(collector.collect(input.as_bytes()).unwrap().max_depth, 102);
+    assert!(collector.parser.as_ref().unwrap().tags.capacity() < 200);
+
+    // detached nodes are not counted
+    let mut dom = parse("<p>a</p><p>b</p>", Default::default()).unwrap();
+    dom.children_mut()[1] = dom.children()[0];
+    assert_eq!(dom.statistics().tags["p"], 2);
+}
+
+#[test]
+fn distinct_ids_and_class_names() {
+    let input = r#"<div id="main" class="b a"><p class="a  c" id="x"></p><p class="c b" id="main"></p><p class="d"></p></div>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_ids().track_classes(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        assert_eq!(dom.ids(), ["main", "x"]);
+        assert_eq!(dom.class_names(), ["b", "a", "c", "d"]);
+    }
+
+    let dom = parse("<p>no attributes</p>", Default::default()).unwrap();
+    assert!(dom.ids().is_empty());
+    assert!(dom.class_names().is_empty());
+}
+
+#[test]
+fn query_selector_is_where() {
+    use crate::queryselector::selector::Selector;
+
+    let dom = parse(
+        r#"<h1 id="a"><a>1</a></h1><h2><span><a>2</a></span></h2><h3 class="x"><a>3</a></h3><p><a>4</a></p>
+        <ul><li class="note">5</li><li>6</li></ul>"#,
+        ParserOptions::default().track_classes(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let texts = |selector: &str| {
+        dom.query_selector(selector)
+            .unwrap()
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser).into_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(texts(":is(h1, h2, h3) a"), ["1", "2", "3"]);
+    assert_eq!(texts(":is(h1,h2,h3) > a"), ["1", "3"]);
+    assert_eq!(texts(":where( h1#a , h3.x ) > a"), ["1", "3"]);
+    assert_eq!(texts("ul :is(li.note, p)"), ["5"]);
+    assert_eq!(texts("li:is(.note)"), ["5"]);
+    assert_eq!(
+        texts(":is(:is(h1), :where(p)) a, li:is(.none, .note)"),
+        ["1", "4", "5"]
+    );
+    assert_eq!(texts(":is(ul > li)"), ["5", "6"]);
+    assert_eq!(texts(":IS(h2 span) a"), ["2"]);
+
+    assert!(matches!(
+        crate::parse_query_selector("div:is(a, b)"),
+        Some(Selector::And(left, right))
+            if matches!(*left, Selector::Tag(b"div")) && matches!(*right, Selector::Or(..))
+    ));
+
+    for invalid in [":is(", ":is()", ":is(a,)", ":where(a", ":is(a, :is(b)"] {
+        assert!(crate::parse_query_selector(invalid).is_none(), "{invalid}");
+    }
+}
+
+#[test]
+fn query_selector_structural_pseudo_classes() {
+    let dom = parse(
+        r#"<html><body><div class="box"> <!-- c --> </div><div class="box">x</div><p></p>
+        <section><h2>a</h2><p>1</p><h2>b</h2><p>2</p><P>3</P><span>s</span></section></body></html>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let names = |selector: &str| {
+        dom.query_selector(selector)
+            .unwrap()
+            .map(|handle| {
+                let node = handle.get(parser).unwrap();
+                let tag = node.as_tag().unwrap();
+                format!(
+                    "{}:{}",
+                    tag.name().as_utf8_str(),
+                    node.inner_text(parser).trim()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names("div:empty, p:empty"), ["div:", "p:"]);
+    assert_eq!(
+        dom.query_selector(":root").unwrap().collect::<Vec<_>>(),
+        [dom.children()[0]]
+    );
+    assert_eq!(names(":root > body > .box:empty"), ["div:"]);
+    assert_eq!(names("section :first-of-type"), ["h2:a", "p:1", "span:s"]);
+    assert_eq!(names("section :last-of-type"), ["h2:b", "p:3", "span:s"]);
+    assert_eq!(names("section > :only-of-type"), ["span:s"]);
+    assert_eq!(names("section :nth-of-type(3)"), ["p:3"]);
+    assert_eq!(names("section :NTH-OF-TYPE( 2 )"), ["h2:b", "p:2"]);
+    assert_eq!(names("body > div:nth-of-type(2):last-of-type"), ["div:x"]);
+
+    // pseudo-class names must end where the keyword ends
+    assert!(crate::parse_query_selector("p:rooted").is_some());
+    assert_eq!(names("p:rooted").len(), 0);
+    assert!(crate::parse_query_selector("p:nth-of-type(0)").is_none());
+}
+
+#[test]
+fn query_selector_namespaces() {
+    use crate::queryselector::Namespaces;
+
+    let input = r##"<a href="/">Home</a><title>Page</title>
+        <svg><a xlink:href="icon" href="alt"><title>Icon</title></a><circle r="1"/></svg>
+        <math><mi>x</mi></math>"##;
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let namespaces = Namespaces::new()
+        .prefix("s", "http://www.w3.org/2000/svg")
+        .prefix("h", "http://www.w3.org/1999/xhtml")
+        .prefix("m", "http://www.w3.org/1998/Math/MathML")
+        .prefix("xl", "http://www.w3.org/1999/xlink");
+
+    let names = |selector| {
+        dom.query_selector_ns(selector, &namespaces)
+            .unwrap()
+            .map(|handle| {
+                let node = handle.get(parser).unwrap();
+                format!(
+                    "{}:{}",
+                    node.as_tag().unwrap().name().as_utf8_str(),
+                    node.inner_text(parser)
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names("s|title"), ["title:Icon"]);
+    assert_eq!(names("h|title"), ["title:Page"]);
+    assert_eq!(names("*|title"), ["title:Page", "title:Icon"]);
+    assert_eq!(names("title"), ["title:Page", "title:Icon"]);
+    assert_eq!(names("s|a > title"), ["title:Icon"]);
+    assert_eq!(names("m|*"), ["math:x", "mi:x"]);
+    assert_eq!(names("s|svg s|*:is(circle)").len(), 1);
+    assert_eq!(names("|a").len(), 0);
+
+    assert_eq!(names("[xl|href]"), ["a:Icon"]);
+    assert_eq!(names("[xl|href='icon']"), ["a:Icon"]);
+    assert_eq!(names("[xl|href=alt]").len(), 0);
+    assert_eq!(names("[*|href$=con]"), ["a:Icon"]);
+    assert_eq!(names("[*|href='/']"), ["a:Home"]);
+    assert_eq!(names("[|href]"), ["a:Home", "a:Icon"]);
+
+    // prefixes must be declared
+    assert!(dom.query_selector_ns("svg|a", &namespaces).is_none());
+    assert!(dom.query_selector_ns("[xlink|href]", &namespaces).is_none());
+    assert!(dom.query_selector("s|a").is_none());
+    assert_eq!(dom.query_selector("*|a").unwrap().count(), 2);
+}
+
+#[test]
+fn query_selector_escapes() {
+    let input = r#"<div id="123"><p class="md:w-1/2 hover:bg-red">a</p><p class="café">b</p></div>
+        <span data-x:y="1">c</span><p>"quoted"</p>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_ids().track_classes(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        let parser = dom.parser();
+        let text = |selector| {
+            dom.query_selector(selector)
+                .unwrap()
+                .map(|handle| handle.get(parser).unwrap().inner_text(parser).into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(text(r"#\31 23 > .md\:w-1\/2"), ["a"]);
+        assert_eq!(text(r"#\000031 23 .hover\3A bg-red"), ["a"]);
+        assert_eq!(text(r"p.hover\:bg-red:first-of-type"), ["a"]);
+        assert_eq!(text(".café, .caf\\E9"), ["b"]);
+        assert_eq!(text(r"[data-x\:y=\31]"), ["c"]);
+        assert_eq!(text(r#"p:contains("\"quoted\"")"#), ["\"quoted\""]);
+        assert_eq!(text("#123").len(), 1);
+    }
+
+    assert_eq!(
+        &*crate::queryselector::unescape(br"\0 \110000 \"),
+        "\u{FFFD}\u{FFFD}\u{FFFD}".as_bytes()
+    );
+}
+
+#[test]
+fn attribute_name_case() {
+    let input =
+        r#"<div ID="a" Class="b c" DATA-X="1" data-x="2"><svg viewBox="0 0 1 1"></svg></div>"#;
+
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(div.attributes().id().unwrap(), "a");
+    assert_eq!(div.attributes().class().unwrap(), "b c");
+    assert_eq!(div.attributes().len(), 3);
+    assert_eq!(div.attributes().get("Data-X").flatten().unwrap(), "2");
+    assert!(div.attributes().iter().any(|(name, _)| name == "data-x"));
+    assert_eq!(dom.query_selector("[DATA-x='2']").unwrap().count(), 1);
+    assert_eq!(dom.query_selector("#a.c").unwrap().count(), 1);
+    assert_eq!(div.to_html(dom.parser()), input);
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div data-x="2" id="a" class="b c"><svg viewBox="0 0 1 1"></svg></div>"#
+    );
+
+    // foreign content keeps the case of its attributes, which are still found case insensitively
+    let svg = dom.nodes()[1].as_tag().unwrap();
+    assert!(svg.attributes().iter().any(|(name, _)| name == "viewBox"));
+    assert!(svg.attributes().contains("viewbox"));
+
+    let mut dom = parse(input, ParserOptions::default().preserve_attribute_case()).unwrap();
+    let div = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    assert!(div.attributes().iter().any(|(name, _)| name == "DATA-X"));
+    assert_eq!(div.attributes().get("data-x").flatten().unwrap(), "2");
+    assert_eq!(div.attributes().len(), 3);
+
+    div.attributes_mut().insert("data-X", Some("3"));
+    assert_eq!(div.attributes().len(), 3);
+    assert_eq!(div.attributes().get("DATA-X").flatten().unwrap(), "3");
+    assert_eq!(
+        div.attributes_mut().remove("Data-x"),
+        Some(Some("3".into()))
+    );
+}
+
+#[test]
+fn tag_name_case() {
+    let input = "<DIV Class=a><Br><P>x</p><svg><linearGradient/><foreignObject><SPAN>y</SPAN></foreignObject></SVG></div>";
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_tags(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        let names = dom
+            .nodes()
+            .iter()
+            .filter_map(|node| node.as_tag())
+            .map(|tag| {
+                format!(
+                    "{}/{}",
+                    tag.name().as_utf8_str(),
+                    tag.raw_name().as_utf8_str()
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            [
+                "div/DIV",
+                "br/Br",
+                "p/P",
+                "svg/svg",
+                "linearGradient/linearGradient",
+                "foreignObject/foreignObject",
+                "span/SPAN"
+            ]
+        );
+
+        // `<Br>` is void and `</p>` closes `<P>`, so everything is inside of the div
+        assert_eq!(dom.children().len(), 1);
+        assert_eq!(dom.query_selector("div > p").unwrap().count(), 1);
+        assert_eq!(
+            dom.query_selector("div.a > br + p, div > br")
+                .unwrap()
+                .count(),
+            1
+        );
+        assert_eq!(
+            dom.query_selector("foreignObject > span").unwrap().count(),
+            1
+        );
+        assert_eq!(dom.get_elements_by_tag_name("div").count(), 1);
+
+        // type selectors are case insensitive for HTML elements, but not for foreign elements
+        assert_eq!(dom.query_selector("DIV").unwrap().count(), 1);
+        assert_eq!(dom.query_selector("Div > P, BR").unwrap().count(), 2);
+        assert_eq!(dom.query_selector("DIV span").unwrap().count(), 1);
+        assert_eq!(dom.query_selector("FOREIGNOBJECT").unwrap().count(), 0);
+        assert_eq!(dom.query_selector("lineargradient").unwrap().count(), 0);
+        assert_eq!(
+            crate::TagName::new("span"),
+            dom.nodes()[7].as_tag().unwrap().tag_name()
+        );
+    }
+
+    let xml = parse("<A><B></b></B></A>", ParserOptions::default().xml_mode()).unwrap();
+    let b = xml.nodes()[1].as_tag().unwrap();
+    assert_eq!(b.name(), "B");
+    assert!(b.span().end_tag().is_some());
+    assert_eq!(xml.query_selector("B").unwrap().count(), 1);
+    assert_eq!(xml.query_selector("b").unwrap().count(), 0);
+}
+
+#[test]
+fn reparse_range() {
+    use crate::incremental::Edit;
+
+    fn check(old: &str, range: std::ops::Range<usize>, text: &str, incremental: bool) {
+        let new = format!("{}{}{}", &old[..range.start], text, &old[range.end..]);
+        let options = ParserOptions::default()
+            .track_ids()
+            .track_classes()
+            .track_tags();
+
+        let dom = parse(old, options).unwrap();
+        let removed = dom.nodes().len();
+        let (dom, changes) = dom
+            .reparse_range(Edit::replace(range, text.len()), &new)
+            .unwrap();
+        let expected = parse(&new, options).unwrap();
+        let parser = dom.parser();
+
+        assert_eq!(changes.is_full(), !incremental, "{new}");
+        assert_eq!(
+            changes.removed().count() + dom.nodes().len() - changes.inserted().count(),
+            removed
+        );
+        assert_eq!(dom.outer_html(), expected.outer_html());
+        assert_eq!(indices(dom.children()), indices(expected.children()));
+        assert_eq!(dom.nodes().len(), expected.nodes().len());
+
+        for (index, (node, expected_node)) in dom.nodes().iter().zip(expected.nodes()).enumerate() {
+            let handle = NodeHandle::new(index as u32);
+            assert_eq!(
+                indices(&parser.parent_of(handle)),
+                indices(&expected.parser().parent_of(handle))
+            );
+            assert_eq!(
+                parser.position_of(handle),
+                expected.parser().position_of(handle)
+            );
+
+            match (node, expected_node) {
+                (Node::Tag(tag), Node::Tag(expected_tag)) => {
+                    assert_eq!(tag.name(), expected_tag.name());
+                    assert_eq!(tag.span(), expected_tag.span());
+                    assert_eq!(tag.raw(), expected_tag.raw());
+                    assert_eq!(
+                        indices(tag.children().top().as_slice()),
+                        indices(expected_tag.children().top().as_slice())
+                    );
+                    assert_eq!(tag._descendants, expected_tag._descendants);
+                    assert_eq!(tag.tag_name(), expected_tag.tag_name());
+                }
+                _ => assert_eq!(
+                    node.outer_html(parser),
+                    expected_node.outer_html(expected.parser())
+                ),
+            }
+        }
+
+        for selector in ["#a", ".x", "li", "p.x", "template"] {
+            assert_eq!(
+                indices(&dom.query_selector(selector).unwrap().collect::<Vec<_>>()),
+                indices(
+                    &expected
+                        .query_selector(selector)
+                        .unwrap()
+                        .collect::<Vec<_>>()
+                ),
+                "{selector}"
+            );
+        }
+        assert_eq!(
+            indices(&dom.get_elements_by_class_name("x").collect::<Vec<_>>()),
+            indices(&expected.get_elements_by_class_name("x").collect::<Vec<_>>())
+        );
+        assert_eq!(
+            indices(&dom.get_elements_by_id("a").collect::<Vec<_>>()),
+            indices(&expected.get_elements_by_id("a").collect::<Vec<_>>())
+        );
+    }
+
+    let old = r#"<p class="x">a</p><ul id="a"><li class="x">one</li><li>two</li></ul><p id="a" class="x">end</p><template><li>t</li></template>"#;
+    let one = old.find("one").unwrap();
+    let two = old.find("two").unwrap();
+
+    // typing inside of a list item only parses the list item again
+    check(old, one + 3..one + 3, "!", true);
+    check(old, one..one + 3, "", true);
+    check(
+        old,
+        one..one + 3,
+        r#"<b id="a" class="x">1</b><li class="x">2"#,
+        false,
+    );
+    check(
+        old,
+        two..two + 3,
+        r#"<b id="a" class="x">2</b><i>3</i>"#,
+        true,
+    );
+    check(old, two..two + 1, "<!-- c -->", true);
+
+    // edits that change the rest of the document fall back to a full parse
+    check(old, two..two + 1, "</li>", false);
+    check(old, two..two + 1, "<b>", false);
+    check(old, two..two + 1, "<!--", false);
+    check(old, 0..0, "text", false);
+    check(old, old.len()..old.len(), "<li>", false);
+
+    let new = format!("{}<i>0</i>{}", &old[..two], &old[two..]);
+    let dom = parse(old, ParserOptions::default()).unwrap();
+    let ul = dom.get_element_by_id("a").unwrap();
+    let (dom, changes) = dom
+        .reparse_range(Edit::insert(two, "<i>0</i>".len()), &new)
+        .unwrap();
+    assert_eq!(changes.removed().count(), 1);
+    assert_eq!(changes.inserted().count(), 3);
+    assert_eq!(changes.parent(), Some(dom.parser().handle(5)));
+    assert_eq!(changes.map(ul), Some(dom.parser().handle(ul.get_inner())));
+    assert_eq!(changes.map(NodeHandle::new(6)), None);
+    assert_eq!(
+        changes.map(NodeHandle::new(7)),
+        Some(dom.parser().handle(9))
+    );
+    assert_eq!(
+        changes
+            .map(NodeHandle::new(7))
+            .unwrap()
+            .get(dom.parser())
+            .unwrap()
+            .inner_text(dom.parser()),
+        "end"
+    );
+
+    // edits that do not match the sources are not trusted
+    let dom = parse("<p>ab</p>", ParserOptions::default()).unwrap();
+    let (dom, changes) = dom.reparse_range(Edit::insert(4, 1), "<p>xab</p>").unwrap();
+    assert!(changes.is_full());
+    assert_eq!(dom.outer_html(), "<p>xab</p>");
+}
+
+#[test]
+fn node_handle_documents() {
+    let a = parse(
+        "<p id=a>a</p><p>b</p>",
+        ParserOptions::default().track_ids(),
+    )
+    .unwrap();
+    let b = parse("<div><span>c</span></div>", ParserOptions::default()).unwrap();
+
+    let p = a.get_element_by_id("a").unwrap();
+    let second = a.query_selector("p").unwrap().nth(1).unwrap();
+    assert!(a.parser().contains(p));
+    assert!(!b.parser().contains(p));
+    assert!(p.get(b.parser()).is_none());
+    assert!(second.next_sibling(b.parser()).is_none());
+    assert_eq!(p.next_sibling(a.parser()), Some(second));
+
+    // handles without a document still work everywhere
+    let first = NodeHandle::new(0);
+    assert!(b.parser().contains(first));
+    assert!(!b.parser().contains(NodeHandle::new(3)));
+
+    // handles are compared by their index only, so they can be looked up with handles without a document
+    assert_eq!(first, p);
+    assert_eq!(first, b.children()[0]);
+    let handles = a
+        .children()
+        .iter()
+        .copied()
+        .collect::<std::collections::HashSet<_>>();
+    assert!(handles.contains(&NodeHandle::new(0)));
+    assert!(handles.contains(&b.children()[0]));
+    assert_eq!(first.cmp(&p), std::cmp::Ordering::Equal);
+    assert_eq!(
+        std::mem::size_of::<Option<NodeHandle>>(),
+        std::mem::size_of::<NodeHandle>()
+    );
+
+    let mut b = b;
+    assert!(p.get_mut(b.parser_mut()).is_none());
+
+    // parsing again creates a new document
+    let b = b.reparse("<p>d</p>").unwrap();
+    let child = b.children()[0];
+    assert!(b.parser().contains(child));
+    let b = b.reparse("<p>e</p>").unwrap();
+    assert!(!b.parser().contains(child));
+    assert!(b.parser().contains(b.children()[0]));
+
+    // documents loaded from the cache have their own handles too
+    let cache = crate::cache::serialize(&a);
+    let loaded = crate::cache::load(&cache).unwrap();
+    let handle = loaded.children()[0];
+    assert!(loaded.parser().contains(handle));
+    assert!(!a.parser().contains(handle));
+    let text = loaded.nodes()[0].as_tag().unwrap().children().top()[0];
+    assert!(loaded.parser().contains(text));
+}
+
+#[test]
+fn clone_subtree() {
+    let fragment = {
+        let source = String::from(
+            r#"<section><div id=card class="card big">Hi <!-- x --><b>there</b></div></section>"#,
+        );
+        let dom = parse(&source, ParserOptions::default()).unwrap();
+        let card = dom.get_element_by_id("card").unwrap();
+        let card = card.get(dom.parser()).unwrap().as_tag().unwrap();
+        card.clone_subtree(dom.parser())
+    };
+
+    assert_eq!(fragment.len(), 5);
+    assert_eq!(
+        fragment.outer_html(),
+        r#"<div id="card" class="card big">Hi <!-- x --><b>there</b></div>"#
+    );
+
+    let copy = fragment.to_dom();
+    let b = copy.query_selector("div > b").unwrap().next().unwrap();
+    assert_eq!(
+        b.get(copy.parser()).unwrap().inner_text(copy.parser()),
+        "there"
+    );
+
+    let mut dom = parse(
+        "<main><p>a</p></main>",
+        ParserOptions::default().track_ids().track_classes(),
+    )
+    .unwrap();
+    let main = dom.children()[0];
+    let first = dom.insert_fragment(Some(main), 0, &fragment).unwrap();
+    let top = dom.insert_fragment(None, 1, &fragment).unwrap();
+    assert_eq!(dom.insert_fragment(None, 3, &fragment), None);
+
+    assert_eq!(
+        dom.to_html(),
+        r#"<main><div id="card" class="card big">Hi <!-- x --><b>there</b></div><p>a</p></main><div id="card" class="card big">Hi <!-- x --><b>there</b></div>"#
+    );
+    assert_eq!(
+        dom.get_elements_by_id("card").collect::<Vec<_>>(),
+        [first, top]
+    );
+    assert_eq!(dom.get_elements_by_class_name("big").count(), 2);
+    assert_eq!(dom.query_selector("main .card b").unwrap().count(), 1);
+
+    let parser = dom.parser();
+    assert!(parser.contains(first));
+    let tag = first.get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(parser.parent_of(first), Some(main));
+    assert_eq!(tag.tag_name(), crate::TagName::new("div"));
+    let text = tag.children().top()[0];
+    assert_eq!(parser.parent_of(text), Some(first));
+    assert_eq!(
+        first
+            .next_sibling(parser)
+            .unwrap()
+            .get(parser)
+            .unwrap()
+            .inner_text(parser),
+        "a"
+    );
+    assert_eq!(top.prev_sibling(parser), Some(main));
+}
+
+#[test]
+fn rewrite_streaming() {
+    use crate::errors::RewriteError;
+    use crate::rewrite::Handlers;
+
+    fn run(input: &str, handlers: Handlers<'_>) -> String {
+        let mut output = Vec::new();
+        crate::rewrite(input, handlers, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    // without handlers, the input is copied exactly, including stray end tags and unclosed elements
+    let input = "<!DOCTYPE html><DIV Class=a>x</span><!-- c --><p>y<br/>z</DIV><ul><li>open";
+    assert_eq!(run(input, Handlers::new()), input);
+
+    let input =
+        r#"<div><p id=a>Hello</p><p class=r>World <b>!</b></p><img src=x.png><span>s</span></div>"#;
+    let mut calls = 0;
+    let output = run(
+        input,
+        Handlers::new()
+            .on("div > p#a", |element| {
+                element.set_attribute("title", "hi");
+                element.before("<hr>");
+                element.prepend("[");
+                element.append("]");
+                element.after("<br>");
+            })
+            .on("p.r", |element| element.set_inner_content("replaced"))
+            .on("b", |_| calls += 1)
+            .on("img", |element| element.replace("<picture></picture>"))
+            .on("span", |element| element.remove_and_keep_content()),
+    );
+    assert_eq!(
+        output,
+        r#"<div><hr><p title="hi" id="a">[Hello]</p><br><p class=r>replaced</p><picture></picture>s</div>"#
+    );
+    // elements inside of replaced content are not passed to handlers
+    assert_eq!(calls, 0);
+
+    // removing elements, renaming them and keeping self-closing tags
+    let input =
+        r#"<section><script>a()</script><x-a>keep</x-a><svg><path d="M0"/></svg></section>"#;
+    let output = run(
+        input,
+        Handlers::new()
+            .on("section script", |element| element.remove())
+            .on("x-a", |element| {
+                assert_eq!(element.get_attribute("missing"), None);
+                element.tag_mut().name_mut().set("span").unwrap();
+            })
+            .on("svg > path", |element| {
+                assert!(element.remove_attribute("d"));
+                assert!(!element.remove_attribute("d"));
+            }),
+    );
+    assert_eq!(
+        output,
+        r#"<section><span>keep</span><svg><path/></svg></section>"#
+    );
+
+    let error = crate::rewrite("", Handlers::new().on("p:empty", |_| {}), Vec::new());
+    assert!(matches!(error, Err(RewriteError::UnsupportedSelector(s)) if s == "p:empty"));
+    let error = crate::rewrite("", Handlers::new().on("p >", |_| {}), Vec::new());
+    assert!(matches!(error, Err(RewriteError::InvalidSelector(_))));
+}
+
+#[test]
+fn css_inline() {
+    use crate::css_inline::CssInliner;
+    use crate::queryselector::Specificity;
+
+    let specificity = |selector| crate::parse_query_selector(selector).unwrap().specificity();
+    assert_eq!(specificity("*"), Specificity::default());
+    assert_eq!(
+        specificity("ul li:nth-child(2) > a[href]"),
+        Specificity {
+            ids: 0,
+            classes: 2,
+            types: 3
+        }
+    );
+    assert_eq!(
+        specificity(":is(#a, .b) p"),
+        Specificity {
+            ids: 1,
+            classes: 0,
+            types: 1
+        }
+    );
+    assert!(specificity("#a") > specificity(".a.b.c"));
+
+    let css = r#"
+        @import url("fonts.css");
+        /* a comment, with { braces } */
+        @media (max-width: 600px) { td { display: block !important } }
+        td { padding: 4px; color: black !important }
+        #cell, .x { color: red; padding-left: 8px }
+        table td { padding: 2px }
+        td::before, p:hover { content: "x" }
+    "#;
+    let inliner = CssInliner::new(css);
+    assert_eq!(inliner.unsupported(), ["td::before", "p:hover"]);
+
+    let mut dom = parse(
+        r#"<table><tr><td id="cell" style="padding-left: 1px">a</td><td style="color: blue !important">b</td><td class="x">c</td></tr></table><p>d</p>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(inliner.inline(&mut dom), 3);
+
+    let styles = dom
+        .query_selector("td")
+        .unwrap()
+        .map(|td| {
+            let tag = td.get(dom.parser()).unwrap().as_tag().unwrap();
+            tag.attributes().style().collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // the important color of `td` wins over `#cell`, and the inline padding-left over `#cell`
+    assert_eq!(
+        styles[0],
+        [
+            ("padding", "2px"),
+            ("padding-left", "1px"),
+            ("color", "black !important")
+        ]
+    );
+    // an important inline declaration wins over important declarations of rules
+    assert_eq!(
+        styles[1],
+        [("padding", "2px"), ("color", "blue !important")]
+    );
+    assert_eq!(
+        styles[2],
+        [
+            ("padding", "2px"),
+            ("padding-left", "8px"),
+            ("color", "black !important")
+        ]
+    );
+
+    // elements that no rule matches keep their markup
+    assert_eq!(dom.nodes().last().unwrap().outer_html(dom.parser()), "d");
+    assert!(dom.outer_html().ends_with("<p>d</p>"));
+}
+
+#[test]
+fn lint_rules() {
+    use crate::lint::{Linter, Rule, Severity};
+
+    let source = concat!(
+        r#"<html><body><ul><li>a<li>b</ul>"#,
+        r#"<p id="x" ID="y">c<br><img src="a.png" alt=""><img src="b.png"><svg><path/></svg>"#,
+        r#"<font color="red" OnMouseOver="f()">d</font><div id="x"><b>e</div></body></html>"#
+    );
+    let dom = parse(source, ParserOptions::default()).unwrap();
+    let diagnostics = crate::lint::lint(&dom);
+
+    let found = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.rule, &source[diagnostic.span.range()]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            (Rule::UnclosedTag, "<ul>"),
+            (Rule::StrayEndTag, "</ul>"),
+            (Rule::DuplicateAttribute, r#"ID="y""#),
+            (Rule::MissingAlt, r#"<img src="b.png">"#),
+            (
+                Rule::DeprecatedElement,
+                r#"<font color="red" OnMouseOver="f()">"#
+            ),
+            (Rule::InlineEventHandler, r#"OnMouseOver="f()""#),
+            (Rule::UnclosedTag, r#"<div id="x">"#),
+            (Rule::DuplicateId, r#"id="x""#),
+            (Rule::UnclosedTag, "<b>"),
+            (Rule::StrayEndTag, "</div>"),
+            (Rule::StrayEndTag, "</body>"),
+            (Rule::StrayEndTag, "</html>"),
+        ]
+    );
+
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].message, "<ul> has no end tag");
+    assert_eq!(diagnostics[1].node, None);
+    assert_eq!(diagnostics[3].severity, Severity::Warning);
+    assert_eq!(
+        diagnostics[3]
+            .node
+            .unwrap()
+            .get(dom.parser())
+            .unwrap()
+            .as_tag()
+            .unwrap()
+            .name(),
+        "img"
+    );
+
+    let diagnostics = Linter::empty()
+        .enable(Rule::StrayEndTag)
+        .enable(Rule::StrayEndTag)
+        .lint(&dom);
+    assert_eq!(diagnostics.len(), 4);
+    assert_eq!(Rule::StrayEndTag.to_string(), "stray-end-tag");
+    assert!(Linter::new()
+        .disable(Rule::MissingAlt)
+        .severity_of(Rule::MissingAlt)
+        .is_none());
+}
+
+#[test]
+fn text_fingerprint() {
+    use crate::fingerprint::{TextFingerprint, TextHasher};
+
+    let hasher = TextHasher::new();
+    let text = hasher.hash_text("one two three four five");
+
+    // markup, case, punctuation, invisible content and boilerplate do not matter
+    let dom = parse(
+        r#"<head><title>Title</title></head><header>Site</header><div role="navigation main">Menu</div>
+        <p>One, <b>two</b> THREE</p><p hidden>secret</p><style>p {}</style><p>four five!</p><footer>(c)</footer>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(dom.text_fingerprint(), text);
+    assert_ne!(hasher.include_boilerplate().hash(&dom), text);
+
+    let p = dom.query_selector("p").unwrap().next().unwrap();
+    assert_eq!(
+        hasher.hash_node(p, dom.parser()),
+        hasher.hash_text("one two three")
+    );
+
+    // word order only matters within a shingle
+    let swapped = hasher.hash_text("four five one two three");
+    assert_ne!(swapped, text);
+    assert_eq!(
+        hasher.shingle_size(1).hash_text("four five one two three"),
+        hasher.shingle_size(0).hash_text("one two three four five")
+    );
+
+    assert_eq!(hasher.hash_text(" ,. "), TextFingerprint(0));
+    assert_eq!(
+        TextFingerprint(0b1011).distance(&TextFingerprint(0b0110)),
+        3
+    );
+    assert_eq!(text.similarity(&text), 1.0);
+    assert_eq!(
+        TextFingerprint(0).similarity(&TextFingerprint(u64::MAX)),
+        0.0
+    );
+}
+
+#[test]
+fn page_stats() {
+    let dom = parse(
+        r#"<title>A
+        title</title><meta name=" Description "><meta name="description" content="ignored">
+        <h2>Intro</h2><h2>More</h2><h6>Small</h6><svg><title>icon</title></svg>
+        <p>One two, <b>three</b>&nbsp;&amp; -- four<br>five</p><template><p>hidden words</p></template>
+        <a href="https://example.com/a">a</a><a href="//cdn.example.org/b">b</a><a href="mailto:x@y.z">c</a>
+        <img src="a.png" ALT="A"><img src="b.png">"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let stats = dom.page_stats(Some("https://example.com/"));
+    // the headings count as words too, but the adjacent links only as one
+    assert_eq!(stats.words, 10);
+    assert_eq!(stats.headings, [0, 2, 0, 0, 0, 1]);
+    assert_eq!(stats.images, 2);
+    assert_eq!(stats.images_without_alt, 1);
+    assert_eq!(stats.internal_links, 1);
+    assert_eq!(stats.external_links, 1);
+    assert_eq!(stats.title_length, Some(7));
+    assert_eq!(stats.meta_description_length, Some(0));
+
+    let stats = dom.page_stats(Some("https://other.com/"));
+    assert_eq!((stats.internal_links, stats.external_links), (0, 2));
+
+    let empty = parse("", ParserOptions::default())
+        .unwrap()
+        .page_stats(None);
+    assert_eq!(empty, crate::stats::PageStats::default());
+}
+
+#[test]
+fn query_selector_quoted_attribute_values() {
+    let input = r#"<button data-x='a"b' onclick="foo(1)">1</button><a title="a ] b, c">2</a>
+        <a href="/x" data-label="it's [here]">3</a><p data-x="a\b">4</p>"#;
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let text = |selector| {
+        dom.query_selector(selector)
+            .unwrap_or_else(|| panic!("{selector} is invalid"))
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser).into_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(text(r#"[data-x="a\"b"]"#), ["1"]);
+    assert_eq!(text(r#"[data-x='a"b']"#), ["1"]);
+    assert_eq!(text(r#"[onclick*="foo(1)"]"#), ["1"]);
+    assert_eq!(text(r#"button[onclick^='foo('][onclick$=")"]"#), ["1"]);
+    assert_eq!(text(r#"[title="a ] b, c"]"#), ["2"]);
+    assert_eq!(text(r#"a[title="a ] b, c"], p"#), ["2", "4"]);
+    assert_eq!(text(r#"[ data-label ~= "[here]" ]"#), ["3"]);
+    assert_eq!(text(r#"[data-label='it\'s [here]']"#), ["3"]);
+    assert_eq!(text(r#":is([title="a ) b"], [href="/x"])"#), ["3"]);
+    assert_eq!(text(r#"[data-x="a\\b"]"#), ["4"]);
+    assert_eq!(text(r#"[data-x="a:empty"]"#), Vec::<String>::new());
+
+    assert!(dom.query_selector(r#"[data-x="a]"#).is_none());
+    assert!(dom.query_selector(r#"[data-x="a\"]"#).is_none());
+    assert!(dom.query_selector(r#"[data-x="a"b]"#).is_none());
+}
+
+#[test]
+fn query_selector_limits() {
+    use crate::errors::SelectorError;
+    use crate::queryselector::SelectorLimits;
+
+    let parse = crate::parse_query_selector_with_limits;
+    let unlimited = SelectorLimits::new()
+        .max_length(usize::MAX)
+        .max_nesting(usize::MAX)
+        .max_components(usize::MAX);
+
+    // nesting stops at the limit instead of recursing through the whole selector
+    let nested = ":is(".repeat(100_000);
+    assert_eq!(
+        parse(&nested, SelectorLimits::new().max_length(usize::MAX)).unwrap_err(),
+        SelectorError::TooDeep { max: 8 }
+    );
+
+    let long = "a".repeat(2000);
+    assert_eq!(
+        parse(&long, SelectorLimits::new()).unwrap_err(),
+        SelectorError::TooLong {
+            length: 2000,
+            max: 1024
+        }
+    );
+    assert!(parse(&long, unlimited).is_ok());
+
+    let list = "a,".repeat(10_000) + "b";
+    assert_eq!(
+        parse(&list, SelectorLimits::new().max_length(usize::MAX)).unwrap_err(),
+        SelectorError::TooManyComponents { max: 128 }
+    );
+
+    // pseudo-classes with a selector list count as one more component
+    let limits = SelectorLimits::new().max_components(3);
+    assert!(parse("div :is(a, b)", limits).is_err());
+    assert!(parse("div :is(a)", limits).is_ok());
+    assert!(parse(":is(a)", SelectorLimits::new().max_nesting(0)).is_err());
+
+    assert_eq!(parse("", limits).unwrap_err(), SelectorError::Invalid);
+    assert_eq!(
+        SelectorError::TooDeep { max: 8 }.to_string(),
+        "The query selector nests more than 8 selector lists"
+    );
+
+    // the limits do not change which elements match
+    let dom = crate::parse(
+        r#"<div><p class="a">1</p><p class="b">2</p><p>3</p></div>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let count = |selector| {
+        dom.query_selector_with_limits(selector, SelectorLimits::new())
+            .unwrap()
+            .count()
+    };
+    assert_eq!(count("div > p:is(.a, .b)"), 2);
+    assert_eq!(
+        count("div > p:is(.a, .b)"),
+        dom.query_selector("div > p:is(.a, .b)").unwrap().count()
+    );
+}
+
+#[test]
+fn attribute_spans() {
+    let source = r#"<div><a data-x = 'one' href=page.html Hidden title="">x</a></div>"#;
+    let mut dom = parse(source, ParserOptions::default()).unwrap();
+    let a = dom.nodes()[1].as_tag().unwrap();
+
+    let span = a.attribute_span("data-x").unwrap();
+    assert_eq!(&source[span.attribute().range()], "data-x = 'one'");
+    assert_eq!(&source[span.name().range()], "data-x");
+    assert_eq!(&source[span.value().unwrap().range()], "one");
+
+    let href = a.attribute_span("HREF").unwrap();
+    assert_eq!(&source[href.value().unwrap().range()], "page.html");
+    let hidden = a.attribute_span("hidden").unwrap();
+    assert_eq!(&source[hidden.name().range()], "Hidden");
+    assert_eq!(hidden.value(), None);
+    assert!(a
+        .attribute_span("title")
+        .unwrap()
+        .value()
+        .unwrap()
+        .is_empty());
+    assert!(a.attribute_span("id").is_none());
+
+    // patching the source string gives the same document as changing the attribute
+    let mut patched = String::from(source);
+    patched.replace_range(href.value().unwrap().range(), "other.html");
+    let patched = parse(&patched, ParserOptions::default()).unwrap();
+    let tag = patched.nodes()[1].as_tag().unwrap();
+    assert_eq!(
+        tag.attributes()
+            .get("href")
+            .flatten()
+            .unwrap()
+            .as_utf8_str(),
+        "other.html"
+    );
+
+    // the spans keep pointing into the source after the attribute was changed
+    let tag = dom.nodes_mut()[1].as_tag_mut().unwrap();
+    tag.attributes_mut().insert("href", Some("changed.html"));
+    tag.attributes_mut().insert("lang", Some("en"));
+    assert_eq!(tag.attribute_span("href"), Some(href));
+    assert!(tag.attribute_span("lang").is_none());
+
+    // repeated attributes use the occurrence whose value was kept
+    let source = r#"<p id="a" class="x" id="b">"#;
+    let first = ParserOptions::default().with_duplicate_attributes(DuplicateAttributes::KeepFirst);
+    for (options, expected) in [(ParserOptions::default(), "b"), (first, "a")] {
+        let dom = parse(source, options).unwrap();
+        let p = dom.nodes()[0].as_tag().unwrap();
+        let span = p.attribute_span("id").unwrap().value().unwrap();
+        assert_eq!(&source[span.range()], expected);
+    }
+}
+
+#[test]
+fn iframes() {
+    let dom = parse(
+        r#"<base href="https://cdn.example.com/">
+        <iframe src=" ads/slot " name="ad" srcdoc="<a href=&quot;/x&quot;>Tom &amp; Jerry &#8212; &#x1F600; &unknown; &amp</a>"></iframe>
+        <iframe src=""></iframe>
+        <frameset><FRAME src="menu.html" srcdoc="ignored"></frameset>"#,
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let frames = dom.iframes(Some("https://example.com/"));
+    assert_eq!(frames.len(), 3);
+
+    let ad = &frames[0];
+    assert_eq!(ad.src.as_deref(), Some("https://cdn.example.com/ads/slot"));
+    assert_eq!(ad.name.as_deref(), Some("ad"));
+    assert_eq!(ad.title, None);
+    assert_eq!(
+        ad.srcdoc.as_deref(),
+        Some("<a href=\"/x\">Tom & Jerry \u{2014} \u{1F600} &unknown; &amp</a>")
+    );
+    assert_eq!(
+        ad.node.get(dom.parser()).unwrap().as_tag().unwrap().name(),
+        "iframe"
+    );
+
+    let embedded = ad.parse_srcdoc(ParserOptions::default()).unwrap().unwrap();
+    let a = embedded.nodes()[0].as_tag().unwrap();
+    assert_eq!(a.attributes().get("href").flatten().unwrap(), "/x");
+
+    assert_eq!(frames[1].src, None);
+    assert!(!frames[1].is_srcdoc());
+    assert!(frames[1].parse_srcdoc(ParserOptions::default()).is_none());
+
+    // <frame> has no srcdoc
+    assert_eq!(
+        frames[2].src.as_deref(),
+        Some("https://cdn.example.com/menu.html")
+    );
+    assert_eq!(frames[2].srcdoc, None);
+
+    // without a base URL, URLs are returned as written
+    let dom = parse(r#"<iframe src="a.html">"#, ParserOptions::default()).unwrap();
+    assert_eq!(dom.iframes(None)[0].src.as_deref(), Some("a.html"));
+}
+
+#[test]
+fn noscript_content() {
+    let input = r#"<div><noscript><img id="pixel" src="a.gif"><p>Enable JS</p></noscript><img src="b.gif"></div>"#;
+
+    let dom = parse(input, ParserOptions::default().track_ids()).unwrap();
+    assert_eq!(dom.query_selector("img").unwrap().count(), 2);
+    assert!(dom.get_element_by_id("pixel").is_some());
+
+    let options = ParserOptions::default()
+        .track_ids()
+        .parse_noscript_content(false);
+    assert!(!options.is_parsing_noscript_content());
+    assert!(options
+        .parse_noscript_content(true)
+        .is_parsing_noscript_content());
+
+    let dom = parse(input, options).unwrap();
+    assert_eq!(dom.query_selector("img").unwrap().count(), 1);
+    assert!(dom.get_element_by_id("pixel").is_none());
+    let noscript = dom.query_selector("noscript").unwrap().next().unwrap();
+    let noscript = noscript.get(dom.parser()).unwrap();
+    assert_eq!(noscript.children().unwrap().top().len(), 1);
+    assert_eq!(
+        noscript.inner_html(dom.parser()),
+        r#"<img id="pixel" src="a.gif"><p>Enable JS</p>"#
+    );
+    assert_eq!(dom.outer_html(), input);
+
+    // only the end tag of the element ends the text, and a missing end tag ends it with the input
+    let input = "<noscript><b>a</noscripts></NOSCRIPT><p>b</p><noscript></noscript><noscript><i>c";
+    let dom = parse(input, options).unwrap();
+    let texts = dom
+        .nodes()
+        .iter()
+        .filter_map(|node| node.as_raw())
+        .map(|text| text.as_utf8_str().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["<b>a</noscripts>", "b", "<i>c"]);
+    assert_eq!(dom.query_selector("p").unwrap().count(), 1);
+
+    // XML has no special elements
+    let dom = parse("<noscript><b>a</b></noscript>", options.xml_mode()).unwrap();
+    assert_eq!(dom.query_selector("b").unwrap().count(), 1);
+
+    // the option is kept by the raw flags
+    assert_eq!(
+        ParserOptions::from_raw_checked(options.to_raw()),
+        Some(options)
+    );
+}
+
+#[test]
+fn attribute_token_lists() {
+    let dom = parse(
+        "<link rel='Icon  icon\u{a0}x apple-touch-icon icon' sizes=\"16x16 32x32\"><td headers>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let link = dom.nodes()[0].as_tag().unwrap().attributes();
+
+    // only ASCII whitespace separates tokens
+    assert_eq!(
+        link.get_list("rel").collect::<Vec<_>>(),
+        ["Icon", "icon\u{a0}x", "apple-touch-icon", "icon"]
+    );
+    assert_eq!(
+        link.get_list("SIZES").collect::<Vec<_>>(),
+        ["16x16", "32x32"]
+    );
+    assert!(link.contains_token("rel", "apple-touch-icon"));
+    assert!(!link.contains_token("rel", "x"));
+    assert!(!link.contains_token("type", "icon"));
+
+    let td = dom.nodes()[1].as_tag().unwrap().attributes();
+    assert_eq!(td.get_list("headers").count(), 0);
+    assert!(!td.contains_token("headers", ""));
+
+    // the `~=` selector uses the same tokens
+    assert_eq!(dom.query_selector("[rel~=Icon]").unwrap().count(), 1);
+    assert_eq!(dom.query_selector("[rel~=x]").unwrap().count(), 0);
+    assert_eq!(dom.query_selector("[sizes~=\"32x32\"]").unwrap().count(), 1);
+}
+
+#[test]
+fn query_selector_with_matcher() {
+    use crate::queryselector::{CompiledSelector, Matcher, QuerySelectorIterator};
+
+    struct Depth(usize);
+
+    impl Matcher for Depth {
+        fn matches<'b>(&self, node: &Node<'b>, parser: &crate::Parser<'b>) -> bool {
+            let Some(tag) = node.as_tag() else {
+                return false;
+            };
+            let mut depth = 0;
+            let mut parent = tag.parent().get(parser);
+            while let Some(node) = parent {
+                depth += 1;
+                parent = node.as_tag().and_then(|tag| tag.parent().get(parser));
+            }
+            depth == self.0
+        }
+    }
+
+    let dom = parse(
+        "<div><p>a</p><template><p>b</p></template><section><p>c</p></section></div>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    // template contents are skipped, like for query selectors
+    let names = dom
+        .query_selector_with(Depth(1))
+        .tags()
+        .map(|tag| tag.name().as_utf8_str().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["p", "template", "section"]);
+
+    // closures see every node, including text
+    let is_text = |node: &Node<'_>, _: &crate::Parser<'_>| node.as_raw().is_some();
+    assert_eq!(dom.query_selector_with(is_text).count(), 2);
+    assert_eq!(
+        dom.query_selector_with(is_text)
+            .filter_text("c")
+            .texts()
+            .collect::<Vec<_>>(),
+        ["c"]
+    );
+
+    // only the subnodes of a tag are queried
+    let section = dom.query_selector("section").unwrap().first().unwrap();
+    let section = section.get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(section.query_selector_with(parser, Depth(2)).count(), 1);
+    assert_eq!(section.query_selector_with(parser, Depth(1)).count(), 0);
+
+    // selectors are matchers too, and give the same results as the query selector iterator
+    let selector = crate::parse_query_selector("div p").unwrap();
+    let compiled = CompiledSelector::new(&selector, parser);
+    let expected = dom.query_selector("div p").unwrap().collect::<Vec<_>>();
+    let by_selector: Vec<_> = dom.query_selector_with(selector).collect();
+    let by_compiled: Vec<_> = QuerySelectorIterator::with_matcher(compiled, parser, &dom).collect();
+    assert_eq!(by_selector, expected);
+    assert_eq!(by_compiled, expected);
+}
+
+#[test]
+fn query_document_order() {
+    let input = r#"<div id="a" class="x"><p class="y x" id="a">1</p><section><p class="x">2</p></section></div><p class="x" data-k="v">3</p><p data-k="v">4</p>"#;
+    let tracked = ParserOptions::default()
+        .track_ids()
+        .track_classes()
+        .track_tags()
+        .track_attribute("data-k");
+
+    for options in [ParserOptions::default(), tracked] {
+        let mut dom = parse(input, options).unwrap();
+        let fragment = parse(r#"<p class="x">0</p>"#, ParserOptions::default()).unwrap();
+        let fragment = fragment.nodes()[0]
+            .as_tag()
+            .unwrap()
+            .clone_subtree(fragment.parser());
+        // inserted before all other nodes, but its handle comes after them
+        let inserted = dom.insert_fragment(None, 0, &fragment).unwrap();
+
+        let handles = |iter: &mut dyn Iterator<Item = NodeHandle>| {
+            iter.map(|handle| handle.get_inner()).collect::<Vec<_>>()
+        };
+        let is_sorted = |handles: &[u32]| handles.windows(2).all(|w| w[0] < w[1]);
+
+        let lookups = [
+            handles(&mut dom.query_selector(".x").unwrap()),
+            handles(&mut dom.query_selector("div .x, p").unwrap()),
+            handles(&mut dom.get_elements_by_class_name("x")),
+            handles(&mut dom.get_elements_by_tag_name("p")),
+            handles(&mut dom.get_elements_by_id("a")),
+            handles(&mut dom.get_elements_by_attribute("data-k", "v")),
+        ];
+        for handles in &lookups {
+            assert!(is_sorted(handles), "{handles:?}");
+        }
+        assert_eq!(lookups[0].last(), Some(&inserted.get_inner()));
+        assert_eq!(lookups[0].len(), 5);
+
+        assert_eq!(
+            dom.query_selector_first(".x").map(|h| h.get_inner()),
+            Some(lookups[0][0])
+        );
+        assert_eq!(
+            dom.query_selector_first("p").map(|h| h.get_inner()),
+            Some(lookups[3][0])
+        );
+        assert_eq!(dom.query_selector_first("table"), None);
+        assert_eq!(dom.query_selector_first("p >"), None);
+
+        let div = dom.get_element_by_id("a").unwrap();
+        let div = div.get(dom.parser()).unwrap().as_tag().unwrap();
+        let first = div.query_selector_first(dom.parser(), "p").unwrap();
+        assert_eq!(
+            first.get(dom.parser()).unwrap().inner_text(dom.parser()),
+            "1"
+        );
+        assert_eq!(div.query_selector_first(dom.parser(), "[data-k]"), None);
+    }
+}
+
+#[test]
+fn memory_usage() {
+    let input = r#"<div id="a" class="x y" data-a="1" data-b="2" data-c="3"><p>1</p><p>2</p><p>3</p></div>"#;
+
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let usage = dom.memory_usage();
+    assert!(usage.nodes >= std::mem::size_of_val(dom.nodes()));
+    // the attributes and children of the <div> do not fit into the element
+    assert!(usage.attributes > 0);
+    assert!(usage.children > 0);
+    assert_eq!(usage.owned_text, 0);
+    assert_eq!(
+        (
+            usage.ids,
+            usage.classes,
+            usage.tag_names,
+            usage.attribute_values
+        ),
+        (0, 0, 0, 0)
+    );
+    // known tag names are interned once for all documents, and there are no templates
+    assert_eq!(usage.other, 0);
+    assert_eq!(
+        usage.total(),
+        usage.nodes + usage.children + usage.attributes
+    );
+
+    let options = ParserOptions::default()
+        .track_ids()
+        .track_classes()
+        .track_tags()
+        .track_attribute("data-a");
+    let mut dom = parse(input, options).unwrap();
+    let tracked = dom.memory_usage();
+    assert!(tracked.ids > 0 && tracked.classes > 0 && tracked.tag_names > 0);
+    assert!(tracked.attribute_values > 0);
+    assert_eq!(tracked.nodes, usage.nodes);
+
+    // values that are set after parsing are owned by the document
+    let value = Bytes::from("a longer value").to_static();
+    dom.nodes_mut()[0]
+        .as_tag_mut()
+        .unwrap()
+        .attributes_mut()
+        .insert("data-a", Some(value));
+    assert_eq!(dom.memory_usage().owned_text, "a longer value".len());
+}
+
+#[test]
+fn stable_ids() {
+    let input = r#"<div><p id="a">a</p><!-- c --><p id="b">b</p></div>"#;
+
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let a = dom.get_element_by_id("a").unwrap();
+    let b = dom.get_element_by_id("b").unwrap();
+    assert_eq!(a.stable_id(), StableId::new(a.document_order()));
+    assert_eq!(b.stable_id().to_string().parse(), Ok(b.stable_id()));
+    assert!("x".parse::<StableId>().is_err());
+
+    let stored = dom.serialize_handles(&[b, a]).unwrap();
+    let empty = dom.serialize_handles(&[]).unwrap();
+
+    // handles of other documents cannot be serialized
+    let other = parse(input, ParserOptions::default()).unwrap();
+    let foreign = other.get_element_by_id("a").unwrap();
+    assert_eq!(dom.serialize_handles(&[foreign]), None);
+
+    let reparsed = parse(input, ParserOptions::default()).unwrap();
+    let handles = reparsed.deserialize_handles(&stored).unwrap();
+    let ids = handles
+        .iter()
+        .map(|handle| {
+            let tag = handle.get(reparsed.parser()).unwrap().as_tag().unwrap();
+            tag.attributes().id().unwrap().as_utf8_str().into_owned()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["b", "a"]);
+    assert!(reparsed.parser().contains(handles[0]));
+    assert_eq!(reparsed.deserialize_handles(&empty), Some(Vec::new()));
+
+    assert_eq!(reparsed.resolve_stable_id(a.stable_id()), Some(handles[1]));
+    let count = reparsed.nodes().len() as u32;
+    assert_eq!(reparsed.resolve_stable_id(StableId::new(count)), None);
+
+    // malformed strings and other sources
+    assert_eq!(reparsed.deserialize_handles("0,1"), None);
+    assert_eq!(reparsed.deserialize_handles(&format!("{stored},x")), None);
+    assert_eq!(reparsed.deserialize_handles(&format!("{stored},99")), None);
+    let changed = parse(r#"<div><p id="a">a</p></div>"#, ParserOptions::default()).unwrap();
+    assert_eq!(changed.deserialize_handles(&stored), None);
+}
+
+#[test]
+fn strict_mode() {
+    use crate::errors::MarkupError;
+    use crate::ParseError;
+
+    let options = ParserOptions::default().strict();
+    assert!(options.is_strict());
+    assert!(!ParserOptions::default().is_strict());
+
+    let valid = [
+        "<!DOCTYPE html><html><head><title>x</title></head><body><p>a</p><br><img src=\"a.png\"/></body></html>",
+        "<div\tclass=\"a\"\r\nid='b' hidden>x</div><input value=1>",
+        "<svg><path d=\"M0\"/></svg><!-- c --><![CDATA[x]]>",
+        "<ul><li>a</li><li>b</li></ul><p>c",
+    ];
+    for input in valid {
+        assert!(parse(input, options).is_ok(), "{input}");
+    }
+
+    let invalid = [
+        ("<div><p>a</p>", MarkupError::UnclosedElement, "<div>"),
+        ("<div><span></div>", MarkupError::StrayEndTag, "</div>"),
+        ("<a href=>x</a>", MarkupError::InvalidAttribute, "href="),
+        (
+            "<a href=\"x\"title=\"y\">x</a>",
+            MarkupError::InvalidAttribute,
+            "href=\"x\"",
+        ),
+        ("<a \"x\">y</a>", MarkupError::InvalidAttribute, "\""),
+        (
+            "<a href=\"x>y</a>",
+            MarkupError::InvalidAttribute,
+            "href=\"x>y</a>",
+        ),
+        ("<p>a</p><div", MarkupError::UnterminatedTag, "<div"),
+        ("<p>a</p", MarkupError::UnterminatedTag, "</p"),
+        ("<!-- x", MarkupError::UnterminatedTag, "<!-- x"),
+        ("<![CDATA[ x", MarkupError::UnterminatedTag, "<![CDATA[ x"),
+    ];
+    for (input, expected, markup) in invalid {
+        match parse(input, options) {
+            Err(ParseError::InvalidMarkup { error, span }) => {
+                assert_eq!(error, expected, "{input}");
+                assert_eq!(&input[span.range()], markup, "{input}");
+            }
+            other => panic!("{input}: {other:?}"),
+        }
+
+        // the same input is recovered from without strict mode
+        assert!(parse(input, ParserOptions::default()).is_ok(), "{input}");
+    }
+
+    // the first problem is reported
+    let error = parse("</b><div>", options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "End tag that does not close an open element at offset 0"
+    );
+
+    // XML has no optional end tags
+    assert!(parse("<li>a", options).is_ok());
+    assert!(parse("<li>a", options.xml_mode()).is_err());
+}
+
+#[test]
+fn find_all_text_nodes() {
+    let input = "<div id=a>one two one<span id=b>done</span></div><!-- one -->one";
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let a = dom.get_element_by_id("a");
+    let b = dom.get_element_by_id("b");
+
+    let matches = dom.find_all_text_nodes("one");
+    let found = matches
+        .iter()
+        .map(|m| (m.element(), m.range()))
+        .collect::<Vec<_>>();
+    // comments are not searched
+    assert_eq!(found, [(a, 0..3), (a, 8..11), (b, 1..4), (None, 0..3)]);
+
+    let text = matches[1].node().get(parser).unwrap().inner_text(parser);
+    assert_eq!(&text[matches[1].range()], "one");
+    assert_eq!(matches[0].node(), matches[1].node());
+    assert_eq!(parser.parent_of(matches[2].node()), b);
+
+    assert_eq!(dom.find_all_text_nodes(&String::from("two")).len(), 1);
+    assert!(dom.find_all_text_nodes("").is_empty());
+    assert!(dom.find_all_text_nodes("three").is_empty());
+
+    #[cfg(feature = "regex")]
+    {
+        let regex = regex::Regex::new(r"\bone\b|x*").unwrap();
+        let ranges = dom
+            .find_all_text_nodes(&regex)
+            .iter()
+            .map(|m| m.range())
+            .collect::<Vec<_>>();
+        // empty matches are skipped and `done` is not a whole word
+        assert_eq!(ranges, [0..3, 8..11, 0..3]);
+    }
+}
+
+#[test]
+fn attribute_storage() {
+    for count in [0, 1, 2, 3, 8, 16, 17, 18, 19, 40] {
+        let names = (0..count).map(|n| format!("data-{n}")).collect::<Vec<_>>();
+        let input = format!(
+            "<div id=x {}></div>",
+            names
+                .iter()
+                .enumerate()
+                .map(|(n, name)| format!("{name}=\"{n}\""))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let mut dom = parse(&input, ParserOptions::default()).unwrap();
+        let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+        assert_eq!(attributes.len(), count + 1);
+        assert_eq!(attributes.raw.is_heap_allocated(), count > 2);
+
+        for (n, name) in names.iter().enumerate() {
+            let value = attributes.get(name.as_str()).flatten().unwrap();
+            assert_eq!(value.as_utf8_str(), n.to_string());
+            assert!(attributes.contains(name.to_uppercase().as_str()));
+        }
+        assert!(!attributes.contains("data-missing"));
+
+        // attributes keep their order until there are too many for a linear search
+        if count <= crate::inline::hashmap::MAX_LINEAR_LEN {
+            let keys = attributes
+                .iter()
+                .filter(|(key, _)| *key != "id")
+                .map(|(key, _)| key.into_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(keys, names);
+        }
+
+        let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+        attributes.insert("DATA-0", Some("new"));
+        attributes.insert("title", Some("t"));
+        if let Some(Some(value)) = attributes.get_mut("title") {
+            value.set("u").unwrap();
+        }
+        assert_eq!(attributes.remove("data-1").is_some(), count > 1);
+        // `DATA-0` is only a new attribute if there was no `data-0`
+        let added = 1 + usize::from(count == 0);
+        assert_eq!(attributes.len(), count + 1 + added - usize::from(count > 1));
+
+        let get = |name: &str| {
+            attributes
+                .get(name)
+                .flatten()
+                .map(|v| v.as_utf8_str().into_owned())
+        };
+        assert_eq!(get("title").as_deref(), Some("u"));
+        assert_eq!(get("data-1"), None);
+        if count > 0 {
+            assert_eq!(get("data-0").as_deref(), Some("new"));
+        }
+        if count > 2 {
+            let last = format!("data-{}", count - 1);
+            assert_eq!(get(&last), Some((count - 1).to_string()));
+        }
+
+        let tag = dom.nodes()[0].as_tag().unwrap();
+        let copy = tag.clone();
+        assert!(copy.attributes().iter().eq(tag.attributes().iter()));
+    }
+}
+
+#[test]
+fn get_elements_by_name() {
+    let input = r#"<form><input name="q"><select name="sort"><option>a</option></select><textarea name="q"></textarea><input NAME="q"></form><a name="q" id="x">x</a>"#;
+
+    let tracked = parse(input, ParserOptions::default().track_names()).unwrap();
+    let plain = parse(input, ParserOptions::default()).unwrap();
+
+    for dom in [&tracked, &plain] {
+        let names = dom
+            .get_elements_by_name("q")
+            .map(|handle| {
+                let tag = handle.get(dom.parser()).unwrap().as_tag().unwrap();
+                tag.name().as_utf8_str().into_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["input", "textarea", "input", "a"]);
+        assert_eq!(dom.get_elements_by_name("sort").count(), 1);
+        assert_eq!(dom.get_elements_by_name("Q").count(), 0);
+        assert_eq!(dom.get_elements_by_name("missing").count(), 0);
+    }
+
+    assert!(tracked.parser().options.is_tracking_names());
+    assert!(!plain.parser().options.is_tracking_names());
+    // the lookup table is shared with other tracked attributes
+    let options = ParserOptions::default()
+        .track_names()
+        .track_attribute("name");
+    assert_eq!(options.tracked_attributes().count(), 1);
+}
+
+#[test]
+fn query_selector_after_mutation() {
+    let options = ParserOptions::default()
+        .track_ids()
+        .track_classes()
+        .track_tags();
+    let mut dom = parse(r#"<p id="a">x</p><span>y</span>"#, options).unwrap();
+    assert_eq!(dom.query_selector(".new").unwrap().count(), 0);
+
+    let tag = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    tag.attributes_mut().insert("class", Some("new"));
+    tag.name_mut().set("div").unwrap();
+
+    // the lookup tables are outdated, so all nodes are searched
+    assert_eq!(dom.query_selector(".new").unwrap().count(), 1);
+    assert_eq!(dom.query_selector("div.new").unwrap().count(), 1);
+    assert_eq!(dom.query_selector("p").unwrap().count(), 0);
+}
+
+#[test]
+fn query_selector_after_strip_attributes() {
+    let options = ParserOptions::default().track_ids().track_classes();
+    let mut dom = parse(r#"<p id="a" class="b">x</p>"#, options).unwrap();
+    assert_eq!(dom.query_selector("#a").unwrap().count(), 1);
+
+    let policy = crate::strip::AttributePolicy::new()
+        .deny("id")
+        .deny("class");
+    assert_eq!(crate::strip_attributes(&mut dom, &policy), 2);
+    assert_eq!(dom.query_selector("#a").unwrap().count(), 0);
+    assert_eq!(dom.query_selector("p.b").unwrap().count(), 0);
+    assert_eq!(dom.query_selector("p").unwrap().count(), 1);
+}
@@ -0,0 +1,315 @@
+use crate::{Edge, Node, NodeHandle, Parser, Traverse};
+
+/// Elements whose text content is not rendered
+pub(crate) const INVISIBLE_ELEMENTS: &[&str] = &["script", "style", "template"];
+
+/// Heading elements, ordered by their level
+pub(crate) const HEADING_ELEMENTS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Elements that are followed and preceded by a line break
+pub(crate) const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "caption",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Elements whose whitespace is significant
+pub(crate) const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "code", "textarea"];
+
+/// Elements that drop a newline directly after their start tag
+const LEADING_NEWLINE_ELEMENTS: &[&str] = &["pre", "textarea", "listing"];
+
+/// Options for [`HTMLTag::inner_text_with`](crate::HTMLTag::inner_text_with)
+///
+/// The default options produce the same text as [`HTMLTag::inner_text`](crate::HTMLTag::inner_text), i.e. the plain concatenation of all text nodes.
+/// [`TextOptions::readable`] enables all options, which approximates the text that a browser renders.
+///
+/// # Example
+/// ```
+/// use tl::text::TextOptions;
+///
+/// let dom = tl::parse(
+///     "<div><p>Hello,\n   <b>world</b>!</p><ul><li>One</li><li>Two</li></ul><script>x()</script></div>",
+///     Default::default(),
+/// )
+/// .unwrap();
+/// let div = dom.nodes()[0].as_tag().unwrap();
+///
+/// assert_eq!(
+///     div.inner_text_with(dom.parser(), TextOptions::readable()),
+///     "Hello, world!\n\nOne\nTwo"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextOptions {
+    collapse_whitespace: bool,
+    line_breaks: bool,
+    skip_invisible: bool,
+    preserve_preformatted: bool,
+    preformatted_elements: &'static [&'static str],
+}
+
+impl TextOptions {
+    /// Creates new [`TextOptions`] with all options disabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates new [`TextOptions`] with all options enabled
+    pub fn readable() -> Self {
+        Self::new()
+            .collapse_whitespace()
+            .line_breaks()
+            .skip_invisible()
+            .preserve_preformatted()
+    }
+
+    /// Replaces runs of whitespace with a single space, and removes leading and trailing whitespace
+    ///
+    /// With [`TextOptions::preserve_preformatted`], the text of preformatted elements is kept as it is.
+    pub fn collapse_whitespace(mut self) -> Self {
+        self.collapse_whitespace = true;
+        self
+    }
+
+    /// Inserts line breaks for `<br>` and around block-level elements like `<div>` or `<li>`, and blank lines around paragraphs and headings
+    pub fn line_breaks(mut self) -> Self {
+        self.line_breaks = true;
+        self
+    }
+
+    /// Skips the content of elements that are not rendered, such as `<script>`, `<style>` and `<template>`
+    pub fn skip_invisible(mut self) -> Self {
+        self.skip_invisible = true;
+        self
+    }
+
+    /// Keeps all whitespace inside of `<pre>`, `<code>` and `<textarea>` elements when collapsing whitespace,
+    /// so that code samples and other preformatted text are not changed
+    ///
+    /// As in browsers, a newline directly after the start tag of `<pre>` and `<textarea>` is not part of the text.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::text::TextOptions;
+    ///
+    /// let dom = tl::parse(
+    ///     "<div>Run   <code>cargo  run</code>:<pre>\nfn main() {\n    run();\n}</pre></div>",
+    ///     Default::default(),
+    /// )
+    /// .unwrap();
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert_eq!(
+    ///     div.inner_text_with(dom.parser(), TextOptions::readable()),
+    ///     "Run cargo  run:\nfn main() {\n    run();\n}"
+    /// );
+    /// ```
+    pub fn preserve_preformatted(mut self) -> Self {
+        self.preserve_preformatted = true;
+        self
+    }
+
+    /// Treats the given elements as preformatted in addition to `<pre>`, `<code>` and `<textarea>`,
+    /// and enables [`TextOptions::preserve_preformatted`]
+    ///
+    /// Calling this again replaces the previously added elements.
+    pub fn preformatted_elements(mut self, names: &'static [&'static str]) -> Self {
+        self.preserve_preformatted = true;
+        self.preformatted_elements = names;
+        self
+    }
+
+    /// Checks whether the whitespace of an element with the given name is kept
+    fn is_preformatted(&self, name: &[u8]) -> bool {
+        self.preserve_preformatted
+            && (is_one_of(name, PREFORMATTED_ELEMENTS)
+                || is_one_of(name, self.preformatted_elements))
+    }
+}
+
+/// Checks whether the given tag name is in the list, ignoring case
+pub(crate) fn is_one_of(name: &[u8], list: &[&str]) -> bool {
+    list.iter().any(|n| n.as_bytes().eq_ignore_ascii_case(name))
+}
+
+/// Builds the text, keeping track of whitespace and line breaks that are only written once more text follows
+struct TextWriter {
+    out: String,
+    options: TextOptions,
+    pending_space: bool,
+    pending_breaks: usize,
+    /// The number of open preformatted elements
+    preformatted: usize,
+    /// Whether the next text directly follows the start tag of an element that drops a leading newline
+    strip_newline: bool,
+}
+
+impl TextWriter {
+    fn request_breaks(&mut self, count: usize) {
+        self.pending_breaks = self.pending_breaks.max(count);
+    }
+
+    fn flush(&mut self) {
+        if self.out.is_empty() {
+            // no whitespace at the very start
+        } else if self.pending_breaks > 0 {
+            let trailing = self.out.len() - self.out.trim_end_matches('\n').len();
+            for _ in trailing..self.pending_breaks {
+                self.out.push('\n');
+            }
+        } else if self.pending_space && !self.out.ends_with('\n') {
+            self.out.push(' ');
+        }
+
+        self.pending_breaks = 0;
+        self.pending_space = false;
+    }
+
+    fn line_break(&mut self) {
+        self.flush();
+        self.out.push('\n');
+    }
+
+    fn text(&mut self, text: &str) {
+        let strip_newline = std::mem::take(&mut self.strip_newline);
+
+        if self.preformatted > 0 {
+            let text = match strip_newline {
+                true => text
+                    .strip_prefix("\r\n")
+                    .or_else(|| text.strip_prefix('\n'))
+                    .unwrap_or(text),
+                false => text,
+            };
+
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+            }
+            return;
+        }
+
+        if !self.options.collapse_whitespace {
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+            }
+            return;
+        }
+
+        for (idx, word) in text.split_ascii_whitespace().enumerate() {
+            if idx == 0 && !text.starts_with(|c: char| c.is_ascii_whitespace()) {
+                // the word continues the previous text, i.e. `<b>a</b>b`
+                if self.pending_breaks > 0 || self.pending_space {
+                    self.flush();
+                }
+            } else {
+                self.pending_space = true;
+                self.flush();
+            }
+
+            self.out.push_str(word);
+        }
+
+        if text.ends_with(|c: char| c.is_ascii_whitespace()) {
+            self.pending_space = true;
+        }
+    }
+}
+
+/// Computes the text of the given nodes according to the options
+pub(crate) fn inner_text_with(
+    roots: &[NodeHandle],
+    parser: &Parser<'_>,
+    options: TextOptions,
+) -> String {
+    let mut writer = TextWriter {
+        out: String::new(),
+        options,
+        pending_space: false,
+        pending_breaks: 0,
+        preformatted: 0,
+        strip_newline: false,
+    };
+
+    let mut traverse = Traverse::new(parser, roots);
+    while let Some(edge) = traverse.next() {
+        let Some(node) = edge.handle().get(parser) else {
+            continue;
+        };
+
+        match (edge, node) {
+            (Edge::Open(_), Node::Tag(tag)) => {
+                let name = tag.name().as_bytes();
+
+                if options.is_preformatted(name) {
+                    writer.preformatted += 1;
+                }
+                writer.strip_newline = is_one_of(name, LEADING_NEWLINE_ELEMENTS);
+
+                if options.skip_invisible && is_one_of(name, INVISIBLE_ELEMENTS) {
+                    traverse.skip_children();
+                } else if options.line_breaks && name.eq_ignore_ascii_case(b"br") {
+                    writer.line_break();
+                } else if options.line_breaks {
+                    writer.request_breaks(breaks_around(name));
+                }
+            }
+            (Edge::Close(_), Node::Tag(tag)) => {
+                let name = tag.name().as_bytes();
+
+                if options.is_preformatted(name) {
+                    writer.preformatted = writer.preformatted.saturating_sub(1);
+                }
+
+                if options.line_breaks {
+                    writer.request_breaks(breaks_around(name));
+                }
+            }
+            (Edge::Open(_), Node::Raw(text)) => writer.text(&text.as_utf8_str()),
+            (Edge::Open(_), Node::CData(_)) => writer.text(&node.inner_text(parser)),
+            _ => {}
+        }
+    }
+
+    writer.out
+}
+
+/// Returns the number of line breaks before and after an element with the given name
+///
+/// Paragraphs and headings are followed and preceded by two line breaks.
+fn breaks_around(name: &[u8]) -> usize {
+    if name.eq_ignore_ascii_case(b"p") || is_one_of(name, HEADING_ELEMENTS) {
+        2
+    } else if is_one_of(name, BLOCK_ELEMENTS) {
+        1
+    } else {
+        0
+    }
+}
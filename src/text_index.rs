@@ -0,0 +1,181 @@
+use std::ops::Range;
+
+use crate::text::{is_one_of, INVISIBLE_ELEMENTS};
+use crate::{Edge, Node, NodeHandle, VDom};
+
+/// A position in the DOM, relative to a text node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextPosition {
+    /// The text node
+    pub node: NodeHandle,
+    /// The byte offset into the text of the node, as returned by [`Node::inner_text`]
+    pub offset: usize,
+}
+
+/// A range of the DOM, starting and ending in a text node
+///
+/// The start position is inclusive and the end position is exclusive.
+/// Both positions may be in different text nodes, if the range spans more than one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextRange {
+    /// The position of the first character in the range
+    pub start: TextPosition,
+    /// The position right after the last character in the range
+    pub end: TextPosition,
+}
+
+/// A text node that contributes to the visible text
+#[derive(Debug, Clone)]
+struct Segment {
+    node: NodeHandle,
+    /// Offset of the first character of this segment in the visible text, in characters
+    char_start: usize,
+    /// Offset of the first character of this segment in the visible text, in bytes
+    byte_start: usize,
+    /// Number of characters in this segment
+    char_len: usize,
+}
+
+/// An index over the visible text of a document that maps character offsets in the text back to positions in the DOM
+///
+/// The visible text is the concatenation of all text nodes, except for those in `<script>`, `<style>` and `<template>` elements.
+/// This is useful for projecting annotations that were computed on the plain text of a document, such as named entities, back onto the HTML.
+///
+/// All offsets that this index accepts are *character* offsets (Unicode scalar values) into [`TextIndex::text`], not byte offsets.
+///
+/// # Example
+/// ```
+/// use tl::text_index::TextIndex;
+///
+/// let dom = tl::parse("<p>Hello <b>wörld</b>!</p>", Default::default()).unwrap();
+/// let index = TextIndex::new(&dom);
+/// assert_eq!(index.text(), "Hello wörld!");
+///
+/// // "wörld"
+/// let range = index.range(6..11).unwrap();
+/// let node = range.start.node.get(dom.parser()).unwrap();
+/// assert_eq!(node.inner_text(dom.parser()), "wörld");
+/// assert_eq!((range.start.offset, range.end.offset), (0, 6));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextIndex {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl TextIndex {
+    /// Builds an index over the visible text of the given DOM
+    pub fn new(dom: &VDom<'_>) -> Self {
+        let parser = dom.parser();
+        let mut text = String::new();
+        let mut segments = Vec::new();
+        let mut char_len = 0;
+
+        let mut traverse = dom.traverse();
+        while let Some(edge) = traverse.next() {
+            let Edge::Open(handle) = edge else {
+                continue;
+            };
+
+            match handle.get(parser) {
+                Some(Node::Tag(tag)) if is_one_of(tag.name().as_bytes(), INVISIBLE_ELEMENTS) => {
+                    traverse.skip_children();
+                }
+                Some(Node::Raw(raw)) => {
+                    let raw = raw.as_utf8_str();
+                    let len = raw.chars().count();
+
+                    if len > 0 {
+                        segments.push(Segment {
+                            node: handle,
+                            char_start: char_len,
+                            byte_start: text.len(),
+                            char_len: len,
+                        });
+                        text.push_str(&raw);
+                        char_len += len;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { text, segments }
+    }
+
+    /// Returns the visible text of the document
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the number of characters in the visible text
+    #[inline]
+    pub fn char_len(&self) -> usize {
+        self.segments
+            .last()
+            .map_or(0, |s| s.char_start + s.char_len)
+    }
+
+    /// Returns the DOM position of the character at the given offset
+    ///
+    /// Returns `None` if the offset is out of bounds.
+    pub fn position(&self, offset: usize) -> Option<TextPosition> {
+        let idx = self
+            .segments
+            .partition_point(|s| s.char_start + s.char_len <= offset);
+
+        let segment = self.segments.get(idx)?;
+        Some(self.resolve(segment, offset - segment.char_start))
+    }
+
+    /// Returns the DOM range of the given character range
+    ///
+    /// If the range ends at the boundary of two text nodes, the end position is at the end of the former one.
+    /// Returns `None` if the range is out of bounds or its start is greater than its end.
+    pub fn range(&self, range: Range<usize>) -> Option<TextRange> {
+        if range.start > range.end || range.end > self.char_len() {
+            return None;
+        }
+
+        let start = self.position(range.start)?;
+
+        let end = if range.is_empty() {
+            start
+        } else {
+            let idx = self
+                .segments
+                .partition_point(|s| s.char_start + s.char_len < range.end);
+
+            let segment = self.segments.get(idx)?;
+            self.resolve(segment, range.end - segment.char_start)
+        };
+
+        Some(TextRange { start, end })
+    }
+
+    /// Returns the character range that the given text node occupies in the visible text
+    ///
+    /// Returns `None` if the node is not part of the visible text, i.e. because it is not a text node, it is empty or it is in a `<script>` tag.
+    pub fn node_range(&self, node: NodeHandle) -> Option<Range<usize>> {
+        self.segments
+            .iter()
+            .find(|s| s.node.get_inner() == node.get_inner())
+            .map(|s| s.char_start..s.char_start + s.char_len)
+    }
+
+    /// Converts a character offset relative to the start of a segment to a position
+    fn resolve(&self, segment: &Segment, chars: usize) -> TextPosition {
+        let text = &self.text[segment.byte_start..];
+
+        let offset = text
+            .char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(offset, _)| offset);
+
+        TextPosition {
+            node: segment.node,
+            offset,
+        }
+    }
+}
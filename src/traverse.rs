@@ -0,0 +1,109 @@
+use crate::{NodeHandle, Parser};
+
+/// An event emitted by [`Traverse`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// The traversal entered a node. All of its subnodes will follow before the matching [`Edge::Close`].
+    Open(NodeHandle),
+    /// The traversal left a node, after all of its subnodes were visited.
+    Close(NodeHandle),
+}
+
+impl Edge {
+    /// Returns the handle to the node this edge belongs to
+    #[inline]
+    pub fn handle(&self) -> NodeHandle {
+        match self {
+            Edge::Open(h) | Edge::Close(h) => *h,
+        }
+    }
+}
+
+/// A list of sibling nodes that is currently being visited
+#[derive(Debug, Clone)]
+struct Frame<'b> {
+    /// The node that the siblings belong to, or `None` for the topmost nodes
+    parent: Option<NodeHandle>,
+    siblings: &'b [NodeHandle],
+    /// Index of the next node in `siblings` to open
+    next: usize,
+}
+
+/// A depth-first iterator over a DOM that emits an [`Edge::Open`] when entering a node and an [`Edge::Close`] when leaving it.
+///
+/// Every node, including text and comment nodes, produces exactly one `Open` and one `Close` edge.
+/// This is obtained by calling [`VDom::traverse`](crate::VDom::traverse).
+#[derive(Debug, Clone)]
+pub struct Traverse<'a, 'b> {
+    parser: &'b Parser<'a>,
+    stack: Vec<Frame<'b>>,
+    /// The node whose `Open` edge was emitted last. Its children are visited next.
+    descend: Option<NodeHandle>,
+    /// The node whose `Open` edge was emitted last, but whose children were skipped
+    skip: Option<NodeHandle>,
+}
+
+impl<'a, 'b> Traverse<'a, 'b> {
+    /// Creates a new traversal over the given nodes and all of their subnodes
+    pub(crate) fn new(parser: &'b Parser<'a>, roots: &'b [NodeHandle]) -> Self {
+        Self {
+            parser,
+            stack: vec![Frame {
+                parent: None,
+                siblings: roots,
+                next: 0,
+            }],
+            descend: None,
+            skip: None,
+        }
+    }
+
+    /// Skips all subnodes of the node whose [`Edge::Open`] was emitted last.
+    /// The next edge will be its [`Edge::Close`].
+    ///
+    /// This has no effect if the last edge was an [`Edge::Close`].
+    #[inline]
+    pub fn skip_children(&mut self) {
+        if let Some(handle) = self.descend.take() {
+            self.skip = Some(handle);
+        }
+    }
+}
+
+impl<'a, 'b> Iterator for Traverse<'a, 'b> {
+    type Item = Edge;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(handle) = self.skip.take() {
+            return Some(Edge::Close(handle));
+        }
+
+        if let Some(handle) = self.descend.take() {
+            let children = handle
+                .get(self.parser)
+                .and_then(|node| node.as_tag())
+                .map_or(&[] as &[NodeHandle], |tag| tag._children.as_slice());
+
+            if children.is_empty() {
+                return Some(Edge::Close(handle));
+            }
+
+            self.stack.push(Frame {
+                parent: Some(handle),
+                siblings: children,
+                next: 0,
+            });
+        }
+
+        let frame = self.stack.last_mut()?;
+
+        if let Some(&handle) = frame.siblings.get(frame.next) {
+            frame.next += 1;
+            self.descend = Some(handle);
+            Some(Edge::Open(handle))
+        } else {
+            let frame = self.stack.pop()?;
+            frame.parent.map(Edge::Close)
+        }
+    }
+}
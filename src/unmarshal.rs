@@ -0,0 +1,242 @@
+//! Extraction of structured records from documents, where every field is described by an [`eval`](crate::eval) rule
+//!
+//! An [`Extractor`](crate::unmarshal::Extractor) lists named fields and the rule that extracts each of them.
+//! It can extract one [`Record`](crate::unmarshal::Record) from a whole document, or one record per element that
+//! matches a selector, like every product card of a listing.
+//! Types that implement [`FromHtml`](crate::unmarshal::FromHtml) describe their extractor and how to build themselves
+//! from a record, so that [`unmarshal`](crate::unmarshal::unmarshal) and
+//! [`unmarshal_each`](crate::unmarshal::unmarshal_each) can return them directly.
+//!
+//! # Example
+//! ```
+//! use tl::errors::UnmarshalError;
+//! use tl::unmarshal::{Extractor, FromHtml, Record};
+//!
+//! struct Product {
+//!     name: String,
+//!     price: f64,
+//!     tags: Vec<String>,
+//! }
+//!
+//! impl FromHtml for Product {
+//!     fn extractor() -> Extractor<'static> {
+//!         Extractor::new()
+//!             .field("name", "h2 | collapse")
+//!             .field("price", ".price @data-amount")
+//!             .list("tags", ".tag | lower")
+//!     }
+//!
+//!     fn from_record(record: Record) -> Result<Self, UnmarshalError> {
+//!         Ok(Self {
+//!             name: record.required("name")?.to_owned(),
+//!             price: record.parse("price")?.unwrap_or_default(),
+//!             tags: record.get_all("tags").to_vec(),
+//!         })
+//!     }
+//! }
+//!
+//! let dom = tl::parse(r#"
+//!     <div class="product"><h2> Lamp </h2><span class="price" data-amount="19.99"></span><i class="tag">Home</i></div>
+//!     <div class="product"><h2>Chair</h2><i class="tag">Home</i><i class="tag">Wood</i></div>
+//! "#, Default::default()).unwrap();
+//!
+//! let products = tl::unmarshal::unmarshal_each::<Product>(&dom, ".product").unwrap();
+//!
+//! assert_eq!(products[0].name, "Lamp");
+//! assert_eq!(products[0].price, 19.99);
+//! assert_eq!(products[1].price, 0.0);
+//! assert_eq!(products[1].tags, ["home", "wood"]);
+//! ```
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::errors::UnmarshalError;
+use crate::eval::Rule;
+use crate::VDom;
+
+/// Whether a field keeps the first or all extracted values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Cardinality {
+    First,
+    All,
+}
+
+/// A list of named fields and the rules that extract them
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Extractor<'s> {
+    fields: Vec<(&'s str, &'s str, Cardinality)>,
+}
+
+impl<'s> Extractor<'s> {
+    /// Creates a new [`Extractor`] without any fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field that holds the first value that the given [`eval`](crate::eval) rule extracts
+    pub fn field(mut self, name: &'s str, rule: &'s str) -> Self {
+        self.fields.push((name, rule, Cardinality::First));
+        self
+    }
+
+    /// Adds a field that holds every value that the given [`eval`](crate::eval) rule extracts, in document order
+    pub fn list(mut self, name: &'s str, rule: &'s str) -> Self {
+        self.fields.push((name, rule, Cardinality::All));
+        self
+    }
+
+    /// Parses the rules of all fields
+    fn rules(&self) -> Result<Vec<(&'s str, Rule<'s>, Cardinality)>, UnmarshalError> {
+        self.fields
+            .iter()
+            .map(|&(name, rule, cardinality)| {
+                let rule = Rule::parse(rule).map_err(|error| UnmarshalError::InvalidRule {
+                    field: name.to_owned(),
+                    error,
+                })?;
+
+                Ok((name, rule, cardinality))
+            })
+            .collect()
+    }
+
+    /// Extracts a single record from the whole document
+    ///
+    /// # Errors
+    /// Returns an error if the rule of any field is invalid.
+    pub fn extract(&self, dom: &VDom<'_>) -> Result<Record, UnmarshalError> {
+        let rules = self.rules()?;
+
+        Ok(Record::new(rules.iter().map(
+            |(name, rule, cardinality)| (*name, rule.eval(dom), *cardinality),
+        )))
+    }
+
+    /// Extracts one record for every element that matches the given query selector, in document order.
+    /// The rules of the fields only match elements inside of the respective element.
+    ///
+    /// # Errors
+    /// Returns an error if the selector or the rule of any field is invalid.
+    pub fn extract_each(
+        &self,
+        dom: &VDom<'_>,
+        selector: &str,
+    ) -> Result<Vec<Record>, UnmarshalError> {
+        let rules = self.rules()?;
+        let parser = dom.parser();
+
+        let elements = dom
+            .query_selector(selector)
+            .ok_or(UnmarshalError::InvalidSelector)?;
+
+        Ok(elements
+            .filter_map(|handle| handle.get(parser)?.as_tag())
+            .map(|tag| {
+                Record::new(rules.iter().map(|(name, rule, cardinality)| {
+                    (*name, rule.eval_in(tag, parser), *cardinality)
+                }))
+            })
+            .collect())
+    }
+}
+
+/// The values extracted for the fields of an [`Extractor`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    values: HashMap<String, Vec<String>>,
+}
+
+impl Record {
+    fn new<'s>(fields: impl Iterator<Item = (&'s str, Vec<String>, Cardinality)>) -> Self {
+        let values = fields
+            .map(|(name, mut values, cardinality)| {
+                if cardinality == Cardinality::First {
+                    values.truncate(1);
+                }
+
+                (name.to_owned(), values)
+            })
+            .collect();
+
+        Self { values }
+    }
+
+    /// Returns the first value of a field, or `None` if it did not match anything
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).first().map(String::as_str)
+    }
+
+    /// Returns all values of a field
+    ///
+    /// This is empty if the field did not match anything, and holds at most one value for fields added with [`Extractor::field`].
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.values.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the first value of a field
+    ///
+    /// # Errors
+    /// Returns [`UnmarshalError::MissingField`] if the field did not match anything.
+    pub fn required(&self, name: &str) -> Result<&str, UnmarshalError> {
+        self.get(name)
+            .ok_or_else(|| UnmarshalError::MissingField(name.to_owned()))
+    }
+
+    /// Parses the first value of a field with [`FromStr`] after trimming it, or returns `None` if the field did not match anything
+    ///
+    /// # Errors
+    /// Returns [`UnmarshalError::InvalidValue`] if the value cannot be parsed.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<Option<T>, UnmarshalError> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| UnmarshalError::InvalidValue {
+                        field: name.to_owned(),
+                        value: value.to_owned(),
+                    })
+            })
+            .transpose()
+    }
+}
+
+/// A type that can be extracted from a document
+///
+/// See the [module documentation](self) for an example.
+pub trait FromHtml: Sized {
+    /// Returns the fields that make up the type
+    fn extractor() -> Extractor<'static>;
+
+    /// Builds the type from the values of its fields
+    ///
+    /// # Errors
+    /// Returns an error if a required field is missing or a value is invalid.
+    fn from_record(record: Record) -> Result<Self, UnmarshalError>;
+}
+
+/// Extracts a value from the whole document
+///
+/// # Errors
+/// Returns an error if the extractor of the type is invalid, or if [`FromHtml::from_record`] fails.
+pub fn unmarshal<T: FromHtml>(dom: &VDom<'_>) -> Result<T, UnmarshalError> {
+    T::extractor().extract(dom).and_then(T::from_record)
+}
+
+/// Extracts a value for every element that matches the given query selector, in document order
+///
+/// # Errors
+/// Returns an error if the selector or the extractor of the type is invalid, or if [`FromHtml::from_record`] fails for any element.
+pub fn unmarshal_each<T: FromHtml>(
+    dom: &VDom<'_>,
+    selector: &str,
+) -> Result<Vec<T>, UnmarshalError> {
+    T::extractor()
+        .extract_each(dom, selector)?
+        .into_iter()
+        .map(T::from_record)
+        .collect()
+}
@@ -1,19 +1,60 @@
-use crate::errors::ParseError;
-use crate::parser::HTMLVersion;
-use crate::parser::NodeHandle;
+use crate::debug::TreeDisplay;
+use crate::errors::{EvalError, ParseError, SelectorError, XPathError};
+use crate::fingerprint::{TextFingerprint, TextHasher};
+use crate::forms::Form;
+use crate::fragment::OwnedFragment;
+use crate::frames::Frame;
+use crate::implied::ImpliedElement;
+use crate::incremental::{Changes, Edit};
+use crate::links::{Anchor, Link, LinkRelations, LinkSummary};
+use crate::meta::Metadata;
+use crate::outline::Heading;
+use crate::parser::{Doctype, HTMLVersion};
+use crate::parser::{FnvHasher, NodeHandle, StableId};
 use crate::queryselector;
-use crate::queryselector::QuerySelectorIterator;
+use crate::queryselector::{Matcher, Namespaces, QuerySelectorIterator, SelectorLimits};
+use crate::resources::{Script, Stylesheet};
+#[cfg(feature = "regex")]
+use crate::search::RegexMatch;
+use crate::search::{TextMatch, TextPattern};
+use crate::stats::{MemoryUsage, PageStats, Statistics};
+use crate::xpath::{Item, XPath};
 use crate::Bytes;
+use crate::DomVisitor;
 use crate::InnerNodeHandle;
 use crate::ParserOptions;
+use crate::Traverse;
 use crate::{Node, Parser};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
 use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
 
 /// VDom represents a [Document Object Model](https://developer.mozilla.org/en/docs/Web/API/Document_Object_Model)
 ///
 /// It is the result of parsing an HTML document.
 /// Internally it is only a wrapper around the [`Parser`] struct, in which all of the HTML tags are stored.
 /// Many functions of the public API take a reference to a [`Parser`] as a parameter to resolve [`NodeHandle`]s to [`Node`]s.
+///
+/// # Thread safety
+/// `VDom`, [`Parser`] and [`Node`] are `Send` and `Sync`, and have no interior mutability.
+/// Every method that takes `&self` only reads from the DOM, so any number of threads can query the same DOM at the same time,
+/// e.g. by sharing a `&VDom` with scoped threads or by turning it into a [`SharedDom`] with [`VDom::into_shared`].
+/// Methods that take `&mut self` require exclusive access, which the borrow checker enforces.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse(r#"<p class="a">1</p><p class="b">2</p>"#, Default::default()).unwrap();
+///
+/// let (a, b) = std::thread::scope(|s| {
+///     let a = s.spawn(|| dom.query_selector("p.a").unwrap().count());
+///     let b = s.spawn(|| dom.query_selector("p.b").unwrap().count());
+///     (a.join().unwrap(), b.join().unwrap())
+/// });
+///
+/// assert_eq!((a, b), (1, 1));
+/// ```
 #[derive(Debug)]
 pub struct VDom<'a> {
     /// Internal parser
@@ -33,14 +74,113 @@ impl<'a> VDom<'a> {
         &self.parser
     }
 
+    /// Turns this DOM into a read-only [`SharedDom`], which can be cloned cheaply and sent to other threads
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Hello</p>", Default::default()).unwrap().into_shared();
+    /// let copy = dom.clone();
+    ///
+    /// let count = std::thread::scope(|s| s.spawn(move || copy.query_selector("p").unwrap().count()).join().unwrap());
+    /// assert_eq!(count, 1);
+    /// assert_eq!(dom.outer_html(), "<p>Hello</p>");
+    /// ```
+    pub fn into_shared(self) -> SharedDom<'a> {
+        SharedDom {
+            dom: Arc::new(self),
+        }
+    }
+
     /// Returns a mutable reference to the underlying parser
     #[inline]
     pub fn parser_mut(&mut self) -> &mut Parser<'a> {
         &mut self.parser
     }
 
+    /// Parses another document with the same options, reusing the allocations of this one
+    ///
+    /// The node table keeps its capacity, so parsing many documents of a similar size in a loop
+    /// allocates much less than calling [`parse`](crate::parse) for each of them.
+    ///
+    /// # Errors
+    /// See [`parse`](crate::parse)
+    ///
+    /// # Example
+    /// ```
+    /// let pages = ["<p>a</p>", "<p>b</p><p>c</p>"];
+    ///
+    /// let mut dom = tl::parse("", Default::default()).unwrap();
+    /// let mut count = 0;
+    ///
+    /// for page in pages {
+    ///     dom = dom.reparse(page).unwrap();
+    ///     count += dom.query_selector("p").unwrap().count();
+    /// }
+    ///
+    /// assert_eq!(count, 3);
+    /// ```
+    pub fn reparse(self, input: &str) -> Result<VDom<'_>, ParseError> {
+        let mut parser = self.parser.reset(input.as_bytes());
+        parser.parse()?;
+        Ok(VDom::from(parser))
+    }
+
+    /// Parses the source of this document again after an edit, reusing the nodes that the edit does not touch
+    ///
+    /// This is meant for editors, which parse the document on every keystroke. Only the content of the innermost element
+    /// around the edit is parsed again, and all other nodes are moved over to the new source, which is much faster than
+    /// parsing the whole document. The result is the same as parsing the new source with [`parse`](crate::parse).
+    /// If the edit changes how the rest of the document is parsed, e.g. because it adds an end tag or it is not inside of
+    /// an element with an end tag, the whole document is parsed again.
+    ///
+    /// The returned [`Changes`](crate::incremental::Changes) tell which nodes were replaced, and where the other nodes are
+    /// in the new document. This document must not have been modified since it was parsed, because the nodes are moved
+    /// over as they were parsed, and modified elements cause a full parse.
+    ///
+    /// # Errors
+    /// See [`parse`](crate::parse)
+    ///
+    /// # Example
+    /// ```
+    /// use tl::incremental::Edit;
+    ///
+    /// let old = "<ul><li>a</li><li>b</li></ul><p>end</p>";
+    /// let new = "<ul><li>a</li><li>b<i>!</i></li></ul><p>end</p>";
+    /// let dom = tl::parse(old, Default::default()).unwrap();
+    ///
+    /// let (dom, changes) = dom.reparse_range(Edit::insert(19, "<i>!</i>".len()), new).unwrap();
+    ///
+    /// // only the content of the second <li> was parsed again
+    /// let li = changes.parent().unwrap();
+    /// assert_eq!(li.get(dom.parser()).unwrap().inner_html(dom.parser()), "b<i>!</i>");
+    /// assert_eq!(changes.inserted().count(), 3);
+    /// assert_eq!(dom.outer_html(), new);
+    /// ```
+    pub fn reparse_range(self, edit: Edit, input: &str) -> Result<(VDom<'_>, Changes), ParseError> {
+        crate::incremental::reparse_range(self, edit, input)
+    }
+
     /// Finds an element by its `id` attribute.
+    ///
+    /// If more than one element has the given ID, the first one in document order is returned.
+    /// Use [`VDom::get_elements_by_id`] to find all of them.
     pub fn get_element_by_id<'b, S>(&'b self, id: S) -> Option<NodeHandle>
+    where
+        S: Into<Bytes<'a>>,
+    {
+        self.get_elements_by_id(id).next()
+    }
+
+    /// Returns a list of all elements with the given `id` attribute, in document order.
+    ///
+    /// IDs are supposed to be unique within a document, but many real world documents contain duplicates.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p id="x">a</p><p id="x">b</p>"#, Default::default()).unwrap();
+    /// assert_eq!(dom.get_elements_by_id("x").count(), 2);
+    /// ```
+    pub fn get_elements_by_id<'b, S>(&'b self, id: S) -> Box<dyn Iterator<Item = NodeHandle> + 'b>
     where
         S: Into<Bytes<'a>>,
     {
@@ -48,21 +188,152 @@ impl<'a> VDom<'a> {
         let parser = self.parser();
 
         if parser.options.is_tracking_ids() {
-            parser.ids.get(&bytes).copied()
+            parser
+                .ids
+                .get(&bytes)
+                .map(|x| Box::new(x.iter().cloned()) as Box<dyn Iterator<Item = NodeHandle>>)
+                .unwrap_or_else(|| Box::new(std::iter::empty()))
         } else {
-            self.nodes()
+            let iter = self
+                .nodes()
                 .iter()
                 .enumerate()
-                .find(|(_, node)| {
-                    node.as_tag().map_or(false, |tag| {
-                        tag._attributes.id.as_ref().map_or(false, |x| x.eq(&bytes))
+                .filter_map(move |(id, node)| {
+                    node.as_tag().and_then(|tag| {
+                        tag._attributes
+                            .id
+                            .as_ref()
+                            .is_some_and(|x| x.eq(&bytes))
+                            .then(|| parser.handle(id as InnerNodeHandle))
                     })
+                });
+
+            Box::new(iter)
+        }
+    }
+
+    /// Returns all IDs that are used by more than one element, along with the elements that use them.
+    ///
+    /// IDs are ordered by their first occurrence, and elements are in document order.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<p id="a"></p><p id="b"></p><p id="a"></p>"#,
+    ///     Default::default(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let duplicates = dom.duplicate_ids();
+    /// assert_eq!(duplicates.len(), 1);
+    /// assert_eq!(duplicates[0].0, "a");
+    /// assert_eq!(duplicates[0].1.len(), 2);
+    /// ```
+    pub fn duplicate_ids(&self) -> Vec<(&Bytes<'a>, Vec<NodeHandle>)> {
+        let parser = self.parser();
+
+        let mut duplicates = if parser.options.is_tracking_ids() {
+            parser
+                .ids
+                .iter()
+                .filter(|(_, handles)| handles.len() > 1)
+                .map(|(id, handles)| (id, handles.to_vec()))
+                .collect::<Vec<_>>()
+        } else {
+            let mut ids: HashMap<&Bytes<'a>, Vec<NodeHandle>> = HashMap::new();
+
+            for (handle, node) in self.nodes().iter().enumerate() {
+                if let Some(id) = node.as_tag().and_then(|tag| tag._attributes.id.as_ref()) {
+                    ids.entry(id)
+                        .or_default()
+                        .push(parser.handle(handle as InnerNodeHandle));
+                }
+            }
+
+            ids.into_iter()
+                .filter(|(_, handles)| handles.len() > 1)
+                .collect()
+        };
+
+        duplicates.sort_unstable_by_key(|(_, handles)| handles[0]);
+        duplicates
+    }
+
+    /// Returns every distinct ID of the document, in the order of their first occurrence
+    ///
+    /// This uses the lookup table of [`ParserOptions::track_ids`] if it is enabled, and goes through all elements otherwise.
+    /// IDs that are not valid UTF-8 are left out.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p id="b"></p><p id="a"></p><p id="b"></p>"#, Default::default()).unwrap();
+    /// assert_eq!(dom.ids(), ["b", "a"]);
+    /// ```
+    pub fn ids(&self) -> Vec<&str> {
+        let parser = self.parser();
+
+        if parser.options.is_tracking_ids() {
+            let mut ids = parser
+                .ids
+                .iter()
+                .filter_map(|(id, handles)| Some((id.try_as_utf8_str()?, handles.get(0)?)))
+                .collect::<Vec<_>>();
+
+            ids.sort_unstable_by_key(|(_, first)| **first);
+            ids.into_iter().map(|(id, _)| id).collect()
+        } else {
+            distinct(
+                self.nodes()
+                    .iter()
+                    .filter_map(|node| node.as_tag()?._attributes.id.as_ref()?.try_as_utf8_str()),
+            )
+        }
+    }
+
+    /// Returns every distinct class name of the document, in the order of their first occurrence
+    ///
+    /// This uses the lookup table of [`ParserOptions::track_classes`] if it is enabled, and goes through all elements otherwise.
+    /// This is useful to find selectors of a stylesheet that match nothing. Class names that are not valid UTF-8 are left out.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p class="card big"></p><p class="card"></p><p class="small"></p>"#, Default::default()).unwrap();
+    /// assert_eq!(dom.class_names(), ["card", "big", "small"]);
+    /// ```
+    pub fn class_names(&self) -> Vec<&str> {
+        let parser = self.parser();
+
+        if parser.options.is_tracking_classes() {
+            // an element with several classes is the first occurrence of all of them, so ties are ordered like in its attribute
+            let mut classes = parser
+                .classes
+                .iter()
+                .filter_map(|(class, handles)| {
+                    let class = class.try_as_utf8_str()?;
+                    let first = *handles.get(0)?;
+                    let tag = first.get(parser)?.as_tag()?;
+                    let position = tag
+                        .attributes()
+                        .class_iter()?
+                        .position(|name| name == class);
+
+                    Some((class, (first, position)))
                 })
-                .map(|(id, _)| NodeHandle::new(id as InnerNodeHandle))
+                .collect::<Vec<_>>();
+
+            classes.sort_unstable_by_key(|(_, key)| *key);
+            classes.into_iter().map(|(class, _)| class).collect()
+        } else {
+            distinct(
+                self.nodes()
+                    .iter()
+                    .filter_map(Node::as_tag)
+                    .flat_map(|tag| tag._attributes.class_iter().into_iter().flatten()),
+            )
         }
     }
 
-    /// Returns a list of elements that match a given class name.
+    /// Returns a list of elements that match a given class name, in document order.
     pub fn get_elements_by_class_name<'b>(
         &'b self,
         id: &'b str,
@@ -86,7 +357,145 @@ impl<'a> VDom<'a> {
                     node.as_tag().and_then(|tag| {
                         tag._attributes
                             .is_class_member(member)
-                            .then(|| NodeHandle::new(id as InnerNodeHandle))
+                            .then(|| parser.handle(id as InnerNodeHandle))
+                    })
+                });
+
+            Box::new(iter)
+        }
+    }
+
+    /// Returns a list of elements with the given tag name, in document order.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<a href="/">Home</a><p><a href="/about">About</a></p>"#,
+    ///     tl::ParserOptions::default().track_tags(),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(dom.get_elements_by_tag_name("a").count(), 2);
+    /// assert_eq!(dom.get_elements_by_tag_name("span").count(), 0);
+    /// ```
+    pub fn get_elements_by_tag_name<'b>(
+        &'b self,
+        name: &'b str,
+    ) -> Box<dyn Iterator<Item = NodeHandle> + 'b> {
+        let parser = self.parser();
+
+        if parser.options.is_tracking_tags() {
+            parser
+                .tag_names
+                .get(&Bytes::from(name.as_bytes()))
+                .map(|x| Box::new(x.iter().cloned()) as Box<dyn Iterator<Item = NodeHandle>>)
+                .unwrap_or_else(|| Box::new(std::iter::empty()))
+        } else {
+            let iter = self
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(move |(id, node)| {
+                    node.as_tag().and_then(|tag| {
+                        (tag.name() == name).then(|| parser.handle(id as InnerNodeHandle))
+                    })
+                });
+
+            Box::new(iter)
+        }
+    }
+
+    /// Returns all custom elements of this document, like `<my-card>`, in document order
+    ///
+    /// See [`HTMLTag::is_custom_element`](crate::HTMLTag::is_custom_element) for which elements are custom elements.
+    /// If the parser tracked tag names (see [`ParserOptions::track_tags`]), only the lookup table is searched
+    /// instead of every node.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     "<my-app><nav-bar></nav-bar><p>Hi</p><my-card></my-card></my-app>",
+    ///     tl::ParserOptions::default().track_tags(),
+    /// )
+    /// .unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let names = dom
+    ///     .custom_elements()
+    ///     .iter()
+    ///     .map(|handle| handle.get(parser).unwrap().as_tag().unwrap().name().as_utf8_str())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(names, ["my-app", "nav-bar", "my-card"]);
+    /// ```
+    pub fn custom_elements(&self) -> Vec<NodeHandle> {
+        let parser = self.parser();
+        let is_custom = |handle: &NodeHandle| {
+            handle
+                .get(parser)
+                .and_then(Node::as_tag)
+                .is_some_and(|tag| tag.is_custom_element())
+        };
+
+        if parser.options.is_tracking_tags() {
+            let mut handles = parser
+                .tag_names
+                .iter()
+                .filter(|(name, _)| crate::parser::is_custom_element_name(name.as_bytes()))
+                .flat_map(|(_, handles)| handles.iter().copied())
+                .filter(is_custom)
+                .collect::<Vec<_>>();
+
+            handles.sort_unstable();
+            handles
+        } else {
+            (0..self.nodes().len())
+                .map(|index| parser.handle(index as InnerNodeHandle))
+                .filter(is_custom)
+                .collect()
+        }
+    }
+
+    /// Returns a list of elements whose attribute `name` has the given value, in document order.
+    ///
+    /// This is equivalent to the query selector `[name=value]`, but uses a lookup table if the attribute is tracked.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<button data-testid="buy">Buy</button><button data-testid="sell">Sell</button>"#,
+    ///     tl::ParserOptions::default().track_attribute("data-testid"),
+    /// )
+    /// .unwrap();
+    ///
+    /// let button = dom.get_elements_by_attribute("data-testid", "sell").next().unwrap();
+    /// assert_eq!(button.get(dom.parser()).unwrap().inner_text(dom.parser()), "Sell");
+    /// ```
+    pub fn get_elements_by_attribute<'b>(
+        &'b self,
+        name: &'b str,
+        value: &'b str,
+    ) -> Box<dyn Iterator<Item = NodeHandle> + 'b> {
+        let parser = self.parser();
+
+        if let Some(name) = parser.options.tracked_attributes().find(|n| *n == name) {
+            parser
+                .attribute_values
+                .get(&(name, Bytes::from(value)))
+                .map(|x| Box::new(x.iter().cloned()) as Box<dyn Iterator<Item = NodeHandle>>)
+                .unwrap_or_else(|| Box::new(std::iter::empty()))
+        } else {
+            let iter = self
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(move |(id, node)| {
+                    node.as_tag().and_then(|tag| {
+                        tag._attributes
+                            .get(name)
+                            .flatten()
+                            .is_some_and(|v| *v == *value)
+                            .then(|| parser.handle(id as InnerNodeHandle))
                     })
                 });
 
@@ -94,6 +503,36 @@ impl<'a> VDom<'a> {
         }
     }
 
+    /// Returns a list of elements whose `name` attribute has the given value, in document order, like `document.getElementsByName()`
+    ///
+    /// This is mostly useful for form controls, which are submitted under their name. It uses a lookup table if names are
+    /// tracked with [`ParserOptions::track_names`], and is otherwise the same as [`VDom::get_elements_by_attribute`].
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(
+    ///     r#"<form><input type="radio" name="size" value="s"><input type="radio" name="size" value="l"><input name="q"></form>"#,
+    ///     tl::ParserOptions::default().track_names(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let values = dom
+    ///     .get_elements_by_name("size")
+    ///     .map(|input| {
+    ///         let tag = input.get(dom.parser()).unwrap().as_tag().unwrap();
+    ///         tag.attributes().get("value").flatten().unwrap().as_utf8_str().into_owned()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(values, ["s", "l"]);
+    /// ```
+    pub fn get_elements_by_name<'b>(
+        &'b self,
+        name: &'b str,
+    ) -> Box<dyn Iterator<Item = NodeHandle> + 'b> {
+        self.get_elements_by_attribute("name", name)
+    }
+
     /// Returns a slice of *all* the elements in the HTML document
     ///
     /// The difference between `children()` and `nodes()` is that children only returns the immediate children of the root node,
@@ -110,6 +549,7 @@ impl<'a> VDom<'a> {
     /// The difference between `children()` and `nodes()` is that children only returns the immediate children of the root node,
     /// while `nodes()` returns all nodes, including nested tags.
     pub fn nodes_mut(&mut self) -> &mut [Node<'a>] {
+        self.parser.invalidate_positions();
         &mut self.parser.tags
     }
 
@@ -120,15 +560,223 @@ impl<'a> VDom<'a> {
 
     /// Returns a mutable reference to the topmost subnodes ("children") of this DOM
     pub fn children_mut(&mut self) -> &mut [NodeHandle] {
+        self.parser.invalidate_positions();
         &mut self.parser.ast
     }
 
+    /// Returns the node with the given stable identifier, see [`NodeHandle::stable_id`]
+    ///
+    /// Returns `None` if the document has no such node. If this document was parsed from another source or
+    /// with other options than the document that the identifier was taken from, the returned node may be unrelated.
+    pub fn resolve_stable_id(&self, id: StableId) -> Option<NodeHandle> {
+        let handle = self.parser.handle(id.get());
+        self.parser.contains(handle).then_some(handle)
+    }
+
+    /// Serializes handles to nodes of this document into a string, which can be stored and turned back into handles
+    /// with [`VDom::deserialize_handles`] after parsing the same source again
+    ///
+    /// The string contains the [stable identifiers](NodeHandle::stable_id) of the nodes and a hash of the source,
+    /// so that handles are not resolved against a document that was parsed from a different source.
+    /// Returns `None` if a handle does not belong to this document.
+    ///
+    /// # Example
+    /// ```
+    /// let input = "<p>a</p><p>b</p><p>c</p>";
+    ///
+    /// let dom = tl::parse(input, Default::default()).unwrap();
+    /// let paragraphs = dom.query_selector("p").unwrap().skip(1).collect::<Vec<_>>();
+    /// let stored = dom.serialize_handles(&paragraphs).unwrap();
+    ///
+    /// // later, e.g. in another run of the program
+    /// let dom = tl::parse(input, Default::default()).unwrap();
+    /// let text = dom
+    ///     .deserialize_handles(&stored)
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|p| p.get(dom.parser()).unwrap().inner_text(dom.parser()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(text, ["b", "c"]);
+    ///
+    /// // the handles do not belong to a changed document
+    /// let changed = tl::parse("<p>a</p><p>c</p>", Default::default()).unwrap();
+    /// assert_eq!(changed.deserialize_handles(&stored), None);
+    /// ```
+    pub fn serialize_handles(&self, handles: &[NodeHandle]) -> Option<String> {
+        let mut out = format!("{:016x}:", self.source_hash());
+
+        for (index, handle) in handles.iter().enumerate() {
+            if !self.parser.contains(*handle) {
+                return None;
+            }
+            if index > 0 {
+                out.push(',');
+            }
+            out.push_str(&handle.stable_id().to_string());
+        }
+
+        Some(out)
+    }
+
+    /// Turns a string that was returned by [`VDom::serialize_handles`] back into handles to nodes of this document
+    ///
+    /// Returns `None` if the string is malformed, if this document was parsed from a different source than the
+    /// document that the handles were serialized from, or if a node does not exist.
+    pub fn deserialize_handles(&self, serialized: &str) -> Option<Vec<NodeHandle>> {
+        let (hash, ids) = serialized.split_once(':')?;
+        if u64::from_str_radix(hash, 16).ok()? != self.source_hash() {
+            return None;
+        }
+        if ids.is_empty() {
+            return Some(Vec::new());
+        }
+
+        ids.split(',')
+            .map(|id| self.resolve_stable_id(id.parse().ok()?))
+            .collect()
+    }
+
+    /// Hashes the source of this document, which is stable across platforms and releases
+    fn source_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hasher.write(self.parser.stream.data());
+        hasher.finish()
+    }
+
+    /// Inserts a copy of a fragment at the given index of the children of an element, or of the topmost nodes if `parent` is `None`
+    ///
+    /// Returns the handle of the inserted root, or `None` if the parent is not an element of this document or the index is
+    /// greater than its number of children. The inserted nodes come after all other nodes in the order of handles,
+    /// and like with other mutations, the descendants of the ancestors are not renumbered. The elements are added to the
+    /// lookup tables that are enabled in the options of this document, like the one of [`VDom::get_element_by_id`].
+    ///
+    /// # Example
+    /// ```
+    /// let template = tl::parse(r#"<p class="note">Copied</p>"#, Default::default()).unwrap();
+    /// let fragment = template.nodes()[0].as_tag().unwrap().clone_subtree(template.parser());
+    ///
+    /// let mut dom = tl::parse("<div><h1>Title</h1></div>", Default::default()).unwrap();
+    /// let div = dom.children()[0];
+    /// let p = dom.insert_fragment(Some(div), 1, &fragment).unwrap();
+    ///
+    /// assert_eq!(dom.outer_html(), r#"<div><h1>Title</h1><p class="note">Copied</p></div>"#);
+    /// assert_eq!(dom.get_elements_by_class_name("note").next(), Some(p));
+    /// assert_eq!(dom.insert_fragment(Some(div), 5, &fragment), None);
+    /// ```
+    pub fn insert_fragment(
+        &mut self,
+        parent: Option<NodeHandle>,
+        index: usize,
+        fragment: &OwnedFragment,
+    ) -> Option<NodeHandle> {
+        let len = match parent {
+            Some(parent) => parent.get(&self.parser)?.as_tag()?._children.len(),
+            None => self.parser.ast.len(),
+        };
+
+        if index > len {
+            return None;
+        }
+
+        self.parser.invalidate_positions();
+        let root = crate::fragment::attach(&mut self.parser, fragment, parent);
+
+        match parent {
+            Some(parent) => parent
+                .get_mut(&mut self.parser)?
+                .as_tag_mut()?
+                ._children
+                .insert(index, root),
+            None => self.parser.ast.insert(index, root),
+        }
+
+        Some(root)
+    }
+
+    /// Merges adjacent text nodes and removes empty text nodes, like [`Node.normalize()`](https://developer.mozilla.org/en-US/docs/Web/API/Node/normalize) in browsers
+    ///
+    /// Adjacent text nodes are left behind by end tags that do not close anything, and by removing or emptying nodes.
+    /// After normalizing, every run of text is a single node, which makes traversing and comparing documents predictable.
+    /// Merged and empty text nodes are only removed from the lists of children and are empty afterwards,
+    /// so their handles stay valid.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<p>Hello <!-- name -->world</p>", Default::default()).unwrap();
+    ///
+    /// // remove the comment
+    /// let p = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    /// p.children_mut().top_mut().remove(1);
+    /// assert_eq!(dom.nodes()[0].as_tag().unwrap().children().top().len(), 2);
+    ///
+    /// dom.normalize();
+    ///
+    /// let p = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(p.children().top().len(), 1);
+    /// assert_eq!(p.inner_text(dom.parser()), "Hello world");
+    /// ```
+    pub fn normalize(&mut self) {
+        self.parser.normalize();
+    }
+
+    /// Returns a depth-first iterator over all nodes of this DOM that emits an [`Edge::Open`](crate::Edge::Open) when entering a node
+    /// and an [`Edge::Close`](crate::Edge::Close) when leaving it.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::Edge;
+    ///
+    /// let dom = tl::parse("<p>a<b>b</b></p>c", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let mut out = String::new();
+    /// for edge in dom.traverse() {
+    ///     let node = edge.handle().get(parser).unwrap();
+    ///     match (edge, node.as_tag()) {
+    ///         (Edge::Open(_), Some(tag)) => out.push_str(&format!("[{}]", tag.name().as_utf8_str())),
+    ///         (Edge::Close(_), Some(tag)) => out.push_str(&format!("[/{}]", tag.name().as_utf8_str())),
+    ///         (Edge::Open(_), None) => out.push_str(&node.inner_text(parser)),
+    ///         (Edge::Close(_), None) => {}
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(out, "[p]a[b]b[/b][/p]c");
+    /// ```
+    pub fn traverse(&self) -> Traverse<'a, '_> {
+        Traverse::new(&self.parser, &self.parser.ast)
+    }
+
+    /// Walks over all nodes of this DOM depth-first and calls the matching hooks of the given visitor
+    ///
+    /// See [`DomVisitor`] for an example.
+    pub fn visit<V: DomVisitor<'a> + ?Sized>(&self, visitor: &mut V) {
+        crate::visit::visit(self.traverse(), &self.parser, visitor);
+    }
+
     /// Returns the HTML version.
-    /// This is determined by the `<!DOCTYPE>` tag
+    /// This is determined by the `<!DOCTYPE>` tag, see [`Doctype::version`]
     pub fn version(&self) -> Option<HTMLVersion> {
         self.parser.version
     }
 
+    /// Returns the document type declaration, if the document has one at the top level
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<!doctype html><html><body>Hi</body></html>", Default::default()).unwrap();
+    /// let doctype = dom.doctype().unwrap();
+    ///
+    /// assert_eq!(doctype.name().unwrap(), "html");
+    /// assert_eq!(doctype.public_id(), None);
+    /// assert_eq!(doctype.raw(), "<!doctype html>");
+    /// assert_eq!(dom.outer_html(), "<!doctype html><html><body>Hi</body></html>");
+    /// ```
+    pub fn doctype(&self) -> Option<&Doctype<'a>> {
+        self.children()
+            .iter()
+            .find_map(|handle| handle.get(&self.parser)?.as_doctype())
+    }
+
     /// Returns the contained markup of all of the elements in this DOM.
     ///
     /// Equivalent to [Element#outerHTML](https://developer.mozilla.org/en-US/docs/Web/API/Element/outerHTML) in browsers)
@@ -160,8 +808,647 @@ impl<'a> VDom<'a> {
         inner_html
     }
 
+    /// Returns the markup of this DOM, keeping the source of everything that was not modified
+    ///
+    /// Unlike [`VDom::outer_html`], this copies unmodified elements from the source string, including their
+    /// whitespace, attribute quotes and character references.
+    /// As long as the nodes were not borrowed mutably, e.g. through [`VDom::nodes_mut`], the markup is exactly the input.
+    /// Only the start tags of elements that were modified through [`HTMLTag::attributes_mut`](crate::HTMLTag::attributes_mut)
+    /// or [`HTMLTag::name_mut`](crate::HTMLTag::name_mut), text that was replaced and nodes that were moved are regenerated.
+    /// Markup that is not part of any node, like stray end tags, is only kept within elements whose children were not modified.
+    ///
+    /// No parser options are needed for this, because the parser records the position of every node in the source.
+    ///
+    /// # Example
+    /// ```
+    /// let input = "<ul class='menu'>\n  <li><a href=x>Home</a>&nbsp;</li>\n  <li><A HREF='y'>About</A></li>\n</ul>";
+    /// let mut dom = tl::parse(input, Default::default()).unwrap();
+    /// assert_eq!(dom.to_html(), input);
+    ///
+    /// let link = dom.query_selector("a[href=x]").unwrap().next().unwrap();
+    /// let link = link.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+    /// link.attributes_mut().get_mut("href").flatten().unwrap().set("/").unwrap();
+    ///
+    /// assert_eq!(
+    ///     dom.to_html(),
+    ///     "<ul class='menu'>\n  <li><a href=\"/\">Home</a>&nbsp;</li>\n  <li><A HREF='y'>About</A></li>\n</ul>"
+    /// );
+    /// ```
+    pub fn to_html(&self) -> String {
+        crate::roundtrip::to_html(self)
+    }
+
+    /// Returns an indented tree of all nodes in this DOM, which implements [`Display`](std::fmt::Display)
+    ///
+    /// Elements are written like `div#main.container` and text is truncated, see [`TreeDisplay`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<html><body><div id=main class=container>Hello</div></body></html>", Default::default()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     dom.debug_tree().to_string(),
+    ///     "html\n  body\n    div#main.container\n      \"Hello\"\n"
+    /// );
+    /// ```
+    pub fn debug_tree(&self) -> TreeDisplay<'_, 'a> {
+        TreeDisplay::new(&self.parser, self.children())
+    }
+
+    /// Searches the text nodes of this DOM for a pattern and returns every match with its text node,
+    /// the element that contains the text node and the byte range of the match in the text
+    ///
+    /// The pattern is a string, which matches literally, or a `regex::Regex` if the `regex` feature is enabled.
+    /// Matches are returned in document order. Like with `VDom::find_text`, the text is searched as written in the source,
+    /// a match never spans several text nodes, and empty matches are skipped. The element and range are what is needed
+    /// to place highlights or annotations on top of the document, e.g. with [`text_index::TextIndex`](crate::text_index::TextIndex).
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>A cat</p><ul><li>cats and <b>dogs</b></li></ul>cat", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let matches = dom.find_all_text_nodes("cat");
+    /// let found = matches
+    ///     .iter()
+    ///     .map(|m| {
+    ///         let element = m.element().map(|element| element.get(parser).unwrap().as_tag().unwrap().name().as_utf8_str());
+    ///         (element, m.range())
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(found, [(Some("p".into()), 2..5), (Some("li".into()), 0..3), (None, 0..3)]);
+    /// assert_eq!(matches[1].node().get(parser).unwrap().as_raw().unwrap(), "cats and ");
+    /// ```
+    pub fn find_all_text_nodes<P>(&self, pattern: &P) -> Vec<TextMatch>
+    where
+        P: TextPattern + ?Sized,
+    {
+        crate::search::find_text_nodes(self, pattern)
+    }
+
+    /// Searches the text nodes of this DOM for matches of a regular expression
+    ///
+    /// Every match is returned with the handle of its text node and its capture groups, in document order.
+    /// The text is searched as written in the source, so character references like `&amp;` are not decoded.
+    /// A match never spans several text nodes, e.g. across a `<b>` tag.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul><li>Apples: 3 kg</li><li>Pears: <b>12 kg</b></li></ul>"#, Default::default()).unwrap();
+    /// let regex = regex::Regex::new(r"(?<amount>\d+) kg").unwrap();
+    ///
+    /// let matches = dom.find_text(&regex);
+    /// let amounts = matches.iter().map(|m| m.name("amount").unwrap()).collect::<Vec<_>>();
+    /// assert_eq!(amounts, ["3", "12"]);
+    ///
+    /// let parent = matches[1].node().get(dom.parser()).unwrap().as_raw().unwrap();
+    /// assert_eq!(parent, "12 kg");
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn find_text(&self, regex: &regex::Regex) -> Vec<RegexMatch> {
+        crate::search::find_text(self, regex)
+    }
+
+    /// Searches the values of the given attribute of all elements for matches of a regular expression
+    ///
+    /// Every match is returned with the handle of its element and its capture groups, in document order.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a href="/item/12">A</a><a href="/about">B</a><a href="/item/7">C</a>"#, Default::default()).unwrap();
+    /// let regex = regex::Regex::new(r"^/item/(\d+)$").unwrap();
+    ///
+    /// let ids = dom
+    ///     .find_attribute("href", &regex)
+    ///     .iter()
+    ///     .map(|m| m.get(1).unwrap().to_owned())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(ids, ["12", "7"]);
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn find_attribute(&self, name: &str, regex: &regex::Regex) -> Vec<RegexMatch> {
+        crate::search::find_attribute(self, name, regex)
+    }
+
+    /// Parses and runs an extraction rule, and returns the extracted value of every matching element.
+    ///
+    /// A rule combines a query selector, an optional accessor and any number of transforms in a single string.
+    /// See the [`eval`](crate::eval) module for the syntax.
+    ///
+    /// # Errors
+    /// Returns an error if the rule cannot be parsed.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <span class="price" data-amount=" 12.50 ">$12.50</span>
+    ///     <span class="price" data-amount="8">$8</span>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// assert_eq!(dom.eval(".price @data-amount | trim").unwrap(), ["12.50", "8"]);
+    /// assert_eq!(dom.eval("span.price").unwrap(), ["$12.50", "$8"]);
+    /// ```
+    pub fn eval(&self, rule: &str) -> Result<Vec<String>, EvalError> {
+        crate::eval::Rule::parse(rule).map(|rule| rule.eval(self))
+    }
+
+    /// Evaluates an XPath expression and returns the nodes it selects in document order
+    ///
+    /// See [`xpath`](crate::xpath) for the supported syntax. Use [`XPath::evaluate`] for expressions that
+    /// evaluate to a string, number or boolean.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul><li>a</li><li id="x">b</li><li>c</li></ul>"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    ///
+    /// let items = dom.xpath("//li[@id='x']/following-sibling::li").unwrap();
+    /// let node = items[0].handle().unwrap().get(parser).unwrap();
+    ///
+    /// assert_eq!(items.len(), 1);
+    /// assert_eq!(node.inner_text(parser), "c");
+    /// ```
+    pub fn xpath(&self, expression: &str) -> Result<Vec<Item>, XPathError> {
+        XPath::parse(expression).map(|xpath| xpath.select(self))
+    }
+
+    /// Returns the forms of this document with their controls, in document order
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <form action="/search" method="POST">
+    ///         <input name="q" value="hello world">
+    ///         <input type="checkbox" name="safe" checked>
+    ///         <select name="lang"><option value="en">English</option><option selected>Deutsch</option></select>
+    ///         <button type="submit">Search</button>
+    ///     </form>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let forms = dom.forms();
+    /// assert_eq!(forms[0].action.as_deref(), Some("/search"));
+    /// assert_eq!(forms[0].method, "post");
+    /// assert_eq!(forms[0].urlencode(), "q=hello+world&safe=on&lang=Deutsch");
+    /// ```
+    pub fn forms(&self) -> Vec<Form> {
+        crate::forms::collect(self)
+    }
+
+    /// Returns the `<head>` element of this document, or the nodes that a browser would place in it if the document omits it
+    ///
+    /// Without a `<head>` tag, the metadata elements (like `<title>`, `<meta>` or `<link>`) at the start of the document make up the head.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<title>Hi</title><meta charset=utf-8><p>Hello</p>", Default::default()).unwrap();
+    /// let head = dom.head();
+    ///
+    /// assert!(head.is_implied());
+    /// assert_eq!(head.children().len(), 2);
+    /// assert_eq!(head.inner_text(dom.parser()), "Hi");
+    /// ```
+    pub fn head(&self) -> ImpliedElement {
+        crate::implied::head(self)
+    }
+
+    /// Returns the `<body>` element of this document, or the nodes that a browser would place in it if the document omits it
+    ///
+    /// Without a `<body>` tag, everything except for the head (see [`VDom::head`]) makes up the body.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<title>Hi</title>\n<p>Hello</p><p>World</p>", Default::default()).unwrap();
+    /// let body = dom.body();
+    ///
+    /// assert!(body.is_implied());
+    /// assert_eq!(body.inner_html(dom.parser()), "<p>Hello</p><p>World</p>");
+    ///
+    /// let dom = tl::parse("<html><body><p>Hello</p></body></html>", Default::default()).unwrap();
+    /// assert_eq!(dom.body().handle(), dom.query_selector("body").unwrap().next());
+    /// ```
+    pub fn body(&self) -> ImpliedElement {
+        crate::implied::body(self)
+    }
+
+    /// Returns the base URL of this document, which relative URLs are resolved against
+    ///
+    /// This is the `href` of the first `<base>` element with one, resolved against the given URL of the document,
+    /// or the URL of the document if there is no such element. Like in browsers, only the first `<base href>` counts,
+    /// and `data:` and `javascript:` base URLs are ignored. Returns `None` if no absolute URL is known.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<base target="_blank"><base href="../assets/"><base href="/other/">"#, Default::default()).unwrap();
+    ///
+    /// assert_eq!(dom.base_url(Some("https://example.com/blog/post")).as_deref(), Some("https://example.com/assets/"));
+    /// assert_eq!(dom.base_url(None), None);
+    ///
+    /// let dom = tl::parse("<p>No base</p>", Default::default()).unwrap();
+    /// assert_eq!(dom.base_url(Some("https://example.com/")).as_deref(), Some("https://example.com/"));
+    /// ```
+    pub fn base_url(&self, document_url: Option<&str>) -> Option<String> {
+        crate::links::base_url(self, document_url)
+    }
+
+    /// Resolves a possibly relative URL against the base URL of this document, see [`VDom::base_url`]
+    ///
+    /// Protocol-relative URLs like `//cdn.example.com/a.js` take the scheme of the base URL, and fragment-only URLs
+    /// like `#top` refer to the base URL like in browsers. The URL is returned unchanged if no absolute base URL is known.
+    /// To resolve many URLs, get the base URL once and use [`links::resolve`](crate::links::resolve).
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<base href="https://cdn.example.com/v2/">"#, Default::default()).unwrap();
+    /// let page = Some("http://example.com/post");
+    ///
+    /// assert_eq!(dom.resolve_url(page, "app.js"), "https://cdn.example.com/v2/app.js");
+    /// assert_eq!(dom.resolve_url(page, "//static.example.com/a.css"), "https://static.example.com/a.css");
+    /// assert_eq!(dom.resolve_url(page, "#top"), "https://cdn.example.com/v2/#top");
+    /// ```
+    pub fn resolve_url(&self, document_url: Option<&str>, url: &str) -> String {
+        match self.base_url(document_url) {
+            Some(base) => crate::links::resolve(&base, url),
+            None => url.trim().to_owned(),
+        }
+    }
+
+    /// Collects the URLs referenced by `href`, `src` and `srcset` attributes of `<a>`, `<area>`, `<img>`, `<link>`, `<script>` and `<iframe>` tags, in document order
+    ///
+    /// Relative URLs are resolved against the first `<base href>` of the document and the given base URL.
+    /// If neither is present, or the base URL is relative, URLs are returned as they appear in the document.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::links::LinkKind;
+    ///
+    /// let dom = tl::parse(r#"
+    ///     <link rel="stylesheet" href="/style.css">
+    ///     <a href="../about" rel="nofollow">About</a>
+    ///     <img src="a.png" srcset="a-2x.png 2x">
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let links = dom.links(Some("https://example.com/blog/post"));
+    /// let urls = links.iter().map(|link| link.url.as_str()).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(urls, [
+    ///     "https://example.com/style.css",
+    ///     "https://example.com/about",
+    ///     "https://example.com/blog/a.png",
+    ///     "https://example.com/blog/a-2x.png",
+    /// ]);
+    /// assert_eq!(links[1].kind, LinkKind::Anchor);
+    /// assert_eq!(links[1].rel.as_deref(), Some("nofollow"));
+    /// ```
+    pub fn links(&self, base_url: Option<&str>) -> Vec<Link> {
+        crate::links::collect(self, base_url)
+    }
+
+    /// Returns the `<a href>` and `<area href>` links of this document with their text, `rel` tokens and section heading,
+    /// in document order
+    ///
+    /// URLs are resolved like in [`VDom::links`].
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <h2>Partners</h2>
+    ///     <p>Visit <a href="https://shop.example.org/" rel="Sponsored NoFollow">our   shop</a>
+    ///     <a href="/home"><img src="logo.png" alt="Home"></a></p>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let anchors = dom.anchors(Some("https://example.com/"));
+    ///
+    /// assert_eq!(anchors[0].text, "our shop");
+    /// assert_eq!(anchors[0].rel, ["sponsored", "nofollow"]);
+    /// assert_eq!(anchors[0].heading.as_deref(), Some("Partners"));
+    /// assert_eq!(anchors[1].url, "https://example.com/home");
+    /// assert_eq!(anchors[1].text, "Home");
+    /// ```
+    pub fn anchors(&self, base_url: Option<&str>) -> Vec<Anchor> {
+        crate::links::anchors(self, base_url)
+    }
+
+    /// Summarizes the `<a href>` and `<area href>` links of this document, which is at the given URL
+    ///
+    /// This is a shorthand for [`links::summarize`](crate::links::summarize) with the result of [`VDom::anchors`].
+    pub fn link_summary(&self, page_url: Option<&str>) -> LinkSummary {
+        crate::links::summarize(&self.anchors(page_url), page_url)
+    }
+
+    /// Returns the `<link>` elements of this document grouped by the tokens of their `rel` attribute, with their
+    /// `type` parsed as a MIME type and their icon `sizes` parsed
+    ///
+    /// URLs are resolved like in [`VDom::links`].
+    ///
+    /// # Example
+    /// ```
+    /// use tl::links::IconSize;
+    ///
+    /// let dom = tl::parse(r#"
+    ///     <link rel="canonical" href="/post">
+    ///     <link rel="alternate" type="application/rss+xml" title="Blog" href="/feed.xml">
+    ///     <link rel="alternate" hreflang="de" href="/de/post">
+    ///     <link rel="Shortcut Icon" href="/favicon.ico">
+    ///     <link rel="apple-touch-icon" sizes="180x180" href="/touch.png">
+    ///     <link rel="preload" as="font" type="font/woff2" href="/font.woff2" crossorigin>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let relations = dom.link_relations(Some("https://example.com/blog/"));
+    ///
+    /// assert_eq!(relations.canonical().unwrap().url, "https://example.com/post");
+    ///
+    /// let feed = relations.feeds().next().unwrap();
+    /// assert_eq!(feed.url, "https://example.com/feed.xml");
+    /// assert_eq!(feed.title.as_deref(), Some("Blog"));
+    ///
+    /// assert_eq!(relations.translations().next().unwrap().hreflang.as_deref(), Some("de"));
+    /// assert_eq!(relations.icons().len(), 2);
+    /// assert_eq!(relations.largest_icon().unwrap().sizes, [IconSize::Pixels { width: 180, height: 180 }]);
+    ///
+    /// let preload = relations.preloads()[0];
+    /// assert_eq!(preload.destination.as_deref(), Some("font"));
+    /// assert_eq!(preload.mime_type.as_ref().unwrap().essence(), "font/woff2");
+    /// ```
+    pub fn link_relations(&self, base_url: Option<&str>) -> LinkRelations {
+        crate::links::relations(self, base_url)
+    }
+
+    /// Returns the `<iframe>` and `<frame>` elements of this document with their `src` and `srcdoc` attributes, in document order
+    ///
+    /// URLs are resolved like in [`VDom::links`]. The markup in `srcdoc` attributes is decoded, but only parsed
+    /// when [`Frame::parse_srcdoc`] is called.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <iframe src="/embed/video" title="Video"></iframe>
+    ///     <iframe srcdoc="<p>Sponsored &lt;b&gt;offer&lt;/b&gt;</p>"></iframe>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let frames = dom.iframes(Some("https://example.com/post"));
+    ///
+    /// assert_eq!(frames[0].src.as_deref(), Some("https://example.com/embed/video"));
+    /// assert_eq!(frames[0].title.as_deref(), Some("Video"));
+    /// assert!(frames[1].is_srcdoc());
+    /// assert_eq!(frames[1].srcdoc.as_deref(), Some("<p>Sponsored <b>offer</b></p>"));
+    ///
+    /// let embedded = frames[1].parse_srcdoc(Default::default()).unwrap().unwrap();
+    /// assert_eq!(embedded.query_selector("b").unwrap().count(), 1);
+    /// ```
+    pub fn iframes(&self, base_url: Option<&str>) -> Vec<Frame> {
+        crate::frames::collect(self, base_url)
+    }
+
+    /// Collects OpenGraph and Twitter card properties, the canonical URL, icon URLs and JSON-LD scripts of this document
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <meta property="og:title" content="Example">
+    ///     <meta name="twitter:card" content="summary">
+    ///     <link rel="canonical" href="https://example.com/">
+    ///     <link rel="shortcut icon" href="/favicon.ico">
+    ///     <script type="application/ld+json">{"@type": "Article"}</script>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let metadata = dom.extract_metadata();
+    /// assert_eq!(metadata.open_graph("og:title"), Some("Example"));
+    /// assert_eq!(metadata.twitter("twitter:card"), Some("summary"));
+    /// assert_eq!(metadata.canonical.as_deref(), Some("https://example.com/"));
+    /// assert_eq!(metadata.icons, ["/favicon.ico"]);
+    /// assert_eq!(metadata.json_ld, [r#"{"@type": "Article"}"#]);
+    /// ```
+    pub fn extract_metadata(&self) -> Metadata {
+        crate::meta::extract(self)
+    }
+
+    /// Returns the character encoding of the source of this document, as declared by a byte order mark or a `<meta>` tag
+    ///
+    /// A byte order mark wins over `<meta charset>` and `<meta http-equiv="Content-Type" content="...; charset=...">`,
+    /// and the first declaring `<meta>` tag wins over later ones. The label is lowercased but not validated,
+    /// e.g. `windows-1252` or `utf-8`. Because the input of [`parse`](crate::parse) is always a string, this is mostly useful
+    /// for documents parsed with [`parse_bytes`](crate::parse_bytes), or to check that a page was decoded correctly.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<meta http-equiv="Content-Type" content="text/html; charset=ISO-8859-1">"#, Default::default()).unwrap();
+    /// assert_eq!(dom.detected_charset().as_deref(), Some("iso-8859-1"));
+    ///
+    /// let dom = tl::parse("\u{feff}<meta charset=windows-1252>", Default::default()).unwrap();
+    /// assert_eq!(dom.detected_charset().as_deref(), Some("utf-8"));
+    /// ```
+    pub fn detected_charset(&self) -> Option<String> {
+        crate::meta::detected_charset(self)
+    }
+
+    /// Returns the language of this document from the `lang` attribute of its `<html>` element
+    ///
+    /// See [`HTMLTag::effective_lang`](crate::HTMLTag::effective_lang) for the language of other elements.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<!DOCTYPE html><html lang="fr"><body></body></html>"#, Default::default()).unwrap();
+    /// assert_eq!(dom.lang().unwrap(), "fr");
+    /// ```
+    pub fn lang(&self) -> Option<&Bytes<'a>> {
+        self.children()
+            .iter()
+            .filter_map(|handle| handle.get(&self.parser)?.as_tag())
+            .find(|tag| tag.name().as_bytes().eq_ignore_ascii_case(b"html"))?
+            .effective_lang(&self.parser)
+    }
+
+    /// Returns the `<script>` elements of this document in document order, with their inline content or external URL,
+    /// their kind and their loading attributes
+    ///
+    /// URLs are returned as written, see [`links::resolve`](crate::links::resolve) to resolve them.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::resources::ScriptKind;
+    ///
+    /// let dom = tl::parse(r#"
+    ///     <script src="/app.js" type="module" integrity="sha384-abc" crossorigin></script>
+    ///     <script nomodule defer src="/legacy.js"></script>
+    ///     <script type="application/ld+json">{"@type": "Article"}</script>
+    ///     <script>if (a < b) run();</script>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let scripts = dom.scripts();
+    /// assert_eq!(scripts[0].kind, ScriptKind::Module);
+    /// assert_eq!(scripts[0].src.as_deref(), Some("/app.js"));
+    /// assert_eq!(scripts[0].integrity.as_deref(), Some("sha384-abc"));
+    /// assert!(scripts[1].is_nomodule && scripts[1].is_defer);
+    /// assert_eq!(scripts[2].json(), Some(r#"{"@type": "Article"}"#));
+    /// assert_eq!(scripts[3].content.as_deref(), Some("if (a < b) run();"));
+    /// ```
+    pub fn scripts(&self) -> Vec<Script> {
+        crate::resources::scripts(self)
+    }
+
+    /// Returns the stylesheets of this document in document order: `<link rel="stylesheet">` elements with their URL
+    /// and `<style>` elements with their content
+    ///
+    /// URLs are returned as written, see [`links::resolve`](crate::links::resolve) to resolve them.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <link rel="stylesheet" href="/main.css" media="screen">
+    ///     <link rel="alternate stylesheet" href="/dark.css" title="Dark">
+    ///     <style>p { color: red }</style>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let stylesheets = dom.stylesheets();
+    /// assert_eq!(stylesheets[0].href.as_deref(), Some("/main.css"));
+    /// assert_eq!(stylesheets[0].media.as_deref(), Some("screen"));
+    /// assert!(stylesheets[1].is_alternate);
+    /// assert_eq!(stylesheets[2].content.as_deref(), Some("p { color: red }"));
+    /// ```
+    pub fn stylesheets(&self) -> Vec<Stylesheet> {
+        crate::resources::stylesheets(self)
+    }
+
+    /// Computes a SimHash of the visible text of this document, for finding near-duplicate pages
+    ///
+    /// Scripts, styles and boilerplate like `<nav>` and `<footer>` are left out. See [`TextHasher`] for the details
+    /// and for other options.
+    ///
+    /// # Example
+    /// ```
+    /// let text = "The river flooded the old town overnight, and residents were evacuated to the school gym \
+    ///     while volunteers filled sandbags along the embankment.";
+    /// let (a, b) = (
+    ///     format!("<main><p>{text}</p></main>"),
+    ///     format!("<main><h1>{text}</h1><p>Share this article</p></main>"),
+    /// );
+    /// let a = tl::parse(&a, Default::default()).unwrap();
+    /// let b = tl::parse(&b, Default::default()).unwrap();
+    /// let c = tl::parse("<p>Rust is a language empowering everyone to build reliable software.</p>", Default::default()).unwrap();
+    ///
+    /// let (a, b, c) = (a.text_fingerprint(), b.text_fingerprint(), c.text_fingerprint());
+    /// assert!(a.distance(&b) < a.distance(&c));
+    /// ```
+    pub fn text_fingerprint(&self) -> TextFingerprint {
+        TextHasher::new().hash(self)
+    }
+
+    /// Builds the outline of this document: a tree of its headings, which can be used to generate a table of contents
+    ///
+    /// Headings are `<h1>` to `<h6>` elements and elements with `role="heading"`. Their level comes from a valid
+    /// `aria-level` attribute, or from the element name, and is `2` for other elements with the `heading` role.
+    /// Every heading contains the following headings with a greater level as its children, which approximates the
+    /// outline that browsers and assistive technologies build. Sectioning elements like `<section>` do not change the levels.
+    ///
+    /// Headings inside of other headings, `hidden` elements, `<template>`, `<script>` and `<style>` are ignored,
+    /// and only the first heading with the lowest level of an `<hgroup>` is part of the outline.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"
+    ///     <h1>Guide</h1>
+    ///     <h2>Install</h2>
+    ///     <h3>From   source</h3>
+    ///     <div role="heading" aria-level="2">Usage</div>
+    ///     <h1>Appendix</h1>
+    /// "#, Default::default()).unwrap();
+    ///
+    /// let outline = dom.outline();
+    /// assert_eq!(outline.len(), 2);
+    ///
+    /// let guide = &outline[0];
+    /// assert_eq!(guide.text, "Guide");
+    /// assert_eq!(guide.children[0].children[0].text, "From source");
+    /// assert_eq!(guide.children[1].level, 2);
+    ///
+    /// let toc = guide.iter().map(|heading| heading.text.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(toc, ["Guide", "Install", "From source", "Usage"]);
+    /// ```
+    pub fn outline(&self) -> Vec<Heading> {
+        crate::outline::outline(self)
+    }
+
+    /// Estimates the memory that this document uses, broken down into the nodes, attributes and lookup tables
+    ///
+    /// This helps to choose [`ParserOptions`](crate::ParserOptions) for a workload, like whether the lookup tables
+    /// of [`ParserOptions::track_classes`](crate::ParserOptions::track_classes) are worth their memory.
+    /// See [`MemoryUsage`] for what is counted.
+    ///
+    /// # Example
+    /// ```
+    /// let input = r#"<ul class="list"><li class="item">a</li><li class="item">b</li></ul>"#;
+    ///
+    /// let plain = tl::parse(input, tl::ParserOptions::default()).unwrap().memory_usage();
+    /// assert_eq!(plain.classes, 0);
+    ///
+    /// let tracked = tl::parse(input, tl::ParserOptions::default().track_classes()).unwrap().memory_usage();
+    /// assert!(tracked.classes > 0);
+    /// assert_eq!(tracked.nodes, plain.nodes);
+    /// assert_eq!(tracked.total(), plain.total() + tracked.classes);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        crate::stats::memory_usage(self.parser())
+    }
+
+    /// Returns statistics of this document, like the number of elements per tag name and the maximum depth
+    ///
+    /// To only collect statistics of many documents, use a [`Collector`](crate::stats::Collector),
+    /// which does not keep a DOM around for every document.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul class="list"><li>a</li><li>b</li></ul><!-- end -->"#, Default::default()).unwrap();
+    /// let stats = dom.statistics();
+    ///
+    /// assert_eq!(stats.elements, 3);
+    /// assert_eq!(stats.tags["li"], 2);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!((stats.text_nodes, stats.comments, stats.attributes), (2, 1, 1));
+    /// ```
+    pub fn statistics(&self) -> Statistics {
+        crate::stats::statistics(&self.parser)
+    }
+
+    /// Computes metrics of this document for SEO audits: the number of words, headings, images with and without `alt`,
+    /// internal and external links, and the lengths of the title and the meta description
+    ///
+    /// Links are counted like in [`VDom::link_summary`], so they are internal if they point to the host of the page URL.
+    /// This is unrelated to [`VDom::statistics`], which counts nodes.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<html><head>
+    ///     <title>Fresh  bread</title>
+    ///     <meta name="description" content="Bread baked every morning.">
+    /// </head><body>
+    ///     <h1>Our bread</h1>
+    ///     <p>Baked daily &mdash; <a href="/hours">see hours</a> or <a href="https://maps.example.org">directions</a>.</p>
+    ///     <img src="loaf.jpg"><img src="line.png" alt="">
+    ///     <script>track("visit")</script>
+    /// </body></html>"#, Default::default()).unwrap();
+    ///
+    /// let stats = dom.page_stats(Some("https://bakery.example.com/"));
+    /// assert_eq!(stats.words, 8);
+    /// assert_eq!(stats.headings, [1, 0, 0, 0, 0, 0]);
+    /// assert_eq!((stats.images, stats.images_without_alt), (2, 1));
+    /// assert_eq!((stats.internal_links, stats.external_links), (1, 1));
+    /// assert_eq!(stats.title_length, Some(11));
+    /// assert_eq!(stats.meta_description_length, Some(26));
+    /// ```
+    pub fn page_stats(&self, page_url: Option<&str>) -> PageStats {
+        crate::stats::page_stats(self, page_url)
+    }
+
     /// Tries to parse the query selector and returns an iterator over elements that match the given query selector.
     ///
+    /// # Order
+    /// Like all lookups of elements, the iterator yields the matching nodes in document order, which is the order of
+    /// their handles. Nodes that were inserted after parsing, like with [`VDom::insert_fragment`], come after the
+    /// nodes of the source. The iterator is lazy, so taking only the first match with [`VDom::query_selector_first`]
+    /// or [`QuerySelectorIterator::first`] stops at that match.
+    ///
     /// # Example
     /// ```
     /// let dom = tl::parse("<div><p class=\"foo\">bar</div>", tl::ParserOptions::default()).unwrap();
@@ -177,6 +1464,170 @@ impl<'a> VDom<'a> {
         let iter = queryselector::QuerySelectorIterator::new(selector, self.parser(), self);
         Some(iter)
     }
+
+    /// Returns the first element in document order that matches the given query selector
+    ///
+    /// No nodes after the first match are checked. Returns `None` if no element matches or the selector is invalid;
+    /// use [`VDom::query_selector`] to tell both cases apart.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<head><title>Page</title></head><body><svg><title>Icon</title></svg></body>", Default::default()).unwrap();
+    ///
+    /// let title = dom.query_selector_first("title").unwrap();
+    /// assert_eq!(title.get(dom.parser()).unwrap().inner_text(dom.parser()), "Page");
+    ///
+    /// assert_eq!(dom.query_selector_first("h1"), None);
+    /// assert_eq!(dom.query_selector_first(">"), None);
+    /// ```
+    pub fn query_selector_first(&self, selector: &str) -> Option<NodeHandle> {
+        self.query_selector(selector)?.next()
+    }
+
+    /// Like [`VDom::query_selector`], but resolves namespace prefixes like `svg|` in the selector with the given map
+    ///
+    /// `svg|a` only matches `<a>` elements in the SVG namespace, `|a` elements without a namespace and `*|a` or `a`
+    /// elements in any namespace. Attribute selectors like `[xlink|href]` match attributes like `xlink:href`.
+    /// Returns `None` if the selector is invalid or uses a prefix that is not declared.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::queryselector::Namespaces;
+    ///
+    /// let dom = tl::parse(
+    ///     r##"<a href="/">Home</a><svg><a xlink:href="#icon"><title>Icon</title></a></svg>"##,
+    ///     Default::default(),
+    /// )
+    /// .unwrap();
+    /// let namespaces = Namespaces::standard();
+    ///
+    /// assert_eq!(dom.query_selector_ns("a", &namespaces).unwrap().count(), 2);
+    /// assert_eq!(dom.query_selector_ns("svg|a", &namespaces).unwrap().count(), 1);
+    /// assert_eq!(dom.query_selector_ns("html|a", &namespaces).unwrap().count(), 1);
+    /// assert_eq!(dom.query_selector_ns("[xlink|href]", &namespaces).unwrap().count(), 1);
+    /// assert!(dom.query_selector_ns("foo|a", &namespaces).is_none());
+    /// ```
+    pub fn query_selector_ns<'b>(
+        &'b self,
+        selector: &'b str,
+        namespaces: &'b Namespaces,
+    ) -> Option<QuerySelectorIterator<'a, 'b, Self>> {
+        let selector = crate::parse_query_selector_ns(selector, namespaces)?;
+        let iter = queryselector::QuerySelectorIterator::new(selector, self.parser(), self);
+        Some(iter)
+    }
+
+    /// Parses an untrusted query selector with [`parse_query_selector_with_limits`](crate::parse_query_selector_with_limits)
+    /// and returns an iterator over the elements that match it
+    ///
+    /// # Errors
+    /// See [`SelectorLimits`] and [`SelectorError`]
+    ///
+    /// # Example
+    /// ```
+    /// use tl::errors::SelectorError;
+    /// use tl::queryselector::SelectorLimits;
+    ///
+    /// let dom = tl::parse("<ul><li>a</li><li>b</li></ul>", Default::default()).unwrap();
+    /// let limits = SelectorLimits::new().max_components(2);
+    ///
+    /// assert_eq!(dom.query_selector_with_limits("ul li", limits).unwrap().count(), 2);
+    /// assert!(matches!(
+    ///     dom.query_selector_with_limits("ul > li:first-of-type", limits),
+    ///     Err(SelectorError::TooManyComponents { max: 2 })
+    /// ));
+    /// ```
+    pub fn query_selector_with_limits<'b>(
+        &'b self,
+        selector: &'b str,
+        limits: SelectorLimits,
+    ) -> Result<QuerySelectorIterator<'a, 'b, Self>, SelectorError> {
+        let selector = crate::parse_query_selector_with_limits(selector, limits)?;
+        let iter = queryselector::QuerySelectorIterator::new(selector, self.parser(), self);
+        Ok(iter)
+    }
+
+    /// Returns an iterator over the nodes that match a custom [`Matcher`], like a closure that takes a node and the parser
+    ///
+    /// The iterator is the same as the one of [`VDom::query_selector`], so its adapters like
+    /// [`QuerySelectorIterator::filter_text`] and [`QuerySelectorIterator::tags`] can be used, and the contents of
+    /// `<template>` elements are skipped. Every node is checked, including text nodes and comments.
+    ///
+    /// # Example
+    /// ```
+    /// use tl::{Node, Parser};
+    ///
+    /// let dom = tl::parse(r#"<p>Call 555-0100</p><p>No number</p><span>555-0199</span>"#, Default::default()).unwrap();
+    ///
+    /// let has_phone_number = |node: &Node<'_>, parser: &Parser<'_>| {
+    ///     node.as_tag().is_some_and(|tag| {
+    ///         tag.inner_text(parser)
+    ///             .split(|c: char| !c.is_ascii_digit() && c != '-')
+    ///             .any(|word| word.len() == 8 && word.as_bytes()[3] == b'-')
+    ///     })
+    /// };
+    ///
+    /// let texts = dom.query_selector_with(has_phone_number).texts().collect::<Vec<_>>();
+    /// assert_eq!(texts, ["Call 555-0100", "555-0199"]);
+    /// ```
+    pub fn query_selector_with<M: Matcher>(
+        &self,
+        matcher: M,
+    ) -> QuerySelectorIterator<'a, '_, Self, M> {
+        QuerySelectorIterator::with_matcher(matcher, self.parser(), self)
+    }
+}
+
+/// A read-only DOM that is shared by reference counting, as returned by [`VDom::into_shared`]
+///
+/// Cloning a `SharedDom` only increments a reference count, and every clone dereferences to the same [`VDom`].
+/// It is guaranteed to be `Send` and `Sync`, so clones can be moved into threads, e.g. to run many selectors
+/// on the same document from a [rayon](https://docs.rs/rayon) pool. The DOM cannot be modified while it is shared;
+/// [`SharedDom::try_unwrap`] returns it once the last clone is gone.
+///
+/// The DOM still borrows its input, so threads that outlive the input need an owned DOM like [`VDomGuard`],
+/// which can be shared with an `Arc` in the same way.
+#[derive(Debug, Clone)]
+pub struct SharedDom<'a> {
+    dom: Arc<VDom<'a>>,
+}
+
+impl<'a> SharedDom<'a> {
+    /// Returns the DOM if this is the only reference to it, or `self` otherwise
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Hello</p>", Default::default()).unwrap().into_shared();
+    /// let copy = dom.clone();
+    ///
+    /// let dom = dom.try_unwrap().unwrap_err();
+    /// drop(copy);
+    /// assert!(dom.try_unwrap().is_ok());
+    /// ```
+    pub fn try_unwrap(self) -> Result<VDom<'a>, Self> {
+        Arc::try_unwrap(self.dom).map_err(|dom| Self { dom })
+    }
+
+    /// Returns the number of references to the DOM, including this one
+    #[inline]
+    pub fn reference_count(&self) -> usize {
+        Arc::strong_count(&self.dom)
+    }
+}
+
+impl<'a> Deref for SharedDom<'a> {
+    type Target = VDom<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.dom
+    }
+}
+
+impl<'a> From<VDom<'a>> for SharedDom<'a> {
+    fn from(dom: VDom<'a>) -> Self {
+        dom.into_shared()
+    }
 }
 
 /// A RAII guarded version of VDom
@@ -199,19 +1650,30 @@ unsafe impl Sync for VDomGuard {}
 impl VDomGuard {
     /// Parses the input string
     pub(crate) fn parse(input: String, options: ParserOptions) -> Result<VDomGuard, ParseError> {
+        Self::try_new(input, |input| {
+            let mut parser = Parser::new(input, options);
+            parser.parse()?;
+            Ok(parser)
+        })
+    }
+
+    /// Builds a DOM that references the given input string using the provided function
+    pub(crate) fn try_new<E, F>(input: String, f: F) -> Result<VDomGuard, E>
+    where
+        F: FnOnce(&'static str) -> Result<Parser<'static>, E>,
+    {
         let input = RawString::new(input);
 
         let ptr = input.as_ptr();
 
         let input_ref: &'static str = unsafe { &*ptr };
 
-        // Parsing will either:
+        // Building the DOM will either:
         // a) succeed, and we return a VDom instance
         //    that, when dropped, will free the input string
-        // b) fail, and we return a ParseError
+        // b) fail, and we return the error
         //    and `RawString`s destructor will run and deallocate the string properly
-        let mut parser = Parser::new(input_ref, options);
-        parser.parse()?;
+        let parser = f(input_ref)?;
 
         Ok(Self {
             _s: input,
@@ -237,6 +1699,104 @@ impl VDomGuard {
     }
 }
 
+/// A RAII guarded version of VDom that references a memory-mapped file
+///
+/// The file is unmapped once this struct goes out of scope.
+/// The only way to construct this is by calling [`parse_file()`](crate::parse_file).
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedVDomGuard {
+    /// Wrapped VDom instance, which is declared first so that it is dropped before the mapping it references
+    dom: VDom<'static>,
+    /// The mapped file that is referenced by self.dom
+    _mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+unsafe impl Send for MappedVDomGuard {}
+#[cfg(feature = "mmap")]
+unsafe impl Sync for MappedVDomGuard {}
+
+#[cfg(feature = "mmap")]
+impl MappedVDomGuard {
+    /// Maps and parses the given file
+    ///
+    /// # Safety
+    /// See [`parse_file()`](crate::parse_file)
+    pub(crate) unsafe fn parse(
+        path: &std::path::Path,
+        options: ParserOptions,
+    ) -> Result<MappedVDomGuard, crate::errors::ReadError> {
+        let file = std::fs::File::open(path)?;
+        let mmap = memmap2::Mmap::map(&file)?;
+
+        // SAFETY: the mapped memory does not move when `mmap` is moved, and is only unmapped
+        // when this guard is dropped, after the DOM that references it
+        let input: &'static [u8] = std::slice::from_raw_parts(mmap.as_ptr(), mmap.len());
+
+        let mut parser = Parser::new_bytes(input, options);
+        parser.parse()?;
+
+        Ok(Self {
+            dom: VDom::from(parser),
+            _mmap: mmap,
+        })
+    }
+
+    /// Returns a reference to the inner DOM.
+    ///
+    /// The lifetime of the returned `VDom` is bound to self so that elements cannot outlive this `MappedVDomGuard` struct.
+    pub fn get_ref<'a>(&'a self) -> &'a VDom<'a> {
+        &self.dom
+    }
+}
+
+/// A RAII guarded version of VDom that references the body of an HTTP response
+///
+/// The body is released once this struct goes out of scope, unless it is still shared elsewhere.
+/// The only way to construct this is by calling [`parse_body()`](crate::parse_body).
+#[cfg(feature = "http")]
+#[derive(Debug)]
+pub struct BodyVDomGuard {
+    /// Wrapped VDom instance, which is declared first so that it is dropped before the body it references
+    dom: VDom<'static>,
+    /// The body that is referenced by self.dom
+    _body: ::bytes::Bytes,
+}
+
+#[cfg(feature = "http")]
+unsafe impl Send for BodyVDomGuard {}
+#[cfg(feature = "http")]
+unsafe impl Sync for BodyVDomGuard {}
+
+#[cfg(feature = "http")]
+impl BodyVDomGuard {
+    /// Parses the given body
+    pub(crate) fn parse(
+        body: ::bytes::Bytes,
+        options: ParserOptions,
+    ) -> Result<BodyVDomGuard, ParseError> {
+        // SAFETY: the data of `Bytes` does not move when it is moved, and is only freed once the last reference is dropped,
+        // which for this guard happens after the DOM that references it
+        let input: &'static [u8] = unsafe { std::slice::from_raw_parts(body.as_ptr(), body.len()) };
+
+        let mut parser = Parser::new_bytes(input, options);
+        parser.parse()?;
+
+        Ok(Self {
+            dom: VDom::from(parser),
+            _body: body,
+        })
+    }
+
+    /// Returns a reference to the inner DOM.
+    ///
+    /// The lifetime of the returned `VDom` is bound to self so that elements cannot outlive this `BodyVDomGuard` struct.
+    pub fn get_ref<'a>(&'a self) -> &'a VDom<'a> {
+        &self.dom
+    }
+}
+
 #[derive(Debug)]
 struct RawString(*mut str);
 
@@ -258,3 +1818,9 @@ impl Drop for RawString {
         };
     }
 }
+
+/// Collects the distinct strings of an iterator, in the order of their first occurrence
+fn distinct<'s>(strings: impl Iterator<Item = &'s str>) -> Vec<&'s str> {
+    let mut seen = HashSet::new();
+    strings.filter(|s| seen.insert(*s)).collect()
+}
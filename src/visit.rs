@@ -0,0 +1,149 @@
+use crate::{Bytes, Doctype, Edge, HTMLTag, Node, NodeHandle, Parser, Traverse};
+
+/// Controls how [`VDom::visit`](crate::VDom::visit) continues after a node was visited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Visit {
+    /// Continues with the subnodes of the visited node, followed by its siblings
+    #[default]
+    Continue,
+    /// Skips all subnodes of the visited node and continues with its siblings
+    ///
+    /// [`DomVisitor::leave_tag`] is still called for the skipped tag.
+    SkipChildren,
+    /// Stops visiting immediately
+    Stop,
+}
+
+/// A visitor for the nodes of a DOM, driven by [`VDom::visit`](crate::VDom::visit)
+///
+/// All methods have a default implementation that does nothing, so only the hooks that are of interest need to be implemented.
+/// Nodes are visited depth-first in document order.
+///
+/// # Example
+/// ```
+/// use tl::{DomVisitor, HTMLTag, NodeHandle, Parser, Visit};
+///
+/// /// Counts the links of a document, ignoring anything inside of `<nav>`
+/// struct LinkCounter(usize);
+///
+/// impl<'a> DomVisitor<'a> for LinkCounter {
+///     fn visit_tag(&mut self, _: NodeHandle, tag: &HTMLTag<'a>, _: &Parser<'a>) -> Visit {
+///         match tag.name().as_bytes() {
+///             b"nav" => Visit::SkipChildren,
+///             b"a" => {
+///                 self.0 += 1;
+///                 Visit::Continue
+///             }
+///             _ => Visit::Continue,
+///         }
+///     }
+/// }
+///
+/// let dom = tl::parse(r#"<nav><a href="/">Home</a></nav><p><a href="/x">x</a></p>"#, Default::default()).unwrap();
+/// let mut counter = LinkCounter(0);
+/// dom.visit(&mut counter);
+/// assert_eq!(counter.0, 1);
+/// ```
+pub trait DomVisitor<'a> {
+    /// Called when entering a tag, before any of its subnodes are visited
+    fn visit_tag(&mut self, handle: NodeHandle, tag: &HTMLTag<'a>, parser: &Parser<'a>) -> Visit {
+        let _ = (handle, tag, parser);
+        Visit::Continue
+    }
+
+    /// Called when leaving a tag, after all of its subnodes were visited
+    fn leave_tag(&mut self, handle: NodeHandle, tag: &HTMLTag<'a>, parser: &Parser<'a>) {
+        let _ = (handle, tag, parser);
+    }
+
+    /// Called for every text node
+    fn visit_text(&mut self, handle: NodeHandle, text: &Bytes<'a>, parser: &Parser<'a>) -> Visit {
+        let _ = (handle, text, parser);
+        Visit::Continue
+    }
+
+    /// Called for every comment
+    fn visit_comment(
+        &mut self,
+        handle: NodeHandle,
+        comment: &Bytes<'a>,
+        parser: &Parser<'a>,
+    ) -> Visit {
+        let _ = (handle, comment, parser);
+        Visit::Continue
+    }
+
+    /// Called for every processing instruction
+    fn visit_processing_instruction(
+        &mut self,
+        handle: NodeHandle,
+        instruction: &Bytes<'a>,
+        parser: &Parser<'a>,
+    ) -> Visit {
+        let _ = (handle, instruction, parser);
+        Visit::Continue
+    }
+
+    /// Called for every CDATA section
+    ///
+    /// The section includes the `<![CDATA[` and `]]>` markers.
+    fn visit_cdata(
+        &mut self,
+        handle: NodeHandle,
+        section: &Bytes<'a>,
+        parser: &Parser<'a>,
+    ) -> Visit {
+        let _ = (handle, section, parser);
+        Visit::Continue
+    }
+
+    /// Called for the document type declaration
+    fn visit_doctype(
+        &mut self,
+        handle: NodeHandle,
+        doctype: &Doctype<'a>,
+        parser: &Parser<'a>,
+    ) -> Visit {
+        let _ = (handle, doctype, parser);
+        Visit::Continue
+    }
+}
+
+/// Drives a visitor over the nodes produced by the given traversal
+pub(crate) fn visit<'a, V>(mut traverse: Traverse<'a, '_>, parser: &Parser<'a>, visitor: &mut V)
+where
+    V: DomVisitor<'a> + ?Sized,
+{
+    while let Some(edge) = traverse.next() {
+        let handle = edge.handle();
+        let Some(node) = handle.get(parser) else {
+            continue;
+        };
+
+        let action = match (edge, node) {
+            (Edge::Open(_), Node::Tag(tag)) => visitor.visit_tag(handle, tag, parser),
+            (Edge::Open(_), Node::Raw(text)) => visitor.visit_text(handle, text, parser),
+            (Edge::Open(_), Node::Comment(comment)) => {
+                visitor.visit_comment(handle, comment, parser)
+            }
+            (Edge::Open(_), Node::ProcessingInstruction(instruction)) => {
+                visitor.visit_processing_instruction(handle, instruction, parser)
+            }
+            (Edge::Open(_), Node::CData(section)) => visitor.visit_cdata(handle, section, parser),
+            (Edge::Open(_), Node::Doctype(doctype)) => {
+                visitor.visit_doctype(handle, doctype, parser)
+            }
+            (Edge::Close(_), Node::Tag(tag)) => {
+                visitor.leave_tag(handle, tag, parser);
+                Visit::Continue
+            }
+            (Edge::Close(_), _) => Visit::Continue,
+        };
+
+        match action {
+            Visit::Continue => {}
+            Visit::SkipChildren => traverse.skip_children(),
+            Visit::Stop => return,
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! A small [`wasm-bindgen`](https://docs.rs/wasm-bindgen) surface for using the parser from JavaScript
+//!
+//! Nodes are referred to by their [`NodeHandle`] indices, which JavaScript sees as plain numbers.
+//! Documents are parsed with the default [`ParserOptions`], which do not build the ID and class lookup tables,
+//! so that the generated module stays small and parsing is as fast as possible.
+//!
+//! # Example
+//! ```js
+//! import { Document } from "tl";
+//!
+//! const doc = new Document('<a href="/a">A</a><a href="/b">B</a>');
+//!
+//! for (const node of doc.querySelectorAll("a[href]")) {
+//!     console.log(doc.attribute(node, "href"), doc.innerText(node));
+//! }
+//!
+//! doc.free();
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{HTMLTag, Node, NodeHandle, ParserOptions, VDom, VDomGuard};
+
+/// The message of the error that is thrown for query selectors that cannot be parsed
+const INVALID_SELECTOR: &str = "invalid query selector";
+
+/// A parsed HTML document
+#[wasm_bindgen]
+pub struct Document {
+    dom: VDomGuard,
+}
+
+impl Document {
+    fn dom(&self) -> &VDom<'_> {
+        self.dom.get_ref()
+    }
+
+    fn node(&self, node: u32) -> Option<&Node<'_>> {
+        NodeHandle::new(node).get(self.dom().parser())
+    }
+
+    fn tag(&self, node: u32) -> Option<&HTMLTag<'_>> {
+        self.node(node)?.as_tag()
+    }
+
+    /// Returns the elements that match the given query selector, or the message of the error to throw
+    fn matches<'s>(
+        &'s self,
+        selector: &'s str,
+    ) -> Result<impl Iterator<Item = u32> + 's, &'static str> {
+        let iter = self
+            .dom()
+            .query_selector(selector)
+            .ok_or(INVALID_SELECTOR)?;
+
+        Ok(iter.map(|handle| handle.get_inner()))
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    /// Parses the given HTML
+    #[wasm_bindgen(constructor)]
+    pub fn new(html: String) -> Result<Document, JsError> {
+        let dom = VDomGuard::parse(html, ParserOptions::default())?;
+        Ok(Self { dom })
+    }
+
+    /// Returns the topmost nodes of the document
+    pub fn children(&self) -> Vec<u32> {
+        self.dom()
+            .children()
+            .iter()
+            .map(NodeHandle::get_inner)
+            .collect()
+    }
+
+    /// Returns the first element that matches the given query selector
+    #[wasm_bindgen(js_name = querySelector)]
+    pub fn query_selector(&self, selector: &str) -> Result<Option<u32>, JsError> {
+        let mut iter = self.matches(selector).map_err(JsError::new)?;
+        Ok(iter.next())
+    }
+
+    /// Returns all elements that match the given query selector, in document order
+    #[wasm_bindgen(js_name = querySelectorAll)]
+    pub fn query_selector_all(&self, selector: &str) -> Result<Vec<u32>, JsError> {
+        let iter = self.matches(selector).map_err(JsError::new)?;
+        Ok(iter.collect())
+    }
+
+    /// Returns the text of a node and all of its subnodes
+    #[wasm_bindgen(js_name = innerText)]
+    pub fn inner_text(&self, node: u32) -> Option<String> {
+        let parser = self.dom().parser();
+        Some(self.node(node)?.inner_text(parser).into_owned())
+    }
+
+    /// Returns the markup of the subnodes of a node
+    #[wasm_bindgen(js_name = innerHTML)]
+    pub fn inner_html(&self, node: u32) -> Option<String> {
+        let parser = self.dom().parser();
+        Some(self.node(node)?.inner_html(parser).into_owned())
+    }
+
+    /// Returns the markup of a node, including the node itself
+    #[wasm_bindgen(js_name = outerHTML)]
+    pub fn outer_html(&self, node: u32) -> Option<String> {
+        let parser = self.dom().parser();
+        Some(self.node(node)?.outer_html(parser).into_owned())
+    }
+
+    /// Returns the name of an element, or `undefined` for text and comment nodes
+    #[wasm_bindgen(js_name = tagName)]
+    pub fn tag_name(&self, node: u32) -> Option<String> {
+        Some(self.tag(node)?.name().as_utf8_str().into_owned())
+    }
+
+    /// Returns the value of an attribute of an element
+    ///
+    /// Attributes without a value, like `disabled`, return an empty string.
+    pub fn attribute(&self, node: u32, name: &str) -> Option<String> {
+        let value = self.tag(node)?.attributes().get(name)?;
+        Some(
+            value
+                .map(|v| v.as_utf8_str().into_owned())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns the names of all attributes of an element, sorted by name
+    #[wasm_bindgen(js_name = attributeNames)]
+    pub fn attribute_names(&self, node: u32) -> Vec<String> {
+        let Some(tag) = self.tag(node) else {
+            return Vec::new();
+        };
+
+        let mut names = tag
+            .attributes()
+            .iter()
+            .map(|(name, _)| name.into_owned())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the direct subnodes of an element
+    #[wasm_bindgen(js_name = childNodes)]
+    pub fn child_nodes(&self, node: u32) -> Vec<u32> {
+        self.tag(node)
+            .map(|tag| {
+                tag.children()
+                    .top()
+                    .iter()
+                    .map(NodeHandle::get_inner)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the parent of a node, or `undefined` for the topmost nodes
+    #[wasm_bindgen(js_name = parentNode)]
+    pub fn parent_node(&self, node: u32) -> Option<u32> {
+        let parser = self.dom().parser();
+        parser
+            .parent_of(NodeHandle::new(node))
+            .map(|parent| parent.get_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `JsError` can only be created on wasm targets, so these tests only call methods that do not fail
+
+    fn document(html: &str) -> Document {
+        Document::new(html.to_owned()).ok().unwrap()
+    }
+
+    #[test]
+    fn children_are_handle_indices() {
+        let doc = document("<p>a</p>b<i></i>");
+
+        assert_eq!(doc.children(), [0, 2, 3]);
+        assert_eq!(doc.child_nodes(0), [1]);
+    }
+
+    #[test]
+    fn matches_in_document_order() {
+        let doc = document(r#"<a href="/a">A</a><p><a href="/b">B</a></p><a>C</a>"#);
+
+        assert_eq!(doc.matches("a[href]").unwrap().collect::<Vec<_>>(), [0, 3]);
+        assert_eq!(doc.matches("p").unwrap().next(), Some(2));
+        assert_eq!(doc.matches("ul").unwrap().next(), None);
+    }
+
+    #[test]
+    fn invalid_selector_is_an_error() {
+        let doc = document("<p></p>");
+
+        assert_eq!(doc.matches("li:nth-child(0)").err(), Some(INVALID_SELECTOR));
+        assert_eq!(doc.matches(r#"[data-x="a]"#).err(), Some(INVALID_SELECTOR));
+    }
+
+    #[test]
+    fn text_and_markup() {
+        let doc = document("<p>a <b>b</b></p>");
+
+        assert_eq!(doc.inner_text(0).as_deref(), Some("a b"));
+        assert_eq!(doc.inner_html(0).as_deref(), Some("a <b>b</b>"));
+        assert_eq!(doc.outer_html(2).as_deref(), Some("<b>b</b>"));
+        assert_eq!(doc.outer_html(1).as_deref(), Some("a "));
+    }
+
+    #[test]
+    fn tag_name_of_elements_only() {
+        let doc = document("<DIV>a</DIV>");
+
+        assert_eq!(doc.tag_name(0).as_deref(), Some("div"));
+        assert_eq!(doc.tag_name(1), None);
+    }
+
+    #[test]
+    fn attribute_values() {
+        let doc = document(r#"<input value="x" disabled>"#);
+
+        assert_eq!(doc.attribute(0, "value").as_deref(), Some("x"));
+        assert_eq!(doc.attribute(0, "disabled").as_deref(), Some(""));
+        assert_eq!(doc.attribute(0, "name"), None);
+    }
+
+    #[test]
+    fn attribute_names_are_sorted() {
+        let doc = document(r#"<a title="t" href="/" class="c">a</a>"#);
+
+        assert_eq!(doc.attribute_names(0), ["class", "href", "title"]);
+        assert!(doc.attribute_names(1).is_empty());
+    }
+
+    #[test]
+    fn parent_nodes() {
+        let doc = document("<ul><li>a</li></ul>");
+
+        assert_eq!(doc.parent_node(2), Some(1));
+        assert_eq!(doc.parent_node(1), Some(0));
+        assert_eq!(doc.parent_node(0), None);
+    }
+
+    #[test]
+    fn unknown_nodes() {
+        let doc = document("<p>a</p>");
+
+        assert_eq!(doc.inner_text(5), None);
+        assert_eq!(doc.tag_name(5), None);
+        assert_eq!(doc.attribute(5, "id"), None);
+        assert!(doc.attribute_names(5).is_empty());
+        assert!(doc.child_nodes(5).is_empty());
+        assert_eq!(doc.parent_node(5), None);
+    }
+}
@@ -0,0 +1,1229 @@
+//! Supports a practical subset of XPath 1.0:
+//!
+//! - Absolute and relative location paths, including `//`, `.`, `..` and `@name`
+//! - The axes `child`, `descendant`, `descendant-or-self`, `self`, `parent`, `ancestor`, `ancestor-or-self`,
+//!   `following-sibling`, `preceding-sibling` and `attribute`
+//! - The node tests `name`, `*`, `text()`, `comment()` and `node()`. Names are compared case insensitively, as in HTML.
+//! - Predicates, including positional ones like `[1]` and `[last()]`
+//! - The operators `or`, `and`, `=`, `!=`, `<`, `<=`, `>`, `>=`, `+`, `-`, `*`, `div`, `mod` and `|`
+//! - The functions `last()`, `position()`, `count()`, `contains()`, `starts-with()`, `ends-with()`, `normalize-space()`,
+//!   `string()`, `string-length()`, `concat()`, `translate()`, `not()`, `true()`, `false()`, `boolean()`, `number()`,
+//!   `name()` and `local-name()`
+//!
+//! Variables, namespaces and the `following`, `preceding` and `namespace` axes are not supported.
+//!
+//! # Example
+//! ```
+//! let dom = tl::parse(r#"
+//!     <ul>
+//!         <li><a href="/a">Read more</a></li>
+//!         <li class="ad"><a href="/b">Sponsored</a></li>
+//!         <li><a href="/c">Read  more</a></li>
+//!     </ul>
+//! "#, Default::default()).unwrap();
+//!
+//! let links = dom
+//!     .xpath(r#"//li[not(@class='ad')]/a[contains(normalize-space(.), 'Read more')]/@href"#)
+//!     .unwrap()
+//!     .iter()
+//!     .map(|item| item.string_value(&dom))
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(links, ["/a", "/c"]);
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::errors::XPathError;
+use crate::{Node, NodeHandle, VDom};
+
+/// A node that an XPath expression can select
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Item {
+    /// The document itself, which is the parent of the topmost nodes. This is what `/` selects.
+    Document,
+    /// An element, text or comment node
+    Node(NodeHandle),
+    /// An attribute of an element
+    Attribute {
+        /// The element the attribute belongs to
+        element: NodeHandle,
+        /// The name of the attribute
+        name: String,
+        /// The value of the attribute, or an empty string if it has no value
+        value: String,
+    },
+}
+
+impl Item {
+    /// Returns the handle of an element, text or comment node
+    pub fn handle(&self) -> Option<NodeHandle> {
+        match self {
+            Item::Node(handle) => Some(*handle),
+            _ => None,
+        }
+    }
+
+    /// Returns the string value of this item: the text of the document or an element, the content of a text node or comment,
+    /// or the value of an attribute
+    pub fn string_value(&self, dom: &VDom<'_>) -> String {
+        let parser = dom.parser();
+
+        match self {
+            Item::Document => dom
+                .children()
+                .iter()
+                .filter_map(|handle| handle.get(parser))
+                .map(|node| node.inner_text(parser))
+                .collect(),
+            Item::Node(handle) => match handle.get(parser) {
+                Some(Node::Comment(comment)) => {
+                    let comment = comment.as_utf8_str();
+                    let comment = comment.strip_prefix("<!--").unwrap_or(&comment);
+                    comment.strip_suffix("-->").unwrap_or(comment).to_owned()
+                }
+                Some(node) => node.inner_text(parser).into_owned(),
+                None => String::new(),
+            },
+            Item::Attribute { value, .. } => value.clone(),
+        }
+    }
+
+    /// Returns the position of this item in document order: the document comes first,
+    /// and attributes come after their element and before its children
+    fn order(&self) -> (u64, u8, &str) {
+        match self {
+            Item::Document => (0, 0, ""),
+            Item::Node(handle) => (u64::from(handle.document_order()) + 1, 0, ""),
+            Item::Attribute { element, name, .. } => {
+                (u64::from(element.document_order()) + 1, 1, name)
+            }
+        }
+    }
+}
+
+/// The result of evaluating an XPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A set of nodes in document order
+    Nodes(Vec<Item>),
+    /// A string
+    String(String),
+    /// A number
+    Number(f64),
+    /// A boolean
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    At,
+    Comma,
+    Pipe,
+    Dot,
+    DotDot,
+    ColonColon,
+    Star,
+    Multiply,
+    Plus,
+    Minus,
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Div,
+    Mod,
+    Literal(String),
+    Number(f64),
+    Name(String),
+}
+
+impl Token {
+    /// Checks whether a `*` or a name after this token is an operator rather than a node test
+    fn precedes_operator(&self) -> bool {
+        !matches!(
+            self,
+            Token::At
+                | Token::ColonColon
+                | Token::LParen
+                | Token::LBracket
+                | Token::Comma
+                | Token::Slash
+                | Token::DoubleSlash
+                | Token::Pipe
+                | Token::Multiply
+                | Token::Plus
+                | Token::Minus
+                | Token::Eq
+                | Token::Neq
+                | Token::Lt
+                | Token::Le
+                | Token::Gt
+                | Token::Ge
+                | Token::And
+                | Token::Or
+                | Token::Div
+                | Token::Mod
+        )
+    }
+}
+
+fn is_name_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.')
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, XPathError> {
+    let bytes = input.as_bytes();
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let start = idx;
+        let c = bytes[idx];
+        let next = bytes.get(idx + 1).copied();
+        let operator_context = tokens
+            .last()
+            .is_some_and(|(token, _)| token.precedes_operator());
+
+        let (token, len) = match c {
+            _ if c.is_ascii_whitespace() => {
+                idx += 1;
+                continue;
+            }
+            b'/' if next == Some(b'/') => (Token::DoubleSlash, 2),
+            b'/' => (Token::Slash, 1),
+            b'[' => (Token::LBracket, 1),
+            b']' => (Token::RBracket, 1),
+            b'(' => (Token::LParen, 1),
+            b')' => (Token::RParen, 1),
+            b'@' => (Token::At, 1),
+            b',' => (Token::Comma, 1),
+            b'|' => (Token::Pipe, 1),
+            b'+' => (Token::Plus, 1),
+            b'-' => (Token::Minus, 1),
+            b'=' => (Token::Eq, 1),
+            b'!' if next == Some(b'=') => (Token::Neq, 2),
+            b'<' if next == Some(b'=') => (Token::Le, 2),
+            b'<' => (Token::Lt, 1),
+            b'>' if next == Some(b'=') => (Token::Ge, 2),
+            b'>' => (Token::Gt, 1),
+            b':' if next == Some(b':') => (Token::ColonColon, 2),
+            b'*' if operator_context => (Token::Multiply, 1),
+            b'*' => (Token::Star, 1),
+            b'.' if next == Some(b'.') => (Token::DotDot, 2),
+            b'.' if !next.is_some_and(|c| c.is_ascii_digit()) => (Token::Dot, 1),
+            b'"' | b'\'' => {
+                let end = input[start + 1..]
+                    .find(c as char)
+                    .ok_or(XPathError::UnexpectedEnd)?;
+                let literal = &input[start + 1..start + 1 + end];
+                (Token::Literal(literal.to_owned()), end + 2)
+            }
+            b'.' | b'0'..=b'9' => {
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                    .count();
+                let number = input[start..start + len]
+                    .parse()
+                    .map_err(|_| XPathError::UnexpectedToken(start))?;
+                (Token::Number(number), len)
+            }
+            _ if c.is_ascii_alphabetic() || c == b'_' => {
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|c| is_name_char(**c))
+                    .count();
+                let name = &input[start..start + len];
+
+                let token = match name {
+                    "and" if operator_context => Token::And,
+                    "or" if operator_context => Token::Or,
+                    "div" if operator_context => Token::Div,
+                    "mod" if operator_context => Token::Mod,
+                    _ => Token::Name(name.to_owned()),
+                };
+                (token, len)
+            }
+            _ => return Err(XPathError::UnexpectedToken(start)),
+        };
+
+        tokens.push((token, start));
+        idx += len;
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Itself,
+    Parent,
+    Ancestor,
+    AncestorOrSelf,
+    FollowingSibling,
+    PrecedingSibling,
+    Attribute,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "child" => Some(Self::Child),
+            "descendant" => Some(Self::Descendant),
+            "descendant-or-self" => Some(Self::DescendantOrSelf),
+            "self" => Some(Self::Itself),
+            "parent" => Some(Self::Parent),
+            "ancestor" => Some(Self::Ancestor),
+            "ancestor-or-self" => Some(Self::AncestorOrSelf),
+            "following-sibling" => Some(Self::FollowingSibling),
+            "preceding-sibling" => Some(Self::PrecedingSibling),
+            "attribute" => Some(Self::Attribute),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeTest {
+    Name(String),
+    Any,
+    Text,
+    Comment,
+    Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    axis: Axis,
+    test: NodeTest,
+    predicates: Vec<Expr>,
+}
+
+impl Step {
+    /// The step that `//` stands for
+    fn descendant_or_self() -> Self {
+        Self {
+            axis: Axis::DescendantOrSelf,
+            test: NodeTest::Node,
+            predicates: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Union,
+}
+
+impl BinaryOp {
+    /// Returns the operator that gives the same result with swapped operands
+    fn swapped(self) -> Self {
+        match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            op => op,
+        }
+    }
+}
+
+/// Functions with their minimum and maximum number of arguments
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("last", 0, 0),
+    ("position", 0, 0),
+    ("count", 1, 1),
+    ("contains", 2, 2),
+    ("starts-with", 2, 2),
+    ("ends-with", 2, 2),
+    ("normalize-space", 0, 1),
+    ("string", 0, 1),
+    ("string-length", 0, 1),
+    ("concat", 2, usize::MAX),
+    ("translate", 3, 3),
+    ("not", 1, 1),
+    ("true", 0, 0),
+    ("false", 0, 0),
+    ("boolean", 1, 1),
+    ("number", 0, 1),
+    ("name", 0, 1),
+    ("local-name", 0, 1),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    Path {
+        absolute: bool,
+        steps: Vec<Step>,
+    },
+    Filter {
+        primary: Box<Expr>,
+        predicates: Vec<Expr>,
+        steps: Vec<Step>,
+    },
+    Literal(String),
+    Number(f64),
+    Call(&'static str, Vec<Expr>),
+}
+
+struct ExprParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, XPathError> {
+        let (token, _) = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(XPathError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self) -> XPathError {
+        match self.tokens.get(self.pos) {
+            Some((_, offset)) => XPathError::UnexpectedToken(*offset),
+            None => XPathError::UnexpectedEnd,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), XPathError> {
+        if self.peek() == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    /// Parses a left associative chain of binary operators, where `operand` parses the operands
+    fn binary(
+        &mut self,
+        operators: &[(Token, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr, XPathError>,
+    ) -> Result<Expr, XPathError> {
+        let mut lhs = operand(self)?;
+
+        while let Some(&(_, op)) = operators
+            .iter()
+            .find(|(token, _)| self.peek() == Some(token))
+        {
+            self.pos += 1;
+            let rhs = operand(self)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn expr(&mut self) -> Result<Expr, XPathError> {
+        self.binary(&[(Token::Or, BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, XPathError> {
+        self.binary(&[(Token::And, BinaryOp::And)], Self::equality)
+    }
+
+    fn equality(&mut self) -> Result<Expr, XPathError> {
+        self.binary(
+            &[(Token::Eq, BinaryOp::Eq), (Token::Neq, BinaryOp::Neq)],
+            Self::relational,
+        )
+    }
+
+    fn relational(&mut self) -> Result<Expr, XPathError> {
+        self.binary(
+            &[
+                (Token::Le, BinaryOp::Le),
+                (Token::Lt, BinaryOp::Lt),
+                (Token::Ge, BinaryOp::Ge),
+                (Token::Gt, BinaryOp::Gt),
+            ],
+            Self::additive,
+        )
+    }
+
+    fn additive(&mut self) -> Result<Expr, XPathError> {
+        self.binary(
+            &[(Token::Plus, BinaryOp::Add), (Token::Minus, BinaryOp::Sub)],
+            Self::multiplicative,
+        )
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, XPathError> {
+        self.binary(
+            &[
+                (Token::Multiply, BinaryOp::Mul),
+                (Token::Div, BinaryOp::Div),
+                (Token::Mod, BinaryOp::Mod),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr, XPathError> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+
+        self.binary(&[(Token::Pipe, BinaryOp::Union)], Self::path)
+    }
+
+    fn path(&mut self) -> Result<Expr, XPathError> {
+        let is_filter = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Literal(_) | Token::Number(_) | Token::LParen), _) => true,
+            (Some(Token::Name(name)), Some(Token::LParen)) => !is_node_type(name),
+            _ => false,
+        };
+
+        if is_filter {
+            let primary = self.primary()?;
+            let predicates = self.predicates()?;
+            let steps = match self.peek() {
+                Some(Token::Slash | Token::DoubleSlash) => self.relative_path()?,
+                _ => Vec::new(),
+            };
+
+            return Ok(if predicates.is_empty() && steps.is_empty() {
+                primary
+            } else {
+                Expr::Filter {
+                    primary: Box::new(primary),
+                    predicates,
+                    steps,
+                }
+            });
+        }
+
+        match self.peek() {
+            Some(Token::Slash) => {
+                self.pos += 1;
+                let steps = match self.peek() {
+                    Some(Token::Name(_) | Token::Star | Token::At | Token::Dot | Token::DotDot) => {
+                        self.steps()?
+                    }
+                    _ => Vec::new(),
+                };
+                Ok(Expr::Path {
+                    absolute: true,
+                    steps,
+                })
+            }
+            Some(Token::DoubleSlash) => Ok(Expr::Path {
+                absolute: true,
+                steps: self.relative_path()?,
+            }),
+            _ => Ok(Expr::Path {
+                absolute: false,
+                steps: self.steps()?,
+            }),
+        }
+    }
+
+    /// Parses steps that start with `/` or `//`
+    fn relative_path(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(Token::Slash) => self.pos += 1,
+                Some(Token::DoubleSlash) => {
+                    self.pos += 1;
+                    steps.push(Step::descendant_or_self());
+                }
+                _ => return Ok(steps),
+            }
+
+            steps.push(self.step()?);
+        }
+    }
+
+    /// Parses steps that start with a step
+    fn steps(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = vec![self.step()?];
+        steps.extend(self.relative_path()?);
+        Ok(steps)
+    }
+
+    fn step(&mut self) -> Result<Step, XPathError> {
+        if self.eat(&Token::Dot) {
+            return Ok(Step {
+                axis: Axis::Itself,
+                test: NodeTest::Node,
+                predicates: Vec::new(),
+            });
+        }
+
+        if self.eat(&Token::DotDot) {
+            return Ok(Step {
+                axis: Axis::Parent,
+                test: NodeTest::Node,
+                predicates: Vec::new(),
+            });
+        }
+
+        let axis = if self.eat(&Token::At) {
+            Axis::Attribute
+        } else if let (Some(Token::Name(name)), Some(Token::ColonColon)) =
+            (self.peek(), self.peek_at(1))
+        {
+            let axis =
+                Axis::from_name(name).ok_or_else(|| XPathError::UnknownAxis(name.clone()))?;
+            self.pos += 2;
+            axis
+        } else {
+            Axis::Child
+        };
+
+        let test = match self.next()? {
+            Token::Star => NodeTest::Any,
+            Token::Name(name) if is_node_type(&name) && self.peek() == Some(&Token::LParen) => {
+                self.expect(Token::LParen)?;
+                self.expect(Token::RParen)?;
+
+                match name.as_str() {
+                    "text" => NodeTest::Text,
+                    "comment" => NodeTest::Comment,
+                    _ => NodeTest::Node,
+                }
+            }
+            Token::Name(name) => NodeTest::Name(name),
+            _ => {
+                self.pos -= 1;
+                return Err(self.unexpected());
+            }
+        };
+
+        Ok(Step {
+            axis,
+            test,
+            predicates: self.predicates()?,
+        })
+    }
+
+    fn predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+        let mut predicates = Vec::new();
+
+        while self.eat(&Token::LBracket) {
+            predicates.push(self.expr()?);
+            self.expect(Token::RBracket)?;
+        }
+
+        Ok(predicates)
+    }
+
+    fn primary(&mut self) -> Result<Expr, XPathError> {
+        match self.next()? {
+            Token::Literal(literal) => Ok(Expr::Literal(literal)),
+            Token::Number(number) => Ok(Expr::Number(number)),
+            Token::LParen => {
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Name(name) => {
+                let &(name, min, max) = FUNCTIONS
+                    .iter()
+                    .find(|(n, _, _)| *n == name)
+                    .ok_or(XPathError::UnknownFunction(name))?;
+
+                self.expect(Token::LParen)?;
+                let mut args = Vec::new();
+
+                if !self.eat(&Token::RParen) {
+                    loop {
+                        args.push(self.expr()?);
+
+                        match self.next()? {
+                            Token::Comma => {}
+                            Token::RParen => break,
+                            _ => {
+                                self.pos -= 1;
+                                return Err(self.unexpected());
+                            }
+                        }
+                    }
+                }
+
+                if args.len() < min || args.len() > max {
+                    return Err(XPathError::InvalidArguments(name.to_owned()));
+                }
+
+                Ok(Expr::Call(name, args))
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+fn is_node_type(name: &str) -> bool {
+    matches!(name, "text" | "comment" | "node")
+}
+
+/// A parsed XPath expression
+///
+/// See the [module documentation](self) for the supported syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPath {
+    expr: Expr,
+}
+
+impl XPath {
+    /// Parses an XPath expression
+    ///
+    /// # Errors
+    /// Returns an error if the expression is invalid or uses unsupported features.
+    pub fn parse(input: &str) -> Result<Self, XPathError> {
+        let mut parser = ExprParser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+
+        let expr = parser.expr()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(parser.unexpected());
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Evaluates this expression with the document as the context node
+    ///
+    /// # Example
+    /// ```
+    /// use tl::xpath::{Value, XPath};
+    ///
+    /// let dom = tl::parse("<p>a</p><p>b</p>", Default::default()).unwrap();
+    /// let xpath = XPath::parse("count(//p) * 10").unwrap();
+    ///
+    /// assert_eq!(xpath.evaluate(&dom), Value::Number(20.0));
+    /// ```
+    pub fn evaluate(&self, dom: &VDom<'_>) -> Value {
+        self.evaluate_from(dom, Item::Document)
+    }
+
+    /// Evaluates this expression with the given item as the context node
+    pub fn evaluate_from(&self, dom: &VDom<'_>, context: Item) -> Value {
+        Evaluator { dom }.eval(
+            &self.expr,
+            &Context {
+                item: &context,
+                position: 1,
+                size: 1,
+            },
+        )
+    }
+
+    /// Evaluates this expression with the document as the context node and returns the selected nodes in document order.
+    /// Returns an empty list if the expression does not evaluate to a set of nodes.
+    pub fn select(&self, dom: &VDom<'_>) -> Vec<Item> {
+        match self.evaluate(dom) {
+            Value::Nodes(items) => items,
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct Context<'c> {
+    item: &'c Item,
+    position: usize,
+    size: usize,
+}
+
+struct Evaluator<'d, 'a> {
+    dom: &'d VDom<'a>,
+}
+
+impl Evaluator<'_, '_> {
+    fn eval(&self, expr: &Expr, ctx: &Context<'_>) -> Value {
+        match expr {
+            Expr::Literal(literal) => Value::String(literal.clone()),
+            Expr::Number(number) => Value::Number(*number),
+            Expr::Negate(expr) => Value::Number(-self.number(self.eval(expr, ctx))),
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => Value::Boolean(
+                self.boolean(self.eval(lhs, ctx)) || self.boolean(self.eval(rhs, ctx)),
+            ),
+            Expr::Binary(BinaryOp::And, lhs, rhs) => Value::Boolean(
+                self.boolean(self.eval(lhs, ctx)) && self.boolean(self.eval(rhs, ctx)),
+            ),
+            Expr::Binary(BinaryOp::Union, lhs, rhs) => {
+                let mut items = self.nodes(self.eval(lhs, ctx));
+                items.extend(self.nodes(self.eval(rhs, ctx)));
+                Value::Nodes(sorted(items))
+            }
+            Expr::Binary(
+                op @ (BinaryOp::Eq
+                | BinaryOp::Neq
+                | BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge),
+                lhs,
+                rhs,
+            ) => Value::Boolean(self.compare(*op, self.eval(lhs, ctx), self.eval(rhs, ctx))),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.number(self.eval(lhs, ctx));
+                let rhs = self.number(self.eval(rhs, ctx));
+
+                Value::Number(match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    _ => lhs % rhs,
+                })
+            }
+            Expr::Path { absolute, steps } => {
+                let start = match absolute {
+                    true => Item::Document,
+                    false => ctx.item.clone(),
+                };
+                Value::Nodes(self.steps(vec![start], steps))
+            }
+            Expr::Filter {
+                primary,
+                predicates,
+                steps,
+            } => {
+                let mut items = self.nodes(self.eval(primary, ctx));
+                for predicate in predicates {
+                    items = self.filter(items, predicate);
+                }
+                Value::Nodes(self.steps(items, steps))
+            }
+            Expr::Call(name, args) => self.call(name, args, ctx),
+        }
+    }
+
+    fn steps(&self, mut items: Vec<Item>, steps: &[Step]) -> Vec<Item> {
+        for step in steps {
+            let mut next = Vec::new();
+
+            for item in &items {
+                let mut candidates = self
+                    .axis(item, step.axis)
+                    .into_iter()
+                    .filter(|candidate| self.test(candidate, step))
+                    .collect::<Vec<_>>();
+
+                for predicate in &step.predicates {
+                    candidates = self.filter(candidates, predicate);
+                }
+
+                next.extend(candidates);
+            }
+
+            items = sorted(next);
+        }
+
+        items
+    }
+
+    /// Keeps the items for which the predicate is true, where a number is compared to the position of the item
+    fn filter(&self, items: Vec<Item>, predicate: &Expr) -> Vec<Item> {
+        let size = items.len();
+
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, item)| {
+                let ctx = Context {
+                    item,
+                    position: idx + 1,
+                    size,
+                };
+
+                match self.eval(predicate, &ctx) {
+                    Value::Number(number) => number == (idx + 1) as f64,
+                    value => self.boolean(value),
+                }
+            })
+            .map(|(_, item)| item)
+            .collect()
+    }
+
+    fn children(&self, item: &Item) -> Vec<Item> {
+        let parser = self.dom.parser();
+
+        let handles = match item {
+            Item::Document => self.dom.children(),
+            Item::Node(handle) => match handle.get(parser) {
+                Some(Node::Tag(tag)) => {
+                    return tag
+                        .children()
+                        .top()
+                        .iter()
+                        .copied()
+                        .map(Item::Node)
+                        .collect()
+                }
+                _ => &[],
+            },
+            Item::Attribute { .. } => &[],
+        };
+
+        handles.iter().copied().map(Item::Node).collect()
+    }
+
+    fn descendants(&self, item: &Item, out: &mut Vec<Item>) {
+        for child in self.children(item) {
+            out.push(child.clone());
+            self.descendants(&child, out);
+        }
+    }
+
+    fn parent(&self, item: &Item) -> Option<Item> {
+        match item {
+            Item::Document => None,
+            Item::Node(handle) => Some(
+                self.dom
+                    .parser()
+                    .parent_of(*handle)
+                    .map_or(Item::Document, Item::Node),
+            ),
+            Item::Attribute { element, .. } => Some(Item::Node(*element)),
+        }
+    }
+
+    /// Returns the items of an axis in axis order, which is reverse document order for reverse axes
+    fn axis(&self, item: &Item, axis: Axis) -> Vec<Item> {
+        let parser = self.dom.parser();
+
+        match axis {
+            Axis::Child => self.children(item),
+            Axis::Descendant | Axis::DescendantOrSelf => {
+                let mut out = Vec::new();
+                if axis == Axis::DescendantOrSelf {
+                    out.push(item.clone());
+                }
+                self.descendants(item, &mut out);
+                out
+            }
+            Axis::Itself => vec![item.clone()],
+            Axis::Parent => self.parent(item).into_iter().collect(),
+            Axis::Ancestor | Axis::AncestorOrSelf => {
+                let mut out = Vec::new();
+                if axis == Axis::AncestorOrSelf {
+                    out.push(item.clone());
+                }
+
+                let mut current = self.parent(item);
+                while let Some(parent) = current {
+                    current = self.parent(&parent);
+                    out.push(parent);
+                }
+                out
+            }
+            Axis::FollowingSibling | Axis::PrecedingSibling => {
+                let Item::Node(handle) = item else {
+                    return Vec::new();
+                };
+
+                let siblings = parser.siblings_of(*handle);
+                let Some(idx) = siblings
+                    .iter()
+                    .position(|h| h.get_inner() == handle.get_inner())
+                else {
+                    return Vec::new();
+                };
+
+                if axis == Axis::FollowingSibling {
+                    siblings[idx + 1..]
+                        .iter()
+                        .copied()
+                        .map(Item::Node)
+                        .collect()
+                } else {
+                    siblings[..idx]
+                        .iter()
+                        .rev()
+                        .copied()
+                        .map(Item::Node)
+                        .collect()
+                }
+            }
+            Axis::Attribute => {
+                let Some(element) = item.handle() else {
+                    return Vec::new();
+                };
+                let Some(Node::Tag(tag)) = element.get(parser) else {
+                    return Vec::new();
+                };
+
+                let mut attributes = tag
+                    .attributes()
+                    .iter()
+                    .map(|(name, value)| Item::Attribute {
+                        element,
+                        name: name.into_owned(),
+                        value: value.map(|v| v.into_owned()).unwrap_or_default(),
+                    })
+                    .collect::<Vec<_>>();
+                attributes.sort_unstable_by(|a, b| a.order().cmp(&b.order()));
+                attributes
+            }
+        }
+    }
+
+    fn test(&self, item: &Item, step: &Step) -> bool {
+        let node = item.handle().and_then(|h| h.get(self.dom.parser()));
+
+        match (&step.test, item) {
+            (NodeTest::Node, _) => true,
+            // the principal node type of the attribute axis is attribute, and of all others element
+            (NodeTest::Any, Item::Attribute { .. }) => step.axis == Axis::Attribute,
+            (NodeTest::Name(test), Item::Attribute { name, .. }) => {
+                step.axis == Axis::Attribute && test.eq_ignore_ascii_case(name)
+            }
+            (NodeTest::Any, _) => matches!(node, Some(Node::Tag(_))),
+            (NodeTest::Name(test), _) => match node {
+                Some(Node::Tag(tag)) => tag.name().as_bytes().eq_ignore_ascii_case(test.as_bytes()),
+                _ => false,
+            },
+            (NodeTest::Text, _) => matches!(node, Some(Node::Raw(_) | Node::CData(_))),
+            (NodeTest::Comment, _) => matches!(node, Some(Node::Comment(_))),
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Expr], ctx: &Context<'_>) -> Value {
+        let arg = |idx: usize| self.eval(&args[idx], ctx);
+        let string_arg = |idx: usize| match args.get(idx) {
+            Some(expr) => self.string(self.eval(expr, ctx)),
+            None => ctx.item.string_value(self.dom),
+        };
+        // the first item of a node set argument, or the context item
+        let item_arg = || match args.first() {
+            Some(expr) => self.nodes(self.eval(expr, ctx)).into_iter().next(),
+            None => Some(ctx.item.clone()),
+        };
+
+        match name {
+            "last" => Value::Number(ctx.size as f64),
+            "position" => Value::Number(ctx.position as f64),
+            "count" => Value::Number(self.nodes(arg(0)).len() as f64),
+            "contains" => Value::Boolean(string_arg(0).contains(&string_arg(1))),
+            "starts-with" => Value::Boolean(string_arg(0).starts_with(&string_arg(1))),
+            "ends-with" => Value::Boolean(string_arg(0).ends_with(&string_arg(1))),
+            "normalize-space" => Value::String(
+                string_arg(0)
+                    .split_ascii_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            "string" => Value::String(string_arg(0)),
+            "string-length" => Value::Number(string_arg(0).chars().count() as f64),
+            "concat" => Value::String((0..args.len()).map(string_arg).collect()),
+            "translate" => {
+                let from = string_arg(1).chars().collect::<Vec<_>>();
+                let to = string_arg(2).chars().collect::<Vec<_>>();
+
+                Value::String(
+                    string_arg(0)
+                        .chars()
+                        .filter_map(|c| match from.iter().position(|&f| f == c) {
+                            Some(idx) => to.get(idx).copied(),
+                            None => Some(c),
+                        })
+                        .collect(),
+                )
+            }
+            "not" => Value::Boolean(!self.boolean(arg(0))),
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            "boolean" => Value::Boolean(self.boolean(arg(0))),
+            "number" => Value::Number(match args.first() {
+                Some(_) => self.number(arg(0)),
+                None => parse_number(&ctx.item.string_value(self.dom)),
+            }),
+            // "name" and "local-name"
+            _ => Value::String(match item_arg() {
+                Some(Item::Attribute { name, .. }) => name,
+                Some(Item::Node(handle)) => match handle.get(self.dom.parser()) {
+                    Some(Node::Tag(tag)) => tag.name().as_utf8_str().into_owned(),
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            }),
+        }
+    }
+
+    fn nodes(&self, value: Value) -> Vec<Item> {
+        match value {
+            Value::Nodes(items) => items,
+            _ => Vec::new(),
+        }
+    }
+
+    fn string(&self, value: Value) -> String {
+        match value {
+            Value::Nodes(items) => items
+                .first()
+                .map(|item| item.string_value(self.dom))
+                .unwrap_or_default(),
+            Value::String(string) => string,
+            Value::Number(number) => number_to_string(number),
+            Value::Boolean(boolean) => boolean.to_string(),
+        }
+    }
+
+    fn number(&self, value: Value) -> f64 {
+        match value {
+            Value::Number(number) => number,
+            Value::Boolean(boolean) => f64::from(u8::from(boolean)),
+            value => parse_number(&self.string(value)),
+        }
+    }
+
+    fn boolean(&self, value: Value) -> bool {
+        match value {
+            Value::Nodes(items) => !items.is_empty(),
+            Value::String(string) => !string.is_empty(),
+            Value::Number(number) => number != 0.0 && !number.is_nan(),
+            Value::Boolean(boolean) => boolean,
+        }
+    }
+
+    fn compare(&self, op: BinaryOp, lhs: Value, rhs: Value) -> bool {
+        match (lhs, rhs) {
+            (Value::Nodes(lhs), Value::Nodes(rhs)) => {
+                let rhs = rhs
+                    .iter()
+                    .map(|item| Value::String(item.string_value(self.dom)))
+                    .collect::<Vec<_>>();
+
+                lhs.iter().any(|item| {
+                    let lhs = Value::String(item.string_value(self.dom));
+                    rhs.iter()
+                        .any(|rhs| self.compare_values(op, lhs.clone(), rhs.clone()))
+                })
+            }
+            (Value::Nodes(items), Value::Boolean(boolean)) => self.compare_values(
+                op,
+                Value::Boolean(!items.is_empty()),
+                Value::Boolean(boolean),
+            ),
+            (Value::Nodes(items), rhs) => items.iter().any(|item| {
+                self.compare_values(op, Value::String(item.string_value(self.dom)), rhs.clone())
+            }),
+            (lhs, rhs @ Value::Nodes(_)) => self.compare(op.swapped(), rhs, lhs),
+            (lhs, rhs) => self.compare_values(op, lhs, rhs),
+        }
+    }
+
+    /// Compares two values that are not node sets
+    fn compare_values(&self, op: BinaryOp, lhs: Value, rhs: Value) -> bool {
+        if matches!(op, BinaryOp::Eq | BinaryOp::Neq) {
+            let equal = match (&lhs, &rhs) {
+                (Value::Boolean(_), _) | (_, Value::Boolean(_)) => {
+                    self.boolean(lhs) == self.boolean(rhs)
+                }
+                (Value::Number(_), _) | (_, Value::Number(_)) => {
+                    self.number(lhs) == self.number(rhs)
+                }
+                _ => self.string(lhs) == self.string(rhs),
+            };
+
+            return equal == (op == BinaryOp::Eq);
+        }
+
+        let ordering = self.number(lhs).partial_cmp(&self.number(rhs));
+
+        match op {
+            BinaryOp::Lt => ordering == Some(Ordering::Less),
+            BinaryOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            BinaryOp::Gt => ordering == Some(Ordering::Greater),
+            _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Sorts items in document order and removes duplicates
+fn sorted(mut items: Vec<Item>) -> Vec<Item> {
+    items.sort_by(|a, b| a.order().cmp(&b.order()));
+    items.dedup();
+    items
+}
+
+/// Converts a string to a number like XPath does: an optional minus sign followed by digits with an optional decimal point,
+/// surrounded by whitespace. Anything else is `NaN`.
+fn parse_number(string: &str) -> f64 {
+    let string = string.trim();
+    let digits = string.strip_prefix('-').unwrap_or(string);
+
+    let is_number = !digits.is_empty()
+        && digits != "."
+        && digits.bytes().all(|c| c.is_ascii_digit() || c == b'.')
+        && digits.bytes().filter(|&c| c == b'.').count() <= 1;
+
+    match is_number {
+        true => string.parse().unwrap_or(f64::NAN),
+        false => f64::NAN,
+    }
+}
+
+fn number_to_string(number: f64) -> String {
+    if number.is_nan() {
+        "NaN".to_owned()
+    } else if number.is_infinite() {
+        if number > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_owned()
+    } else if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}